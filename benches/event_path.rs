@@ -0,0 +1,152 @@
+//! Benchmark of the pointer input path of windows over a recorded stream of 10k events.
+//!
+//! The stream is generated in the format of input recordings, as a session moving, clicking and
+//! scrolling, and fed to [`Verso::handle_winit_window_event`] from the event loop once a page is
+//! loaded, like Winit would.
+//!
+//! Before the bench runs, the allocations the event loop thread makes handling the stream once
+//! are counted and printed. Servo's threads allocate for the resulting events as well, so only
+//! the thread running the entry point is counted.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use common::Scenario;
+use criterion::{black_box, Criterion};
+use servo_url::ServoUrl;
+use verso::{recorder::RecordedInput, webview::LoadStatus, Verso};
+use winit::event::WindowEvent;
+
+/// Allocator counting the allocations each thread makes through it.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_allocation() {
+    // The counter is gone while the thread exits.
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Number of events in the stream.
+const EVENTS: usize = 10_000;
+
+/// A session moving the cursor across the window, clicking every 50 events and scrolling with a
+/// touchpad in between.
+fn recorded_stream() -> Vec<WindowEvent> {
+    (0..EVENTS)
+        .map(|i| {
+            let input = match i % 50 {
+                0 => RecordedInput::MouseInput {
+                    pressed: true,
+                    button: 0,
+                },
+                1 => RecordedInput::MouseInput {
+                    pressed: false,
+                    button: 0,
+                },
+                2..=29 => RecordedInput::CursorMoved {
+                    x: (i % 800) as f64 + 0.5,
+                    y: (i % 600) as f64 + 0.25,
+                },
+                n => RecordedInput::MouseWheel {
+                    x: 0.0,
+                    y: -2.7,
+                    pixel: true,
+                    phase: match n {
+                        30 => 0,
+                        49 => 2,
+                        _ => 1,
+                    },
+                },
+            };
+            input.to_window_event()
+        })
+        .collect()
+}
+
+/// Handle the stream in the current window.
+fn handle_stream(verso: &mut Verso, stream: &[WindowEvent]) {
+    let window_id = verso.current_window_id().expect("There's no window");
+    for event in stream {
+        verso.handle_winit_window_event(window_id, event.clone());
+    }
+}
+
+/// Load the page, then run the bench in its window.
+struct EventPath {
+    url: ServoUrl,
+    loading: bool,
+    stream: Vec<WindowEvent>,
+    criterion: Criterion,
+}
+
+impl Scenario for EventPath {
+    fn step(&mut self, verso: &mut Verso) {
+        if !self.loading {
+            if verso.current_webview_id().is_some() {
+                verso.load_url(self.url.clone());
+                self.loading = true;
+            }
+            return;
+        }
+        if verso.current_raw_url().as_ref() != Some(&self.url)
+            || verso.load_status() != LoadStatus::Complete
+        {
+            return;
+        }
+
+        let before = allocations();
+        handle_stream(verso, &self.stream);
+        let count = allocations() - before;
+        println!("{EVENTS} inputs handled with {count} allocations on the event loop thread");
+
+        let stream = &self.stream;
+        self.criterion
+            .bench_function("handle 10k recorded inputs", |b| {
+                b.iter(|| handle_stream(verso, black_box(stream)))
+            });
+        verso.shutdown();
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let scenario = EventPath {
+        url: common::fixture("latency.html"),
+        loading: false,
+        stream: recorded_stream(),
+        criterion: Criterion::default().configure_from_args(),
+    };
+    let (scenario, _) = common::run(common::config(), scenario)?;
+    scenario.criterion.final_summary();
+    Ok(())
+}
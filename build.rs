@@ -1,3 +1,6 @@
+use std::path::Path;
+use std::process::Command;
+
 fn main() {
     cfg_aliases::cfg_aliases! {
         // Platforms
@@ -7,8 +10,97 @@ fn main() {
         // windows: { target_os = "windows" },
         apple: { any(target_os = "ios", target_os = "macos") },
         linux: { all(unix, not(apple), not(android)) },
+        // Content process sandboxing of Servo
+        gaol_sandbox: { all(unix, not(ios), not(android), not(target_arch = "arm"), not(target_arch = "aarch64")) },
     }
 
     #[cfg(all(feature = "packager", target_os = "macos"))]
     println!("cargo:rustc-link-arg=-Wl,-rpath,@executable_path/../Resources/lib");
+
+    emit_version_info();
+}
+
+/// Set the environment variables read by the `version` module. Anything which can't be found,
+/// like the commit outside of a git checkout, is reported as "unknown" instead of failing.
+fn emit_version_info() {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let manifest_dir = Path::new(&manifest_dir);
+    for path in [".git/HEAD", ".git/index", "Cargo.lock"] {
+        let path = manifest_dir.join(path);
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+
+    let git_dir = manifest_dir.to_string_lossy();
+    let commit = command_output("git", &["-C", &git_dir, "rev-parse", "--short=12", "HEAD"]);
+    let dirty = command_output(
+        "git",
+        &[
+            "-C",
+            &git_dir,
+            "status",
+            "--porcelain",
+            "--untracked-files=no",
+        ],
+    )
+    .map(|status| (!status.is_empty()).to_string());
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = command_output(&rustc, &["--version"]);
+    let lock = std::fs::read_to_string(manifest_dir.join("Cargo.lock")).ok();
+    let servo_revision = lock
+        .as_deref()
+        .and_then(|lock| locked_source(lock, "servo_config"))
+        .and_then(|source| {
+            source
+                .rsplit_once('#')
+                .map(|(_, rev)| rev[..12.min(rev.len())].to_string())
+        });
+    let webrender_version = lock
+        .as_deref()
+        .and_then(|lock| locked_version(lock, "webrender"));
+
+    for (name, value) in [
+        ("VERSO_GIT_COMMIT", commit),
+        ("VERSO_GIT_DIRTY", dirty),
+        ("VERSO_RUSTC_VERSION", rustc_version),
+        ("VERSO_SERVO_REVISION", servo_revision),
+        ("VERSO_WEBRENDER_VERSION", webrender_version),
+    ] {
+        let value = value.unwrap_or_else(|| "unknown".to_string());
+        println!("cargo:rustc-env={name}={value}");
+    }
+}
+
+/// Trimmed standard output of the command, if it succeeds.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+/// Field of the package in `Cargo.lock`.
+fn locked_field<'a>(lock: &'a str, package: &str, field: &str) -> Option<&'a str> {
+    let name = format!("name = \"{package}\"");
+    let entry = lock
+        .split("[[package]]")
+        .find(|entry| entry.lines().any(|line| line.trim() == name))?;
+    entry.lines().find_map(|line| {
+        let value = line.trim().strip_prefix(field)?.trim_start();
+        value.strip_prefix("= \"")?.strip_suffix('"')
+    })
+}
+
+fn locked_source<'a>(lock: &'a str, package: &str) -> Option<&'a str> {
+    locked_field(lock, package, "source")
+}
+
+fn locked_version(lock: &str, package: &str) -> Option<String> {
+    let version = locked_field(lock, package, "version")?;
+    match locked_source(lock, package).and_then(|source| source.rsplit_once('#')) {
+        Some((_, rev)) => Some(format!("{version} ({})", &rev[..12.min(rev.len())])),
+        None => Some(version.to_string()),
+    }
 }
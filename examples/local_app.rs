@@ -0,0 +1,91 @@
+//! An app shipped as a directory of HTML and JavaScript next to the binary, served over HTTP
+//! instead of being loaded from `file` URLs, so it can use `fetch` and modules.
+//!
+//! Run it from the repository root so the resources directory can be found:
+//!
+//! ```sh
+//! cargo run --example local_app --features local-server
+//! ```
+
+use servo_url::ServoUrl;
+use verso::config::Config;
+use verso::local_server::ServeOptions;
+use verso::{Result, Verso};
+use winit::application::ApplicationHandler;
+use winit::event_loop::{self, EventLoop, EventLoopProxy};
+
+struct LocalApp {
+    verso: Option<Verso>,
+    proxy: EventLoopProxy<()>,
+    app_url: Option<ServoUrl>,
+    app_loaded: bool,
+}
+
+impl LocalApp {
+    fn update(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        let Some(verso) = self.verso.as_mut() else {
+            return;
+        };
+        verso.handle_servo_messages(event_loop);
+        // The web view is created once the panel has loaded.
+        if !self.app_loaded && verso.current_webview_id().is_some() {
+            if let Some(url) = self.app_url.clone() {
+                verso.load_url(url);
+            }
+            self.app_loaded = true;
+        }
+    }
+}
+
+impl ApplicationHandler for LocalApp {
+    fn resumed(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        let current_dir = std::env::current_dir().unwrap();
+        let config = Config::new(current_dir.join("resources"));
+        let mut verso = match Verso::new(event_loop, self.proxy.clone(), config) {
+            Ok(verso) => verso,
+            Err(e) => {
+                eprintln!("Failed to start Verso: {e}");
+                event_loop.exit();
+                return;
+            }
+        };
+        let options = ServeOptions {
+            port: None,
+            spa_fallback: true,
+        };
+        match verso.serve_directory_with(current_dir.join("examples/local_app"), options) {
+            Ok(url) => self.app_url = Some(url),
+            Err(e) => log::error!("Failed to serve the app: {e}"),
+        }
+        self.verso = Some(verso);
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &event_loop::ActiveEventLoop,
+        window_id: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        if let Some(verso) = self.verso.as_mut() {
+            verso.handle_winit_window_event(window_id, event);
+        }
+        self.update(event_loop);
+    }
+
+    fn user_event(&mut self, event_loop: &event_loop::ActiveEventLoop, _: ()) {
+        self.update(event_loop);
+    }
+}
+
+fn main() -> Result<()> {
+    let event_loop = EventLoop::new()?;
+    let proxy = event_loop.create_proxy();
+    let mut app = LocalApp {
+        verso: None,
+        proxy,
+        app_url: None,
+        app_loaded: false,
+    };
+    event_loop.run_app(&mut app)?;
+    Ok(())
+}
@@ -0,0 +1,18 @@
+// Pages are rendered on the client, so the server answers `/about` with index.html.
+const pages = await fetch('/pages.json').then((response) => response.json());
+
+const render = () => {
+  const page = pages[location.pathname] ?? { title: 'Not found', text: location.pathname };
+  document.title = page.title;
+  document.getElementById('page').innerHTML = `<h1>${page.title}</h1><p>${page.text}</p>`;
+};
+
+document.addEventListener('click', (event) => {
+  const link = event.target.closest('a');
+  if (!link || link.origin !== location.origin) return;
+  event.preventDefault();
+  history.pushState(null, '', link.pathname);
+  render();
+});
+addEventListener('popstate', render);
+render();
@@ -0,0 +1,84 @@
+//! A floating clock widget. The window stays on top of other windows and is hidden from the
+//! taskbar where the platform allows.
+//!
+//! Run it from the repository root so the resources directory can be found:
+//!
+//! ```sh
+//! cargo run --example widget
+//! ```
+
+use servo_url::ServoUrl;
+use verso::config::{Config, WindowConfig};
+use verso::{Result, Verso};
+use winit::application::ApplicationHandler;
+use winit::event_loop::{self, EventLoop, EventLoopProxy};
+
+const CLOCK: &str = "data:text/html,<body style='margin:0;display:flex;align-items:center;\
+justify-content:center;height:100vh;font:48px sans-serif'><div id=clock></div><script>\
+const tick=()=>clock.textContent=new Date().toLocaleTimeString();tick();setInterval(tick,1000);\
+</script></body>";
+
+struct Widget {
+    verso: Option<Verso>,
+    proxy: EventLoopProxy<()>,
+    clock_loaded: bool,
+}
+
+impl Widget {
+    fn update(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        let Some(verso) = self.verso.as_mut() else {
+            return;
+        };
+        verso.handle_servo_messages(event_loop);
+        // The web view is created once the panel has loaded.
+        if !self.clock_loaded && verso.current_webview_id().is_some() {
+            verso.load_url(ServoUrl::parse(CLOCK).unwrap());
+            self.clock_loaded = true;
+        }
+    }
+}
+
+impl ApplicationHandler for Widget {
+    fn resumed(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        let resource_dir = std::env::current_dir().unwrap().join("resources");
+        let mut config = Config::new(resource_dir);
+        config.window = WindowConfig::default()
+            .always_on_top(true)
+            .skip_taskbar(true);
+        match Verso::new(event_loop, self.proxy.clone(), config) {
+            Ok(verso) => self.verso = Some(verso),
+            Err(e) => {
+                eprintln!("Failed to start Verso: {e}");
+                event_loop.exit();
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &event_loop::ActiveEventLoop,
+        window_id: winit::window::WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        if let Some(verso) = self.verso.as_mut() {
+            verso.handle_winit_window_event(window_id, event);
+        }
+        self.update(event_loop);
+    }
+
+    fn user_event(&mut self, event_loop: &event_loop::ActiveEventLoop, _: ()) {
+        self.update(event_loop);
+    }
+}
+
+fn main() -> Result<()> {
+    let event_loop = EventLoop::new()?;
+    let proxy = event_loop.create_proxy();
+    let mut widget = Widget {
+        verso: None,
+        proxy,
+        clock_loaded: false,
+    };
+    event_loop.run_app(&mut widget)?;
+    Ok(())
+}
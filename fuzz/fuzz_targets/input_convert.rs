@@ -0,0 +1,88 @@
+//! Feed arbitrary streams of pointer inputs and window settings to the input conversion, which
+//! must not panic and must keep the events of an input inline.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use verso::{
+    config::AxisLockMode,
+    flow::{PageFlow, WritingMode},
+    input::{convert_input, InputOptions, InputState},
+    recorder::RecordedInput,
+};
+
+#[derive(Arbitrary, Debug)]
+enum Input {
+    CursorMoved {
+        x: f64,
+        y: f64,
+    },
+    MouseInput {
+        pressed: bool,
+        button: u16,
+    },
+    MouseWheel {
+        x: f64,
+        y: f64,
+        pixel: bool,
+        phase: u8,
+    },
+    PinchGesture {
+        delta: f64,
+        phase: u8,
+    },
+    Focused(bool),
+}
+
+impl Input {
+    fn recorded(&self) -> RecordedInput {
+        match *self {
+            Input::CursorMoved { x, y } => RecordedInput::CursorMoved { x, y },
+            Input::MouseInput { pressed, button } => RecordedInput::MouseInput { pressed, button },
+            Input::MouseWheel { x, y, pixel, phase } => {
+                RecordedInput::MouseWheel { x, y, pixel, phase }
+            }
+            Input::PinchGesture { delta, phase } => RecordedInput::PinchGesture { delta, phase },
+            Input::Focused(focused) => RecordedInput::Focused { focused },
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+struct Stream {
+    /// Scale factor in 32ths, as windows always have a positive one.
+    scale_factor: u8,
+    axis_lock: u8,
+    snap_scroll: bool,
+    shift: bool,
+    writing_mode: u8,
+    rtl: bool,
+    inputs: Vec<Input>,
+}
+
+fuzz_target!(|stream: Stream| {
+    let options = InputOptions {
+        scale_factor: f64::from(stream.scale_factor.max(1)) / 32.0,
+        wheel_axis_lock: match stream.axis_lock % 3 {
+            0 => AxisLockMode::Always,
+            1 => AxisLockMode::Never,
+            _ => AxisLockMode::SmartDiagonal,
+        },
+        snap_scroll: stream.snap_scroll,
+        shift: stream.shift,
+        flow: PageFlow {
+            writing_mode: match stream.writing_mode % 3 {
+                0 => WritingMode::HorizontalTb,
+                1 => WritingMode::VerticalRl,
+                _ => WritingMode::VerticalLr,
+            },
+            rtl: stream.rtl,
+        },
+    };
+    let mut state = InputState::default();
+    for input in &stream.inputs {
+        let events = convert_input(&input.recorded().to_window_event(), &mut state, &options);
+        assert!(!events.spilled());
+    }
+});
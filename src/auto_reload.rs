@@ -0,0 +1,142 @@
+//! Scheduled reload of the page of a window, like for dashboards, started with
+//! [`crate::Verso::set_auto_reload`].
+//!
+//! The page is reloaded every interval, counted from the last reload. Each reload is reported to
+//! [`crate::VersoBuilder::on_auto_reload`] with the number of reloads so far. Verso wakes the
+//! event loop at the deadline, so reloads happen on time without any event. Turning auto-reload
+//! off, or closing the window, cancels the pending reload.
+
+use std::time::{Duration, Instant};
+
+use servo_url::ServoUrl;
+use winit::window::WindowId;
+
+/// Time since the last input after which the user isn't considered interacting anymore.
+pub const ACTIVITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Key of the scroll position saved in the session storage of the page before reloading.
+const SCROLL_KEY: &str = "verso-auto-reload-scroll";
+
+/// Options of auto-reload. All of them are enabled by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutoReloadOptions {
+    /// Postpone a due reload while the user interacts with the window, until no input arrived
+    /// for [`ACTIVITY_TIMEOUT`].
+    pub skip_while_active: bool,
+    /// Scroll the page back to where it was after reloading.
+    pub preserve_scroll: bool,
+    /// Stop reloading once the page navigated away from the URL it had when auto-reload started.
+    pub same_url_only: bool,
+}
+
+impl Default for AutoReloadOptions {
+    fn default() -> Self {
+        Self {
+            skip_while_active: true,
+            preserve_scroll: true,
+            same_url_only: true,
+        }
+    }
+}
+
+/// What to do with the page once [`AutoReload::poll`] is called.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReloadAction {
+    /// Nothing is due yet.
+    Wait,
+    /// Reload the page. This is the reload of the given count.
+    Reload(u32),
+    /// The page navigated away, so auto-reload stops.
+    Cancel,
+}
+
+/// Auto-reload of the page of a window. Verso polls it from the event loop, and it only decides
+/// when to reload, so it can be driven with any clock.
+#[derive(Clone, Debug)]
+pub struct AutoReload {
+    /// Window whose page is reloaded.
+    pub window_id: WindowId,
+    /// URL of the page when auto-reload started.
+    url: ServoUrl,
+    interval: Duration,
+    options: AutoReloadOptions,
+    /// Time the next reload is due.
+    due: Instant,
+    /// Time of the last input in the window.
+    last_input: Option<Instant>,
+    /// Number of reloads so far.
+    count: u32,
+    /// Whether the page is reloading and should be scrolled back once it's loaded.
+    restoring: bool,
+}
+
+impl AutoReload {
+    /// Start reloading the page of the window every interval from `now`.
+    pub fn new(
+        window_id: WindowId,
+        url: ServoUrl,
+        interval: Duration,
+        options: AutoReloadOptions,
+        now: Instant,
+    ) -> Self {
+        Self {
+            window_id,
+            url,
+            interval,
+            options,
+            due: now + interval,
+            last_input: None,
+            count: 0,
+            restoring: false,
+        }
+    }
+
+    /// Record an input in the window.
+    pub fn on_input(&mut self, now: Instant) {
+        self.last_input = Some(now);
+    }
+
+    /// Time the event loop should wake up to reload the page.
+    pub fn deadline(&self) -> Instant {
+        match self.last_input {
+            Some(input) if self.options.skip_while_active => self.due.max(input + ACTIVITY_TIMEOUT),
+            _ => self.due,
+        }
+    }
+
+    /// Decide what to do with the page, whose current URL is `url`, at `now`.
+    pub fn poll(&mut self, url: Option<&ServoUrl>, now: Instant) -> ReloadAction {
+        if self.options.same_url_only && url != Some(&self.url) {
+            return ReloadAction::Cancel;
+        }
+        if now < self.deadline() {
+            return ReloadAction::Wait;
+        }
+        self.due = now + self.interval;
+        self.count += 1;
+        self.restoring = self.options.preserve_scroll;
+        ReloadAction::Reload(self.count)
+    }
+
+    /// Script saving the scroll position and reloading the page, or `None` to reload it without
+    /// a script.
+    pub fn reload_script(&self) -> Option<String> {
+        self.options.preserve_scroll.then(|| {
+            format!(
+                "sessionStorage.setItem('{SCROLL_KEY}', JSON.stringify([scrollX, scrollY]));
+                location.reload();"
+            )
+        })
+    }
+
+    /// Script scrolling the page back once it's reloaded, if it should be.
+    pub fn take_restore_script(&mut self) -> Option<String> {
+        std::mem::take(&mut self.restoring).then(|| {
+            format!(
+                "const scroll = sessionStorage.getItem('{SCROLL_KEY}');
+                sessionStorage.removeItem('{SCROLL_KEY}');
+                if (scroll) scrollTo(...JSON.parse(scroll));"
+            )
+        })
+    }
+}
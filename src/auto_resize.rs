@@ -0,0 +1,150 @@
+//! Notifications of the content size of pages, and windows resized to fit their content, like
+//! for an inline HTML widget such as an email preview pane.
+//!
+//! Pages report the size of their root element through a script injected once their head is
+//! parsed, with a `ResizeObserver` or by polling where it's missing. Sizes are reported at most
+//! once per 50 ms, only when they change, to
+//! [`crate::VersoBuilder::on_content_size_changed`] in CSS pixels. The script is only injected
+//! while the hook is set or auto-resizing is enabled.
+//!
+//! With [`crate::Verso::auto_resize_window`], the window is resized so its web view fits the
+//! content, up to a maximum size. The width only grows for content wider than the window, as
+//! the width of pages follows the viewport. Content which follows the viewport height, like an
+//! element of `100vh` with a margin, would make the window grow forever: if the content outgrows
+//! the window within [`FEEDBACK_WINDOW`] after each of [`FEEDBACK_LIMIT`] resizes in a row,
+//! auto-resizing is disabled with a warning, and
+//! [`crate::VersoBuilder::on_auto_resize_stopped`] is called.
+
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use winit::dpi::LogicalSize;
+
+/// Time after a resize in which content outgrowing the window counts as following it.
+pub const FEEDBACK_WINDOW: Duration = Duration::from_millis(500);
+
+/// Number of resizes in a row the content follows before auto-resizing is disabled.
+pub const FEEDBACK_LIMIT: u32 = 5;
+
+/// Prefix of the message the page sends with `prompt()` with its content size.
+pub(crate) const CONTENT_SIZE_PROMPT: &str = "VERSO_CONTENT_SIZE:";
+
+/// Script reporting the content size of the page when it changes.
+pub(crate) const CONTENT_SIZE_SCRIPT: &str = "
+(() => {
+    if (window.versoContentSize) {
+        return;
+    }
+    window.versoContentSize = true;
+    const root = document.documentElement;
+    let last = '';
+    let timer = null;
+    const report = () => {
+        timer = null;
+        const size = JSON.stringify({
+            width: root.scrollWidth,
+            height: Math.ceil(root.getBoundingClientRect().height),
+        });
+        if (size !== last) {
+            last = size;
+            prompt('VERSO_CONTENT_SIZE:' + size);
+        }
+    };
+    const schedule = () => {
+        if (timer === null) {
+            timer = setTimeout(report, 50);
+        }
+    };
+    if (typeof ResizeObserver === 'function') {
+        new ResizeObserver(schedule).observe(root);
+    } else {
+        setInterval(schedule, 250);
+    }
+    addEventListener('load', schedule);
+    schedule();
+})();";
+
+/// Size of the content of a page in CSS pixels, reported to
+/// [`crate::VersoBuilder::on_content_size_changed`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub struct ContentSize {
+    /// Scroll width of the root element, which is at least the width of the viewport.
+    pub width: f32,
+    /// Height of the root element, which may be smaller than the viewport.
+    pub height: f32,
+}
+
+/// Read the content size of a [`CONTENT_SIZE_PROMPT`] message from the page.
+pub(crate) fn read_content_size(message: &str) -> Option<ContentSize> {
+    let json = message.strip_prefix(CONTENT_SIZE_PROMPT)?;
+    let size: ContentSize = serde_json::from_str(json).ok()?;
+    (size.width.is_finite() && size.height.is_finite() && size.width >= 0.0 && size.height >= 0.0)
+        .then_some(size)
+}
+
+/// What to do with a window after its content size changed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AutoResizeStep {
+    /// The window already has the size for the content.
+    Keep,
+    /// Resize the window to the inner size.
+    Resize(LogicalSize<f64>),
+    /// The content follows the size of the window, so stop resizing it.
+    Stop,
+}
+
+/// State of a window resized to fit its content.
+#[derive(Clone, Debug)]
+pub struct AutoResize {
+    max_size: LogicalSize<u32>,
+    last_resize: Option<(Instant, LogicalSize<f64>)>,
+    feedback: u32,
+}
+
+impl AutoResize {
+    /// Resize the window up to the inner size.
+    pub fn new(max_size: LogicalSize<u32>) -> Self {
+        Self {
+            max_size,
+            last_resize: None,
+            feedback: 0,
+        }
+    }
+
+    /// Decide how to resize the window for the content, in logical pixels including the page
+    /// zoom. `window` is the inner size of the window, and `chrome` the part of it around the
+    /// web view, like a panel.
+    pub fn fit(
+        &mut self,
+        content: LogicalSize<f64>,
+        window: LogicalSize<f64>,
+        chrome: LogicalSize<f64>,
+        now: Instant,
+    ) -> AutoResizeStep {
+        let needed = LogicalSize::new(
+            (content.width + chrome.width).ceil(),
+            (content.height + chrome.height).ceil(),
+        );
+        let following = self.last_resize.is_some_and(|(time, size)| {
+            now.saturating_duration_since(time) < FEEDBACK_WINDOW
+                && (needed.width > size.width + 1.0 || needed.height > size.height + 1.0)
+        });
+        self.feedback = if following { self.feedback + 1 } else { 0 };
+        if self.feedback >= FEEDBACK_LIMIT {
+            return AutoResizeStep::Stop;
+        }
+        let width = if needed.width > window.width + 1.0 {
+            needed.width.min(f64::from(self.max_size.width))
+        } else {
+            window.width
+        };
+        let height = needed.height.min(f64::from(self.max_size.height));
+        let target = LogicalSize::new(width.max(1.0), height.max(1.0));
+        if (target.width - window.width).abs() < 1.0 && (target.height - window.height).abs() < 1.0
+        {
+            return AutoResizeStep::Keep;
+        }
+        self.last_resize = Some((now, target));
+        AutoResizeStep::Resize(target)
+    }
+}
@@ -0,0 +1,270 @@
+//! Autofill of form fields with previously submitted values, enabled with
+//! [`crate::config::Config::autofill`].
+//!
+//! When a form is submitted, the values of its text fields are stored by field signature, which is
+//! the `autocomplete` token of the field, or its name or id. When a field with a known signature
+//! is focused, the stored values are listed below it, and choosing one writes it into the field.
+//! Values are kept in `autofill.json` in the config directory, or in memory if there's none, and
+//! wiped with [`crate::Verso::clear_autofill`].
+//!
+//! The page asks for values with `prompt()` messages, which any script can send. Verso only
+//! answers them within [`crate::dispatch::USER_ACTIVATION_DURATION`] of a click, touch or key
+//! press the embedder routed to the window, so pages can't read values without the user focusing
+//! a field. Messages carry a random token of the Verso instance, which is only given to the script
+//! Verso injects into the top-level page, so frames can't ask for the values of the top-level
+//! origin. Messages also carry the origin of the page, which must be the one of the web view, and
+//! values are only offered to the origin they were submitted on.
+//!
+//! Passwords, fields with names of payment or identity data, and values which look like card
+//! numbers are never stored.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use base::id::WebViewId;
+use compositing_traits::ConstellationMsg;
+use crossbeam_channel::Sender;
+use ipc_channel::ipc::IpcSender;
+
+use crate::{token::parse_message, verso::run_script};
+
+/// Prefix of the message the page sends with `prompt()` when a form is submitted, followed by the
+/// JSON of the token, the origin of the page, and the signatures and values of its fields.
+pub(crate) const SUBMIT_PROMPT: &str = "VERSO_AUTOFILL_SUBMIT:";
+
+/// Prefix of the message the page sends with `prompt()` when a field is focused, followed by the
+/// JSON of the token, the origin of the page and the signature of the field. The answer is the
+/// JSON of the stored values.
+pub(crate) const QUERY_PROMPT: &str = "VERSO_AUTOFILL_QUERY:";
+
+/// Number of values stored per signature, the most recent first.
+const MAX_VALUES: usize = 10;
+
+/// Parts of field signatures of sensitive data. `pin` is only matched as a whole word, since it's
+/// part of words like `shipping`.
+const SENSITIVE_NAMES: [&str; 9] = [
+    "pass", "card", "cc-", "cvv", "cvc", "csc", "iban", "ssn", "security",
+];
+
+/// Script reporting submitted forms and showing stored values under focused fields, run after
+/// the declaration of the `token` of the instance.
+const AUTOFILL_SCRIPT: &str = "
+if (!window.versoAutofill) {
+    window.versoAutofill = true;
+    const ask = window.prompt.bind(window);
+    const message = (prefix, data) => prefix + JSON.stringify([token, location.origin, data]);
+    const sensitive = /pass|card|cc-|cvv|cvc|csc|iban|ssn|security|(^|[^a-z])pin([^a-z]|$)/i;
+    const textTypes = ['text', 'email', 'tel', 'url', 'search'];
+    const signature = (field) => {
+        const token = field.getAttribute('autocomplete');
+        const name = token && token !== 'on' ? token : field.name || field.id;
+        return name ? name.trim().toLowerCase() : '';
+    };
+    const fillable = (field) =>
+        (field instanceof HTMLTextAreaElement ||
+            (field instanceof HTMLInputElement && textTypes.includes(field.type))) &&
+        field.getAttribute('autocomplete') !== 'off' &&
+        signature(field) !== '' &&
+        !sensitive.test(signature(field));
+    document.addEventListener('submit', (event) => {
+        const fields = [...event.target.elements]
+            .filter((field) => fillable(field) && field.value.trim() !== '')
+            .map((field) => [signature(field), field.value.trim()]);
+        if (fields.length) ask(message('VERSO_AUTOFILL_SUBMIT:', fields));
+    }, true);
+    let list = null;
+    const close = () => {
+        list?.remove();
+        list = null;
+    };
+    document.addEventListener('focusin', (event) => {
+        close();
+        const field = event.target;
+        if (!fillable(field)) return;
+        let values;
+        try {
+            values = JSON.parse(ask(message('VERSO_AUTOFILL_QUERY:', signature(field))) || '[]');
+        } catch (e) {
+            return;
+        }
+        if (!values.length) return;
+        const rect = field.getBoundingClientRect();
+        list = document.createElement('div');
+        list.style.cssText = `position: fixed; left: ${rect.left}px; top: ${rect.bottom}px;
+            min-width: ${rect.width}px; z-index: 2147483647; background: #fff; color: #000;
+            border: 1px solid #888; box-shadow: 0 2px 6px rgba(0, 0, 0, 0.3); font: 13px sans-serif;`;
+        for (const value of values) {
+            const item = document.createElement('div');
+            item.textContent = value;
+            item.style.cssText = 'padding: 4px 8px; cursor: default;';
+            item.onmouseenter = () => (item.style.background = '#ddd');
+            item.onmouseleave = () => (item.style.background = '');
+            // Choosing a value on mouse down keeps the focus in the field.
+            item.onmousedown = (event) => {
+                event.preventDefault();
+                field.value = value;
+                field.dispatchEvent(new Event('input', { bubbles: true }));
+                field.dispatchEvent(new Event('change', { bubbles: true }));
+                close();
+            };
+            list.appendChild(item);
+        }
+        document.documentElement.appendChild(list);
+    }, true);
+    document.addEventListener('focusout', close, true);
+    document.addEventListener('input', close, true);
+    document.addEventListener('keydown', (event) => {
+        if (event.key === 'Escape') close();
+    }, true);
+}";
+
+/// Request of a page to the autofill store of the Verso instance.
+#[derive(Debug)]
+pub(crate) enum AutofillRequest {
+    /// Inject the autofill script into the page of the web view.
+    Inject(WebViewId),
+    /// Store the values of a [`SUBMIT_PROMPT`] message from the origin.
+    Record(String, String),
+    /// Answer a [`QUERY_PROMPT`] message from the origin.
+    Query(IpcSender<Option<String>>, String, String),
+}
+
+/// Stored values by origin and signature, the file they're saved to, and the token of the
+/// messages of pages.
+#[derive(Debug)]
+pub(crate) struct Autofill {
+    path: Option<PathBuf>,
+    values: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    token: String,
+}
+
+impl Autofill {
+    /// Load the stored values from the config directory, and save them there from now on.
+    pub fn load(config_dir: Option<&Path>) -> std::io::Result<Self> {
+        let mut autofill = Self {
+            path: None,
+            values: BTreeMap::new(),
+            token: crate::token::generate()?,
+        };
+        let Some(path) = config_dir.map(|dir| dir.join("autofill.json")) else {
+            return Ok(autofill);
+        };
+        if let Ok(json) = fs::read_to_string(&path) {
+            match serde_json::from_str(&json) {
+                Ok(values) => autofill.values = values,
+                Err(e) => log::warn!("Verso failed to read autofill values from {path:?}: {e}"),
+            }
+        }
+        autofill.path = Some(path);
+        Ok(autofill)
+    }
+
+    /// Script of the top-level pages, holding the token.
+    pub fn script(&self) -> String {
+        format!("const token = {:?};{AUTOFILL_SCRIPT}", self.token)
+    }
+
+    /// Handle the request of a page, injecting scripts with the sender.
+    pub fn handle(&mut self, request: AutofillRequest, sender: &Sender<ConstellationMsg>) {
+        match request {
+            AutofillRequest::Inject(webview_id) => {
+                run_script(sender, webview_id, self.script(), "apply autofill")
+            }
+            AutofillRequest::Record(origin, message) => self.record(&origin, &message),
+            AutofillRequest::Query(prompt_sender, origin, message) => {
+                let _ = prompt_sender.send(self.query(&origin, &message));
+            }
+        }
+    }
+
+    /// Store the field values of a form submitted on the origin, reported by a [`SUBMIT_PROMPT`]
+    /// message.
+    fn record(&mut self, origin: &str, message: &str) {
+        let Some(fields) = message
+            .strip_prefix(SUBMIT_PROMPT)
+            .and_then(|json| parse_message::<Vec<(String, String)>>(json, &self.token, origin))
+        else {
+            return;
+        };
+        for (signature, value) in fields {
+            if is_sensitive(&signature, &value) {
+                continue;
+            }
+            let values = self
+                .values
+                .entry(origin.to_string())
+                .or_default()
+                .entry(signature)
+                .or_default();
+            values.retain(|v| *v != value);
+            values.insert(0, value);
+            values.truncate(MAX_VALUES);
+        }
+        self.save();
+    }
+
+    /// Answer a [`QUERY_PROMPT`] message from the origin with the JSON of the values stored for
+    /// the signature.
+    fn query(&self, origin: &str, message: &str) -> Option<String> {
+        let json = message.strip_prefix(QUERY_PROMPT)?;
+        let signature: String = parse_message(json, &self.token, origin)?;
+        let values = self.values.get(origin)?.get(&signature)?;
+        serde_json::to_string(values).ok()
+    }
+
+    /// Remove all stored values.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = serde_json::to_string(&self.values)
+            .map_err(std::io::Error::from)
+            .and_then(|json| fs::write(path, json));
+        if let Err(e) = result {
+            log::warn!("Verso failed to save autofill values to {path:?}: {e}");
+        }
+    }
+}
+
+/// Return true if the field holds data which must never be stored.
+fn is_sensitive(signature: &str, value: &str) -> bool {
+    let signature = signature.to_ascii_lowercase();
+    SENSITIVE_NAMES.iter().any(|name| signature.contains(name))
+        || signature
+            .split(|c: char| !c.is_ascii_alphabetic())
+            .any(|word| word == "pin")
+        || is_card_number(value)
+}
+
+/// Return true if the value is 13 to 19 digits passing the Luhn check, ignoring spaces and
+/// dashes.
+fn is_card_number(value: &str) -> bool {
+    let digits: Vec<u32> = value
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-'))
+        .map(|c| c.to_digit(10))
+        .collect::<Option<_>>()
+        .unwrap_or_default();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum % 10 == 0
+}
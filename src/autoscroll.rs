@@ -0,0 +1,93 @@
+//! Middle-click autoscroll, enabled with [`crate::Verso::set_autoscroll`].
+//!
+//! Middle clicking the web view marks the point with an indicator, and the page scrolls towards
+//! the cursor, faster the farther the cursor is from the point, until the next click. Dragging
+//! with the middle button held scrolls the same way until it's released.
+
+use std::time::{Duration, Instant};
+
+use webrender_api::units::DevicePoint;
+
+/// Distance from the origin in device pixels within which the page doesn't scroll.
+pub(crate) const DEAD_ZONE: f32 = 12.0;
+/// Radius of the origin indicator in device pixels.
+pub(crate) const INDICATOR_RADIUS: f32 = 14.0;
+/// Scroll speed in device pixels per second, per device pixel of distance beyond the dead zone.
+const SPEED: f32 = 8.0;
+/// Shortest time between two scrolls.
+const FRAME_TIME: Duration = Duration::from_millis(16);
+/// Longest time scrolled at once, so a stalled frame doesn't jump.
+const MAX_FRAME_TIME: Duration = Duration::from_millis(100);
+
+/// Autoscroll in progress in a window.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Autoscroll {
+    /// Point the page scrolls from, where the middle button was pressed.
+    pub origin: DevicePoint,
+    /// Whether the middle button is still held since autoscroll started.
+    held: bool,
+    /// Whether the cursor left the dead zone while the middle button was held.
+    dragged: bool,
+    /// Whether a click ended autoscroll, and its release is still to be swallowed.
+    ending: bool,
+    last_tick: Option<Instant>,
+}
+
+impl Autoscroll {
+    /// Start autoscroll from the point where the middle button was pressed.
+    pub fn new(origin: DevicePoint) -> Self {
+        Self {
+            origin,
+            held: true,
+            dragged: false,
+            ending: false,
+            last_tick: None,
+        }
+    }
+
+    /// Track whether the cursor is dragged with the middle button held.
+    pub fn on_cursor_moved(&mut self, cursor: DevicePoint) {
+        if self.held && (cursor - self.origin).length() > DEAD_ZONE {
+            self.dragged = true;
+        }
+    }
+
+    /// Handle a button press, which ends autoscroll once the button is released.
+    pub fn on_press(&mut self) {
+        self.ending = true;
+    }
+
+    /// Handle a button release. Return true if autoscroll ends.
+    pub fn on_release(&mut self) -> bool {
+        let ends = self.ending || (self.held && self.dragged);
+        self.held = false;
+        ends
+    }
+
+    /// Return true if autoscroll was ended by a click.
+    pub fn is_ending(&self) -> bool {
+        self.ending
+    }
+
+    /// Advance autoscroll to `now` and return the scroll delta for the cursor position, following
+    /// Winit's convention where positive values reveal content to the left and top.
+    pub fn tick(&mut self, cursor: DevicePoint, now: Instant) -> Option<(f32, f32)> {
+        if self.ending {
+            return None;
+        }
+        let elapsed = match self.last_tick {
+            Some(last) if now.saturating_duration_since(last) < FRAME_TIME => return None,
+            Some(last) => now.saturating_duration_since(last).min(MAX_FRAME_TIME),
+            None => Duration::ZERO,
+        };
+        self.last_tick = Some(now);
+        let offset = cursor - self.origin;
+        let distance = offset.length();
+        if distance <= DEAD_ZONE {
+            return None;
+        }
+        let speed = (distance - DEAD_ZONE) * SPEED * elapsed.as_secs_f32();
+        let delta = -offset / distance * speed;
+        Some((delta.x, delta.y))
+    }
+}
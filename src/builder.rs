@@ -0,0 +1,564 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, TcpListener},
+    path::PathBuf,
+    time::Duration,
+};
+
+use base::id::WebViewId;
+use servo_url::ServoUrl;
+use winit::{
+    dpi::PhysicalSize,
+    event_loop::{ActiveEventLoop, EventLoopProxy},
+    window::WindowId,
+};
+
+use crate::{
+    auto_resize::ContentSize,
+    caret::ContentInsets,
+    compositor::SurfaceResetCause,
+    config::{
+        AutoplayPolicy, AxisLockMode, BeforeUnloadPolicy, Config, CrashRecoveryPolicy, FontConfig,
+        RetryPolicy, SameUrlPolicy, WebRtcConfig, WindowConfig, WindowControlPolicy,
+    },
+    content_scripts::ContentScript,
+    contrast::ForcedColors,
+    discard::DiscardPolicy,
+    dns::normalize_host,
+    downloads::Download,
+    errors::Error,
+    features::{normalize_origin, FeaturePolicy},
+    hooks::{Hooks, LoadResult},
+    partitions::validate_name,
+    passwords::{LoginForm, SubmittedCredentials},
+    prefs::PrefValue,
+    print::{PrintConfig, PrintRequest, PrintResult},
+    progress::ProgressBarStyle,
+    sandbox::SandboxMode,
+    scrollbars::ScrollbarStyle,
+    shortcuts::ShortcutMap,
+    suspend::ResumeProgress,
+    tabs::TabInfo,
+    url_display::UrlDisplayMode,
+    zoom::ZoomChange,
+    Verso,
+};
+
+/// Builder of [`Verso`] with a setter for each startup option of [`Config`]. Options are
+/// validated when building, so incompatible combinations are reported as
+/// [`Error::InvalidConfig`] instead of being silently ignored.
+///
+/// ```no_run
+/// # use verso::{builder::VersoBuilder, config::AutoplayPolicy};
+/// # fn build(evl: &winit::event_loop::ActiveEventLoop, proxy: winit::event_loop::EventLoopProxy<()>) -> verso::Result<()> {
+/// let verso = VersoBuilder::new("resources".into())
+///     .autoplay(AutoplayPolicy::Block)
+///     .smart_magnify(false)
+///     .build(evl, proxy)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct VersoBuilder {
+    config: Config,
+    hooks: Hooks,
+}
+
+impl VersoBuilder {
+    /// Create a builder with the default options. It must provide the path of resources
+    /// directory.
+    pub fn new(resource_dir: PathBuf) -> Self {
+        Self {
+            config: Config::new(resource_dir),
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// Start from an existing configuration.
+    pub fn from_config(config: Config) -> Self {
+        Self {
+            config,
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// Set what to do when a page with a `beforeunload` handler is about to be unloaded.
+    pub fn before_unload(mut self, policy: BeforeUnloadPolicy) -> Self {
+        self.config.before_unload = policy;
+        self
+    }
+
+    /// Record input events and embedder messages to the file.
+    pub fn record_events(mut self, path: PathBuf) -> Self {
+        self.config.record_events = Some(path);
+        self
+    }
+
+    /// Set the keyboard shortcuts of browser actions, like ones loaded with
+    /// [`ShortcutMap::load`].
+    pub fn shortcuts(mut self, shortcuts: ShortcutMap) -> Self {
+        self.config.shortcuts = shortcuts;
+        self
+    }
+
+    /// Set whether the smart magnify gesture zooms into the element under the cursor, and double
+    /// tap or double-click with Ctrl toggles fitting the width of the page.
+    pub fn smart_magnify(mut self, enabled: bool) -> Self {
+        self.config.smart_magnify = enabled;
+        self
+    }
+
+    /// Set the options of the windows.
+    pub fn window(mut self, window: WindowConfig) -> Self {
+        self.config.window = window;
+        self
+    }
+
+    /// Set when pages can autoplay media with sound.
+    pub fn autoplay(mut self, policy: AutoplayPolicy) -> Self {
+        self.config.autoplay = policy;
+        self
+    }
+
+    /// Set whether the primary selection is supported on Linux.
+    pub fn primary_selection(mut self, enabled: bool) -> Self {
+        self.config.primary_selection = enabled;
+        self
+    }
+
+    /// Set how mouse wheel and touchpad scrolling is locked to a single axis.
+    pub fn wheel_axis_lock(mut self, mode: AxisLockMode) -> Self {
+        self.config.wheel_axis_lock = mode;
+        self
+    }
+
+    /// Set whether submitted form values are stored and offered in form fields.
+    pub fn autofill(mut self, enabled: bool) -> Self {
+        self.config.autofill = enabled;
+        self
+    }
+
+    /// Set whether the username and password are included in submitted credentials reported to
+    /// [`VersoBuilder::on_credentials_submitted`].
+    pub fn share_credentials(mut self, enabled: bool) -> Self {
+        self.config.share_credentials = enabled;
+        self
+    }
+
+    /// Store the cookies, local storage and cache of web views in the named partition. It applies
+    /// to all web views of the Verso instance, as partitions can't be chosen per web view. See
+    /// [`crate::partitions`].
+    pub fn partition(mut self, name: impl Into<String>) -> Self {
+        self.config.partition = Some(name.into());
+        self
+    }
+
+    /// Resolve the hosts to the addresses instead of asking the system, like with a hosts file.
+    /// Keys are exact hosts like `staging.example.com`. See [`crate::dns`].
+    pub fn dns_overrides(mut self, map: HashMap<String, IpAddr>) -> Self {
+        self.config.dns_overrides = map.into_iter().collect();
+        self
+    }
+
+    /// Set what loading the URL of the page already loaded does.
+    pub fn same_url(mut self, policy: SameUrlPolicy) -> Self {
+        self.config.same_url = policy;
+        self
+    }
+
+    /// Set what [`crate::Verso::current_url`] shows of the URL of the page.
+    pub fn url_display_mode(mut self, mode: UrlDisplayMode) -> Self {
+        self.config.url_display = mode;
+        self
+    }
+
+    /// Set which changes of the window state pages can request.
+    pub fn window_control(mut self, policy: WindowControlPolicy) -> Self {
+        self.config.window_control = policy;
+        self
+    }
+
+    /// Set whether middle clicking the web view starts autoscroll.
+    pub fn autoscroll(mut self, enabled: bool) -> Self {
+        self.config.autoscroll = enabled;
+        self
+    }
+
+    /// Set whether scrolling snaps to whole device pixels.
+    pub fn snap_scroll(mut self, enabled: bool) -> Self {
+        self.config.snap_scroll = enabled;
+        self
+    }
+
+    /// Set whether the Battery Status API is exposed to pages.
+    pub fn battery_status(mut self, enabled: bool) -> Self {
+        self.config.battery_status = enabled;
+        self
+    }
+
+    /// Set the WebRTC options.
+    pub fn webrtc(mut self, webrtc: WebRtcConfig) -> Self {
+        self.config.webrtc = webrtc;
+        self
+    }
+
+    /// Set the default fonts of pages.
+    pub fn fonts(mut self, fonts: FontConfig) -> Self {
+        self.config.fonts = fonts;
+        self
+    }
+
+    /// Set whether the default font sizes follow the text scale of the operating system.
+    pub fn follow_os_text_scale(mut self, enabled: bool) -> Self {
+        self.config.follow_os_text_scale = enabled;
+        self
+    }
+
+    /// Set the features denied to pages of the origin. See [`crate::features`].
+    pub fn feature_policy(mut self, origin: impl Into<String>, policy: FeaturePolicy) -> Self {
+        self.config.feature_policies.insert(origin.into(), policy);
+        self
+    }
+
+    /// Set whether JavaScript dialogs are kept open until they're answered.
+    pub fn hold_dialogs(mut self, enabled: bool) -> Self {
+        self.config.hold_dialogs = enabled;
+        self
+    }
+
+    /// Set whether the latency from input events to presents is measured.
+    pub fn measure_input_latency(mut self, enabled: bool) -> Self {
+        self.config.measure_input_latency = enabled;
+        self
+    }
+
+    /// Set how web views are restored after their content crashes.
+    pub fn crash_recovery(mut self, policy: CrashRecoveryPolicy) -> Self {
+        self.config.crash_recovery = policy;
+        self
+    }
+
+    /// Set how loads failing with a transient network error are retried.
+    pub fn navigation_retry(mut self, policy: RetryPolicy) -> Self {
+        self.config.navigation_retry = policy;
+        self
+    }
+
+    /// Draw a loading progress bar with the style over web views.
+    pub fn builtin_progress_bar(mut self, style: ProgressBarStyle) -> Self {
+        self.config.progress_bar = Some(style);
+        self
+    }
+
+    /// Set the style of the scrollbars drawn over web views.
+    pub fn scrollbar_style(mut self, style: ScrollbarStyle) -> Self {
+        self.config.scrollbars = style;
+        self
+    }
+
+    /// Set the parts of web views covered by the embedder, which the caret is kept out of.
+    pub fn content_insets(mut self, insets: ContentInsets) -> Self {
+        self.config.content_insets = insets;
+        self
+    }
+
+    /// Override the Servo preference, unless a mandatory policy sets it. See [`crate::prefs`].
+    pub fn pref(mut self, key: impl Into<String>, value: impl Into<PrefValue>) -> Self {
+        self.config.pref_overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Set whether pages run in sandboxed content processes. See [`crate::sandbox`].
+    pub fn sandbox(mut self, mode: SandboxMode) -> Self {
+        self.config.sandbox = mode;
+        self
+    }
+
+    /// Set the page loaded in new windows, unless a mandatory policy sets it. See
+    /// [`crate::policies`].
+    pub fn homepage(mut self, url: ServoUrl) -> Self {
+        self.config.homepage = Some(url);
+        self
+    }
+
+    /// Call the closure once the web view of the first window is opened. See [`crate::hooks`].
+    pub fn on_ready(mut self, hook: impl FnOnce(&mut Verso) + 'static) -> Self {
+        self.hooks.on_ready = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure when a page asks to navigate to the URL. The navigation is cancelled if
+    /// it returns false. See [`crate::hooks`].
+    pub fn before_navigate(mut self, hook: impl FnMut(&ServoUrl) -> bool + 'static) -> Self {
+        self.hooks.before_navigate = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure when a page finished loading or failed with a network error. See
+    /// [`crate::hooks`].
+    pub fn after_load(mut self, hook: impl FnMut(&ServoUrl, LoadResult) + 'static) -> Self {
+        self.hooks.after_load = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure with links of schemes handled by other applications, like `mailto:`,
+    /// instead of opening them with the handler of the operating system. See [`crate::external`].
+    pub fn on_external_url(mut self, hook: impl FnMut(&ServoUrl) + 'static) -> Self {
+        self.hooks.on_external_url = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure to print a page, which shows the save dialog with a paper size selector
+    /// and writes the PDF. See [`crate::print`].
+    pub fn on_print(mut self, hook: impl FnMut(&PrintRequest) -> PrintResult + 'static) -> Self {
+        self.hooks.on_print = Some(Box::new(hook));
+        self
+    }
+
+    /// Set the shortest delay of timers in hidden windows, in seconds, or 0 to disable throttling.
+    pub fn background_timer_throttle(mut self, factor: u32) -> Self {
+        self.config.background_timer_throttle = factor;
+        self
+    }
+
+    /// Set the shortest interval between updates of the title and the favicon of a window, or
+    /// zero to apply every update. See [`crate::dispatch::UpdateThrottle`].
+    pub fn title_update_interval(mut self, interval: Duration) -> Self {
+        self.config.title_update_interval = interval;
+        self
+    }
+
+    /// Start Servo's devtools server on the port, so Firefox developer tools can attach to pages.
+    /// Use port `0` to pick a free port. The server is started with Servo, as the engine is
+    /// connected to it from the start, and its address is given by
+    /// [`Verso::devtools_address`]. Building fails if the port is in use, or if developer tools
+    /// are disabled by policy. See [`crate::policies`].
+    pub fn enable_devtools(mut self, port: u16) -> Self {
+        self.config.opts.devtools_server_enabled = true;
+        self.config.opts.devtools_port = port;
+        self
+    }
+
+    /// Inject the script into pages matching its URL patterns. See [`crate::content_scripts`].
+    pub fn content_script(mut self, script: ContentScript) -> Self {
+        self.config.content_scripts.push(script);
+        self
+    }
+
+    /// Call the closure after Verso recreated the rendering surface because the GL context or the
+    /// surface was lost, like after a driver reset or a suspend. The GL context and the renderer
+    /// aren't recreated, and there is no software fallback. See [`SurfaceResetCause`].
+    pub fn on_surface_reset(mut self, hook: impl FnMut(&SurfaceResetCause) + 'static) -> Self {
+        self.hooks.on_surface_reset = Some(Box::new(hook));
+        self
+    }
+
+    /// Set the forced colors mode of pages. See [`crate::contrast`].
+    pub fn forced_colors(mut self, mode: ForcedColors) -> Self {
+        self.config.forced_colors = mode;
+        self
+    }
+
+    /// Set when web views of background windows are discarded. See [`crate::discard`].
+    pub fn discard(mut self, policy: DiscardPolicy) -> Self {
+        self.config.discard = policy;
+        self
+    }
+
+    /// Call the closure with the window and `true` when its web view is discarded, and `false`
+    /// when it's restored, like to grey out discarded windows. See [`crate::discard`].
+    pub fn on_discard(mut self, hook: impl FnMut(WindowId, bool) + 'static) -> Self {
+        self.hooks.on_discard = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure with the window and the number of reloads so far when auto-reload
+    /// reloads its page. See [`crate::auto_reload`].
+    pub fn on_auto_reload(mut self, hook: impl FnMut(WindowId, u32) + 'static) -> Self {
+        self.hooks.on_auto_reload = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure with the window and the title and favicon of its page when one of them
+    /// changes, like to update a tab strip. See [`crate::tabs`].
+    pub fn on_tab_updated(mut self, hook: impl FnMut(WindowId, &TabInfo) + 'static) -> Self {
+        self.hooks.on_tab_updated = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure with the web view resumed by [`crate::Verso::resume_webview`] when it
+    /// starts loading its page, and when its page state is restored. See [`crate::suspend`].
+    pub fn on_resume(mut self, hook: impl FnMut(WebViewId, ResumeProgress) + 'static) -> Self {
+        self.hooks.on_resume = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure with the window and its zoom factors when the page zoom, the pinch zoom
+    /// or the fit mode changes, like to show the zoom level in a toolbar. See [`crate::zoom`].
+    pub fn on_zoom_changed(mut self, hook: impl FnMut(WindowId, &ZoomChange) + 'static) -> Self {
+        self.hooks.on_zoom_changed = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure when a page downloads a `data:` or `blob:` URL, like an exported image,
+    /// right after a user input. Return true if the closure handled the file, or false to save it
+    /// to the downloads directory. See [`crate::downloads`].
+    pub fn on_download(mut self, hook: impl FnMut(&Download) -> bool + 'static) -> Self {
+        self.hooks.on_download = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure with the window and the size of the content of its page in CSS pixels
+    /// when it changes, like to fit an embedded view to its content. See [`crate::auto_resize`].
+    pub fn on_content_size_changed(
+        mut self,
+        hook: impl FnMut(WindowId, ContentSize) + 'static,
+    ) -> Self {
+        self.hooks.on_content_size_changed = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure with the window when it stops fitting its content because the content
+    /// follows the size of the window. See [`crate::Verso::auto_resize_window`].
+    pub fn on_auto_resize_stopped(mut self, hook: impl FnMut(WindowId) + 'static) -> Self {
+        self.hooks.on_auto_resize_stopped = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure when a page shows a login form, which can then be filled with
+    /// [`crate::Verso::fill_credentials`]. See [`crate::passwords`].
+    pub fn on_login_form(mut self, hook: impl FnMut(&LoginForm) + 'static) -> Self {
+        self.hooks.on_login_form = Some(Box::new(hook));
+        self
+    }
+
+    /// Call the closure when a page submits a login form, like to offer saving the password. See
+    /// [`crate::passwords`].
+    pub fn on_credentials_submitted(
+        mut self,
+        hook: impl FnMut(&SubmittedCredentials) + 'static,
+    ) -> Self {
+        self.hooks.on_credentials_submitted = Some(Box::new(hook));
+        self
+    }
+
+    /// Set the printing options.
+    pub fn print(mut self, print: PrintConfig) -> Self {
+        self.config.print = print;
+        self
+    }
+
+    /// Block links of the scheme, like `"tel"`, instead of opening them with another application.
+    pub fn block_scheme(mut self, scheme: impl Into<String>) -> Self {
+        self.config.blocked_schemes.insert(scheme.into());
+        self
+    }
+
+    /// Validate the options and return the configuration, for [`Verso::new`]. Hooks are
+    /// dropped since they aren't part of the configuration.
+    pub fn into_config(mut self) -> crate::Result<Config> {
+        self.validate()?;
+        Ok(self.config)
+    }
+
+    /// Validate the options and create Verso.
+    pub fn build(
+        mut self,
+        evl: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+    ) -> crate::Result<Verso> {
+        self.validate()?;
+        let mut verso = Verso::new(evl, proxy, self.config)?;
+        verso.hooks = self.hooks;
+        Ok(verso)
+    }
+
+    /// Validate the options and create Verso with hidden windows of the size in physical pixels,
+    /// like for automation. See [`WindowConfig::headless`].
+    pub fn build_headless(
+        mut self,
+        evl: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        size: PhysicalSize<u32>,
+    ) -> crate::Result<Verso> {
+        self.config.window.headless = Some(size);
+        self.build(evl, proxy)
+    }
+
+    fn validate(&mut self) -> crate::Result<()> {
+        let config = &mut self.config;
+        if !config.resource_dir.is_dir() {
+            return Err(invalid(format!(
+                "resources directory {} doesn't exist",
+                config.resource_dir.display()
+            )));
+        }
+        let webrtc = &config.webrtc;
+        if !webrtc.enabled && (webrtc.hide_non_relay_candidates || !webrtc.ice_servers.is_empty()) {
+            return Err(invalid("WebRTC options are set but WebRTC is disabled"));
+        }
+        if webrtc
+            .ice_servers
+            .iter()
+            .any(|server| server.urls.is_empty())
+        {
+            return Err(invalid("an ICE server has no URL"));
+        }
+        if let Some(size) = config.window.headless {
+            if size.width == 0 || size.height == 0 {
+                return Err(invalid("headless window size must not be zero"));
+            }
+            if config.window.fullscreen {
+                return Err(invalid("headless windows can't be fullscreen"));
+            }
+        }
+        let fonts = &config.fonts;
+        if fonts.default_size == Some(0) || fonts.default_monospace_size == Some(0) {
+            return Err(invalid("default font size must not be zero"));
+        }
+        if config.sandbox == SandboxMode::Enabled && !crate::sandbox::is_supported() {
+            return Err(invalid("sandboxing isn't supported on this platform"));
+        }
+        let insets = config.content_insets;
+        if [insets.top, insets.right, insets.bottom, insets.left]
+            .iter()
+            .any(|inset| !inset.is_finite() || *inset < 0.0)
+        {
+            return Err(invalid("content insets must be finite and not negative"));
+        }
+        if config.opts.devtools_server_enabled {
+            if config.policies.disable_devtools {
+                return Err(Error::DisabledByPolicy("developer tools"));
+            }
+            // Servo binds the port on its own thread and only logs failures, so check that it's
+            // free, and pick a free port now so its address is known.
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, config.opts.devtools_port))?;
+            config.opts.devtools_port = listener.local_addr()?.port();
+        }
+        if let Some(name) = &config.partition {
+            validate_name(name).map_err(|e| invalid(e.to_string()))?;
+            if config.opts.config_dir.is_none() {
+                return Err(invalid("a partition is set without a config directory"));
+            }
+        }
+        config.feature_policies = std::mem::take(&mut config.feature_policies)
+            .into_iter()
+            .map(|(origin, policy)| match normalize_origin(&origin) {
+                Ok(origin) => Ok((origin, policy)),
+                Err(e) => Err(invalid(format!("feature policy origin {origin}: {e}"))),
+            })
+            .collect::<crate::Result<_>>()?;
+        config.dns_overrides = std::mem::take(&mut config.dns_overrides)
+            .into_iter()
+            .map(|(host, address)| match normalize_host(&host) {
+                Ok(host) => Ok((host, address)),
+                Err(e) => Err(invalid(e.to_string())),
+            })
+            .collect::<crate::Result<_>>()?;
+        Ok(())
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidConfig(message.into())
+}
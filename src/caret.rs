@@ -0,0 +1,74 @@
+//! Scrolling the page to keep the caret visible while typing or moving it with the keyboard in
+//! editable fields, like in a long text area.
+//!
+//! Servo doesn't report caret positions to the embedder, so after each key press in an editable
+//! element, Verso runs a script on the next frame which finds the caret and scrolls the page by
+//! the distance it's out of view. The visible region excludes the content insets set with
+//! [`crate::Verso::set_content_insets`], like a toolbar or an on-screen keyboard drawn over the
+//! web view. In text areas, the caret line is found from the line breaks before it, so lines
+//! wrapped by layout are ignored.
+
+/// Parts of the web view covered by the embedder, in CSS pixels, which the caret is kept out of.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ContentInsets {
+    /// Height covered at the top.
+    pub top: f32,
+    /// Width covered on the right.
+    pub right: f32,
+    /// Height covered at the bottom.
+    pub bottom: f32,
+    /// Width covered on the left.
+    pub left: f32,
+}
+
+/// Script scrolling the page on the next frame so the caret of the focused element is visible
+/// out of the insets.
+pub(crate) fn caret_follow_script(insets: ContentInsets) -> String {
+    let ContentInsets {
+        top,
+        right,
+        bottom,
+        left,
+    } = insets;
+    format!(
+        "requestAnimationFrame(() => {{
+            const caretRect = () => {{
+                const field = document.activeElement;
+                if (field && (field.tagName === 'TEXTAREA' || field.tagName === 'INPUT')) {{
+                    const rect = field.getBoundingClientRect();
+                    if (field.tagName === 'INPUT' || field.selectionStart == null) return rect;
+                    const style = getComputedStyle(field);
+                    const lineHeight = parseFloat(style.lineHeight)
+                        || parseFloat(style.fontSize) * 1.2;
+                    const line = field.value.slice(0, field.selectionEnd).split('\\n').length - 1;
+                    const y = rect.top + field.clientTop + parseFloat(style.paddingTop)
+                        + line * lineHeight - field.scrollTop;
+                    return {{ left: rect.left, right: rect.right, top: y, bottom: y + lineHeight }};
+                }}
+                const selection = getSelection();
+                if (!selection.rangeCount) return null;
+                const range = selection.getRangeAt(0).cloneRange();
+                range.collapse(false);
+                const rect = range.getBoundingClientRect();
+                if (rect.width || rect.height) return rect;
+                return field ? field.getBoundingClientRect() : null;
+            }};
+            const rect = caretRect();
+            if (!rect) return;
+            const view = {{
+                left: {left},
+                top: {top},
+                right: innerWidth - {right},
+                bottom: innerHeight - {bottom},
+            }};
+            const delta = (start, end, viewStart, viewEnd) => {{
+                if (end - start > viewEnd - viewStart || start < viewStart) return start - viewStart;
+                if (end > viewEnd) return end - viewEnd;
+                return 0;
+            }};
+            const x = delta(rect.left, rect.right, view.left, view.right);
+            const y = delta(rect.top, rect.bottom, view.top, view.bottom);
+            if (x || y) scrollBy(x, y);
+        }});"
+    )
+}
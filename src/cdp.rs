@@ -0,0 +1,348 @@
+//! A small subset of the [Chrome DevTools Protocol](https://chromedevtools.github.io/devtools-protocol/)
+//! so automation clients can attach to Verso.
+//!
+//! The server listens for WebSocket connections on
+//! `ws://127.0.0.1:<port>/devtools/browser/<token>`, given by [`CdpServer::url`], where the
+//! token is generated randomly when the server starts. It only accepts connections from loopback
+//! to the path with the token, and rejects handshakes with an `Origin` header, which browsers
+//! send for WebSockets opened by web pages. Following methods are supported:
+//!
+//! - `Page.navigate` with `url`
+//! - `Runtime.evaluate` with `expression`, which may be statements like with `eval`
+//! - `Input.dispatchMouseEvent` with `type` (`mousePressed`, `mouseReleased` or `mouseMoved`),
+//!   `x`, `y` in CSS pixels of the page and `button` (`left`, `right` or `middle`)
+//! - `Page.captureScreenshot`, which returns the web view of the current window as a PNG image
+//!   encoded in base64, read back from the next frame
+//!
+//! Other methods are answered with a "method not found" error.
+//!
+//! A single thread serves all connections. It polls their sockets without blocking and sends the
+//! replies of requests in the order they came, once Verso answered them.
+
+use std::{
+    collections::VecDeque,
+    io,
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
+use script_traits::webdriver_msg::{WebDriverJSResult, WebDriverJSValue};
+use serde_json::{json, Value};
+use tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::StatusCode,
+    Message, WebSocket,
+};
+use winit::event_loop::EventLoopProxy;
+
+/// Time the server sleeps when no connection has anything to read or to reply.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Longest time the WebSocket handshake of a new connection may take.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A request came from a CDP client.
+pub struct CdpRequest {
+    /// The method and its parameters.
+    pub method: CdpMethod,
+    /// Sender to reply the result of the method.
+    pub reply: Sender<Result<Value, String>>,
+}
+
+/// Supported CDP methods.
+#[derive(Clone, Debug)]
+pub enum CdpMethod {
+    /// `Page.navigate`
+    Navigate(String),
+    /// `Runtime.evaluate`
+    Evaluate(String),
+    /// `Input.dispatchMouseEvent`
+    DispatchMouseEvent {
+        /// `mousePressed`, `mouseReleased` or `mouseMoved`.
+        event_type: String,
+        /// X position in CSS pixels of the page.
+        x: f32,
+        /// Y position in CSS pixels of the page.
+        y: f32,
+        /// `left`, `right` or `middle`.
+        button: String,
+    },
+    /// `Page.captureScreenshot`
+    CaptureScreenshot,
+}
+
+impl CdpMethod {
+    fn parse(method: &str, params: &Value) -> Option<Self> {
+        let str_param = |name: &str| params.get(name).and_then(Value::as_str).map(String::from);
+        let f32_param = |name: &str| params.get(name).and_then(Value::as_f64).map(|v| v as f32);
+        Some(match method {
+            "Page.navigate" => CdpMethod::Navigate(str_param("url")?),
+            "Runtime.evaluate" => CdpMethod::Evaluate(str_param("expression")?),
+            "Input.dispatchMouseEvent" => CdpMethod::DispatchMouseEvent {
+                event_type: str_param("type")?,
+                x: f32_param("x")?,
+                y: f32_param("y")?,
+                button: str_param("button").unwrap_or_else(|| "left".into()),
+            },
+            "Page.captureScreenshot" => CdpMethod::CaptureScreenshot,
+            _ => return None,
+        })
+    }
+}
+
+/// Handle of the CDP server thread.
+pub struct CdpServer {
+    receiver: Receiver<CdpRequest>,
+    address: SocketAddr,
+    token: String,
+    /// Replies of `Page.captureScreenshot` waiting for the frame to be read back.
+    pub(crate) screenshot_replies: Vec<Sender<Result<Value, String>>>,
+}
+
+impl CdpServer {
+    /// Start listening on the provided port. Use port `0` to pick a free one.
+    pub fn start(port: u16, proxy: EventLoopProxy<()>) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let token = crate::token::generate()?;
+        let (sender, receiver) = unbounded();
+        let path = format!("/devtools/browser/{token}");
+        thread::Builder::new()
+            .name("CdpServer".into())
+            .spawn(move || serve(listener, path, sender, proxy))?;
+        log::info!("CDP server is listening on ws://{address}/devtools/browser/");
+        Ok(Self {
+            receiver,
+            address,
+            token,
+            screenshot_replies: Vec::new(),
+        })
+    }
+
+    /// The address the server is listening on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The WebSocket URL clients connect to, with the token of the server.
+    pub fn url(&self) -> String {
+        format!("ws://{}/devtools/browser/{}", self.address, self.token)
+    }
+
+    /// Take the next pending request without blocking.
+    pub fn try_recv(&self) -> Option<CdpRequest> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Serve all connections until Verso shuts down.
+fn serve(
+    listener: TcpListener,
+    path: String,
+    sender: Sender<CdpRequest>,
+    proxy: EventLoopProxy<()>,
+) {
+    let mut connections: Vec<Connection> = Vec::new();
+    loop {
+        let mut idle = true;
+        match listener.accept() {
+            Ok((stream, _)) => {
+                idle = false;
+                connections.extend(Connection::accept(stream, &path));
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => log::warn!("CDP server failed to accept connection: {e}"),
+        }
+        let mut shut_down = false;
+        connections.retain_mut(|connection| match connection.poll(&sender, &proxy) {
+            Poll::Idle => true,
+            Poll::Busy => {
+                idle = false;
+                true
+            }
+            Poll::Closed => false,
+            Poll::ShutDown => {
+                shut_down = true;
+                false
+            }
+        });
+        if shut_down {
+            return;
+        }
+        if idle {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Outcome of polling a connection.
+enum Poll {
+    /// Nothing was read or replied.
+    Idle,
+    /// Requests were read or replied.
+    Busy,
+    /// The client closed the connection.
+    Closed,
+    /// Verso has shut down.
+    ShutDown,
+}
+
+/// A client connection with the requests waiting for Verso to reply.
+struct Connection {
+    socket: WebSocket<TcpStream>,
+    pending: VecDeque<(Value, Receiver<Result<Value, String>>)>,
+}
+
+impl Connection {
+    /// Accept a connection from loopback and handshake, if it's to the path with the token and
+    /// not from a web page.
+    fn accept(stream: TcpStream, path: &str) -> Option<Self> {
+        if !stream.peer_addr().map_or(false, |a| a.ip().is_loopback()) {
+            log::warn!("CDP server rejected a connection not from loopback");
+            return None;
+        }
+        let blocking = stream
+            .set_nonblocking(false)
+            .and_then(|()| stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)));
+        if let Err(e) = blocking {
+            log::warn!("CDP server failed to set up connection: {e}");
+            return None;
+        }
+        let check =
+            |request: &Request, response: Response| check_handshake(request, path, response);
+        let socket = match tungstenite::accept_hdr(stream, check) {
+            Ok(socket) => socket,
+            Err(e) => {
+                log::warn!("CDP server failed to handshake: {e}");
+                return None;
+            }
+        };
+        if let Err(e) = socket.get_ref().set_nonblocking(true) {
+            log::warn!("CDP server failed to set up connection: {e}");
+            return None;
+        }
+        Some(Self {
+            socket,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Read the requests which arrived, and send the replies which are ready.
+    fn poll(&mut self, sender: &Sender<CdpRequest>, proxy: &EventLoopProxy<()>) -> Poll {
+        let mut poll = Poll::Idle;
+        loop {
+            let text = match self.socket.read() {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) => return Poll::Closed,
+                Ok(_) => continue,
+                Err(tungstenite::Error::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(_) => return Poll::Closed,
+            };
+            poll = Poll::Busy;
+            let Ok(request) = serde_json::from_str::<Value>(&text) else {
+                continue;
+            };
+            let id = request.get("id").cloned().unwrap_or(Value::Null);
+            let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+            let params = request.get("params").cloned().unwrap_or(Value::Null);
+            let (reply, reply_receiver) = unbounded();
+            match CdpMethod::parse(method, &params) {
+                Some(method) => {
+                    if sender.send(CdpRequest { method, reply }).is_err() {
+                        return Poll::ShutDown;
+                    }
+                    let _ = proxy.send_event(());
+                }
+                None => {
+                    let _ = reply.send(Err(format!("'{method}' wasn't found")));
+                }
+            }
+            self.pending.push_back((id, reply_receiver));
+        }
+
+        while let Some((_, reply)) = self.pending.front() {
+            let result = match reply.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => Err("Verso has shut down".into()),
+            };
+            let Some((id, _)) = self.pending.pop_front() else {
+                break;
+            };
+            let response = match result {
+                Ok(result) => json!({ "id": id, "result": result }),
+                Err(message) => {
+                    json!({ "id": id, "error": { "code": -32000, "message": message } })
+                }
+            };
+            poll = Poll::Busy;
+            match self.socket.send(Message::Text(response.to_string())) {
+                Ok(()) => {}
+                // The reply is queued and sent by later reads.
+                Err(tungstenite::Error::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(_) => return Poll::Closed,
+            }
+        }
+        poll
+    }
+}
+
+/// Reject handshakes to other paths than the one with the token, and handshakes of web pages,
+/// which carry their origin.
+fn check_handshake(
+    request: &Request,
+    path: &str,
+    response: Response,
+) -> Result<Response, ErrorResponse> {
+    let has_origin = request
+        .headers()
+        .get("origin")
+        .is_some_and(|origin| !origin.is_empty());
+    if has_origin || !crate::token::matches(request.uri().path(), path) {
+        let mut error = ErrorResponse::new(Some("Forbidden".into()));
+        *error.status_mut() = StatusCode::FORBIDDEN;
+        return Err(error);
+    }
+    Ok(response)
+}
+
+/// Script evaluating the expression of `Runtime.evaluate` in the global scope. It may be
+/// statements, and the value is the one of the last statement, like with `eval`.
+pub(crate) fn evaluate_script(expression: &str) -> String {
+    let source = serde_json::to_string(expression).unwrap_or_default();
+    format!("return (0, eval)({source});")
+}
+
+/// The `Page.captureScreenshot` result of a frame encoded as PNG.
+pub(crate) fn screenshot_result(png: Result<Vec<u8>, String>) -> Result<Value, String> {
+    use base64::Engine;
+
+    let png = png?;
+    Ok(json!({ "data": base64::engine::general_purpose::STANDARD.encode(png) }))
+}
+
+/// Convert the result of script evaluation to the `Runtime.evaluate` result.
+pub fn evaluate_result(result: WebDriverJSResult) -> Result<Value, String> {
+    let value = result.map_err(|e| format!("{e:?}"))?;
+    Ok(json!({ "result": remote_object(value) }))
+}
+
+fn remote_object(value: WebDriverJSValue) -> Value {
+    match value {
+        WebDriverJSValue::Undefined => json!({ "type": "undefined" }),
+        WebDriverJSValue::Null => json!({ "type": "object", "subtype": "null", "value": null }),
+        WebDriverJSValue::Boolean(b) => json!({ "type": "boolean", "value": b }),
+        WebDriverJSValue::Int(i) => json!({ "type": "number", "value": i }),
+        WebDriverJSValue::Number(n) => json!({ "type": "number", "value": n }),
+        WebDriverJSValue::String(s) => json!({ "type": "string", "value": s }),
+        WebDriverJSValue::ArrayLike(values) => json!({
+            "type": "object",
+            "subtype": "array",
+            "value": values.into_iter().map(remote_object).collect::<Vec<_>>(),
+        }),
+        v => json!({ "type": "object", "description": format!("{v:?}") }),
+    }
+}
@@ -0,0 +1,119 @@
+//! Rich clipboard contents, complementing the plain text Servo copies.
+//!
+//! Servo only passes the text of a copied selection to the embedder, so Verso reads the HTML of
+//! the selection with a script and writes both flavors to the clipboard. Images are copied with
+//! [`crate::Verso::copy_image`] as decoded pixels. Pasting still uses the text flavor, which is
+//! the only one Servo's editors accept.
+
+use std::borrow::Cow;
+
+use arboard::{Clipboard, ImageData};
+use base::id::BrowsingContextId;
+use compositing_traits::ConstellationMsg;
+use crossbeam_channel::Sender;
+use script_traits::webdriver_msg::WebDriverJSValue;
+
+use crate::verso::execute_script;
+
+/// Script returning the HTML and the text of the selection as JSON.
+const GET_SELECTION_HTML: &str = "
+const selection = window.getSelection();
+if (!selection.rangeCount || selection.isCollapsed) return '';
+const container = document.createElement('div');
+for (let i = 0; i < selection.rangeCount; i++) {
+    container.appendChild(selection.getRangeAt(i).cloneContents());
+}
+return JSON.stringify({ html: container.innerHTML, text: selection.toString() });";
+
+/// Add the HTML of the selection in the browsing context to the clipboard, alongside the text
+/// Servo copied. The clipboard is left alone if the selection changed since the copy.
+pub fn copy_selection_html(
+    sender: &Sender<ConstellationMsg>,
+    browsing_context_id: BrowsingContextId,
+    text: String,
+) {
+    let Some(receiver) = execute_script(sender, browsing_context_id, GET_SELECTION_HTML) else {
+        return;
+    };
+    std::thread::spawn(move || {
+        let Ok(Ok(WebDriverJSValue::String(json))) = receiver.recv() else {
+            return;
+        };
+        let Ok(selection) = serde_json::from_str::<serde_json::Value>(&json) else {
+            return;
+        };
+        let (Some(html), Some(selected)) = (selection["html"].as_str(), selection["text"].as_str())
+        else {
+            return;
+        };
+        if html.is_empty() || selected != text {
+            return;
+        }
+        let result =
+            Clipboard::new().and_then(|mut clipboard| clipboard.set_html(html, Some(&text)));
+        if let Err(e) = result {
+            log::warn!("Verso failed to copy HTML to the clipboard: {e}");
+        }
+    });
+}
+
+/// Copy the pixels of the image at the point in CSS pixels of the browsing context to the
+/// clipboard. Images of other origins can't be read by the page, so they aren't copied.
+pub fn copy_image_at(
+    sender: &Sender<ConstellationMsg>,
+    browsing_context_id: BrowsingContextId,
+    point: (f32, f32),
+) {
+    // Pixels are returned as a string of one character per byte, which is much smaller than a
+    // JSON array of numbers.
+    let script = format!(
+        "const image = document.elementFromPoint({x}, {y});
+        if (!(image instanceof HTMLImageElement) || !image.complete) return '';
+        const canvas = document.createElement('canvas');
+        canvas.width = image.naturalWidth;
+        canvas.height = image.naturalHeight;
+        const context = canvas.getContext('2d');
+        context.drawImage(image, 0, 0);
+        const data = context.getImageData(0, 0, canvas.width, canvas.height).data;
+        let bytes = '';
+        for (let i = 0; i < data.length; i += 0x8000) {{
+            bytes += String.fromCharCode.apply(null, data.subarray(i, i + 0x8000));
+        }}
+        return JSON.stringify({{ width: canvas.width, height: canvas.height, bytes }});",
+        x = point.0,
+        y = point.1,
+    );
+    let Some(receiver) = execute_script(sender, browsing_context_id, script) else {
+        return;
+    };
+    std::thread::spawn(move || {
+        let json = match receiver.recv() {
+            Ok(Ok(WebDriverJSValue::String(json))) if !json.is_empty() => json,
+            Ok(Ok(_)) => return log::debug!("Verso found no image to copy"),
+            result => return log::warn!("Verso failed to read the image: {result:?}"),
+        };
+        let Ok(image) = serde_json::from_str::<serde_json::Value>(&json) else {
+            return;
+        };
+        let (Some(width), Some(height), Some(bytes)) = (
+            image["width"].as_u64(),
+            image["height"].as_u64(),
+            image["bytes"].as_str(),
+        ) else {
+            return;
+        };
+        let bytes: Vec<u8> = bytes.chars().map(|c| c as u8).collect();
+        if bytes.len() as u64 != width * height * 4 {
+            return log::warn!("Verso read an image of the wrong size");
+        }
+        let image = ImageData {
+            width: width as usize,
+            height: height as usize,
+            bytes: Cow::Owned(bytes),
+        };
+        let result = Clipboard::new().and_then(|mut clipboard| clipboard.set_image(image));
+        if let Err(e) = result {
+            log::warn!("Verso failed to copy the image to the clipboard: {e}");
+        }
+    });
+}
@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use std::ffi::c_void;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
@@ -11,27 +12,30 @@ use compositing_traits::{
 };
 use crossbeam_channel::Sender;
 use embedder_traits::Cursor;
-use euclid::{Point2D, Scale, Size2D, Transform3D, Vector2D};
+use euclid::{Point2D, Rect, Scale, Size2D, Transform3D, Vector2D};
 use gleam::gl;
 use ipc_channel::ipc;
 use log::{debug, error, trace, warn};
 use profile_traits::time::{self as profile_time, profile, ProfilerCategory};
 use profile_traits::{mem, time};
+use raw_window_handle::HasWindowHandle;
 use script_traits::CompositorEvent::{MouseButtonEvent, MouseMoveEvent, TouchEvent, WheelEvent};
 use script_traits::{
     AnimationState, AnimationTickType, ConstellationControlMsg, MouseButton, MouseEventType,
     ScrollState, TouchEventType, TouchId, WheelDelta, WindowSizeData, WindowSizeType,
 };
+use servo_config::opts;
 use servo_geometry::DeviceIndependentPixel;
 use style_traits::{CSSPixel, DevicePixel, PinchZoomFactor};
 use surfman::Surface;
-use webrender::{RenderApi, Transaction};
+use webrender::{ClearCache, DebugCommand, RenderApi, Transaction};
 use webrender_api::units::{
-    DeviceIntPoint, DeviceIntRect, DeviceIntSize, DevicePoint, LayoutPoint, LayoutRect, LayoutSize,
-    LayoutVector2D, WorldPoint,
+    DeviceIntPoint, DeviceIntRect, DeviceIntSize, DevicePoint, DeviceVector2D, LayoutPoint,
+    LayoutRect, LayoutSize, LayoutVector2D, WorldPoint,
 };
 use webrender_api::{
-    BuiltDisplayList, DirtyRect, DisplayListPayload, DocumentId, Epoch as WebRenderEpoch,
+    BorderRadius, BuiltDisplayList, ClipMode, ColorF, ColorU, CommonItemProperties,
+    ComplexClipRegion, DirtyRect, DisplayListPayload, DocumentId, Epoch as WebRenderEpoch,
     ExternalScrollId, FontInstanceOptions, HitTestFlags, PipelineId as WebRenderPipelineId,
     PropertyBinding, ReferenceFrameKind, RenderReasons, SampledScrollOffset, ScrollLocation,
     SpaceAndClipInfo, SpatialId, SpatialTreeItemKey, TransformStyle,
@@ -44,8 +48,16 @@ use webrender_traits::{
 };
 use winit::window::WindowId;
 
+use crate::autoscroll::INDICATOR_RADIUS;
+use crate::debug_overlay::{format_memory, resident_memory, DebugOverlay};
+use crate::hooks::LifecycleEvent;
+use crate::latency::{LatencyStats, LatencyTracker};
+use crate::progress::{ProgressBarPosition, ProgressBarStyle};
+use crate::scrollbars::{ScrollGeometry, ScrollbarMode, ScrollbarStyle};
 use crate::touch::{TouchAction, TouchHandler};
+use crate::video::{RecordingOptions, VideoRecorder};
 use crate::window::Window;
+use crate::zoom::{css_to_device_scale, magnify_factor, FitMode, MagnifyTarget, ZoomChange};
 
 /// Data used to construct a compositor.
 pub struct InitialCompositorState {
@@ -99,6 +111,12 @@ pub enum MouseWindowEvent {
 const MAX_ZOOM: f32 = 8.0;
 const MIN_ZOOM: f32 = 0.1;
 
+// Left and right margin in device pixels of the element zoomed by smart magnify gesture
+const SMART_MAGNIFY_MARGIN: f32 = 16.0;
+
+// Pinch zoom factors closer to 1 than this are treated as unzoomed
+const PINCH_ZOOM_EPSILON: f32 = 1e-3;
+
 // NB: Never block on the Constellation, because sometimes the Constellation blocks on us.
 /// The Verso compositor contains a GL rendering context with a WebRender instance.
 /// The compositor will communicate with Servo using messages from the Constellation,
@@ -138,6 +156,13 @@ pub struct IOCompositor {
     /// "Desktop-style" zoom that resizes the viewport to fit the window.
     page_zoom: Scale<f32, CSSPixel, DeviceIndependentPixel>,
 
+    /// Fit mode which set the page zoom, kept until the zoom is changed otherwise.
+    fit_mode: Option<FitMode>,
+
+    /// Set when the window is resized in a fit mode, to the epoch of the display list of the web
+    /// view laid out for the old size. The zoom is fitted again once a newer one arrives.
+    pending_refit: Option<Option<WebRenderEpoch>>,
+
     /// Tracks whether we should composite this frame.
     composition_request: CompositionRequest,
 
@@ -166,6 +191,10 @@ pub struct IOCompositor {
     /// Pending scroll/zoom events.
     pending_scroll_zoom_events: Vec<ScrollZoomEvent>,
 
+    /// Buffer of compositor messages received in one batch. It's kept around so the
+    /// allocation can be reused between batches.
+    compositor_messages: Vec<CompositorMsg>,
+
     /// Used by the logic that determines when it is safe to output an
     /// image for the reftest framework.
     ready_to_save_state: ReadyState,
@@ -216,6 +245,135 @@ pub struct IOCompositor {
     /// will want to avoid blocking on UI events, and just
     /// run the event loop at the vsync interval.
     pub is_animating: bool,
+
+    /// Latency samples from input events to presents.
+    input_latency: LatencyTracker,
+
+    /// Style of the built-in loading progress bar, if it's enabled.
+    progress_bar_style: Option<ProgressBarStyle>,
+
+    /// Style of the scrollbars drawn over the document.
+    scrollbar_style: ScrollbarStyle,
+
+    /// Heads-up display of live browser state, if it's on.
+    debug_overlay: Option<DebugOverlay>,
+
+    /// Number of compositor messages handled in the last batch, shown by the debug overlay.
+    last_batch_size: usize,
+
+    /// Size the rendering context failed to resize to, retried before a composite once
+    /// `next_resize_retry` is reached.
+    pending_resize: Option<DeviceIntSize>,
+
+    /// Number of failed attempts to resize the rendering context in a row.
+    resize_failures: u32,
+
+    /// Time the pending resize is retried at.
+    next_resize_retry: Instant,
+
+    /// Cause of a lost GL context or surface, detected while compositing and recovered from
+    /// before the next composite.
+    surface_lost: Option<SurfaceResetCause>,
+
+    /// Number of resets since the last successful present.
+    failed_resets: u32,
+
+    /// Size of the current window. It's the viewport size unless a render resolution is set.
+    window_size: DeviceIntSize,
+
+    /// Fixed size to render at regardless of the window size.
+    render_resolution: Option<DeviceIntSize>,
+
+    /// Offscreen framebuffer rendered into when a render resolution is set.
+    render_target: Option<RenderTarget>,
+
+    /// Video recording of the composited frames, if one is in progress.
+    video_recorder: Option<VideoRecorder>,
+
+    /// Whether the next composite is read back into `captured_frame`.
+    capture_pending: bool,
+
+    /// Last frame read back after a capture was requested, as RGBA pixels, bottom row first.
+    #[cfg_attr(
+        not(any(feature = "visual-test", feature = "remote-control")),
+        allow(dead_code)
+    )]
+    captured_frame: Option<(DeviceIntSize, Vec<u8>)>,
+}
+
+/// An offscreen framebuffer with a color texture and a depth stencil buffer.
+struct RenderTarget {
+    size: DeviceIntSize,
+    framebuffer: gl::GLuint,
+    texture: gl::GLuint,
+    depth_stencil: gl::GLuint,
+}
+
+impl RenderTarget {
+    fn new(gl: &dyn gl::Gl, size: DeviceIntSize) -> Self {
+        let texture = gl.gen_textures(1)[0];
+        gl.bind_texture(gl::TEXTURE_2D, texture);
+        gl.tex_image_2d(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA as gl::GLint,
+            size.width,
+            size.height,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            None,
+        );
+        gl.tex_parameter_i(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MIN_FILTER,
+            gl::LINEAR as gl::GLint,
+        );
+        gl.tex_parameter_i(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MAG_FILTER,
+            gl::LINEAR as gl::GLint,
+        );
+        gl.bind_texture(gl::TEXTURE_2D, 0);
+
+        let depth_stencil = gl.gen_renderbuffers(1)[0];
+        gl.bind_renderbuffer(gl::RENDERBUFFER, depth_stencil);
+        gl.renderbuffer_storage(
+            gl::RENDERBUFFER,
+            gl::DEPTH24_STENCIL8,
+            size.width,
+            size.height,
+        );
+        gl.bind_renderbuffer(gl::RENDERBUFFER, 0);
+
+        let framebuffer = gl.gen_framebuffers(1)[0];
+        gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer);
+        gl.framebuffer_texture_2d(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            gl::TEXTURE_2D,
+            texture,
+            0,
+        );
+        gl.framebuffer_renderbuffer(
+            gl::FRAMEBUFFER,
+            gl::DEPTH_STENCIL_ATTACHMENT,
+            gl::RENDERBUFFER,
+            depth_stencil,
+        );
+        Self {
+            size,
+            framebuffer,
+            texture,
+            depth_stencil,
+        }
+    }
+
+    fn delete(self, gl: &dyn gl::Gl) {
+        gl.delete_framebuffers(&[self.framebuffer]);
+        gl.delete_textures(&[self.texture]);
+        gl.delete_renderbuffers(&[self.depth_stencil]);
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -252,8 +410,58 @@ enum CompositingReason {
     NewWebRenderFrame,
     /// The window has been resized and will need to be synchronously repainted.
     Resize,
+    /// A video recording started and needs its first frame.
+    Recording,
+    /// A frame is captured to be read back.
+    Capture,
+    /// The text of the debug overlay changed.
+    DebugOverlay,
 }
 
+/// Cause of a reset of the rendering surface, reported to
+/// [`crate::VersoBuilder::on_surface_reset`].
+///
+/// Only the native surface is recreated. The GL context and the WebRender renderer are kept, so a
+/// context which lost its resources keeps rendering black, and there is no fallback to software
+/// rendering. Verso stops recovering after [`MAX_SURFACE_RESETS`] resets without presenting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SurfaceResetCause {
+    /// The GL context was lost, like after a driver reset or a suspend.
+    ContextLost,
+    /// Presenting the surface failed with the error.
+    PresentFailed(String),
+    /// The loss was simulated with `Verso::simulate_context_loss` in debug builds.
+    Simulated,
+}
+
+/// Renderer and process isolation of Verso, returned by [`crate::Verso::renderer_info`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RendererInfo {
+    /// Vendor of the GL implementation.
+    pub vendor: String,
+    /// Name of the GL renderer, usually the GPU.
+    pub renderer: String,
+    /// Version of the GL implementation.
+    pub version: String,
+    /// Whether pages run in content processes.
+    pub multiprocess: bool,
+    /// Whether content processes are sandboxed. See [`crate::sandbox`].
+    pub sandboxed: bool,
+}
+
+/// Number of consecutive resets after which Verso stops recovering the surface.
+pub const MAX_SURFACE_RESETS: u32 = 3;
+
+/// Delay before retrying to resize the rendering context after a failure, doubled after each
+/// failure in a row.
+const RESIZE_RETRY_DELAY: Duration = Duration::from_millis(16);
+
+/// Longest delay between retries to resize the rendering context.
+const MAX_RESIZE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// `GL_CONTEXT_LOST` of `KHR_robustness`, which gleam doesn't define.
+const GL_CONTEXT_LOST: gl::GLenum = 0x0507;
+
 #[derive(Debug, PartialEq)]
 enum CompositionRequest {
     NoCompositingNecessary,
@@ -298,6 +506,9 @@ struct PipelineDetails {
     /// The compositor-side [ScrollTree]. This is used to allow finding and scrolling
     /// nodes in the compositor before forwarding new offsets to WebRender.
     scroll_tree: ScrollTree,
+
+    /// Size of the scrollable content of the document, from its most recent display list.
+    content_size: LayoutSize,
 }
 
 impl PipelineDetails {
@@ -311,6 +522,7 @@ impl PipelineDetails {
             throttled: false,
             hit_test_items: Vec::new(),
             scroll_tree: ScrollTree::default(),
+            content_size: LayoutSize::zero(),
         }
     }
 
@@ -361,8 +573,11 @@ impl IOCompositor {
             composition_request: CompositionRequest::NoCompositingNecessary,
             touch_handler: TouchHandler::new(),
             pending_scroll_zoom_events: Vec::new(),
+            compositor_messages: Vec::new(),
             shutdown_state: ShutdownState::NotShuttingDown,
             page_zoom: Scale::new(1.0),
+            fit_mode: None,
+            pending_refit: None,
             viewport_zoom: PinchZoomFactor::new(1.0),
             min_viewport_zoom: Some(PinchZoomFactor::new(1.0)),
             max_viewport_zoom: None,
@@ -386,6 +601,22 @@ impl IOCompositor {
             pending_frames: 0,
             last_animation_tick: Instant::now(),
             is_animating: false,
+            input_latency: LatencyTracker::default(),
+            progress_bar_style: None,
+            scrollbar_style: ScrollbarStyle::default(),
+            debug_overlay: None,
+            last_batch_size: 0,
+            pending_resize: None,
+            resize_failures: 0,
+            next_resize_retry: Instant::now(),
+            surface_lost: None,
+            failed_resets: 0,
+            window_size: viewport,
+            render_resolution: None,
+            render_target: None,
+            video_recorder: None,
+            capture_pending: false,
+            captured_frame: None,
         };
 
         // Make sure the GL state is OK
@@ -393,6 +624,17 @@ impl IOCompositor {
         compositor
     }
 
+    /// Record an input event received at the provided time, to be measured until the present of
+    /// a frame requested after it.
+    pub fn on_input_event(&mut self, time: Instant) {
+        self.input_latency.on_input(time);
+    }
+
+    /// Get the statistics of the latency from input events to presents.
+    pub fn input_latency_stats(&self) -> LatencyStats {
+        self.input_latency.stats()
+    }
+
     /// Consume compositor itself and deinit webrender.
     pub fn deinit(mut self) {
         if let Err(err) = self.rendering_context.make_gl_context_current() {
@@ -463,6 +705,32 @@ impl IOCompositor {
         }
     }
 
+    /// Create a native surface for the window and bind it to the context, after it was
+    /// invalidated with [`IOCompositor::invalidate_native_surface`].
+    pub fn rebind_native_surface(&mut self, window: &mut Window) {
+        debug!("Rebinding native surface of window {:?}", window.id());
+        let handle = match window.window.window_handle() {
+            Ok(handle) => handle,
+            Err(e) => return warn!("Failed to get window handle ({:?})", e),
+        };
+        let native_widget = match self
+            .rendering_context
+            .connection()
+            .create_native_widget_from_window_handle(handle, window.size().to_untyped())
+        {
+            Ok(native_widget) => native_widget,
+            Err(e) => return warn!("Failed to create native widget ({:?})", e),
+        };
+        if let Err(e) = self
+            .rendering_context
+            .bind_native_surface_to_context(native_widget)
+        {
+            return warn!("Binding native surface to context failed ({:?})", e);
+        }
+        self.resize(window.size(), window);
+        self.composite_if_necessary(CompositingReason::Resize);
+    }
+
     /// On Android, this function will be called when the app moves to foreground
     /// and the system creates a new native surface that needs to bound to the current
     /// context.
@@ -571,6 +839,7 @@ impl IOCompositor {
 
             CompositorMsg::NewWebRenderFrameReady(recomposite_needed) => {
                 self.pending_frames -= 1;
+                self.input_latency.on_frame_ready();
 
                 if recomposite_needed {
                     if let Some(result) = self.hit_test_at_point(self.cursor_pos) {
@@ -736,6 +1005,7 @@ impl IOCompositor {
                 details.most_recent_display_list_epoch = Some(display_list_info.epoch);
                 details.hit_test_items = display_list_info.hit_test_info;
                 details.install_new_scroll_tree(display_list_info.scroll_tree);
+                details.content_size = display_list_info.content_size;
 
                 let mut transaction = Transaction::new();
                 transaction
@@ -952,6 +1222,7 @@ impl IOCompositor {
             CompositorMsg::NewWebRenderFrameReady(_) => {
                 // Subtract from the number of pending frames, but do not do any compositing.
                 self.pending_frames -= 1;
+                self.input_latency.on_frame_ready();
             }
             CompositorMsg::PendingPaintMetric(pipeline_id, epoch) => {
                 self.pending_paint_metrics.insert(pipeline_id, epoch);
@@ -967,6 +1238,7 @@ impl IOCompositor {
     /// Queue a new frame in the transaction and increase the pending frames count.
     fn generate_frame(&mut self, transaction: &mut Transaction, reason: RenderReasons) {
         self.pending_frames += 1;
+        self.input_latency.on_frame_requested();
         transaction.generate_frame(0, reason);
     }
 
@@ -1090,6 +1362,135 @@ impl IOCompositor {
             }
         }
 
+        let scrollbars = window.scrollbars.get();
+        if let (Some(geometry), Some(webview), true) = (
+            scrollbars.geometry,
+            &window.webview,
+            scrollbars.opacity > 0.0,
+        ) {
+            let style = self.scrollbar_style;
+            let width = style.device_width(self.scale_factor.get());
+            let overlay = matches!(style.mode.resolve(), ScrollbarMode::Overlay { .. });
+            let space_and_clip = SpaceAndClipInfo {
+                spatial_id: zoom_reference_frame,
+                clip_chain_id,
+            };
+            let color = |[r, g, b, a]: [f32; 4]| ColorF::new(r, g, b, a * scrollbars.opacity);
+            for rects in geometry.rects(webview.rect.to_f32(), width, zoom_factor) {
+                let track = LayoutRect::from_untyped(&(rects.track / zoom_factor).to_untyped());
+                let thumb = LayoutRect::from_untyped(&(rects.thumb / zoom_factor).to_untyped());
+                if !overlay {
+                    builder.push_rect(
+                        &CommonItemProperties::new(track, space_and_clip),
+                        track,
+                        color(style.track_color),
+                    );
+                }
+                // Overlay thumbs are rounded and inset from the edge.
+                let (thumb, clip_chain_id) = if overlay {
+                    let inset = width / zoom_factor * 0.2;
+                    let thumb = thumb.inflate(-inset, -inset);
+                    let radius = thumb.width().min(thumb.height()) / 2.0;
+                    let rounded = builder.define_clip_rounded_rect(
+                        zoom_reference_frame,
+                        ComplexClipRegion::new(
+                            thumb,
+                            BorderRadius::uniform(radius),
+                            ClipMode::Clip,
+                        ),
+                    );
+                    (
+                        thumb,
+                        builder.define_clip_chain(Some(clip_chain_id), [rounded]),
+                    )
+                } else {
+                    (thumb, clip_chain_id)
+                };
+                builder.push_rect(
+                    &CommonItemProperties::new(
+                        thumb,
+                        SpaceAndClipInfo {
+                            spatial_id: zoom_reference_frame,
+                            clip_chain_id,
+                        },
+                    ),
+                    thumb,
+                    color(style.thumb_color),
+                );
+            }
+        }
+
+        let progress_bar = window.progress_bar.get();
+        if let (Some(style), Some(webview), true) = (
+            self.progress_bar_style,
+            &window.webview,
+            progress_bar.opacity > 0.0,
+        ) {
+            let rect = webview.rect.to_f32();
+            let height = (style.height as f32).min(rect.height());
+            let y = match style.position {
+                ProgressBarPosition::Top => rect.min.y,
+                ProgressBarPosition::Bottom => rect.max.y - height,
+            };
+            let bar_rect = euclid::Box2D::new(
+                Point2D::new(rect.min.x, y),
+                Point2D::new(
+                    rect.min.x + rect.width() * progress_bar.progress,
+                    y + height,
+                ),
+            ) / zoom_factor;
+            let bar_rect = LayoutRect::from_untyped(&bar_rect.to_untyped());
+            let [r, g, b, a] = style.color;
+            builder.push_rect(
+                &CommonItemProperties::new(
+                    bar_rect,
+                    SpaceAndClipInfo {
+                        spatial_id: zoom_reference_frame,
+                        clip_chain_id,
+                    },
+                ),
+                bar_rect,
+                ColorF::new(r, g, b, a * progress_bar.opacity),
+            );
+        }
+
+        if let Some(autoscroll) = window.autoscroll.get().filter(|a| !a.is_ending()) {
+            let radius = INDICATOR_RADIUS / zoom_factor;
+            let center = LayoutPoint::from_untyped(autoscroll.origin.to_untyped()) / zoom_factor;
+            let rect = LayoutRect::new(
+                center - LayoutVector2D::new(radius, radius),
+                center + LayoutVector2D::new(radius, radius),
+            );
+            let circle = builder.define_clip_rounded_rect(
+                zoom_reference_frame,
+                ComplexClipRegion::new(rect, BorderRadius::uniform(radius), ClipMode::Clip),
+            );
+            let circle = builder.define_clip_chain(Some(clip_chain_id), [circle]);
+            builder.push_rect(
+                &CommonItemProperties::new(
+                    rect,
+                    SpaceAndClipInfo {
+                        spatial_id: zoom_reference_frame,
+                        clip_chain_id: circle,
+                    },
+                ),
+                rect,
+                ColorF::new(0.25, 0.25, 0.25, 0.6),
+            );
+            let dot = rect.inflate(-radius * 0.7, -radius * 0.7);
+            builder.push_rect(
+                &CommonItemProperties::new(
+                    dot,
+                    SpaceAndClipInfo {
+                        spatial_id: zoom_reference_frame,
+                        clip_chain_id: circle,
+                    },
+                ),
+                dot,
+                ColorF::new(1.0, 1.0, 1.0, 0.9),
+            );
+        }
+
         let built_display_list = builder.end();
 
         // NB: We are always passing 0 as the epoch here, but this doesn't seem to
@@ -1298,8 +1699,23 @@ impl IOCompositor {
     /// Resize the rendering context and all web views. Return true if the compositor should repaint and present
     /// after this.
     pub fn resize(&mut self, size: Size2D<i32, DevicePixel>, window: &mut Window) -> bool {
+        self.window_size = size;
+        let size = self.render_resolution.unwrap_or(size);
+        log::debug!(
+            "Verso Compositor resizes buffer to {size:?} with scale factor {}",
+            self.scale_factor.get()
+        );
+        // Resize the surface before relayouting the web views, so the frames of the new layout
+        // are never composited to a surface of the old size.
         let need_resize = self.on_resize_window_event(size);
+        if self.pending_resize.is_none() {
+            self.resize_webviews(size, window);
+        }
+        need_resize
+    }
 
+    /// Tell the constellation the web views of the window are resized to fit the viewport size.
+    fn resize_webviews(&mut self, size: DeviceIntSize, window: &mut Window) {
         if let Some(panel) = &mut window.panel {
             let rect = DeviceIntRect::from_size(size);
             panel.rect = rect;
@@ -1314,7 +1730,17 @@ impl IOCompositor {
         }
 
         self.send_root_pipeline_display_list(window);
-        need_resize
+        if self.fit_mode.is_some() {
+            self.pending_refit = Some(self.display_list_epoch(window));
+        }
+    }
+
+    /// Get the epoch of the most recent display list of the web view of the window.
+    fn display_list_epoch(&self, window: &Window) -> Option<WebRenderEpoch> {
+        let pipeline_id = self.webviews.get(&window.webview.as_ref()?.webview_id)?;
+        self.pipeline_details
+            .get(pipeline_id)?
+            .most_recent_display_list_epoch
     }
 
     /// Handle the window resize event and return a boolean to tell embedder if it should further
@@ -1324,7 +1750,34 @@ impl IOCompositor {
             return false;
         }
 
-        let _ = self.rendering_context.resize(new_viewport.to_untyped());
+        if let Err(e) = self.rendering_context.resize(self.window_size.to_untyped()) {
+            // The context usually keeps failing for a while, so only the first failure is
+            // reported and retries back off.
+            if self.resize_failures == 0 {
+                warn!(
+                    "Failed to resize rendering context to {:?}, retrying later ({e:?})",
+                    self.window_size
+                );
+            } else {
+                debug!(
+                    "Failed to resize rendering context to {:?} again ({e:?})",
+                    self.window_size
+                );
+            }
+            let delay = RESIZE_RETRY_DELAY * 2u32.pow(self.resize_failures.min(6));
+            self.resize_failures = self.resize_failures.saturating_add(1);
+            self.next_resize_retry = Instant::now() + delay.min(MAX_RESIZE_RETRY_DELAY);
+            self.pending_resize = Some(new_viewport);
+            return true;
+        }
+        if self.resize_failures > 0 {
+            debug!(
+                "Resized rendering context after {} failed attempts",
+                self.resize_failures
+            );
+            self.resize_failures = 0;
+        }
+        self.pending_resize = None;
         self.viewport = new_viewport;
         let mut transaction = Transaction::new();
         transaction.set_document_view(DeviceIntRect::from_size(self.viewport));
@@ -1334,6 +1787,140 @@ impl IOCompositor {
         true
     }
 
+    /// Render at the fixed size instead of the window size, or at the window size if it's
+    /// `None`. The frame is scaled to fit the window keeping its aspect ratio, centered with
+    /// black bars, and filtered linearly. Input events are mapped back to the frame.
+    pub fn set_render_resolution(&mut self, size: Option<DeviceIntSize>, window: &mut Window) {
+        if self.render_resolution == size {
+            return;
+        }
+        self.render_resolution = size;
+        if let Some(target) = self.render_target.take() {
+            target.delete(&*self.webrender_gl);
+        }
+        self.resize(window.size(), window);
+    }
+
+    /// Get the size of the scrollable content of the document in the web view, from its most
+    /// recent display list, which layout sends again as the document grows.
+    pub fn content_size(&self, webview_id: TopLevelBrowsingContextId) -> Option<LayoutSize> {
+        let pipeline_id = self.webviews.get(&webview_id)?;
+        Some(self.pipeline_details.get(pipeline_id)?.content_size)
+    }
+
+    /// Get the scroll position and range of the document in the current web view of the window.
+    fn scroll_geometry(&self, window: &Window) -> Option<ScrollGeometry> {
+        let pipeline_id = *self.webviews.get(&window.webview.as_ref()?.webview_id)?;
+        let details = self.pipeline_details.get(&pipeline_id)?;
+        // Layout gives the scroll node of the document the first id of the pipeline.
+        let root_id = ExternalScrollId(0, pipeline_id.into());
+        let info = details
+            .scroll_tree
+            .nodes
+            .iter()
+            .filter_map(|node| node.scroll_info.as_ref())
+            .find(|info| info.external_id == root_id)?;
+        Some(ScrollGeometry {
+            position: -info.offset,
+            scrollable: info.scrollable_size,
+        })
+    }
+
+    /// Start recording the composited frames to a video file. A recording in progress is
+    /// finished first. See [`crate::video`].
+    pub fn start_recording(&mut self, path: &Path, options: RecordingOptions) {
+        if let Err(e) = self.stop_recording() {
+            warn!("Failed to finish the previous recording: {e}");
+        }
+        self.video_recorder = Some(VideoRecorder::new(path, options));
+        self.composite_if_necessary(CompositingReason::Recording);
+    }
+
+    /// Finish the recording in progress and return the path of the video file.
+    pub fn stop_recording(&mut self) -> std::io::Result<Option<PathBuf>> {
+        self.video_recorder
+            .take()
+            .map(|recorder| recorder.finish(Instant::now()))
+            .transpose()
+    }
+
+    /// Composite and read back the next frame, taken with [`IOCompositor::take_captured_frame`].
+    #[cfg_attr(
+        not(any(feature = "visual-test", feature = "remote-control")),
+        allow(dead_code)
+    )]
+    pub(crate) fn capture_frame(&mut self) {
+        self.capture_pending = true;
+        self.composite_if_necessary(CompositingReason::Capture);
+    }
+
+    /// Take the frame read back after [`IOCompositor::capture_frame`], as RGBA pixels with the
+    /// bottom row first.
+    #[cfg_attr(
+        not(any(feature = "visual-test", feature = "remote-control")),
+        allow(dead_code)
+    )]
+    pub(crate) fn take_captured_frame(&mut self) -> Option<(DeviceIntSize, Vec<u8>)> {
+        self.captured_frame.take()
+    }
+
+    /// Read back the frame rendered into the framebuffer and pass it to the video recorder and
+    /// the pending capture.
+    fn read_frame(&mut self, framebuffer: gl::GLuint) {
+        let size = self.viewport;
+        let gl = &self.webrender_gl;
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, framebuffer);
+        let pixels = gl.read_pixels(0, 0, size.width, size.height, gl::RGBA, gl::UNSIGNED_BYTE);
+        if std::mem::take(&mut self.capture_pending) {
+            self.captured_frame = Some((size, pixels.clone()));
+        }
+        if let Some(recorder) = &mut self.video_recorder {
+            recorder.push_frame(size, pixels, Instant::now());
+        }
+    }
+
+    /// Scale and offset from the render resolution to the window.
+    fn render_fit(&self) -> (f32, DeviceVector2D) {
+        let window = self.window_size.to_f32();
+        let frame = self.viewport.to_f32();
+        let scale = (window.width / frame.width).min(window.height / frame.height);
+        let offset = (window - frame * scale) / 2.0;
+        (scale, DeviceVector2D::new(offset.width, offset.height))
+    }
+
+    /// Map the point in the window to the rendered frame.
+    fn window_to_frame_point(&self, point: DevicePoint) -> DevicePoint {
+        if self.render_resolution.is_none() {
+            return point;
+        }
+        let (scale, offset) = self.render_fit();
+        (point - offset) / scale
+    }
+
+    /// Map a rectangle in CSS pixels relative to the viewport of the web view at `webview_rect`
+    /// to device pixels in the window. The offset of pinch zoom isn't taken into account.
+    pub(crate) fn page_rect_to_window(
+        &self,
+        rect: Rect<f32, CSSPixel>,
+        webview_rect: DeviceIntRect,
+    ) -> DeviceIntRect {
+        let rect = (rect * self.device_pixels_per_page_pixel())
+            .translate(webview_rect.min.to_f32().to_vector());
+        let rect = match self.render_resolution {
+            Some(_) => {
+                let (scale, offset) = self.render_fit();
+                rect.scale(scale, scale).translate(offset)
+            }
+            None => rect,
+        };
+        rect.round_out().to_i32().to_box2d()
+    }
+
+    /// The scale factor of the current window, which can be fractional like `1.25`.
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor.get()
+    }
+
     /// Handle the window scale factor event and return a boolean to tell embedder if it should further
     /// handle the scale factor event.
     pub fn on_scale_factor_event(&mut self, scale_factor: f32, window: &Window) -> bool {
@@ -1353,6 +1940,18 @@ impl IOCompositor {
             return;
         }
 
+        let mouse_window_event = match mouse_window_event {
+            MouseWindowEvent::Click(button, p) => {
+                MouseWindowEvent::Click(button, self.window_to_frame_point(p))
+            }
+            MouseWindowEvent::MouseDown(button, p) => {
+                MouseWindowEvent::MouseDown(button, self.window_to_frame_point(p))
+            }
+            MouseWindowEvent::MouseUp(button, p) => {
+                MouseWindowEvent::MouseUp(button, self.window_to_frame_point(p))
+            }
+        };
+
         if self.convert_mouse_to_touch {
             match mouse_window_event {
                 MouseWindowEvent::Click(_, _) => {}
@@ -1454,6 +2053,7 @@ impl IOCompositor {
         if self.shutdown_state != ShutdownState::NotShuttingDown {
             return;
         }
+        let cursor = self.window_to_frame_point(cursor);
 
         if self.convert_mouse_to_touch {
             self.on_touch_move(TouchId(0), cursor);
@@ -1518,6 +2118,7 @@ impl IOCompositor {
         if self.shutdown_state != ShutdownState::NotShuttingDown {
             return;
         }
+        let location = self.window_to_frame_point(location);
 
         match event_type {
             TouchEventType::Down => self.on_touch_down(identifier, location),
@@ -1590,6 +2191,7 @@ impl IOCompositor {
         if self.shutdown_state != ShutdownState::NotShuttingDown {
             return;
         }
+        let p = self.window_to_frame_point(p);
 
         self.send_wheel_event(delta, p);
     }
@@ -1604,6 +2206,7 @@ impl IOCompositor {
         if self.shutdown_state != ShutdownState::NotShuttingDown {
             return;
         }
+        let cursor = self.window_to_frame_point(cursor.to_f32()).to_i32();
 
         match phase {
             TouchEventType::Move => self.on_scroll_window_event(scroll_location, cursor),
@@ -1625,7 +2228,7 @@ impl IOCompositor {
             }));
     }
 
-    fn process_pending_scroll_events(&mut self, window: &Window) {
+    fn process_pending_scroll_events(&mut self, window: &mut Window) {
         // Batch up all scroll events into one, or else we'll do way too much painting.
         let mut combined_scroll_event: Option<ScrollEvent> = None;
         let mut combined_magnification = 1.0;
@@ -1689,6 +2292,7 @@ impl IOCompositor {
         let mut transaction = Transaction::new();
         if zoom_changed {
             self.send_root_pipeline_display_list_in_transaction(&mut transaction, window);
+            self.report_zoom(window);
         }
 
         if let Some((pipeline_id, external_id, offset)) = scroll_result {
@@ -1811,8 +2415,12 @@ impl IOCompositor {
         }
     }
 
-    fn device_pixels_per_page_pixel(&self) -> Scale<f32, CSSPixel, DevicePixel> {
-        self.device_pixels_per_page_pixel_not_including_page_zoom() * self.pinch_zoom_level()
+    pub(crate) fn device_pixels_per_page_pixel(&self) -> Scale<f32, CSSPixel, DevicePixel> {
+        css_to_device_scale(
+            self.scale_factor.get(),
+            self.page_zoom.get(),
+            self.pinch_zoom_level().get(),
+        )
     }
 
     fn device_pixels_per_page_pixel_not_including_page_zoom(
@@ -1821,28 +2429,123 @@ impl IOCompositor {
         self.page_zoom * self.scale_factor
     }
 
-    /// Handle zoom reset event
-    pub fn on_zoom_reset_window_event(&mut self, window: &Window) {
+    /// Handle zoom reset event. Both the page zoom and the pinch zoom are reset.
+    pub fn on_zoom_reset_window_event(&mut self, window: &mut Window) {
+        if self.shutdown_state != ShutdownState::NotShuttingDown {
+            return;
+        }
+
+        self.reset_pinch_zoom();
+        self.set_page_zoom(1.0, None, window);
+    }
+
+    /// Handle zoom to fit event. The page zoom is set so the content fits the web view in the
+    /// mode, and fitted again when the window is resized until the zoom is changed otherwise.
+    /// The pinch zoom is reset.
+    pub fn on_zoom_fit_window_event(&mut self, mode: FitMode, window: &mut Window) {
         if self.shutdown_state != ShutdownState::NotShuttingDown {
             return;
         }
 
-        self.page_zoom = Scale::new(1.0);
+        self.reset_pinch_zoom();
+        self.pending_refit = None;
+        self.fit_page_zoom(mode, window);
+    }
+
+    /// Handle double tap, or double-click with Ctrl, which toggles the page zoom between fitting
+    /// the width of the content and 1.
+    pub fn on_fit_width_toggle_window_event(&mut self, window: &mut Window) {
+        if self.fit_mode == Some(FitMode::Width) {
+            self.on_zoom_reset_window_event(window);
+        } else {
+            self.on_zoom_fit_window_event(FitMode::Width, window);
+        }
+    }
+
+    /// Set the page zoom so the content of the web view of the window fits it in the mode.
+    fn fit_page_zoom(&mut self, mode: FitMode, window: &mut Window) {
+        let Some(webview) = &window.webview else {
+            return;
+        };
+        let content = self.content_size(webview.webview_id).unwrap_or_default();
+        let viewport = webview.rect.size().to_f32()
+            / self.device_pixels_per_page_pixel_not_including_page_zoom();
+        let factor = crate::zoom::fit_factor(mode, content, viewport.cast_unit());
+        self.set_page_zoom(self.page_zoom.get() * factor, Some(mode), window);
+    }
+
+    /// Set the page zoom within its limits and the fit mode which set it, and report them if
+    /// they changed.
+    fn set_page_zoom(&mut self, zoom: f32, fit_mode: Option<FitMode>, window: &mut Window) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if zoom == self.page_zoom.get() && fit_mode == self.fit_mode {
+            return;
+        }
+        self.page_zoom = Scale::new(zoom);
+        self.fit_mode = fit_mode;
+        if fit_mode.is_none() {
+            self.pending_refit = None;
+        }
         self.update_after_zoom_or_hidpi_change(window);
+        self.report_zoom(window);
+    }
+
+    /// Report both zoom factors and the fit mode of the window to the `on_zoom_changed` hook.
+    fn report_zoom(&self, window: &mut Window) {
+        window.lifecycle_events.push(LifecycleEvent::ZoomChanged(
+            window.id(),
+            ZoomChange {
+                page_zoom: self.page_zoom(),
+                pinch_zoom: self.pinch_zoom(),
+                fit: self.fit_mode,
+            },
+        ));
+    }
+
+    /// Get the GL implementation and the process isolation of pages.
+    pub fn renderer_info(&self) -> RendererInfo {
+        let gl = &self.webrender_gl;
+        RendererInfo {
+            vendor: gl.get_string(gl::VENDOR),
+            renderer: gl.get_string(gl::RENDERER),
+            version: gl.get_string(gl::VERSION),
+            multiprocess: opts::get().multiprocess,
+            sandboxed: crate::sandbox::is_active(),
+        }
+    }
+
+    /// Get the page zoom factor, which changes the size of CSS pixels and lays out the page again.
+    pub fn page_zoom(&self) -> f32 {
+        self.page_zoom.get()
+    }
+
+    /// Get the fit mode which set the page zoom, or `None` if it was set by hand.
+    pub fn fit_mode(&self) -> Option<FitMode> {
+        self.fit_mode
+    }
+
+    /// Get the pinch zoom factor, which magnifies the rendered page without laying it out again.
+    pub fn pinch_zoom(&self) -> f32 {
+        self.pinch_zoom_level().get()
+    }
+
+    /// Reset the pinch zoom to 1. It applies when the pending scroll and zoom events are
+    /// processed in [`IOCompositor::perform_updates`].
+    pub fn reset_pinch_zoom(&mut self) {
+        if self.is_pinch_zoomed() {
+            let zoom = self.pinch_zoom_level().get();
+            self.pending_scroll_zoom_events
+                .push(ScrollZoomEvent::PinchZoom(1.0 / zoom));
+        }
     }
 
     /// Handle zoom event in the window
-    pub fn on_zoom_window_event(&mut self, magnification: f32, window: &Window) {
+    pub fn on_zoom_window_event(&mut self, magnification: f32, window: &mut Window) {
         if self.shutdown_state != ShutdownState::NotShuttingDown {
             return;
         }
 
-        self.page_zoom = Scale::new(
-            (self.page_zoom.get() * magnification)
-                .max(MIN_ZOOM)
-                .min(MAX_ZOOM),
-        );
-        self.update_after_zoom_or_hidpi_change(window);
+        self.set_page_zoom(self.page_zoom.get() * magnification, None, window);
     }
 
     fn update_after_zoom_or_hidpi_change(&mut self, window: &Window) {
@@ -1868,6 +2571,62 @@ impl IOCompositor {
             .push(ScrollZoomEvent::PinchZoom(magnification));
     }
 
+    /// Handle smart magnify gesture, like double tapping with two fingers on a touchpad. If the
+    /// view is pinch zoomed already, the zoom is reset and `None` is returned. Otherwise return
+    /// the cursor in CSS pixels of the web view at `webview_rect`, where the page looks up the
+    /// element to zoom into with [`IOCompositor::on_smart_magnify_target`].
+    pub fn on_smart_magnify_window_event(
+        &mut self,
+        cursor: DevicePoint,
+        webview_rect: DeviceIntRect,
+    ) -> Option<LayoutPoint> {
+        if self.shutdown_state != ShutdownState::NotShuttingDown {
+            return None;
+        }
+
+        if self.is_pinch_zoomed() {
+            self.reset_pinch_zoom();
+            return None;
+        }
+        let point = self.window_to_frame_point(cursor) - webview_rect.min.to_f32().to_vector();
+        Some(LayoutPoint::new(point.x, point.y) / self.device_pixels_per_page_pixel().get())
+    }
+
+    /// Zoom into the element found for smart magnify gesture, so its width fits the web view at
+    /// `webview_rect`. The left edge of the element is aligned near the left edge of the web
+    /// view, and the vertical position of the gesture stays in place.
+    pub fn on_smart_magnify_target(&mut self, target: MagnifyTarget, webview_rect: DeviceIntRect) {
+        if self.shutdown_state != ShutdownState::NotShuttingDown {
+            return;
+        }
+
+        // The view may have been zoomed while the page looked up the element.
+        if self.is_pinch_zoomed() {
+            return;
+        }
+        let scale = self.device_pixels_per_page_pixel().get();
+        let zoom = magnify_factor(
+            target.element.width() * scale,
+            webview_rect.width() as f32,
+            SMART_MAGNIFY_MARGIN,
+        )
+        .min(MAX_ZOOM);
+        if zoom - 1.0 <= PINCH_ZOOM_EPSILON {
+            return;
+        }
+        let origin = webview_rect.min.to_f32();
+        let left = origin.x + target.element.min.x * scale;
+        let y = origin.y + target.y * scale;
+        let delta =
+            LayoutVector2D::new(origin.x + SMART_MAGNIFY_MARGIN - left * zoom, y - y * zoom);
+        self.pending_scroll_zoom_events
+            .push(ScrollZoomEvent::PinchZoom(zoom));
+        self.on_scroll_window_event(
+            ScrollLocation::Delta(delta),
+            DevicePoint::new(left, y).to_i32(),
+        );
+    }
+
     fn send_scroll_positions_to_layout_for_pipeline(&self, pipeline_id: &PipelineId) {
         let details = match self.pipeline_details.get(pipeline_id) {
             Some(details) => details,
@@ -1979,11 +2738,24 @@ impl IOCompositor {
 
     /// Composite to the given target if any, or the current target otherwise.
     fn composite_specific_target(&mut self) -> Result<(), UnableToComposite> {
+        if self.surface_lost.is_some() {
+            return Err(UnableToComposite::SurfaceLost);
+        }
         if let Err(err) = self.rendering_context.make_gl_context_current() {
             warn!("Failed to make GL context current: {:?}", err);
         }
+        if self.webrender_gl.get_error() == GL_CONTEXT_LOST {
+            warn!("GL context is lost");
+            self.surface_lost = Some(SurfaceResetCause::ContextLost);
+            return Err(UnableToComposite::SurfaceLost);
+        }
         self.assert_no_gl_error();
 
+        // Presenting before the surface is resized would stretch the frame to the window.
+        if self.pending_resize.is_some() {
+            return Err(UnableToComposite::SurfaceResizePending);
+        }
+
         self.webrender.update();
 
         let wait_for_stable_image = self.exit_after_load;
@@ -2010,8 +2782,22 @@ impl IOCompositor {
             .unwrap_or(None)
             .map(|info| info.framebuffer_object)
             .unwrap_or(0);
+        let render_framebuffer = match self.render_resolution {
+            Some(size) => {
+                if self.render_target.as_ref().map(|t| t.size) != Some(size) {
+                    if let Some(target) = self.render_target.take() {
+                        target.delete(&*self.webrender_gl);
+                    }
+                    self.render_target = Some(RenderTarget::new(&*self.webrender_gl, size));
+                }
+                self.render_target
+                    .as_ref()
+                    .map_or(framebuffer_object, |t| t.framebuffer)
+            }
+            None => framebuffer_object,
+        };
         self.webrender_gl
-            .bind_framebuffer(gl::FRAMEBUFFER, framebuffer_object);
+            .bind_framebuffer(gl::FRAMEBUFFER, render_framebuffer);
         self.assert_gl_framebuffer_complete();
 
         profile(
@@ -2020,6 +2806,20 @@ impl IOCompositor {
             self.time_profiler_chan.clone(),
             || {
                 trace!("Compositing");
+                if let Some(overlay) = &self.debug_overlay {
+                    if let Some(debug_renderer) = self.webrender.debug_renderer() {
+                        let color = ColorU::new(255, 255, 0, 255);
+                        for (i, line) in overlay.lines().iter().enumerate() {
+                            debug_renderer.add_text(
+                                10.0,
+                                20.0 + 16.0 * i as f32,
+                                line,
+                                color,
+                                None,
+                            );
+                        }
+                    }
+                }
                 // Paint the scene.
                 // TODO(gw): Take notice of any errors the renderer returns!
                 self.webrender
@@ -2029,6 +2829,34 @@ impl IOCompositor {
             },
         );
 
+        // Scale the frame rendered at the fixed resolution to the window.
+        if render_framebuffer != framebuffer_object {
+            let (scale, offset) = self.render_fit();
+            let frame = self.viewport.to_f32() * scale;
+            let gl = &self.webrender_gl;
+            gl.bind_framebuffer(gl::READ_FRAMEBUFFER, render_framebuffer);
+            gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, framebuffer_object);
+            gl.clear_color(0.0, 0.0, 0.0, 1.0);
+            gl.clear(gl::COLOR_BUFFER_BIT);
+            gl.blit_framebuffer(
+                0,
+                0,
+                self.viewport.width,
+                self.viewport.height,
+                offset.x as i32,
+                offset.y as i32,
+                (offset.x + frame.width) as i32,
+                (offset.y + frame.height) as i32,
+                gl::COLOR_BUFFER_BIT,
+                gl::LINEAR,
+            );
+            gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer_object);
+        }
+
+        if self.video_recorder.is_some() || self.capture_pending {
+            self.read_frame(render_framebuffer);
+        }
+
         // If there are pending paint metrics, we check if any of the painted epochs is one of the
         // ones that the paint metrics recorder is expecting. In that case, we get the current
         // time, inform layout about it and remove the pending metric from the list.
@@ -2077,6 +2905,13 @@ impl IOCompositor {
 
         if let Err(err) = self.rendering_context.present() {
             warn!("Failed to present surface: {:?}", err);
+            self.surface_lost = Some(SurfaceResetCause::PresentFailed(format!("{err:?}")));
+        } else {
+            self.failed_resets = 0;
+        }
+        self.input_latency.on_present(Instant::now());
+        if let Some(overlay) = &mut self.debug_overlay {
+            overlay.on_composite(Instant::now());
         }
         self.composition_request = CompositionRequest::NoCompositingNecessary;
 
@@ -2112,7 +2947,7 @@ impl IOCompositor {
     /// Receive and handle compositor messages.
     pub fn receive_messages(&mut self, windows: &mut HashMap<WindowId, Window>) -> bool {
         // Check for new messages coming from the other threads in the system.
-        let mut compositor_messages = vec![];
+        let mut compositor_messages = std::mem::take(&mut self.compositor_messages);
         let mut found_recomposite_msg = false;
         while let Some(msg) = self.port.try_recv_compositor_msg() {
             match msg {
@@ -2120,6 +2955,7 @@ impl IOCompositor {
                     // Only take one of duplicate NewWebRendeFrameReady messages, but do subtract
                     // one frame from the pending frames.
                     self.pending_frames -= 1;
+                    self.input_latency.on_frame_ready();
                 }
                 CompositorMsg::NewWebRenderFrameReady(_) => {
                     found_recomposite_msg = true;
@@ -2128,12 +2964,19 @@ impl IOCompositor {
                 _ => compositor_messages.push(msg),
             }
         }
-        for msg in compositor_messages {
+        self.last_batch_size = compositor_messages.len();
+        let mut keep_going = true;
+        for msg in compositor_messages.drain(..) {
             if !self.handle_browser_message(msg, windows) {
-                return false;
+                keep_going = false;
+                break;
             }
         }
-        true
+        // Dropping the rest of the batch if compositor stops handling messages, and keep the
+        // buffer for the next batch.
+        compositor_messages.clear();
+        self.compositor_messages = compositor_messages;
+        keep_going
     }
 
     /// Perform composition and related actions.
@@ -2151,6 +2994,46 @@ impl IOCompositor {
             self.zoom_action = false;
         }
 
+        if let Some(size) = self
+            .pending_resize
+            .filter(|_| Instant::now() >= self.next_resize_retry)
+        {
+            self.on_resize_window_event(size);
+            if self.pending_resize.is_none() {
+                if let Some(window) = windows.get_mut(&self.current_window) {
+                    self.resize_webviews(size, window);
+                }
+            }
+        }
+
+        if let Some(epoch) = self.pending_refit {
+            if let Some(window) = windows.get_mut(&self.current_window) {
+                if let Some(mode) = self
+                    .fit_mode
+                    .filter(|_| self.display_list_epoch(window) != epoch)
+                {
+                    self.pending_refit = None;
+                    self.fit_page_zoom(mode, window);
+                }
+            }
+        }
+
+        if let Some(cause) = self.surface_lost.take() {
+            if let Some(window) = windows.get_mut(&self.current_window) {
+                self.reset_surface(cause, window);
+            }
+        }
+
+        if self
+            .debug_overlay
+            .as_ref()
+            .is_some_and(|overlay| overlay.next_refresh() <= Instant::now())
+        {
+            if let Some(window) = windows.get(&self.current_window) {
+                self.refresh_debug_overlay(window);
+            }
+        }
+
         match self.composition_request {
             CompositionRequest::NoCompositingNecessary => {}
             CompositionRequest::CompositeNow(_) => self.composite(),
@@ -2162,11 +3045,45 @@ impl IOCompositor {
         // The WebXR thread may make a different context current
         let _ = self.rendering_context.make_gl_context_current();
 
+        if let Some(window) = windows.get(&self.current_window) {
+            if let Some(mut autoscroll) = window.autoscroll.get() {
+                if let Some((x, y)) = autoscroll.tick(window.cursor(), Instant::now()) {
+                    self.on_scroll_event(
+                        ScrollLocation::Delta(LayoutVector2D::new(x, y)),
+                        autoscroll.origin.to_i32(),
+                        TouchEventType::Move,
+                    );
+                }
+                window.autoscroll.set(Some(autoscroll));
+            }
+        }
+
         if !self.pending_scroll_zoom_events.is_empty() {
-            if let Some(window) = windows.get(&self.current_window) {
+            if let Some(window) = windows.get_mut(&self.current_window) {
                 self.process_pending_scroll_events(window)
             }
         }
+
+        if let Some(window) = windows.get(&self.current_window) {
+            let geometry = self.scroll_geometry(window);
+            let mut scrollbars = window.scrollbars.get();
+            let changed = scrollbars.tick(geometry, self.scrollbar_style.mode, Instant::now());
+            window.scrollbars.set(scrollbars);
+            if changed {
+                self.send_root_pipeline_display_list(window);
+            }
+        }
+
+        if self.progress_bar_style.is_some() {
+            if let Some(window) = windows.get(&self.current_window) {
+                let mut progress_bar = window.progress_bar.get();
+                let changed = progress_bar.tick(window.load_status(), Instant::now());
+                window.progress_bar.set(progress_bar);
+                if changed {
+                    self.send_root_pipeline_display_list(window);
+                }
+            }
+        }
         self.shutdown_state != ShutdownState::FinishedShuttingDown
     }
     /// Repaints and recomposites synchronously. You must be careful when calling this, as if a
@@ -2188,6 +3105,120 @@ impl IOCompositor {
         }
     }
 
+    /// Recreate the rendering surface after the GL context or the surface was lost, and composite
+    /// the whole window again. The compositor keeps its state, like the zoom and scroll offsets,
+    /// and the GL context and WebRender renderer are kept as they are.
+    fn reset_surface(&mut self, cause: SurfaceResetCause, window: &mut Window) {
+        if self.failed_resets >= MAX_SURFACE_RESETS {
+            return;
+        }
+        self.failed_resets += 1;
+        if self.failed_resets == MAX_SURFACE_RESETS {
+            error!("Giving up recovering the surface after {MAX_SURFACE_RESETS} resets");
+        }
+        warn!(
+            "Resetting the surface of window {:?}: {cause:?}",
+            window.id()
+        );
+        self.invalidate_native_surface();
+        self.rebind_native_surface(window);
+        self.send_root_pipeline_display_list(window);
+        window
+            .lifecycle_events
+            .push(LifecycleEvent::SurfaceReset(cause));
+        window.request_redraw();
+    }
+
+    /// Drop WebRender's cached tiles, glyphs, images and render targets, and render the whole
+    /// window again on the next composite, to recover from stale or corrupted tiles.
+    pub(crate) fn invalidate(&mut self, window: &Window) {
+        debug!("Invalidating the rendering of window {:?}", window.id());
+        self.webrender_api
+            .send_debug_cmd(DebugCommand::ClearCaches(ClearCache::all()));
+        let mut transaction = Transaction::new();
+        self.send_root_pipeline_display_list_in_transaction(&mut transaction, window);
+        transaction.invalidate_rendered_frame(RenderReasons::SCENE);
+        self.generate_frame(&mut transaction, RenderReasons::SCENE);
+        self.webrender_api
+            .send_transaction(self.webrender_document, transaction);
+        window.request_redraw();
+    }
+
+    /// Simulate the loss of the GL context, so it's recovered from before the next composite.
+    #[cfg(debug_assertions)]
+    pub(crate) fn simulate_context_loss(&mut self) {
+        self.surface_lost = Some(SurfaceResetCause::Simulated);
+        self.composite_if_necessary(CompositingReason::Resize);
+    }
+
+    /// Set the style of the built-in loading progress bar, or disable it with `None`.
+    pub(crate) fn set_progress_bar_style(
+        &mut self,
+        style: Option<ProgressBarStyle>,
+        window: &Window,
+    ) {
+        self.progress_bar_style = style;
+        self.send_root_pipeline_display_list(window);
+    }
+
+    /// Set the style of the scrollbars drawn over the document.
+    pub(crate) fn set_scrollbar_style(&mut self, style: ScrollbarStyle, window: &Window) {
+        self.scrollbar_style = style;
+        self.send_root_pipeline_display_list(window);
+    }
+
+    /// Turn the debug overlay on or off.
+    pub(crate) fn set_debug_overlay(&mut self, on: bool) {
+        if on == self.debug_overlay.is_some() {
+            return;
+        }
+        self.debug_overlay = on.then(DebugOverlay::default);
+        self.composite_if_necessary(CompositingReason::DebugOverlay);
+    }
+
+    /// Time the failed resize of the rendering context is retried at, if there's one.
+    pub(crate) fn next_resize_retry(&self) -> Option<Instant> {
+        self.pending_resize.map(|_| self.next_resize_retry)
+    }
+
+    /// Time the debug overlay should be refreshed, if it's on.
+    pub(crate) fn next_debug_overlay_refresh(&self) -> Option<Instant> {
+        self.debug_overlay.as_ref().map(DebugOverlay::next_refresh)
+    }
+
+    /// Refresh the text of the debug overlay from the state of the window.
+    fn refresh_debug_overlay(&mut self, window: &Window) {
+        let now = Instant::now();
+        let latency = self.input_latency.stats();
+        let events = self.last_batch_size;
+        let scroll_events = self.pending_scroll_zoom_events.len();
+        let Some(overlay) = &mut self.debug_overlay else {
+            return;
+        };
+        let url = window
+            .url
+            .as_ref()
+            .map_or("(none)".to_string(), |url| url.to_string());
+        let lines = vec![
+            format!("FPS: {}", overlay.frame_rate(now)),
+            format!("URL: {url}"),
+            format!("Load status: {:?}", window.load_status()),
+            format!("Memory: {}", format_memory(resident_memory())),
+            format!("Events: {events} in last batch, {scroll_events} pending scrolls"),
+            format!(
+                "Input latency: p50 {:?}, p95 {:?} ({} samples)",
+                latency.p50, latency.p95, latency.samples
+            ),
+        ];
+        overlay.refresh(lines, now);
+        self.composite_if_necessary(CompositingReason::DebugOverlay);
+    }
+
+    /// Return true if the view is pinch zoomed.
+    pub(crate) fn is_pinch_zoomed(&self) -> bool {
+        (self.pinch_zoom_level().get() - 1.0).abs() > PINCH_ZOOM_EPSILON
+    }
+
     fn pinch_zoom_level(&self) -> Scale<f32, DevicePixel, DevicePixel> {
         Scale::new(self.viewport_zoom.get())
     }
@@ -2229,6 +3260,8 @@ impl IOCompositor {
 #[derive(Debug, PartialEq)]
 enum UnableToComposite {
     NotReadyToPaintImage(NotReadyToPaint),
+    SurfaceLost,
+    SurfaceResizePending,
 }
 
 #[derive(Debug, PartialEq)]
@@ -1,7 +1,36 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use embedder_traits::resources::{self, Resource, ResourceReaderMethods};
+use serde::Serialize;
 use servo_config::opts::{default_opts, set_options, Opts};
+use servo_config::set_pref;
+use servo_url::ServoUrl;
+use winit::{
+    dpi::PhysicalSize,
+    window::{Theme, WindowLevel},
+};
+
+use crate::caret::ContentInsets;
+use crate::content_scripts::ContentScript;
+use crate::contrast::ForcedColors;
+use crate::discard::DiscardPolicy;
+use crate::dispatch::DEFAULT_TITLE_UPDATE_INTERVAL;
+use crate::features::FeaturePolicy;
+use crate::policies::{ActivePolicies, Policies, PolicyLevel};
+use crate::prefs::PrefValue;
+use crate::print::PrintConfig;
+use crate::progress::ProgressBarStyle;
+use crate::sandbox::SandboxMode;
+use crate::scrollbars::ScrollbarStyle;
+use crate::shortcuts::ShortcutMap;
+use crate::timers::DEFAULT_BACKGROUND_TIMER_THROTTLE;
+use crate::url_display::UrlDisplayMode;
 
 /// Configuration of Verso instance.
 #[derive(Clone, Debug)]
@@ -10,6 +39,117 @@ pub struct Config {
     pub opts: Opts,
     /// Path to resources directory.
     pub resource_dir: PathBuf,
+    /// What to do when a page with a `beforeunload` handler is about to be unloaded.
+    pub before_unload: BeforeUnloadPolicy,
+    /// Path of the file to record input events and embedder messages to. Nothing is recorded if
+    /// it's `None`. See [`crate::recorder`] for the format.
+    pub record_events: Option<PathBuf>,
+    /// Keyboard shortcuts of browser actions. Defaults to the shortcuts of the platform.
+    /// [`Config::load_shortcuts`] adds the ones of `shortcuts.json` in the resources directory.
+    pub shortcuts: ShortcutMap,
+    /// Zoom into the element under the cursor on smart magnify gesture, like double tapping with
+    /// two fingers on macOS touchpads, and toggle fitting the width of the page on double tap or
+    /// double-click with Ctrl. Enabled by default.
+    pub smart_magnify: bool,
+    /// Options of the windows.
+    pub window: WindowConfig,
+    /// When pages can autoplay media with sound.
+    pub autoplay: AutoplayPolicy,
+    /// Copy selected text to the primary selection and paste it into editable elements on middle
+    /// click. This only takes effect on Linux. Enabled by default.
+    pub primary_selection: bool,
+    /// How mouse wheel and touchpad scrolling is locked to a single axis.
+    pub wheel_axis_lock: AxisLockMode,
+    /// Scroll by moving the mouse after middle clicking the web view, until the next click. It
+    /// takes over middle clicks from the primary selection. Enabled by default on Windows. See
+    /// [`crate::autoscroll`].
+    pub autoscroll: bool,
+    /// Scroll by whole device pixels, carrying fractions of slow touchpad scrolls over to the next
+    /// delta, so text doesn't blur. It's ignored while the view is pinch zoomed.
+    pub snap_scroll: bool,
+    /// Expose the Battery Status API to pages. Disabled by default for privacy. See
+    /// [`crate::sensors`].
+    pub battery_status: bool,
+    /// WebRTC options.
+    pub webrtc: WebRtcConfig,
+    /// Default fonts of pages.
+    pub fonts: FontConfig,
+    /// Multiply the default font sizes by the text scale of the operating system's accessibility
+    /// settings. Enabled by default. See [`crate::text_scale`].
+    pub follow_os_text_scale: bool,
+    /// Features denied to pages, keyed by serialized origin like `https://example.com`, or
+    /// [`crate::features::ALL_ORIGINS`] for every origin. See [`crate::features`].
+    pub feature_policies: BTreeMap<String, FeaturePolicy>,
+    /// Keep JavaScript dialogs and `beforeunload` confirmations of web views open until they're
+    /// answered with [`crate::Verso::respond_to_dialog`], for embedders with their own dialogs
+    /// and for automation. Verso has no dialog UI yet, so by default JavaScript dialogs are
+    /// dismissed right away and [`Config::before_unload`] answers confirmations.
+    pub hold_dialogs: bool,
+    /// Store submitted form values and offer them in form fields. Disabled by default. See
+    /// [`crate::autofill`].
+    pub autofill: bool,
+    /// Include the username and password in the credentials reported to
+    /// [`crate::VersoBuilder::on_credentials_submitted`]. Disabled by default. See
+    /// [`crate::passwords`].
+    pub share_credentials: bool,
+    /// Storage partition of all web views of the instance, or `None`, the default, for the
+    /// default partition. Partitions can't be chosen per web view. See [`crate::partitions`].
+    pub partition: Option<String>,
+    /// Addresses hosts resolve to instead of asking the system, like with a hosts file. Keys are
+    /// exact hosts in lowercase. See [`crate::dns`].
+    pub dns_overrides: BTreeMap<String, IpAddr>,
+    /// What loading the URL of the page already loaded does. See [`SameUrlPolicy`].
+    pub same_url: SameUrlPolicy,
+    /// What [`crate::Verso::current_url`] shows of the URL of the page. See
+    /// [`crate::url_display`].
+    pub url_display: UrlDisplayMode,
+    /// Which changes of the window state pages can request. See [`WindowControlPolicy`].
+    pub window_control: WindowControlPolicy,
+    /// Measure the latency from input events to presents. See [`crate::Verso::input_latency_stats`].
+    pub measure_input_latency: bool,
+    /// How loads failing with a transient network error are retried.
+    pub navigation_retry: RetryPolicy,
+    /// How web views are restored after their content crashes.
+    pub crash_recovery: CrashRecoveryPolicy,
+    /// Style of the loading progress bar drawn by Verso, or `None` to disable it, which is the
+    /// default. See [`crate::progress`].
+    pub progress_bar: Option<ProgressBarStyle>,
+    /// Style of the scrollbars drawn by Verso. Defaults to the style of the platform. See
+    /// [`crate::scrollbars`].
+    pub scrollbars: ScrollbarStyle,
+    /// Parts of web views covered by the embedder, which the caret is kept out of while typing.
+    /// See [`crate::caret`].
+    pub content_insets: ContentInsets,
+    /// Schemes of links which are ignored instead of being opened by another application, like
+    /// `"tel"`. See [`crate::external`].
+    pub blocked_schemes: BTreeSet<String>,
+    /// Shortest delay of timers in hidden windows, in seconds, or 0 to run them at full rate.
+    /// Defaults to [`DEFAULT_BACKGROUND_TIMER_THROTTLE`]. See [`crate::timers`].
+    pub background_timer_throttle: u32,
+    /// Shortest interval between updates of the title and the favicon of a window, or zero to
+    /// apply every update. Defaults to [`DEFAULT_TITLE_UPDATE_INTERVAL`]. See
+    /// [`crate::dispatch::UpdateThrottle`].
+    pub title_update_interval: Duration,
+    /// Scripts injected into pages matching their URL patterns. See [`crate::content_scripts`].
+    pub content_scripts: Vec<ContentScript>,
+    /// Forced colors mode of pages, following the high-contrast setting of the operating system
+    /// by default. See [`crate::contrast`].
+    pub forced_colors: ForcedColors,
+    /// When web views of background windows are discarded to save memory. See
+    /// [`crate::discard`].
+    pub discard: DiscardPolicy,
+    /// Printing options. See [`crate::print`].
+    pub print: PrintConfig,
+    /// Servo preferences overriding `prefs.json`. See [`crate::prefs`].
+    pub pref_overrides: Vec<(String, PrefValue)>,
+    /// Page loaded in new windows, or `None` for the homepage of the policies or the built-in
+    /// one. A mandatory policy takes precedence. See [`crate::policies`].
+    pub homepage: Option<ServoUrl>,
+    /// Policies of the administrator, read from the policy file by [`Config::new`]. See
+    /// [`crate::policies`].
+    pub policies: Policies,
+    /// Whether pages run in sandboxed content processes. See [`crate::sandbox`].
+    pub sandbox: SandboxMode,
 }
 
 impl Config {
@@ -17,20 +157,464 @@ impl Config {
     /// resources directory.
     pub fn new(resource_dir: PathBuf) -> Self {
         let opts = default_opts();
-        Self { opts, resource_dir }
+        Self {
+            opts,
+            resource_dir,
+            before_unload: BeforeUnloadPolicy::default(),
+            record_events: None,
+            shortcuts: ShortcutMap::default(),
+            smart_magnify: true,
+            window: WindowConfig::default(),
+            autoplay: AutoplayPolicy::default(),
+            primary_selection: true,
+            wheel_axis_lock: AxisLockMode::default(),
+            autoscroll: cfg!(target_os = "windows"),
+            snap_scroll: true,
+            battery_status: false,
+            webrtc: WebRtcConfig::default(),
+            hold_dialogs: false,
+            autofill: false,
+            share_credentials: false,
+            partition: None,
+            dns_overrides: BTreeMap::new(),
+            same_url: SameUrlPolicy::default(),
+            url_display: UrlDisplayMode::default(),
+            window_control: WindowControlPolicy::default(),
+            measure_input_latency: false,
+            navigation_retry: RetryPolicy::default(),
+            crash_recovery: CrashRecoveryPolicy::default(),
+            pref_overrides: Vec::new(),
+            homepage: None,
+            policies: Policies::load(),
+            sandbox: SandboxMode::default(),
+            blocked_schemes: BTreeSet::new(),
+            background_timer_throttle: DEFAULT_BACKGROUND_TIMER_THROTTLE,
+            title_update_interval: DEFAULT_TITLE_UPDATE_INTERVAL,
+            content_scripts: Vec::new(),
+            forced_colors: ForcedColors::default(),
+            discard: DiscardPolicy::default(),
+            print: PrintConfig::default(),
+            progress_bar: None,
+            scrollbars: ScrollbarStyle::default(),
+            content_insets: ContentInsets::default(),
+            fonts: FontConfig::default(),
+            follow_os_text_scale: true,
+            feature_policies: BTreeMap::new(),
+        }
+    }
+
+    /// Load `shortcuts.json` of the resources directory on top of the default shortcuts, if it
+    /// exists. See [`ShortcutMap::from_json`] for its format.
+    pub fn load_shortcuts(&mut self) {
+        self.shortcuts = ShortcutMap::load(&self.resource_dir.join("shortcuts.json"));
     }
 
-    /// Init options and preferences.
-    pub fn init(self) {
+    /// Init options and preferences. Fails if the directory of the storage partition can't be
+    /// created, rather than storing the state of the partition in the default one.
+    pub fn init(&self) -> crate::Result<()> {
         // Set the resource files and preferences of Servo.
-        resources::set(Box::new(ResourceReader(self.resource_dir)));
+        resources::set(Box::new(ResourceReader(self.resource_dir.clone())));
+
+        // Set the global options of Servo, with the state stored in the partition.
+        let mut opts = self.opts.clone();
+        if let Some(name) = &self.partition {
+            opts.config_dir = Some(crate::partitions::create(opts.config_dir.as_deref(), name)?);
+        }
+        if self.policies.disable_devtools {
+            opts.devtools_server_enabled = false;
+        }
+        crate::sandbox::apply(self.sandbox, &mut opts);
+        set_options(opts);
+        if !self.dns_overrides.is_empty() {
+            crate::dns::apply(&self.dns_overrides)?;
+        }
+
+        // Pages can't see `RTCPeerConnection` when WebRTC is disabled.
+        set_pref!(dom.webrtc.enabled, self.webrtc.enabled);
+
+        self.fonts.apply(self.text_scale());
+
+        // Users override default policies, and mandatory policies override users.
+        crate::prefs::set_overrides(&self.policies.prefs_at(PolicyLevel::Default));
+        crate::prefs::set_overrides(&self.pref_overrides);
+        crate::prefs::set_overrides(&self.policies.prefs_at(PolicyLevel::Mandatory));
+        Ok(())
+    }
+
+    /// Resolve the policies against the options of the user. See [`crate::policies`].
+    pub fn active_policies(&self) -> ActivePolicies {
+        self.policies
+            .active(&self.pref_overrides, self.homepage.as_ref())
+    }
 
-        // Set the global options of Servo.
-        set_options(self.opts);
+    /// Page loaded in new windows.
+    pub(crate) fn homepage_url(&self) -> ServoUrl {
+        self.active_policies().homepage.0
+    }
+
+    /// Text scale multiplying the default font sizes.
+    pub(crate) fn text_scale(&self) -> f64 {
+        if self.follow_os_text_scale {
+            crate::text_scale::os_text_scale()
+        } else {
+            1.0
+        }
     }
 }
 
-struct ResourceReader(PathBuf);
+/// Default fonts of pages. `None` keeps Servo's default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FontConfig {
+    /// Family used for the `serif` generic family, and for text without a family.
+    pub serif: Option<String>,
+    /// Family used for the `sans-serif` generic family.
+    pub sans_serif: Option<String>,
+    /// Family used for the `monospace` generic family.
+    pub monospace: Option<String>,
+    /// Default font size in CSS pixels, before the text scale is applied.
+    pub default_size: Option<u32>,
+    /// Default font size of the `monospace` generic family in CSS pixels, before the text scale
+    /// is applied.
+    pub default_monospace_size: Option<u32>,
+}
+
+/// Servo's default font size.
+const DEFAULT_FONT_SIZE: u32 = 16;
+/// Servo's default font size of the `monospace` generic family.
+const DEFAULT_MONOSPACE_FONT_SIZE: u32 = 13;
+
+impl FontConfig {
+    /// Set the font preferences of Servo, multiplying the font sizes by the text scale. They're
+    /// read when styles are computed, so they apply to pages loaded afterwards.
+    pub(crate) fn apply(&self, text_scale: f64) {
+        if let Some(family) = &self.serif {
+            set_pref!(fonts.serif, family.clone());
+        }
+        if let Some(family) = &self.sans_serif {
+            set_pref!(fonts.sans_serif, family.clone());
+        }
+        if let Some(family) = &self.monospace {
+            set_pref!(fonts.monospace, family.clone());
+        }
+        let scale = |size: u32| (size as f64 * text_scale).round() as i64;
+        set_pref!(
+            fonts.default_size,
+            scale(self.default_size.unwrap_or(DEFAULT_FONT_SIZE))
+        );
+        set_pref!(
+            fonts.default_monospace_size,
+            scale(
+                self.default_monospace_size
+                    .unwrap_or(DEFAULT_MONOSPACE_FONT_SIZE)
+            )
+        );
+    }
+}
+
+/// List the family names of fonts installed on the system, sorted and without duplicates.
+pub fn list_system_fonts() -> Vec<String> {
+    let mut families = match font_kit::source::SystemSource::new().all_families() {
+        Ok(families) => families,
+        Err(e) => {
+            log::warn!("Verso failed to list system fonts: {e}");
+            return Vec::new();
+        }
+    };
+    families.sort_unstable();
+    families.dedup();
+    families
+}
+
+/// Read a URL given on the command line, like for [`Config::homepage`]. URLs with a scheme, like
+/// `https://example.com` or `file:///tmp/page.html`, are kept, and anything else is a path of an
+/// existing file, relative to `current_dir`.
+pub fn parse_url_argument(argument: &str, current_dir: &Path) -> crate::Result<ServoUrl> {
+    match ServoUrl::parse(argument) {
+        // A single letter is a Windows drive rather than a scheme.
+        Ok(url) if url.scheme().len() > 1 => return Ok(url),
+        Ok(_) | Err(url::ParseError::RelativeUrlWithoutBase) => {}
+        Err(e) => return Err(crate::Error::InvalidUrl(argument.to_string(), e)),
+    }
+    let path = current_dir.join(argument);
+    let path = fs::canonicalize(&path).map_err(|e| crate::Error::FileError(path, e))?;
+    ServoUrl::from_file_path(&path).map_err(|()| crate::Error::InvalidPath(path))
+}
+
+/// Flags of the `verso` executable which take the next argument as their value.
+const VALUE_FLAGS: [&str; 1] = [crate::sandbox::CONTENT_PROCESS_ARG];
+
+/// Find the page to open in the command line arguments, without the executable, for
+/// [`parse_url_argument`]. It's the last argument which is neither a flag nor the value of one.
+/// Only the flags of the executable which take a value, like `--content-process`, take the next
+/// argument as their value. Other flags, like `--crash-test`, take none. Arguments after `--`
+/// are never flags.
+pub fn find_url_argument(args: impl IntoIterator<Item = String>) -> Option<String> {
+    let mut args = args.into_iter();
+    let mut url = None;
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            return args.last().or(url);
+        }
+        if arg.len() > 1 && arg.starts_with('-') {
+            if VALUE_FLAGS.contains(&arg.as_str()) {
+                args.next();
+            }
+        } else {
+            url = Some(arg);
+        }
+    }
+    url
+}
+
+/// Options of the Verso windows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowConfig {
+    /// Theme of the window decorations, like the title bar. `None` follows the system theme.
+    pub theme: Option<Theme>,
+    /// Declare the process as per-monitor DPI aware (v2). This only takes effect on Windows,
+    /// and must be decided before the event loop is created. Enabled by default.
+    pub per_monitor_dpi_aware: bool,
+    /// The monitor to place windows on. `None` lets the platform decide.
+    pub monitor: Option<MonitorSelector>,
+    /// Open windows in borderless fullscreen, like in kiosk deployments.
+    pub fullscreen: bool,
+    /// Window level, like always on top of other windows.
+    pub level: WindowLevel,
+    /// Hide windows from the taskbar and the window switcher. This takes effect on Windows and
+    /// X11. On X11 the windows are marked as utility windows.
+    pub skip_taskbar: bool,
+    /// Open windows hidden with this inner size in physical pixels, like to replay recorded
+    /// events without showing a window. Pages are still painted to the surface of the window, so
+    /// a display connection is needed, like Xvfb on Linux.
+    pub headless: Option<PhysicalSize<u32>>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            theme: None,
+            per_monitor_dpi_aware: true,
+            monitor: None,
+            fullscreen: false,
+            level: WindowLevel::Normal,
+            skip_taskbar: false,
+            headless: None,
+        }
+    }
+}
+
+impl WindowConfig {
+    /// Place windows on the selected monitor.
+    pub fn monitor(mut self, selector: MonitorSelector) -> Self {
+        self.monitor = Some(selector);
+        self
+    }
+
+    /// Open windows in borderless fullscreen.
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    /// Keep windows on top of other windows.
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.level = if always_on_top {
+            WindowLevel::AlwaysOnTop
+        } else {
+            WindowLevel::Normal
+        };
+        self
+    }
+
+    /// Hide windows from the taskbar and the window switcher.
+    pub fn skip_taskbar(mut self, skip_taskbar: bool) -> Self {
+        self.skip_taskbar = skip_taskbar;
+        self
+    }
+
+    /// Open windows hidden with the inner size in physical pixels.
+    pub fn headless(mut self, size: PhysicalSize<u32>) -> Self {
+        self.headless = Some(size);
+        self
+    }
+}
+
+/// Which monitor to place a window on. If no monitor matches, the primary monitor is used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MonitorSelector {
+    /// The primary monitor.
+    Primary,
+    /// The monitor at the index of the available monitors.
+    Index(usize),
+    /// The first monitor whose name contains the string.
+    NameContains(String),
+}
+
+/// The answer Verso gives when a page asks to confirm leaving it with a `beforeunload` handler.
+///
+/// When [`Config::hold_dialogs`] is enabled, the embedder answers the confirmation instead. Verso
+/// doesn't have a dialog to ask the user yet, so otherwise this decides whether navigating away
+/// from or closing such page can proceed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BeforeUnloadPolicy {
+    /// Let the page unload. This is the default.
+    #[default]
+    Allow,
+    /// Keep the page and cancel the navigation or closing.
+    Block,
+}
+
+/// What [`crate::Verso::load_url`] does with the URL of the page already loaded, like when an
+/// address bar submits the same URL again by accident. URLs are compared after parsing, so
+/// `https://Example.com` is the same as `https://example.com/`. An empty fragment is the same as
+/// none, and URLs with different fragments aren't the same, so they still scroll to the fragment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SameUrlPolicy {
+    /// Reload the page, like browsers do. This is the default.
+    #[default]
+    Reload,
+    /// Keep the page as it is.
+    Ignore,
+}
+
+/// When media with sound can autoplay. Muted media can always autoplay.
+///
+/// Servo doesn't have an autoplay policy yet, so Verso stops audible media of the page once it
+/// has loaded if autoplay isn't allowed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AutoplayPolicy {
+    /// Media can always autoplay.
+    Allow,
+    /// Media can autoplay after the user has interacted with the window since the page started
+    /// loading. This is the default.
+    #[default]
+    AllowAfterUserGesture,
+    /// Media with sound never autoplays.
+    Block,
+}
+
+/// How loads failing with a transient network error, like a DNS lookup failing temporarily or a
+/// connection reset, are retried. The page is reloaded after `initial_delay`, and the delay doubles
+/// for each following attempt. Definitive errors, like certificate errors or HTTP error statuses,
+/// are never retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries of a load. Zero, the default, disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry.
+    pub initial_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the retry, counted from 1.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+/// How a web view is restored after its content crashes. Servo shows its crash page in the web
+/// view, which Verso replaces by loading the page again, scrolled back to where it was.
+///
+/// The scroll position and form fields are captured every few seconds while the page is loaded,
+/// so changes made just before the crash may be lost. Password and file fields are never
+/// captured.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CrashRecoveryPolicy {
+    /// Number of times a page is restored before Verso leaves the crash page, to avoid crash
+    /// loops. The count starts over when the web view navigates. Zero, the default, disables
+    /// restoring.
+    pub max_restores: u32,
+    /// Also restore the values of form fields.
+    pub restore_form_data: bool,
+}
+
+/// Which changes of the window state pages can request, like with `window.moveTo()`,
+/// `window.resizeTo()` or `element.requestFullscreen()`. Everything is denied by default.
+///
+/// Pages moving or resizing the window can hide it off screen, cover other applications, or
+/// imitate dialogs of the operating system, and fullscreen hides the window decorations which
+/// tell users which application they're using. Embedders which trust their content can allow
+/// each change. Kiosks opened with [`WindowConfig::fullscreen`] deny all of them regardless of
+/// the policy, so pages can't leave the kiosk layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WindowControlPolicy {
+    /// Let pages move the window.
+    pub allow_move: bool,
+    /// Let pages resize the window.
+    pub allow_resize: bool,
+    /// Let pages enter and leave fullscreen.
+    pub allow_fullscreen: bool,
+}
+
+/// WebRTC options.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WebRtcConfig {
+    /// Expose WebRTC to pages. Disabled by default, like in Servo.
+    pub enabled: bool,
+    /// ICE servers used by peer connections which don't configure their own.
+    pub ice_servers: Vec<IceServer>,
+    /// Ask peer connections for relay candidates only, and hide other candidates from their
+    /// `icecandidate` listeners. This needs a TURN server in `ice_servers` or the page's
+    /// configuration.
+    ///
+    /// This is best effort and doesn't keep the local or public IP addresses from peers. It wraps
+    /// `RTCPeerConnection` from a script once the head is parsed, so scripts in the head keep the
+    /// original constructor, and candidates still reach the local and remote descriptions. Servo
+    /// doesn't let embedders restrict ICE gathering itself.
+    pub hide_non_relay_candidates: bool,
+}
+
+/// A STUN or TURN server.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct IceServer {
+    /// URLs of the server, like `turn:turn.example.com:3478`.
+    pub urls: Vec<String>,
+    /// User name for TURN servers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Credential for TURN servers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
+}
+
+/// How scroll deltas with both axes are handled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AxisLockMode {
+    /// Only scroll along the dominant axis. This is the default.
+    #[default]
+    Always,
+    /// Scroll along both axes, like panning maps or large canvases with a touchpad.
+    Never,
+    /// Scroll along both axes when the gesture is roughly diagonal, and along the dominant axis
+    /// otherwise. This keeps mostly vertical scrolling from drifting sideways.
+    SmartDiagonal,
+}
+
+impl AxisLockMode {
+    /// Return true if the scroll delta should be locked to its dominant axis.
+    pub fn locks(self, x: f64, y: f64) -> bool {
+        match self {
+            AxisLockMode::Always => true,
+            AxisLockMode::Never => false,
+            // Diagonal means the minor axis moves at least half as far as the major one, which
+            // is about 27 degrees away from either axis.
+            AxisLockMode::SmartDiagonal => x.abs().min(y.abs()) < x.abs().max(y.abs()) * 0.5,
+        }
+    }
+}
+
+/// Reader of Servo's resource files from the resources directory.
+pub(crate) struct ResourceReader(pub PathBuf);
 
 impl ResourceReaderMethods for ResourceReader {
     fn read(&self, file: Resource) -> Vec<u8> {
@@ -43,6 +627,7 @@ impl ResourceReaderMethods for ResourceReader {
     }
 
     fn sandbox_access_files_dirs(&self) -> Vec<PathBuf> {
-        vec![]
+        // Sandboxed content processes read the resources too.
+        vec![self.0.clone()]
     }
 }
@@ -0,0 +1,317 @@
+//! Scripts injected into pages matching URL patterns, added with
+//! [`crate::Verso::add_content_script`], as a base for extensions.
+//!
+//! Servo doesn't report when a document is created, so the earliest a script can run is when the
+//! head of the document is parsed:
+//!
+//! - [`RunAt::DocumentStart`] scripts run once the head is parsed.
+//! - [`RunAt::DocumentEnd`] scripts run on `DOMContentLoaded`.
+//! - [`RunAt::DocumentIdle`] scripts run once the page finished loading.
+//!
+//! Servo also reports the loads of top-level pages only, so scripts with
+//! [`ContentScript::all_frames`] are injected into child frames once the page finished loading,
+//! whatever their timing. Frames of other origins can't be reached from the page and are skipped.
+//!
+//! URLs are matched with the patterns of browser extensions, like `https://*.example.com/*` or
+//! `<all_urls>`. The pattern `*` as scheme matches `http` and `https`, a host starting with `*.`
+//! matches the domain and its subdomains, and `*` in the path matches any characters. Ports are
+//! ignored, and the path is matched with the query.
+
+use std::str::FromStr;
+
+use url::{Position, Url};
+
+/// Schemes allowed in match patterns, and matched by `<all_urls>`.
+const SCHEMES: [&str; 3] = ["http", "https", "file"];
+
+/// When a content script runs in the page.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RunAt {
+    /// Once the head of the document is parsed.
+    DocumentStart,
+    /// When the document is parsed, on `DOMContentLoaded`.
+    DocumentEnd,
+    /// Once the page finished loading.
+    #[default]
+    DocumentIdle,
+}
+
+/// Error of parsing a [`MatchPattern`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum MatchPatternError {
+    /// The pattern doesn't contain `://`.
+    #[error("match pattern `{0}` doesn't have a scheme")]
+    MissingScheme(String),
+    /// The scheme isn't `*`, `http`, `https` or `file`.
+    #[error("match pattern `{0}` has an unsupported scheme")]
+    UnsupportedScheme(String),
+    /// The host contains `*` other than as `*` or a leading `*.`, or is empty for a scheme other
+    /// than `file`.
+    #[error("match pattern `{0}` has an invalid host")]
+    InvalidHost(String),
+    /// The pattern doesn't have a path after the host.
+    #[error("match pattern `{0}` doesn't have a path")]
+    MissingPath(String),
+}
+
+/// Pattern of the URLs a content script is injected into.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MatchPattern {
+    /// Any URL with the `http`, `https` or `file` scheme, written `<all_urls>`.
+    AllUrls,
+    /// URLs matching the scheme, host and path.
+    Url {
+        /// Scheme, or `None` for `*` which matches `http` and `https`.
+        scheme: Option<String>,
+        /// Host, or `*` for any host. A leading `*.` also matches subdomains.
+        host: String,
+        /// Path with `*` wildcards.
+        path: String,
+    },
+}
+
+impl FromStr for MatchPattern {
+    type Err = MatchPatternError;
+
+    /// Parse patterns like `https://*.example.com/*`, `*://localhost/app/*` or `<all_urls>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "<all_urls>" {
+            return Ok(MatchPattern::AllUrls);
+        }
+        let (scheme, rest) = s
+            .split_once("://")
+            .ok_or_else(|| MatchPatternError::MissingScheme(s.into()))?;
+        let scheme = match scheme {
+            "*" => None,
+            scheme if SCHEMES.contains(&scheme) => Some(scheme.to_string()),
+            _ => return Err(MatchPatternError::UnsupportedScheme(s.into())),
+        };
+        let (host, path) = rest
+            .find('/')
+            .map(|i| rest.split_at(i))
+            .ok_or_else(|| MatchPatternError::MissingPath(s.into()))?;
+        let wildcard = host.strip_prefix("*.").unwrap_or(host);
+        let valid_host = match host {
+            "*" => true,
+            "" => scheme.as_deref() == Some("file"),
+            _ => !wildcard.is_empty() && !wildcard.contains('*'),
+        };
+        if !valid_host {
+            return Err(MatchPatternError::InvalidHost(s.into()));
+        }
+        Ok(MatchPattern::Url {
+            scheme,
+            host: host.to_ascii_lowercase(),
+            path: path.to_string(),
+        })
+    }
+}
+
+impl MatchPattern {
+    /// JavaScript regular expression matching the same URLs as the pattern.
+    fn to_regex(&self) -> String {
+        let MatchPattern::Url { scheme, host, path } = self else {
+            return format!("/^({}):/", SCHEMES.join("|"));
+        };
+        let scheme = scheme.as_deref().map_or("https?".into(), escape_regex);
+        let host = match host.as_str() {
+            "*" => "[^/]*".to_string(),
+            host => match host.strip_prefix("*.") {
+                Some(domain) => format!("([^/]*\\.)?{}", escape_regex(domain)),
+                None => escape_regex(host),
+            },
+        };
+        let path: Vec<String> = path.split('*').map(escape_regex).collect();
+        format!(
+            "/^{scheme}:\\/\\/{host}(:\\d+)?{}(#.*)?$/i",
+            path.join(".*")
+        )
+    }
+
+    /// Return true if the URL matches the pattern, like the regular expression of
+    /// [`MatchPattern::to_regex`].
+    pub(crate) fn matches(&self, url: &Url) -> bool {
+        let MatchPattern::Url { scheme, host, path } = self else {
+            return SCHEMES.contains(&url.scheme());
+        };
+        let scheme_matches = match scheme {
+            Some(scheme) => url.scheme() == scheme,
+            None => matches!(url.scheme(), "http" | "https"),
+        };
+        let url_host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let host_matches = match host.as_str() {
+            "*" => true,
+            host => match host.strip_prefix("*.") {
+                Some(domain) => url_host
+                    .strip_suffix(domain)
+                    .is_some_and(|sub| sub.is_empty() || sub.ends_with('.')),
+                None => url_host == host,
+            },
+        };
+        scheme_matches
+            && host_matches
+            && glob_matches(path, &url[Position::BeforePath..Position::AfterQuery])
+    }
+}
+
+/// Parse the pattern, adding the `/*` path to patterns without a path, like
+/// `https://example.com`, so they match the whole origin.
+pub(crate) fn parse_pattern(pattern: &str) -> Result<(String, MatchPattern), MatchPatternError> {
+    let has_path = pattern
+        .split_once("://")
+        .map_or(true, |(_, rest)| rest.contains('/'));
+    let pattern = if has_path || pattern == "<all_urls>" {
+        pattern.to_string()
+    } else {
+        format!("{pattern}/*")
+    };
+    let parsed = pattern.parse()?;
+    Ok((pattern, parsed))
+}
+
+/// Return true if the text matches the pattern, where `*` matches any characters.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+fn escape_regex(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\^$.|?*+()[]{}/".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Script injected into pages whose URL matches one of its patterns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContentScript {
+    /// JavaScript source of the script.
+    pub source: String,
+    /// Patterns of the URLs the script is injected into.
+    pub matches: Vec<MatchPattern>,
+    /// When the script runs.
+    pub run_at: RunAt,
+    /// Also inject the script into child frames of the same origin as the page.
+    pub all_frames: bool,
+}
+
+impl ContentScript {
+    /// Create a script injected into the top-level pages matching the patterns once they
+    /// finished loading.
+    pub fn new(source: impl Into<String>, matches: &[&str]) -> Result<Self, MatchPatternError> {
+        Ok(Self {
+            source: source.into(),
+            matches: matches
+                .iter()
+                .map(|pattern| pattern.parse())
+                .collect::<Result<_, _>>()?,
+            run_at: RunAt::default(),
+            all_frames: false,
+        })
+    }
+
+    /// Set when the script runs.
+    pub fn run_at(mut self, run_at: RunAt) -> Self {
+        self.run_at = run_at;
+        self
+    }
+
+    /// Set whether the script is also injected into child frames.
+    pub fn all_frames(mut self, all_frames: bool) -> Self {
+        self.all_frames = all_frames;
+        self
+    }
+
+    fn patterns(&self) -> String {
+        let patterns: Vec<String> = self.matches.iter().map(MatchPattern::to_regex).collect();
+        format!("[{}]", patterns.join(", "))
+    }
+}
+
+/// Script injecting the content scripts of the timing into the page, if its URL matches.
+pub(crate) fn page_script(scripts: &[ContentScript], run_at: RunAt) -> Option<String> {
+    let injections: Vec<String> = scripts
+        .iter()
+        .filter(|script| script.run_at == run_at)
+        .map(|script| {
+            let run = format!("(() => {{\n{}\n}})();", script.source);
+            let run = match run_at {
+                RunAt::DocumentEnd => format!(
+                    "const run = () => {{ {run} }};
+                    if (document.readyState === 'loading') {{
+                        document.addEventListener('DOMContentLoaded', run, {{ once: true }});
+                    }} else {{
+                        run();
+                    }}"
+                ),
+                RunAt::DocumentStart | RunAt::DocumentIdle => run,
+            };
+            format!(
+                "if ({}.some((pattern) => pattern.test(location.href))) {{ {run} }}",
+                script.patterns()
+            )
+        })
+        .collect();
+    (!injections.is_empty()).then(|| format!("{{ {} }}", injections.join(" } { ")))
+}
+
+/// Script injecting the content scripts with [`ContentScript::all_frames`] into the child frames
+/// of the page whose URLs match. Frames which already ran a script are skipped.
+pub(crate) fn frames_script(scripts: &[ContentScript]) -> Option<String> {
+    let injections: Vec<String> = scripts
+        .iter()
+        .enumerate()
+        .filter(|(_, script)| script.all_frames)
+        .filter_map(|(i, script)| {
+            let source = serde_json::to_string(&script.source).ok()?;
+            Some(format!("[{i}, {}, {source}]", script.patterns()))
+        })
+        .collect();
+    if injections.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "const scripts = [{}];
+        const inject = (parent) => {{
+            for (let i = 0; i < parent.frames.length; i++) {{
+                const frame = parent.frames[i];
+                try {{
+                    frame.versoContentScripts ??= new Set();
+                    for (const [id, patterns, source] of scripts) {{
+                        if (frame.versoContentScripts.has(id)) continue;
+                        if (!patterns.some((pattern) => pattern.test(frame.location.href))) continue;
+                        frame.versoContentScripts.add(id);
+                        try {{
+                            new frame.Function(source)();
+                        }} catch (e) {{
+                            console.error(e);
+                        }}
+                    }}
+                    inject(frame);
+                }} catch (e) {{
+                    // Frames of other origins can't be reached.
+                }}
+            }}
+        }};
+        inject(window);",
+        injections.join(", ")
+    ))
+}
@@ -0,0 +1,198 @@
+//! High-contrast settings of the operating system, exposed to pages as forced colors.
+//!
+//! Servo doesn't evaluate the `forced-colors` and `prefers-contrast` media features, so while
+//! forced colors are active, Verso injects a style sheet replacing the colors of pages with a
+//! high-contrast palette, and answers `matchMedia()` queries of those features. Style sheets of
+//! the page using the media features in `@media` rules aren't affected.
+//!
+//! The setting is followed by default, and overridden with [`crate::Verso::set_forced_colors`].
+//! On Linux it's read from the settings portal, with the `high-contrast` feature, and changes are
+//! applied to open pages. On Windows it's read when Verso starts and when the system theme
+//! changes.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use winit::event_loop::EventLoopProxy;
+
+/// Whether the operating system uses high contrast.
+static HIGH_CONTRAST: AtomicBool = AtomicBool::new(false);
+/// Set when the high-contrast setting changed since it was applied.
+static CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Forced colors mode of pages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ForcedColors {
+    /// Follow the high-contrast setting of the operating system.
+    #[default]
+    Auto,
+    /// Always force the high-contrast palette.
+    Active,
+    /// Never force colors.
+    None,
+}
+
+impl ForcedColors {
+    /// Return true if colors are forced in this mode.
+    pub fn is_active(self) -> bool {
+        match self {
+            ForcedColors::Auto => os_high_contrast(),
+            ForcedColors::Active => true,
+            ForcedColors::None => false,
+        }
+    }
+}
+
+/// Return true if the operating system uses high contrast.
+pub fn os_high_contrast() -> bool {
+    HIGH_CONTRAST.load(Ordering::Relaxed)
+}
+
+fn set_high_contrast(enabled: bool) {
+    if HIGH_CONTRAST.swap(enabled, Ordering::Relaxed) != enabled {
+        log::debug!("Verso high contrast of the operating system is {enabled}");
+        CHANGED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Return true once after the high-contrast setting changed.
+pub(crate) fn take_change() -> bool {
+    CHANGED.swap(false, Ordering::Relaxed)
+}
+
+/// Read the high-contrast setting again, on platforms which don't notify its changes.
+pub(crate) fn refresh() {
+    #[cfg(target_os = "windows")]
+    set_high_contrast(windows_high_contrast());
+}
+
+/// Read the high-contrast setting, and watch it for changes where it's supported, waking up the
+/// event loop when it changes.
+pub(crate) fn watch(proxy: EventLoopProxy<()>) {
+    #[cfg(all(linux, feature = "high-contrast"))]
+    std::thread::spawn(move || {
+        if let Err(e) = portal::watch(proxy) {
+            log::warn!("Verso failed to read the contrast from the settings portal: {e}");
+        }
+    });
+    #[cfg(not(all(linux, feature = "high-contrast")))]
+    {
+        drop(proxy);
+        refresh();
+    }
+}
+
+#[cfg(all(linux, feature = "high-contrast"))]
+mod portal {
+    use winit::event_loop::EventLoopProxy;
+    use zbus::zvariant::{OwnedValue, Value};
+
+    const NAMESPACE: &str = "org.freedesktop.appearance";
+    const KEY: &str = "contrast";
+
+    pub fn watch(proxy: EventLoopProxy<()>) -> zbus::Result<()> {
+        let connection = zbus::blocking::Connection::session()?;
+        let settings = zbus::blocking::Proxy::new(
+            &connection,
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Settings",
+        )?;
+        let changes = settings.receive_signal("SettingChanged")?;
+        let value: OwnedValue = settings.call("Read", &(NAMESPACE, KEY))?;
+        if let Some(high) = is_high(&value) {
+            super::set_high_contrast(high);
+            let _ = proxy.send_event(());
+        }
+        for message in changes {
+            let Ok((namespace, key, value)) =
+                message.body().deserialize::<(String, String, OwnedValue)>()
+            else {
+                continue;
+            };
+            if namespace == NAMESPACE && key == KEY {
+                if let Some(high) = is_high(&value) {
+                    super::set_high_contrast(high);
+                    let _ = proxy.send_event(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_high(value: &Value) -> Option<bool> {
+        match value {
+            // `Read` wraps the value in another variant.
+            Value::Value(inner) => is_high(inner),
+            // 0 is no preference and 1 is high contrast.
+            Value::U32(contrast) => Some(*contrast == 1),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn windows_high_contrast() -> bool {
+    use windows_sys::Win32::UI::{
+        Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW},
+        WindowsAndMessaging::{SystemParametersInfoW, SPI_GETHIGHCONTRAST},
+    };
+
+    let mut high_contrast = HIGHCONTRASTW {
+        cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+        dwFlags: 0,
+        lpszDefaultScheme: std::ptr::null_mut(),
+    };
+    // SAFETY: The structure is initialized with its size, as SPI_GETHIGHCONTRAST requires.
+    let ok = unsafe {
+        SystemParametersInfoW(
+            SPI_GETHIGHCONTRAST,
+            high_contrast.cbSize,
+            &mut high_contrast as *mut HIGHCONTRASTW as *mut _,
+            0,
+        )
+    };
+    ok != 0 && high_contrast.dwFlags & HCF_HIGHCONTRASTON != 0
+}
+
+/// Script applying or removing the forced colors in the page. It can run again when the mode
+/// changes.
+pub(crate) fn forced_colors_script(active: bool) -> String {
+    format!(
+        "const active = {active};
+        let state = window.versoForcedColors;
+        if (!state) {{
+            if (!active) return;
+            state = window.versoForcedColors = {{ active }};
+            const nativeMatchMedia = window.matchMedia.bind(window);
+            const always = '(min-width: 0px)';
+            const never = '(min-width: 99999999px)';
+            window.matchMedia = function matchMedia(query) {{
+                const on = state.active ? always : never;
+                const off = state.active ? never : always;
+                query = String(query)
+                    .replace(/\\(\\s*forced-colors\\s*(:\\s*active\\s*)?\\)/gi, on)
+                    .replace(/\\(\\s*forced-colors\\s*:\\s*none\\s*\\)/gi, off)
+                    .replace(/\\(\\s*prefers-contrast\\s*(:\\s*more\\s*)?\\)/gi, on)
+                    .replace(/\\(\\s*prefers-contrast\\s*:\\s*no-preference\\s*\\)/gi, off);
+                return nativeMatchMedia(query);
+            }};
+            state.style = document.createElement('style');
+            state.style.textContent = `
+                * {{
+                    color: #fff !important;
+                    background-color: #000 !important;
+                    border-color: #fff !important;
+                    text-shadow: none !important;
+                    box-shadow: none !important;
+                }}
+                a, a * {{ color: #ff0 !important; }}
+                ::selection {{ color: #000 !important; background-color: #0ff !important; }}`;
+        }}
+        state.active = active;
+        if (active) {{
+            document.documentElement.appendChild(state.style);
+        }} else {{
+            state.style.remove();
+        }}"
+    )
+}
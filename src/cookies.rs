@@ -0,0 +1,168 @@
+//! Inspecting and removing individual cookies of Servo's cookie store, like for a settings page.
+//!
+//! Servo looks cookies up by URL and can't enumerate its store, so Verso remembers the URLs of
+//! the pages loaded in this session and lists the cookies sent to them. Cookies of sites which
+//! weren't visited in this session, and cookies only sent to subresources of other sites, aren't
+//! listed.
+//!
+//! Domains are compared without their leading dot and case, so `.example.com` and `example.com`
+//! refer to the same cookies.
+
+use std::{collections::BTreeSet, time::SystemTime};
+
+use hyper_serde::Serde;
+use ipc_channel::ipc;
+use net_traits::{CookieSource, CoreResourceMsg, IpcSend, ResourceThreads};
+use servo_url::ServoUrl;
+
+/// Cookie of Servo's cookie store.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cookie {
+    /// Name of the cookie.
+    pub name: String,
+    /// Value of the cookie.
+    pub value: String,
+    /// Domain the cookie is sent to, without leading dot.
+    pub domain: String,
+    /// Path the cookie is sent to.
+    pub path: String,
+    /// Whether the cookie is only sent over secure connections.
+    pub secure: bool,
+    /// Whether the cookie is hidden from scripts.
+    pub http_only: bool,
+    /// Expiry time, or `None` for a session cookie.
+    pub expires: Option<SystemTime>,
+}
+
+impl Cookie {
+    fn from_servo(cookie: &cookie::Cookie<'static>, url: &ServoUrl) -> Self {
+        Self {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain: normalize_domain(
+                cookie
+                    .domain()
+                    .or_else(|| url.host_str())
+                    .unwrap_or_default(),
+            ),
+            path: cookie.path().unwrap_or("/").to_string(),
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            expires: cookie.expires_datetime().map(SystemTime::from),
+        }
+    }
+}
+
+/// Normalize a cookie domain for comparisons, removing the leading dot.
+pub fn normalize_domain(domain: &str) -> String {
+    domain.trim_start_matches('.').to_ascii_lowercase()
+}
+
+/// URLs of the pages loaded in this session, whose cookies are listed.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CookieUrls(BTreeSet<String>);
+
+impl CookieUrls {
+    /// Remember the URL of a loaded page, without its query and fragment.
+    pub fn insert(&mut self, url: &ServoUrl) {
+        if !matches!(url.scheme(), "http" | "https") {
+            return;
+        }
+        let mut url = url.as_url().clone();
+        url.set_query(None);
+        url.set_fragment(None);
+        self.0.insert(url.to_string());
+    }
+
+    /// Origins of the remembered URLs, as URLs with an empty path.
+    pub fn origins(&self) -> BTreeSet<String> {
+        self.0
+            .iter()
+            .filter_map(|url| ServoUrl::parse(url).ok())
+            .map(|url| format!("{}/", url.origin().ascii_serialization()))
+            .collect()
+    }
+}
+
+/// List the cookies sent to the remembered URLs, without duplicates.
+pub(crate) fn list(resource_threads: &ResourceThreads, urls: &CookieUrls) -> Vec<Cookie> {
+    let mut cookies: Vec<Cookie> = vec![];
+    for url in urls.0.iter().filter_map(|url| ServoUrl::parse(url).ok()) {
+        let Ok((sender, receiver)) = ipc::channel() else {
+            continue;
+        };
+        let msg = CoreResourceMsg::GetCookiesDataForUrl(url.clone(), sender, CookieSource::HTTP);
+        if let Err(e) = resource_threads.send(msg) {
+            log::warn!("Verso failed to request cookies of {url}: {e:?}");
+            continue;
+        }
+        let Ok(data) = receiver.recv() else {
+            continue;
+        };
+        for Serde(cookie) in data {
+            let cookie = Cookie::from_servo(&cookie, &url);
+            let known = cookies.iter().any(|c| {
+                c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path
+            });
+            if !known {
+                cookies.push(cookie);
+            }
+        }
+    }
+    cookies
+}
+
+/// Remove the cookies of the name whose domain is the domain, on any path. Return the number of
+/// cookies removed.
+pub(crate) fn remove(
+    resource_threads: &ResourceThreads,
+    urls: &CookieUrls,
+    domain: &str,
+    name: &str,
+) -> usize {
+    let domain = normalize_domain(domain);
+    remove_matching(resource_threads, urls, |c| {
+        c.domain == domain && c.name == name
+    })
+}
+
+/// Remove all the cookies whose domain is the domain. Return the number of cookies removed.
+pub(crate) fn remove_domain(
+    resource_threads: &ResourceThreads,
+    urls: &CookieUrls,
+    domain: &str,
+) -> usize {
+    let domain = normalize_domain(domain);
+    remove_matching(resource_threads, urls, |c| c.domain == domain)
+}
+
+fn remove_matching(
+    resource_threads: &ResourceThreads,
+    urls: &CookieUrls,
+    matches: impl Fn(&Cookie) -> bool,
+) -> usize {
+    let mut removed = 0;
+    for cookie in list(resource_threads, urls)
+        .into_iter()
+        .filter(|c| matches(c))
+    {
+        // Servo replaces a cookie with one of the same name, domain and path, and drops expired
+        // cookies, so removing is setting an expired cookie.
+        let Ok(url) = ServoUrl::parse(&format!("https://{}{}", cookie.domain, cookie.path)) else {
+            continue;
+        };
+        let name = cookie.name.clone();
+        let mut expired = cookie::Cookie::new(cookie.name, "");
+        expired.set_domain(cookie.domain);
+        expired.set_path(cookie.path);
+        expired.set_secure(cookie.secure);
+        expired.set_http_only(cookie.http_only);
+        expired.set_max_age(cookie::time::Duration::ZERO);
+        let msg = CoreResourceMsg::SetCookieForUrl(url, Serde(expired), CookieSource::HTTP);
+        match resource_threads.send(msg) {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!("Verso failed to remove cookie {name}: {e:?}"),
+        }
+    }
+    removed
+}
@@ -0,0 +1,155 @@
+//! Crash reports written when Verso panics.
+//!
+//! The panic hook is global to the process, so embedders install it with [`install_panic_hook`].
+//! The `verso` binary always installs it, writing to the `verso-crashes` directory of the
+//! profile, or to [`CRASH_REPORTS_ENV`] when set. A panic then writes a directory named
+//! `crash-<unix time>` in the given directory with:
+//!
+//! - `report.txt`: the panic message and backtrace, the versions from [`crate::version`], the
+//!   GL renderer, the URLs of the web views, and how many embedder messages of each kind Verso
+//!   didn't handle.
+//! - `log.txt`: the last log lines which were enabled. Log lines are only kept when
+//!   [`CRASH_REPORTS_ENV`] is set, so the file is empty otherwise.
+//!
+//! The previous panic hook runs afterwards, so the panic behaves as before.
+
+use std::{
+    backtrace::Backtrace,
+    collections::{BTreeMap, VecDeque},
+    fmt::Write as _,
+    fs,
+    panic::PanicInfo,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock, TryLockError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{Log, Metadata, Record};
+
+/// Environment variable with the directory the `verso` binary writes crash reports to. Setting
+/// it also keeps the log lines for the reports.
+pub const CRASH_REPORTS_ENV: &str = "VERSO_CRASH_REPORTS";
+
+/// Number of log lines kept for the crash report.
+const LOG_LINES: usize = 200;
+
+/// State recorded while running, to be written in crash reports.
+#[derive(Default)]
+struct CrashState {
+    logs: VecDeque<String>,
+    renderer: Option<String>,
+    urls: BTreeMap<String, String>,
+    unhandled_messages: BTreeMap<String, u64>,
+}
+
+fn state() -> &'static Mutex<CrashState> {
+    static STATE: OnceLock<Mutex<CrashState>> = OnceLock::new();
+    STATE.get_or_init(Mutex::default)
+}
+
+fn update(f: impl FnOnce(&mut CrashState)) {
+    if let Ok(mut state) = state().lock() {
+        f(&mut state);
+    }
+}
+
+/// Record the GL renderer.
+pub(crate) fn set_renderer(renderer: String) {
+    update(|state| state.renderer = Some(renderer));
+}
+
+/// Record the current URL of the web view.
+pub(crate) fn set_url(webview: impl std::fmt::Debug, url: &impl std::fmt::Display) {
+    update(|state| {
+        state.urls.insert(format!("{webview:?}"), url.to_string());
+    });
+}
+
+/// Count an embedder message Verso didn't handle, by its variant name.
+pub(crate) fn count_unhandled_message(message: &impl std::fmt::Debug) {
+    let name = format!("{message:?}");
+    let name = name
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    update(|state| *state.unhandled_messages.entry(name).or_default() += 1);
+}
+
+/// Logger keeping the lines its inner logger logs for the crash report.
+pub(crate) struct RecordingLogger<L>(pub L);
+
+impl<L: Log> Log for RecordingLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.0.enabled(record.metadata()) {
+            let line = format!("{} {}: {}", record.level(), record.target(), record.args());
+            update(|state| {
+                if state.logs.len() == LOG_LINES {
+                    state.logs.pop_front();
+                }
+                state.logs.push_back(line);
+            });
+        }
+        self.0.log(record);
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
+
+/// Install the panic hook writing crash reports to the directory. Each call wraps the hooks
+/// installed before, so call it once.
+pub fn install_panic_hook(dir: PathBuf) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        match write_report(&dir, info) {
+            Ok(path) => eprintln!("Verso wrote a crash report to {}", path.display()),
+            Err(e) => eprintln!("Verso failed to write a crash report: {e}"),
+        }
+        previous(info);
+    }));
+}
+
+fn write_report(dir: &Path, info: &PanicInfo) -> std::io::Result<PathBuf> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = dir.join(format!("crash-{time}"));
+    fs::create_dir_all(&path)?;
+
+    let mut report = String::new();
+    let _ = writeln!(report, "Panic: {info}");
+    let _ = writeln!(report, "Version: {}", crate::version::info());
+    // The panic may come from a thread holding the lock, so don't wait for it.
+    let state = match state().try_lock() {
+        Ok(state) => Some(state),
+        Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    };
+    match state {
+        Some(state) => {
+            let renderer = state.renderer.as_deref().unwrap_or("unknown");
+            let _ = writeln!(report, "Renderer: {renderer}");
+            let _ = writeln!(report, "\nURLs:");
+            for (webview, url) in &state.urls {
+                let _ = writeln!(report, "{webview}: {url}");
+            }
+            let _ = writeln!(report, "\nUnhandled embedder messages:");
+            for (message, count) in &state.unhandled_messages {
+                let _ = writeln!(report, "{message}: {count}");
+            }
+            let logs: Vec<&str> = state.logs.iter().map(String::as_str).collect();
+            fs::write(path.join("log.txt"), logs.join("\n"))?;
+        }
+        None => report.push_str("Runtime state is unavailable.\n"),
+    }
+    let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+    fs::write(path.join("report.txt"), report)?;
+    Ok(path)
+}
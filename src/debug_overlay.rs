@@ -0,0 +1,90 @@
+//! Heads-up display of live browser state drawn over the current window, toggled with
+//! [`crate::Verso::set_debug_overlay`], for triaging performance reports.
+//!
+//! The overlay is text drawn by WebRender's debug renderer at the end of each composite, so it
+//! doesn't touch the page or its layout. It shows the frame rate, the URL and load status of the
+//! page, the resident memory of the process, the number of compositor messages in the last batch
+//! and pending scroll events, and the input latency. Resident memory is only known on Linux.
+//!
+//! The text refreshes once per second, compositing one more frame if nothing else did, so the
+//! overlay costs about a frame per second while it's on. It's a development tool, off by default.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Time between two refreshes of the text.
+pub(crate) const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Text and frame timing of the debug overlay.
+#[derive(Debug, Default)]
+pub(crate) struct DebugOverlay {
+    /// Times of the composites in the last second.
+    frames: VecDeque<Instant>,
+    /// Lines of text drawn over the window.
+    lines: Vec<String>,
+    refreshed: Option<Instant>,
+}
+
+impl DebugOverlay {
+    /// Record a composite at `now`.
+    pub fn on_composite(&mut self, now: Instant) {
+        self.frames.push_back(now);
+        self.forget_old_frames(now);
+    }
+
+    fn forget_old_frames(&mut self, now: Instant) {
+        while self
+            .frames
+            .front()
+            .is_some_and(|&frame| now.saturating_duration_since(frame) > Duration::from_secs(1))
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Number of composites in the last second.
+    pub fn frame_rate(&mut self, now: Instant) -> usize {
+        self.forget_old_frames(now);
+        self.frames.len()
+    }
+
+    /// Time the text should be refreshed.
+    pub fn next_refresh(&self) -> Instant {
+        self.refreshed
+            .map_or_else(Instant::now, |refreshed| refreshed + REFRESH_INTERVAL)
+    }
+
+    /// Replace the text, refreshed at `now`.
+    pub fn refresh(&mut self, lines: Vec<String>, now: Instant) {
+        self.lines = lines;
+        self.refreshed = Some(now);
+    }
+
+    /// Lines of text to draw.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+}
+
+/// Resident memory of the process in bytes, if the platform reports it.
+pub(crate) fn resident_memory() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        // The second field is the number of resident pages.
+        let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+        let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+        Some(pages * 4096)
+    }
+    #[cfg(not(target_os = "linux"))]
+    None
+}
+
+/// Format a number of bytes in mebibytes.
+pub(crate) fn format_memory(bytes: Option<u64>) -> String {
+    match bytes {
+        Some(bytes) => format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)),
+        None => "n/a".to_string(),
+    }
+}
@@ -0,0 +1,110 @@
+//! Discarding the web views of background windows to save memory, enabled with
+//! [`DiscardPolicy`].
+//!
+//! A discarded window closes its web view but keeps the URL and scroll position of the page.
+//! When the window is focused again, the page is loaded again and scrolled back. The session
+//! history of the web view is lost, since Servo can't restore it. Changes are reported to
+//! [`crate::VersoBuilder::on_discard`], so the UI can grey out discarded windows.
+//!
+//! Verso discards the least recently focused windows first, and never discards the focused
+//! window, windows pinned with [`crate::Verso::set_pinned`], or windows playing audio.
+
+use std::time::{Duration, Instant};
+
+use servo_url::ServoUrl;
+use webrender_api::units::DeviceIntRect;
+
+/// Interval of checking the number of web views and the memory pressure.
+pub(crate) const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Script returning true if the page plays audible media.
+pub(crate) const IS_AUDIBLE: &str = "
+return [...document.querySelectorAll('audio, video')]
+    .some((media) => !media.paused && !media.muted && media.volume > 0);";
+
+/// When web views of background windows are discarded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiscardPolicy {
+    /// Maximum number of web views kept alive, or `None` for no limit, which is the default.
+    pub max_alive: Option<usize>,
+    /// Discard a web view when the system runs low on memory. It's detected on Linux, and
+    /// embedders can report it with [`crate::Verso::on_memory_pressure`] on all platforms.
+    pub discard_on_memory_pressure: bool,
+}
+
+impl DiscardPolicy {
+    /// Return how many of the `alive` web views to discard: the ones beyond
+    /// [`DiscardPolicy::max_alive`], or one if the system is under memory `pressure` and the
+    /// policy discards on it.
+    pub fn discard_count(&self, alive: usize, pressure: bool) -> usize {
+        let excess = self.max_alive.map_or(0, |max| alive.saturating_sub(max));
+        let pressure = self.discard_on_memory_pressure && pressure;
+        excess.max(pressure as usize)
+    }
+}
+
+/// Page of a discarded web view, restored when its window is focused again.
+#[derive(Debug)]
+pub(crate) struct DiscardedPage {
+    /// URL of the page.
+    pub url: ServoUrl,
+    /// Scroll position and form fields of the page.
+    pub state: Option<String>,
+    /// Position and size of the web view.
+    pub rect: DeviceIntRect,
+}
+
+/// Window which may be discarded.
+#[derive(Clone, Copy, Debug)]
+pub struct Candidate<K> {
+    /// Key of the window, returned by [`victims`].
+    pub key: K,
+    /// Time the window was last focused.
+    pub last_focused: Instant,
+    /// Whether the window is focused now.
+    pub focused: bool,
+    /// Whether the window is pinned with [`crate::Verso::set_pinned`].
+    pub pinned: bool,
+    /// Whether the window has a web view with a page, which isn't discarded yet.
+    pub alive: bool,
+}
+
+/// Return the keys of the windows which may be discarded, least recently focused first. Windows
+/// playing audio aren't known here, so Verso skips them while discarding.
+pub fn victims<K: Copy>(candidates: &[Candidate<K>]) -> Vec<K> {
+    let mut victims: Vec<_> = candidates
+        .iter()
+        .filter(|c| c.alive && !c.focused && !c.pinned)
+        .collect();
+    victims.sort_by_key(|c| c.last_focused);
+    victims.into_iter().map(|c| c.key).collect()
+}
+
+/// Return true if the system runs low on memory, with less than a tenth of it available.
+pub(crate) fn memory_pressure() -> bool {
+    #[cfg(linux)]
+    {
+        let Ok(meminfo) = std::fs::read_to_string("/proc/meminfo") else {
+            return false;
+        };
+        let field = |name: &str| {
+            meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix(name))
+                .and_then(|value| {
+                    value
+                        .trim()
+                        .trim_end_matches("kB")
+                        .trim()
+                        .parse::<u64>()
+                        .ok()
+                })
+        };
+        match (field("MemTotal:"), field("MemAvailable:")) {
+            (Some(total), Some(available)) => available * 10 < total,
+            _ => false,
+        }
+    }
+    #[cfg(not(linux))]
+    false
+}
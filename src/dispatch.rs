@@ -0,0 +1,758 @@
+//! Handling of the embedder messages of web views, split into deciding what to do and doing it.
+//!
+//! [`dispatch`] turns a message into a list of [`Action`]s from a [`DispatchState`], a read-only
+//! view of the state of the window, without touching the window, Servo or the clipboard. The
+//! window then applies the actions in order. Messages without a web view, like cursor changes,
+//! go through [`dispatch_global`]. This keeps the handling of each message testable without an
+//! event loop or a running engine: a test builds the message, dispatches it and checks the
+//! actions.
+//!
+//! Messages of the panel are still handled directly by the window, as they drive its UI.
+//!
+//! Title and favicon changes go through an [`UpdateThrottle`] before they are applied, so pages
+//! animating their title don't flood the window manager and the tab hooks with updates.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use base::id::WebViewId;
+use compositing_traits::ConstellationMsg;
+use embedder_traits::{
+    CompositorEventVariant, Cursor, EmbedderMsg, PermissionPrompt, PermissionRequest,
+    PromptDefinition,
+};
+use ipc_channel::ipc::IpcSender;
+use servo_url::ServoUrl;
+use webrender_api::units::{DeviceIntPoint, DeviceIntRect, DeviceIntSize};
+
+use crate::{
+    auto_resize::{read_content_size, ContentSize, CONTENT_SIZE_PROMPT, CONTENT_SIZE_SCRIPT},
+    autofill::{QUERY_PROMPT, SUBMIT_PROMPT},
+    config::{AutoplayPolicy, BeforeUnloadPolicy, Config, WebRtcConfig, WindowControlPolicy},
+    content_scripts::{frames_script, page_script, RunAt},
+    contrast::forced_colors_script,
+    downloads::{read_download, DOWNLOAD_PROMPT, DOWNLOAD_SCRIPT, MAX_DOWNLOADS_PER_PAGE},
+    drag_out::{read_drag, DragItem, DRAG_PROMPT, DRAG_SCRIPT},
+    features::{denies_feature, denies_permission, feature_policy_script, Feature},
+    hooks::{LifecycleEvent, LoadResult},
+    passwords::{
+        parse_credentials, parse_login_form, passwords_script, CREDENTIALS_PROMPT,
+        LOGIN_FORM_PROMPT,
+    },
+    print::{OVERRIDE_PRINT, PRINT_PROMPT},
+    suspend::ResumeProgress,
+    tabs::{Favicon, FAVICON_PROMPT},
+    timers::timer_throttle_script,
+    webview::{restore_page_state_script, CrashRestore, HeldDialog, LoadStatus},
+    zoom::{read_magnify_target, MagnifyTarget, MAGNIFY_PROMPT},
+};
+
+/// Script to stop audible media from autoplaying. Muted media are left playing.
+const STOP_AUDIBLE_AUTOPLAY: &str = "
+for (const media of document.querySelectorAll('audio, video')) {
+    if (!media.muted && media.volume > 0) {
+        media.autoplay = false;
+        media.pause();
+    }
+}";
+
+/// Window state changes pages can request. Kiosks deny all of them.
+fn window_control_policy(config: &Config) -> WindowControlPolicy {
+    if config.window.fullscreen {
+        WindowControlPolicy::default()
+    } else {
+        config.window_control
+    }
+}
+
+/// Script wrapping `RTCPeerConnection` to apply the default ICE servers and hide candidates other
+/// than relay ones. It runs once the head of the document is parsed, so scripts in the head still
+/// see the original constructor. This is best effort, see
+/// [`WebRtcConfig::hide_non_relay_candidates`].
+fn webrtc_script(config: &WebRtcConfig) -> Option<String> {
+    if !config.enabled || (config.ice_servers.is_empty() && !config.hide_non_relay_candidates) {
+        return None;
+    }
+    let ice_servers = serde_json::to_string(&config.ice_servers).ok()?;
+    Some(format!(
+        "const Native = window.RTCPeerConnection;
+        if (!Native || Native.versoWrapped) return;
+        const iceServers = {ice_servers};
+        const relayOnly = {relay_only};
+        function RTCPeerConnection(configuration = {{}}) {{
+            configuration = Object.assign({{}}, configuration);
+            if (!configuration.iceServers || !configuration.iceServers.length) {{
+                configuration.iceServers = iceServers;
+            }}
+            if (relayOnly) configuration.iceTransportPolicy = 'relay';
+            const connection = new Native(configuration);
+            if (relayOnly) {{
+                // Registered first, so it runs before handlers added by the page.
+                connection.addEventListener('icecandidate', (event) => {{
+                    if (event.candidate && !/ typ relay( |$)/.test(event.candidate.candidate)) {{
+                        event.stopImmediatePropagation();
+                    }}
+                }});
+            }}
+            return connection;
+        }}
+        RTCPeerConnection.prototype = Native.prototype;
+        RTCPeerConnection.versoWrapped = true;
+        window.RTCPeerConnection = RTCPeerConnection;",
+        relay_only = config.hide_non_relay_candidates,
+    ))
+}
+
+/// Time after a click, touch or key press during which the page is taken to act for the user.
+pub const USER_ACTIVATION_DURATION: Duration = Duration::from_secs(1);
+
+/// State of a window that the handling of messages depends on.
+#[derive(Clone, Copy, Debug)]
+pub struct DispatchState<'a> {
+    /// Configuration of Verso.
+    pub config: &'a Config,
+    /// URL of the page.
+    pub url: Option<&'a ServoUrl>,
+    /// Title of the page.
+    pub title: Option<&'a str>,
+    /// Whether the user interacted with the page since it started loading.
+    pub user_gesture: bool,
+    /// Whether the user clicked, touched or pressed a key in the window within
+    /// [`USER_ACTIVATION_DURATION`].
+    pub user_activation: bool,
+    /// Random token of the window, which messages of the scripts Verso injects carry. See
+    /// [`crate::passwords`].
+    pub token: &'a str,
+    /// Progress of restoring the web view after a crash or from a snapshot.
+    pub crash_restore: Option<&'a CrashRestore>,
+    /// Number of times the web view was restored after a crash.
+    pub restores: u32,
+    /// Number of downloads the page started since it started loading.
+    pub downloads: u32,
+    /// Whether the window is hidden.
+    pub occluded: bool,
+    /// Position and size of the web view.
+    pub webview_rect: Option<DeviceIntRect>,
+    /// Whether the errors of loads are reported to the `after_load` hook.
+    pub report_load_errors: bool,
+    /// Whether the page reports its content size. See [`crate::auto_resize`].
+    pub report_content_size: bool,
+}
+
+/// Effect of an embedder message on the window, the engine or the clipboard.
+#[derive(Debug)]
+pub enum Action {
+    /// Set the load status of the web view.
+    SetLoadStatus(LoadStatus),
+    /// Forget that the user interacted with the page.
+    ClearUserGesture,
+    /// Decode pages with the encoding they declare again.
+    ClearEncodingOverride,
+    /// Run a script in the main frame, with what it does for its error messages.
+    RunScript(&'static str, String),
+    /// Queue a lifecycle event for the hooks.
+    PushEvent(LifecycleEvent),
+    /// Send a message to the constellation.
+    Send(ConstellationMsg),
+    /// Request a redraw of the window.
+    RequestRedraw,
+    /// Set the progress of restoring the web view.
+    SetCrashRestore(Option<CrashRestore>),
+    /// Save the page state to restore it once the crash page is loaded, after the given number
+    /// of restores.
+    SaveCrashState(u32),
+    /// Set the number of downloads the page started.
+    SetDownloads(u32),
+    /// Read the writing mode and direction of the page. See [`crate::flow`].
+    ReadPageFlow,
+    /// Open the page in the PDF viewer if it's a PDF document. See [`crate::pdf`].
+    #[cfg(feature = "pdf-viewer")]
+    OpenPdf,
+    /// Check whether the page is Servo's network error page, retrying transient errors if true,
+    /// and report the load to the `after_load` hook.
+    CheckLoadError(bool),
+    /// Reset the retries of the load, which succeeded.
+    ResetRetries,
+    /// Answer whether the page may unload.
+    AllowUnload(IpcSender<bool>, bool),
+    /// Answer with the text of the clipboard.
+    SendClipboardContents(IpcSender<String>),
+    /// Copy the text, with the HTML of the selection if any, to the clipboard.
+    SetClipboardContents(String),
+    /// Show the input method at the second rectangle, in the web view at the first rectangle.
+    ShowIme(DeviceIntRect, DeviceIntRect),
+    /// Hide the input method.
+    HideIme,
+    /// Drag the link or image out of the page. See [`crate::drag_out`].
+    StartDragOut(DragItem),
+    /// Pinch zoom into the element for the smart magnify gesture. See [`crate::zoom`].
+    SmartMagnify(MagnifyTarget),
+    /// Set the title of the page and report the tab update.
+    SetTitle(Option<String>),
+    /// Set the favicon of the page and report the tab update.
+    SetFavicon(Arc<Favicon>),
+    /// Record the content size of the page and report it. See [`crate::auto_resize`].
+    SetContentSize(ContentSize),
+    /// Set the session history and the index of the current entry.
+    SetHistory(Vec<ServoUrl>, usize),
+    /// Answer a prompt message of the page.
+    AnswerPrompt(IpcSender<Option<String>>, Option<String>),
+    /// Answer a page of the PDF viewer with the rendered page of the message.
+    #[cfg(feature = "pdf-viewer")]
+    RenderPdfPage(IpcSender<Option<String>>, String),
+    /// Download the PDF document and answer with its path.
+    #[cfg(feature = "pdf-viewer")]
+    DownloadPdf(IpcSender<Option<String>>),
+    /// Inject the autofill script with the token of the instance. See [`crate::autofill`].
+    InjectAutofill,
+    /// Record the values of a submitted form for the origin. See [`crate::autofill`].
+    RecordAutofill(String, String),
+    /// Answer with the values previously submitted for the origin. See [`crate::autofill`].
+    QueryAutofill(IpcSender<Option<String>>, String, String),
+    /// Answer a permission request.
+    AnswerPermission(IpcSender<PermissionRequest>, PermissionRequest),
+    /// Keep the dialog open until the embedder answers it, dismissing the previous one.
+    HoldDialog(HeldDialog),
+    /// Dismiss the dialog.
+    DismissDialog(PromptDefinition),
+    /// Move the window.
+    MoveWindow(DeviceIntPoint),
+    /// Resize the inner size of the window.
+    ResizeWindow(DeviceIntSize),
+    /// Enter or leave fullscreen.
+    SetFullscreen(bool),
+    /// Set the cursor of the current window.
+    SetCursor(Cursor),
+    /// Let a client connect to the devtools server.
+    AllowDevtoolsConnection(IpcSender<bool>),
+    /// Record the port of the devtools server, or `None` if it failed to start.
+    SetDevtoolsPort(Option<u16>),
+    /// Count a message Verso doesn't handle. See [`crate::crash`].
+    Unhandled(EmbedderMsg),
+}
+
+/// Origin of the URL, if any.
+fn origin(url: Option<&ServoUrl>) -> Option<String> {
+    url.map(|url| url.origin().ascii_serialization())
+}
+
+/// Decide how the window handles a message of its web view.
+pub fn dispatch(webview_id: WebViewId, message: EmbedderMsg, state: DispatchState) -> Vec<Action> {
+    let config = state.config;
+    let mut actions = Vec::new();
+    match message {
+        EmbedderMsg::LoadStart => {
+            actions.push(Action::ClearUserGesture);
+            actions.push(Action::SetDownloads(0));
+            actions.push(Action::ClearEncodingOverride);
+            actions.push(Action::SetLoadStatus(LoadStatus::Started));
+        }
+        EmbedderMsg::HeadParsed => {
+            actions.push(Action::SetLoadStatus(LoadStatus::HeadParsed));
+            let scripts = [
+                ("apply WebRTC", webrtc_script(&config.webrtc)),
+                (
+                    "apply feature policy",
+                    feature_policy_script(&config.feature_policies),
+                ),
+                ("apply print", Some(OVERRIDE_PRINT.to_string())),
+                ("apply downloads", Some(DOWNLOAD_SCRIPT.to_string())),
+                (
+                    "apply drag out",
+                    cfg!(feature = "drag-out").then(|| DRAG_SCRIPT.to_string()),
+                ),
+                (
+                    "apply content size",
+                    state
+                        .report_content_size
+                        .then(|| CONTENT_SIZE_SCRIPT.to_string()),
+                ),
+                (
+                    "apply passwords",
+                    Some(passwords_script(config.share_credentials, state.token)),
+                ),
+                (
+                    "apply forced colors",
+                    config
+                        .forced_colors
+                        .is_active()
+                        .then(|| forced_colors_script(true)),
+                ),
+                (
+                    "apply content scripts",
+                    page_script(&config.content_scripts, RunAt::DocumentStart),
+                ),
+                (
+                    "apply content scripts",
+                    page_script(&config.content_scripts, RunAt::DocumentEnd),
+                ),
+                (
+                    "apply timer throttling",
+                    (config.background_timer_throttle > 0).then(|| {
+                        timer_throttle_script(config.background_timer_throttle, state.occluded)
+                    }),
+                ),
+            ];
+            for (name, script) in scripts {
+                if let Some(script) = script {
+                    actions.push(Action::RunScript(name, script));
+                }
+            }
+            if config.autofill {
+                actions.push(Action::InjectAutofill);
+            }
+        }
+        EmbedderMsg::WebViewOpened(_) => {
+            actions.push(Action::PushEvent(LifecycleEvent::Ready));
+        }
+        EmbedderMsg::WebViewClosed(_) => {
+            // Most WebView messages are ignored because it's done by compositor.
+            log::trace!("Verso WebView {webview_id:?} ignores this message: {message:?}")
+        }
+        EmbedderMsg::WebViewFocused(w) => {
+            log::debug!("Verso WebView {w} has loaded completely.");
+        }
+        EmbedderMsg::LoadComplete => {
+            actions.push(Action::SetLoadStatus(LoadStatus::Complete));
+            actions.push(Action::RequestRedraw);
+            actions.push(Action::Send(ConstellationMsg::FocusWebView(webview_id)));
+            if state.crash_restore.is_some() {
+                actions.push(Action::SetCrashRestore(None));
+            }
+            match state.crash_restore {
+                Some(CrashRestore::Crashed(page)) => {
+                    // Servo's crash page has loaded, so load the page again.
+                    if let Some(url) = state.url {
+                        log::debug!("Verso WebView {webview_id:?} restores {url}");
+                        actions.push(Action::SetLoadStatus(LoadStatus::NavigationStarted));
+                        actions.push(Action::Send(ConstellationMsg::LoadUrl(
+                            webview_id,
+                            url.clone(),
+                        )));
+                        actions.push(Action::SetCrashRestore(Some(CrashRestore::Reloading(
+                            page.clone(),
+                        ))));
+                        return actions;
+                    }
+                }
+                Some(CrashRestore::Reloading(Some(page))) => {
+                    let script =
+                        restore_page_state_script(page, config.crash_recovery.restore_form_data);
+                    actions.push(Action::RunScript("restore page state", script));
+                }
+                Some(CrashRestore::Resuming(page)) => {
+                    if let Some(page) = page {
+                        let script = restore_page_state_script(page, true);
+                        actions.push(Action::RunScript("restore page state", script));
+                    }
+                    actions.push(Action::PushEvent(LifecycleEvent::Resumed(
+                        webview_id,
+                        ResumeProgress::Restored,
+                    )));
+                }
+                _ => {}
+            }
+            let content_scripts = [
+                page_script(&config.content_scripts, RunAt::DocumentIdle),
+                frames_script(&config.content_scripts),
+            ];
+            for script in content_scripts.into_iter().flatten() {
+                actions.push(Action::RunScript("inject content scripts", script));
+            }
+            actions.push(Action::ReadPageFlow);
+            #[cfg(feature = "pdf-viewer")]
+            actions.push(Action::OpenPdf);
+            let retry = config.navigation_retry.max_retries > 0;
+            if retry || state.report_load_errors {
+                actions.push(Action::CheckLoadError(retry));
+            } else {
+                actions.push(Action::ResetRetries);
+                if let Some(url) = state.url {
+                    actions.push(Action::PushEvent(LifecycleEvent::Loaded(
+                        url.clone(),
+                        LoadResult::Loaded,
+                    )));
+                }
+            }
+            let block_autoplay = match config.autoplay {
+                AutoplayPolicy::Allow => false,
+                AutoplayPolicy::AllowAfterUserGesture => !state.user_gesture,
+                AutoplayPolicy::Block => true,
+            };
+            if block_autoplay {
+                log::debug!("Verso WebView {webview_id:?} stops autoplaying media with sound");
+                actions.push(Action::RunScript(
+                    "stop autoplaying media",
+                    STOP_AUDIBLE_AUTOPLAY.to_string(),
+                ));
+            }
+        }
+        EmbedderMsg::AllowNavigationRequest(id, url) => {
+            // Verso answers once the `before_navigate` hook is called.
+            actions.push(Action::PushEvent(LifecycleEvent::Navigate(
+                webview_id, id, url,
+            )));
+        }
+        EmbedderMsg::AllowUnload(sender) => {
+            if config.hold_dialogs {
+                actions.push(Action::HoldDialog(HeldDialog::BeforeUnload(sender)));
+            } else {
+                let allow = config.before_unload == BeforeUnloadPolicy::Allow;
+                actions.push(Action::AllowUnload(sender, allow));
+            }
+        }
+        EmbedderMsg::GetClipboardContents(sender) => {
+            actions.push(Action::SendClipboardContents(sender));
+        }
+        EmbedderMsg::SetClipboardContents(text) => {
+            actions.push(Action::SetClipboardContents(text));
+        }
+        EmbedderMsg::EventDelivered(event) => {
+            if let CompositorEventVariant::MouseButtonEvent = event {
+                actions.push(Action::Send(ConstellationMsg::FocusWebView(webview_id)));
+            }
+        }
+        EmbedderMsg::ShowIME(_, _, _, rect) => {
+            if let Some(webview_rect) = state.webview_rect {
+                actions.push(Action::ShowIme(webview_rect, rect));
+            }
+        }
+        EmbedderMsg::HideIME => actions.push(Action::HideIme),
+        EmbedderMsg::ChangePageTitle(title) => {
+            if state.title != title.as_deref() {
+                actions.push(Action::SetTitle(title));
+            }
+        }
+        EmbedderMsg::NewFavicon(url) => match crate::tabs::cached(&url) {
+            Some(favicon) => actions.push(Action::SetFavicon(favicon)),
+            None => {
+                if let Some(script) = crate::tabs::decode_script(&url) {
+                    actions.push(Action::RunScript("decode favicon", script));
+                }
+            }
+        },
+        EmbedderMsg::HistoryChanged(entries, current) => {
+            actions.push(Action::SetHistory(entries, current));
+        }
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg == PRINT_PROMPT =>
+        {
+            actions.push(Action::AnswerPrompt(prompt_sender, None));
+            actions.push(Action::PushEvent(LifecycleEvent::Print(webview_id)));
+        }
+        #[cfg(feature = "pdf-viewer")]
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(crate::pdf::PAGE_PROMPT) =>
+        {
+            actions.push(Action::RenderPdfPage(prompt_sender, msg));
+        }
+        #[cfg(feature = "pdf-viewer")]
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg == crate::pdf::DOWNLOAD_PROMPT =>
+        {
+            if config.policies.disable_downloads {
+                log::warn!("Verso WebView {webview_id:?} can't download: disabled by policy");
+                actions.push(Action::AnswerPrompt(prompt_sender, None));
+            } else {
+                actions.push(Action::DownloadPdf(prompt_sender));
+            }
+        }
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(DOWNLOAD_PROMPT) =>
+        {
+            actions.push(Action::AnswerPrompt(prompt_sender, None));
+            if config.policies.disable_downloads {
+                log::warn!("Verso WebView {webview_id:?} can't download: disabled by policy");
+            } else if !state.user_activation {
+                log::warn!("Verso WebView {webview_id:?} can't download without user activation");
+            } else if state.downloads >= MAX_DOWNLOADS_PER_PAGE {
+                log::warn!("Verso WebView {webview_id:?} can't download: too many downloads");
+            } else if let Some(download) = read_download(state.url, &msg) {
+                actions.push(Action::SetDownloads(state.downloads + 1));
+                actions.push(Action::PushEvent(LifecycleEvent::Download(download)));
+            }
+        }
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(DRAG_PROMPT) =>
+        {
+            actions.push(Action::AnswerPrompt(prompt_sender, None));
+            if let Some(item) = read_drag(&msg) {
+                actions.push(Action::StartDragOut(item));
+            }
+        }
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(MAGNIFY_PROMPT) =>
+        {
+            actions.push(Action::AnswerPrompt(prompt_sender, None));
+            if let Some(target) = read_magnify_target(&msg) {
+                actions.push(Action::SmartMagnify(target));
+            }
+        }
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(CONTENT_SIZE_PROMPT) =>
+        {
+            actions.push(Action::AnswerPrompt(prompt_sender, None));
+            if let Some(size) = read_content_size(&msg) {
+                actions.push(Action::SetContentSize(size));
+            }
+        }
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(FAVICON_PROMPT) =>
+        {
+            actions.push(Action::AnswerPrompt(prompt_sender, None));
+            if let Some(favicon) = crate::tabs::read_decoded(&msg) {
+                actions.push(Action::SetFavicon(favicon));
+            }
+        }
+        #[cfg(feature = "svg-favicons")]
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(crate::tabs::SVG_FAVICON_PROMPT) =>
+        {
+            actions.push(Action::AnswerPrompt(prompt_sender, None));
+            if let Some(favicon) = crate::tabs::read_svg(&msg) {
+                actions.push(Action::SetFavicon(favicon));
+            }
+        }
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(LOGIN_FORM_PROMPT) =>
+        {
+            actions.push(Action::AnswerPrompt(prompt_sender, None));
+            // Frames and page scripts can send the message too, so it's ignored unless it has the
+            // token of the script in the top-level page.
+            if let Some(form) =
+                origin(state.url).and_then(|origin| parse_login_form(&origin, state.token, &msg))
+            {
+                actions.push(Action::PushEvent(LifecycleEvent::LoginForm(form)));
+            }
+        }
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(CREDENTIALS_PROMPT) =>
+        {
+            actions.push(Action::AnswerPrompt(prompt_sender, None));
+            if let Some(credentials) = origin(state.url).and_then(|origin| {
+                parse_credentials(&origin, state.token, &msg, config.share_credentials)
+            }) {
+                actions.push(Action::PushEvent(LifecycleEvent::CredentialsSubmitted(
+                    credentials,
+                )));
+            }
+        }
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(SUBMIT_PROMPT) =>
+        {
+            actions.push(Action::AnswerPrompt(prompt_sender, None));
+            if let (true, true, Some(origin)) =
+                (config.autofill, state.user_activation, origin(state.url))
+            {
+                actions.push(Action::RecordAutofill(origin, msg));
+            }
+        }
+        EmbedderMsg::Prompt(PromptDefinition::Input(msg, _, prompt_sender), _)
+            if msg.starts_with(QUERY_PROMPT) =>
+        {
+            // Pages can focus fields by themselves, so values are only offered when the user
+            // just interacted with the window.
+            match (config.autofill, state.user_activation, origin(state.url)) {
+                (true, true, Some(origin)) => {
+                    actions.push(Action::QueryAutofill(prompt_sender, origin, msg))
+                }
+                _ => actions.push(Action::AnswerPrompt(prompt_sender, None)),
+            }
+        }
+        EmbedderMsg::PromptPermission(
+            PermissionPrompt::Request(name) | PermissionPrompt::Insecure(name),
+            sender,
+        ) if denies_permission(&config.feature_policies, state.url, name) => {
+            log::debug!("Verso WebView {webview_id:?} denies permission {name:?} by policy");
+            actions.push(Action::AnswerPermission(sender, PermissionRequest::Denied));
+        }
+        EmbedderMsg::GetSelectedBluetoothDevice(_, sender)
+            if denies_feature(&config.feature_policies, state.url, Feature::Bluetooth) =>
+        {
+            log::debug!("Verso WebView {webview_id:?} denies Bluetooth by policy");
+            actions.push(Action::AnswerPrompt(sender, None));
+        }
+        EmbedderMsg::Prompt(definition, _origin) => {
+            if config.hold_dialogs {
+                actions.push(Action::HoldDialog(HeldDialog::Prompt(definition)));
+            } else {
+                log::debug!("Verso WebView {webview_id:?} dismisses dialog");
+                actions.push(Action::DismissDialog(definition));
+            }
+        }
+        EmbedderMsg::MoveTo(position) => {
+            if window_control_policy(config).allow_move {
+                actions.push(Action::MoveWindow(position));
+            } else {
+                log::debug!("Verso WebView {webview_id:?} isn't allowed to move the window");
+            }
+        }
+        EmbedderMsg::ResizeTo(size) => {
+            if window_control_policy(config).allow_resize {
+                actions.push(Action::ResizeWindow(size));
+            } else {
+                log::debug!("Verso WebView {webview_id:?} isn't allowed to resize the window");
+            }
+        }
+        EmbedderMsg::SetFullscreenState(fullscreen) => {
+            if window_control_policy(config).allow_fullscreen {
+                actions.push(Action::SetFullscreen(fullscreen));
+            } else {
+                log::debug!(
+                    "Verso WebView {webview_id:?} isn't allowed to change the fullscreen state"
+                );
+            }
+        }
+        EmbedderMsg::Panic(reason, _backtrace) => {
+            log::error!("Verso WebView {webview_id:?} crashed: {reason}");
+            let policy = &config.crash_recovery;
+            if state.restores < policy.max_restores {
+                actions.push(Action::SaveCrashState(state.restores + 1));
+            } else if policy.max_restores > 0 {
+                log::warn!(
+                    "Verso WebView {webview_id:?} crashed again after {} restores",
+                    state.restores
+                );
+            }
+        }
+        e => actions.push(Action::Unhandled(e)),
+    }
+    actions
+}
+
+/// Decide how Verso handles a message which isn't sent by a web view.
+pub fn dispatch_global(message: EmbedderMsg) -> Vec<Action> {
+    match message {
+        // TODO: This should move to compositor
+        EmbedderMsg::SetCursor(cursor) => vec![Action::SetCursor(cursor)],
+        EmbedderMsg::Shutdown | EmbedderMsg::ReadyToPresent(_) => Vec::new(),
+        // The server only runs if the embedder enabled it.
+        EmbedderMsg::RequestDevtoolsConnection(sender) => {
+            vec![Action::AllowDevtoolsConnection(sender)]
+        }
+        EmbedderMsg::OnDevtoolsStarted(result, _token) => {
+            vec![Action::SetDevtoolsPort(result.ok())]
+        }
+        e => {
+            log::trace!("Verso Window isn't supporting handling this message yet: {e:?}");
+            Vec::new()
+        }
+    }
+}
+
+/// Shortest interval between title or favicon updates of a window by default.
+pub const DEFAULT_TITLE_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Latest value of a kind of update, and when the last one was applied.
+#[derive(Debug)]
+struct UpdateSlot<T> {
+    last: Option<Instant>,
+    pending: Option<T>,
+}
+
+impl<T> Default for UpdateSlot<T> {
+    fn default() -> Self {
+        Self {
+            last: None,
+            pending: None,
+        }
+    }
+}
+
+impl<T> UpdateSlot<T> {
+    /// Return the value if it can be applied now, or keep it until the interval elapsed,
+    /// replacing any value kept before.
+    fn offer(&mut self, value: T, interval: Duration, now: Instant) -> Option<T> {
+        if self.is_due(interval, now) {
+            self.last = Some(now);
+            self.pending = None;
+            Some(value)
+        } else {
+            self.pending = Some(value);
+            None
+        }
+    }
+
+    /// Return the kept value if the interval elapsed.
+    fn take_due(&mut self, interval: Duration, now: Instant) -> Option<T> {
+        if self.pending.is_some() && self.is_due(interval, now) {
+            self.last = Some(now);
+            self.pending.take()
+        } else {
+            None
+        }
+    }
+
+    fn is_due(&self, interval: Duration, now: Instant) -> bool {
+        self.last
+            .map_or(true, |last| now.saturating_duration_since(last) >= interval)
+    }
+
+    fn deadline(&self, interval: Duration) -> Option<Instant> {
+        self.pending.as_ref()?;
+        Some(self.last? + interval)
+    }
+}
+
+/// Coalesces the [`Action::SetTitle`] and [`Action::SetFavicon`] actions of a window, so each is
+/// applied at most once per interval. The first update after a quiet interval is applied at
+/// once, and later ones only keep the latest value, which [`UpdateThrottle::flush`] applies once
+/// the interval elapsed. Other actions pass through in order.
+#[derive(Debug, Default)]
+pub struct UpdateThrottle {
+    title: UpdateSlot<Option<String>>,
+    favicon: UpdateSlot<Arc<Favicon>>,
+}
+
+impl UpdateThrottle {
+    /// Create a throttle which applies the next updates at once.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the actions to apply now, keeping the updates which came too soon after the last
+    /// one. An interval of zero applies every update.
+    pub fn filter(
+        &mut self,
+        actions: Vec<Action>,
+        interval: Duration,
+        now: Instant,
+    ) -> Vec<Action> {
+        actions
+            .into_iter()
+            .filter_map(|action| match action {
+                Action::SetTitle(title) => {
+                    self.title.offer(title, interval, now).map(Action::SetTitle)
+                }
+                Action::SetFavicon(favicon) => self
+                    .favicon
+                    .offer(favicon, interval, now)
+                    .map(Action::SetFavicon),
+                action => Some(action),
+            })
+            .collect()
+    }
+
+    /// Return the kept updates whose interval elapsed, with their latest value.
+    pub fn flush(&mut self, interval: Duration, now: Instant) -> Vec<Action> {
+        let title = self.title.take_due(interval, now).map(Action::SetTitle);
+        let favicon = self.favicon.take_due(interval, now).map(Action::SetFavicon);
+        title.into_iter().chain(favicon).collect()
+    }
+
+    /// When the next kept update is due, if any.
+    pub fn deadline(&self, interval: Duration) -> Option<Instant> {
+        self.title
+            .deadline(interval)
+            .into_iter()
+            .chain(self.favicon.deadline(interval))
+            .min()
+    }
+
+    /// Title waiting to be applied, which is the latest title of the page.
+    pub fn pending_title(&self) -> Option<Option<&str>> {
+        self.title.pending.as_ref().map(Option::as_deref)
+    }
+}
@@ -0,0 +1,65 @@
+//! Overrides of DNS resolution for specific hosts, like a hosts file, set with
+//! [`crate::VersoBuilder::dns_overrides`], to test against staging servers without editing
+//! `/etc/hosts`.
+//!
+//! Hosts are matched without case, and other hosts are resolved by the system. Servo resolves
+//! hosts through a table of exact names, which it reads once per process from the hosts file in
+//! the `HOST_FILE` environment variable. Verso writes the entries of any previous `HOST_FILE`,
+//! followed by the overrides, to a file of its own in the temporary directory and points
+//! `HOST_FILE` to it before Servo starts. So the overrides:
+//!
+//! - apply to every request of the process, including subresources and other frames,
+//! - can't change once Servo resolved its first host, so they're only set when building Verso,
+//! - can't be wildcards like `*.example.com`, which Servo's table has no way to match.
+
+use std::{collections::BTreeMap, fmt::Write as _, fs, io, net::IpAddr};
+
+/// Environment variable with the hosts file Servo reads.
+const HOST_FILE_ENV: &str = "HOST_FILE";
+
+/// Error of validating a host of the overrides.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum DnsOverrideError {
+    /// The host is a wildcard, which Servo can't match.
+    #[error("DNS override `{0}` is a wildcard, which isn't supported")]
+    Wildcard(String),
+    /// The host is empty, or has characters other than letters, digits, `-`, `_` and `.`.
+    #[error("DNS override `{0}` isn't a host name")]
+    InvalidHost(String),
+}
+
+/// Validate the host of an override, and return it in lowercase without a trailing dot.
+pub fn normalize_host(host: &str) -> Result<String, DnsOverrideError> {
+    if host.starts_with("*.") {
+        return Err(DnsOverrideError::Wildcard(host.into()));
+    }
+    let normalized = host.trim_end_matches('.').to_ascii_lowercase();
+    let valid = !normalized.is_empty()
+        && normalized.split('.').all(|label| {
+            !label.is_empty()
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        });
+    if !valid {
+        return Err(DnsOverrideError::InvalidHost(host.into()));
+    }
+    Ok(normalized)
+}
+
+/// Write the overrides to a hosts file and point Servo to it. The entries of the previous
+/// `HOST_FILE` are kept before the overrides, as later entries of a host replace earlier ones.
+pub(crate) fn apply(overrides: &BTreeMap<String, IpAddr>) -> io::Result<()> {
+    let mut content = match std::env::var_os(HOST_FILE_ENV) {
+        Some(previous) => fs::read_to_string(previous)? + "\n",
+        None => String::new(),
+    };
+    for (host, address) in overrides {
+        let _ = writeln!(content, "{address} {host}");
+    }
+    let path = std::env::temp_dir().join(format!("verso-hosts-{}", std::process::id()));
+    fs::write(&path, content)?;
+    // Servo reads the variable when it resolves its first host, which happens after this.
+    std::env::set_var(HOST_FILE_ENV, path);
+    Ok(())
+}
@@ -0,0 +1,227 @@
+//! Downloads of generated files, like images exported with `canvas.toDataURL()`, started by
+//! clicking or calling `click()` on a link with a `download` attribute and a `data:` or `blob:`
+//! URL.
+//!
+//! Servo doesn't implement downloads, and would navigate to such links instead. Verso intercepts
+//! them in the page: `data:` URLs are sent as they are, and `blob:` URLs are read into `data:`
+//! URLs, as the page may revoke them right after the click. Verso decodes them, including
+//! percent-encoded and base64 ones, and passes the bytes to
+//! [`crate::VersoBuilder::on_download`]. Without the hook, or if it returns false, the file is
+//! saved to the downloads directory under the name suggested by the `download` attribute,
+//! without overwriting existing files.
+//!
+//! Pages can't download on their own: a download is dropped unless the user clicked, touched or
+//! pressed a key in the window within [`crate::dispatch::USER_ACTIVATION_DURATION`]. Files larger
+//! than [`MAX_DOWNLOAD_SIZE`], and downloads past [`MAX_DOWNLOADS_PER_PAGE`] since the page
+//! started loading, are dropped too.
+//!
+//! Links to other URLs are left to Servo. Downloads are dropped when
+//! [`crate::policies::Policies`] disable them.
+
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use data_url::DataUrl;
+use serde::Deserialize;
+use servo_url::ServoUrl;
+
+/// Prefix of the message the page sends with `prompt()` with a link to download.
+pub(crate) const DOWNLOAD_PROMPT: &str = "VERSO_DOWNLOAD:";
+
+/// Largest file a page can download, in bytes.
+pub const MAX_DOWNLOAD_SIZE: usize = 64 * 1024 * 1024;
+
+/// Number of downloads a page can start from when it starts loading.
+pub const MAX_DOWNLOADS_PER_PAGE: u32 = 10;
+
+/// Names Windows reserves for devices, with or without an extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Script intercepting clicks on links which download `data:` and `blob:` URLs.
+pub(crate) const DOWNLOAD_SCRIPT: &str = "
+(() => {
+    const download = (event) => {
+        const link = event.target instanceof Element ? event.target.closest('a[download]') : null;
+        if (!link || event.defaultPrevented || !/^(data|blob):/i.test(link.href)) {
+            return;
+        }
+        event.preventDefault();
+        const url = link.href;
+        const name = link.getAttribute('download');
+        const send = (data) => prompt('VERSO_DOWNLOAD:' + JSON.stringify({ name, data }));
+        if (/^data:/i.test(url)) {
+            send(url);
+            return;
+        }
+        fetch(url)
+            .then((response) => response.blob())
+            .then((blob) => {
+                const reader = new FileReader();
+                reader.onload = () => send(reader.result);
+                reader.readAsDataURL(blob);
+            })
+            .catch((e) => console.error(`Verso failed to download ${url}: ${e}`));
+    };
+    addEventListener('click', download);
+    const click = HTMLAnchorElement.prototype.click;
+    HTMLAnchorElement.prototype.click = function () {
+        // Clicks on links outside the document don't reach the window.
+        if (!this.isConnected) {
+            this.addEventListener('click', download, { once: true });
+        }
+        click.call(this);
+    };
+})();";
+
+/// File downloaded by a page, passed to [`crate::VersoBuilder::on_download`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct Download {
+    /// URL of the page which started the download.
+    pub page_url: Option<ServoUrl>,
+    /// Name to save the file as, from the `download` attribute of the link, without directories.
+    pub file_name: String,
+    /// MIME type of the data, like `image/png`.
+    pub mime_type: String,
+    /// Decoded content of the file.
+    pub bytes: Vec<u8>,
+}
+
+impl std::fmt::Debug for Download {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Download")
+            .field("page_url", &self.page_url.as_ref().map(ServoUrl::as_str))
+            .field("file_name", &self.file_name)
+            .field("mime_type", &self.mime_type)
+            .field("len", &self.bytes.len())
+            .finish()
+    }
+}
+
+impl Download {
+    /// Save the file to the downloads directory, adding a number to its name if a file of that
+    /// name exists. Return the path of the file.
+    pub fn save(&self) -> io::Result<PathBuf> {
+        let dir = downloads_dir();
+        for path in candidate_paths(&dir, &self.file_name) {
+            // Creating the file fails if it exists, even if another process created it since the
+            // last attempt, so existing files are never overwritten.
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(&self.bytes)?;
+                    return Ok(path);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("all names of {} are taken", self.file_name),
+        ))
+    }
+}
+
+/// Message of a link to download.
+#[derive(Deserialize)]
+struct DownloadMessage {
+    name: Option<String>,
+    data: String,
+}
+
+/// Read the download of a [`DOWNLOAD_PROMPT`] message from the page, decoding its `data:` URL.
+pub(crate) fn read_download(page_url: Option<&ServoUrl>, message: &str) -> Option<Download> {
+    let json = message.strip_prefix(DOWNLOAD_PROMPT)?;
+    // Base64 takes 4 bytes for 3, so larger URLs can't hold files within the limit.
+    if json.len() > MAX_DOWNLOAD_SIZE / 3 * 4 + 4096 {
+        log::warn!("Verso drops a download larger than {MAX_DOWNLOAD_SIZE} bytes");
+        return None;
+    }
+    let message: DownloadMessage = serde_json::from_str(json).ok()?;
+    let url = DataUrl::process(&message.data)
+        .map_err(|e| log::warn!("Verso failed to parse a downloaded data URL: {e:?}"))
+        .ok()?;
+    let (bytes, _) = url
+        .decode_to_vec()
+        .map_err(|e| log::warn!("Verso failed to decode a downloaded data URL: {e:?}"))
+        .ok()?;
+    if bytes.len() > MAX_DOWNLOAD_SIZE {
+        log::warn!("Verso drops a download larger than {MAX_DOWNLOAD_SIZE} bytes");
+        return None;
+    }
+    let mime = url.mime_type();
+    let mime_type = format!("{}/{}", mime.type_, mime.subtype);
+    let file_name = file_name(message.name.as_deref().unwrap_or_default(), &mime_type);
+    Some(Download {
+        page_url: page_url.cloned(),
+        file_name,
+        mime_type,
+        bytes,
+    })
+}
+
+/// Name of the file suggested by the page, without directories or characters file systems
+/// reject. Names Windows reserves for devices get a leading `_`. Links without a suggestion are
+/// named `download`, with an extension for common types.
+fn file_name(suggested: &str, mime_type: &str) -> String {
+    let name: String = suggested
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control() && !matches!(c, ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .collect();
+    let name = name.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    let device = name.split('.').next().unwrap_or_default().trim_end();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(device))
+    {
+        return format!("_{name}");
+    }
+    if !name.is_empty() {
+        return name.to_string();
+    }
+    let extension = match mime_type {
+        "image/png" => ".png",
+        "image/jpeg" => ".jpg",
+        "image/gif" => ".gif",
+        "image/webp" => ".webp",
+        "image/svg+xml" => ".svg",
+        "application/pdf" => ".pdf",
+        "application/json" => ".json",
+        "application/zip" => ".zip",
+        "text/csv" => ".csv",
+        "text/html" => ".html",
+        "text/plain" => ".txt",
+        _ => "",
+    };
+    format!("download{extension}")
+}
+
+/// Paths of the name in the directory to try in turn: the name, then `name (1).ext` and so on.
+fn candidate_paths<'a>(dir: &'a Path, name: &'a str) -> impl Iterator<Item = PathBuf> + 'a {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{extension}")),
+        _ => (name, String::new()),
+    };
+    std::iter::once(dir.join(name))
+        .chain((1..=u16::MAX).map(move |n| dir.join(format!("{stem} ({n}){extension}"))))
+}
+
+/// Directory downloads are saved to, or the temporary directory if there's none.
+pub(crate) fn downloads_dir() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    let home = std::env::var_os("USERPROFILE");
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var_os("HOME");
+    home.map(PathBuf::from)
+        .map(|home| home.join("Downloads"))
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(std::env::temp_dir)
+}
@@ -0,0 +1,626 @@
+//! Dragging links and images out of pages into other applications, like an image into a file
+//! manager, with the `drag-out` feature.
+//!
+//! Servo doesn't implement drag and drop, so a page script reports a drag once the mouse moved a
+//! few pixels with the left button held on a link or an image. Verso then starts a drag of the
+//! platform offering the [`flavors`] of the [`DragItem`]: its URL, and the image as PNG when the
+//! page can read its pixels, which excludes cross-origin images. The page doesn't receive mouse
+//! moves until the drag ends.
+//!
+//! Drags use XDND on X11 and `NSDraggingSession` on macOS. They aren't supported on Wayland and
+//! Windows yet, where they're ignored with a warning. On X11, images too large for a single
+//! request fail to drop, as Verso doesn't send data incrementally.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Deserialize;
+use servo_url::ServoUrl;
+use winit::window::Window as WinitWindow;
+
+/// Prefix of the message the page sends with `prompt()` when a link or an image is dragged.
+pub(crate) const DRAG_PROMPT: &str = "VERSO_DRAG_OUT:";
+
+/// Script reporting drags of links and images.
+pub(crate) const DRAG_SCRIPT: &str = "
+(() => {
+    let start = null;
+    addEventListener('mousedown', (event) => {
+        const target = event.target instanceof Element ? event.target.closest('img, a[href]') : null;
+        start = event.button === 0 && target ? { x: event.clientX, y: event.clientY, target } : null;
+    });
+    addEventListener('mouseup', () => {
+        start = null;
+    });
+    addEventListener('mousemove', (event) => {
+        if (!start || !(event.buttons & 1)
+            || Math.hypot(event.clientX - start.x, event.clientY - start.y) < 5) {
+            return;
+        }
+        const target = start.target;
+        start = null;
+        const isImage = target instanceof HTMLImageElement;
+        let image = null;
+        if (isImage && target.complete && target.naturalWidth > 0) {
+            try {
+                const canvas = document.createElement('canvas');
+                canvas.width = target.naturalWidth;
+                canvas.height = target.naturalHeight;
+                canvas.getContext('2d').drawImage(target, 0, 0);
+                image = canvas.toDataURL('image/png');
+            } catch (e) {
+                // Cross-origin images can't be read.
+            }
+        }
+        const url = isImage ? target.currentSrc || target.src : target.href;
+        const title = isImage ? target.alt : target.textContent.trim();
+        prompt('VERSO_DRAG_OUT:' + JSON.stringify({ url, title, image }));
+    });
+})();";
+
+/// Set while a drag started by Verso is in progress.
+static DRAGGING: AtomicBool = AtomicBool::new(false);
+
+/// Link or image dragged out of a page.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DragItem {
+    /// URL of the link, or source of the image.
+    pub url: ServoUrl,
+    /// Text of the link or alternative text of the image, if it has one.
+    pub title: Option<String>,
+    /// Pixels of the image encoded as PNG, if it's an image the page can read.
+    pub png: Option<Vec<u8>>,
+}
+
+impl std::fmt::Debug for DragItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DragItem")
+            .field("url", &self.url.as_str())
+            .field("title", &self.title)
+            .field("png", &self.png.as_ref().map(Vec::len))
+            .finish()
+    }
+}
+
+/// Data offered to the drop target in one format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Flavor {
+    /// MIME type of the data.
+    pub mime_type: &'static str,
+    /// Data in that format.
+    pub data: Vec<u8>,
+}
+
+/// Formats the item is offered in, the preferred one first: the image as `image/png` if there's
+/// one, then the URL as `text/uri-list` and `text/plain`.
+pub fn flavors(item: &DragItem) -> Vec<Flavor> {
+    let png = item.png.iter().map(|png| Flavor {
+        mime_type: "image/png",
+        data: png.clone(),
+    });
+    png.chain([
+        Flavor {
+            mime_type: "text/uri-list",
+            data: format!("{}\r\n", item.url).into_bytes(),
+        },
+        Flavor {
+            mime_type: "text/plain",
+            data: item.url.as_str().as_bytes().to_vec(),
+        },
+    ])
+    .collect()
+}
+
+/// Message of a dragged link or image.
+#[derive(Deserialize)]
+struct DragMessage {
+    url: String,
+    title: Option<String>,
+    image: Option<String>,
+}
+
+/// Read the item of a [`DRAG_PROMPT`] message.
+pub(crate) fn read_drag(message: &str) -> Option<DragItem> {
+    let json = message.strip_prefix(DRAG_PROMPT)?;
+    let message: DragMessage = serde_json::from_str(json).ok()?;
+    let png = message.image.and_then(|image| {
+        let url = data_url::DataUrl::process(&image).ok()?;
+        let mime = url.mime_type();
+        if (mime.type_.as_str(), mime.subtype.as_str()) != ("image", "png") {
+            return None;
+        }
+        url.decode_to_vec().ok().map(|(png, _)| png)
+    });
+    Some(DragItem {
+        url: ServoUrl::parse(&message.url).ok()?,
+        title: message.title.filter(|title| !title.is_empty()),
+        png,
+    })
+}
+
+/// Return true while a drag started by Verso is in progress.
+pub(crate) fn is_dragging() -> bool {
+    DRAGGING.load(Ordering::Relaxed)
+}
+
+/// End the drag in progress once the mouse button is released.
+pub(crate) fn end() {
+    DRAGGING.store(false, Ordering::Relaxed);
+}
+
+/// Start a drag of the item from the window.
+pub(crate) fn start(window: &WinitWindow, item: &DragItem) -> Result<(), String> {
+    let flavors = flavors(item);
+    #[cfg(all(linux, feature = "drag-out"))]
+    let result = x11::start(window, flavors);
+    #[cfg(all(macos, feature = "drag-out"))]
+    let result = macos::start(window, item, flavors);
+    #[cfg(not(all(any(linux, macos), feature = "drag-out")))]
+    let result = {
+        let _ = (window, flavors);
+        Err("dragging out of pages isn't supported on this platform".to_string())
+    };
+    if result.is_ok() {
+        DRAGGING.store(true, Ordering::Relaxed);
+    }
+    result
+}
+
+/// XDND source, polling the pointer instead of grabbing it, as Winit holds the implicit grab of
+/// the button press.
+#[cfg(all(linux, feature = "drag-out"))]
+mod x11 {
+    use std::{
+        error::Error,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use winit::window::Window as WinitWindow;
+    use x11rb::{
+        connection::Connection,
+        protocol::{
+            xproto::{
+                Atom, AtomEnum, ClientMessageEvent, ConnectionExt as _, CreateWindowAux, EventMask,
+                KeyButMask, PropMode, SelectionNotifyEvent, SelectionRequestEvent, Window,
+                WindowClass, SELECTION_NOTIFY_EVENT,
+            },
+            Event,
+        },
+        rust_connection::RustConnection,
+        wrapper::ConnectionExt as _,
+        CURRENT_TIME, NONE,
+    };
+
+    use super::Flavor;
+
+    /// Interval of polling the pointer and the events of the drop target.
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+    /// Time the drop target has to accept the last position after the button is released.
+    const STATUS_TIMEOUT: Duration = Duration::from_millis(200);
+    /// Time the drop target has to read the data.
+    const DROP_TIMEOUT: Duration = Duration::from_secs(5);
+    /// Most recent XDND version Verso speaks.
+    const XDND_VERSION: u32 = 5;
+
+    x11rb::atom_manager! {
+        Atoms: AtomsCookie {
+            XdndAware,
+            XdndSelection,
+            XdndEnter,
+            XdndPosition,
+            XdndStatus,
+            XdndLeave,
+            XdndDrop,
+            XdndFinished,
+            XdndActionCopy,
+            XdndTypeList,
+            TARGETS,
+        }
+    }
+
+    /// Window under the pointer which accepts drops.
+    struct Target {
+        window: Window,
+        accepted: bool,
+        awaiting_status: bool,
+    }
+
+    /// Window owning the XDND selection for the drag.
+    struct Source {
+        conn: RustConnection,
+        root: Window,
+        window: Window,
+        atoms: Atoms,
+        flavors: Vec<(Atom, Flavor)>,
+    }
+
+    pub(super) fn start(window: &WinitWindow, flavors: Vec<Flavor>) -> Result<(), String> {
+        let handle = window.window_handle().map_err(|e| e.to_string())?;
+        if !matches!(
+            handle.as_raw(),
+            RawWindowHandle::Xlib(_) | RawWindowHandle::Xcb(_)
+        ) {
+            return Err("dragging out of pages is only supported on X11".into());
+        }
+        let source = Source::new(flavors).map_err(|e| e.to_string())?;
+        thread::spawn(move || {
+            if let Err(e) = source.run() {
+                log::warn!("Verso failed to drag out of the page: {e}");
+            }
+            super::end();
+        });
+        Ok(())
+    }
+
+    impl Source {
+        fn new(flavors: Vec<Flavor>) -> Result<Self, Box<dyn Error>> {
+            let (conn, screen) = x11rb::connect(None)?;
+            let root = conn.setup().roots[screen].root;
+            let atoms = Atoms::new(&conn)?.reply()?;
+            let mut typed = Vec::with_capacity(flavors.len());
+            for flavor in flavors {
+                let atom = conn
+                    .intern_atom(false, flavor.mime_type.as_bytes())?
+                    .reply()?
+                    .atom;
+                typed.push((atom, flavor));
+            }
+            let window = conn.generate_id()?;
+            conn.create_window(
+                0,
+                window,
+                root,
+                -1,
+                -1,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_ONLY,
+                0,
+                &CreateWindowAux::new().override_redirect(1),
+            )?;
+            let types: Vec<Atom> = typed.iter().map(|(atom, _)| *atom).collect();
+            conn.change_property32(
+                PropMode::REPLACE,
+                window,
+                atoms.XdndTypeList,
+                AtomEnum::ATOM,
+                &types,
+            )?;
+            conn.set_selection_owner(window, atoms.XdndSelection, CURRENT_TIME)?;
+            conn.flush()?;
+            Ok(Self {
+                conn,
+                root,
+                window,
+                atoms,
+                flavors: typed,
+            })
+        }
+
+        /// Follow the pointer until the button is released, then drop on the target under it.
+        fn run(&self) -> Result<(), Box<dyn Error>> {
+            let mut target: Option<Target> = None;
+            let mut position = None;
+            loop {
+                let pointer = self.conn.query_pointer(self.root)?.reply()?;
+                if !pointer.mask.contains(KeyButMask::BUTTON1) {
+                    break;
+                }
+                let aware = self.find_target(pointer.child)?;
+                if aware.map(|(window, _)| window) != target.as_ref().map(|t| t.window) {
+                    if let Some(left) = target.take() {
+                        self.send(left.window, self.atoms.XdndLeave, [self.window, 0, 0, 0, 0])?;
+                    }
+                    if let Some((window, version)) = aware {
+                        self.enter(window, version)?;
+                        target = Some(Target {
+                            window,
+                            accepted: false,
+                            awaiting_status: false,
+                        });
+                        position = None;
+                    }
+                }
+                if let Some(target) = target.as_mut() {
+                    let current = (pointer.root_x, pointer.root_y);
+                    if !target.awaiting_status && position != Some(current) {
+                        let (x, y) = (current.0 as u16 as u32, current.1 as u16 as u32);
+                        self.send(
+                            target.window,
+                            self.atoms.XdndPosition,
+                            [
+                                self.window,
+                                0,
+                                (x << 16) | y,
+                                CURRENT_TIME,
+                                self.atoms.XdndActionCopy,
+                            ],
+                        )?;
+                        target.awaiting_status = true;
+                        position = Some(current);
+                    }
+                }
+                self.conn.flush()?;
+                self.handle_events(&mut target)?;
+                thread::sleep(POLL_INTERVAL);
+            }
+            super::end();
+
+            let deadline = Instant::now() + STATUS_TIMEOUT;
+            while target.as_ref().is_some_and(|t| t.awaiting_status) && Instant::now() < deadline {
+                thread::sleep(POLL_INTERVAL);
+                self.handle_events(&mut target)?;
+            }
+            let Some(target) = target else {
+                return Ok(());
+            };
+            if !target.accepted {
+                self.send(
+                    target.window,
+                    self.atoms.XdndLeave,
+                    [self.window, 0, 0, 0, 0],
+                )?;
+                self.conn.flush()?;
+                return Ok(());
+            }
+            self.send(
+                target.window,
+                self.atoms.XdndDrop,
+                [self.window, 0, CURRENT_TIME, 0, 0],
+            )?;
+            self.conn.flush()?;
+            let deadline = Instant::now() + DROP_TIMEOUT;
+            let mut target = Some(target);
+            while Instant::now() < deadline {
+                if self.handle_events(&mut target)? {
+                    return Ok(());
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+            Err("the drop target didn't finish reading the data in time".into())
+        }
+
+        /// Find the window accepting drops under the pointer, from the child of the root window
+        /// under it, and the XDND version to speak with it.
+        fn find_target(&self, mut window: Window) -> Result<Option<(Window, u32)>, Box<dyn Error>> {
+            while window != NONE {
+                let aware = self
+                    .conn
+                    .get_property(false, window, self.atoms.XdndAware, AtomEnum::ATOM, 0, 1)?
+                    .reply()?;
+                if let Some(version) = aware.value32().and_then(|mut values| values.next()) {
+                    return Ok(Some((window, version.min(XDND_VERSION))));
+                }
+                window = self.conn.query_pointer(window)?.reply()?.child;
+            }
+            Ok(None)
+        }
+
+        fn enter(&self, window: Window, version: u32) -> Result<(), Box<dyn Error>> {
+            let mut data = [self.window, version << 24, 0, 0, 0];
+            if self.flavors.len() > 3 {
+                // The target reads the types from the XdndTypeList property.
+                data[1] |= 1;
+            }
+            for (slot, (atom, _)) in data[2..].iter_mut().zip(&self.flavors) {
+                *slot = *atom;
+            }
+            self.send(window, self.atoms.XdndEnter, data)
+        }
+
+        fn send(
+            &self,
+            window: Window,
+            message: Atom,
+            data: [u32; 5],
+        ) -> Result<(), Box<dyn Error>> {
+            let event = ClientMessageEvent::new(32, window, message, data);
+            self.conn
+                .send_event(false, window, EventMask::NO_EVENT, event)?;
+            Ok(())
+        }
+
+        /// Handle the pending events. Return true once the target finished the drop.
+        fn handle_events(&self, target: &mut Option<Target>) -> Result<bool, Box<dyn Error>> {
+            let mut finished = false;
+            while let Some(event) = self.conn.poll_for_event()? {
+                match event {
+                    Event::ClientMessage(message) if message.type_ == self.atoms.XdndStatus => {
+                        let data = message.data.as_data32();
+                        if let Some(target) = target.as_mut().filter(|t| t.window == data[0]) {
+                            target.awaiting_status = false;
+                            target.accepted = data[1] & 1 == 1;
+                        }
+                    }
+                    Event::ClientMessage(message) if message.type_ == self.atoms.XdndFinished => {
+                        finished = true;
+                    }
+                    Event::SelectionRequest(request) => self.answer(request)?,
+                    _ => {}
+                }
+            }
+            Ok(finished)
+        }
+
+        /// Send the data of the requested flavor, or the list of flavors.
+        fn answer(&self, request: SelectionRequestEvent) -> Result<(), Box<dyn Error>> {
+            // Obsolete clients don't name a property.
+            let property = if request.property == NONE {
+                request.target
+            } else {
+                request.property
+            };
+            let answered = if request.target == self.atoms.TARGETS {
+                let targets: Vec<Atom> = self.flavors.iter().map(|(atom, _)| *atom).collect();
+                self.conn.change_property32(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    AtomEnum::ATOM,
+                    &targets,
+                )?;
+                true
+            } else if let Some((_, flavor)) = self
+                .flavors
+                .iter()
+                .find(|(atom, _)| *atom == request.target)
+            {
+                self.conn.change_property8(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    request.target,
+                    &flavor.data,
+                )?;
+                true
+            } else {
+                false
+            };
+            let notify = SelectionNotifyEvent {
+                response_type: SELECTION_NOTIFY_EVENT,
+                sequence: 0,
+                time: request.time,
+                requestor: request.requestor,
+                selection: request.selection,
+                target: request.target,
+                property: if answered { property } else { NONE },
+            };
+            self.conn
+                .send_event(false, request.requestor, EventMask::NO_EVENT, notify)?;
+            self.conn.flush()?;
+            Ok(())
+        }
+    }
+}
+
+/// `NSDraggingSession` of the view of the window, started from the current mouse event.
+#[cfg(all(macos, feature = "drag-out"))]
+mod macos {
+    use std::{cell::OnceCell, sync::atomic::Ordering};
+
+    use objc2::{
+        declare_class, msg_send_id, mutability,
+        rc::Id,
+        runtime::{AnyObject, NSObject, NSObjectProtocol, ProtocolObject},
+        ClassType, DeclaredClass,
+    };
+    use objc2_app_kit::{
+        NSApplication, NSDragOperation, NSDraggingContext, NSDraggingItem, NSDraggingSession,
+        NSDraggingSource, NSImage, NSPasteboardItem, NSPasteboardTypePNG, NSPasteboardTypeString,
+        NSPasteboardTypeURL, NSView,
+    };
+    use objc2_foundation::{MainThreadMarker, NSArray, NSData, NSPoint, NSRect, NSSize};
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    use winit::window::Window as WinitWindow;
+
+    use super::{DragItem, Flavor, DRAGGING};
+
+    /// Largest side of the image shown under the pointer, in points.
+    const MAX_IMAGE_SIZE: f64 = 128.0;
+
+    declare_class!(
+        struct DragSource;
+
+        unsafe impl ClassType for DragSource {
+            type Super = NSObject;
+            type Mutability = mutability::MainThreadOnly;
+            const NAME: &'static str = "VersoDragSource";
+        }
+
+        impl DeclaredClass for DragSource {}
+
+        unsafe impl NSObjectProtocol for DragSource {}
+
+        unsafe impl NSDraggingSource for DragSource {
+            #[method(draggingSession:sourceOperationMaskForDraggingContext:)]
+            fn source_operation_mask(
+                &self,
+                _session: &NSDraggingSession,
+                _context: NSDraggingContext,
+            ) -> NSDragOperation {
+                NSDragOperation::Copy
+            }
+
+            #[method(draggingSession:endedAtPoint:operation:)]
+            fn ended(
+                &self,
+                _session: &NSDraggingSession,
+                _point: NSPoint,
+                _operation: NSDragOperation,
+            ) {
+                DRAGGING.store(false, Ordering::Relaxed);
+            }
+        }
+    );
+
+    impl DragSource {
+        fn new(mtm: MainThreadMarker) -> Id<Self> {
+            unsafe { msg_send_id![mtm.alloc::<Self>(), init] }
+        }
+    }
+
+    thread_local! {
+        /// Source of all drags, kept alive while sessions refer to it.
+        static SOURCE: OnceCell<Id<DragSource>> = const { OnceCell::new() };
+    }
+
+    pub(super) fn start(
+        window: &WinitWindow,
+        item: &DragItem,
+        flavors: Vec<Flavor>,
+    ) -> Result<(), String> {
+        let mtm = MainThreadMarker::new().ok_or("drags must start on the main thread")?;
+        let handle = window.window_handle().map_err(|e| e.to_string())?;
+        let RawWindowHandle::AppKit(handle) = handle.as_raw() else {
+            return Err("the window has no AppKit view".into());
+        };
+        let event = NSApplication::sharedApplication(mtm)
+            .currentEvent()
+            .ok_or("there's no mouse event to start the drag from")?;
+        let source = SOURCE.with(|source| source.get_or_init(|| DragSource::new(mtm)).clone());
+
+        // SAFETY: The view is valid while the Winit window is alive, and the pasteboard types
+        // are constants of AppKit.
+        unsafe {
+            let view: Id<NSView> =
+                Id::retain(handle.ns_view.as_ptr().cast()).ok_or("the view is null")?;
+            let pasteboard_item = NSPasteboardItem::new();
+            for flavor in &flavors {
+                let kind = match flavor.mime_type {
+                    "image/png" => NSPasteboardTypePNG,
+                    "text/uri-list" => NSPasteboardTypeURL,
+                    "text/plain" => NSPasteboardTypeString,
+                    _ => continue,
+                };
+                pasteboard_item.setData_forType(&NSData::with_bytes(&flavor.data), kind);
+            }
+
+            let image = item
+                .png
+                .as_ref()
+                .and_then(|png| NSImage::initWithData(NSImage::alloc(), &NSData::with_bytes(png)));
+            let size = image.as_ref().map_or(NSSize::new(32.0, 32.0), |image| {
+                let size = image.size();
+                let scale = (MAX_IMAGE_SIZE / size.width.max(size.height)).min(1.0);
+                NSSize::new(size.width * scale, size.height * scale)
+            });
+            let point = view.convertPoint_fromView(event.locationInWindow(), None);
+            let origin = NSPoint::new(point.x - size.width / 2.0, point.y - size.height / 2.0);
+            let dragging_item = NSDraggingItem::initWithPasteboardWriter(
+                NSDraggingItem::alloc(),
+                ProtocolObject::from_ref(&*pasteboard_item),
+            );
+            let contents: Option<&AnyObject> = image.as_deref().map(AsRef::as_ref);
+            dragging_item.setDraggingFrame_contents(NSRect::new(origin, size), contents);
+
+            view.beginDraggingSessionWithItems_event_source(
+                &NSArray::from_vec(vec![dragging_item]),
+                &event,
+                ProtocolObject::from_ref(&*source),
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+//! Selection commands for menu items and shortcuts, run with [`crate::Verso::select_all`] and
+//! [`crate::Verso::clear_selection`].
+//!
+//! Servo only handles `Ctrl+A` in editable elements, so the commands run as scripts in the page
+//! instead of key presses. They act on the focused text field or editable element, and on the
+//! text of the whole document otherwise. Text selected with [`crate::Verso::select_all`] is copied
+//! to the primary selection on Linux, like after selecting with the mouse.
+
+/// Script selecting the content of the focused editable element, or the whole document.
+pub(crate) const SELECT_ALL: &str = "
+const element = document.activeElement;
+if (element instanceof HTMLInputElement || element instanceof HTMLTextAreaElement) {
+    element.select();
+    return;
+}
+const root = element && element.isContentEditable
+    ? element
+    : document.body || document.documentElement;
+if (root) getSelection().selectAllChildren(root);";
+
+/// Script clearing the selection, leaving the caret at its end in text fields.
+pub(crate) const CLEAR_SELECTION: &str = "
+const element = document.activeElement;
+if (element instanceof HTMLInputElement || element instanceof HTMLTextAreaElement) {
+    if (element.selectionEnd != null) {
+        element.setSelectionRange(element.selectionEnd, element.selectionEnd);
+    }
+} else {
+    getSelection().removeAllRanges();
+}";
@@ -0,0 +1,50 @@
+//! Text encoding override for legacy pages without a charset declaration.
+//!
+//! Servo can't change the encoding used to decode a response, so
+//! [`crate::Verso::override_encoding`] fetches the document again and decodes it with the chosen
+//! encoding in the page, then replaces the document with the result. Only the top-level document
+//! is re-decoded, and the override is dropped on the next navigation.
+
+pub use encoding_rs::Encoding;
+
+/// Script fetching the current document and rewriting it decoded with the encoding.
+pub(crate) fn override_script(encoding: &'static Encoding) -> String {
+    format!(
+        "fetch(location.href, {{ cache: 'force-cache' }})
+            .then((response) => response.arrayBuffer())
+            .then((buffer) => {{
+                const text = new TextDecoder('{label}').decode(buffer);
+                document.open();
+                document.write(text);
+                document.close();
+            }});",
+        label = encoding.name(),
+    )
+}
+
+/// Suggest the legacy encoding commonly used by pages in the language, given as a BCP 47 tag like
+/// the `lang` attribute of the document. This is a hint for users choosing an override, not a
+/// detection of the page's actual encoding.
+pub fn encoding_hint_for_language(language: &str) -> Option<&'static Encoding> {
+    let language = language.to_ascii_lowercase();
+    let mut subtags = language.split(['-', '_']);
+    let primary = subtags.next().filter(|primary| !primary.is_empty())?;
+    let region = subtags.find(|subtag| subtag.len() == 2 || subtag.len() == 4);
+    let encoding = match (primary, region) {
+        ("ja", _) => encoding_rs::SHIFT_JIS,
+        ("zh", Some("tw" | "hk" | "mo" | "hant")) => encoding_rs::BIG5,
+        ("zh", _) => encoding_rs::GBK,
+        ("ko", _) => encoding_rs::EUC_KR,
+        ("ru" | "uk" | "be" | "bg" | "sr" | "mk", _) => encoding_rs::WINDOWS_1251,
+        ("el", _) => encoding_rs::WINDOWS_1253,
+        ("tr", _) => encoding_rs::WINDOWS_1254,
+        ("he", _) => encoding_rs::WINDOWS_1255,
+        ("ar" | "fa" | "ur", _) => encoding_rs::WINDOWS_1256,
+        ("lt" | "lv" | "et", _) => encoding_rs::WINDOWS_1257,
+        ("vi", _) => encoding_rs::WINDOWS_1258,
+        ("th", _) => encoding_rs::WINDOWS_874,
+        ("cs" | "hu" | "pl" | "sk" | "sl" | "hr" | "ro", _) => encoding_rs::WINDOWS_1250,
+        _ => encoding_rs::WINDOWS_1252,
+    };
+    Some(encoding)
+}
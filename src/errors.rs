@@ -11,4 +11,37 @@ pub enum Error {
     /// A general error that may occur while running the Winit event loop.
     #[error(transparent)]
     EventLoopError(#[from] winit::error::EventLoopError),
+    /// The window handle or the display handle of a window isn't available.
+    #[error(transparent)]
+    HandleError(#[from] raw_window_handle::HandleError),
+    /// Surfman failed to create the rendering context of a window.
+    #[error("failed to create the rendering context: {0:?}")]
+    SurfmanError(surfman::Error),
+    /// An I/O error while reading or writing files.
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// An I/O error on the file at the path, like a missing file given on the command line.
+    #[error("{}: {1}", .0.display())]
+    FileError(std::path::PathBuf, #[source] std::io::Error),
+    /// The path can't be converted to a `file` URL, like a relative path.
+    #[error("{} can't be converted to a file URL", .0.display())]
+    InvalidPath(std::path::PathBuf),
+    /// The URL can't be parsed.
+    #[error("invalid URL {0}: {1}")]
+    InvalidUrl(String, url::ParseError),
+    /// The configuration has invalid or incompatible options.
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(String),
+    /// The operation isn't supported on this platform.
+    #[error("{0} isn't supported on this platform")]
+    Unsupported(&'static str),
+    /// The feature is disabled by a policy of the administrator. See [`crate::policies`].
+    #[error("{0} is disabled by policy")]
+    DisabledByPolicy(&'static str),
+    /// The storage partition of the configuration can't be used. See [`crate::partitions`].
+    #[error("failed to use the storage partition: {0}")]
+    PartitionError(#[from] crate::partitions::PartitionError),
+    /// The content process failed before running its pages. See [`crate::sandbox`].
+    #[error("content process failed: {0}")]
+    ContentProcessError(#[from] crate::sandbox::ContentProcessError),
 }
@@ -0,0 +1,44 @@
+//! Links with schemes handled by other applications, like `mailto:` and `tel:`.
+//!
+//! Navigations to URLs of other schemes than the ones Servo loads as pages are cancelled, and the
+//! URL is passed to [`crate::VersoBuilder::on_external_url`], or opened with the handler of the
+//! operating system by default. Schemes in [`crate::config::Config::blocked_schemes`] are
+//! ignored instead.
+
+use std::process::Command;
+
+use servo_url::ServoUrl;
+
+/// Schemes Servo loads as pages.
+const PAGE_SCHEMES: [&str; 7] = ["http", "https", "file", "data", "about", "blob", "resource"];
+
+/// Return true if the URL isn't loaded as a page, and should be opened by another application.
+pub fn is_external_url(url: &ServoUrl) -> bool {
+    !PAGE_SCHEMES.contains(&url.scheme())
+}
+
+/// Open the URL with the handler of the operating system.
+pub fn open_with_os(url: &ServoUrl) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("rundll32");
+        command.args(["url.dll,FileProtocolHandler", url.as_str()]);
+        command
+    };
+    #[cfg(apple)]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg(url.as_str());
+        command
+    };
+    #[cfg(not(any(target_os = "windows", apple)))]
+    let mut command = {
+        let mut command = Command::new("xdg-open");
+        command.arg(url.as_str());
+        command
+    };
+    // The handler may keep running like a mail client, so wait for it on another thread.
+    let mut child = command.spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
@@ -0,0 +1,162 @@
+//! Web features which can be disabled per origin with [`crate::Verso::set_feature_policy`].
+//!
+//! Features backed by a permission are denied where Servo asks the embedder: permission prompts
+//! for the camera, the microphone and Bluetooth are answered with a denial, and so is the choice
+//! of a Bluetooth device. Servo sends these without the origin of the frame asking, so the policy
+//! of the page in the web view applies.
+//!
+//! Servo's preferences are global and it asks for no permission before the other features, so
+//! Verso also replaces their APIs in each page once the head of the document is parsed. Denied
+//! features answer the way browsers do when the user refuses them or the feature isn't available:
+//!
+//! | Feature | Page sees |
+//! | --- | --- |
+//! | [`Feature::Camera`] | `getUserMedia()` with video rejects with `NotAllowedError`, the `camera` permission is denied |
+//! | [`Feature::Microphone`] | `getUserMedia()` with audio rejects with `NotAllowedError`, the `microphone` permission is denied |
+//! | [`Feature::WebGl`] | `canvas.getContext("webgl")` and `"webgl2"` return `null` |
+//! | [`Feature::WebGpu`] | `navigator.gpu` is `undefined` |
+//! | [`Feature::WebAssembly`] | `WebAssembly` is not defined |
+//! | [`Feature::Bluetooth`] | `navigator.bluetooth.requestDevice()` rejects with `SecurityError`, the `bluetooth` permission is denied and no device is chosen |
+//!
+//! Geolocation isn't listed because Servo doesn't implement it. Scripts in the head of the
+//! document run before the replaced APIs are applied, so only the permission denials hold for
+//! them.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use embedder_traits::PermissionName;
+use serde::Serialize;
+use servo_url::ServoUrl;
+
+/// Origin key of the policy applied to every origin.
+pub const ALL_ORIGINS: &str = "*";
+
+/// A web feature which can be denied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Video capture with `getUserMedia()`.
+    Camera,
+    /// Audio capture with `getUserMedia()`.
+    Microphone,
+    /// WebGL and WebGL 2 canvas contexts.
+    WebGl,
+    /// The WebGPU API.
+    WebGpu,
+    /// The WebAssembly API.
+    WebAssembly,
+    /// The Web Bluetooth API.
+    Bluetooth,
+}
+
+impl Feature {
+    /// The feature Servo asks the permission for, if any.
+    fn from_permission(name: PermissionName) -> Option<Self> {
+        match name {
+            PermissionName::Camera => Some(Self::Camera),
+            PermissionName::Microphone => Some(Self::Microphone),
+            PermissionName::Bluetooth => Some(Self::Bluetooth),
+            _ => None,
+        }
+    }
+}
+
+/// Set of features denied to an origin.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct FeaturePolicy {
+    denied: BTreeSet<Feature>,
+}
+
+impl FeaturePolicy {
+    /// Deny the feature.
+    pub fn deny(mut self, feature: Feature) -> Self {
+        self.denied.insert(feature);
+        self
+    }
+
+    /// Return true if the feature is denied.
+    pub fn denies(&self, feature: Feature) -> bool {
+        self.denied.contains(&feature)
+    }
+}
+
+/// Serialize the origin of the URL like `https://example.com/page`, or keep [`ALL_ORIGINS`].
+pub(crate) fn normalize_origin(origin: &str) -> Result<String, url::ParseError> {
+    if origin == ALL_ORIGINS {
+        return Ok(origin.to_string());
+    }
+    Ok(ServoUrl::parse(origin)?.origin().ascii_serialization())
+}
+
+/// Return true if `policies`, keyed like for [`feature_policy_script`], deny the feature to the
+/// page of the URL.
+pub(crate) fn denies_feature(
+    policies: &BTreeMap<String, FeaturePolicy>,
+    url: Option<&ServoUrl>,
+    feature: Feature,
+) -> bool {
+    let origin = url.map(|url| url.origin().ascii_serialization());
+    [Some(ALL_ORIGINS.to_string()), origin]
+        .into_iter()
+        .flatten()
+        .filter_map(|origin| policies.get(&origin))
+        .any(|policy| policy.denies(feature))
+}
+
+/// Return true if `policies` deny the feature of the permission to the page of the URL.
+pub(crate) fn denies_permission(
+    policies: &BTreeMap<String, FeaturePolicy>,
+    url: Option<&ServoUrl>,
+    name: PermissionName,
+) -> bool {
+    Feature::from_permission(name).is_some_and(|feature| denies_feature(policies, url, feature))
+}
+
+/// Script disabling the features denied to the page's origin by `policies`, which are keyed by
+/// serialized origin or [`ALL_ORIGINS`].
+pub(crate) fn feature_policy_script(policies: &BTreeMap<String, FeaturePolicy>) -> Option<String> {
+    if policies.values().all(|policy| policy.denied.is_empty()) {
+        return None;
+    }
+    let policies: BTreeMap<&String, &BTreeSet<Feature>> = policies
+        .iter()
+        .map(|(origin, policy)| (origin, &policy.denied))
+        .collect();
+    let policies = serde_json::to_string(&policies).ok()?;
+    Some(format!(
+        "const policies = {policies};
+        const denied = new Set([
+            ...(policies['{ALL_ORIGINS}'] || []),
+            ...(policies[location.origin] || []),
+        ]);
+        if (!denied.size) return;
+        const mediaDevices = navigator.mediaDevices;
+        if (mediaDevices && (denied.has('camera') || denied.has('microphone'))) {{
+            const getUserMedia = mediaDevices.getUserMedia.bind(mediaDevices);
+            mediaDevices.getUserMedia = (constraints = {{}}) => {{
+                if ((constraints.video && denied.has('camera')) ||
+                    (constraints.audio && denied.has('microphone'))) {{
+                    return Promise.reject(new DOMException('Permission denied', 'NotAllowedError'));
+                }}
+                return getUserMedia(constraints);
+            }};
+        }}
+        if (denied.has('web_gl')) {{
+            const getContext = HTMLCanvasElement.prototype.getContext;
+            HTMLCanvasElement.prototype.getContext = function (type, ...args) {{
+                if (/^(experimental-)?webgl2?$/.test(type)) return null;
+                return getContext.call(this, type, ...args);
+            }};
+        }}
+        if (denied.has('web_gpu')) {{
+            Object.defineProperty(Navigator.prototype, 'gpu', {{ get: () => undefined }});
+        }}
+        if (denied.has('web_assembly')) {{
+            delete globalThis.WebAssembly;
+        }}
+        if (denied.has('bluetooth') && navigator.bluetooth) {{
+            navigator.bluetooth.requestDevice = () =>
+                Promise.reject(new DOMException('Bluetooth is not allowed', 'SecurityError'));
+        }}"
+    ))
+}
@@ -0,0 +1,104 @@
+//! Writing mode and direction of pages, which decide where scrolling goes.
+//!
+//! Verso reads the writing mode and the direction of the page once it finished loading, from the
+//! body which propagates them to the viewport, and adjusts the scrolling it does itself:
+//!
+//! - In vertical writing modes, the wheel scrolls the block axis, which is horizontal, and
+//!   `Shift` with the wheel scrolls the inline axis.
+//! - In horizontal writing modes, `Shift` with the wheel scrolls horizontally, towards the end of
+//!   the lines, which is leftwards in right-to-left pages.
+//! - In vertical writing modes, `Home` and `End` scroll to the logical start and end of the block
+//!   axis. Horizontal pages keep the scrolling of Servo.
+
+/// Script returning the writing mode and the direction of the page as JSON.
+pub(crate) const GET_PAGE_FLOW: &str = "
+const element = document.body ?? document.documentElement;
+const style = getComputedStyle(element);
+return JSON.stringify({ writingMode: style.writingMode, direction: style.direction });";
+
+/// Scroll delta far beyond any page, scrolling to its edge.
+const EDGE_DELTA: f64 = 1e7;
+
+/// Writing mode of a page, in which direction lines are stacked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WritingMode {
+    /// Horizontal lines stacked from top to bottom.
+    #[default]
+    HorizontalTb,
+    /// Vertical lines stacked from right to left, like Japanese and Chinese.
+    VerticalRl,
+    /// Vertical lines stacked from left to right, like Mongolian.
+    VerticalLr,
+}
+
+/// Writing mode and direction of a page.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageFlow {
+    /// Writing mode of the page.
+    pub writing_mode: WritingMode,
+    /// Whether the inline direction is right to left, like Hebrew and Arabic.
+    pub rtl: bool,
+}
+
+impl PageFlow {
+    /// Parse the JSON returned by [`GET_PAGE_FLOW`]. Unknown values are read as the default,
+    /// horizontal left-to-right flow.
+    pub(crate) fn from_json(json: &str) -> Self {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+            return Self::default();
+        };
+        let writing_mode = match value["writingMode"].as_str() {
+            // `sideways-*` and the SVG values are the same flows.
+            Some("vertical-rl" | "sideways-rl" | "tb-rl" | "tb") => WritingMode::VerticalRl,
+            Some("vertical-lr" | "sideways-lr" | "tb-lr") => WritingMode::VerticalLr,
+            _ => WritingMode::HorizontalTb,
+        };
+        Self {
+            writing_mode,
+            rtl: value["direction"].as_str() == Some("rtl"),
+        }
+    }
+
+    /// Return true if lines are vertical.
+    pub fn is_vertical(self) -> bool {
+        self.writing_mode != WritingMode::HorizontalTb
+    }
+
+    /// Map a vertical wheel delta to the axis it scrolls in this flow, with `Shift` held or not.
+    /// Deltas follow Winit's convention, where positive values reveal content to the left and top.
+    /// Deltas with a horizontal part, like from touchpads, are kept.
+    pub(crate) fn map_wheel(self, x: f64, y: f64, shift: bool) -> (f64, f64) {
+        if x != 0.0 {
+            return (x, y);
+        }
+        // Whether scrolling forward goes leftwards on the horizontal axis.
+        let leftwards = match (self.writing_mode, shift) {
+            (WritingMode::HorizontalTb, true) => self.rtl,
+            (WritingMode::VerticalRl, false) => true,
+            (WritingMode::VerticalLr, false) => false,
+            (WritingMode::HorizontalTb, false) | (_, true) => return (x, y),
+        };
+        if leftwards {
+            (-y, 0.0)
+        } else {
+            (y, 0.0)
+        }
+    }
+
+    /// Scroll delta of `Home`, or of `End` if `end` is true, in Winit's convention, if it's
+    /// handled by Verso in this flow.
+    pub(crate) fn edge_delta(self, end: bool) -> Option<(f64, f64)> {
+        // The start of the block axis is on the right in vertical-rl, and on the left in
+        // vertical-lr.
+        let to_right = match self.writing_mode {
+            WritingMode::HorizontalTb => return None,
+            WritingMode::VerticalRl => !end,
+            WritingMode::VerticalLr => end,
+        };
+        Some(if to_right {
+            (-EDGE_DELTA, 0.0)
+        } else {
+            (EDGE_DELTA, 0.0)
+        })
+    }
+}
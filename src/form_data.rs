@@ -0,0 +1,146 @@
+//! Form submissions reconstructed by the embedder, like to restore a page which was the result of
+//! a POST request from session state, submitted with [`crate::Verso::submit_form_data`].
+//!
+//! Servo doesn't let embedders load a URL with a request body, so the current page of the web view
+//! submits a hidden form with the entries, and Servo encodes the body like for any other form:
+//! `application/x-www-form-urlencoded` or `multipart/form-data`, in UTF-8. File entries are sent as
+//! file parts of multipart bodies, and only their file names are sent in URL-encoded bodies, like
+//! with HTML forms. Files are attached with `DataTransfer`, so forms with files fail with a logged
+//! error on pages where Servo doesn't support it.
+//!
+//! Submitting a form again can repeat its action, like a purchase, so embedders should ask the
+//! user before restoring a POST page.
+
+use serde::Serialize;
+use servo_url::ServoUrl;
+
+/// Encoding of the body of a form submission.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FormEncoding {
+    /// `application/x-www-form-urlencoded`.
+    #[default]
+    UrlEncoded,
+    /// `multipart/form-data`, needed to send the content of files.
+    Multipart,
+}
+
+impl FormEncoding {
+    fn enctype(self) -> &'static str {
+        match self {
+            Self::UrlEncoded => "application/x-www-form-urlencoded",
+            Self::Multipart => "multipart/form-data",
+        }
+    }
+}
+
+/// Value of a form entry.
+#[derive(Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum FormValue {
+    /// Text, like the value of an input field.
+    Text(String),
+    /// File, like one selected in a file input.
+    File {
+        /// Name of the file.
+        filename: String,
+        /// MIME type of the file, like `image/png`.
+        content_type: String,
+        /// Content of the file.
+        data: Vec<u8>,
+    },
+}
+
+impl std::fmt::Debug for FormValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(text) => f.debug_tuple("Text").field(text).finish(),
+            Self::File {
+                filename,
+                content_type,
+                data,
+            } => f
+                .debug_struct("File")
+                .field("filename", filename)
+                .field("content_type", content_type)
+                .field("len", &data.len())
+                .finish(),
+        }
+    }
+}
+
+/// Entries of a form submission, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FormData {
+    /// Encoding of the body.
+    pub encoding: FormEncoding,
+    /// Names and values of the entries. Names can repeat, like for checkboxes.
+    pub entries: Vec<(String, FormValue)>,
+}
+
+impl FormData {
+    /// Create an empty form with the encoding.
+    pub fn new(encoding: FormEncoding) -> Self {
+        Self {
+            encoding,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add a text entry.
+    pub fn text(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.entries
+            .push((name.into(), FormValue::Text(value.into())));
+        self
+    }
+
+    /// Add a file entry.
+    pub fn file(
+        mut self,
+        name: impl Into<String>,
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        data: Vec<u8>,
+    ) -> Self {
+        self.entries.push((
+            name.into(),
+            FormValue::File {
+                filename: filename.into(),
+                content_type: content_type.into(),
+                data,
+            },
+        ));
+        self
+    }
+}
+
+/// Script submitting the form to the URL from the current page.
+pub(crate) fn submit_script(url: &ServoUrl, form: &FormData) -> Option<String> {
+    let url = serde_json::to_string(url.as_str()).ok()?;
+    let entries = serde_json::to_string(&form.entries).ok()?;
+    let enctype = form.encoding.enctype();
+    Some(format!(
+        "const form = document.createElement('form');
+        form.method = 'post';
+        form.action = {url};
+        form.enctype = '{enctype}';
+        form.acceptCharset = 'utf-8';
+        form.style.display = 'none';
+        for (const [name, value] of {entries}) {{
+            const input = document.createElement('input');
+            input.name = name;
+            if (typeof value === 'string') {{
+                input.type = 'hidden';
+                input.value = value;
+            }} else {{
+                const transfer = new DataTransfer();
+                const data = new Uint8Array(value.data);
+                transfer.items.add(new File([data], value.filename, {{ type: value.content_type }}));
+                input.type = 'file';
+                input.files = transfer.files;
+            }}
+            form.appendChild(input);
+        }}
+        (document.body || document.documentElement).appendChild(form);
+        HTMLFormElement.prototype.submit.call(form);"
+    ))
+}
@@ -0,0 +1,160 @@
+//! Closures called at points of the lifecycle of web views, registered with
+//! [`crate::VersoBuilder`].
+//!
+//! Hooks are called on the event loop thread at the end of [`crate::Verso::handle_servo_messages`],
+//! after the embedder messages of that call are handled, so [`crate::Verso::load_status`]
+//! already reflects them. Hooks of several messages run in the order the messages arrived:
+//!
+//! 1. [`on_ready`](crate::VersoBuilder::on_ready) once the web view of the first window is opened.
+//! 2. [`before_navigate`](crate::VersoBuilder::before_navigate) when a page asks to navigate.
+//!    The navigation waits for its answer, and the load status becomes
+//!    [`crate::webview::LoadStatus::NavigationStarted`] only if it's allowed.
+//! 3. [`after_load`](crate::VersoBuilder::after_load) once the page finished loading, or failed
+//!    with a network error. Loads which are retried with [`crate::config::RetryPolicy`] report
+//!    only their last attempt.
+//!
+//! [`on_external_url`](crate::VersoBuilder::on_external_url) is called instead of
+//! `before_navigate` for URLs opened by other applications. See [`crate::external`].
+//! [`on_print`](crate::VersoBuilder::on_print) is called when a page is printed. See
+//! [`crate::print`]. [`on_surface_reset`](crate::VersoBuilder::on_surface_reset) is called
+//! after Verso recreated the rendering surface for a lost GL context or surface.
+//! [`on_discard`](crate::VersoBuilder::on_discard) is called when the web view of a window is
+//! discarded or restored. See [`crate::discard`].
+//! [`on_login_form`](crate::VersoBuilder::on_login_form) and
+//! [`on_credentials_submitted`](crate::VersoBuilder::on_credentials_submitted) are called for
+//! login forms. See [`crate::passwords`].
+//! [`on_auto_reload`](crate::VersoBuilder::on_auto_reload) is called when a page is reloaded by
+//! auto-reload. See [`crate::auto_reload`].
+//! [`on_tab_updated`](crate::VersoBuilder::on_tab_updated) is called when the title or the
+//! favicon of a page changes. See [`crate::tabs`].
+//! [`on_resume`](crate::VersoBuilder::on_resume) is called while a web view resumes from a
+//! snapshot. See [`crate::suspend`].
+//! [`on_download`](crate::VersoBuilder::on_download) is called when a page downloads a generated
+//! file. See [`crate::downloads`].
+//! [`on_zoom_changed`](crate::VersoBuilder::on_zoom_changed) is called when the page zoom,
+//! the pinch zoom or the fit mode changes. See [`crate::zoom`].
+//! [`on_content_size_changed`](crate::VersoBuilder::on_content_size_changed) is called when the
+//! content size of a page changes, and
+//! [`on_auto_resize_stopped`](crate::VersoBuilder::on_auto_resize_stopped) when a window stops
+//! fitting its content. See [`crate::auto_resize`].
+//!
+//! Hooks may call methods of Verso which send requests to Servo, like
+//! [`crate::Verso::load_url`], but must not drive the event loop by calling
+//! [`crate::Verso::handle_servo_messages`] or [`crate::Verso::handle_winit_window_event`].
+//! Those calls are ignored with an error logged.
+
+use std::fmt;
+
+use base::id::{PipelineId, WebViewId};
+use servo_url::ServoUrl;
+use winit::window::WindowId;
+
+use crate::{
+    auto_resize::ContentSize,
+    compositor::SurfaceResetCause,
+    downloads::Download,
+    passwords::{LoginForm, SubmittedCredentials},
+    print::{PrintRequest, PrintResult},
+    suspend::ResumeProgress,
+    tabs::TabInfo,
+    zoom::ZoomChange,
+    Verso,
+};
+
+/// Result of a page load reported to [`crate::VersoBuilder::after_load`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadResult {
+    /// The page finished loading.
+    Loaded,
+    /// The page failed to load with the network error.
+    Failed(String),
+}
+
+/// Closures registered with [`crate::VersoBuilder`].
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pub on_ready: Option<Box<dyn FnOnce(&mut Verso)>>,
+    pub before_navigate: Option<Box<dyn FnMut(&ServoUrl) -> bool>>,
+    pub after_load: Option<Box<dyn FnMut(&ServoUrl, LoadResult)>>,
+    pub on_external_url: Option<Box<dyn FnMut(&ServoUrl)>>,
+    pub on_print: Option<Box<dyn FnMut(&PrintRequest) -> PrintResult>>,
+    pub on_surface_reset: Option<Box<dyn FnMut(&SurfaceResetCause)>>,
+    pub on_discard: Option<Box<dyn FnMut(WindowId, bool)>>,
+    pub on_login_form: Option<Box<dyn FnMut(&LoginForm)>>,
+    pub on_credentials_submitted: Option<Box<dyn FnMut(&SubmittedCredentials)>>,
+    pub on_auto_reload: Option<Box<dyn FnMut(WindowId, u32)>>,
+    pub on_tab_updated: Option<Box<dyn FnMut(WindowId, &TabInfo)>>,
+    pub on_resume: Option<Box<dyn FnMut(WebViewId, ResumeProgress)>>,
+    pub on_zoom_changed: Option<Box<dyn FnMut(WindowId, &ZoomChange)>>,
+    pub on_download: Option<Box<dyn FnMut(&Download) -> bool>>,
+    pub on_content_size_changed: Option<Box<dyn FnMut(WindowId, ContentSize)>>,
+    pub on_auto_resize_stopped: Option<Box<dyn FnMut(WindowId)>>,
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("on_ready", &self.on_ready.is_some())
+            .field("before_navigate", &self.before_navigate.is_some())
+            .field("after_load", &self.after_load.is_some())
+            .field("on_external_url", &self.on_external_url.is_some())
+            .field("on_print", &self.on_print.is_some())
+            .field("on_surface_reset", &self.on_surface_reset.is_some())
+            .field("on_discard", &self.on_discard.is_some())
+            .field("on_login_form", &self.on_login_form.is_some())
+            .field(
+                "on_credentials_submitted",
+                &self.on_credentials_submitted.is_some(),
+            )
+            .field("on_auto_reload", &self.on_auto_reload.is_some())
+            .field("on_tab_updated", &self.on_tab_updated.is_some())
+            .field("on_resume", &self.on_resume.is_some())
+            .field("on_zoom_changed", &self.on_zoom_changed.is_some())
+            .field("on_download", &self.on_download.is_some())
+            .field(
+                "on_content_size_changed",
+                &self.on_content_size_changed.is_some(),
+            )
+            .field(
+                "on_auto_resize_stopped",
+                &self.on_auto_resize_stopped.is_some(),
+            )
+            .finish()
+    }
+}
+
+/// Lifecycle event of a window, queued until Verso calls the hooks.
+#[derive(Debug)]
+pub enum LifecycleEvent {
+    /// The web view was opened.
+    Ready,
+    /// The page of the web view asks to navigate to the URL.
+    Navigate(WebViewId, PipelineId, ServoUrl),
+    /// The page of the URL finished loading.
+    Loaded(ServoUrl, LoadResult),
+    /// The URL should be opened by another application. See [`crate::external`].
+    OpenExternal(ServoUrl),
+    /// The page of the web view should be printed. See [`crate::print`].
+    Print(WebViewId),
+    /// The rendering surface was recreated after the GL context or the surface was lost.
+    SurfaceReset(SurfaceResetCause),
+    /// The web view of the window was discarded, or restored if false. See [`crate::discard`].
+    Discarded(WindowId, bool),
+    /// The page shows a login form. See [`crate::passwords`].
+    LoginForm(LoginForm),
+    /// The page submitted a login form. See [`crate::passwords`].
+    CredentialsSubmitted(SubmittedCredentials),
+    /// The page of the window was reloaded by auto-reload, for the given count. See
+    /// [`crate::auto_reload`].
+    AutoReloadFired(WindowId, u32),
+    /// The title or the favicon of the page of the window changed. See [`crate::tabs`].
+    TabUpdated(WindowId, TabInfo),
+    /// The web view made progress resuming from a snapshot. See [`crate::suspend`].
+    Resumed(WebViewId, ResumeProgress),
+    /// The page zoom or the fit mode of the window changed. See [`crate::zoom`].
+    ZoomChanged(WindowId, ZoomChange),
+    /// The page downloads a generated file. See [`crate::downloads`].
+    Download(Download),
+    /// The content size of the page of the window changed. See [`crate::auto_resize`].
+    ContentSizeChanged(WindowId, ContentSize),
+}
@@ -0,0 +1,207 @@
+//! Conversion of Winit's pointer inputs to the events Verso passes to the compositor.
+//!
+//! [`convert`] doesn't access the window or the compositor: everything it depends on is either
+//! kept in [`InputState`] across events or passed in [`InputOptions`], so the same sequence of
+//! inputs always results in the same events.
+
+use euclid::Point2D;
+use script_traits::{MouseButton, TouchEventType, WheelDelta, WheelMode};
+use smallvec::{smallvec, SmallVec};
+use webrender_api::{
+    units::{DeviceIntPoint, DevicePoint, LayoutVector2D},
+    ScrollLocation,
+};
+use winit::{
+    dpi::PhysicalPosition,
+    event::{ElementState, MouseScrollDelta, TouchPhase, WindowEvent},
+};
+
+use crate::{compositor::MouseWindowEvent, config::AxisLockMode, flow::PageFlow};
+
+// FIXME: Pixels per line, should be configurable (from browser setting?) and vary by zoom level.
+const LINE_HEIGHT: f32 = 38.0;
+
+/// State of the pointer kept across inputs.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InputState {
+    /// Physical position of the cursor in the window.
+    pub cursor: PhysicalPosition<f64>,
+    /// Fractions of device pixels of scroll deltas not scrolled yet.
+    scroll_remainder: ScrollRemainder,
+}
+
+/// Settings of the window and the compositor the conversion depends on.
+#[derive(Clone, Copy, Debug)]
+pub struct InputOptions {
+    /// Scale factor of the window.
+    pub scale_factor: f64,
+    /// How scroll deltas with both axes are handled.
+    pub wheel_axis_lock: AxisLockMode,
+    /// Snap scroll deltas to whole device pixels.
+    pub snap_scroll: bool,
+    /// Whether `Shift` is held.
+    pub shift: bool,
+    /// Writing mode and direction of the page, deciding the axis the wheel scrolls.
+    pub flow: PageFlow,
+}
+
+/// Event passed to the compositor.
+#[derive(Clone)]
+pub enum InputEvent {
+    /// The cursor moved to the point.
+    MouseMove(DevicePoint),
+    /// A mouse button was pressed, released or clicked.
+    Mouse(MouseWindowEvent),
+    /// The wheel turned at the point, dispatched as a `wheel` event to the page.
+    Wheel(WheelDelta, DevicePoint),
+    /// The page scrolls at the point.
+    Scroll(ScrollLocation, DeviceIntPoint, TouchEventType),
+}
+
+/// Events resulting from one input. An input results in two events at most, so they're kept
+/// inline.
+pub type InputEvents = SmallVec<[InputEvent; 2]>;
+
+/// Convert a pointer input of the window to the events passed to the compositor, in order.
+/// Other inputs result in no events.
+pub(crate) fn convert(
+    event: &WindowEvent,
+    state: &mut InputState,
+    options: &InputOptions,
+) -> InputEvents {
+    match event {
+        WindowEvent::CursorMoved { position, .. } => {
+            state.cursor = *position;
+            smallvec![InputEvent::MouseMove(DevicePoint::new(
+                position.x as f32,
+                position.y as f32,
+            ))]
+        }
+        WindowEvent::MouseInput {
+            state: element_state,
+            button,
+            ..
+        } => {
+            let button = match button {
+                winit::event::MouseButton::Left => MouseButton::Left,
+                winit::event::MouseButton::Right => MouseButton::Right,
+                winit::event::MouseButton::Middle => MouseButton::Middle,
+                _ => {
+                    log::trace!("Verso Window isn't supporting this mouse button yet: {button:?}");
+                    return SmallVec::new();
+                }
+            };
+            let position = Point2D::new(state.cursor.x as f32, state.cursor.y as f32);
+            match element_state {
+                ElementState::Pressed => {
+                    smallvec![InputEvent::Mouse(MouseWindowEvent::MouseDown(
+                        button, position,
+                    ))]
+                }
+                // Winit doesn't send click events, so a click follows every mouse up.
+                ElementState::Released => smallvec![
+                    InputEvent::Mouse(MouseWindowEvent::MouseUp(button, position)),
+                    InputEvent::Mouse(MouseWindowEvent::Click(button, position)),
+                ],
+            }
+        }
+        WindowEvent::MouseWheel { delta, phase, .. } => {
+            convert_wheel(*delta, *phase, state, options)
+        }
+        _ => SmallVec::new(),
+    }
+}
+
+/// [`convert`] for tests, benchmarks and fuzz targets, which are built outside of the crate.
+#[doc(hidden)]
+pub fn convert_input(
+    event: &WindowEvent,
+    state: &mut InputState,
+    options: &InputOptions,
+) -> InputEvents {
+    convert(event, state, options)
+}
+
+fn convert_wheel(
+    delta: MouseScrollDelta,
+    phase: TouchPhase,
+    state: &mut InputState,
+    options: &InputOptions,
+) -> InputEvents {
+    let (mut x, mut y, mode) = match delta {
+        MouseScrollDelta::LineDelta(x, y) => {
+            (x as f64, (y * LINE_HEIGHT) as f64, WheelMode::DeltaLine)
+        }
+        MouseScrollDelta::PixelDelta(position) => {
+            let position = position.to_logical::<f64>(options.scale_factor);
+            (position.x, position.y, WheelMode::DeltaPixel)
+        }
+    };
+    let cursor = DevicePoint::new(state.cursor.x as f32, state.cursor.y as f32);
+    let wheel = InputEvent::Wheel(WheelDelta { x, y, z: 0.0, mode }, cursor);
+
+    (x, y) = options.flow.map_wheel(x, y, options.shift);
+
+    if options.wheel_axis_lock.locks(x, y) {
+        // Do one axis at a time.
+        if y.abs() >= x.abs() {
+            x = 0.0;
+        } else {
+            y = 0.0;
+        }
+    }
+
+    if phase == TouchPhase::Started || !options.snap_scroll {
+        state.scroll_remainder = ScrollRemainder::default();
+    }
+    if options.snap_scroll {
+        (x, y) = state.scroll_remainder.snap(x, y);
+    }
+
+    let phase = match phase {
+        TouchPhase::Started => TouchEventType::Down,
+        TouchPhase::Moved => TouchEventType::Move,
+        TouchPhase::Ended => TouchEventType::Up,
+        TouchPhase::Cancelled => TouchEventType::Cancel,
+    };
+    let scroll = InputEvent::Scroll(
+        ScrollLocation::Delta(LayoutVector2D::new(x as f32, y as f32)),
+        DeviceIntPoint::new(state.cursor.x as i32, state.cursor.y as i32),
+        phase,
+    );
+    smallvec![wheel, scroll]
+}
+
+/// Distance to a whole pixel under which a snapped scroll total is taken as that pixel.
+const SNAP_EPSILON: f64 = 1e-6;
+
+/// Fractions of device pixels left over by [`ScrollRemainder::snap`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ScrollRemainder {
+    x: f64,
+    y: f64,
+}
+
+impl ScrollRemainder {
+    /// Add the remainder to the scroll delta in device pixels and return the whole pixels of it,
+    /// keeping the fraction for the next delta. Fractional scroll offsets make text blur between
+    /// frames during slow scrolls. The remainder of an axis is dropped when it changes direction.
+    fn snap(&mut self, x: f64, y: f64) -> (f64, f64) {
+        (snap_axis(&mut self.x, x), snap_axis(&mut self.y, y))
+    }
+}
+
+fn snap_axis(remainder: &mut f64, delta: f64) -> f64 {
+    if *remainder * delta < 0.0 {
+        *remainder = 0.0;
+    }
+    let total = *remainder + delta;
+    // Deltas like 0.1 don't add up to whole pixels exactly, so totals this close count as whole.
+    let whole = if (total - total.round()).abs() < SNAP_EPSILON {
+        total.round()
+    } else {
+        total.trunc()
+    };
+    *remainder = total - whole;
+    whole
+}
@@ -1,6 +1,6 @@
 use keyboard_types::{Code, Key, KeyState, KeyboardEvent, Location, Modifiers};
 use log::info;
-use winit::event::{ElementState, KeyEvent};
+use winit::event::{ElementState, Ime, KeyEvent};
 use winit::keyboard::{Key as LogicalKey, KeyCode, ModifiersState, NamedKey, PhysicalKey};
 
 /// Some shortcuts use Cmd on Mac and Control on other systems.
@@ -263,7 +263,82 @@ pub fn keyboard_event_from_winit(input: &KeyEvent, state: ModifiersState) -> Key
         code: get_servo_code_from_physical_key(input.physical_key),
         location: get_servo_location_from_physical_key(input.physical_key),
         modifiers: get_modifiers(state),
-        repeat: false,
+        repeat: input.repeat,
         is_composing: false,
     }
 }
+
+/// Input of an input method, so text reaches the page once.
+///
+/// While text is composed, the input method consumes the key presses, so they aren't forwarded.
+/// Committed text is sent as key presses of its characters, unless the key press which committed
+/// it already produced the same text, as some platforms deliver both.
+#[derive(Clone, Debug, Default)]
+pub struct ImeInput {
+    /// If the input method shows composed text.
+    composing: bool,
+    /// If text was composed since the last commit.
+    composed: bool,
+    /// Text of the last key press forwarded to the page.
+    echo: Option<String>,
+}
+
+impl ImeInput {
+    /// Create the state of an input method which isn't composing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return true if the key event should be forwarded to the page. `text` is the text the key
+    /// produces.
+    pub fn filter_key(&mut self, state: KeyState, text: Option<&str>) -> bool {
+        if self.composing {
+            return false;
+        }
+        if state == KeyState::Down {
+            self.echo = text.map(str::to_owned);
+        }
+        true
+    }
+
+    /// Return the key events to send to the page for the input method event.
+    pub fn handle_ime(&mut self, ime: &Ime) -> Vec<KeyboardEvent> {
+        match ime {
+            Ime::Preedit(text, _) => {
+                self.composing = !text.is_empty();
+                if self.composing {
+                    self.composed = true;
+                    self.echo = None;
+                }
+                Vec::new()
+            }
+            Ime::Commit(text) => {
+                self.composing = false;
+                let composed = std::mem::take(&mut self.composed);
+                let echo = self.echo.take();
+                if !composed && echo.as_deref() == Some(text.as_str()) {
+                    return Vec::new();
+                }
+                text.chars().flat_map(character_events).collect()
+            }
+            Ime::Enabled | Ime::Disabled => {
+                *self = Self::default();
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Key presses typing the character, without modifiers which could trigger shortcuts.
+fn character_events(character: char) -> [KeyboardEvent; 2] {
+    let event = |state| KeyboardEvent {
+        state,
+        key: Key::Character(character.to_string()),
+        code: Code::Unidentified,
+        location: Location::Standard,
+        modifiers: Modifiers::empty(),
+        repeat: false,
+        is_composing: false,
+    };
+    [event(KeyState::Down), event(KeyState::Up)]
+}
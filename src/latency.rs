@@ -0,0 +1,129 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Number of latency samples kept. Older samples are overwritten.
+const CAPACITY: usize = 128;
+
+/// Statistics of the time from input events to the present of the next frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// Number of samples the statistics are computed from.
+    pub samples: usize,
+    /// Shortest latency.
+    pub min: Duration,
+    /// Longest latency.
+    pub max: Duration,
+    /// Mean latency.
+    pub mean: Duration,
+    /// Median latency.
+    pub p50: Duration,
+    /// 95th percentile latency.
+    pub p95: Duration,
+}
+
+/// An input event waiting for a frame which can show its effect.
+#[derive(Clone, Copy, Debug)]
+struct TimedInput {
+    /// Time Verso received the event.
+    time: Instant,
+    /// Number of frames requested from WebRender before the event.
+    frame_token: u64,
+}
+
+/// Ring buffer of input latency samples.
+///
+/// Winit events don't carry a timestamp, so the time of an input event is taken when Verso
+/// receives it, along with a token counting the frames requested from WebRender so far. Frames
+/// requested before the input can't show its effect, so the input is only completed by the
+/// present of a frame requested after it. All inputs completed by a present are measured from the
+/// oldest of them, which is the latency the user feels.
+///
+/// The compositor keeps one for [`crate::Verso::input_latency_stats`]. It only needs times, so it
+/// can also be driven directly.
+#[derive(Debug)]
+pub struct LatencyTracker {
+    pending: VecDeque<TimedInput>,
+    frames_requested: u64,
+    frames_ready: u64,
+    samples: [Duration; CAPACITY],
+    len: usize,
+    next: usize,
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            frames_requested: 0,
+            frames_ready: 0,
+            samples: [Duration::ZERO; CAPACITY],
+            len: 0,
+            next: 0,
+        }
+    }
+}
+
+impl LatencyTracker {
+    /// Record an input event received at `time`.
+    pub fn on_input(&mut self, time: Instant) {
+        // Inputs before the same frame are completed together, so only the oldest is kept.
+        if self
+            .pending
+            .back()
+            .map_or(true, |input| input.frame_token != self.frames_requested)
+        {
+            self.pending.push_back(TimedInput {
+                time,
+                frame_token: self.frames_requested,
+            });
+        }
+    }
+
+    /// Record that a frame was requested from WebRender.
+    pub fn on_frame_requested(&mut self) {
+        self.frames_requested += 1;
+    }
+
+    /// Record that WebRender finished rendering a requested frame. Frames are finished in order.
+    pub fn on_frame_ready(&mut self) {
+        self.frames_ready = (self.frames_ready + 1).min(self.frames_requested);
+    }
+
+    /// Record a present at `time` of the latest ready frame, completing the inputs it can show.
+    pub fn on_present(&mut self, time: Instant) {
+        let mut oldest = None;
+        while let Some(input) = self.pending.front() {
+            if input.frame_token >= self.frames_ready {
+                break;
+            }
+            oldest.get_or_insert(input.time);
+            self.pending.pop_front();
+        }
+        let Some(input) = oldest else {
+            return;
+        };
+        self.samples[self.next] = time.saturating_duration_since(input);
+        self.next = (self.next + 1) % CAPACITY;
+        self.len = (self.len + 1).min(CAPACITY);
+    }
+
+    /// Compute statistics of the recorded samples.
+    pub fn stats(&self) -> LatencyStats {
+        if self.len == 0 {
+            return LatencyStats::default();
+        }
+        let mut samples = self.samples[..self.len].to_vec();
+        samples.sort_unstable();
+        let total: Duration = samples.iter().sum();
+        LatencyStats {
+            samples: self.len,
+            min: samples[0],
+            max: samples[self.len - 1],
+            mean: total / self.len as u32,
+            p50: samples[self.len.div_ceil(2) - 1],
+            p95: samples[(self.len * 95).div_ceil(100) - 1],
+        }
+    }
+}
@@ -5,25 +5,141 @@
 
 #![deny(missing_docs)]
 
+/// Scheduled reload of pages.
+pub mod auto_reload;
+/// Content size notifications and windows resized to fit their content.
+pub mod auto_resize;
+/// Autofill of form fields with previously submitted values.
+pub mod autofill;
+/// Middle-click autoscroll.
+pub mod autoscroll;
+/// Builder of Verso with validated options.
+pub mod builder;
+/// Keeping the caret visible while typing in long fields.
+pub mod caret;
+/// Chrome DevTools Protocol server for automation clients.
+#[cfg(feature = "cdp")]
+pub mod cdp;
+/// Rich clipboard contents like HTML and images.
+pub mod clipboard;
 /// Verso's compositor component to handle webrender.
 pub mod compositor;
 /// Utilities to read options and preferences.
 pub mod config;
+/// Scripts injected into pages matching URL patterns.
+pub mod content_scripts;
+/// High-contrast settings exposed to pages as forced colors.
+pub mod contrast;
+/// Listing and removing individual cookies.
+pub mod cookies;
+/// Crash reports written on panic.
+pub mod crash;
+/// Heads-up display of live browser state for development.
+pub mod debug_overlay;
+/// Discarding web views of background windows to save memory.
+pub mod discard;
+/// Handling of the embedder messages of web views as testable actions.
+pub mod dispatch;
+/// Overrides of DNS resolution for specific hosts, set when building Verso.
+pub mod dns;
+/// Downloads of generated files from `data:` and `blob:` URLs.
+pub mod downloads;
+/// Dragging links and images out of pages.
+pub mod drag_out;
+/// Selection commands run in pages by the embedder.
+pub mod editing;
+/// Text encoding override for legacy pages.
+pub mod encoding;
 /// Error and result types.
 pub mod errors;
+/// Links handled by other applications.
+pub mod external;
+/// Web features which can be disabled per origin.
+pub mod features;
+/// Writing mode and direction of pages.
+pub mod flow;
+/// Form submissions reconstructed by the embedder.
+pub mod form_data;
+/// Closures called at points of the lifecycle of web views.
+pub mod hooks;
+/// Conversion of pointer inputs to compositor events.
+pub mod input;
 /// Utilities to handle keyboard inputs and states.
 pub mod keyboard;
+/// Input latency measurement.
+pub mod latency;
+/// HTTP server for apps shipped as a local directory.
+#[cfg(feature = "local-server")]
+pub mod local_server;
+/// Application menu bar on macOS.
+#[cfg(macos)]
+pub mod menu;
+/// Storage partitions of web views.
+pub mod partitions;
+/// Hooks for password managers.
+pub mod passwords;
+/// Viewer of PDF documents.
+#[cfg(feature = "pdf-viewer")]
+pub mod pdf;
+/// Policies locking down Verso for administrators.
+pub mod policies;
+/// Overrides of Servo preferences.
+pub mod prefs;
+/// Printing pages to PDF.
+pub mod print;
+/// Loading progress bar drawn over web views.
+pub mod progress;
+/// Recording and replaying of input events for debugging.
+pub mod recorder;
+/// HTTP interface to control Verso from scripts.
+#[cfg(feature = "remote-control")]
+pub mod remote_control;
+/// Sandboxing of content processes.
+pub mod sandbox;
+/// Scrollbars drawn over web views.
+pub mod scrollbars;
+/// Primary selection support on Linux.
+#[cfg(linux)]
+pub mod selection;
+/// Sensor values exposed to pages.
+pub mod sensors;
+/// Keyboard shortcuts of browser actions.
+pub mod shortcuts;
+/// Storage used by each origin, and removing it.
+pub mod storage;
+/// Suspending web views and resuming them from snapshots.
+pub mod suspend;
+/// Title and favicon of pages for tab strips.
+pub mod tabs;
+/// Text scale of the operating system's accessibility settings.
+pub mod text_scale;
+/// Throttling of timers in hidden windows.
+pub mod timers;
+/// Random tokens authenticating the clients of local servers and the scripts Verso injects.
+mod token;
 /// Utilities to handle touch inputs and states.
 pub mod touch;
+/// Display forms of page URLs for address bars.
+pub mod url_display;
+/// Versions of Verso and its engine captured at build time.
+pub mod version;
 /// Main entry types and functions.
 pub mod verso;
+/// Recording of the window to video files.
+pub mod video;
+/// Visual regression testing of rendered pages.
+#[cfg(feature = "visual-test")]
+pub mod visual_test;
 /// Web view types to handle web browsing contexts.
 pub mod webview;
 /// Verso's window types to handle Winit's window.
 pub mod window;
-pub use errors::{Error, Result};
+/// Page zoom fitted to the content.
+pub mod zoom;
 /// Utilities to write tests.
 // pub mod test;
+pub use builder::VersoBuilder;
+pub use errors::{Error, Result};
 pub use verso::Verso;
 /// Re-exporting Winit for the sake of convenience.
 pub use winit;
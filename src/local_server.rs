@@ -0,0 +1,201 @@
+//! HTTP server for an app shipped as a directory next to the binary, started with
+//! [`crate::Verso::serve_directory`], so its pages get an `http` origin instead of the
+//! restrictions of `file` URLs.
+//!
+//! The server only listens on `127.0.0.1` and answers `GET` and `HEAD` requests with the files of
+//! the directory, with a `Content-Type` from their extension. Directory URLs are answered with
+//! their `index.html`. Paths leaving the directory, through `..` segments or symbolic links, are
+//! answered with 404 like missing files. With [`ServeOptions::spa_fallback`], missing paths
+//! without an extension are answered with the `index.html` of the root instead, for apps which
+//! route on the client. The server stops when Verso shuts down.
+
+use std::{
+    fs::File,
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+use servo_url::ServoUrl;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Options of [`crate::Verso::serve_directory_with`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ServeOptions {
+    /// Port to listen on, or `None` for a free port picked by the system.
+    pub port: Option<u16>,
+    /// Answer missing paths without an extension with the `index.html` of the root.
+    pub spa_fallback: bool,
+}
+
+/// Handle of a running server, which stops it when dropped.
+pub(crate) struct LocalServer {
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+    address: SocketAddr,
+}
+
+impl LocalServer {
+    /// Start serving the directory.
+    pub fn start(root: &Path, options: ServeOptions) -> io::Result<Self> {
+        let root = root.canonicalize()?;
+        if !root.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} isn't a directory", root.display()),
+            ));
+        }
+        let server = Server::http((Ipv4Addr::LOCALHOST, options.port.unwrap_or(0)))
+            .map_err(io::Error::other)?;
+        let address = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| io::Error::other("server isn't listening on an IP address"))?;
+        let server = Arc::new(server);
+        let thread = {
+            let server = server.clone();
+            thread::Builder::new()
+                .name("LocalServer".into())
+                .spawn(move || {
+                    for request in server.incoming_requests() {
+                        respond(request, &root, options.spa_fallback);
+                    }
+                })?
+        };
+        log::info!("Verso is serving {root:?} on http://{address}/");
+        Ok(Self {
+            server,
+            thread: Some(thread),
+            address,
+        })
+    }
+
+    /// URL of the root of the directory.
+    pub fn url(&self) -> ServoUrl {
+        ServoUrl::parse(&format!("http://{}/", self.address)).expect("Server URL is valid")
+    }
+}
+
+impl Drop for LocalServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn respond(request: Request, root: &Path, spa_fallback: bool) {
+    let result = match request.method() {
+        Method::Get | Method::Head => {
+            let path = resolve(root, request.url()).or_else(|| {
+                let fallback = spa_fallback && !has_extension(request.url());
+                fallback
+                    .then(|| root.join("index.html"))
+                    .filter(|p| p.is_file())
+            });
+            match path.and_then(|path| File::open(&path).ok().map(|file| (path, file))) {
+                Some((path, file)) => {
+                    let content_type = Header::from_bytes("Content-Type", content_type(&path))
+                        .expect("Content type is a valid header");
+                    request.respond(Response::from_file(file).with_header(content_type))
+                }
+                None => request.respond(Response::from_string("Not Found").with_status_code(404)),
+            }
+        }
+        _ => request.respond(Response::empty(405)),
+    };
+    if let Err(e) = result {
+        log::warn!("Verso local server failed to respond: {e}");
+    }
+}
+
+/// Resolve the path of a request URL to a file in the root, or `None` if it's missing or outside
+/// of the root.
+fn resolve(root: &Path, url: &str) -> Option<PathBuf> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let mut resolved = root.to_path_buf();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        let segment = percent_decode(segment)?;
+        let mut components = Path::new(&segment).components();
+        // Each segment must be one plain file name, not `..`, a root or a drive prefix.
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) if !segment.contains(['/', '\\']) => {
+                resolved.push(name)
+            }
+            (Some(Component::CurDir), None) => {}
+            _ => return None,
+        }
+    }
+    let mut resolved = resolved.canonicalize().ok()?;
+    if resolved.is_dir() {
+        resolved = resolved.join("index.html").canonicalize().ok()?;
+    }
+    // Symbolic links may point out of the root.
+    (resolved.starts_with(root) && resolved.is_file()).then_some(resolved)
+}
+
+fn has_extension(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.rsplit('/')
+        .next()
+        .is_some_and(|name| name.contains('.'))
+}
+
+/// Decode the percent-encoded bytes of a path segment, or return `None` if it isn't UTF-8 or
+/// contains a NUL byte.
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok()?;
+                i += 2;
+                u8::from_str_radix(hex, 16).ok()?
+            }
+            byte => byte,
+        };
+        decoded.push(byte);
+        i += 1;
+    }
+    let decoded = String::from_utf8(decoded).ok()?;
+    (!decoded.contains('\0')).then_some(decoded)
+}
+
+/// MIME type of a file from its extension.
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json" | "map") => "application/json",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("xml") => "application/xml",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        Some("otf") => "font/otf",
+        Some("wasm") => "application/wasm",
+        Some("mp3") => "audio/mpeg",
+        Some("ogg") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        Some("pdf") => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
@@ -2,20 +2,39 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use verso::config::Config;
-use verso::{Result, Verso};
+use verso::{Error, Result, Verso};
 use winit::application::ApplicationHandler;
+use winit::event::StartCause;
 use winit::event_loop::{self, DeviceEvents};
 use winit::event_loop::{EventLoop, EventLoopProxy};
 
 struct App {
     verso: Option<Verso>,
     proxy: EventLoopProxy<()>,
+    config: Option<Config>,
+    /// Error which stopped Verso from starting.
+    error: Option<Error>,
 }
 
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        let config = Config::new(resources_dir_path().unwrap());
-        self.verso = Some(Verso::new(event_loop, self.proxy.clone(), config));
+        if let Some(config) = self.config.take() {
+            match Verso::new(event_loop, self.proxy.clone(), config) {
+                Ok(verso) => self.verso = Some(verso),
+                Err(e) => {
+                    self.error = Some(e);
+                    event_loop.exit();
+                }
+            }
+        } else if let Some(verso) = self.verso.as_mut() {
+            verso.resume();
+        }
+    }
+
+    fn suspended(&mut self, _: &winit::event_loop::ActiveEventLoop) {
+        if let Some(verso) = self.verso.as_mut() {
+            verso.suspend();
+        }
     }
 
     fn window_event(
@@ -30,6 +49,15 @@ impl ApplicationHandler for App {
         });
     }
 
+    fn new_events(&mut self, event_loop: &event_loop::ActiveEventLoop, cause: StartCause) {
+        // Wake up by the deadline of replaying events.
+        if let StartCause::ResumeTimeReached { .. } = cause {
+            self.verso.as_mut().map(|v| {
+                v.handle_servo_messages(event_loop);
+            });
+        }
+    }
+
     fn user_event(&mut self, event_loop: &event_loop::ActiveEventLoop, _: ()) {
         self.verso.as_mut().map(|v| {
             v.handle_servo_messages(event_loop);
@@ -38,13 +66,58 @@ impl ApplicationHandler for App {
 }
 
 fn main() -> Result<()> {
+    let resource_dir = resources_dir_path().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Verso can't locate the resources directory",
+        )
+    })?;
+    // Servo starts content processes by running this executable again.
+    if let Some(token) = verso::sandbox::content_process_token() {
+        verso::sandbox::run_content_process(token, resource_dir)?;
+        return Ok(());
+    }
+    let mut config = Config::new(resource_dir);
+    config.load_shortcuts();
+    let crash_reports = match std::env::var_os(verso::crash::CRASH_REPORTS_ENV) {
+        Some(dir) => dir.into(),
+        None => config
+            .opts
+            .config_dir
+            .clone()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("verso-crashes"),
+    };
+    verso::crash::install_panic_hook(crash_reports);
+    // Hidden flag to check crash reports.
+    if std::env::args().any(|arg| arg == "--crash-test") {
+        panic!("Verso crashes deliberately for --crash-test");
+    }
+    // The page to open, like a URL or a file path.
+    if let Some(url) = verso::config::find_url_argument(std::env::args().skip(1)) {
+        let current_dir = std::env::current_dir()?;
+        config.homepage = Some(verso::config::parse_url_argument(&url, &current_dir)?);
+    }
+    if let Ok(prefs) = std::env::var("VERSO_PREFS") {
+        config.pref_overrides = verso::prefs::parse_overrides(&prefs)?;
+    }
+    #[cfg(target_os = "windows")]
+    if config.window.per_monitor_dpi_aware {
+        verso::window::declare_per_monitor_dpi_awareness();
+    }
+
     let event_loop = EventLoop::new()?;
     event_loop.listen_device_events(DeviceEvents::Never);
     let proxy = event_loop.create_proxy();
-    let mut app = App { verso: None, proxy };
+    let mut app = App {
+        verso: None,
+        proxy,
+        config: Some(config),
+        error: None,
+    };
     event_loop.run_app(&mut app)?;
 
-    Ok(())
+    app.error.map_or(Ok(()), Err)
 }
 
 fn resources_dir_path() -> Option<std::path::PathBuf> {
@@ -0,0 +1,236 @@
+use crossbeam_channel::{unbounded, Receiver};
+use keyboard_types::{Code, Key, KeyState, KeyboardEvent, Location, Modifiers};
+use muda::{
+    accelerator::{Accelerator, Code as AccelCode, Modifiers as AccelModifiers},
+    AboutMetadata, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu,
+};
+use winit::event_loop::EventLoopProxy;
+
+use crate::shortcuts::ShortcutAction;
+
+/// Actions triggered by the items of the application menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    /// Shut down Verso.
+    Quit,
+    /// Open a new window.
+    NewWindow,
+    /// Close the focused window.
+    CloseWindow,
+    /// Cut the selection to the clipboard.
+    Cut,
+    /// Copy the selection to the clipboard.
+    Copy,
+    /// Paste from the clipboard.
+    Paste,
+    /// Select everything in the focused web view.
+    SelectAll,
+    /// Browser action which is also available as a keyboard shortcut.
+    Shortcut(ShortcutAction),
+}
+
+impl MenuAction {
+    const ITEMS: [(&'static str, MenuAction); 13] = [
+        ("quit", MenuAction::Quit),
+        ("new-window", MenuAction::NewWindow),
+        ("close-window", MenuAction::CloseWindow),
+        ("cut", MenuAction::Cut),
+        ("copy", MenuAction::Copy),
+        ("paste", MenuAction::Paste),
+        ("select-all", MenuAction::SelectAll),
+        ("reload", MenuAction::Shortcut(ShortcutAction::Reload)),
+        ("zoom-in", MenuAction::Shortcut(ShortcutAction::ZoomIn)),
+        ("zoom-out", MenuAction::Shortcut(ShortcutAction::ZoomOut)),
+        (
+            "reset-zoom",
+            MenuAction::Shortcut(ShortcutAction::ResetZoom),
+        ),
+        ("back", MenuAction::Shortcut(ShortcutAction::Back)),
+        ("forward", MenuAction::Shortcut(ShortcutAction::Forward)),
+    ];
+
+    fn id(self) -> &'static str {
+        Self::ITEMS
+            .iter()
+            .find(|(_, action)| *action == self)
+            .map(|(id, _)| *id)
+            .unwrap_or_default()
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ITEMS
+            .iter()
+            .find(|(i, _)| *i == id)
+            .map(|(_, action)| *action)
+    }
+
+    /// Keyboard events which trigger the edit action in the web view. Menu key equivalents are
+    /// consumed by AppKit before they reach the window, so edit actions are sent to the page as
+    /// the key presses they stand for.
+    pub fn key_events(self) -> Option<[KeyboardEvent; 2]> {
+        let (key, code) = match self {
+            MenuAction::Cut => ("x", Code::KeyX),
+            MenuAction::Copy => ("c", Code::KeyC),
+            MenuAction::Paste => ("v", Code::KeyV),
+            MenuAction::SelectAll => ("a", Code::KeyA),
+            _ => return None,
+        };
+        let event = |state| KeyboardEvent {
+            state,
+            key: Key::Character(key.into()),
+            code,
+            location: Location::Standard,
+            modifiers: Modifiers::META,
+            repeat: false,
+            is_composing: false,
+        };
+        Some([event(KeyState::Down), event(KeyState::Up)])
+    }
+}
+
+/// The application menu bar on macOS.
+pub struct AppMenu {
+    _menu: Menu,
+    back: MenuItem,
+    forward: MenuItem,
+    receiver: Receiver<MenuAction>,
+}
+
+impl AppMenu {
+    /// Create the menu bar and install it to the application. Menu events wake up the event
+    /// loop with the proxy.
+    pub fn new(proxy: EventLoopProxy<()>) -> Result<Self, muda::Error> {
+        let item = |action: MenuAction, text: &str, key: Option<(AccelModifiers, AccelCode)>| {
+            MenuItem::with_id(
+                action.id(),
+                text,
+                true,
+                key.map(|(modifiers, code)| Accelerator::new(Some(modifiers), code)),
+            )
+        };
+        let cmd = AccelModifiers::SUPER;
+        let back = item(
+            MenuAction::Shortcut(ShortcutAction::Back),
+            "Back",
+            Some((cmd, AccelCode::BracketLeft)),
+        );
+        let forward = item(
+            MenuAction::Shortcut(ShortcutAction::Forward),
+            "Forward",
+            Some((cmd, AccelCode::BracketRight)),
+        );
+        back.set_enabled(false);
+        forward.set_enabled(false);
+
+        let about = AboutMetadata {
+            name: Some("Verso".into()),
+            version: Some(env!("CARGO_PKG_VERSION").into()),
+            ..Default::default()
+        };
+        let menu = Menu::with_items(&[
+            &Submenu::with_items(
+                "Verso",
+                true,
+                &[
+                    &PredefinedMenuItem::about(None, Some(about)),
+                    &PredefinedMenuItem::separator(),
+                    &PredefinedMenuItem::hide(None),
+                    &PredefinedMenuItem::hide_others(None),
+                    &PredefinedMenuItem::show_all(None),
+                    &PredefinedMenuItem::separator(),
+                    &item(MenuAction::Quit, "Quit Verso", Some((cmd, AccelCode::KeyQ))),
+                ],
+            )?,
+            &Submenu::with_items(
+                "File",
+                true,
+                &[
+                    &item(
+                        MenuAction::NewWindow,
+                        "New Window",
+                        Some((cmd, AccelCode::KeyN)),
+                    ),
+                    &item(
+                        MenuAction::CloseWindow,
+                        "Close",
+                        Some((cmd, AccelCode::KeyW)),
+                    ),
+                ],
+            )?,
+            &Submenu::with_items(
+                "Edit",
+                true,
+                &[
+                    &item(MenuAction::Cut, "Cut", Some((cmd, AccelCode::KeyX))),
+                    &item(MenuAction::Copy, "Copy", Some((cmd, AccelCode::KeyC))),
+                    &item(MenuAction::Paste, "Paste", Some((cmd, AccelCode::KeyV))),
+                    &item(
+                        MenuAction::SelectAll,
+                        "Select All",
+                        Some((cmd, AccelCode::KeyA)),
+                    ),
+                ],
+            )?,
+            &Submenu::with_items(
+                "View",
+                true,
+                &[
+                    &item(
+                        MenuAction::Shortcut(ShortcutAction::Reload),
+                        "Reload",
+                        Some((cmd, AccelCode::KeyR)),
+                    ),
+                    &PredefinedMenuItem::separator(),
+                    &item(
+                        MenuAction::Shortcut(ShortcutAction::ResetZoom),
+                        "Actual Size",
+                        Some((cmd, AccelCode::Digit0)),
+                    ),
+                    &item(
+                        MenuAction::Shortcut(ShortcutAction::ZoomIn),
+                        "Zoom In",
+                        Some((cmd, AccelCode::Equal)),
+                    ),
+                    &item(
+                        MenuAction::Shortcut(ShortcutAction::ZoomOut),
+                        "Zoom Out",
+                        Some((cmd, AccelCode::Minus)),
+                    ),
+                    &PredefinedMenuItem::separator(),
+                    &PredefinedMenuItem::fullscreen(Some("Enter Full Screen")),
+                ],
+            )?,
+            &Submenu::with_items("History", true, &[&back, &forward])?,
+        ])?;
+        menu.init_for_nsapp();
+
+        let (sender, receiver) = unbounded();
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            match MenuAction::from_id(&event.id.0) {
+                Some(action) => {
+                    let _ = sender.send(action);
+                    let _ = proxy.send_event(());
+                }
+                None => log::trace!("Verso ignores unknown menu item {:?}", event.id),
+            }
+        }));
+
+        Ok(Self {
+            _menu: menu,
+            back,
+            forward,
+            receiver,
+        })
+    }
+
+    /// Take the next activated menu action without blocking.
+    pub fn try_recv(&self) -> Option<MenuAction> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Enable or disable the history items.
+    pub fn set_history_state(&self, can_go_back: bool, can_go_forward: bool) {
+        self.back.set_enabled(can_go_back);
+        self.forward.set_enabled(can_go_forward);
+    }
+}
@@ -0,0 +1,90 @@
+//! Storage partitions, which keep the cookies, local storage and cache of web views apart, like
+//! for embedders hosting several third-party apps.
+//!
+//! A partition is a directory named after it under `partitions` in the config directory of
+//! Servo, selected with [`crate::config::Config::partition`]. Web views of the same partition
+//! share their state, and partitions don't see each other's state. `None` keeps the default
+//! partition, which is the config directory itself. If the directory of the partition can't be
+//! created, Verso fails to start with [`crate::Error::PartitionError`] rather than storing the
+//! state in the default partition.
+//!
+//! Servo has a single set of resource threads per process, so the partition applies to all web
+//! views of a Verso instance, and web views can't be created in a partition of their own. Apps
+//! which must be isolated from each other run in Verso instances of their own, in separate
+//! processes.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Name of the directory holding the partitions in the config directory.
+const PARTITIONS_DIR: &str = "partitions";
+
+/// Error of managing storage partitions.
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum PartitionError {
+    /// The name is empty, or has characters other than ASCII letters, digits, `-` and `_`.
+    #[error("invalid partition name `{0}`")]
+    InvalidName(String),
+    /// The partition is used by the web views of this Verso instance.
+    #[error("partition `{0}` is in use")]
+    InUse(String),
+    /// Servo has no config directory, so state isn't stored on disk.
+    #[error("there's no config directory to store partitions in")]
+    NoConfigDir,
+    /// An I/O error while creating or deleting the directory of the partition.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Check that the partition name is usable as a directory name.
+pub(crate) fn validate_name(name: &str) -> Result<(), PartitionError> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        return Err(PartitionError::InvalidName(name.into()));
+    }
+    Ok(())
+}
+
+/// Directory of the partition in the config directory.
+pub(crate) fn partition_dir(config_dir: &Path, name: &str) -> PathBuf {
+    config_dir.join(PARTITIONS_DIR).join(name)
+}
+
+/// Create the directory of the partition if it doesn't exist, and return it.
+pub(crate) fn create(config_dir: Option<&Path>, name: &str) -> Result<PathBuf, PartitionError> {
+    validate_name(name)?;
+    let dir = partition_dir(config_dir.ok_or(PartitionError::NoConfigDir)?, name);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Delete the directory of the partition and all the state in it. Deleting a partition which
+/// doesn't exist succeeds.
+pub(crate) fn delete(config_dir: Option<&Path>, name: &str) -> Result<(), PartitionError> {
+    validate_name(name)?;
+    let dir = partition_dir(config_dir.ok_or(PartitionError::NoConfigDir)?, name);
+    match fs::remove_dir_all(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// List the partitions in the config directory.
+pub(crate) fn list(config_dir: Option<&Path>) -> Vec<String> {
+    let Some(Ok(entries)) = config_dir.map(|dir| fs::read_dir(dir.join(PARTITIONS_DIR))) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort_unstable();
+    names
+}
@@ -0,0 +1,171 @@
+//! Hooks for password managers, which are registered with [`crate::VersoBuilder`].
+//!
+//! Verso doesn't store passwords itself. It reports login forms and their submissions to the
+//! embedder, which can hand them over to an external password manager:
+//!
+//! - [`on_login_form`](crate::VersoBuilder::on_login_form) is called when a page shows a form
+//!   with a password field. Its fields can then be filled with
+//!   [`crate::Verso::fill_credentials`].
+//! - [`on_credentials_submitted`](crate::VersoBuilder::on_credentials_submitted) is called when
+//!   a form with a password is submitted, so the manager can offer to save it. The username and
+//!   password are only included if [`crate::config::Config::share_credentials`] is enabled.
+//!
+//! The script finding login forms only runs in the top-level page, and its messages carry the
+//! random token of the window and the origin of the page. Messages of frames and page scripts,
+//! which don't have the token, are ignored. Filling checks that the page still has the origin
+//! the embedder expects before it touches any field.
+
+use serde::Deserialize;
+
+use crate::token::parse_message;
+
+/// Prefix of the message the page sends with `prompt()` when a login form is found, followed by
+/// the JSON of the token, the origin of the page, and its fields.
+pub(crate) const LOGIN_FORM_PROMPT: &str = "VERSO_LOGIN_FORM:";
+
+/// Prefix of the message the page sends with `prompt()` when a login form is submitted, followed
+/// by the JSON of the token, the origin of the page, and its fields, with their values if they're
+/// shared.
+pub(crate) const CREDENTIALS_PROMPT: &str = "VERSO_CREDENTIALS:";
+
+/// Login form found in a page, reported to [`crate::VersoBuilder::on_login_form`].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct LoginForm {
+    /// Serialized origin of the page, like `https://example.com`.
+    #[serde(skip)]
+    pub origin: String,
+    /// Identifier of the username field, its id or name, if the form has one.
+    pub username_field: Option<String>,
+    /// Identifier of the password field, its id or name, or an empty string if it has neither.
+    pub password_field: String,
+}
+
+/// Login form submitted by a page, reported to [`crate::VersoBuilder::on_credentials_submitted`].
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+pub struct SubmittedCredentials {
+    /// The form which was submitted.
+    #[serde(flatten)]
+    pub form: LoginForm,
+    /// Submitted username, if the form has a username field and
+    /// [`crate::config::Config::share_credentials`] is enabled.
+    pub username: Option<String>,
+    /// Submitted password, if [`crate::config::Config::share_credentials`] is enabled.
+    pub password: Option<String>,
+}
+
+impl std::fmt::Debug for SubmittedCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubmittedCredentials")
+            .field("form", &self.form)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<hidden>"))
+            .finish()
+    }
+}
+
+/// Parse a [`LOGIN_FORM_PROMPT`] message from the origin, if it has the token.
+pub(crate) fn parse_login_form(origin: &str, token: &str, message: &str) -> Option<LoginForm> {
+    let json = message.strip_prefix(LOGIN_FORM_PROMPT)?;
+    let form: LoginForm = parse_message(json, token, origin)?;
+    Some(LoginForm {
+        origin: origin.to_string(),
+        ..form
+    })
+}
+
+/// Parse a [`CREDENTIALS_PROMPT`] message from the origin, if it has the token. Values are
+/// dropped unless `share` is true, even if the page sent them.
+pub(crate) fn parse_credentials(
+    origin: &str,
+    token: &str,
+    message: &str,
+    share: bool,
+) -> Option<SubmittedCredentials> {
+    let json = message.strip_prefix(CREDENTIALS_PROMPT)?;
+    let mut credentials: SubmittedCredentials = parse_message(json, token, origin)?;
+    credentials.form.origin = origin.to_string();
+    if !share {
+        credentials.username = None;
+        credentials.password = None;
+    }
+    Some(credentials)
+}
+
+/// Script finding login forms and reporting their submission with the token, with the values if
+/// `share` is true.
+pub(crate) fn passwords_script(share: bool, token: &str) -> String {
+    format!(
+        "if (!window.versoPasswords) {{
+            window.versoPasswords = true;
+            const share = {share};
+            const ask = window.prompt.bind(window);
+            const stringify = JSON.stringify;
+            const message = (prefix, data) => prefix + stringify([{token:?}, location.origin, data]);
+            const identifier = (field) => field.id || field.name || '';
+            const fieldsOf = (password) => {{
+                const scope = password.form ?? document;
+                const inputs = [...scope.querySelectorAll('input')];
+                const index = inputs.indexOf(password);
+                const username = inputs
+                    .slice(0, index)
+                    .reverse()
+                    .find((field) => ['text', 'email', 'tel'].includes(field.type));
+                return {{ username, password }};
+            }};
+            const describe = ({{ username, password }}) => ({{
+                username_field: username ? identifier(username) : null,
+                password_field: identifier(password),
+            }});
+            const reported = new WeakSet();
+            const detect = () => {{
+                for (const password of document.querySelectorAll('input[type=password]')) {{
+                    if (reported.has(password)) continue;
+                    reported.add(password);
+                    window.versoLoginFields = fieldsOf(password);
+                    ask(message('{LOGIN_FORM_PROMPT}', describe(window.versoLoginFields)));
+                }}
+            }};
+            new MutationObserver(detect).observe(document, {{ childList: true, subtree: true }});
+            document.addEventListener('DOMContentLoaded', detect);
+            detect();
+            document.addEventListener('submit', (event) => {{
+                const password = event.target.querySelector('input[type=password]');
+                if (!password || password.value === '') return;
+                const fields = fieldsOf(password);
+                const credentials = describe(fields);
+                credentials.username = share && fields.username ? fields.username.value : null;
+                credentials.password = share ? password.value : null;
+                ask(message('{CREDENTIALS_PROMPT}', credentials));
+            }}, true);
+            // Frameworks like React track the value set through the prototype, so the native
+            // setter is called and the events are dispatched as if the user typed.
+            const setValue = Object.getOwnPropertyDescriptor(HTMLInputElement.prototype, 'value').set;
+            const fill = (origin, username, password) => {{
+                const fields = window.versoLoginFields;
+                if (location.origin !== origin) return false;
+                if (!fields || !fields.password.isConnected) return false;
+                for (const [field, value] of [[fields.username, username], [fields.password, password]]) {{
+                    if (!field) continue;
+                    field.focus();
+                    setValue.call(field, value);
+                    field.dispatchEvent(new InputEvent('input', {{ bubbles: true, inputType: 'insertReplacementText' }}));
+                    field.dispatchEvent(new Event('change', {{ bubbles: true }}));
+                }}
+                return true;
+            }};
+            Object.defineProperty(window, 'versoFillCredentials', {{ value: fill }});
+        }}"
+    )
+}
+
+/// Script filling the last login form found in the page with the credentials, if the page has
+/// the origin. Nothing is read or written in pages of other origins.
+pub(crate) fn fill_script(origin: &str, username: &str, password: &str) -> Option<String> {
+    let origin = serde_json::to_string(origin).ok()?;
+    let username = serde_json::to_string(username).ok()?;
+    let password = serde_json::to_string(password).ok()?;
+    Some(format!(
+        "if (location.origin !== {origin}) return false;
+        return window.versoFillCredentials?.({origin}, {username}, {password}) ?? false;"
+    ))
+}
@@ -0,0 +1,345 @@
+//! Viewer of PDF documents, enabled with the `pdf-viewer` feature.
+//!
+//! Servo can't display PDFs, so once a page finished loading as `application/pdf`, Verso reads
+//! its bytes from the page, and replaces it with a viewer page generated in a temporary
+//! directory. The viewer lays out a placeholder for every page, and asks Verso with `prompt()` to
+//! render the pages scrolled into view with pdfium, so large documents are rendered lazily. It
+//! has page navigation, zoom, and a download button saving the original bytes to the downloads
+//! directory.
+//!
+//! pdfium is loaded at runtime, from the directory of the executable or from the system
+//! libraries. Without it, PDFs are left to Servo.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use base::id::{BrowsingContextId, WebViewId};
+use compositing_traits::ConstellationMsg;
+use crossbeam_channel::Sender;
+use pdfium_render::prelude::*;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+
+use crate::verso::{execute_script, send_to_constellation};
+
+/// Prefix of the message the viewer sends with `prompt()` to render a page, followed by the
+/// index of the page and the width in device pixels, like `VERSO_PDF_PAGE:0:1200`. The answer is
+/// the URL of the image.
+pub(crate) const PAGE_PROMPT: &str = "VERSO_PDF_PAGE:";
+
+/// Message the viewer sends with `prompt()` to download the document. The answer is the path
+/// of the saved file.
+pub(crate) const DOWNLOAD_PROMPT: &str = "VERSO_PDF_DOWNLOAD";
+
+/// Script returning the bytes of the page as a binary string if it's a PDF, or `null`.
+const GET_PDF: &str = "
+if (document.contentType !== 'application/pdf') return null;
+const request = new XMLHttpRequest();
+request.open('GET', location.href, false);
+request.overrideMimeType('text/plain; charset=x-user-defined');
+request.send();
+return request.status === 200 || request.status === 0 ? request.responseText : null;";
+
+/// Widths are rounded up to this step, so zooming doesn't render every page again for each
+/// pixel.
+const WIDTH_STEP: u32 = 256;
+
+/// PDF document shown in a viewer.
+#[derive(Debug)]
+struct PdfView {
+    /// URL of the document.
+    url: ServoUrl,
+    /// Bytes of the document.
+    bytes: Vec<u8>,
+    /// Directory of the viewer page and the rendered pages.
+    dir: PathBuf,
+    /// URL of the viewer page.
+    viewer: ServoUrl,
+}
+
+impl Drop for PdfView {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn views() -> &'static Mutex<HashMap<WebViewId, PdfView>> {
+    static VIEWS: OnceLock<Mutex<HashMap<WebViewId, PdfView>>> = OnceLock::new();
+    VIEWS.get_or_init(Mutex::default)
+}
+
+fn pdfium() -> Result<Pdfium, PdfiumError> {
+    let next_to_exe = std::env::current_exe().ok().and_then(|exe| {
+        let dir = exe.parent()?;
+        Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(dir)).ok()
+    });
+    let bindings = match next_to_exe {
+        Some(bindings) => bindings,
+        None => Pdfium::bind_to_system_library()?,
+    };
+    Ok(Pdfium::new(bindings))
+}
+
+/// Check if the page loaded in the web view is a PDF, and show it in the viewer if it is. The
+/// viewer of a document the web view navigated away from is closed.
+pub(crate) fn open_if_pdf(
+    sender: &Sender<ConstellationMsg>,
+    webview_id: WebViewId,
+    url: Option<&ServoUrl>,
+) {
+    if let Ok(mut views) = views().lock() {
+        let viewer = views.get(&webview_id).map(|view| &view.viewer);
+        if url.is_some() && viewer == url {
+            return;
+        }
+        views.remove(&webview_id);
+    }
+    let Some(url) = url.cloned() else {
+        return;
+    };
+    let Some(receiver) = execute_script(sender, BrowsingContextId::from(webview_id), GET_PDF)
+    else {
+        return;
+    };
+    let sender = sender.clone();
+    std::thread::spawn(move || {
+        let bytes = match receiver.recv() {
+            Ok(Ok(WebDriverJSValue::String(bytes))) => bytes,
+            Ok(Ok(_)) => return,
+            result => return log::warn!("Verso failed to check if the page is a PDF: {result:?}"),
+        };
+        // Each character of the binary string holds a byte in its lower 8 bits.
+        let bytes: Vec<u8> = bytes.chars().map(|c| c as u32 as u8).collect();
+        match open(webview_id, url, bytes) {
+            Ok(viewer) => {
+                send_to_constellation(&sender, ConstellationMsg::LoadUrl(webview_id, viewer))
+            }
+            Err(e) => log::warn!("Verso failed to open the PDF viewer: {e}"),
+        }
+    });
+}
+
+fn open(webview_id: WebViewId, url: ServoUrl, bytes: Vec<u8>) -> Result<ServoUrl, String> {
+    let pdfium = pdfium().map_err(|e| format!("pdfium isn't available ({e:?})"))?;
+    let document = pdfium
+        .load_pdf_from_byte_slice(&bytes, None)
+        .map_err(|e| format!("{e:?}"))?;
+    let sizes: Vec<(f32, f32)> = document
+        .pages()
+        .iter()
+        .map(|page| (page.width().value, page.height().value))
+        .collect();
+    drop(document);
+
+    let dir = std::env::temp_dir()
+        .join("verso-pdf")
+        .join(format!("{webview_id}").replace(|c: char| !c.is_alphanumeric(), "-"));
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join("viewer.html");
+    fs::write(&path, viewer_page(&url, &sizes)).map_err(|e| e.to_string())?;
+    let viewer = ServoUrl::from_file_path(&path).map_err(|()| "invalid path".to_string())?;
+    log::debug!(
+        "Verso opens the PDF viewer for {url} with {} pages",
+        sizes.len()
+    );
+
+    let view = PdfView {
+        url,
+        bytes,
+        dir,
+        viewer: viewer.clone(),
+    };
+    views()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(webview_id, view);
+    Ok(viewer)
+}
+
+/// Render the page of the document shown in the web view, answering a [`PAGE_PROMPT`] message.
+/// Return the URL of the image.
+pub(crate) fn render_page(webview_id: WebViewId, message: &str) -> Option<String> {
+    let (index, width) = message.strip_prefix(PAGE_PROMPT)?.split_once(':')?;
+    let index: PdfPageIndex = index.parse().ok()?;
+    let width = width
+        .parse::<u32>()
+        .ok()?
+        .clamp(1, 8192)
+        .div_ceil(WIDTH_STEP)
+        * WIDTH_STEP;
+    let views = views().lock().ok()?;
+    let view = views.get(&webview_id)?;
+    let path = view.dir.join(format!("page-{index}-{width}.png"));
+    if !path.exists() {
+        if let Err(e) = render(&view.bytes, index, width, &path) {
+            log::warn!("Verso failed to render page {index} of {}: {e}", view.url);
+            return None;
+        }
+    }
+    ServoUrl::from_file_path(&path)
+        .ok()
+        .map(|url| url.to_string())
+}
+
+fn render(bytes: &[u8], index: PdfPageIndex, width: u32, path: &Path) -> Result<(), String> {
+    let pdfium = pdfium().map_err(|e| format!("{e:?}"))?;
+    let document = pdfium
+        .load_pdf_from_byte_slice(bytes, None)
+        .map_err(|e| format!("{e:?}"))?;
+    let page = document.pages().get(index).map_err(|e| format!("{e:?}"))?;
+    let config = PdfRenderConfig::new().set_target_width(width as Pixels);
+    let bitmap = page
+        .render_with_config(&config)
+        .map_err(|e| format!("{e:?}"))?;
+    bitmap
+        .as_image()
+        .save_with_format(path, image::ImageFormat::Png)
+        .map_err(|e| e.to_string())
+}
+
+/// Save the document shown in the web view to the downloads directory, answering a
+/// [`DOWNLOAD_PROMPT`] message. Return the path of the file.
+pub(crate) fn download(webview_id: WebViewId) -> Option<String> {
+    let views = views().lock().ok()?;
+    let view = views.get(&webview_id)?;
+    let name = view
+        .url
+        .path_segments()
+        .and_then(|segments| segments.last())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("document.pdf");
+    let path = crate::downloads::downloads_dir().join(name);
+    match fs::write(&path, &view.bytes) {
+        Ok(()) => Some(path.display().to_string()),
+        Err(e) => {
+            log::warn!("Verso failed to save {} to {path:?}: {e}", view.url);
+            None
+        }
+    }
+}
+
+/// Close the viewer of the web view, deleting its files.
+pub(crate) fn close(webview_id: WebViewId) {
+    if let Ok(mut views) = views().lock() {
+        views.remove(&webview_id);
+    }
+}
+
+/// HTML of the viewer of a document with pages of the sizes in points.
+fn viewer_page(url: &ServoUrl, sizes: &[(f32, f32)]) -> String {
+    let title = html_escape(
+        url.path_segments()
+            .and_then(|segments| segments.last())
+            .unwrap_or(url.as_str()),
+    );
+    let pages: String = sizes
+        .iter()
+        .enumerate()
+        .map(|(i, (width, height))| {
+            format!(
+                "<img class=\"page\" data-index=\"{i}\" alt=\"Page {}\" \
+                style=\"aspect-ratio: {width} / {height}\">",
+                i + 1
+            )
+        })
+        .collect();
+    format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+<style>
+body {{ margin: 0; background: #525659; font-family: sans-serif; }}
+#toolbar {{
+    position: fixed; top: 0; left: 0; right: 0; z-index: 1; display: flex; gap: 8px;
+    align-items: center; padding: 6px 12px; background: #323639; color: #fff;
+}}
+#toolbar input {{ width: 4em; }}
+#status {{ margin-left: auto; }}
+#pages {{ display: flex; flex-direction: column; align-items: center; gap: 12px;
+    padding: 56px 0 12px; }}
+.page {{ display: block; width: calc(816px * var(--zoom, 1)); max-width: none;
+    background: #fff; }}
+</style>
+</head>
+<body>
+<div id=\"toolbar\">
+<button id=\"previous\" title=\"Previous page\">&#9650;</button>
+<button id=\"next\" title=\"Next page\">&#9660;</button>
+<input id=\"page\" type=\"number\" min=\"1\" max=\"{count}\" value=\"1\"> / {count}
+<button id=\"zoom-out\" title=\"Zoom out\">&minus;</button>
+<span id=\"zoom\">100%</span>
+<button id=\"zoom-in\" title=\"Zoom in\">+</button>
+<span id=\"status\"></span>
+<button id=\"download\" title=\"Download\">Download</button>
+</div>
+<div id=\"pages\">{pages}</div>
+<script>
+const pages = [...document.querySelectorAll('.page')];
+const pageInput = document.getElementById('page');
+let zoom = 1;
+function visible(page) {{
+    const rect = page.getBoundingClientRect();
+    return rect.bottom > -innerHeight && rect.top < innerHeight * 2;
+}}
+function render() {{
+    for (const page of pages) {{
+        if (!visible(page)) continue;
+        const width = Math.ceil(page.clientWidth * devicePixelRatio);
+        if (Number(page.dataset.width) >= width) continue;
+        const src = prompt('{PAGE_PROMPT}' + page.dataset.index + ':' + width);
+        if (src) {{
+            page.src = src;
+            page.dataset.width = width;
+        }}
+    }}
+}}
+function currentPage() {{
+    const index = pages.findIndex((page) => page.getBoundingClientRect().bottom > 56);
+    return index < 0 ? pages.length - 1 : index;
+}}
+function goTo(index) {{
+    index = Math.max(0, Math.min(pages.length - 1, index));
+    scrollTo(0, pages[index].offsetTop - 56);
+}}
+function setZoom(value) {{
+    const index = currentPage();
+    zoom = Math.max(0.25, Math.min(4, value));
+    document.body.style.setProperty('--zoom', zoom);
+    document.getElementById('zoom').textContent = Math.round(zoom * 100) + '%';
+    goTo(index);
+    render();
+}}
+document.getElementById('previous').onclick = () => goTo(currentPage() - 1);
+document.getElementById('next').onclick = () => goTo(currentPage() + 1);
+pageInput.onchange = () => goTo(pageInput.valueAsNumber - 1);
+document.getElementById('zoom-out').onclick = () => setZoom(zoom / 1.25);
+document.getElementById('zoom-in').onclick = () => setZoom(zoom * 1.25);
+document.getElementById('download').onclick = () => {{
+    const path = prompt('{DOWNLOAD_PROMPT}');
+    document.getElementById('status').textContent = path ? 'Saved to ' + path : 'Download failed';
+}};
+addEventListener('scroll', () => {{
+    pageInput.value = currentPage() + 1;
+    render();
+}});
+addEventListener('resize', render);
+render();
+</script>
+</body>
+</html>",
+        count = sizes.len(),
+    )
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,389 @@
+//! Policies set by administrators to lock down Verso, read from a JSON file when the
+//! configuration is created with [`crate::config::Config::new`].
+//!
+//! The file is read from the path in the `VERSO_POLICIES` environment variable, or else from
+//! `/etc/verso/policies.json` (`%ProgramData%\Verso\policies.json` on Windows). Errors in the
+//! file are logged and reported by [`crate::Verso::active_policies`], and never prevent Verso
+//! from starting: a file which isn't valid JSON sets no policy, and invalid entries are skipped.
+//!
+//! ```json
+//! {
+//!     "prefs": {
+//!         "dom.webgpu.enabled": false,
+//!         "layout.threads": { "value": 4, "level": "default" }
+//!     },
+//!     "homepage": "https://intranet.example.com/",
+//!     "url_denylist": ["*://*/*"],
+//!     "url_allowlist": ["https://*.example.com/*"],
+//!     "disable_downloads": true,
+//!     "disable_devtools": true,
+//!     "disable_private_mode": true
+//! }
+//! ```
+//!
+//! Preferences and the homepage are mandatory when given as a plain value, so users can't
+//! override them with [`crate::config::Config::pref_overrides`] or
+//! [`crate::config::Config::homepage`]. With `"level": "default"`, they replace the built-in
+//! default and users can still override them.
+//!
+//! URL lists use the match patterns of [`crate::content_scripts`]. Navigations to URLs matching
+//! the deny list are blocked unless they also match the allow list, so denying `*://*/*` and
+//! allowing some origins only allows those. The lists apply to [`crate::Verso::load_url`] and to
+//! navigations started by pages.
+//!
+//! Disabling downloads stops the PDF viewer from saving documents and pages from downloading
+//! files. See [`crate::downloads`]. Disabling developer tools turns off Servo's devtools server
+//! and [`crate::Verso::enable_remote_debugging`]. Verso has no private mode itself, so embedders
+//! offering one should check [`ActivePolicies::private_mode_disabled`].
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+use servo_url::ServoUrl;
+
+use crate::{
+    content_scripts::{parse_pattern, MatchPattern},
+    prefs::PrefValue,
+};
+
+/// Environment variable with the path of the policy file, replacing the system path.
+pub const POLICY_FILE_ENV: &str = "VERSO_POLICIES";
+
+/// Page loaded in new windows when neither a policy nor the user sets a homepage.
+const DEFAULT_HOMEPAGE: &str = "https://example.com";
+
+/// Whether users can override a policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PolicyLevel {
+    /// The policy value is used whatever users set.
+    Mandatory,
+    /// The policy value replaces the built-in default, and users can override it.
+    Default,
+}
+
+/// Where the value of a preference or of the homepage comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrefSource {
+    /// A policy, mandatory or default.
+    Policy,
+    /// The user, through the configuration.
+    User,
+    /// The built-in default, like `prefs.json` of the resources directory.
+    Default,
+}
+
+/// Preference set by a policy.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PolicyPref {
+    /// Key of the preference, like `dom.webgpu.enabled`.
+    pub key: String,
+    /// Value of the preference.
+    pub value: PrefValue,
+    /// Whether users can override it.
+    pub level: PolicyLevel,
+}
+
+/// Policies read from the policy file. See [`Policies::active`] for their effect.
+#[derive(Clone, Debug, Default)]
+pub struct Policies {
+    /// Path of the file the policies were read from, if one exists.
+    pub(crate) source: Option<PathBuf>,
+    /// Preferences set by the policies.
+    pub(crate) prefs: Vec<PolicyPref>,
+    /// Homepage of new windows.
+    pub(crate) homepage: Option<(ServoUrl, PolicyLevel)>,
+    /// Patterns of the URLs allowed despite the deny list.
+    pub(crate) url_allowlist: Vec<String>,
+    /// Patterns of the URLs pages can't navigate to.
+    pub(crate) url_denylist: Vec<String>,
+    /// Stop the PDF viewer and pages from saving files.
+    pub(crate) disable_downloads: bool,
+    /// Turn off the devtools server, remote debugging and remote control.
+    pub(crate) disable_devtools: bool,
+    /// Tell embedders not to offer a private mode.
+    pub(crate) disable_private_mode: bool,
+    /// Errors of the policy file. The entries with errors are skipped.
+    pub(crate) errors: Vec<String>,
+    allow_patterns: Vec<MatchPattern>,
+    deny_patterns: Vec<MatchPattern>,
+}
+
+impl Policies {
+    /// Read the policy file from the path in [`POLICY_FILE_ENV`] or the system path. Return no
+    /// policy if there's no file.
+    pub fn load() -> Self {
+        let path = match std::env::var_os(POLICY_FILE_ENV) {
+            Some(path) => PathBuf::from(path),
+            None => match system_policy_file() {
+                Some(path) if path.exists() => path,
+                _ => return Self::default(),
+            },
+        };
+        Self::from_file(&path)
+    }
+
+    /// Read the policies of the file.
+    pub fn from_file(path: &Path) -> Self {
+        let mut policies = match fs::read_to_string(path) {
+            Ok(json) => Self::parse(&json),
+            Err(e) => Self {
+                errors: vec![format!("can't read {}: {e}", path.display())],
+                ..Self::default()
+            },
+        };
+        policies.source = Some(path.to_path_buf());
+        for error in &policies.errors {
+            log::error!("Verso policy file {}: {error}", path.display());
+        }
+        policies
+    }
+
+    /// Parse the policies of a policy file, skipping the invalid entries.
+    pub fn parse(json: &str) -> Self {
+        let mut policies = Self::default();
+        let root = match serde_json::from_str::<Value>(json) {
+            Ok(Value::Object(root)) => root,
+            Ok(_) => {
+                policies.errors.push("the policies aren't an object".into());
+                return policies;
+            }
+            Err(e) => {
+                policies.errors.push(format!("invalid JSON: {e}"));
+                return policies;
+            }
+        };
+        for (name, value) in root {
+            let result = match name.as_str() {
+                "prefs" => policies.parse_prefs(value),
+                "homepage" => policies.parse_homepage(value),
+                "url_allowlist" => parse_url_list(value).map(|(list, patterns)| {
+                    policies.url_allowlist = list;
+                    policies.allow_patterns = patterns;
+                }),
+                "url_denylist" => parse_url_list(value).map(|(list, patterns)| {
+                    policies.url_denylist = list;
+                    policies.deny_patterns = patterns;
+                }),
+                "disable_downloads" => parse_bool(value).map(|v| policies.disable_downloads = v),
+                "disable_devtools" => parse_bool(value).map(|v| policies.disable_devtools = v),
+                "disable_private_mode" => {
+                    parse_bool(value).map(|v| policies.disable_private_mode = v)
+                }
+                _ => Err("unknown policy".into()),
+            };
+            if let Err(e) = result {
+                policies.errors.push(format!("{name}: {e}"));
+            }
+        }
+        policies
+    }
+
+    fn parse_prefs(&mut self, value: Value) -> Result<(), String> {
+        let Value::Object(prefs) = value else {
+            return Err("must be an object".into());
+        };
+        for (key, value) in prefs {
+            let parsed = parse_leveled(value).and_then(|(value, level)| {
+                let value = match value {
+                    Value::Bool(b) => PrefValue::Bool(b),
+                    Value::Number(n) => match n.as_i64() {
+                        Some(int) => PrefValue::Int(int),
+                        None => PrefValue::Float(n.as_f64().unwrap_or_default()),
+                    },
+                    Value::String(s) => PrefValue::Str(s),
+                    _ => return Err("must be a boolean, a number or a string".to_string()),
+                };
+                Ok((value, level))
+            });
+            match parsed {
+                Ok((value, level)) => self.prefs.push(PolicyPref { key, value, level }),
+                Err(e) => self.errors.push(format!("prefs.{key}: {e}")),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_homepage(&mut self, value: Value) -> Result<(), String> {
+        let (value, level) = parse_leveled(value)?;
+        let Value::String(url) = value else {
+            return Err("must be a URL".into());
+        };
+        let url = ServoUrl::parse(&url).map_err(|e| format!("invalid URL {url}: {e}"))?;
+        self.homepage = Some((url, level));
+        Ok(())
+    }
+
+    /// Preferences of the policies at the level.
+    pub(crate) fn prefs_at(&self, level: PolicyLevel) -> Vec<(String, PrefValue)> {
+        self.prefs
+            .iter()
+            .filter(|pref| pref.level == level)
+            .map(|pref| (pref.key.clone(), pref.value.clone()))
+            .collect()
+    }
+
+    /// Return false if the URL matches the deny list and not the allow list.
+    pub fn allows_url(&self, url: &ServoUrl) -> bool {
+        let url = url.as_url();
+        !self.deny_patterns.iter().any(|p| p.matches(url))
+            || self.allow_patterns.iter().any(|p| p.matches(url))
+    }
+
+    /// Resolve the policies against the options of the user.
+    pub fn active(
+        &self,
+        user_prefs: &[(String, PrefValue)],
+        user_homepage: Option<&ServoUrl>,
+    ) -> ActivePolicies {
+        let mut prefs: Vec<ActivePref> = Vec::new();
+        let mut set = |key: &str, value: &PrefValue, source, locked| {
+            let pref = ActivePref {
+                key: key.to_string(),
+                value: value.clone(),
+                source,
+                locked,
+            };
+            match prefs.iter_mut().find(|p| p.key == key) {
+                Some(existing) => *existing = pref,
+                None => prefs.push(pref),
+            }
+        };
+        // Later values take precedence, like when the preferences are set.
+        for (key, value) in self.prefs_at(PolicyLevel::Default) {
+            set(&key, &value, PrefSource::Policy, false);
+        }
+        for (key, value) in user_prefs {
+            set(key, value, PrefSource::User, false);
+        }
+        for (key, value) in self.prefs_at(PolicyLevel::Mandatory) {
+            set(&key, &value, PrefSource::Policy, true);
+        }
+
+        let homepage = match (&self.homepage, user_homepage) {
+            (Some((url, PolicyLevel::Mandatory)), _) => (url.clone(), PrefSource::Policy),
+            (_, Some(url)) => (url.clone(), PrefSource::User),
+            (Some((url, PolicyLevel::Default)), None) => (url.clone(), PrefSource::Policy),
+            (None, None) => (
+                ServoUrl::parse(DEFAULT_HOMEPAGE).expect("Default homepage is valid"),
+                PrefSource::Default,
+            ),
+        };
+
+        ActivePolicies {
+            source: self.source.clone(),
+            prefs,
+            homepage,
+            homepage_locked: matches!(self.homepage, Some((_, PolicyLevel::Mandatory))),
+            url_allowlist: self.url_allowlist.clone(),
+            url_denylist: self.url_denylist.clone(),
+            downloads_disabled: self.disable_downloads,
+            devtools_disabled: self.disable_devtools,
+            private_mode_disabled: self.disable_private_mode,
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+/// Effective value of a preference set by a policy or the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActivePref {
+    /// Key of the preference.
+    pub key: String,
+    /// Value in effect.
+    pub value: PrefValue,
+    /// Where the value comes from.
+    pub source: PrefSource,
+    /// Whether a mandatory policy sets it, so users can't change it.
+    pub locked: bool,
+}
+
+/// Effective policies and where preferences come from, returned by
+/// [`crate::Verso::active_policies`], like for a page listing preferences.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActivePolicies {
+    /// Path of the policy file, if one exists.
+    pub source: Option<PathBuf>,
+    /// Preferences set by policies or the user. Others have their built-in default.
+    pub prefs: Vec<ActivePref>,
+    /// Homepage of new windows and where it comes from.
+    pub homepage: (ServoUrl, PrefSource),
+    /// Whether a mandatory policy sets the homepage.
+    pub homepage_locked: bool,
+    /// Patterns of the URLs allowed despite the deny list.
+    pub url_allowlist: Vec<String>,
+    /// Patterns of the URLs pages can't navigate to.
+    pub url_denylist: Vec<String>,
+    /// Whether downloads are disabled.
+    pub downloads_disabled: bool,
+    /// Whether developer tools are disabled.
+    pub devtools_disabled: bool,
+    /// Whether embedders shouldn't offer a private mode.
+    pub private_mode_disabled: bool,
+    /// Errors of the policy file.
+    pub errors: Vec<String>,
+}
+
+impl ActivePolicies {
+    /// Where the value of the preference comes from.
+    pub fn pref_source(&self, key: &str) -> PrefSource {
+        self.prefs
+            .iter()
+            .find(|pref| pref.key == key)
+            .map_or(PrefSource::Default, |pref| pref.source)
+    }
+}
+
+#[cfg(windows)]
+fn system_policy_file() -> Option<PathBuf> {
+    std::env::var_os("ProgramData")
+        .map(|dir| PathBuf::from(dir).join("Verso").join("policies.json"))
+}
+
+#[cfg(not(windows))]
+fn system_policy_file() -> Option<PathBuf> {
+    Some(PathBuf::from("/etc/verso/policies.json"))
+}
+
+/// Parse a plain value, which is mandatory, or a `{ "value", "level" }` object.
+fn parse_leveled(value: Value) -> Result<(Value, PolicyLevel), String> {
+    let Value::Object(mut object) = value else {
+        return Ok((value, PolicyLevel::Mandatory));
+    };
+    let level = match object.remove("level") {
+        None => PolicyLevel::Mandatory,
+        Some(Value::String(level)) if level == "mandatory" => PolicyLevel::Mandatory,
+        Some(Value::String(level)) if level == "default" => PolicyLevel::Default,
+        Some(level) => return Err(format!("level {level} isn't \"mandatory\" or \"default\"")),
+    };
+    let value = object
+        .remove("value")
+        .ok_or_else(|| "missing value".to_string())?;
+    if let Some(key) = object.keys().next() {
+        return Err(format!("unknown key {key}"));
+    }
+    Ok((value, level))
+}
+
+fn parse_bool(value: Value) -> Result<bool, String> {
+    value.as_bool().ok_or_else(|| "must be a boolean".into())
+}
+
+fn parse_url_list(value: Value) -> Result<(Vec<String>, Vec<MatchPattern>), String> {
+    let Value::Array(items) = value else {
+        return Err("must be a list of URL patterns".into());
+    };
+    let mut list = Vec::new();
+    let mut patterns = Vec::new();
+    for item in items {
+        let Value::String(pattern) = item else {
+            return Err("must be a list of URL patterns".into());
+        };
+        let (pattern, parsed) = parse_pattern(&pattern).map_err(|e| e.to_string())?;
+        list.push(pattern);
+        patterns.push(parsed);
+    }
+    Ok((list, patterns))
+}
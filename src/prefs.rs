@@ -0,0 +1,66 @@
+//! Overrides of individual Servo preferences, complementing `prefs.json` in the resources
+//! directory.
+//!
+//! Overrides are set with [`crate::config::Config::pref_overrides`] and applied by
+//! [`crate::config::Config::init`], after the options of Verso which are also preferences like
+//! [`crate::config::Config::webrtc`], so they take precedence.
+
+use servo_config::prefs::pref_map;
+pub use servo_config::prefs::PrefValue;
+
+/// Set the Servo preferences. Values are checked against the type of the preference, and
+/// integers are accepted for float preferences. Unknown keys and values of the wrong type are
+/// skipped with a warning.
+///
+/// It must be called after the resources are set, which [`crate::config::Config::init`] does.
+pub fn set_overrides(pairs: &[(String, PrefValue)]) {
+    let prefs = pref_map();
+    for (key, value) in pairs {
+        let value = match (prefs.get(key), value) {
+            (PrefValue::Missing, _) => {
+                log::warn!("Verso ignores unknown preference {key}");
+                continue;
+            }
+            (PrefValue::Float(_), PrefValue::Int(int)) => PrefValue::Float(*int as f64),
+            (current, value)
+                if std::mem::discriminant(&current) != std::mem::discriminant(value) =>
+            {
+                log::warn!("Verso ignores preference {key} of type {current:?}: {value:?}");
+                continue;
+            }
+            (_, value) => value.clone(),
+        };
+        log::debug!("Verso sets preference {key} to {value:?}");
+        if let Err(e) = prefs.set(key, value) {
+            log::warn!("Verso failed to set preference {key}: {e:?}");
+        }
+    }
+}
+
+/// Parse a comma-separated list of `key=value` overrides, like
+/// `dom.webgpu.enabled=true,layout.threads=4`. Values are parsed as booleans, integers or floats
+/// when possible, and strings otherwise.
+pub fn parse_overrides(list: &str) -> crate::Result<Vec<(String, PrefValue)>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').ok_or_else(|| {
+                crate::Error::InvalidConfig(format!("preference override {pair} isn't key=value"))
+            })?;
+            Ok((key.trim().to_string(), parse_value(value.trim())))
+        })
+        .collect()
+}
+
+fn parse_value(value: &str) -> PrefValue {
+    if let Ok(value) = value.parse::<bool>() {
+        PrefValue::Bool(value)
+    } else if let Ok(value) = value.parse::<i64>() {
+        PrefValue::Int(value)
+    } else if let Ok(value) = value.parse::<f64>() {
+        PrefValue::Float(value)
+    } else {
+        PrefValue::Str(value.to_string())
+    }
+}
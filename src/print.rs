@@ -0,0 +1,121 @@
+//! Printing pages to PDF, triggered by `window.print()` or [`crate::shortcuts::ShortcutAction::Print`],
+//! which is bound to `CmdOrCtrl+P` by default.
+//!
+//! Servo can't paginate a page or write PDF yet, so print requests are passed to
+//! [`crate::VersoBuilder::on_print`], which shows the save dialog and writes the file. Without the
+//! hook, requests fail with an error logged. Either way the page receives `beforeprint` and
+//! `afterprint` events, and failures are logged to its console.
+//!
+//! Kiosks can set [`PrintConfig::save_to`], so requests carry a destination in that directory
+//! and the hook is expected to save without a dialog.
+
+use std::path::PathBuf;
+
+use servo_url::ServoUrl;
+
+/// Message the page sends with `prompt()` when it calls `window.print()`.
+pub(crate) const PRINT_PROMPT: &str = "VERSO_PRINT";
+
+/// Script replacing `window.print()`, which Servo doesn't implement, with a request to Verso.
+pub(crate) const OVERRIDE_PRINT: &str = "
+window.print = function print() {
+    dispatchEvent(new Event('beforeprint'));
+    prompt('VERSO_PRINT');
+};";
+
+/// Result of printing a page, with the error reported to the page's console.
+pub type PrintResult = Result<(), String>;
+
+/// Size of the paper a page is printed on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaperSize {
+    /// ISO A4, 210 × 297 mm.
+    #[default]
+    A4,
+    /// US Letter, 8.5 × 11 in.
+    Letter,
+    /// US Legal, 8.5 × 14 in.
+    Legal,
+}
+
+impl PaperSize {
+    /// All paper sizes, in the order a dialog should list them.
+    pub const ALL: [PaperSize; 3] = [PaperSize::A4, PaperSize::Letter, PaperSize::Legal];
+
+    /// Width and height in millimeters.
+    pub fn size_mm(self) -> (f32, f32) {
+        match self {
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::Letter => (215.9, 279.4),
+            PaperSize::Legal => (215.9, 355.6),
+        }
+    }
+}
+
+/// Printing options.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrintConfig {
+    /// Paper size selected when the dialog opens.
+    pub paper_size: PaperSize,
+    /// Directory to save PDFs to without showing a dialog, like for kiosks. `None`, the default,
+    /// shows the dialog.
+    pub save_to: Option<PathBuf>,
+}
+
+/// Request to print a page, passed to [`crate::VersoBuilder::on_print`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrintRequest {
+    /// URL of the page.
+    pub url: ServoUrl,
+    /// Paper size to preselect, or to use if there's no dialog.
+    pub paper_size: PaperSize,
+    /// File to save the PDF to without a dialog, set when [`PrintConfig::save_to`] is.
+    pub destination: Option<PathBuf>,
+}
+
+impl PrintRequest {
+    /// Create the request to print the page of the URL.
+    pub fn new(url: ServoUrl, config: &PrintConfig) -> Self {
+        let destination = config.save_to.as_ref().map(|dir| dir.join(file_name(&url)));
+        Self {
+            url,
+            paper_size: config.paper_size,
+            destination,
+        }
+    }
+}
+
+/// File name of the PDF of the page, from the last segment of its path or its host.
+fn file_name(url: &ServoUrl) -> String {
+    let stem = url
+        .as_url()
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.rsplit_once('.').map_or(segment, |(stem, _)| stem))
+        .or_else(|| url.host_str())
+        .unwrap_or("page");
+    let stem: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{stem}.pdf")
+}
+
+/// Script reporting the end of printing to the page.
+pub(crate) fn after_print_script(result: &PrintResult) -> String {
+    let error = match result {
+        Ok(()) => String::new(),
+        Err(e) => format!(
+            "console.error({});",
+            serde_json::to_string(&format!("Printing failed: {e}")).unwrap_or_default()
+        ),
+    };
+    format!("{error}dispatchEvent(new Event('afterprint'));")
+}
@@ -0,0 +1,123 @@
+//! Loading progress bar drawn by Verso over the web view, enabled with
+//! [`crate::Verso::set_builtin_progress_bar`].
+//!
+//! Servo doesn't report how many bytes or subresources are loaded, so the progress follows the
+//! stages of [`LoadStatus`]. The bar eases towards the progress of the current stage, fills up when
+//! the load completes and then fades out.
+
+use std::time::{Duration, Instant};
+
+use crate::webview::LoadStatus;
+
+/// Time for the bar to cover most of the distance to the progress of the current stage.
+const EASE_TIME: Duration = Duration::from_millis(250);
+/// Time for the bar to fade out after the load completes.
+const FADE_TIME: Duration = Duration::from_millis(300);
+/// Shortest time between two frames of the animation.
+const FRAME_TIME: Duration = Duration::from_millis(16);
+
+/// Edge of the web view the progress bar is drawn at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProgressBarPosition {
+    /// Top edge of the web view.
+    #[default]
+    Top,
+    /// Bottom edge of the web view.
+    Bottom,
+}
+
+/// Appearance of the built-in progress bar.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProgressBarStyle {
+    /// Color as RGBA components between 0 and 1.
+    pub color: [f32; 4],
+    /// Height in device pixels.
+    pub height: u32,
+    /// Edge of the web view the bar is drawn at.
+    pub position: ProgressBarPosition,
+}
+
+impl Default for ProgressBarStyle {
+    fn default() -> Self {
+        Self {
+            color: [0.2, 0.5, 1.0, 1.0],
+            height: 3,
+            position: ProgressBarPosition::Top,
+        }
+    }
+}
+
+/// Animated state of the progress bar of a window.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct ProgressBar {
+    /// Drawn fraction of the width.
+    pub progress: f32,
+    /// Opacity of the bar, 0 when it's hidden.
+    pub opacity: f32,
+    /// Whether the bar is moving or fading.
+    animating: bool,
+    last_tick: Option<Instant>,
+}
+
+impl ProgressBar {
+    /// Advance the animation to `now` for the load status. Return true if the bar changed, so it
+    /// needs to be drawn again.
+    pub fn tick(&mut self, status: LoadStatus, now: Instant) -> bool {
+        if self.last_tick.map_or(false, |last| {
+            now.saturating_duration_since(last) < FRAME_TIME
+        }) {
+            return false;
+        }
+        let elapsed = self
+            .last_tick
+            .map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        let old = (self.progress, self.opacity);
+        let target = stage_progress(status);
+        match target {
+            None => *self = Self::default(),
+            Some(target) => {
+                if self.opacity == 0.0 || target < self.progress {
+                    // A new load starts from the left edge.
+                    if status != LoadStatus::Complete {
+                        self.progress = 0.0;
+                        self.opacity = 1.0;
+                    }
+                }
+                if self.opacity > 0.0 {
+                    let ease = 1.0 - (-elapsed.as_secs_f32() * 3.0 / EASE_TIME.as_secs_f32()).exp();
+                    self.progress += (target - self.progress) * ease;
+                    if target - self.progress < 0.005 {
+                        self.progress = target;
+                    }
+                    if status == LoadStatus::Complete && self.progress == target {
+                        self.opacity = (self.opacity
+                            - elapsed.as_secs_f32() / FADE_TIME.as_secs_f32())
+                        .max(0.0);
+                    }
+                }
+            }
+        }
+        self.animating =
+            self.opacity > 0.0 && (target != Some(self.progress) || status == LoadStatus::Complete);
+        self.last_tick = self.animating.then_some(now);
+        old != (self.progress, self.opacity)
+    }
+
+    /// Return true if the bar is still moving or fading.
+    pub fn is_animating(&self) -> bool {
+        self.animating
+    }
+}
+
+/// Progress reached at the start of the load stage, or `None` if no load is in progress.
+fn stage_progress(status: LoadStatus) -> Option<f32> {
+    match status {
+        LoadStatus::Idle => None,
+        LoadStatus::NavigationStarted | LoadStatus::Reloading | LoadStatus::Retrying(_) => {
+            Some(0.1)
+        }
+        LoadStatus::Started => Some(0.3),
+        LoadStatus::HeadParsed => Some(0.6),
+        LoadStatus::Complete => Some(1.0),
+    }
+}
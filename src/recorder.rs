@@ -0,0 +1,527 @@
+//! Recording of input events and embedder messages to a JSON Lines file, enabled with
+//! [`crate::config::Config::record_events`], and replay of the inputs with
+//! [`crate::Verso::replay`].
+//!
+//! Each line is a [`RecordedLine`] with the milliseconds since Verso started. Inputs are fed back
+//! at their original timing divided by the speed factor. An input is also held back until the
+//! replay reached as many embedder messages as the recording had before it, for at most
+//! [`SYNC_TIMEOUT`], so inputs don't overtake slower page loads. Servo's IDs differ between
+//! sessions, so embedder messages are compared with their IDs remapped by [`IdTable`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use base::id::WebViewId;
+use embedder_traits::EmbedderMsg;
+use keyboard_types::KeyboardEvent;
+use serde::{Deserialize, Serialize};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{DeviceId, ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent},
+};
+
+use crate::{Error, Result};
+
+/// Slowest replay speed factor.
+pub const MIN_SPEED: f32 = 0.01;
+/// Fastest replay speed factor.
+pub const MAX_SPEED: f32 = 1000.0;
+/// Longest time an input is held back past its deadline, waiting for the embedder messages
+/// recorded before it.
+pub const SYNC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A line of the recording file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedLine {
+    /// Milliseconds since the recording started.
+    pub time_ms: u64,
+    /// The recorded event.
+    pub event: RecordedEvent,
+}
+
+/// An event written to the recording file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    /// Input event came from Winit. This is what will be fed back while replaying.
+    Input {
+        /// The input event.
+        input: RecordedInput,
+    },
+    /// Keyboard event converted from Winit. It's recorded after conversion because Winit's
+    /// `KeyEvent` can't be constructed outside of Winit.
+    Key {
+        /// The keyboard event.
+        event: KeyboardEvent,
+        /// Text the key inserts.
+        text: Option<String>,
+    },
+    /// Message came from Servo. This is only recorded to compare sessions and won't be replayed.
+    Embedder {
+        /// The web view which the message belongs to.
+        webview: Option<String>,
+        /// Debug representation of the message.
+        message: String,
+    },
+}
+
+/// Winit input events that can be recorded and replayed. Keyboard inputs are recorded as
+/// [`RecordedEvent::Key`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordedInput {
+    /// Window is resized.
+    Resized {
+        /// Width in physical pixels.
+        width: u32,
+        /// Height in physical pixels.
+        height: u32,
+    },
+    /// Window gains or loses focus.
+    Focused {
+        /// If the window is focused.
+        focused: bool,
+    },
+    /// Cursor enters the window.
+    CursorEntered,
+    /// Cursor moves in the window.
+    CursorMoved {
+        /// Physical x position.
+        x: f64,
+        /// Physical y position.
+        y: f64,
+    },
+    /// Mouse button is pressed or released.
+    MouseInput {
+        /// True if it's pressed.
+        pressed: bool,
+        /// 0 for left, 1 for right, and 2 for middle button.
+        button: u16,
+    },
+    /// Mouse wheel or touchpad scrolls.
+    MouseWheel {
+        /// Horizontal delta.
+        x: f64,
+        /// Vertical delta.
+        y: f64,
+        /// True if the delta is in pixels, false if it's in lines.
+        pixel: bool,
+        /// 0 for started, 1 for moved, 2 for ended, 3 for cancelled.
+        phase: u8,
+    },
+    /// Touchpad pinch gesture.
+    PinchGesture {
+        /// Magnification delta.
+        delta: f64,
+        /// 0 for started, 1 for moved, 2 for ended, 3 for cancelled.
+        phase: u8,
+    },
+}
+
+impl RecordedInput {
+    /// Convert Winit window event to a recordable input. Return `None` if it's not supported.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        Some(match event {
+            WindowEvent::Resized(size) => RecordedInput::Resized {
+                width: size.width,
+                height: size.height,
+            },
+            WindowEvent::Focused(focused) => RecordedInput::Focused { focused: *focused },
+            WindowEvent::CursorEntered { .. } => RecordedInput::CursorEntered,
+            WindowEvent::CursorMoved { position, .. } => RecordedInput::CursorMoved {
+                x: position.x,
+                y: position.y,
+            },
+            WindowEvent::MouseInput { state, button, .. } => RecordedInput::MouseInput {
+                pressed: *state == ElementState::Pressed,
+                button: match button {
+                    MouseButton::Left => 0,
+                    MouseButton::Right => 1,
+                    MouseButton::Middle => 2,
+                    _ => return None,
+                },
+            },
+            WindowEvent::MouseWheel { delta, phase, .. } => {
+                let (x, y, pixel) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (*x as f64, *y as f64, false),
+                    MouseScrollDelta::PixelDelta(p) => (p.x, p.y, true),
+                };
+                RecordedInput::MouseWheel {
+                    x,
+                    y,
+                    pixel,
+                    phase: phase_to_u8(*phase),
+                }
+            }
+            WindowEvent::PinchGesture { delta, phase, .. } => RecordedInput::PinchGesture {
+                delta: *delta,
+                phase: phase_to_u8(*phase),
+            },
+            _ => return None,
+        })
+    }
+
+    /// Convert the recorded input back to Winit window event.
+    pub fn to_window_event(self) -> WindowEvent {
+        // SAFETY: The dummy device ID is only used to fill the field and never compared with
+        // real device IDs.
+        let device_id = unsafe { DeviceId::dummy() };
+        match self {
+            RecordedInput::Resized { width, height } => {
+                WindowEvent::Resized(PhysicalSize::new(width, height))
+            }
+            RecordedInput::Focused { focused } => WindowEvent::Focused(focused),
+            RecordedInput::CursorEntered => WindowEvent::CursorEntered { device_id },
+            RecordedInput::CursorMoved { x, y } => WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(x, y),
+            },
+            RecordedInput::MouseInput { pressed, button } => WindowEvent::MouseInput {
+                device_id,
+                state: if pressed {
+                    ElementState::Pressed
+                } else {
+                    ElementState::Released
+                },
+                button: match button {
+                    0 => MouseButton::Left,
+                    1 => MouseButton::Right,
+                    _ => MouseButton::Middle,
+                },
+            },
+            RecordedInput::MouseWheel { x, y, pixel, phase } => WindowEvent::MouseWheel {
+                device_id,
+                delta: if pixel {
+                    MouseScrollDelta::PixelDelta(PhysicalPosition::new(x, y))
+                } else {
+                    MouseScrollDelta::LineDelta(x as f32, y as f32)
+                },
+                phase: phase_from_u8(phase),
+            },
+            RecordedInput::PinchGesture { delta, phase } => WindowEvent::PinchGesture {
+                device_id,
+                delta,
+                phase: phase_from_u8(phase),
+            },
+        }
+    }
+}
+
+fn phase_to_u8(phase: TouchPhase) -> u8 {
+    match phase {
+        TouchPhase::Started => 0,
+        TouchPhase::Moved => 1,
+        TouchPhase::Ended => 2,
+        TouchPhase::Cancelled => 3,
+    }
+}
+
+fn phase_from_u8(phase: u8) -> TouchPhase {
+    match phase {
+        0 => TouchPhase::Started,
+        2 => TouchPhase::Ended,
+        3 => TouchPhase::Cancelled,
+        _ => TouchPhase::Moved,
+    }
+}
+
+/// Recorder writing input events and embedder messages to a JSON Lines file.
+pub struct EventRecorder {
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl EventRecorder {
+    /// Create a recorder writing to the provided path. The file will be truncated.
+    pub fn new(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            start: Instant::now(),
+        })
+    }
+
+    /// Record a Winit window event if it's a supported input.
+    pub fn record_input(&mut self, event: &WindowEvent) {
+        if let Some(input) = RecordedInput::from_window_event(event) {
+            self.write(RecordedEvent::Input { input });
+        }
+    }
+
+    /// Record a keyboard event with the text the key inserts.
+    pub fn record_key(&mut self, event: &KeyboardEvent, text: Option<&str>) {
+        self.write(RecordedEvent::Key {
+            event: event.clone(),
+            text: text.map(str::to_owned),
+        });
+    }
+
+    /// Record a message came from Servo.
+    pub fn record_embedder(&mut self, webview: Option<WebViewId>, message: &EmbedderMsg) {
+        self.write(RecordedEvent::Embedder {
+            webview: webview.map(|id| id.to_string()),
+            message: format!("{message:?}"),
+        });
+    }
+
+    fn write(&mut self, event: RecordedEvent) {
+        let line = RecordedLine {
+            time_ms: self.start.elapsed().as_millis() as u64,
+            event,
+        };
+        let result = serde_json::to_writer(&mut self.writer, &line)
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"));
+        if let Err(e) = result {
+            log::warn!("Verso failed to record event: {e}");
+        }
+    }
+}
+
+/// Table remapping Servo's IDs, which differ between sessions, to their order of appearance.
+#[derive(Debug, Default)]
+pub struct IdTable {
+    ids: HashMap<String, HashMap<String, usize>>,
+}
+
+impl IdTable {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return `{kind}#{n}`, where `n` is the order of the first appearance of `raw` among the IDs
+    /// of this kind.
+    pub fn id(&mut self, kind: &str, raw: &str) -> String {
+        let ids = self.ids.entry(kind.to_string()).or_default();
+        let next = ids.len();
+        let n = *ids.entry(raw.to_string()).or_insert(next);
+        format!("{kind}#{n}")
+    }
+
+    /// Replace the IDs in the debug representation of a message, like `PipelineId { .. }` or
+    /// `TopLevelBrowsingContextId(..)`, with [`IdTable::id`] of their type name.
+    pub fn remap(&mut self, text: &str) -> String {
+        let bytes = text.as_bytes();
+        let mut remapped = String::with_capacity(text.len());
+        let mut copied = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            if !bytes[i].is_ascii_alphabetic() {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            let name = &text[start..i];
+            if !name.ends_with("Id") {
+                continue;
+            }
+            let open = if text[i..].starts_with('(') {
+                i
+            } else if text[i..].starts_with(" {") {
+                i + 1
+            } else {
+                continue;
+            };
+            let Some(end) = closing_bracket(bytes, open) else {
+                continue;
+            };
+            remapped.push_str(&text[copied..start]);
+            remapped.push_str(&self.id(name, &text[start..=end]));
+            i = end + 1;
+            copied = i;
+        }
+        remapped.push_str(&text[copied..]);
+        remapped
+    }
+
+    /// Remap the ID of the web view and the IDs in the message.
+    fn remap_message(&mut self, webview: Option<&str>, message: &str) -> String {
+        let webview = match webview {
+            Some(webview) => self.id("WebView", webview),
+            None => "-".to_string(),
+        };
+        format!("{webview} {}", self.remap(message))
+    }
+}
+
+/// Return the index of the bracket closing the one at `open`.
+fn closing_bracket(bytes: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, byte) in bytes.iter().enumerate().skip(open) {
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Input fed back by [`EventReplayer`].
+#[derive(Clone, Debug)]
+pub enum ReplayedInput {
+    /// Winit window event.
+    Window(WindowEvent),
+    /// Keyboard event with the text the key inserts.
+    Key(KeyboardEvent, Option<String>),
+}
+
+/// Embedder messages of a recording and of its replay, with IDs remapped by [`IdTable`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Messages of the recording.
+    pub recorded: Vec<String>,
+    /// Messages received while replaying.
+    pub replayed: Vec<String>,
+}
+
+impl ReplayReport {
+    /// Index of the first message which differs, or `None` if the replay matches the recording.
+    pub fn first_divergence(&self) -> Option<usize> {
+        self.recorded
+            .iter()
+            .zip(&self.replayed)
+            .position(|(recorded, replayed)| recorded != replayed)
+            .or_else(|| {
+                (self.recorded.len() != self.replayed.len())
+                    .then(|| self.recorded.len().min(self.replayed.len()))
+            })
+    }
+}
+
+/// A recorded input waiting to be replayed.
+struct PendingInput {
+    /// Time since the recording started.
+    time: Duration,
+    /// Number of embedder messages recorded before the input.
+    after: usize,
+    /// The input.
+    input: ReplayedInput,
+}
+
+/// Replayer feeding recorded input events back at their original timing, scaled by a speed
+/// factor, and collecting the embedder messages of the replay to compare them with the recording.
+pub struct EventReplayer {
+    inputs: VecDeque<PendingInput>,
+    start: Instant,
+    speed: f32,
+    report: ReplayReport,
+    ids: IdTable,
+}
+
+impl EventReplayer {
+    /// Load the input events from a recording file. A speed of `2.0` replays twice as fast as
+    /// the original session. The speed must be between [`MIN_SPEED`] and [`MAX_SPEED`].
+    pub fn new(path: &Path, speed: f32) -> Result<Self> {
+        if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+            return Err(Error::InvalidConfig(format!(
+                "replay speed {speed} isn't between {MIN_SPEED} and {MAX_SPEED}"
+            )));
+        }
+        let mut inputs = VecDeque::new();
+        let mut recorded = Vec::new();
+        let mut ids = IdTable::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line: RecordedLine = serde_json::from_str(&line).map_err(std::io::Error::from)?;
+            let input = match line.event {
+                RecordedEvent::Input { input } => ReplayedInput::Window(input.to_window_event()),
+                RecordedEvent::Key { event, text } => ReplayedInput::Key(event, text),
+                RecordedEvent::Embedder { webview, message } => {
+                    recorded.push(ids.remap_message(webview.as_deref(), &message));
+                    continue;
+                }
+            };
+            inputs.push_back(PendingInput {
+                time: Duration::from_millis(line.time_ms),
+                after: recorded.len(),
+                input,
+            });
+        }
+        Ok(Self {
+            inputs,
+            start: Instant::now(),
+            speed,
+            report: ReplayReport {
+                recorded,
+                replayed: Vec::new(),
+            },
+            ids: IdTable::new(),
+        })
+    }
+
+    /// Return the next input if it's due, without removing it. Call [`EventReplayer::advance`]
+    /// once it's dispatched.
+    pub fn due(&self, now: Instant) -> Option<ReplayedInput> {
+        let input = self.inputs.front()?;
+        let deadline = self.deadline(input);
+        let synced = self.report.replayed.len() >= input.after;
+        if now < deadline || (!synced && now < deadline + SYNC_TIMEOUT) {
+            return None;
+        }
+        Some(input.input.clone())
+    }
+
+    /// Remove the next input once it's dispatched.
+    pub fn advance(&mut self) {
+        if let Some(input) = self.inputs.pop_front() {
+            if self.report.replayed.len() < input.after {
+                log::warn!(
+                    "Verso replayed an input after {} embedder messages instead of {}",
+                    self.report.replayed.len(),
+                    input.after
+                );
+            }
+        }
+    }
+
+    /// Collect a message came from Servo while replaying.
+    pub fn observe(&mut self, webview: Option<WebViewId>, message: &EmbedderMsg) {
+        let webview = webview.map(|id| id.to_string());
+        let message = self
+            .ids
+            .remap_message(webview.as_deref(), &format!("{message:?}"));
+        self.report.replayed.push(message);
+    }
+
+    /// The instant when the replayer needs to be checked again for the next input.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let input = self.inputs.front()?;
+        let deadline = self.deadline(input);
+        if Instant::now() < deadline || self.report.replayed.len() >= input.after {
+            Some(deadline)
+        } else {
+            Some(deadline + SYNC_TIMEOUT)
+        }
+    }
+
+    fn deadline(&self, input: &PendingInput) -> Instant {
+        self.start + input.time.div_f32(self.speed)
+    }
+
+    /// Return true if all input events are replayed.
+    pub fn is_finished(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Stop replaying and return the embedder messages of the recording and of the replay.
+    pub fn into_report(self) -> ReplayReport {
+        self.report
+    }
+}
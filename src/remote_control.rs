@@ -0,0 +1,382 @@
+//! HTTP interface to control a running Verso from scripts, like in headless and kiosk
+//! deployments, started with [`crate::Verso::enable_remote_control`].
+//!
+//! The server only listens on `127.0.0.1` and rejects connections which don't come from
+//! loopback. Requests must carry the token in an `Authorization: Bearer <token>` header. The
+//! token is read from the `VERSO_REMOTE_CONTROL_TOKEN` environment variable, or generated when
+//! the server starts, and returned by [`crate::Verso::enable_remote_control`]. Requests beyond
+//! [`RATE_LIMIT`] per second are answered with 429, and requests arriving while [`WORKERS`]
+//! requests are already waiting are answered with 503. The following endpoints act on the current
+//! window and answer JSON, except the screenshot:
+//!
+//! - `GET /status`, answered with the `url`, `title`, `load_status` and `page_zoom`
+//! - `POST /navigate` with `{ "url": ... }`
+//! - `POST /reload`, `POST /back` and `POST /forward`
+//! - `GET /screenshot`, answered with a PNG of the web view
+//! - `POST /evaluate` with `{ "script": ... }`, evaluated as the body of a function like in
+//!   [`crate::Verso::evaluate_script`], answered with the returned `result`
+//! - `POST /zoom` with `{ "page_zoom": ... }`
+//! - `POST /shutdown`
+//!
+//! The listening thread checks the address, the rate and the token of requests, and passes the
+//! accepted ones to a fixed pool of worker threads. Workers hand them to the thread of the event
+//! loop, which is woken up through its proxy, and answer them once their result is ready. Errors
+//! are answered with `{ "error": ... }`. The server stops when Verso shuts down.
+
+use std::{
+    io::{self, Cursor, Read},
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender, TrySendError};
+use script_traits::webdriver_msg::{WebDriverJSResult, WebDriverJSValue};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+use winit::event_loop::EventLoopProxy;
+
+/// Environment variable holding the token of the server.
+pub const TOKEN_VARIABLE: &str = "VERSO_REMOTE_CONTROL_TOKEN";
+
+/// Number of requests accepted per second, with bursts of up to twice as many.
+pub const RATE_LIMIT: u32 = 20;
+
+/// Number of threads handling accepted requests, and of accepted requests waiting for one.
+pub const WORKERS: usize = 4;
+
+/// Time to wait for the event loop to answer a request.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest request body read, in bytes.
+const MAX_BODY: u64 = 1 << 20;
+
+/// Command of a request, run on the thread of the event loop.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RemoteCommand {
+    Status,
+    Navigate(String),
+    Reload,
+    Back,
+    Forward,
+    Screenshot,
+    Evaluate(String),
+    Zoom(f32),
+    Shutdown,
+}
+
+/// Answer to a request.
+#[derive(Clone, Debug)]
+pub(crate) enum RemoteResponse {
+    Json(Value),
+    Png(Vec<u8>),
+    Error(u16, String),
+}
+
+impl RemoteResponse {
+    /// An empty JSON object, answering commands without a result.
+    pub fn ok() -> Self {
+        Self::Json(json!({}))
+    }
+}
+
+/// A request waiting for the event loop.
+pub(crate) struct RemoteRequest {
+    pub command: RemoteCommand,
+    pub reply: Sender<RemoteResponse>,
+}
+
+/// Handle of the running server, which stops it when dropped.
+pub(crate) struct RemoteControlServer {
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+    receiver: Receiver<RemoteRequest>,
+    address: SocketAddr,
+    token: String,
+    /// Replies waiting for the next captured frame.
+    pub screenshot_replies: Vec<Sender<RemoteResponse>>,
+}
+
+impl RemoteControlServer {
+    /// Start listening on the port, or a free one with `0`.
+    pub fn start(port: u16, proxy: EventLoopProxy<()>) -> io::Result<Self> {
+        let token = match std::env::var(TOKEN_VARIABLE) {
+            Ok(token) if !token.is_empty() => token,
+            _ => crate::token::generate()?,
+        };
+        let server = Server::http((Ipv4Addr::LOCALHOST, port)).map_err(io::Error::other)?;
+        let address = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| io::Error::other("server isn't listening on an IP address"))?;
+        let server = Arc::new(server);
+        let (sender, receiver) = unbounded();
+        let (work_sender, work_receiver) = bounded::<Request>(WORKERS);
+        // Workers stop once the listening thread drops the sender of accepted requests.
+        for _ in 0..WORKERS {
+            let work_receiver = work_receiver.clone();
+            let sender = sender.clone();
+            let proxy = proxy.clone();
+            thread::Builder::new()
+                .name("RemoteControlWorker".into())
+                .spawn(move || {
+                    for request in work_receiver {
+                        handle_request(request, &sender, &proxy);
+                    }
+                })?;
+        }
+        let thread = {
+            let server = server.clone();
+            let token = token.clone();
+            let mut limiter = RateLimiter::new(Instant::now());
+            thread::Builder::new()
+                .name("RemoteControl".into())
+                .spawn(move || {
+                    for request in server.incoming_requests() {
+                        if let Some(response) = check_request(&request, &token, &mut limiter) {
+                            respond(request, response);
+                            continue;
+                        }
+                        if let Err(TrySendError::Full(request)) = work_sender.try_send(request) {
+                            respond(request, RemoteResponse::Error(503, "Verso is busy".into()));
+                        }
+                    }
+                })?
+        };
+        log::info!("Verso remote control is listening on http://{address}/");
+        Ok(Self {
+            server,
+            thread: Some(thread),
+            receiver,
+            address,
+            token,
+            screenshot_replies: Vec::new(),
+        })
+    }
+
+    /// The address the server is listening on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The token requests must carry.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Take the next pending request without blocking.
+    pub fn try_recv(&self) -> Option<RemoteRequest> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for RemoteControlServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Token bucket refilled with [`RATE_LIMIT`] requests per second.
+struct RateLimiter {
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    fn new(now: Instant) -> Self {
+        Self {
+            tokens: f64::from(RATE_LIMIT * 2),
+            last: now,
+        }
+    }
+
+    /// Take a token if any is left.
+    fn allow(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * f64::from(RATE_LIMIT)).min(f64::from(RATE_LIMIT * 2));
+        self.last = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// Return the error to answer if the request isn't from loopback, exceeds the rate or doesn't
+/// carry the token.
+fn check_request(
+    request: &Request,
+    token: &str,
+    limiter: &mut RateLimiter,
+) -> Option<RemoteResponse> {
+    if !request.remote_addr().is_some_and(|a| a.ip().is_loopback()) {
+        log::warn!("Verso remote control rejected a request not from loopback");
+        Some(RemoteResponse::Error(
+            403,
+            "Only loopback connections are accepted".into(),
+        ))
+    } else if !limiter.allow(Instant::now()) {
+        Some(RemoteResponse::Error(429, "Too many requests".into()))
+    } else if !request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|given| crate::token::matches(given, token))
+    {
+        Some(RemoteResponse::Error(
+            401,
+            "The token is missing or wrong".into(),
+        ))
+    } else {
+        None
+    }
+}
+
+/// Run the command of an accepted request and answer it.
+fn handle_request(
+    mut request: Request,
+    sender: &Sender<RemoteRequest>,
+    proxy: &EventLoopProxy<()>,
+) {
+    let response = match parse_command(&mut request) {
+        Ok(command) => run_command(command, sender, proxy),
+        Err(response) => response,
+    };
+    respond(request, response);
+}
+
+fn respond(request: Request, response: RemoteResponse) {
+    let result = match response {
+        RemoteResponse::Json(value) => request.respond(json_response(200, &value)),
+        RemoteResponse::Png(png) => {
+            let content_type =
+                Header::from_bytes("Content-Type", "image/png").expect("Content type is valid");
+            request.respond(Response::from_data(png).with_header(content_type))
+        }
+        RemoteResponse::Error(status, message) => {
+            request.respond(json_response(status, &json!({ "error": message })))
+        }
+    };
+    if let Err(e) = result {
+        log::warn!("Verso remote control failed to respond: {e}");
+    }
+}
+
+fn json_response(status: u16, value: &Value) -> Response<Cursor<Vec<u8>>> {
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("Content type is valid");
+    Response::from_data(value.to_string().into_bytes())
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+/// Read the command of the request, or the error to answer.
+fn parse_command(request: &mut Request) -> Result<RemoteCommand, RemoteResponse> {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let method = request.method().clone();
+    let mut body = String::new();
+    if method == Method::Post {
+        request
+            .as_reader()
+            .take(MAX_BODY)
+            .read_to_string(&mut body)
+            .map_err(|e| RemoteResponse::Error(400, format!("Failed to read the body: {e}")))?;
+    }
+    let params: Value = if body.trim().is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_str(&body)
+            .map_err(|e| RemoteResponse::Error(400, format!("The body isn't JSON: {e}")))?
+    };
+    let param = |name: &str| {
+        params.get(name).cloned().ok_or_else(|| {
+            RemoteResponse::Error(400, format!("The body has no {name:?} parameter"))
+        })
+    };
+    let string = |name: &str| {
+        param(name)?.as_str().map(String::from).ok_or_else(|| {
+            RemoteResponse::Error(400, format!("The {name:?} parameter isn't a string"))
+        })
+    };
+    let command = match (&method, path.as_str()) {
+        (Method::Get, "/status") => RemoteCommand::Status,
+        (Method::Post, "/navigate") => RemoteCommand::Navigate(string("url")?),
+        (Method::Post, "/reload") => RemoteCommand::Reload,
+        (Method::Post, "/back") => RemoteCommand::Back,
+        (Method::Post, "/forward") => RemoteCommand::Forward,
+        (Method::Get, "/screenshot") => RemoteCommand::Screenshot,
+        (Method::Post, "/evaluate") => RemoteCommand::Evaluate(string("script")?),
+        (Method::Post, "/zoom") => {
+            let zoom = param("page_zoom")?
+                .as_f64()
+                .filter(|zoom| zoom.is_finite() && *zoom > 0.0)
+                .ok_or_else(|| {
+                    RemoteResponse::Error(400, "The page zoom isn't a positive number".into())
+                })?;
+            RemoteCommand::Zoom(zoom as f32)
+        }
+        (Method::Post, "/shutdown") => RemoteCommand::Shutdown,
+        (
+            _,
+            "/status" | "/navigate" | "/reload" | "/back" | "/forward" | "/screenshot"
+            | "/evaluate" | "/zoom" | "/shutdown",
+        ) => {
+            return Err(RemoteResponse::Error(
+                405,
+                format!("{method} isn't allowed"),
+            ))
+        }
+        _ => return Err(RemoteResponse::Error(404, format!("{path} wasn't found"))),
+    };
+    Ok(command)
+}
+
+/// Pass the command to the event loop and wait for its answer.
+fn run_command(
+    command: RemoteCommand,
+    sender: &Sender<RemoteRequest>,
+    proxy: &EventLoopProxy<()>,
+) -> RemoteResponse {
+    let (reply, reply_receiver) = unbounded();
+    if sender.send(RemoteRequest { command, reply }).is_err() {
+        return RemoteResponse::Error(503, "Verso has shut down".into());
+    }
+    let _ = proxy.send_event(());
+    reply_receiver
+        .recv_timeout(REPLY_TIMEOUT)
+        .unwrap_or_else(|_| RemoteResponse::Error(504, "Verso didn't answer in time".into()))
+}
+
+/// Convert the result of a script to the answer of `/evaluate`.
+pub(crate) fn evaluate_response(result: WebDriverJSResult) -> RemoteResponse {
+    match result {
+        Ok(value) => RemoteResponse::Json(json!({ "result": json_value(value) })),
+        Err(e) => RemoteResponse::Error(500, format!("The script failed: {e:?}")),
+    }
+}
+
+fn json_value(value: WebDriverJSValue) -> Value {
+    match value {
+        WebDriverJSValue::Undefined | WebDriverJSValue::Null => Value::Null,
+        WebDriverJSValue::Boolean(b) => json!(b),
+        WebDriverJSValue::Int(i) => json!(i),
+        WebDriverJSValue::Number(n) => json!(n),
+        WebDriverJSValue::String(s) => json!(s),
+        WebDriverJSValue::ArrayLike(values) => {
+            Value::Array(values.into_iter().map(json_value).collect())
+        }
+        v => json!(format!("{v:?}")),
+    }
+}
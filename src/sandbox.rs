@@ -0,0 +1,154 @@
+//! Sandboxing of content processes, set with [`crate::config::Config::sandbox`] and reported by
+//! [`crate::Verso::renderer_info`].
+//!
+//! By default, Verso runs pages in its own process, like Servo's single process mode, so a bug
+//! in the engine exploited by a page has the rights of the whole application. With
+//! [`SandboxMode::Enabled`], pages run in content processes restricted to reading the resources
+//! Servo needs, without network or file system access of their own. Rendering, networking and
+//! GPU access stay in the main process, as Servo has no separate GPU process.
+//!
+//! Sandboxing uses seccomp-bpf and namespaces on Linux and `sandbox_init` profiles on macOS. It
+//! isn't available on Windows, Android, iOS or ARM processors, where
+//! [`SandboxMode::Enabled`] is rejected when building Verso instead of running without it. Some
+//! environments, like containers without user namespaces or with a restrictive seccomp policy,
+//! can't initialize the sandbox; content processes then fail to start, so such deployments need
+//! [`SandboxMode::Disabled`], trading isolation for being able to run. Content processes also
+//! cost memory and startup time for each page.
+//!
+//! Content processes are started by running the executable again with [`CONTENT_PROCESS_ARG`]
+//! and a token, so applications enabling the sandbox must call [`run_content_process`] at the
+//! start of `main`, before anything else, when [`content_process_token`] returns a token. The
+//! sandbox allows reading the resources directory. A content process which can't reach the main
+//! process or enter the sandbox returns a [`ContentProcessError`], and the main process sees the
+//! pipeline fail to start.
+
+use std::{path::PathBuf, sync::Arc};
+
+use constellation::UnprivilegedContent;
+use embedder_traits::resources;
+use ipc_channel::ipc::{self, IpcSender};
+use servo_config::opts::{self, Opts};
+
+use crate::config::ResourceReader;
+
+/// Argument Servo passes to the executable to start a content process, followed by a token.
+pub const CONTENT_PROCESS_ARG: &str = "--content-process";
+
+/// Error of a content process before it runs its pages.
+#[derive(Debug, thiserror::Error)]
+pub enum ContentProcessError {
+    /// The IPC channel to the main process can't be created, reached or read.
+    #[error("failed to talk to the main process: {0}")]
+    Ipc(String),
+    /// The preferences of the main process can't be applied.
+    #[error("failed to set the preferences: {0}")]
+    Prefs(String),
+    /// The sandbox can't be activated.
+    #[error("failed to activate the sandbox: {0}")]
+    Sandbox(String),
+}
+
+/// Whether pages run in sandboxed content processes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SandboxMode {
+    /// Keep the `multiprocess` and `sandbox` fields of [`crate::config::Config::opts`], which
+    /// run pages in the main process by default.
+    #[default]
+    Auto,
+    /// Run pages in sandboxed content processes. Building Verso fails where sandboxing isn't
+    /// supported.
+    Enabled,
+    /// Never sandbox content processes, for environments where the sandbox can't initialize.
+    Disabled,
+}
+
+/// Return true if content processes can be sandboxed on this platform.
+pub fn is_supported() -> bool {
+    cfg!(gaol_sandbox)
+}
+
+/// Set the options of Servo for the mode.
+pub(crate) fn apply(mode: SandboxMode, opts: &mut Opts) {
+    match mode {
+        SandboxMode::Auto => {}
+        SandboxMode::Enabled => {
+            opts.multiprocess = true;
+            opts.sandbox = true;
+        }
+        SandboxMode::Disabled => opts.sandbox = false,
+    }
+}
+
+/// Return true if pages run in sandboxed content processes.
+pub(crate) fn is_active() -> bool {
+    let opts = opts::get();
+    opts.multiprocess && opts.sandbox
+}
+
+/// Token of the content process to run if the executable was started as one.
+pub fn content_process_token() -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != CONTENT_PROCESS_ARG);
+    args.next()?;
+    args.next()
+}
+
+/// Run the content process of the token, sent by the main process, until its pages close. The
+/// resources directory must be the one of [`crate::config::Config`].
+pub fn run_content_process(
+    token: String,
+    resource_dir: PathBuf,
+) -> Result<(), ContentProcessError> {
+    let (content_sender, content_receiver) =
+        ipc::channel::<UnprivilegedContent>().map_err(ipc_error)?;
+    let bootstrap: IpcSender<IpcSender<UnprivilegedContent>> =
+        IpcSender::connect(token).map_err(ipc_error)?;
+    bootstrap.send(content_sender).map_err(ipc_error)?;
+    let content = content_receiver.recv().map_err(ipc_error)?;
+
+    resources::set(Box::new(ResourceReader(resource_dir)));
+    opts::set_options(content.opts());
+    servo_config::prefs::pref_map()
+        .set_all(content.prefs())
+        .map_err(|e| ContentProcessError::Prefs(format!("{e:?}")))?;
+    if opts::get().sandbox {
+        enter_sandbox()?;
+    }
+
+    let _js_engine_setup = script::init();
+    match content {
+        UnprivilegedContent::Pipeline(mut content) => {
+            servo_media::ServoMedia::init::<servo_media_dummy::DummyBackend>();
+            let background_hang_monitor_register = content.register_with_background_hang_monitor();
+            let layout_factory = Arc::new(layout_thread_2020::LayoutFactoryImpl());
+            content.start_all::<script::ScriptThread>(
+                true,
+                layout_factory,
+                background_hang_monitor_register,
+            );
+        }
+        UnprivilegedContent::ServiceWorker(content) => {
+            content.start::<script::ServiceWorkerManager>();
+        }
+    }
+    Ok(())
+}
+
+fn ipc_error(e: impl std::fmt::Debug) -> ContentProcessError {
+    ContentProcessError::Ipc(format!("{e:?}"))
+}
+
+#[cfg(gaol_sandbox)]
+fn enter_sandbox() -> Result<(), ContentProcessError> {
+    use gaol::sandbox::{ChildSandbox, ChildSandboxMethods};
+
+    ChildSandbox::new(constellation::content_process_sandbox_profile())
+        .activate()
+        .map_err(|e| ContentProcessError::Sandbox(format!("{e:?}")))
+}
+
+#[cfg(not(gaol_sandbox))]
+fn enter_sandbox() -> Result<(), ContentProcessError> {
+    Err(ContentProcessError::Sandbox(
+        "sandboxing isn't supported on this platform".to_string(),
+    ))
+}
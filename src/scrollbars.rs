@@ -0,0 +1,238 @@
+//! Scrollbars drawn by Verso over the web view, styled with [`crate::Verso::set_scrollbar_style`].
+//!
+//! Servo doesn't draw scrollbars, so Verso draws them for the document of the current window from
+//! the scroll offsets the compositor keeps, and updates them on the next composite after the page
+//! scrolls or grows. Only the scroller of the document gets scrollbars, not elements with
+//! `overflow: scroll`. Scrollbars are drawn over the edge of the page, since layout doesn't reserve
+//! room for them even in the classic mode, and they only show the position: dragging them
+//! doesn't scroll yet.
+
+use std::time::{Duration, Instant};
+
+use euclid::{Box2D, Point2D};
+use webrender_api::units::{DevicePixel, LayoutSize, LayoutVector2D};
+
+/// Time the overlay scrollbars stay visible after the last scroll when they auto-hide.
+const HIDE_DELAY: Duration = Duration::from_millis(1000);
+/// Time for auto-hiding scrollbars to fade out.
+const FADE_TIME: Duration = Duration::from_millis(300);
+/// Shortest time between two frames of the fade.
+const FRAME_TIME: Duration = Duration::from_millis(16);
+/// Shortest length of the thumb in device pixels, so it stays visible on long pages.
+const MIN_THUMB_LENGTH: f32 = 24.0;
+
+/// How scrollbars are shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScrollbarMode {
+    /// The style of the platform: overlay scrollbars which auto-hide on macOS, iOS and Android,
+    /// and classic scrollbars elsewhere.
+    #[default]
+    Native,
+    /// Thumb on a track, always visible while the page can scroll.
+    Classic,
+    /// Rounded thumb without a track.
+    Overlay {
+        /// Show the thumb only while scrolling, and fade it out afterwards.
+        auto_hide: bool,
+    },
+    /// No scrollbars, like Servo without Verso.
+    Hidden,
+}
+
+impl ScrollbarMode {
+    /// Resolve [`ScrollbarMode::Native`] to the mode of the platform.
+    pub(crate) fn resolve(self) -> Self {
+        match self {
+            Self::Native
+                if cfg!(any(
+                    target_os = "macos",
+                    target_os = "ios",
+                    target_os = "android"
+                )) =>
+            {
+                Self::Overlay { auto_hide: true }
+            }
+            Self::Native => Self::Classic,
+            mode => mode,
+        }
+    }
+}
+
+/// Appearance of the scrollbars.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollbarStyle {
+    /// How scrollbars are shown.
+    pub mode: ScrollbarMode,
+    /// Width in device pixels, or `None` for the width of the mode scaled by the HiDPI factor.
+    pub width: Option<u32>,
+    /// Color of the thumb as RGBA components between 0 and 1.
+    pub thumb_color: [f32; 4],
+    /// Color of the track of classic scrollbars as RGBA components between 0 and 1.
+    pub track_color: [f32; 4],
+}
+
+impl Default for ScrollbarStyle {
+    fn default() -> Self {
+        Self {
+            mode: ScrollbarMode::Native,
+            width: None,
+            thumb_color: [0.4, 0.4, 0.4, 0.7],
+            track_color: [0.94, 0.94, 0.94, 0.9],
+        }
+    }
+}
+
+impl ScrollbarStyle {
+    /// Width in device pixels for the HiDPI factor.
+    pub(crate) fn device_width(&self, hidpi_factor: f32) -> f32 {
+        match (self.width, self.mode.resolve()) {
+            (Some(width), _) => width as f32,
+            (None, ScrollbarMode::Overlay { .. }) => 8.0 * hidpi_factor,
+            (None, _) => 12.0 * hidpi_factor,
+        }
+    }
+}
+
+/// Scroll position and range of the document, in page pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ScrollGeometry {
+    /// Distance the document is scrolled from its top left corner.
+    pub position: LayoutVector2D,
+    /// Distance the document can scroll on each axis.
+    pub scrollable: LayoutSize,
+}
+
+/// Track and thumb of a scrollbar in device pixels.
+pub(crate) struct ScrollbarRects {
+    pub track: Box2D<f32, DevicePixel>,
+    pub thumb: Box2D<f32, DevicePixel>,
+}
+
+impl ScrollGeometry {
+    /// Rectangles of the vertical and horizontal scrollbars of the web view, for the axes which
+    /// can scroll.
+    pub fn rects(
+        &self,
+        webview: Box2D<f32, DevicePixel>,
+        width: f32,
+        device_pixels_per_page_pixel: f32,
+    ) -> Vec<ScrollbarRects> {
+        let vertical = self.scrollable.height > 0.0;
+        let horizontal = self.scrollable.width > 0.0;
+        // Both scrollbars leave the corner free.
+        let corner = |other: bool| if other { width } else { 0.0 };
+        let mut rects = Vec::new();
+        if vertical {
+            let track = Box2D::new(
+                Point2D::new(webview.max.x - width, webview.min.y),
+                Point2D::new(webview.max.x, webview.max.y - corner(horizontal)),
+            );
+            let visible = webview.height() / device_pixels_per_page_pixel;
+            let (start, end) = thumb_span(
+                track.height(),
+                visible,
+                self.position.y,
+                self.scrollable.height,
+            );
+            let thumb = Box2D::new(
+                Point2D::new(track.min.x, track.min.y + start),
+                Point2D::new(track.max.x, track.min.y + end),
+            );
+            rects.push(ScrollbarRects { track, thumb });
+        }
+        if horizontal {
+            let track = Box2D::new(
+                Point2D::new(webview.min.x, webview.max.y - width),
+                Point2D::new(webview.max.x - corner(vertical), webview.max.y),
+            );
+            let visible = webview.width() / device_pixels_per_page_pixel;
+            let (start, end) = thumb_span(
+                track.width(),
+                visible,
+                self.position.x,
+                self.scrollable.width,
+            );
+            let thumb = Box2D::new(
+                Point2D::new(track.min.x + start, track.min.y),
+                Point2D::new(track.min.x + end, track.max.y),
+            );
+            rects.push(ScrollbarRects { track, thumb });
+        }
+        rects
+    }
+}
+
+/// Start and end of the thumb along a track of the length, for a document scrolled to `position`
+/// of `scrollable` with `visible` page pixels in view.
+fn thumb_span(track: f32, visible: f32, position: f32, scrollable: f32) -> (f32, f32) {
+    let length = (track * visible / (visible + scrollable))
+        .max(MIN_THUMB_LENGTH)
+        .min(track);
+    let start = (track - length) * (position / scrollable).clamp(0.0, 1.0);
+    (start, start + length)
+}
+
+/// Drawn state of the scrollbars of a window.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Scrollbars {
+    /// Scroll position the scrollbars are drawn for.
+    pub geometry: Option<ScrollGeometry>,
+    /// Opacity of the scrollbars, 0 when they're hidden.
+    pub opacity: f32,
+    last_scroll: Option<Instant>,
+    last_tick: Option<Instant>,
+    /// Whether the scrollbars are waiting to fade or fading.
+    animating: bool,
+}
+
+impl Scrollbars {
+    /// Update the scrollbars to the scroll position of the document at `now`. Return true if they
+    /// changed, so they need to be drawn again.
+    pub fn tick(
+        &mut self,
+        geometry: Option<ScrollGeometry>,
+        mode: ScrollbarMode,
+        now: Instant,
+    ) -> bool {
+        let mode = mode.resolve();
+        if mode == ScrollbarMode::Hidden {
+            let visible = self.opacity > 0.0;
+            *self = Self::default();
+            return visible;
+        }
+        let old_opacity = self.opacity;
+        let moved = geometry != self.geometry;
+        if !moved
+            && self.last_tick.map_or(false, |last| {
+                now.saturating_duration_since(last) < FRAME_TIME
+            })
+        {
+            return false;
+        }
+        // Loading or growing a page isn't scrolling.
+        if let (Some(old), Some(new)) = (self.geometry, geometry) {
+            if old.position != new.position {
+                self.last_scroll = Some(now);
+            }
+        }
+        self.geometry = geometry;
+        self.opacity = match mode {
+            ScrollbarMode::Overlay { auto_hide: true } => {
+                let idle = self.last_scroll.map_or(HIDE_DELAY + FADE_TIME, |last| {
+                    now.saturating_duration_since(last)
+                });
+                let fading = idle.saturating_sub(HIDE_DELAY);
+                (1.0 - fading.as_secs_f32() / FADE_TIME.as_secs_f32()).max(0.0)
+            }
+            _ => 1.0,
+        };
+        self.animating = self.opacity > 0.0 && mode == ScrollbarMode::Overlay { auto_hide: true };
+        self.last_tick = self.animating.then_some(now);
+        moved || old_opacity != self.opacity
+    }
+
+    /// Return true if the scrollbars are waiting to fade out or fading.
+    pub fn is_animating(&self) -> bool {
+        self.animating
+    }
+}
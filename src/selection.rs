@@ -0,0 +1,98 @@
+use arboard::{Clipboard, GetExtLinux, LinuxClipboardKind, SetExtLinux};
+use base::id::BrowsingContextId;
+use compositing_traits::ConstellationMsg;
+use crossbeam_channel::Sender;
+use script_traits::webdriver_msg::WebDriverJSValue;
+
+use crate::verso::execute_script;
+
+/// Script returning the text selected in the page.
+const GET_SELECTION: &str = "return window.getSelection().toString();";
+
+/// Copy the text selected in the browsing context to the primary selection. The script result
+/// is awaited on another thread to not block the event loop.
+pub fn copy_selection_to_primary(
+    sender: &Sender<ConstellationMsg>,
+    browsing_context_id: BrowsingContextId,
+) {
+    let Some(receiver) = execute_script(sender, browsing_context_id, GET_SELECTION) else {
+        return;
+    };
+    std::thread::spawn(move || {
+        let Ok(Ok(WebDriverJSValue::String(text))) = receiver.recv() else {
+            return;
+        };
+        if text.is_empty() {
+            return;
+        }
+        let result = Clipboard::new().and_then(|mut clipboard| {
+            clipboard
+                .set()
+                .clipboard(LinuxClipboardKind::Primary)
+                .text(text)
+        });
+        if let Err(e) = result {
+            log::warn!("Verso failed to set primary selection: {e}");
+        }
+    });
+}
+
+/// Paste the primary selection into the editable element at the point in CSS pixels of the
+/// browsing context. Nothing happens if the element isn't editable, so the middle click keeps
+/// its default behaviour there.
+pub fn paste_primary(
+    clipboard: &mut Clipboard,
+    sender: &Sender<ConstellationMsg>,
+    browsing_context_id: BrowsingContextId,
+    point: (f32, f32),
+) {
+    let text = match clipboard
+        .get()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text()
+    {
+        Ok(text) if !text.is_empty() => text,
+        Ok(_) => return,
+        Err(e) => return log::trace!("Verso has no primary selection to paste: {e}"),
+    };
+    let Ok(text) = serde_json::to_string(&text) else {
+        return;
+    };
+    let script = format!(
+        "const text = {text};
+        const element = document.elementFromPoint({x}, {y});
+        if (!element) return false;
+        try {{
+            if (element instanceof HTMLInputElement || element instanceof HTMLTextAreaElement) {{
+                if (element.readOnly || element.disabled) return false;
+                element.focus();
+                const start = element.selectionStart ?? element.value.length;
+                const end = element.selectionEnd ?? element.value.length;
+                element.setRangeText(text, start, end, 'end');
+            }} else if (element.isContentEditable) {{
+                element.focus();
+                const selection = window.getSelection();
+                if (!selection.rangeCount) return false;
+                const range = selection.getRangeAt(0);
+                range.deleteContents();
+                range.insertNode(document.createTextNode(text));
+                range.collapse(false);
+            }} else {{
+                return false;
+            }}
+        }} catch (e) {{
+            return false;
+        }}
+        element.dispatchEvent(new Event('input', {{ bubbles: true }}));
+        return true;",
+        x = point.0,
+        y = point.1,
+    );
+    if let Some(receiver) = execute_script(sender, browsing_context_id, script) {
+        std::thread::spawn(move || {
+            if let Ok(Err(e)) = receiver.recv() {
+                log::warn!("Verso failed to paste primary selection: {e:?}");
+            }
+        });
+    }
+}
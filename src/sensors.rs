@@ -0,0 +1,107 @@
+//! Sensor values exposed to pages.
+//!
+//! Servo doesn't implement the Battery Status API, so when it's enabled with
+//! [`crate::config::Config::battery_status`], Verso defines `navigator.getBattery()` in each page
+//! after it loads, answering with the values of the [`SensorProvider`].
+
+/// Battery status reported to pages.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatteryStatus {
+    /// Charge level from `0.0` to `1.0`.
+    pub level: f64,
+    /// True if the battery is charging.
+    pub charging: bool,
+}
+
+/// Provider of sensor values which Verso exposes to pages. Embedders can supply their own with
+/// [`crate::Verso::set_sensor_provider`].
+pub trait SensorProvider {
+    /// Current battery status, or `None` if there's no battery or it's unknown.
+    fn battery(&self) -> Option<BatteryStatus> {
+        None
+    }
+}
+
+/// Sensor provider answering fixed values. It's used when there's no platform provider.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MockSensorProvider {
+    /// Battery status to report.
+    pub battery: Option<BatteryStatus>,
+}
+
+impl SensorProvider for MockSensorProvider {
+    fn battery(&self) -> Option<BatteryStatus> {
+        self.battery
+    }
+}
+
+/// Sensor provider reading the battery from UPower's display device over D-Bus.
+#[cfg(all(linux, feature = "upower"))]
+pub struct UPowerSensorProvider {
+    proxy: zbus::blocking::Proxy<'static>,
+}
+
+#[cfg(all(linux, feature = "upower"))]
+impl UPowerSensorProvider {
+    /// Connect to UPower on the system bus.
+    pub fn new() -> zbus::Result<Self> {
+        let connection = zbus::blocking::Connection::system()?;
+        let proxy = zbus::blocking::Proxy::new(
+            &connection,
+            "org.freedesktop.UPower",
+            "/org/freedesktop/UPower/devices/DisplayDevice",
+            "org.freedesktop.UPower.Device",
+        )?;
+        Ok(Self { proxy })
+    }
+}
+
+#[cfg(all(linux, feature = "upower"))]
+impl SensorProvider for UPowerSensorProvider {
+    fn battery(&self) -> Option<BatteryStatus> {
+        if !self.proxy.get_property::<bool>("IsPresent").ok()? {
+            return None;
+        }
+        let percentage = self.proxy.get_property::<f64>("Percentage").ok()?;
+        // 1 is charging and 4 is fully charged, which the API reports as charging.
+        let state = self.proxy.get_property::<u32>("State").ok()?;
+        Some(BatteryStatus {
+            level: (percentage / 100.0).clamp(0.0, 1.0),
+            charging: state == 1 || state == 4,
+        })
+    }
+}
+
+/// The default sensor provider of the platform.
+pub fn default_sensor_provider() -> Box<dyn SensorProvider> {
+    #[cfg(all(linux, feature = "upower"))]
+    match UPowerSensorProvider::new() {
+        Ok(provider) => return Box::new(provider),
+        Err(e) => log::warn!("Verso failed to connect to UPower: {e}"),
+    }
+    Box::new(MockSensorProvider::default())
+}
+
+/// Script defining `navigator.getBattery()` with the battery status.
+pub(crate) fn battery_script(status: Option<BatteryStatus>) -> String {
+    // Without a battery, the API reports a fully charged battery connected to power.
+    let BatteryStatus { level, charging } = status.unwrap_or(BatteryStatus {
+        level: 1.0,
+        charging: true,
+    });
+    format!(
+        "const battery = {{
+            level: {level},
+            charging: {charging},
+            chargingTime: {charging} ? 0 : Infinity,
+            dischargingTime: Infinity,
+            onchargingchange: null,
+            onchargingtimechange: null,
+            ondischargingtimechange: null,
+            onlevelchange: null,
+            addEventListener() {{}},
+            removeEventListener() {{}},
+        }};
+        navigator.getBattery = () => Promise.resolve(battery);"
+    )
+}
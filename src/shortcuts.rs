@@ -0,0 +1,334 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::Path,
+    str::FromStr,
+};
+
+use keyboard_types::{Code, Key, KeyState, KeyboardEvent, Modifiers};
+use serde::Deserialize;
+
+use crate::keyboard::CMD_OR_CONTROL;
+
+/// Browser actions which can be bound to a keyboard shortcut.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum ShortcutAction {
+    /// Reload the page.
+    Reload,
+    /// Clear the HTTP cache and reload the page.
+    HardReload,
+    /// Focus the panel which contains the address bar.
+    FocusAddress,
+    /// Zoom in the page.
+    ZoomIn,
+    /// Zoom out the page.
+    ZoomOut,
+    /// Reset the page zoom and the pinch zoom.
+    ResetZoom,
+    /// Zoom the page so its whole content is visible. See [`crate::zoom`].
+    ZoomToFit,
+    /// Zoom the page so the width of its content is visible. See [`crate::zoom`].
+    ZoomFitWidth,
+    /// Navigate to previous page.
+    Back,
+    /// Navigate to next page.
+    Forward,
+    /// Print the page. See [`crate::print`].
+    Print,
+}
+
+/// A key combined with modifiers, like `Ctrl+Shift+R`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Chord {
+    /// Modifiers which must be pressed.
+    pub modifiers: Modifiers,
+    /// The key which triggers the chord. Characters are stored in lower case.
+    pub key: Key,
+}
+
+impl Chord {
+    /// Create a chord. Character keys are normalized to lower case.
+    pub fn new(modifiers: Modifiers, key: Key) -> Self {
+        let key = match key {
+            Key::Character(c) => Key::Character(c.to_lowercase()),
+            key => key,
+        };
+        Self { modifiers, key }
+    }
+
+    /// Get the chord of a key down event.
+    pub fn from_keyboard_event(event: &KeyboardEvent) -> Self {
+        Self::new(event.modifiers, event.key.clone())
+    }
+}
+
+/// Error of parsing a [`Chord`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ChordParseError {
+    /// The chord doesn't contain a key.
+    #[error("shortcut `{0}` doesn't have a key")]
+    MissingKey(String),
+    /// The modifier isn't known.
+    #[error("shortcut `{0}` has an unknown modifier `{1}`")]
+    UnknownModifier(String, String),
+    /// The key isn't known.
+    #[error("shortcut `{0}` has an unknown key `{1}`")]
+    UnknownKey(String, String),
+}
+
+impl FromStr for Chord {
+    type Err = ChordParseError;
+
+    /// Parse chords like `Ctrl+Shift+R`, `Cmd+L`, `CmdOrCtrl+F5` or `Ctrl++`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (modifiers, key) = match s.strip_suffix("++") {
+            Some(rest) => (rest, "+"),
+            None => match s.rsplit_once('+') {
+                Some((modifiers, key)) => (modifiers, key),
+                None => ("", s),
+            },
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(ChordParseError::MissingKey(s.into()));
+        }
+
+        let mut mods = Modifiers::empty();
+        for modifier in modifiers
+            .split('+')
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+        {
+            mods |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CONTROL,
+                "shift" => Modifiers::SHIFT,
+                "alt" | "option" => Modifiers::ALT,
+                "cmd" | "command" | "meta" | "super" => Modifiers::META,
+                "cmdorctrl" | "cmdorcontrol" => CMD_OR_CONTROL,
+                _ => return Err(ChordParseError::UnknownModifier(s.into(), modifier.into())),
+            };
+        }
+
+        let key = if key.chars().count() == 1 {
+            Key::Character(key.into())
+        } else {
+            match Key::from_str(key) {
+                Ok(Key::Character(_)) | Ok(Key::Unidentified) | Err(_) => {
+                    return Err(ChordParseError::UnknownKey(s.into(), key.into()))
+                }
+                Ok(key) => key,
+            }
+        };
+        Ok(Chord::new(mods, key))
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (Modifiers::CONTROL, "Ctrl+"),
+            (Modifiers::ALT, "Alt+"),
+            (Modifiers::SHIFT, "Shift+"),
+            (Modifiers::META, "Cmd+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match &self.key {
+            Key::Character(c) => f.write_str(&c.to_uppercase()),
+            key => write!(f, "{key}"),
+        }
+    }
+}
+
+/// Two actions bound to the same chord. The latter one wins.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShortcutConflict {
+    /// The chord bound twice.
+    pub chord: Chord,
+    /// The action which is overridden.
+    pub overridden: ShortcutAction,
+    /// The action which is bound to the chord now.
+    pub action: ShortcutAction,
+}
+
+/// Map of keyboard shortcuts to browser actions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShortcutMap {
+    map: HashMap<Chord, ShortcutAction>,
+}
+
+impl Default for ShortcutMap {
+    /// Platform aware default shortcuts. They use Cmd on macOS and Ctrl on other platforms.
+    fn default() -> Self {
+        let mut map = HashMap::new();
+        let character = |c: &str| Key::Character(c.into());
+        for (modifiers, key, action) in [
+            (CMD_OR_CONTROL, character("r"), ShortcutAction::Reload),
+            (Modifiers::empty(), Key::F5, ShortcutAction::Reload),
+            (
+                CMD_OR_CONTROL | Modifiers::SHIFT,
+                character("r"),
+                ShortcutAction::HardReload,
+            ),
+            (CMD_OR_CONTROL, character("l"), ShortcutAction::FocusAddress),
+            (CMD_OR_CONTROL, character("="), ShortcutAction::ZoomIn),
+            (CMD_OR_CONTROL, character("+"), ShortcutAction::ZoomIn),
+            (
+                CMD_OR_CONTROL | Modifiers::SHIFT,
+                character("+"),
+                ShortcutAction::ZoomIn,
+            ),
+            (CMD_OR_CONTROL, character("-"), ShortcutAction::ZoomOut),
+            (CMD_OR_CONTROL, character("0"), ShortcutAction::ResetZoom),
+            (
+                CMD_OR_CONTROL | Modifiers::ALT,
+                character("0"),
+                ShortcutAction::ZoomToFit,
+            ),
+            (
+                CMD_OR_CONTROL | Modifiers::ALT,
+                character("9"),
+                ShortcutAction::ZoomFitWidth,
+            ),
+            (Modifiers::ALT, Key::ArrowLeft, ShortcutAction::Back),
+            (Modifiers::ALT, Key::ArrowRight, ShortcutAction::Forward),
+            (CMD_OR_CONTROL, character("p"), ShortcutAction::Print),
+        ] {
+            map.insert(Chord::new(modifiers, key), action);
+        }
+        Self { map }
+    }
+}
+
+impl ShortcutMap {
+    /// Create a map without any shortcut.
+    pub fn empty() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Load the shortcuts of a JSON file like [`ShortcutMap::from_json`]. The default shortcuts
+    /// are used if the file doesn't exist or is invalid.
+    pub fn load(path: &Path) -> Self {
+        let Ok(json) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        match Self::from_json(&json) {
+            Ok((shortcuts, _conflicts)) => shortcuts,
+            Err(e) => {
+                log::warn!("Verso failed to parse {path:?}, using default shortcuts: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Parse a JSON object of chords to actions, like `{ "Ctrl+Shift+R": "HardReload" }`, and
+    /// apply it on top of the default shortcuts. Invalid chords are skipped with a warning, and
+    /// conflicts between the chords in the object are returned.
+    pub fn from_json(json: &str) -> Result<(Self, Vec<ShortcutConflict>), serde_json::Error> {
+        let bindings: HashMap<String, ShortcutAction> = serde_json::from_str(json)?;
+        let mut map = Self::default();
+        let mut parsed = HashMap::<Chord, ShortcutAction>::new();
+        let mut conflicts = vec![];
+        // Sort the bindings to make conflict resolution deterministic.
+        let mut bindings: Vec<_> = bindings.into_iter().collect();
+        bindings.sort();
+        for (chord, action) in bindings {
+            let chord = match chord.parse::<Chord>() {
+                Ok(chord) => chord,
+                Err(e) => {
+                    log::warn!("Verso ignores invalid shortcut: {e}");
+                    continue;
+                }
+            };
+            if let Some(overridden) = parsed.insert(chord.clone(), action) {
+                if overridden != action {
+                    conflicts.push(ShortcutConflict {
+                        chord: chord.clone(),
+                        overridden,
+                        action,
+                    });
+                }
+            }
+            map.map.insert(chord, action);
+        }
+        for conflict in &conflicts {
+            log::warn!(
+                "Verso shortcut {} is bound to both {:?} and {:?}, {:?} is used",
+                conflict.chord,
+                conflict.overridden,
+                conflict.action,
+                conflict.action
+            );
+        }
+        Ok((map, conflicts))
+    }
+
+    /// Bind the chord to the action, replacing previous chords of the same action. Return the
+    /// action which was bound to the chord before, if any.
+    pub fn set(&mut self, action: ShortcutAction, chord: Chord) -> Option<ShortcutAction> {
+        self.map.retain(|_, a| *a != action);
+        self.map.insert(chord, action).filter(|a| *a != action)
+    }
+
+    /// Get the action bound to the chord.
+    pub fn get(&self, chord: &Chord) -> Option<ShortcutAction> {
+        self.map.get(chord).copied()
+    }
+
+    /// Get the action the keyboard event triggers. Only key down events trigger actions.
+    pub fn action_for(&self, event: &KeyboardEvent) -> Option<ShortcutAction> {
+        if event.state != KeyState::Down {
+            return None;
+        }
+        self.get(&Chord::from_keyboard_event(event))
+    }
+}
+
+/// What happens to a keyboard event with the shortcuts. See [`HeldShortcuts::handle`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyOutcome {
+    /// The event goes to the page.
+    Forward,
+    /// The event triggers the action instead of going to the page.
+    Trigger(ShortcutAction),
+    /// The event releases a key which triggered a shortcut, so it doesn't go to the page either.
+    Suppress,
+}
+
+/// Keys which triggered a shortcut and aren't released yet. Pages never see their key down, so
+/// their key up is held back too.
+#[derive(Clone, Debug, Default)]
+pub struct HeldShortcuts {
+    codes: HashSet<Code>,
+}
+
+impl HeldShortcuts {
+    /// Decide what happens to the keyboard event, and track the keys which triggered shortcuts.
+    pub fn handle(&mut self, shortcuts: &ShortcutMap, event: &KeyboardEvent) -> KeyOutcome {
+        match event.state {
+            KeyState::Down => match shortcuts.action_for(event) {
+                Some(action) => {
+                    self.codes.insert(event.code);
+                    KeyOutcome::Trigger(action)
+                }
+                None => KeyOutcome::Forward,
+            },
+            KeyState::Up => {
+                if self.codes.remove(&event.code) {
+                    KeyOutcome::Suppress
+                } else {
+                    KeyOutcome::Forward
+                }
+            }
+        }
+    }
+
+    /// Forget the held keys, like when the window loses focus and won't see them released.
+    pub fn clear(&mut self) {
+        self.codes.clear();
+    }
+}
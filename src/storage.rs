@@ -0,0 +1,168 @@
+//! Storage used by the sites visited in this session, reported by [`crate::Verso::storage_usage`]
+//! and removed with [`crate::Verso::clear_origin_data`], like for a "this site uses 40 MB"
+//! settings page.
+//!
+//! Like for [`crate::cookies`], Servo can't enumerate its stores, so the origins of the pages
+//! loaded in this session are reported. Local storage is measured like its quota is, as the
+//! UTF-8 length of keys and values. Servo doesn't implement IndexedDB or the Cache API, and its
+//! HTTP cache is shared without accounting per origin, so [`OriginUsage::indexed_db_bytes`] and
+//! [`OriginUsage::cache_bytes`] are `None`.
+//!
+//! A store which fails to answer for an origin, like when its data is corrupt, is reported in
+//! [`OriginUsage::error`] of that origin, and other origins are still reported.
+
+use std::time::Duration;
+
+use ipc_channel::ipc::{self, IpcSender};
+use net_traits::{
+    storage_thread::{StorageThreadMsg, StorageType},
+    IpcSend, ResourceThreads,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use servo_url::ServoUrl;
+
+use crate::cookies::{normalize_domain, Cookie, CookieUrls};
+
+/// Time a store has to answer for an origin before it's reported as failed.
+const STORE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Kind of data stored by an origin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StorageKind {
+    /// Cookies set for the host of the origin.
+    Cookies,
+    /// `localStorage`.
+    LocalStorage,
+    /// IndexedDB databases, which Servo doesn't implement yet.
+    IndexedDb,
+    /// Cache API caches, which Servo doesn't implement yet.
+    Cache,
+}
+
+/// Storage used by an origin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OriginUsage {
+    /// URL of the origin, with an empty path, like `https://example.com/`. Pass it to
+    /// [`crate::Verso::clear_origin_data`] to remove the data.
+    pub origin: ServoUrl,
+    /// Number of cookies set for the host of the origin.
+    pub cookies: usize,
+    /// Size of keys and values in `localStorage`.
+    pub local_storage_bytes: u64,
+    /// Size of IndexedDB databases, or `None` if the engine doesn't support them.
+    pub indexed_db_bytes: Option<u64>,
+    /// Size of Cache API caches, or `None` if the engine doesn't support them.
+    pub cache_bytes: Option<u64>,
+    /// Error of a store which couldn't be read for the origin. The sizes of that store are 0.
+    pub error: Option<String>,
+}
+
+impl OriginUsage {
+    /// Total size of the data of the origin, without cookies.
+    pub fn total_bytes(&self) -> u64 {
+        self.local_storage_bytes
+            + self.indexed_db_bytes.unwrap_or_default()
+            + self.cache_bytes.unwrap_or_default()
+    }
+}
+
+/// Gather the storage used by each origin of the remembered URLs. It blocks on the stores, so
+/// it runs on another thread.
+pub(crate) fn usage(resource_threads: &ResourceThreads, urls: &CookieUrls) -> Vec<OriginUsage> {
+    let cookies = crate::cookies::list(resource_threads, urls);
+    urls.origins()
+        .iter()
+        .filter_map(|origin| ServoUrl::parse(origin).ok())
+        .map(|origin| origin_usage(resource_threads, origin, &cookies))
+        .collect()
+}
+
+fn origin_usage(
+    resource_threads: &ResourceThreads,
+    origin: ServoUrl,
+    cookies: &[Cookie],
+) -> OriginUsage {
+    let host = origin.host_str().map(normalize_domain);
+    let cookies = cookies
+        .iter()
+        .filter(|cookie| Some(&cookie.domain) == host.as_ref())
+        .count();
+    let (local_storage_bytes, error) = match local_storage_bytes(resource_threads, &origin) {
+        Ok(bytes) => (bytes, None),
+        Err(e) => {
+            log::warn!("Verso failed to read the local storage of {origin}: {e}");
+            (0, Some(format!("local storage: {e}")))
+        }
+    };
+    OriginUsage {
+        origin,
+        cookies,
+        local_storage_bytes,
+        indexed_db_bytes: None,
+        cache_bytes: None,
+        error,
+    }
+}
+
+/// Send a message to the storage thread and wait for its answer.
+fn ask<T: Serialize + DeserializeOwned>(
+    resource_threads: &ResourceThreads,
+    msg: impl FnOnce(IpcSender<T>) -> StorageThreadMsg,
+) -> Result<T, String> {
+    let (sender, receiver) = ipc::channel::<T>().map_err(|e| e.to_string())?;
+    resource_threads
+        .send(msg(sender))
+        .map_err(|e| format!("{e:?}"))?;
+    receiver
+        .try_recv_timeout(STORE_TIMEOUT)
+        .map_err(|e| format!("{e:?}"))
+}
+
+fn local_storage_bytes(
+    resource_threads: &ResourceThreads,
+    origin: &ServoUrl,
+) -> Result<u64, String> {
+    let keys = ask(resource_threads, |sender| {
+        StorageThreadMsg::Keys(sender, origin.clone(), StorageType::Local)
+    })?;
+    let mut bytes = 0;
+    for key in keys {
+        let value: Option<String> = ask(resource_threads, |sender| {
+            StorageThreadMsg::GetItem(sender, origin.clone(), StorageType::Local, key.clone())
+        })?;
+        bytes += (key.len() + value.map_or(0, |value| value.len())) as u64;
+    }
+    Ok(bytes)
+}
+
+/// Remove the data of the kinds stored by the origin of the URL. Kinds Servo doesn't implement
+/// are ignored.
+pub(crate) fn clear(
+    resource_threads: &ResourceThreads,
+    urls: &CookieUrls,
+    origin: &ServoUrl,
+    kinds: &[StorageKind],
+) {
+    for kind in kinds {
+        match kind {
+            StorageKind::Cookies => {
+                let Some(host) = origin.host_str() else {
+                    continue;
+                };
+                let removed = crate::cookies::remove_domain(resource_threads, urls, host);
+                log::debug!("Verso removed {removed} cookies of {host}");
+            }
+            StorageKind::LocalStorage => {
+                let cleared = ask(resource_threads, |sender| {
+                    StorageThreadMsg::Clear(sender, origin.clone(), StorageType::Local)
+                });
+                if let Err(e) = cleared {
+                    log::warn!("Verso failed to clear the local storage of {origin}: {e}");
+                }
+            }
+            StorageKind::IndexedDb | StorageKind::Cache => {
+                log::debug!("Verso has no {kind:?} data to clear");
+            }
+        }
+    }
+}
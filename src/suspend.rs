@@ -0,0 +1,64 @@
+//! Suspending a web view to free its memory and resuming it later, even after a restart, with
+//! [`crate::Verso::suspend_webview`] and [`crate::Verso::resume_webview`].
+//!
+//! Suspending captures the URL, session history, scroll position, page zoom and form fields of
+//! the page into a [`WebViewSnapshot`], and closes the web view. Snapshots are serializable with
+//! serde, so embedders can persist them. Resuming opens a web view with the page in the current
+//! window, replacing its web view, and restores the scroll position and form fields once the page
+//! loaded, reported to [`crate::VersoBuilder::on_resume`].
+//!
+//! Form fields are restored by their index in the document, skipping fields which no longer
+//! exist, and nothing is restored if the page redirected to another URL. Like for discarded web
+//! views, Servo can't restore the session history, so it's kept in the snapshot for display but
+//! the resumed web view starts a new history. Password and file fields are never captured.
+
+use serde::{Deserialize, Serialize};
+use servo_url::ServoUrl;
+
+/// State of a suspended web view, returned by [`crate::Verso::suspend_webview`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WebViewSnapshot {
+    /// URL of the page.
+    pub url: ServoUrl,
+    /// URLs of the session history, oldest first.
+    pub history: Vec<ServoUrl>,
+    /// Index of the page in the session history.
+    pub history_index: usize,
+    /// Page zoom factor.
+    pub page_zoom: f32,
+    /// Scroll position and form fields, if the page answered in time.
+    pub page: Option<PageState>,
+}
+
+/// Scroll position and form fields of a page.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PageState {
+    /// URL the state was captured on. It's only restored on the same URL.
+    pub url: String,
+    /// Horizontal scroll position in CSS pixels.
+    pub x: f64,
+    /// Vertical scroll position in CSS pixels.
+    pub y: f64,
+    /// Index of the field in the document with its value, or its checked state for checkboxes
+    /// and radio buttons.
+    pub fields: Vec<(usize, FieldValue)>,
+}
+
+/// Value of a form field.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FieldValue {
+    /// Checked state of a checkbox or a radio button.
+    Checked(bool),
+    /// Value of another field.
+    Text(String),
+}
+
+/// Progress of resuming a web view, reported to [`crate::VersoBuilder::on_resume`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResumeProgress {
+    /// The web view is opened and loads the page.
+    Loading,
+    /// The page loaded, and its scroll position and form fields are being restored.
+    Restored,
+}
@@ -0,0 +1,212 @@
+//! Title and favicon of the page of each window, for embedders drawing their own tab strips,
+//! read with [`crate::Verso::tab_info`] and reported to [`crate::VersoBuilder::on_tab_updated`].
+//!
+//! Favicons are decoded by the page: the icon is drawn to a canvas of at most
+//! [`MAX_FAVICON_SIZE`] pixels per side, keeping its aspect ratio, and its pixels are sent back as
+//! RGBA. Data URLs work like any other URL. Canvas can't read icons of other origins which don't
+//! allow it with CORS, so those are missing. With the `svg-favicons` feature, SVG icons are
+//! fetched as text and rasterized with `resvg`, since Servo can't draw SVG images yet.
+//!
+//! Decoded icons are cached by URL, so tabs of the same site don't fetch them again. The favicon
+//! also becomes the icon of the window on platforms which show one.
+
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use serde::Deserialize;
+use servo_url::ServoUrl;
+
+/// Largest width and height of favicons in pixels. Larger icons are downscaled.
+pub const MAX_FAVICON_SIZE: u32 = 64;
+
+/// Prefix of the message the page sends with `prompt()` once a favicon is decoded, followed by
+/// the JSON of its URL, size and RGBA pixels.
+pub(crate) const FAVICON_PROMPT: &str = "VERSO_FAVICON:";
+
+/// Prefix of the message the page sends with `prompt()` with the source of an SVG favicon.
+pub(crate) const SVG_FAVICON_PROMPT: &str = "VERSO_FAVICON_SVG:";
+
+/// Favicon of a page, decoded to RGBA pixels.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Favicon {
+    /// URL the icon was loaded from.
+    pub url: ServoUrl,
+    /// Width in pixels, at most [`MAX_FAVICON_SIZE`].
+    pub width: u32,
+    /// Height in pixels, at most [`MAX_FAVICON_SIZE`].
+    pub height: u32,
+    /// Pixels, 4 bytes per pixel, rows from top to bottom, not premultiplied.
+    pub rgba: Vec<u8>,
+}
+
+impl std::fmt::Debug for Favicon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Favicon")
+            .field("url", &self.url.as_str())
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Title and favicon of the page of a window.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TabInfo {
+    /// URL of the page.
+    pub url: Option<ServoUrl>,
+    /// Title of the page, if it has one.
+    pub title: Option<String>,
+    /// Favicon of the page, once it's decoded.
+    pub favicon: Option<Arc<Favicon>>,
+    /// Hash of the URL, title and favicon, which only changes when one of them changes, so UIs
+    /// can skip redundant updates.
+    pub hash: u64,
+}
+
+impl TabInfo {
+    /// Create the tab info and compute its hash.
+    pub(crate) fn new(
+        url: Option<ServoUrl>,
+        title: Option<String>,
+        favicon: Option<Arc<Favicon>>,
+    ) -> Self {
+        let mut hasher = DefaultHasher::new();
+        url.as_ref().map(ServoUrl::as_str).hash(&mut hasher);
+        title.hash(&mut hasher);
+        if let Some(favicon) = &favicon {
+            favicon.url.as_str().hash(&mut hasher);
+            favicon.rgba.hash(&mut hasher);
+        }
+        Self {
+            url,
+            title,
+            favicon,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<ServoUrl, Arc<Favicon>>> {
+    static CACHE: OnceLock<Mutex<HashMap<ServoUrl, Arc<Favicon>>>> = OnceLock::new();
+    CACHE.get_or_init(Mutex::default)
+}
+
+/// Get the decoded favicon of the URL, if it's cached.
+pub(crate) fn cached(url: &ServoUrl) -> Option<Arc<Favicon>> {
+    cache().lock().ok()?.get(url).cloned()
+}
+
+fn insert(favicon: Favicon) -> Arc<Favicon> {
+    let favicon = Arc::new(favicon);
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(favicon.url.clone(), favicon.clone());
+    }
+    favicon
+}
+
+/// Script decoding the favicon of the URL in the page and sending it back with `prompt()`.
+pub(crate) fn decode_script(url: &ServoUrl) -> Option<String> {
+    let url = serde_json::to_string(url.as_str()).ok()?;
+    let svg = cfg!(feature = "svg-favicons");
+    Some(format!(
+        "const url = {url};
+        if ({svg} && (/\\.svg([?#]|$)/i.test(url) || url.startsWith('data:image/svg+xml'))) {{
+            fetch(url)
+                .then((response) => response.text())
+                .then((svg) => prompt('{SVG_FAVICON_PROMPT}' + JSON.stringify({{ url, svg }})))
+                .catch(() => {{}});
+            return;
+        }}
+        const image = new Image();
+        image.crossOrigin = 'anonymous';
+        image.onload = () => {{
+            const scale = Math.min(1, {MAX_FAVICON_SIZE} / Math.max(image.naturalWidth, image.naturalHeight));
+            const width = Math.max(1, Math.round(image.naturalWidth * scale));
+            const height = Math.max(1, Math.round(image.naturalHeight * scale));
+            const canvas = document.createElement('canvas');
+            canvas.width = width;
+            canvas.height = height;
+            const context = canvas.getContext('2d');
+            context.drawImage(image, 0, 0, width, height);
+            let pixels;
+            try {{
+                pixels = context.getImageData(0, 0, width, height).data;
+            }} catch (e) {{
+                return;
+            }}
+            const rgba = Array.from(pixels);
+            prompt('{FAVICON_PROMPT}' + JSON.stringify({{ url, width, height, rgba }}));
+        }};
+        image.src = url;"
+    ))
+}
+
+/// Message of a decoded favicon.
+#[derive(Deserialize)]
+struct DecodedFavicon {
+    url: String,
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+/// Read the favicon of a [`FAVICON_PROMPT`] message, and cache it.
+pub(crate) fn read_decoded(message: &str) -> Option<Arc<Favicon>> {
+    let json = message.strip_prefix(FAVICON_PROMPT)?;
+    let decoded: DecodedFavicon = serde_json::from_str(json).ok()?;
+    let valid = (1..=MAX_FAVICON_SIZE).contains(&decoded.width)
+        && (1..=MAX_FAVICON_SIZE).contains(&decoded.height)
+        && decoded.rgba.len() == (decoded.width * decoded.height * 4) as usize;
+    if !valid {
+        return None;
+    }
+    Some(insert(Favicon {
+        url: ServoUrl::parse(&decoded.url).ok()?,
+        width: decoded.width,
+        height: decoded.height,
+        rgba: decoded.rgba,
+    }))
+}
+
+/// Rasterize the SVG favicon of a [`SVG_FAVICON_PROMPT`] message, and cache it.
+#[cfg(feature = "svg-favicons")]
+pub(crate) fn read_svg(message: &str) -> Option<Arc<Favicon>> {
+    #[derive(Deserialize)]
+    struct SvgFavicon {
+        url: String,
+        svg: String,
+    }
+
+    let json = message.strip_prefix(SVG_FAVICON_PROMPT)?;
+    let icon: SvgFavicon = serde_json::from_str(json).ok()?;
+    let tree = resvg::usvg::Tree::from_str(&icon.svg, &resvg::usvg::Options::default())
+        .map_err(|e| log::warn!("Verso failed to parse SVG favicon {}: {e}", icon.url))
+        .ok()?;
+    let size = tree.size();
+    let scale = MAX_FAVICON_SIZE as f32 / size.width().max(size.height());
+    let width = ((size.width() * scale).round() as u32).clamp(1, MAX_FAVICON_SIZE);
+    let height = ((size.height() * scale).round() as u32).clamp(1, MAX_FAVICON_SIZE);
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height)?;
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    Some(insert(Favicon {
+        url: ServoUrl::parse(&icon.url).ok()?,
+        width,
+        height,
+        rgba,
+    }))
+}
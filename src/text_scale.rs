@@ -0,0 +1,133 @@
+//! Text scale of the operating system's accessibility settings, like GNOME's text scaling
+//! factor or the text size of Windows.
+//!
+//! The scale multiplies the default font sizes of [`crate::config::FontConfig`], rather than the
+//! scale factor of the window. Text sized relative to the default, like with `rem`, `em` or
+//! keywords, grows, while CSS pixels and the viewport keep their size, so media queries and
+//! responsive breakpoints match the same way as without text scaling. Text sized in `px` doesn't
+//! follow the scale.
+//!
+//! On Linux the scale is read from the settings portal, with the `text-scale` feature, and
+//! changes are applied to pages loaded afterwards. On Windows it's read when Verso starts. It's
+//! ignored when [`crate::config::Config::follow_os_text_scale`] is disabled.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use winit::event_loop::EventLoopProxy;
+
+/// Bits of the text scale as `f64`, 1.0 until it's read.
+static TEXT_SCALE: AtomicU64 = AtomicU64::new(0x3FF0_0000_0000_0000);
+/// Set when the text scale changed since the fonts were applied.
+static CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Get the text scale of the operating system, 1.0 if it's unknown.
+pub fn os_text_scale() -> f64 {
+    f64::from_bits(TEXT_SCALE.load(Ordering::Relaxed))
+}
+
+fn set_text_scale(scale: f64) {
+    if scale.is_finite() && scale > 0.0 && scale != os_text_scale() {
+        log::debug!("Verso text scale of the operating system is {scale}");
+        TEXT_SCALE.store(scale.to_bits(), Ordering::Relaxed);
+        CHANGED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Return true once after the text scale changed.
+pub(crate) fn take_change() -> bool {
+    CHANGED.swap(false, Ordering::Relaxed)
+}
+
+/// Read the text scale, and watch it for changes where it's supported, waking up the event loop
+/// when it changes.
+pub(crate) fn watch(proxy: EventLoopProxy<()>) {
+    #[cfg(all(linux, feature = "text-scale"))]
+    std::thread::spawn(move || {
+        if let Err(e) = portal::watch(proxy) {
+            log::warn!("Verso failed to read the text scale from the settings portal: {e}");
+        }
+    });
+    #[cfg(target_os = "windows")]
+    {
+        drop(proxy);
+        if let Some(scale) = windows_text_scale() {
+            set_text_scale(scale);
+        }
+    }
+    #[cfg(not(any(all(linux, feature = "text-scale"), target_os = "windows")))]
+    drop(proxy);
+}
+
+#[cfg(all(linux, feature = "text-scale"))]
+mod portal {
+    use winit::event_loop::EventLoopProxy;
+    use zbus::zvariant::{OwnedValue, Value};
+
+    const NAMESPACE: &str = "org.gnome.desktop.interface";
+    const KEY: &str = "text-scaling-factor";
+
+    pub fn watch(proxy: EventLoopProxy<()>) -> zbus::Result<()> {
+        let connection = zbus::blocking::Connection::session()?;
+        let settings = zbus::blocking::Proxy::new(
+            &connection,
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Settings",
+        )?;
+        let changes = settings.receive_signal("SettingChanged")?;
+        let value: OwnedValue = settings.call("Read", &(NAMESPACE, KEY))?;
+        if let Some(scale) = to_f64(&value) {
+            super::set_text_scale(scale);
+            let _ = proxy.send_event(());
+        }
+        for message in changes {
+            let Ok((namespace, key, value)) =
+                message.body().deserialize::<(String, String, OwnedValue)>()
+            else {
+                continue;
+            };
+            if namespace == NAMESPACE && key == KEY {
+                if let Some(scale) = to_f64(&value) {
+                    super::set_text_scale(scale);
+                    let _ = proxy.send_event(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn to_f64(value: &Value) -> Option<f64> {
+        match value {
+            // `Read` wraps the value in another variant.
+            Value::Value(inner) => to_f64(inner),
+            Value::F64(scale) => Some(*scale),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn windows_text_scale() -> Option<f64> {
+    use windows_sys::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+    let subkey: Vec<u16> = "Software\\Microsoft\\Accessibility\0"
+        .encode_utf16()
+        .collect();
+    let value: Vec<u16> = "TextScaleFactor\0".encode_utf16().collect();
+    let mut percent: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    // SAFETY: The strings are null-terminated and the output buffer is a DWORD of `size` bytes.
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            subkey.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_DWORD,
+            std::ptr::null_mut(),
+            &mut percent as *mut u32 as *mut _,
+            &mut size,
+        )
+    };
+    // The value is missing until the text size is changed from 100%.
+    (status == 0).then(|| percent as f64 / 100.0)
+}
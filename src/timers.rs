@@ -0,0 +1,56 @@
+//! Throttling of `setTimeout` and `setInterval` in windows which are hidden, like minimized
+//! windows or windows covered by others.
+//!
+//! Servo runs the timers of hidden pages at full rate, so Verso wraps the timer functions of pages
+//! with a script. While the window is occluded, timers wait at least the clamp set with
+//! [`crate::Verso::set_background_timer_throttle`], which is 1 second by default like in other
+//! browsers, and a clamp of 0 disables throttling. Timeouts scheduled before the window is hidden
+//! keep their delay, while intervals are clamped from their next run.
+
+/// Default clamp of timers in hidden windows, in seconds.
+pub const DEFAULT_BACKGROUND_TIMER_THROTTLE: u32 = 1;
+
+/// Script wrapping the timer functions of the page, and setting the clamp and whether the window
+/// is hidden. It can run again to update them.
+pub(crate) fn timer_throttle_script(clamp_secs: u32, hidden: bool) -> String {
+    format!(
+        "let state = window.versoTimers;
+        if (!state) {{
+            state = window.versoTimers = {{ clamp: 0, hidden: false }};
+            const nativeSetTimeout = window.setTimeout.bind(window);
+            const nativeClearTimeout = window.clearTimeout.bind(window);
+            const intervals = new Map();
+            let nextInterval = 1 << 30;
+            const delay = (ms) => {{
+                ms = Number(ms) || 0;
+                return state.hidden ? Math.max(ms, state.clamp) : ms;
+            }};
+            const run = (handler, args) =>
+                typeof handler === 'function' ? handler(...args) : (0, eval)(String(handler));
+            window.setTimeout = function setTimeout(handler, ms, ...args) {{
+                return nativeSetTimeout(() => run(handler, args), delay(ms));
+            }};
+            // Intervals are chains of timeouts, so a change of the clamp applies to their next run.
+            window.setInterval = function setInterval(handler, ms, ...args) {{
+                const id = nextInterval++;
+                const tick = () => {{
+                    intervals.set(id, nativeSetTimeout(tick, delay(ms)));
+                    run(handler, args);
+                }};
+                intervals.set(id, nativeSetTimeout(tick, delay(ms)));
+                return id;
+            }};
+            window.clearTimeout = window.clearInterval = function clearTimeout(id) {{
+                if (intervals.has(id)) {{
+                    nativeClearTimeout(intervals.get(id));
+                    intervals.delete(id);
+                }} else {{
+                    nativeClearTimeout(id);
+                }}
+            }};
+        }}
+        state.clamp = {clamp_ms};
+        state.hidden = {hidden};",
+        clamp_ms = u64::from(clamp_secs) * 1000,
+    )
+}
@@ -0,0 +1,39 @@
+//! Random tokens of the local servers, which clients must present to be served, and of the
+//! scripts Verso injects into top-level pages, which their messages must carry.
+
+use std::io;
+
+use serde::de::DeserializeOwned;
+
+/// Generate a token of 32 hexadecimal digits from the randomness of the operating system.
+pub(crate) fn generate() -> io::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(io::Error::from)?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Compare the tokens in a time which doesn't depend on where they differ.
+pub(crate) fn matches(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Parse the JSON of a message of an injected script, `[token, origin, data]`, and return its
+/// data if it has the token and comes from the origin. Frames and page scripts don't have the
+/// token, so they can't forge messages.
+pub(crate) fn parse_message<T: DeserializeOwned>(
+    json: &str,
+    token: &str,
+    origin: &str,
+) -> Option<T> {
+    let (given, page_origin, data): (String, String, T) = serde_json::from_str(json).ok()?;
+    if !matches(&given, token) {
+        log::warn!("Verso ignores a message without the token from {origin}");
+        return None;
+    }
+    (page_origin == origin).then_some(data)
+}
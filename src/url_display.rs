@@ -0,0 +1,97 @@
+//! Display forms of page URLs for address bars, returned by [`crate::Verso::current_url`] in the
+//! mode set with [`crate::Verso::set_url_display_mode`]. Navigation always uses the raw URL,
+//! returned by [`crate::Verso::current_raw_url`].
+//!
+//! [`UrlDisplayMode::PunycodeDecoded`] shows internationalized domains in Unicode, like
+//! `bücher.example` instead of `xn--bcher-kva.example`. A label mixing letters of several scripts,
+//! like Latin and Cyrillic, can imitate another domain, so such domains are flagged with
+//! [`DisplayUrl::mixed_script`] and kept in their ASCII form. Latin mixed with Han, Hiragana,
+//! Katakana, Hangul or Bopomofo, as written in Chinese, Japanese and Korean, isn't flagged.
+
+use servo_url::ServoUrl;
+use unicode_script::{Script, UnicodeScript};
+use url::{Position, Url};
+
+/// What [`crate::Verso::current_url`] shows of the URL of the page.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UrlDisplayMode {
+    /// The whole URL, with domains in their ASCII form.
+    #[default]
+    Full,
+    /// The URL without its query and fragment.
+    HideQuery,
+    /// Only the host of the URL. URLs without a host, like `file` URLs, are shown without their
+    /// query and fragment.
+    DomainOnly,
+    /// The whole URL, with internationalized domains in Unicode unless they mix scripts.
+    PunycodeDecoded,
+}
+
+/// URL of a page formatted for display.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DisplayUrl {
+    /// Text to show.
+    pub text: String,
+    /// Whether a label of the domain mixes scripts, which may be a homograph attack. It's set in
+    /// every mode.
+    pub mixed_script: bool,
+}
+
+impl DisplayUrl {
+    /// Format the URL for display in the mode.
+    pub fn new(url: &ServoUrl, mode: UrlDisplayMode) -> Self {
+        let url = url.as_url();
+        let unicode_host = url
+            .domain()
+            .map(url::quirks::domain_to_unicode)
+            .filter(|host| !host.is_empty());
+        let mixed_script = unicode_host
+            .as_deref()
+            .is_some_and(|host| host.split('.').any(is_mixed_script));
+        let text = match mode {
+            UrlDisplayMode::Full => url.as_str().to_string(),
+            UrlDisplayMode::HideQuery => without_query(url),
+            UrlDisplayMode::DomainOnly => match url.host_str() {
+                Some(host) if !host.is_empty() => host.to_string(),
+                _ => without_query(url),
+            },
+            UrlDisplayMode::PunycodeDecoded => match (url.domain(), unicode_host) {
+                (Some(ascii), Some(unicode)) if !mixed_script && ascii != unicode => {
+                    let mut text = url.as_str().to_string();
+                    let start = url[..Position::BeforeHost].len();
+                    text.replace_range(start..start + ascii.len(), &unicode);
+                    text
+                }
+                _ => url.as_str().to_string(),
+            },
+        };
+        Self { text, mixed_script }
+    }
+}
+
+fn without_query(url: &Url) -> String {
+    url[..Position::AfterPath].to_string()
+}
+
+/// Return true if the label has letters of several scripts, apart from the combinations of
+/// Chinese, Japanese and Korean with Latin.
+fn is_mixed_script(label: &str) -> bool {
+    let mut scripts = Vec::new();
+    for script in label.chars().map(|c| c.script()) {
+        if matches!(script, Script::Common | Script::Inherited | Script::Unknown) {
+            continue;
+        }
+        if !scripts.contains(&script) {
+            scripts.push(script);
+        }
+    }
+    let han = [Script::Han, Script::Bopomofo];
+    let japanese = [Script::Han, Script::Hiragana, Script::Katakana];
+    let korean = [Script::Han, Script::Hangul];
+    let allowed = |set: &[Script]| {
+        scripts
+            .iter()
+            .all(|script| *script == Script::Latin || set.contains(script))
+    };
+    scripts.len() > 1 && !allowed(&han) && !allowed(&japanese) && !allowed(&korean)
+}
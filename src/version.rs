@@ -0,0 +1,51 @@
+use std::fmt;
+
+/// Versions of Verso and its engine, captured at build time. Fields which couldn't be found,
+/// like the commit of a build outside a git checkout, are `"unknown"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VersionInfo {
+    /// Version of the Verso crate.
+    pub version: &'static str,
+    /// Short hash of the git commit Verso was built from.
+    pub git_commit: &'static str,
+    /// `"true"` if the checkout had uncommitted changes, `"false"` otherwise.
+    pub git_dirty: &'static str,
+    /// Short hash of the Servo revision Verso depends on.
+    pub servo_revision: &'static str,
+    /// Version of WebRender.
+    pub webrender_version: &'static str,
+    /// Version of the compiler.
+    pub rustc_version: &'static str,
+}
+
+/// Get the versions of Verso and its engine.
+pub const fn info() -> VersionInfo {
+    VersionInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_commit: env!("VERSO_GIT_COMMIT"),
+        git_dirty: env!("VERSO_GIT_DIRTY"),
+        servo_revision: env!("VERSO_SERVO_REVISION"),
+        webrender_version: env!("VERSO_WEBRENDER_VERSION"),
+        rustc_version: env!("VERSO_RUSTC_VERSION"),
+    }
+}
+
+impl fmt::Display for VersionInfo {
+    /// Format the versions on one line, like for bug reports.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dirty = if self.git_dirty == "true" {
+            "-dirty"
+        } else {
+            ""
+        };
+        write!(
+            f,
+            "Verso {} ({}{dirty}), Servo {}, WebRender {}, {}",
+            self.version,
+            self.git_commit,
+            self.servo_revision,
+            self.webrender_version,
+            self.rustc_version
+        )
+    }
+}
@@ -1,12 +1,14 @@
 use std::{
     borrow::Cow,
     collections::HashMap,
-    path::PathBuf,
-    sync::{atomic::Ordering, Arc},
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 use arboard::Clipboard;
-use base::id::WebViewId;
+use base::id::{BrowsingContextId, WebViewId};
 use bluetooth::BluetoothThreadFactory;
 use bluetooth_traits::BluetoothRequest;
 use canvas::canvas_paint_thread::{self, CanvasPaintThread};
@@ -17,38 +19,88 @@ use constellation::{Constellation, FromCompositorLogger, InitialConstellationSta
 use crossbeam_channel::{unbounded, Sender};
 use devtools;
 use embedder_traits::{EmbedderMsg, EmbedderProxy, EmbedderReceiver, EventLoopWaker};
-use euclid::Scale;
+use euclid::{Scale, Size2D};
 use fonts::FontCacheThread;
 use gleam::gl;
-use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::{
+    ipc::{self, IpcReceiver, IpcSender},
+    router::ROUTER,
+};
+use keyboard_types::KeyboardEvent;
 use layout_thread_2020;
 use log::{Log, Metadata, Record};
 use media::{GlApi, GlContext, NativeDisplay, WindowGLContext};
 use net::resource_thread;
+use net_traits::ResourceThreads;
 use profile;
 use script::{self, JSEngineSetup};
-use script_traits::WindowSizeData;
+use script_traits::{
+    webdriver_msg::{
+        WebDriverFrameId, WebDriverJSResult, WebDriverJSValue, WebDriverScriptCommand,
+    },
+    TraversalDirection, WebDriverCommandMsg, WindowSizeData,
+};
 use servo_config::{opts, pref};
 use servo_url::ServoUrl;
 use style;
+use style_traits::DevicePixel;
 use surfman::GLApi;
-use units::DeviceIntRect;
+use units::{DeviceIntRect, LayoutPixel};
 use webgpu;
 use webrender::{create_webrender_instance, ShaderPrecacheFlags, WebRenderOptions};
 use webrender_api::*;
 use webrender_traits::*;
 use webxr_api::{LayerGrandManager, LayerGrandManagerAPI, LayerManager, LayerManagerFactory};
 use winit::{
+    dpi::LogicalSize,
     event::WindowEvent,
     event_loop::{ActiveEventLoop, ControlFlow, EventLoopProxy},
     window::WindowId,
 };
 
 use crate::{
-    compositor::{IOCompositor, InitialCompositorState, ShutdownState},
-    config::Config,
-    webview::WebView,
-    window::Window,
+    auto_reload::{AutoReload, AutoReloadOptions, ReloadAction},
+    auto_resize::{AutoResize, AutoResizeStep, ContentSize, CONTENT_SIZE_SCRIPT},
+    autofill::Autofill,
+    caret::ContentInsets,
+    compositor::{IOCompositor, InitialCompositorState, RendererInfo, ShutdownState},
+    config::{
+        AutoplayPolicy, AxisLockMode, BeforeUnloadPolicy, Config, CrashRecoveryPolicy, FontConfig,
+        MonitorSelector, RetryPolicy, SameUrlPolicy, WindowControlPolicy,
+    },
+    content_scripts::ContentScript,
+    contrast::{forced_colors_script, ForcedColors},
+    cookies::{Cookie, CookieUrls},
+    discard::{victims, Candidate, DiscardPolicy, IS_AUDIBLE},
+    dispatch::{dispatch_global, Action},
+    downloads::Download,
+    editing::{CLEAR_SELECTION, SELECT_ALL},
+    encoding::{override_script, Encoding},
+    external::is_external_url,
+    features::{normalize_origin, FeaturePolicy},
+    form_data::FormData,
+    hooks::{Hooks, LifecycleEvent},
+    keyboard::keyboard_event_from_winit,
+    latency::LatencyStats,
+    partitions::PartitionError,
+    policies::ActivePolicies,
+    print::{after_print_script, PrintRequest},
+    progress::{ProgressBar, ProgressBarStyle},
+    recorder::{EventRecorder, EventReplayer, ReplayReport, ReplayedInput},
+    scrollbars::ScrollbarStyle,
+    sensors::{battery_script, default_sensor_provider, BatteryStatus, SensorProvider},
+    shortcuts::{Chord, ShortcutAction},
+    storage::{OriginUsage, StorageKind},
+    suspend::WebViewSnapshot,
+    tabs::TabInfo,
+    url_display::{DisplayUrl, UrlDisplayMode},
+    video::RecordingOptions,
+    webview::{
+        cancel_request, dialog_info, respond_to_dialog, DialogInfo, DialogResponse, FrameInfo,
+        HeldDialog, LoadStatus, PerformanceTiming, WebView, GET_PAGE_STATE,
+    },
+    window::{is_input, MonitorInfo, Window},
+    zoom::FitMode,
 };
 
 /// Main entry point of Verso browser.
@@ -63,7 +115,46 @@ pub struct Verso {
     _js_engine_setup: Option<JSEngineSetup>,
     /// FIXME: It's None on wayland in Flatpak. Find a way to support this.
     clipboard: Option<Clipboard>,
-    resource_dir: PathBuf,
+    config: Config,
+    /// Recorder of input events and embedder messages, if recording is enabled.
+    recorder: Option<EventRecorder>,
+    /// Replayer of recorded input events, kept until [`Verso::finish_replay`] is called.
+    replayer: Option<EventReplayer>,
+    /// Proxy to wake up the event loop from the threads of the servers.
+    #[cfg(any(feature = "cdp", feature = "remote-control"))]
+    event_loop_proxy: EventLoopProxy<()>,
+    /// Chrome DevTools Protocol server, if it's enabled.
+    #[cfg(feature = "cdp")]
+    cdp_server: Option<crate::cdp::CdpServer>,
+    /// Address of Servo's devtools server, if it's enabled.
+    devtools_address: Option<SocketAddr>,
+    /// Servers of local app directories, stopped when Verso shuts down.
+    #[cfg(feature = "local-server")]
+    local_servers: Vec<crate::local_server::LocalServer>,
+    #[cfg(feature = "remote-control")]
+    remote_control: Option<crate::remote_control::RemoteControlServer>,
+    /// Application menu bar.
+    #[cfg(macos)]
+    menu: Option<crate::menu::AppMenu>,
+    /// Provider of sensor values exposed to pages. The platform default is created when it's
+    /// first needed.
+    sensor_provider: Option<Box<dyn SensorProvider>>,
+    /// Closures registered with [`crate::VersoBuilder`].
+    pub(crate) hooks: Hooks,
+    /// Set while hooks are called, so they can't drive the event loop.
+    running_hooks: bool,
+    /// Time page states were last captured for crash recovery.
+    last_page_capture: Instant,
+    /// Time web views were last checked for discarding.
+    last_discard_check: Instant,
+    /// Resource threads of Servo, to access the cookie store.
+    resource_threads: ResourceThreads,
+    /// URLs of the pages loaded in this session, whose cookies are listed.
+    cookie_urls: CookieUrls,
+    /// Auto-reload of the page of a window, if it's enabled.
+    auto_reload: Option<AutoReload>,
+    /// Values of form fields, offered back to the pages of their origins.
+    autofill: Autofill,
 }
 
 impl Verso {
@@ -82,11 +173,25 @@ impl Verso {
     /// - Font cache
     /// - Canvas
     /// - Constellation
-    pub fn new(evl: &ActiveEventLoop, proxy: EventLoopProxy<()>, config: Config) -> Self {
+    ///
+    /// The configuration isn't validated. Use [`crate::builder::VersoBuilder`] to catch invalid
+    /// options. Fails if the window or its GL context can't be created, and the event loop can
+    /// keep running then.
+    pub fn new(
+        evl: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        config: Config,
+    ) -> crate::Result<Self> {
         // Initialize configurations and Verso window
-        let resource_dir = config.resource_dir.clone();
-        config.init();
-        let (window, rendering_context) = Window::new(evl);
+        if config.follow_os_text_scale {
+            crate::text_scale::watch(proxy.clone());
+        }
+        crate::contrast::watch(proxy.clone());
+        config.init()?;
+        let autofill = Autofill::load(opts::get().config_dir.as_deref())?;
+        let (window, rendering_context) = Window::new(evl, &config)?;
+        #[cfg(any(macos, feature = "cdp", feature = "remote-control"))]
+        let event_loop_proxy = proxy.clone();
         let event_loop_waker = Box::new(Waker(proxy));
         let opts = opts::get();
 
@@ -110,8 +215,15 @@ impl Verso {
         };
 
         // Make sure the gl context is made current.
-        rendering_context.make_gl_context_current().unwrap();
+        rendering_context
+            .make_gl_context_current()
+            .map_err(crate::Error::SurfmanError)?;
         debug_assert_eq!(webrender_gl.get_error(), gl::NO_ERROR,);
+        crate::crash::set_renderer(format!(
+            "{} ({})",
+            webrender_gl.get_string(gl::RENDERER),
+            webrender_gl.get_string(gl::VERSION)
+        ));
         // Bind the webrender framebuffer
         let framebuffer_object = rendering_context
             .context_surface_info()
@@ -287,6 +399,7 @@ impl Verso {
             font_cache_thread.clone(),
             public_resource_threads.clone(),
         );
+        let resource_threads = public_resource_threads.clone();
 
         // Create layout factory
         let layout_factory = Arc::new(layout_thread_2020::LayoutFactoryImpl());
@@ -341,7 +454,7 @@ impl Verso {
 
         // The compositor coordinates with the client window to create the final
         // rendered page and display it somewhere.
-        let compositor = IOCompositor::new(
+        let mut compositor = IOCompositor::new(
             window.id(),
             window.size(),
             Scale::new(window.scale_factor() as f32),
@@ -362,19 +475,39 @@ impl Verso {
             opts.debug.convert_mouse_to_touch,
         );
 
+        compositor.set_progress_bar_style(config.progress_bar, &window);
+        compositor.set_scrollbar_style(config.scrollbars, &window);
+
         // Send the constellation message to start Panel UI
         // TODO: Should become a window method
-        let panel_id = window.panel.as_ref().unwrap().webview_id;
-        let path = resource_dir.join("panel.html");
-        let url = ServoUrl::from_file_path(path.to_str().unwrap()).unwrap();
-        send_to_constellation(
-            &constellation_sender,
-            ConstellationMsg::NewWebView(url, panel_id),
-        );
+        // The first window is always created with a panel.
+        let panel_id = window
+            .panel
+            .as_ref()
+            .expect("Window has no panel")
+            .webview_id;
+        match panel_url(&config) {
+            Ok(url) => send_to_constellation(
+                &constellation_sender,
+                ConstellationMsg::NewWebView(url, panel_id),
+            ),
+            Err(e) => log::error!("Verso failed to load panel: {e}"),
+        }
 
         let mut windows = HashMap::new();
         windows.insert(window.id(), window);
 
+        let recorder = config.record_events.as_ref().and_then(|path| {
+            EventRecorder::new(path)
+                .map_err(|e| log::error!("Verso failed to create event recording {path:?}: {e}"))
+                .ok()
+        });
+
+        #[cfg(macos)]
+        let menu = crate::menu::AppMenu::new(event_loop_proxy.clone())
+            .map_err(|e| log::error!("Verso failed to create application menu: {e}"))
+            .ok();
+
         // Create Verso instance
         let verso = Verso {
             windows,
@@ -383,41 +516,302 @@ impl Verso {
             embedder_receiver,
             _js_engine_setup: js_engine_setup,
             clipboard: Clipboard::new().ok(),
-            resource_dir,
+            config,
+            recorder,
+            replayer: None,
+            #[cfg(any(feature = "cdp", feature = "remote-control"))]
+            event_loop_proxy,
+            #[cfg(feature = "cdp")]
+            cdp_server: None,
+            devtools_address: opts
+                .devtools_server_enabled
+                .then(|| SocketAddr::from((Ipv4Addr::LOCALHOST, opts.devtools_port))),
+            #[cfg(feature = "local-server")]
+            local_servers: Vec::new(),
+            #[cfg(feature = "remote-control")]
+            remote_control: None,
+            #[cfg(macos)]
+            menu,
+            sensor_provider: None,
+            hooks: Hooks::default(),
+            running_hooks: false,
+            last_page_capture: Instant::now(),
+            last_discard_check: Instant::now(),
+            resource_threads,
+            cookie_urls: CookieUrls::default(),
+            auto_reload: None,
+            autofill,
         };
 
         verso.setup_logging();
-        verso
+        Ok(verso)
     }
 
-    /// Handle Winit window events
-    pub fn handle_winit_window_event(&mut self, window_id: WindowId, event: WindowEvent) {
-        log::trace!("Verso is handling Winit event: {event:?}");
+    /// Release the rendering surface and pause playing media when the application is suspended.
+    /// On Android the native surface is destroyed while the app is in background, so it must
+    /// not be used until [`Verso::resume`].
+    pub fn suspend(&mut self) {
+        log::debug!("Verso is suspending");
         if let Some(compositor) = &mut self.compositor {
-            if let WindowEvent::CloseRequested = event {
-                // self.windows.remove(&window_id);
-                compositor.maybe_start_shutting_down();
-            } else {
-                let mut need_repaint = false;
-                for (id, window) in &mut self.windows {
-                    if window_id == *id {
-                        need_repaint = window.handle_winit_window_event(
-                            &self.constellation_sender,
-                            compositor,
-                            &event,
-                        );
+            compositor.invalidate_native_surface();
+        }
+        self.run_in_webviews("pause media", PAUSE_MEDIA);
+    }
+
+    /// Discard the cached rendering of the current window and repaint it entirely, as a way for
+    /// users to fix rendering glitches like stale tiles, or corruption after the GL context was
+    /// lost.
+    pub fn invalidate(&mut self) {
+        let Some(compositor) = &mut self.compositor else {
+            return;
+        };
+        if let Some(window) = self.windows.get(&compositor.current_window) {
+            compositor.invalidate(window);
+        }
+    }
+
+    /// Simulate the loss of the GL context, to test recovering from it without a driver reset.
+    /// Verso recreates the rendering surface before the next composite, and calls the
+    /// `on_surface_reset` hook. The context isn't actually lost, so this doesn't show whether a
+    /// driver keeps the resources of a lost one.
+    #[cfg(debug_assertions)]
+    pub fn simulate_context_loss(&mut self) {
+        if let Some(compositor) = &mut self.compositor {
+            compositor.simulate_context_loss();
+        }
+    }
+
+    /// Recreate the rendering surface and resume the media paused by [`Verso::suspend`] when
+    /// the application is resumed.
+    pub fn resume(&mut self) {
+        log::debug!("Verso is resuming");
+        if let Some(compositor) = &mut self.compositor {
+            if let Some(window) = self.windows.get_mut(&compositor.current_window) {
+                compositor.rebind_native_surface(window);
+                window.request_redraw();
+            }
+        }
+        self.run_in_webviews("resume media", RESUME_MEDIA);
+    }
+
+    /// Run the script in the web view of every window, logging failures.
+    fn run_in_webviews(&self, name: &'static str, script: &str) {
+        for webview in self.windows.values().filter_map(|w| w.webview.as_ref()) {
+            let id = BrowsingContextId::from(webview.webview_id);
+            if let Some(receiver) = execute_script(&self.constellation_sender, id, script) {
+                std::thread::spawn(move || {
+                    if let Ok(Err(e)) = receiver.recv() {
+                        log::warn!("Verso failed to {name}: {e:?}");
                     }
+                });
+            }
+        }
+    }
+
+    /// Handle Winit window events
+    pub fn handle_winit_window_event(&mut self, window_id: WindowId, event: WindowEvent) {
+        if self.running_hooks {
+            log::error!("Verso hooks must not handle Winit events");
+            return;
+        }
+        if log::log_enabled!(log::Level::Trace) {
+            log::trace!("Verso is handling Winit event: {event:?}");
+        }
+        if let WindowEvent::KeyboardInput { event: key, .. } = &event {
+            if let Some(window) = self.windows.get(&window_id) {
+                let keyboard = keyboard_event_from_winit(key, window.modifiers());
+                self.handle_keyboard_event(window_id, keyboard, key.text.as_deref());
+            }
+            return;
+        }
+        // Replayed inputs were recorded already.
+        if let (Some(recorder), None) = (&mut self.recorder, &self.replayer) {
+            recorder.record_input(&event);
+        }
+        if let Some(auto_reload) = &mut self.auto_reload {
+            if auto_reload.window_id == window_id && is_input(&event) {
+                auto_reload.on_input(Instant::now());
+            }
+        }
+        if let WindowEvent::CloseRequested = event {
+            // self.windows.remove(&window_id);
+            self.shutdown();
+        } else if let Some(compositor) = &mut self.compositor {
+            let mut need_repaint = false;
+            for (id, window) in &mut self.windows {
+                if window_id == *id {
+                    need_repaint = window.handle_winit_window_event(
+                        &self.constellation_sender,
+                        compositor,
+                        &event,
+                        &self.config,
+                    );
                 }
+            }
+
+            if need_repaint {
+                compositor.repaint_synchronously(&mut self.windows);
+            }
+        }
+
+        #[cfg(linux)]
+        if self.config.primary_selection {
+            self.handle_primary_selection(window_id, &event);
+        }
+    }
+
+    /// Handle a keyboard event of the window with the text the key inserts. Winit's keyboard
+    /// input is converted to these by [`Verso::handle_winit_window_event`]. Winit's `KeyEvent`
+    /// can't be constructed outside of Winit, so this is how replays and embedders feed keys.
+    pub fn handle_keyboard_event(
+        &mut self,
+        window_id: WindowId,
+        event: KeyboardEvent,
+        text: Option<&str>,
+    ) {
+        if self.running_hooks {
+            log::error!("Verso hooks must not handle Winit events");
+            return;
+        }
+        if let (Some(recorder), None) = (&mut self.recorder, &self.replayer) {
+            recorder.record_key(&event, text);
+        }
+        if let Some(auto_reload) = &mut self.auto_reload {
+            if auto_reload.window_id == window_id {
+                auto_reload.on_input(Instant::now());
+            }
+        }
+        if let (Some(compositor), Some(window)) =
+            (&mut self.compositor, self.windows.get_mut(&window_id))
+        {
+            window.handle_keyboard_event(
+                &self.constellation_sender,
+                compositor,
+                event,
+                text,
+                &self.config,
+            );
+        }
+    }
 
-                if need_repaint {
-                    compositor.repaint_synchronously(&mut self.windows);
+    /// Copy the selection to the primary selection after left click, and paste the primary
+    /// selection into editable elements on middle click.
+    #[cfg(linux)]
+    fn handle_primary_selection(&mut self, window_id: WindowId, event: &WindowEvent) {
+        use winit::event::{ElementState, MouseButton};
+
+        let WindowEvent::MouseInput {
+            state: ElementState::Released,
+            button,
+            ..
+        } = event
+        else {
+            return;
+        };
+        let (Some(compositor), Some(window)) = (&self.compositor, self.windows.get(&window_id))
+        else {
+            return;
+        };
+        let Some(webview) = &window.webview else {
+            return;
+        };
+        let browsing_context_id = BrowsingContextId::from(webview.webview_id);
+        match button {
+            MouseButton::Left => {
+                crate::selection::copy_selection_to_primary(
+                    &self.constellation_sender,
+                    browsing_context_id,
+                );
+            }
+            MouseButton::Middle => {
+                if let (Some(clipboard), Some(point)) = (
+                    self.clipboard.as_mut(),
+                    window.mouse_position_in_webview(compositor),
+                ) {
+                    crate::selection::paste_primary(
+                        clipboard,
+                        &self.constellation_sender,
+                        browsing_context_id,
+                        point,
+                    );
                 }
             }
+            _ => {}
+        }
+    }
+
+    /// Select the content of the focused text field or editable element of the current window,
+    /// or the text of the whole page if none is focused. The selection is copied to the primary
+    /// selection on Linux. See [`crate::editing`].
+    pub fn select_all(&mut self) {
+        let Some(browsing_context_id) = self.current_browsing_context_id() else {
+            return;
+        };
+        self.run_selection_command(browsing_context_id, SELECT_ALL);
+        #[cfg(linux)]
+        if self.config.primary_selection {
+            crate::selection::copy_selection_to_primary(
+                &self.constellation_sender,
+                browsing_context_id,
+            );
+        }
+    }
+
+    /// Clear the selection of the page of the current window. The caret of a focused text field
+    /// is kept at the end of its former selection. See [`crate::editing`].
+    pub fn clear_selection(&mut self) {
+        if let Some(browsing_context_id) = self.current_browsing_context_id() {
+            self.run_selection_command(browsing_context_id, CLEAR_SELECTION);
         }
     }
 
+    fn current_browsing_context_id(&self) -> Option<BrowsingContextId> {
+        let webview = self.current_window()?.webview.as_ref()?;
+        Some(BrowsingContextId::from(webview.webview_id))
+    }
+
+    fn run_selection_command(&self, browsing_context_id: BrowsingContextId, script: &str) {
+        let Some(receiver) =
+            execute_script(&self.constellation_sender, browsing_context_id, script)
+        else {
+            return;
+        };
+        std::thread::spawn(move || {
+            if let Ok(Err(e)) = receiver.recv() {
+                log::warn!("Verso failed to change the selection: {e:?}");
+            }
+        });
+    }
+
     /// Handle message came from Servo.
     pub fn handle_servo_messages(&mut self, evl: &ActiveEventLoop) {
+        if self.running_hooks {
+            log::error!("Verso hooks must not handle Servo messages");
+            return;
+        }
+        if crate::text_scale::take_change() && self.config.follow_os_text_scale {
+            self.config.fonts.apply(self.config.text_scale());
+        }
+        if crate::contrast::take_change() && self.config.forced_colors == ForcedColors::Auto {
+            self.apply_forced_colors();
+        }
+        if self.config.crash_recovery.max_restores > 0 {
+            self.capture_page_states();
+        }
+        self.check_discards();
+        self.replay_due_events();
+        self.tick_auto_reload();
+        for window in self.windows.values_mut() {
+            window.flush_updates(&self.constellation_sender, &self.config);
+        }
+        #[cfg(feature = "cdp")]
+        self.handle_cdp_requests();
+        #[cfg(feature = "remote-control")]
+        self.handle_remote_control_requests();
+
+        #[cfg(macos)]
+        self.handle_menu_actions(evl);
+
         let mut shutdown = false;
         if let Some(compositor) = &mut self.compositor {
             // Handle Compositor's messages first
@@ -429,32 +823,75 @@ impl Verso {
                     compositor.shutdown_state
                 );
                 while let Some((webview_id, msg)) = self.embedder_receiver.try_recv_embedder_msg() {
+                    if let Some(replayer) = &mut self.replayer {
+                        replayer.observe(webview_id, &msg);
+                    } else if let Some(recorder) = &mut self.recorder {
+                        recorder.record_embedder(webview_id, &msg);
+                    }
                     match compositor.shutdown_state {
                         ShutdownState::NotShuttingDown => {
                             if let Some(id) = webview_id {
                                 for window in self.windows.values_mut() {
                                     if window.has_webview(id) {
-                                        if window.handle_servo_message(
+                                        let page_loaded = matches!(msg, EmbedderMsg::LoadComplete)
+                                            && window
+                                                .webview
+                                                .as_ref()
+                                                .map_or(false, |w| w.webview_id == id);
+                                        if let Some(script) = self
+                                            .auto_reload
+                                            .as_mut()
+                                            .filter(|r| page_loaded && r.window_id == window.id())
+                                            .and_then(AutoReload::take_restore_script)
+                                        {
+                                            run_script(
+                                                &self.constellation_sender,
+                                                id,
+                                                script,
+                                                "restore the scroll position",
+                                            );
+                                        }
+                                        if page_loaded && self.config.battery_status {
+                                            let provider = self
+                                                .sensor_provider
+                                                .get_or_insert_with(default_sensor_provider);
+                                            expose_battery_status(
+                                                &self.constellation_sender,
+                                                id,
+                                                provider.battery(),
+                                            );
+                                        }
+                                        window.report_load_errors = self.hooks.after_load.is_some();
+                                        window.report_content_size =
+                                            self.hooks.on_content_size_changed.is_some()
+                                                || window.auto_resize.is_some();
+                                        let new_window = window.handle_servo_message(
                                             id,
                                             msg,
                                             &self.constellation_sender,
                                             self.clipboard.as_mut(),
                                             compositor,
-                                        ) {
-                                            let mut window =
-                                                Window::new_with_compositor(evl, compositor);
-                                            let panel_id = WebViewId::new();
-                                            let path = self.resource_dir.join("panel.html");
-                                            let url =
-                                                ServoUrl::from_file_path(path.to_str().unwrap())
-                                                    .unwrap();
-                                            send_to_constellation(
+                                            &self.config,
+                                        );
+                                        for request in std::mem::take(&mut window.autofill_requests)
+                                        {
+                                            self.autofill
+                                                .handle(request, &self.constellation_sender);
+                                        }
+                                        if new_window {
+                                            match create_window(
+                                                evl,
+                                                compositor,
                                                 &self.constellation_sender,
-                                                ConstellationMsg::NewWebView(url, panel_id),
-                                            );
-                                            let rect = DeviceIntRect::from_size(window.size());
-                                            window.panel = Some(WebView::new(panel_id, rect));
-                                            self.windows.insert(window.id(), window);
+                                                &self.config,
+                                            ) {
+                                                Ok(window) => {
+                                                    self.windows.insert(window.id(), window);
+                                                }
+                                                Err(e) => log::error!(
+                                                    "Verso failed to create a window: {e}"
+                                                ),
+                                            }
                                         }
                                         break;
                                     }
@@ -462,18 +899,35 @@ impl Verso {
                             } else {
                                 // Handle message in Verso Window
                                 log::trace!("Verso Window is handling Embedder message: {msg:?}");
-                                match msg {
-                                    EmbedderMsg::SetCursor(cursor) => {
-                                        // TODO: This should move to compositor
-                                        if let Some(window) =
-                                            self.windows.get(&compositor.current_window)
-                                        {
-                                            window.set_cursor_icon(cursor);
+                                for action in dispatch_global(msg) {
+                                    match action {
+                                        Action::SetCursor(cursor) => {
+                                            if let Some(window) =
+                                                self.windows.get(&compositor.current_window)
+                                            {
+                                                window.set_cursor_icon(cursor);
+                                            }
                                         }
-                                    }
-                                    EmbedderMsg::Shutdown | EmbedderMsg::ReadyToPresent(_) => {}
-                                    e => {
-                                        log::trace!("Verso Window isn't supporting handling this message yet: {e:?}")
+                                        Action::AllowDevtoolsConnection(sender) => {
+                                            log::info!("Verso accepts a devtools client");
+                                            if let Err(e) = sender.send(true) {
+                                                log::warn!(
+                                                    "Verso failed to accept a devtools client: {e}"
+                                                );
+                                            }
+                                        }
+                                        Action::SetDevtoolsPort(Some(port)) => {
+                                            log::info!("Verso devtools server listens on {port}");
+                                            self.devtools_address =
+                                                Some(SocketAddr::from((Ipv4Addr::LOCALHOST, port)));
+                                        }
+                                        Action::SetDevtoolsPort(None) => {
+                                            log::error!("Verso devtools server failed to start");
+                                            self.devtools_address = None;
+                                        }
+                                        action => log::warn!(
+                                            "Verso can't apply {action:?} without a web view"
+                                        ),
                                     }
                                 }
                             }
@@ -481,11 +935,20 @@ impl Verso {
                         ShutdownState::FinishedShuttingDown => {
                             log::error!("Verso shouldn't be handling messages after compositor has shut down");
                         }
-                        ShutdownState::ShuttingDown => {}
+                        // Pages still waiting for an answer are told no, instead of finding the
+                        // channel closed.
+                        ShutdownState::ShuttingDown => cancel_request(msg),
                     }
                 }
             }
 
+            #[cfg(macos)]
+            if let (Some(menu), Some(window)) =
+                (&self.menu, self.windows.get(&compositor.current_window))
+            {
+                menu.set_history_state(window.can_go_back(), window.can_go_forward());
+            }
+
             if compositor.shutdown_state != ShutdownState::FinishedShuttingDown {
                 // Update compositor
                 compositor.perform_updates(&mut self.windows);
@@ -494,111 +957,2013 @@ impl Verso {
             }
         }
 
+        self.run_hooks();
+
         // Check if Verso need to start shutting down.
         if self.windows.is_empty() {
-            self.compositor
-                .as_mut()
-                .map(IOCompositor::maybe_start_shutting_down);
+            self.shutdown();
         }
 
         // Check compositor status and set control flow.
         if shutdown {
             // If Compositor has shut down, deinit and remove it.
             self.compositor.take().map(IOCompositor::deinit);
+            #[cfg(feature = "local-server")]
+            self.local_servers.clear();
+            #[cfg(feature = "remote-control")]
+            self.remote_control.take();
             evl.exit();
         } else if self.is_animating() {
             evl.set_control_flow(ControlFlow::Poll);
+        } else if let Some(deadline) = self
+            .replayer
+            .as_ref()
+            .and_then(EventReplayer::next_deadline)
+            .into_iter()
+            .chain(self.auto_reload.as_ref().map(AutoReload::deadline))
+            .chain(self.windows.values().filter_map(|window| {
+                window
+                    .update_throttle
+                    .deadline(self.config.title_update_interval)
+            }))
+            .chain(
+                self.compositor
+                    .as_ref()
+                    .and_then(IOCompositor::next_debug_overlay_refresh),
+            )
+            .chain(
+                self.compositor
+                    .as_ref()
+                    .and_then(IOCompositor::next_resize_retry),
+            )
+            .min()
+        {
+            evl.set_control_flow(ControlFlow::WaitUntil(deadline));
         } else {
             evl.set_control_flow(ControlFlow::Wait);
         }
     }
 
-    /// Return true if one of the Verso windows is animating.
-    pub fn is_animating(&self) -> bool {
-        self.compositor
-            .as_ref()
-            .map(|c| c.is_animating)
-            .unwrap_or(false)
+    /// Capture the scroll position and form fields of loaded pages every few seconds, to restore
+    /// them after a crash. See [`CrashRecoveryPolicy`].
+    fn capture_page_states(&mut self) {
+        if self.last_page_capture.elapsed() < PAGE_STATE_INTERVAL {
+            return;
+        }
+        self.last_page_capture = Instant::now();
+        for window in self.windows.values() {
+            let Some(webview) = &window.webview else {
+                continue;
+            };
+            if window.load_status() != LoadStatus::Complete || window.crash_restore.is_some() {
+                continue;
+            }
+            let id = BrowsingContextId::from(webview.webview_id);
+            let Some(receiver) = execute_script(&self.constellation_sender, id, GET_PAGE_STATE)
+            else {
+                continue;
+            };
+            let page_state = window.page_state.clone();
+            std::thread::spawn(move || match receiver.recv() {
+                Ok(Ok(WebDriverJSValue::String(state))) => {
+                    if let Ok(mut page_state) = page_state.lock() {
+                        *page_state = Some(state);
+                    }
+                }
+                result => log::debug!("Verso failed to capture page state: {result:?}"),
+            });
+        }
     }
 
-    fn setup_logging(&self) {
-        let constellation_chan = self.constellation_sender.clone();
-        let env = env_logger::Env::default();
-        let env_logger = env_logger::Builder::from_env(env).build();
-        let con_logger = FromCompositorLogger::new(constellation_chan);
+    /// Discard web views beyond [`DiscardPolicy::max_alive`], or one under memory pressure.
+    fn check_discards(&mut self) {
+        let policy = self.config.discard;
+        if policy.max_alive.is_none() && !policy.discard_on_memory_pressure {
+            return;
+        }
+        if self.last_discard_check.elapsed() < crate::discard::CHECK_INTERVAL {
+            return;
+        }
+        self.last_discard_check = Instant::now();
+        let alive = self
+            .windows
+            .values()
+            .filter(|w| w.webview.is_some())
+            .count();
+        let pressure = policy.discard_on_memory_pressure && crate::discard::memory_pressure();
+        let count = policy.discard_count(alive, pressure);
+        if count > 0 {
+            self.discard_webviews(count);
+        }
+    }
 
-        let filter = std::cmp::max(env_logger.filter(), con_logger.filter());
-        let logger = BothLogger(env_logger, con_logger);
+    /// Discard up to `count` web views of background windows, and return how many were
+    /// discarded. See [`crate::discard`].
+    fn discard_webviews(&mut self, count: usize) -> usize {
+        let current = self.compositor.as_ref().map(|c| c.current_window);
+        let candidates: Vec<_> = self
+            .windows
+            .values()
+            .map(|w| Candidate {
+                key: w.id(),
+                last_focused: w.last_focused.get(),
+                focused: Some(w.id()) == current,
+                pinned: w.pinned,
+                alive: w.webview.is_some() && w.url.is_some(),
+            })
+            .collect();
+        let mut discarded = 0;
+        for window_id in victims(&candidates) {
+            if discarded == count {
+                break;
+            }
+            let Some(webview_id) = self
+                .windows
+                .get(&window_id)
+                .and_then(|w| w.webview.as_ref())
+                .map(|w| w.webview_id)
+            else {
+                continue;
+            };
+            let id = BrowsingContextId::from(webview_id);
+            let audible = self.frame_script_command(id, |sender| {
+                WebDriverScriptCommand::ExecuteScript(IS_AUDIBLE.to_string(), sender)
+            });
+            if matches!(audible, Some(Ok(WebDriverJSValue::Boolean(true)))) {
+                continue;
+            }
+            let state = match self.frame_script_command(id, |sender| {
+                WebDriverScriptCommand::ExecuteScript(GET_PAGE_STATE.to_string(), sender)
+            }) {
+                Some(Ok(WebDriverJSValue::String(state))) => Some(state),
+                _ => None,
+            };
+            if let Some(window) = self.windows.get_mut(&window_id) {
+                window.discard(state, &self.constellation_sender);
+                discarded += 1;
+            }
+        }
+        discarded
+    }
 
-        log::set_boxed_logger(Box::new(logger)).expect("Failed to set logger.");
-        log::set_max_level(filter);
+    /// Call the hooks of the lifecycle events queued by windows. See [`crate::hooks`].
+    fn run_hooks(&mut self) {
+        let events: Vec<LifecycleEvent> = self
+            .windows
+            .values_mut()
+            .flat_map(|window| std::mem::take(&mut window.lifecycle_events))
+            .collect();
+        if events.is_empty() {
+            return;
+        }
+        self.running_hooks = true;
+        for event in events {
+            match event {
+                LifecycleEvent::Ready => {
+                    if let Some(on_ready) = self.hooks.on_ready.take() {
+                        on_ready(self);
+                    }
+                }
+                LifecycleEvent::Navigate(webview_id, pipeline_id, url) if is_external_url(&url) => {
+                    self.open_external_url(&url);
+                    send_to_constellation(
+                        &self.constellation_sender,
+                        ConstellationMsg::AllowNavigationResponse(pipeline_id, false),
+                    );
+                }
+                LifecycleEvent::Navigate(webview_id, pipeline_id, url)
+                    if !self.config.policies.allows_url(&url) =>
+                {
+                    log::warn!("Verso WebView {webview_id:?} can't load {url}: denied by policy");
+                    send_to_constellation(
+                        &self.constellation_sender,
+                        ConstellationMsg::AllowNavigationResponse(pipeline_id, false),
+                    );
+                }
+                LifecycleEvent::Navigate(webview_id, pipeline_id, url) => {
+                    let allow = self
+                        .hooks
+                        .before_navigate
+                        .as_mut()
+                        .map_or(true, |before_navigate| before_navigate(&url));
+                    if allow {
+                        if let Some(window) =
+                            self.windows.values().find(|w| w.has_webview(webview_id))
+                        {
+                            window.set_load_status(LoadStatus::NavigationStarted);
+                            if window.crash_restore.is_none() {
+                                window.restores.set(0);
+                            }
+                        }
+                    } else {
+                        log::debug!("Verso WebView {webview_id:?} isn't allowed to load {url}");
+                    }
+                    send_to_constellation(
+                        &self.constellation_sender,
+                        ConstellationMsg::AllowNavigationResponse(pipeline_id, allow),
+                    );
+                }
+                LifecycleEvent::Loaded(url, result) => {
+                    self.cookie_urls.insert(&url);
+                    if let Some(after_load) = self.hooks.after_load.as_mut() {
+                        after_load(&url, result);
+                    }
+                }
+                LifecycleEvent::OpenExternal(url) => self.open_external_url(&url),
+                LifecycleEvent::Print(webview_id) => self.print_webview(webview_id),
+                LifecycleEvent::Discarded(window_id, discarded) => {
+                    if let Some(on_discard) = self.hooks.on_discard.as_mut() {
+                        on_discard(window_id, discarded);
+                    }
+                }
+                LifecycleEvent::LoginForm(form) => {
+                    if let Some(on_login_form) = self.hooks.on_login_form.as_mut() {
+                        on_login_form(&form);
+                    }
+                }
+                LifecycleEvent::CredentialsSubmitted(credentials) => {
+                    if let Some(on_credentials_submitted) =
+                        self.hooks.on_credentials_submitted.as_mut()
+                    {
+                        on_credentials_submitted(&credentials);
+                    }
+                }
+                LifecycleEvent::TabUpdated(window_id, info) => {
+                    if let Some(on_tab_updated) = self.hooks.on_tab_updated.as_mut() {
+                        on_tab_updated(window_id, &info);
+                    }
+                }
+                LifecycleEvent::AutoReloadFired(window_id, count) => {
+                    if let Some(on_auto_reload) = self.hooks.on_auto_reload.as_mut() {
+                        on_auto_reload(window_id, count);
+                    }
+                }
+                LifecycleEvent::SurfaceReset(cause) => {
+                    if let Some(on_surface_reset) = self.hooks.on_surface_reset.as_mut() {
+                        on_surface_reset(&cause);
+                    }
+                }
+                LifecycleEvent::Resumed(webview_id, progress) => {
+                    if let Some(on_resume) = self.hooks.on_resume.as_mut() {
+                        on_resume(webview_id, progress);
+                    }
+                }
+                LifecycleEvent::ZoomChanged(window_id, change) => {
+                    if let Some(on_zoom_changed) = self.hooks.on_zoom_changed.as_mut() {
+                        on_zoom_changed(window_id, &change);
+                    }
+                }
+                LifecycleEvent::Download(download) => self.save_download(download),
+                LifecycleEvent::ContentSizeChanged(window_id, size) => {
+                    if let Some(on_content_size_changed) =
+                        self.hooks.on_content_size_changed.as_mut()
+                    {
+                        on_content_size_changed(window_id, size);
+                    }
+                    self.fit_window_to_content(window_id, size);
+                }
+            }
+        }
+        self.running_hooks = false;
     }
-}
 
-#[derive(Debug, Clone)]
-struct Waker(pub EventLoopProxy<()>);
+    /// Resize the window to fit the content size of its page, if it's enabled. See
+    /// [`crate::auto_resize`].
+    fn fit_window_to_content(&mut self, window_id: WindowId, content: ContentSize) {
+        let zoom = f64::from(self.page_zoom());
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+        let (Some(auto_resize), Some(webview)) = (&mut window.auto_resize, &window.webview) else {
+            return;
+        };
+        let scale_factor = window.window.scale_factor();
+        let inner = window.window.inner_size().to_logical::<f64>(scale_factor);
+        let view = webview.rect.size();
+        let chrome = LogicalSize::new(
+            inner.width - f64::from(view.width) / scale_factor,
+            inner.height - f64::from(view.height) / scale_factor,
+        );
+        let content = LogicalSize::new(
+            f64::from(content.width) * zoom,
+            f64::from(content.height) * zoom,
+        );
+        match auto_resize.fit(content, inner, chrome, Instant::now()) {
+            AutoResizeStep::Keep => {}
+            AutoResizeStep::Resize(size) => {
+                log::debug!("Verso Window {window_id:?} fits its content with size {size:?}");
+                let _ = window.window.request_inner_size(size);
+            }
+            AutoResizeStep::Stop => {
+                log::warn!(
+                    "Verso Window {window_id:?} stops fitting its content, which follows the \
+                    size of the window"
+                );
+                window.auto_resize = None;
+                if let Some(on_auto_resize_stopped) = self.hooks.on_auto_resize_stopped.as_mut() {
+                    on_auto_resize_stopped(window_id);
+                }
+            }
+        }
+    }
 
-impl EventLoopWaker for Waker {
-    fn clone_box(&self) -> Box<dyn EventLoopWaker> {
-        Box::new(self.clone())
+    /// Pass the URL to the `on_external_url` hook, or open it with the handler of the operating
+    /// system, unless its scheme is blocked.
+    fn open_external_url(&mut self, url: &ServoUrl) {
+        if self.config.blocked_schemes.contains(url.scheme()) {
+            log::debug!("Verso blocks external URL {url}");
+            return;
+        }
+        match self.hooks.on_external_url.as_mut() {
+            Some(on_external_url) => on_external_url(url),
+            None => {
+                if let Err(e) = crate::external::open_with_os(url) {
+                    log::warn!("Verso failed to open {url}: {e}");
+                }
+            }
+        }
     }
 
-    fn wake(&self) {
-        if let Err(e) = self.0.send_event(()) {
-            log::error!("Servo failed to send wake up event to Verso: {}", e);
+    /// Pass a request to print the page of the web view to the `on_print` hook, and report the
+    /// result to the page.
+    fn print_webview(&mut self, webview_id: WebViewId) {
+        let Some(url) = self
+            .windows
+            .values()
+            .find(|w| w.has_webview(webview_id))
+            .and_then(|w| w.url.clone())
+        else {
+            return;
+        };
+        let request = PrintRequest::new(url, &self.config.print);
+        log::debug!("Verso WebView {webview_id:?} prints {request:?}");
+        let result = match self.hooks.on_print.as_mut() {
+            Some(on_print) => on_print(&request),
+            None => Err("Verso can't print to PDF without the on_print hook".into()),
+        };
+        if let Err(e) = &result {
+            log::warn!("Verso failed to print {}: {e}", request.url);
+        }
+        let script = after_print_script(&result);
+        if let Some(receiver) = execute_script(
+            &self.constellation_sender,
+            BrowsingContextId::from(webview_id),
+            script,
+        ) {
+            std::thread::spawn(move || {
+                if let Ok(Err(e)) = receiver.recv() {
+                    log::warn!("Verso failed to report printing to the page: {e:?}");
+                }
+            });
         }
     }
-}
 
-fn default_user_agent_string() -> &'static str {
-    #[cfg(macos)]
-    const UA_STRING: &str =
-        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:109.0) Servo/1.0 Firefox/111.0";
-    #[cfg(ios)]
-    const UA_STRING: &str =
-        "Mozilla/5.0 (iPhone; CPU iPhone OS 16_4 like Mac OS X; rv:109.0) Servo/1.0 Firefox/111.0";
-    #[cfg(android)]
-    const UA_STRING: &str = "Mozilla/5.0 (Android; Mobile; rv:109.0) Servo/1.0 Firefox/111.0";
-    #[cfg(linux)]
-    const UA_STRING: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Servo/1.0 Firefox/111.0";
-    #[cfg(windows)]
-    const UA_STRING: &str =
-        "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:109.0) Servo/1.0 Firefox/111.0";
+    /// Pass a download of a page to the `on_download` hook, or save it to the downloads
+    /// directory if the hook doesn't handle it. See [`crate::downloads`].
+    fn save_download(&mut self, download: Download) {
+        log::debug!("Verso downloads {download:?}");
+        if let Some(on_download) = self.hooks.on_download.as_mut() {
+            if on_download(&download) {
+                return;
+            }
+        }
+        match download.save() {
+            Ok(path) => log::info!("Verso saved download to {path:?}"),
+            Err(e) => log::warn!("Verso failed to save download {}: {e}", download.file_name),
+        }
+    }
 
-    UA_STRING
-}
+    /// Copy the image under the mouse in the current window to the clipboard, like the "Copy
+    /// Image" item of a context menu. See [`crate::clipboard`].
+    pub fn copy_image(&self) {
+        let (Some(compositor), Some(window)) = (&self.compositor, self.current_window()) else {
+            return;
+        };
+        let (Some(webview), Some(point)) = (
+            &window.webview,
+            window.mouse_position_in_webview(compositor),
+        ) else {
+            return;
+        };
+        crate::clipboard::copy_image_at(
+            &self.constellation_sender,
+            BrowsingContextId::from(webview.webview_id),
+            point,
+        );
+    }
 
-#[derive(Clone)]
-struct RenderNotifier {
-    compositor_proxy: CompositorProxy,
-}
+    /// Print the page of the current window. See [`crate::print`].
+    pub fn print(&mut self) {
+        if let Some(webview_id) = self.current_webview_id() {
+            self.print_webview(webview_id);
+        }
+    }
 
-impl RenderNotifier {
-    pub fn new(compositor_proxy: CompositorProxy) -> RenderNotifier {
-        RenderNotifier { compositor_proxy }
+    /// Start recording the composited frames of the current window to a video file with
+    /// `ffmpeg`, like for demos and bug reports. A recording in progress is finished first. See
+    /// [`crate::video`] for the performance impact.
+    pub fn start_recording(&mut self, path: impl AsRef<Path>, options: RecordingOptions) {
+        if let Some(compositor) = &mut self.compositor {
+            compositor.start_recording(path.as_ref(), options);
+        }
     }
-}
 
-impl webrender::api::RenderNotifier for RenderNotifier {
-    fn clone(&self) -> Box<dyn webrender::api::RenderNotifier> {
-        Box::new(RenderNotifier::new(self.compositor_proxy.clone()))
+    /// Finish the recording in progress and return the path of the video file, or `None` if
+    /// there's no recording.
+    pub fn stop_recording(&mut self) -> crate::Result<Option<PathBuf>> {
+        match &mut self.compositor {
+            Some(compositor) => Ok(compositor.stop_recording()?),
+            None => Ok(None),
+        }
     }
 
-    fn wake_up(&self, _composite_needed: bool) {}
+    /// Composite the current window and read the frame back, taken with
+    /// [`Verso::take_captured_frame`].
+    #[cfg_attr(
+        not(any(feature = "visual-test", feature = "remote-control", feature = "cdp")),
+        allow(dead_code)
+    )]
+    pub(crate) fn capture_frame(&mut self) {
+        if let Some(compositor) = &mut self.compositor {
+            compositor.capture_frame();
+        }
+    }
 
-    fn new_frame_ready(
-        &self,
-        _document_id: DocumentId,
-        _scrolled: bool,
-        composite_needed: bool,
-        _frame_publish_id: FramePublishId,
-    ) {
-        self.compositor_proxy
-            .send(CompositorMsg::NewWebRenderFrameReady(composite_needed));
+    /// Take the frame read back after [`Verso::capture_frame`], cropped to the web view of the
+    /// current window, as RGBA pixels with the top row first.
+    #[cfg_attr(
+        not(any(feature = "visual-test", feature = "remote-control", feature = "cdp")),
+        allow(dead_code)
+    )]
+    pub(crate) fn take_captured_frame(&mut self) -> Option<(units::DeviceIntSize, Vec<u8>)> {
+        let (size, pixels) = self.compositor.as_mut()?.take_captured_frame()?;
+        let rect = self
+            .current_window()?
+            .webview
+            .as_ref()?
+            .rect
+            .intersection(&DeviceIntRect::from_size(size))?;
+        let row = size.width as usize * 4;
+        let mut cropped = Vec::with_capacity(rect.area() as usize * 4);
+        // GL rows start at the bottom.
+        for y in rect.min.y..rect.max.y {
+            let start = (size.height - 1 - y) as usize * row + rect.min.x as usize * 4;
+            cropped.extend_from_slice(&pixels[start..start + rect.width() as usize * 4]);
+        }
+        Some((rect.size(), cropped))
     }
-}
 
-struct WebRenderFontApiCompositorProxy(CompositorProxy);
+    /// Take the frame read back after [`Verso::capture_frame`] like
+    /// [`Verso::take_captured_frame`], encoded as PNG.
+    #[cfg(any(feature = "remote-control", feature = "cdp"))]
+    pub(crate) fn take_captured_png(&mut self) -> Option<Result<Vec<u8>, String>> {
+        use image::{ImageFormat, RgbaImage};
+
+        let (size, rgba) = self.take_captured_frame()?;
+        let Some(image) = RgbaImage::from_raw(size.width as u32, size.height as u32, rgba) else {
+            return Some(Err("The captured frame has the wrong size".into()));
+        };
+        let mut png = std::io::Cursor::new(Vec::new());
+        let encoded = image
+            .write_to(&mut png, ImageFormat::Png)
+            .map(|()| png.into_inner())
+            .map_err(|e| format!("Failed to encode the screenshot: {e}"));
+        Some(encoded)
+    }
+
+    /// Render the current window at the scale factor instead of the one of its monitor, until
+    /// the window moves to another monitor.
+    #[cfg_attr(not(feature = "visual-test"), allow(dead_code))]
+    pub(crate) fn override_scale_factor(&mut self, scale_factor: f32) {
+        let Some(compositor) = &mut self.compositor else {
+            return;
+        };
+        if let Some(window) = self.windows.get(&compositor.current_window) {
+            compositor.on_scale_factor_event(scale_factor, window);
+        }
+    }
+
+    /// Replay input events recorded by [`Config::record_events`]. Events are fed to the current
+    /// window at their original timing divided by `speed`, which must be between
+    /// [`crate::recorder::MIN_SPEED`] and [`crate::recorder::MAX_SPEED`]. Nothing is recorded
+    /// while replaying. Replays can run in hidden windows with
+    /// [`crate::config::WindowConfig::headless`]. See [`crate::recorder`].
+    ///
+    /// Call this right after creating Verso to compare its embedder messages with the recording,
+    /// which started with Verso too.
+    pub fn replay(&mut self, path: impl AsRef<Path>, speed: f32) -> crate::Result<()> {
+        self.replayer = Some(EventReplayer::new(path.as_ref(), speed)?);
+        Ok(())
+    }
+
+    /// Stop replaying, and return the embedder messages of the recording and of the replay to
+    /// compare them. Return `None` if there's no replay.
+    pub fn finish_replay(&mut self) -> Option<ReplayReport> {
+        self.replayer.take().map(EventReplayer::into_report)
+    }
+
+    /// Return true if a replay is in progress and has inputs left to feed.
+    pub fn is_replaying(&self) -> bool {
+        self.replayer
+            .as_ref()
+            .map_or(false, |replayer| !replayer.is_finished())
+    }
+
+    /// Get the ID of the current window, which receives replayed inputs.
+    pub fn current_window_id(&self) -> Option<WindowId> {
+        self.current_window().map(Window::id)
+    }
+
+    fn current_window(&self) -> Option<&Window> {
+        let compositor = self.compositor.as_ref()?;
+        self.windows.get(&compositor.current_window)
+    }
+
+    /// Get the ID of the web view in the current window.
+    pub fn current_webview_id(&self) -> Option<WebViewId> {
+        self.current_window()?
+            .webview
+            .as_ref()
+            .map(|w| w.webview_id)
+    }
+
+    /// Get the URL of the page of the current window formatted for display, like in an address
+    /// bar, in the mode set with [`Verso::set_url_display_mode`]. Use
+    /// [`Verso::current_raw_url`] to navigate. See [`crate::url_display`].
+    pub fn current_url(&self) -> Option<DisplayUrl> {
+        let url = self.current_window()?.url.as_ref()?;
+        Some(DisplayUrl::new(url, self.config.url_display))
+    }
+
+    /// Get the URL of the page of the current window as loaded.
+    pub fn current_raw_url(&self) -> Option<ServoUrl> {
+        self.current_window()?.url.clone()
+    }
+
+    /// Set what [`Verso::current_url`] shows of the URL of the page. It doesn't change the URL
+    /// pages are loaded with. See [`UrlDisplayMode`].
+    pub fn set_url_display_mode(&mut self, mode: UrlDisplayMode) {
+        self.config.url_display = mode;
+    }
+
+    /// Return true if the web view of the current window can navigate back. This follows the
+    /// session history reported by Servo, so it can be used to enable toolbar buttons.
+    pub fn can_go_back(&self) -> bool {
+        self.current_window().map_or(false, Window::can_go_back)
+    }
+
+    /// Return true if the web view of the current window can navigate forward.
+    pub fn can_go_forward(&self) -> bool {
+        self.current_window().map_or(false, Window::can_go_forward)
+    }
+
+    /// Navigate the web view of the current window back in its session history.
+    pub fn go_back(&self) {
+        self.traverse_history(TraversalDirection::Back(1));
+    }
+
+    /// Navigate the web view of the current window forward in its session history.
+    pub fn go_forward(&self) {
+        self.traverse_history(TraversalDirection::Forward(1));
+    }
+
+    fn traverse_history(&self, direction: TraversalDirection) {
+        if let Some(window) = self.current_window() {
+            window.set_load_status(LoadStatus::NavigationStarted);
+            window.retries.set(0);
+            window.restores.set(0);
+        }
+        match self.current_webview_id() {
+            Some(id) => send_to_constellation(
+                &self.constellation_sender,
+                ConstellationMsg::TraverseHistory(id, direction),
+            ),
+            None => log::warn!("Verso has no web view to traverse history {direction:?}"),
+        }
+    }
+
+    /// Get the load status of the web view in the current window.
+    pub fn load_status(&self) -> LoadStatus {
+        self.current_window()
+            .map_or(LoadStatus::Idle, Window::load_status)
+    }
+
+    /// Navigate the web view of the current window to the provided URL. URLs of schemes handled
+    /// by other applications, like `mailto:`, are opened with them. See [`crate::external`].
+    pub fn load_url(&mut self, url: ServoUrl) {
+        if is_external_url(&url) {
+            return self.open_external_url(&url);
+        }
+        if !self.config.policies.allows_url(&url) {
+            log::warn!("Verso can't load {url}: denied by policy");
+            return;
+        }
+        let same_url = self
+            .current_window()
+            .and_then(|window| window.url.as_ref())
+            .map_or(false, |current| is_same_url(current, &url));
+        if let (true, Some(id)) = (same_url, self.current_webview_id()) {
+            match self.config.same_url {
+                SameUrlPolicy::Reload => {
+                    if let Some(window) = self.current_window() {
+                        window.set_load_status(LoadStatus::Reloading);
+                    }
+                    send_to_constellation(&self.constellation_sender, ConstellationMsg::Reload(id));
+                }
+                SameUrlPolicy::Ignore => log::debug!("Verso ignores loading {url} again"),
+            }
+            return;
+        }
+        if let Some(window) = self.current_window() {
+            window.set_load_status(LoadStatus::NavigationStarted);
+            window.retries.set(0);
+            window.restores.set(0);
+        }
+        match self.current_webview_id() {
+            Some(id) => send_to_constellation(
+                &self.constellation_sender,
+                ConstellationMsg::LoadUrl(id, url),
+            ),
+            None => log::warn!("Verso has no web view to load {url}"),
+        }
+    }
+
+    /// Navigate the web view of the current window to the URL with a POST request of the form
+    /// entries, like to restore a page which was the result of a form submission. Ask the user
+    /// before submitting a form again, since it can repeat its action. See [`crate::form_data`].
+    pub fn submit_form_data(&mut self, url: ServoUrl, form: FormData) {
+        let Some(webview_id) = self.current_webview_id() else {
+            return log::warn!("Verso has no web view to submit a form to {url}");
+        };
+        if let Some(script) = crate::form_data::submit_script(&url, &form) {
+            run_script(
+                &self.constellation_sender,
+                webview_id,
+                script,
+                "submit form data",
+            );
+        }
+    }
+
+    /// Get the title and favicon of the page of the window. See [`crate::tabs`].
+    pub fn tab_info(&self, window_id: WindowId) -> Option<TabInfo> {
+        self.windows.get(&window_id).map(Window::tab_info)
+    }
+
+    /// Quit Verso. Dialogs waiting for an answer are dismissed first, and requests pages make
+    /// while Servo shuts down are denied, so no page waits on a closed channel. The event loop
+    /// exits once Servo has shut down.
+    pub fn shutdown(&mut self) {
+        let Some(compositor) = &mut self.compositor else {
+            return;
+        };
+        if compositor.shutdown_state != ShutdownState::NotShuttingDown {
+            return;
+        }
+        for window in self.windows.values_mut() {
+            match window.dialog.take() {
+                // Unloading doesn't block quitting.
+                Some(dialog @ HeldDialog::BeforeUnload(_)) => {
+                    respond_to_dialog(dialog, DialogResponse::Accept(None))
+                }
+                Some(dialog) => respond_to_dialog(dialog, DialogResponse::Dismiss),
+                None => {}
+            }
+        }
+        compositor.maybe_start_shutting_down();
+    }
+
+    /// Get the dialog waiting for an answer in the current window, a JavaScript dialog or a
+    /// `beforeunload` confirmation. Dialogs are only kept open when [`Config::hold_dialogs`] is
+    /// enabled.
+    pub fn dialog_info(&self) -> Option<DialogInfo> {
+        self.current_window()?.dialog.as_ref().map(dialog_info)
+    }
+
+    /// Answer the dialog of the current window. Nothing happens if there's no dialog.
+    pub fn respond_to_dialog(&mut self, response: DialogResponse) {
+        let Some(compositor) = self.compositor.as_ref() else {
+            return;
+        };
+        let dialog = self
+            .windows
+            .get_mut(&compositor.current_window)
+            .and_then(|window| window.dialog.take());
+        match dialog {
+            Some(dialog) => respond_to_dialog(dialog, response),
+            None => log::debug!("Verso has no dialog to respond with {response:?}"),
+        }
+    }
+
+    /// Re-decode the document of the current window with the encoding, for legacy pages which
+    /// don't declare their charset. `None` clears the override and reloads the page with the
+    /// encoding sniffed normally. See [`crate::encoding`].
+    pub fn override_encoding(&self, encoding: Option<&'static Encoding>) {
+        let (Some(window), Some(id)) = (self.current_window(), self.current_webview_id()) else {
+            return log::warn!("Verso has no web view to override encoding");
+        };
+        let old = window.encoding_override();
+        if old == encoding {
+            return;
+        }
+        match encoding {
+            Some(encoding) => {
+                log::debug!(
+                    "Verso overrides encoding of {id:?} with {}",
+                    encoding.name()
+                );
+                let script = override_script(encoding);
+                if let Some(receiver) = execute_script(
+                    &self.constellation_sender,
+                    BrowsingContextId::from(id),
+                    script,
+                ) {
+                    std::thread::spawn(move || {
+                        if let Ok(Err(e)) = receiver.recv() {
+                            log::warn!("Verso failed to override encoding: {e:?}");
+                        }
+                    });
+                }
+                window.set_encoding_override(Some(encoding));
+            }
+            None => {
+                window.set_load_status(LoadStatus::Reloading);
+                send_to_constellation(&self.constellation_sender, ConstellationMsg::Reload(id));
+            }
+        }
+    }
+
+    /// Get the encoding override of the document in the current window. It's `None` unless
+    /// [`Verso::override_encoding`] was called since the page loaded.
+    pub fn encoding_override(&self) -> Option<&'static Encoding> {
+        self.current_window().and_then(Window::encoding_override)
+    }
+
+    /// List the browsing contexts of the web view in the current window, the main frame first
+    /// and then its iframes in tree order, and pass them to the callback. Each frame is asked for
+    /// its URL and child frames without blocking, and the callback runs on Servo's IPC router
+    /// thread once every frame answered. Frames whose script thread goes away are left out, and
+    /// a frame which never answers holds the callback back.
+    pub fn frames(&self, callback: impl FnOnce(Vec<FrameInfo>) + Send + 'static) {
+        let Some(webview_id) = self.current_webview_id() else {
+            callback(vec![]);
+            return;
+        };
+        let query = Arc::new(FrameQuery {
+            sender: self.constellation_sender.clone(),
+            frames: Mutex::new(vec![]),
+            callback: Mutex::new(Some(Box::new(callback))),
+        });
+        query.visit(BrowsingContextId::from(webview_id), None, vec![]);
+    }
+
+    /// Find every match of the text in the web view of the current window, ignoring case, and
+    /// return their bounding boxes in device pixels of the window. Text split across lines has
+    /// a box per line. The boxes are for the current scroll position and size, so query again
+    /// after the page scrolls, resizes or zooms. This blocks until the page answers.
+    pub fn find_all(&mut self, query: &str) -> Vec<DeviceIntRect> {
+        let (Some(window), Some(compositor)) = (self.current_window(), self.compositor.as_ref())
+        else {
+            return vec![];
+        };
+        let Some(webview) = window.webview.as_ref() else {
+            return vec![];
+        };
+        if query.is_empty() {
+            return vec![];
+        }
+        let Ok(query) = serde_json::to_string(query) else {
+            return vec![];
+        };
+        let script = format!(
+            "const query = {query}.toLowerCase();
+            const rects = [];
+            const walker = document.createTreeWalker(document.body || document, NodeFilter.SHOW_TEXT);
+            while (walker.nextNode()) {{
+                const node = walker.currentNode;
+                const text = node.data.toLowerCase();
+                for (let i = text.indexOf(query); i >= 0; i = text.indexOf(query, i + query.length)) {{
+                    const range = document.createRange();
+                    range.setStart(node, i);
+                    range.setEnd(node, i + query.length);
+                    for (const r of range.getClientRects()) {{
+                        if (r.width && r.height) rects.push([r.x, r.y, r.width, r.height]);
+                    }}
+                }}
+            }}
+            return JSON.stringify(rects);"
+        );
+        let rects = match self
+            .frame_script_command(BrowsingContextId::from(webview.webview_id), |sender| {
+                WebDriverScriptCommand::ExecuteScript(script, sender)
+            }) {
+            Some(Ok(WebDriverJSValue::String(rects))) => rects,
+            result => {
+                log::warn!("Verso failed to find {query}: {result:?}");
+                return vec![];
+            }
+        };
+        let rects: Vec<[f32; 4]> = serde_json::from_str(&rects).unwrap_or_default();
+        rects
+            .into_iter()
+            .map(|[x, y, width, height]| {
+                compositor.page_rect_to_window(euclid::rect(x, y, width, height), webview.rect)
+            })
+            .collect()
+    }
+
+    /// Get the full scrollable size of the document in the current window, like to size scrollbar
+    /// thumbs. It's updated with each display list of the document, so it follows the page as
+    /// lazily loaded content makes it grow. This doesn't wait for layout, and returns the last
+    /// known size, or zero before the first display list.
+    pub fn content_size(&mut self) -> Size2D<f32, LayoutPixel> {
+        let (Some(compositor), Some(webview_id)) = (&self.compositor, self.current_webview_id())
+        else {
+            return Size2D::zero();
+        };
+        compositor.content_size(webview_id).unwrap_or_default()
+    }
+
+    /// Get the timing metrics of the page in the web view of the current window, and pass them to
+    /// the callback. Metrics which aren't available yet, like during the load, are `None`. They
+    /// start over when the web view navigates to another document. This doesn't block: the
+    /// callback runs on Servo's IPC router thread once the page answers.
+    pub fn performance_timing(&self, callback: impl FnOnce(PerformanceTiming) + Send + 'static) {
+        let Some(webview_id) = self.current_webview_id() else {
+            callback(PerformanceTiming::default());
+            return;
+        };
+        route_script_command(
+            &self.constellation_sender,
+            BrowsingContextId::from(webview_id),
+            |sender| {
+                WebDriverScriptCommand::ExecuteScript(GET_PERFORMANCE_TIMING.to_string(), sender)
+            },
+            move |result| {
+                let timing = match result {
+                    Ok(WebDriverJSValue::String(json)) => {
+                        PerformanceTiming::from_json(&json).unwrap_or_default()
+                    }
+                    result => {
+                        log::warn!("Verso failed to get performance timing: {result:?}");
+                        PerformanceTiming::default()
+                    }
+                };
+                callback(timing);
+            },
+        );
+    }
+
+    /// Evaluate a media query, like `(prefers-color-scheme: dark)` or `(max-width: 600px)`, with
+    /// `matchMedia()` in the page of the current window, so responsive pages can be checked
+    /// against the viewport and the environment Verso gives them. Invalid queries are false with
+    /// a warning. This blocks until the page answers.
+    pub fn matches_media(&mut self, query: &str) -> bool {
+        let Some(webview_id) = self.current_webview_id() else {
+            return false;
+        };
+        let Ok(json) = serde_json::to_string(query) else {
+            return false;
+        };
+        // Queries which fail to parse are serialized as `not all`.
+        let script = format!(
+            "const query = {json};
+            const list = matchMedia(query);
+            return list.media === 'not all' && query.trim().toLowerCase() !== 'not all'
+                ? null
+                : list.matches;"
+        );
+        match self.frame_script_command(BrowsingContextId::from(webview_id), |sender| {
+            WebDriverScriptCommand::ExecuteScript(script, sender)
+        }) {
+            Some(Ok(WebDriverJSValue::Boolean(matches))) => matches,
+            Some(Ok(WebDriverJSValue::Null)) => {
+                log::warn!("Verso can't evaluate invalid media query `{query}`");
+                false
+            }
+            result => {
+                log::warn!("Verso failed to evaluate media query `{query}`: {result:?}");
+                false
+            }
+        }
+    }
+
+    /// Send a WebDriver script command to the browsing context and wait for its reply.
+    fn frame_script_command<T>(
+        &self,
+        browsing_context_id: BrowsingContextId,
+        command: impl FnOnce(IpcSender<T>) -> WebDriverScriptCommand,
+    ) -> Option<T>
+    where
+        T: serde::Serialize + for<'de> serde::Deserialize<'de>,
+    {
+        let (sender, receiver) = ipc::channel().ok()?;
+        send_to_constellation(
+            &self.constellation_sender,
+            ConstellationMsg::WebDriverCommand(WebDriverCommandMsg::ScriptCommand(
+                browsing_context_id,
+                command(sender),
+            )),
+        );
+        receiver
+            .try_recv_timeout(std::time::Duration::from_secs(1))
+            .map_err(|e| {
+                log::debug!("Verso didn't get a reply from frame {browsing_context_id}: {e:?}")
+            })
+            .ok()
+    }
+
+    /// Evaluate JavaScript in the web view of the current window. The script is evaluated as
+    /// the body of a function, so use `return` to provide the result. The result will be sent
+    /// to the returned receiver once the script finishes. Return `None` if there's no web view.
+    pub fn evaluate_script(
+        &self,
+        script: impl Into<String>,
+    ) -> Option<IpcReceiver<WebDriverJSResult>> {
+        let webview_id = self.current_webview_id()?;
+        self.evaluate_script_in_frame(BrowsingContextId::from(webview_id), script)
+    }
+
+    /// Evaluate JavaScript in the provided browsing context. See [`Verso::evaluate_script`].
+    pub fn evaluate_script_in_frame(
+        &self,
+        browsing_context_id: BrowsingContextId,
+        script: impl Into<String>,
+    ) -> Option<IpcReceiver<WebDriverJSResult>> {
+        execute_script(&self.constellation_sender, browsing_context_id, script)
+    }
+
+    /// Get the monitors available to Verso windows.
+    pub fn available_monitors(&self) -> Vec<MonitorInfo> {
+        self.windows
+            .values()
+            .next()
+            .map(Window::available_monitors)
+            .unwrap_or_default()
+    }
+
+    /// Move the current window to the selected monitor, and place new windows there too. A
+    /// fullscreen window stays fullscreen on the new monitor.
+    pub fn move_to_monitor(&mut self, selector: MonitorSelector) {
+        match self.current_window() {
+            Some(window) => window.move_to_monitor(&selector),
+            None => log::warn!("Verso has no window to move to monitor {selector:?}"),
+        }
+        self.config.window.monitor = Some(selector);
+    }
+
+    /// Keep the current window and new windows on top of other windows or not. It returns
+    /// [`crate::Error::Unsupported`] on platforms which don't allow changing the window level.
+    pub fn set_always_on_top(&mut self, always_on_top: bool) -> crate::Result<()> {
+        self.config.window = self.config.window.clone().always_on_top(always_on_top);
+        match self.current_window() {
+            Some(window) => window.set_always_on_top(always_on_top),
+            None => Ok(()),
+        }
+    }
+
+    /// Hide the current window and new windows from the taskbar or not. It returns
+    /// [`crate::Error::Unsupported`] on platforms which can't change it at runtime.
+    pub fn set_skip_taskbar(&mut self, skip_taskbar: bool) -> crate::Result<()> {
+        self.config.window.skip_taskbar = skip_taskbar;
+        match self.current_window() {
+            Some(window) => window.set_skip_taskbar(skip_taskbar),
+            None => Ok(()),
+        }
+    }
+
+    /// Render the current window at a fixed size, like for screenshots which must not depend on
+    /// the display, or at the window size if it's `None`. The frame is scaled to fit the window
+    /// keeping its aspect ratio with bilinear filtering, and centered with black bars.
+    pub fn set_render_resolution(&mut self, size: Option<Size2D<u32, DevicePixel>>) {
+        let Some(compositor) = &mut self.compositor else {
+            return;
+        };
+        let Some(window) = self.windows.get_mut(&compositor.current_window) else {
+            return;
+        };
+        let size = size.map(|size| size.max(Size2D::new(1, 1)).to_i32());
+        compositor.set_render_resolution(size, window);
+        window.request_redraw();
+    }
+
+    /// Set how mouse wheel and touchpad scrolling is locked to a single axis.
+    pub fn set_wheel_axis_lock(&mut self, mode: AxisLockMode) {
+        self.config.wheel_axis_lock = mode;
+    }
+
+    /// Draw a loading progress bar with the style over web views, or stop drawing it with `None`.
+    /// It's disabled by default. See [`crate::progress`].
+    pub fn set_builtin_progress_bar(&mut self, style: Option<ProgressBarStyle>) {
+        self.config.progress_bar = style;
+        for window in self.windows.values() {
+            window.progress_bar.set(ProgressBar::default());
+        }
+        let Some(compositor) = self.compositor.as_mut() else {
+            return;
+        };
+        if let Some(window) = self.windows.get(&compositor.current_window) {
+            compositor.set_progress_bar_style(style, window);
+        }
+    }
+
+    /// Set the width, colors and mode of the scrollbars drawn over web views, applied on the next
+    /// composite. See [`crate::scrollbars`].
+    pub fn set_scrollbar_style(&mut self, style: ScrollbarStyle) {
+        self.config.scrollbars = style;
+        let Some(compositor) = self.compositor.as_mut() else {
+            return;
+        };
+        if let Some(window) = self.windows.get(&compositor.current_window) {
+            compositor.set_scrollbar_style(style, window);
+        }
+    }
+
+    /// Show or hide a heads-up display of the frame rate, URL, load status, memory, event queue
+    /// and input latency of the current window. It's meant for development and costs a frame per
+    /// second while it's on. See [`crate::debug_overlay`].
+    pub fn set_debug_overlay(&mut self, on: bool) {
+        if let Some(compositor) = self.compositor.as_mut() {
+            compositor.set_debug_overlay(on);
+        }
+    }
+
+    /// Get the GL implementation rendering pages, and whether pages run in sandboxed content
+    /// processes. See [`crate::sandbox`].
+    pub fn renderer_info(&self) -> Option<RendererInfo> {
+        self.compositor.as_ref().map(IOCompositor::renderer_info)
+    }
+
+    /// Get the page zoom factor of the current window, changed with the zoom shortcuts. It scales
+    /// CSS pixels, so the page is laid out again for a smaller or larger viewport.
+    pub fn page_zoom(&self) -> f32 {
+        self.compositor
+            .as_ref()
+            .map_or(1.0, IOCompositor::page_zoom)
+    }
+
+    /// Zoom the page of the current window so its whole content is visible, and keep it fitting
+    /// when the window is resized until the zoom is changed otherwise. See [`crate::zoom`].
+    pub fn zoom_to_fit(&mut self) {
+        self.zoom_fit(FitMode::Page);
+    }
+
+    /// Zoom the page of the current window so the width of its content is visible, and keep it
+    /// fitting when the window is resized until the zoom is changed otherwise. See
+    /// [`crate::zoom`].
+    pub fn zoom_fit_width(&mut self) {
+        self.zoom_fit(FitMode::Width);
+    }
+
+    fn zoom_fit(&mut self, mode: FitMode) {
+        if let Some(compositor) = self.compositor.as_mut() {
+            if let Some(window) = self.windows.get_mut(&compositor.current_window) {
+                compositor.on_zoom_fit_window_event(mode, window);
+            }
+        }
+    }
+
+    /// Get the fit mode which set the page zoom, or `None` if it was set by hand.
+    pub fn fit_mode(&self) -> Option<FitMode> {
+        self.compositor.as_ref().and_then(IOCompositor::fit_mode)
+    }
+
+    /// Get the pinch zoom factor of the current window, changed with the smart magnify gesture.
+    /// It magnifies the rendered page without laying it out again. It applies after the page
+    /// zoom, so a CSS pixel covers `scale factor × page zoom × pinch zoom` device pixels.
+    pub fn pinch_zoom(&self) -> f32 {
+        self.compositor
+            .as_ref()
+            .map_or(1.0, IOCompositor::pinch_zoom)
+    }
+
+    /// Reset the pinch zoom of the current window to 1, keeping the page zoom.
+    /// [`ShortcutAction::ResetZoom`] resets both.
+    pub fn reset_pinch_zoom(&mut self) {
+        if let Some(compositor) = self.compositor.as_mut() {
+            compositor.reset_pinch_zoom();
+            if let Some(window) = self.windows.get(&compositor.current_window) {
+                window.request_redraw();
+            }
+        }
+    }
+
+    /// Set whether scrolling snaps to whole device pixels. See [`Config::snap_scroll`].
+    pub fn set_snap_scroll(&mut self, enabled: bool) {
+        self.config.snap_scroll = enabled;
+    }
+
+    /// Set the features denied to pages of the origin, like `https://example.com`, or of every
+    /// origin with [`crate::features::ALL_ORIGINS`]. It applies to pages loaded afterwards. See
+    /// [`crate::features`] for the features which can be denied.
+    pub fn set_feature_policy(&mut self, origin: &str, policy: FeaturePolicy) {
+        match normalize_origin(origin) {
+            Ok(origin) => {
+                self.config.feature_policies.insert(origin, policy);
+            }
+            Err(e) => log::error!("Verso can't set feature policy of {origin}: {e}"),
+        }
+    }
+
+    /// Set the default fonts of pages. It applies to pages loaded afterwards.
+    pub fn set_font_config(&mut self, fonts: FontConfig) {
+        fonts.apply(self.config.text_scale());
+        self.config.fonts = fonts;
+    }
+
+    /// Set whether the default font sizes follow the text scale of the operating system. It
+    /// applies to pages loaded afterwards. See [`crate::text_scale`].
+    pub fn set_follow_os_text_scale(&mut self, enabled: bool) {
+        self.config.follow_os_text_scale = enabled;
+        self.config.fonts.apply(self.config.text_scale());
+    }
+
+    /// List the cookies of the sites visited in this session. See [`crate::cookies`].
+    pub fn list_all_cookies(&mut self) -> Vec<Cookie> {
+        crate::cookies::list(&self.resource_threads, &self.cookie_urls)
+    }
+
+    /// Remove the cookie of the name set for the domain, on any path. A leading dot of the domain
+    /// is ignored.
+    pub fn remove_cookie(&mut self, domain: &str, name: &str) {
+        let removed =
+            crate::cookies::remove(&self.resource_threads, &self.cookie_urls, domain, name);
+        log::debug!("Verso removed {removed} cookies {name} of {domain}");
+    }
+
+    /// Gather the storage used by each origin visited in this session on another thread, and call
+    /// the callback with it. See [`crate::storage`].
+    pub fn storage_usage(&self, callback: impl FnOnce(Vec<OriginUsage>) + Send + 'static) {
+        let resource_threads = self.resource_threads.clone();
+        let urls = self.cookie_urls.clone();
+        std::thread::spawn(move || callback(crate::storage::usage(&resource_threads, &urls)));
+    }
+
+    /// Remove the data of the kinds stored by the origin of the URL, like its cookies and local
+    /// storage.
+    pub fn clear_origin_data(&mut self, origin: &ServoUrl, kinds: &[StorageKind]) {
+        crate::storage::clear(&self.resource_threads, &self.cookie_urls, origin, kinds);
+    }
+
+    /// Discard the web view of the least recently focused background window, when the system runs
+    /// low on memory, like on `onTrimMemory` of Android. See [`crate::discard`].
+    pub fn on_memory_pressure(&mut self) {
+        if self.discard_webviews(1) == 0 {
+            log::debug!("Verso has no web view to discard under memory pressure");
+        }
+    }
+
+    /// Keep the web view of the window alive when web views are discarded, or allow discarding
+    /// it again.
+    pub fn set_pinned(&mut self, window_id: WindowId, pinned: bool) {
+        if let Some(window) = self.windows.get_mut(&window_id) {
+            window.pinned = pinned;
+        }
+    }
+
+    /// Return true if the web view of the window is discarded. It's restored when the window is
+    /// focused.
+    pub fn is_discarded(&self, window_id: WindowId) -> bool {
+        self.windows
+            .get(&window_id)
+            .map_or(false, |w| w.discarded.is_some())
+    }
+
+    /// Set when web views of background windows are discarded. See [`DiscardPolicy`].
+    pub fn set_discard_policy(&mut self, policy: DiscardPolicy) {
+        self.config.discard = policy;
+    }
+
+    /// Capture the URL, session history, scroll position, page zoom and form fields of the web
+    /// view, and close it to free its memory. Its window stays open until the snapshot is resumed
+    /// with [`Verso::resume_webview`]. Returns `None` if the web view doesn't exist or has no
+    /// page. See [`crate::suspend`].
+    pub fn suspend_webview(&mut self, id: WebViewId) -> Option<WebViewSnapshot> {
+        let (url, (history, history_index)) = self
+            .windows
+            .values()
+            .find(|w| w.webview.as_ref().is_some_and(|w| w.webview_id == id))
+            .and_then(|w| Some((w.url.clone()?, w.history.clone())))?;
+        let page = match self.frame_script_command(BrowsingContextId::from(id), |sender| {
+            WebDriverScriptCommand::ExecuteScript(GET_PAGE_STATE.to_string(), sender)
+        }) {
+            Some(Ok(WebDriverJSValue::String(state))) => serde_json::from_str(&state)
+                .map_err(|e| log::warn!("Verso failed to parse page state: {e}"))
+                .ok(),
+            result => {
+                log::warn!("Verso failed to capture page state of {id}: {result:?}");
+                None
+            }
+        };
+        let page_zoom = self.page_zoom();
+        let window = self
+            .windows
+            .values_mut()
+            .find(|w| w.webview.as_ref().is_some_and(|w| w.webview_id == id))?;
+        window.suspend(&self.constellation_sender);
+        Some(WebViewSnapshot {
+            url,
+            history,
+            history_index,
+            page_zoom,
+            page,
+        })
+    }
+
+    /// Open a web view with the page of the snapshot in the current window, replacing its web
+    /// view, and restore its page zoom, scroll position and form fields. Progress is reported to
+    /// [`crate::VersoBuilder::on_resume`]. Returns the new web view, or `None` if there's no
+    /// window. See [`crate::suspend`].
+    pub fn resume_webview(&mut self, snapshot: &WebViewSnapshot) -> Option<WebViewId> {
+        let compositor = self.compositor.as_mut()?;
+        let window = self.windows.get_mut(&compositor.current_window)?;
+        let id = window.resume(snapshot, &self.constellation_sender);
+        let zoom = compositor.page_zoom();
+        if snapshot.page_zoom.is_finite() && snapshot.page_zoom > 0.0 && snapshot.page_zoom != zoom
+        {
+            compositor.on_zoom_window_event(snapshot.page_zoom / zoom, window);
+        }
+        Some(id)
+    }
+
+    /// Resize the current window to fit the content of its page, up to the maximum inner size in
+    /// logical pixels, or stop with `None`. The window follows the content size reported to
+    /// [`crate::VersoBuilder::on_content_size_changed`] until the mode is stopped, or until it's
+    /// disabled because the content follows the size of the window. See
+    /// [`crate::auto_resize`].
+    pub fn auto_resize_window(&mut self, max_size: Option<LogicalSize<u32>>) {
+        let Some(window_id) = self.compositor.as_ref().map(|c| c.current_window) else {
+            return;
+        };
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+        window.auto_resize = max_size.map(AutoResize::new);
+        let Some(webview_id) = window.webview.as_ref().map(|w| w.webview_id) else {
+            return;
+        };
+        if max_size.is_none() {
+            return;
+        }
+        window.report_content_size = true;
+        match window.content_size {
+            Some(size) => self.fit_window_to_content(window_id, size),
+            // Pages loaded before only report their size once the script runs.
+            None => run_script(
+                &self.constellation_sender,
+                webview_id,
+                CONTENT_SIZE_SCRIPT.to_string(),
+                "report the content size",
+            ),
+        }
+    }
+
+    /// Pause the playing `<audio>` and `<video>` elements of the web view and its same-origin
+    /// frames, like when switching away from a tab. Unlike muting, playback stops where it is.
+    /// Media started again by the page stay playing. Does nothing if the web view doesn't exist.
+    pub fn pause_all_media(&mut self, id: WebViewId) {
+        self.run_in_webview(id, "pause all media", PAUSE_ALL_MEDIA);
+    }
+
+    /// Resume the media paused by [`Verso::pause_all_media`] in the web view. Media paused by
+    /// the page or the user stay paused.
+    pub fn resume_all_media(&mut self, id: WebViewId) {
+        self.run_in_webview(id, "resume all media", RESUME_ALL_MEDIA);
+    }
+
+    /// Run the script in the web view if it exists, logging failures.
+    fn run_in_webview(&self, id: WebViewId, name: &'static str, script: &str) {
+        let exists = self
+            .windows
+            .values()
+            .any(|w| w.webview.as_ref().is_some_and(|w| w.webview_id == id));
+        if exists {
+            run_script(&self.constellation_sender, id, script.to_string(), name);
+        } else {
+            log::debug!("Verso can't {name} of WebView {id:?} which doesn't exist");
+        }
+    }
+
+    /// Override the forced colors mode of pages, or follow the high-contrast setting of the
+    /// operating system with [`ForcedColors::Auto`]. It applies to open pages too. See
+    /// [`crate::contrast`].
+    pub fn set_forced_colors(&mut self, mode: ForcedColors) {
+        self.config.forced_colors = mode;
+        self.apply_forced_colors();
+    }
+
+    fn apply_forced_colors(&self) {
+        let script = forced_colors_script(self.config.forced_colors.is_active());
+        self.run_in_webviews("apply forced colors", &script);
+    }
+
+    /// Inject the script into pages matching its URL patterns, from the next navigation on. See
+    /// [`crate::content_scripts`].
+    pub fn add_content_script(&mut self, script: ContentScript) {
+        self.config.content_scripts.push(script);
+    }
+
+    /// Throttle `setTimeout` and `setInterval` of pages in hidden windows, so they run at most
+    /// once per `factor` seconds, or run them at full rate with 0. The default clamp is 1 second,
+    /// like in other browsers. See [`crate::timers`].
+    pub fn set_background_timer_throttle(&mut self, factor: u32) {
+        self.config.background_timer_throttle = factor;
+        for window in self.windows.values() {
+            window.apply_timer_throttle(&self.constellation_sender, factor);
+        }
+    }
+
+    /// Apply title and favicon changes of pages at most once per interval, with the latest value
+    /// applied once it elapsed, or apply every change with zero. The default is 100 ms. See
+    /// [`crate::dispatch::UpdateThrottle`].
+    pub fn set_title_update_interval(&mut self, interval: Duration) {
+        self.config.title_update_interval = interval;
+    }
+
+    /// Set the parts of web views covered by the embedder, like a toolbar or an on-screen
+    /// keyboard, in CSS pixels. The page scrolls to keep the caret out of them while typing. See
+    /// [`crate::caret`].
+    pub fn set_content_insets(&mut self, insets: ContentInsets) {
+        self.config.content_insets = insets;
+    }
+
+    /// Enable or disable storing submitted form values and offering them in form fields. It takes
+    /// effect on the next load. See [`crate::autofill`].
+    pub fn set_autofill(&mut self, enabled: bool) {
+        self.config.autofill = enabled;
+    }
+
+    /// Remove all stored autofill values.
+    pub fn clear_autofill(&mut self) {
+        self.autofill.clear();
+    }
+
+    /// Set whether the username and password are included in submitted credentials reported to
+    /// [`crate::VersoBuilder::on_credentials_submitted`]. It takes effect on the next load.
+    pub fn set_share_credentials(&mut self, enabled: bool) {
+        self.config.share_credentials = enabled;
+    }
+
+    /// Fill the last login form reported to [`crate::VersoBuilder::on_login_form`] in the current
+    /// window with the credentials, if the page still has the origin, like the
+    /// [`LoginForm::origin`](crate::passwords::LoginForm::origin) of the form. The fields receive
+    /// input events as if they were typed. See [`crate::passwords`].
+    pub fn fill_credentials(&self, origin: &str, username: &str, password: &str) {
+        let (Some(webview_id), Some(script)) = (
+            self.current_webview_id(),
+            crate::passwords::fill_script(origin, username, password),
+        ) else {
+            return;
+        };
+        if let Some(receiver) = execute_script(
+            &self.constellation_sender,
+            BrowsingContextId::from(webview_id),
+            script,
+        ) {
+            std::thread::spawn(move || {
+                if let Ok(Err(e)) = receiver.recv() {
+                    log::warn!("Verso failed to fill credentials: {e:?}");
+                }
+            });
+        }
+    }
+
+    /// Get the storage partition of the web views, or `None` for the default partition. See
+    /// [`crate::partitions`].
+    pub fn partition(&self) -> Option<&str> {
+        self.config.partition.as_deref()
+    }
+
+    /// Create the storage partition if it doesn't exist and return its directory, like to
+    /// prepare it for another Verso instance.
+    pub fn create_partition(&self, name: &str) -> Result<PathBuf, PartitionError> {
+        crate::partitions::create(self.config.opts.config_dir.as_deref(), name)
+    }
+
+    /// Delete the storage partition and its cookies, local storage and cache. It fails with
+    /// [`PartitionError::InUse`] if it's the partition of the web views of this instance.
+    /// Partitions used by other processes must not be deleted.
+    pub fn delete_partition(&self, name: &str) -> Result<(), PartitionError> {
+        if self.partition() == Some(name) {
+            return Err(PartitionError::InUse(name.into()));
+        }
+        crate::partitions::delete(self.config.opts.config_dir.as_deref(), name)
+    }
+
+    /// List the storage partitions in the config directory.
+    pub fn list_partitions(&self) -> Vec<String> {
+        crate::partitions::list(self.config.opts.config_dir.as_deref())
+    }
+
+    /// Get the policies in effect and where preferences come from, like for a page listing
+    /// preferences. See [`crate::policies`].
+    pub fn active_policies(&self) -> ActivePolicies {
+        self.config.active_policies()
+    }
+
+    /// Set what [`Verso::load_url`] does with the URL of the page already loaded. See
+    /// [`SameUrlPolicy`].
+    pub fn set_same_url_policy(&mut self, policy: SameUrlPolicy) {
+        self.config.same_url = policy;
+    }
+
+    /// Set which changes of the window state pages can request. See [`WindowControlPolicy`].
+    pub fn set_window_control_policy(&mut self, policy: WindowControlPolicy) {
+        self.config.window_control = policy;
+    }
+
+    /// Set how web views are restored after their content crashes. See [`CrashRecoveryPolicy`].
+    pub fn set_crash_recovery(&mut self, policy: CrashRecoveryPolicy) {
+        self.config.crash_recovery = policy;
+    }
+
+    /// Set how loads failing with a transient network error are retried. See [`RetryPolicy`].
+    pub fn set_navigation_retry(&mut self, policy: RetryPolicy) {
+        self.config.navigation_retry = policy;
+    }
+
+    /// List the family names of fonts installed on the system, for example to offer choices of
+    /// [`FontConfig`] in a settings page.
+    pub fn list_system_fonts() -> Vec<String> {
+        crate::config::list_system_fonts()
+    }
+
+    /// Supply the values of sensors exposed to pages, like the battery status. Sensors are only
+    /// exposed when they're enabled in [`Config`].
+    pub fn set_sensor_provider(&mut self, provider: Box<dyn SensorProvider>) {
+        self.sensor_provider = Some(provider);
+    }
+
+    /// Set when pages can autoplay media with sound.
+    pub fn set_autoplay_policy(&mut self, policy: AutoplayPolicy) {
+        self.config.autoplay = policy;
+    }
+
+    /// Get the statistics of the latency from input events to the present of a frame which can
+    /// show their effect, over the latest samples. It's empty unless [`Config::measure_input_latency`] is enabled.
+    pub fn input_latency_stats(&self) -> LatencyStats {
+        self.compositor
+            .as_ref()
+            .map_or_else(LatencyStats::default, IOCompositor::input_latency_stats)
+    }
+
+    /// Return true if the user has interacted with the current window since its page started
+    /// loading. This is what [`AutoplayPolicy::AllowAfterUserGesture`] checks.
+    pub fn has_user_gesture(&self) -> bool {
+        self.current_window().map_or(false, |w| w.user_gesture)
+    }
+
+    /// Get the address of Servo's devtools server, which Firefox developer tools can attach to
+    /// through `about:debugging`, or `None` if it isn't enabled or failed to start. It's enabled
+    /// with [`crate::VersoBuilder::enable_devtools`].
+    pub fn devtools_address(&self) -> Option<SocketAddr> {
+        self.devtools_address
+    }
+
+    /// Start a server of the Chrome DevTools Protocol subset documented in [`crate::cdp`] on
+    /// the provided port, and return the WebSocket URL clients connect to, which holds the token
+    /// of the server. Use port `0` to pick a free port. It fails if developer tools are disabled
+    /// by policy. See [`crate::policies`].
+    #[cfg(feature = "cdp")]
+    pub fn enable_remote_debugging(&mut self, port: u16) -> crate::Result<String> {
+        if self.config.policies.disable_devtools {
+            return Err(crate::Error::DisabledByPolicy("remote debugging"));
+        }
+        let server = crate::cdp::CdpServer::start(port, self.event_loop_proxy.clone())?;
+        let url = server.url();
+        self.cdp_server = Some(server);
+        Ok(url)
+    }
+
+    /// Start the HTTP interface documented in [`crate::remote_control`] on the provided port, and
+    /// return the address it's listening on and the token of requests. Use port `0` to pick a
+    /// free port. The token is read from the `VERSO_REMOTE_CONTROL_TOKEN` environment variable, or
+    /// generated. It fails if developer tools are disabled by policy. See [`crate::policies`].
+    #[cfg(feature = "remote-control")]
+    pub fn enable_remote_control(&mut self, port: u16) -> crate::Result<(SocketAddr, String)> {
+        if self.config.policies.disable_devtools {
+            return Err(crate::Error::DisabledByPolicy("remote control"));
+        }
+        let server =
+            crate::remote_control::RemoteControlServer::start(port, self.event_loop_proxy.clone())?;
+        let address = server.address();
+        let token = server.token().to_string();
+        self.remote_control = Some(server);
+        Ok((address, token))
+    }
+
+    /// Serve the directory over HTTP on `127.0.0.1`, and return the URL of its root to load
+    /// with [`Verso::load_url`]. Use `None` to pick a free port. See [`crate::local_server`].
+    #[cfg(feature = "local-server")]
+    pub fn serve_directory(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        port: Option<u16>,
+    ) -> crate::Result<ServoUrl> {
+        self.serve_directory_with(
+            path,
+            crate::local_server::ServeOptions {
+                port,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Serve the directory over HTTP with the options, like the fallback to `index.html` for apps
+    /// which route on the client. See [`Verso::serve_directory`].
+    #[cfg(feature = "local-server")]
+    pub fn serve_directory_with(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        options: crate::local_server::ServeOptions,
+    ) -> crate::Result<ServoUrl> {
+        let server = crate::local_server::LocalServer::start(path.as_ref(), options)?;
+        let url = server.url();
+        self.local_servers.push(server);
+        Ok(url)
+    }
+
+    #[cfg(feature = "cdp")]
+    fn handle_cdp_requests(&mut self) {
+        use crate::cdp::{evaluate_result, evaluate_script, screenshot_result, CdpMethod};
+        use crate::compositor::MouseWindowEvent;
+        use script_traits::MouseButton;
+
+        let Some(server) = &mut self.cdp_server else {
+            return;
+        };
+        if !server.screenshot_replies.is_empty() {
+            let replies = std::mem::take(&mut server.screenshot_replies);
+            match self.take_captured_png() {
+                Some(png) => {
+                    let result = screenshot_result(png);
+                    for reply in replies {
+                        let _ = reply.send(result.clone());
+                    }
+                }
+                None => {
+                    if let Some(server) = &mut self.cdp_server {
+                        server.screenshot_replies = replies;
+                    }
+                }
+            }
+        }
+
+        while let Some(request) = self.cdp_server.as_ref().and_then(|s| s.try_recv()) {
+            let result = match request.method {
+                CdpMethod::Navigate(url) => match ServoUrl::parse(&url) {
+                    Ok(url) => {
+                        self.load_url(url);
+                        Ok(serde_json::json!({}))
+                    }
+                    Err(e) => Err(format!("Invalid URL {url}: {e}")),
+                },
+                CdpMethod::Evaluate(expression) => {
+                    match self.evaluate_script(evaluate_script(&expression)) {
+                        Some(receiver) => {
+                            // Servo's IPC router thread replies once the script thread answers,
+                            // so the event loop isn't blocked.
+                            let reply = request.reply;
+                            ROUTER.add_route(
+                                receiver.to_opaque(),
+                                Box::new(move |message| {
+                                    let result = message
+                                        .to()
+                                        .map_err(|e| format!("{e:?}"))
+                                        .and_then(evaluate_result);
+                                    let _ = reply.send(result);
+                                }),
+                            );
+                            continue;
+                        }
+                        None => Err("There's no web view to evaluate the script".into()),
+                    }
+                }
+                CdpMethod::DispatchMouseEvent {
+                    event_type,
+                    x,
+                    y,
+                    button,
+                } => {
+                    // CDP positions are in CSS pixels of the page, from the web view's corner.
+                    let origin = self
+                        .current_window()
+                        .and_then(|w| w.webview.as_ref())
+                        .map_or(units::DevicePoint::origin(), |w| w.rect.min.to_f32());
+                    let scale = self
+                        .compositor
+                        .as_ref()
+                        .map_or(1.0, |c| c.device_pixels_per_page_pixel().get());
+                    let point = origin + units::DeviceVector2D::new(x, y) * scale;
+                    let button = match button.as_str() {
+                        "right" => MouseButton::Right,
+                        "middle" => MouseButton::Middle,
+                        _ => MouseButton::Left,
+                    };
+                    match (self.compositor.as_mut(), event_type.as_str()) {
+                        (Some(c), "mouseMoved") => {
+                            c.on_mouse_window_move_event_class(point);
+                            Ok(serde_json::json!({}))
+                        }
+                        (Some(c), "mousePressed") => {
+                            c.on_mouse_window_event_class(MouseWindowEvent::MouseDown(
+                                button, point,
+                            ));
+                            Ok(serde_json::json!({}))
+                        }
+                        (Some(c), "mouseReleased") => {
+                            c.on_mouse_window_event_class(MouseWindowEvent::MouseUp(button, point));
+                            c.on_mouse_window_event_class(MouseWindowEvent::Click(button, point));
+                            Ok(serde_json::json!({}))
+                        }
+                        (None, _) => Err("Verso has shut down".into()),
+                        (_, t) => Err(format!("Mouse event type {t} isn't supported")),
+                    }
+                }
+                CdpMethod::CaptureScreenshot => {
+                    self.capture_frame();
+                    if let Some(server) = &mut self.cdp_server {
+                        server.screenshot_replies.push(request.reply);
+                    }
+                    continue;
+                }
+            };
+            let _ = request.reply.send(result);
+        }
+    }
+
+    #[cfg(feature = "remote-control")]
+    fn handle_remote_control_requests(&mut self) {
+        use crate::remote_control::{evaluate_response, RemoteCommand, RemoteResponse};
+
+        let Some(server) = &mut self.remote_control else {
+            return;
+        };
+        if !server.screenshot_replies.is_empty() {
+            let replies = std::mem::take(&mut server.screenshot_replies);
+            match self.take_captured_png() {
+                Some(png) => {
+                    let response = match png {
+                        Ok(png) => RemoteResponse::Png(png),
+                        Err(e) => RemoteResponse::Error(500, e),
+                    };
+                    for reply in replies {
+                        let _ = reply.send(response.clone());
+                    }
+                }
+                None => {
+                    if let Some(server) = &mut self.remote_control {
+                        server.screenshot_replies = replies;
+                    }
+                }
+            }
+        }
+
+        while let Some(request) = self.remote_control.as_ref().and_then(|s| s.try_recv()) {
+            log::debug!("Verso remote control runs {:?}", request.command);
+            let response = match request.command {
+                RemoteCommand::Status => match self.current_window() {
+                    Some(window) => RemoteResponse::Json(serde_json::json!({
+                        "url": window.url.as_ref().map(ServoUrl::as_str),
+                        "title": window.title,
+                        "load_status": format!("{:?}", window.load_status()),
+                        "page_zoom": self.page_zoom(),
+                    })),
+                    None => RemoteResponse::Error(503, "There's no window".into()),
+                },
+                RemoteCommand::Navigate(url) => match ServoUrl::parse(&url) {
+                    Ok(url) => {
+                        self.load_url(url);
+                        RemoteResponse::ok()
+                    }
+                    Err(e) => RemoteResponse::Error(400, format!("Invalid URL {url}: {e}")),
+                },
+                RemoteCommand::Reload => match self.current_webview_id() {
+                    Some(id) => {
+                        if let Some(window) = self.current_window() {
+                            window.set_load_status(LoadStatus::Reloading);
+                        }
+                        send_to_constellation(
+                            &self.constellation_sender,
+                            ConstellationMsg::Reload(id),
+                        );
+                        RemoteResponse::ok()
+                    }
+                    None => RemoteResponse::Error(503, "There's no web view to reload".into()),
+                },
+                RemoteCommand::Back => {
+                    self.go_back();
+                    RemoteResponse::ok()
+                }
+                RemoteCommand::Forward => {
+                    self.go_forward();
+                    RemoteResponse::ok()
+                }
+                RemoteCommand::Screenshot => {
+                    self.capture_frame();
+                    if let Some(server) = &mut self.remote_control {
+                        server.screenshot_replies.push(request.reply);
+                    }
+                    continue;
+                }
+                RemoteCommand::Evaluate(script) => match self.evaluate_script(script) {
+                    Some(receiver) => {
+                        // Wait for the script thread on another thread to not block the event
+                        // loop.
+                        let reply = request.reply;
+                        std::thread::spawn(move || {
+                            let response = match receiver.recv() {
+                                Ok(result) => evaluate_response(result),
+                                Err(e) => RemoteResponse::Error(500, format!("{e:?}")),
+                            };
+                            let _ = reply.send(response);
+                        });
+                        continue;
+                    }
+                    None => RemoteResponse::Error(503, "There's no web view to evaluate".into()),
+                },
+                RemoteCommand::Zoom(page_zoom) => match (&mut self.compositor, &mut self.windows) {
+                    (Some(compositor), windows) => {
+                        if let Some(window) = windows.get_mut(&compositor.current_window) {
+                            let zoom = compositor.page_zoom();
+                            compositor.on_zoom_window_event(page_zoom / zoom, window);
+                        }
+                        RemoteResponse::ok()
+                    }
+                    (None, _) => RemoteResponse::Error(503, "Verso has shut down".into()),
+                },
+                RemoteCommand::Shutdown => {
+                    let _ = request.reply.send(RemoteResponse::ok());
+                    self.shutdown();
+                    continue;
+                }
+            };
+            let _ = request.reply.send(response);
+        }
+    }
+
+    #[cfg(macos)]
+    fn handle_menu_actions(&mut self, evl: &ActiveEventLoop) {
+        use crate::menu::MenuAction;
+
+        while let Some(action) = self.menu.as_ref().and_then(|m| m.try_recv()) {
+            log::debug!("Verso is handling menu action {action:?}");
+            let Some(compositor) = &mut self.compositor else {
+                return;
+            };
+            match action {
+                MenuAction::Quit => self.shutdown(),
+                MenuAction::NewWindow => {
+                    match create_window(evl, compositor, &self.constellation_sender, &self.config) {
+                        Ok(window) => {
+                            self.windows.insert(window.id(), window);
+                        }
+                        Err(e) => log::error!("Verso failed to create a window: {e}"),
+                    }
+                }
+                MenuAction::CloseWindow => {
+                    let window_id = compositor.current_window;
+                    self.handle_winit_window_event(window_id, WindowEvent::CloseRequested);
+                }
+                MenuAction::SelectAll => self.select_all(),
+                MenuAction::Cut | MenuAction::Copy | MenuAction::Paste => {
+                    for event in action.key_events().into_iter().flatten() {
+                        send_to_constellation(
+                            &self.constellation_sender,
+                            ConstellationMsg::Keyboard(event),
+                        );
+                    }
+                }
+                MenuAction::Shortcut(action) => {
+                    if let Some(window) = self.windows.get_mut(&compositor.current_window) {
+                        window.handle_shortcut_action(
+                            action,
+                            &self.constellation_sender,
+                            compositor,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn replay_due_events(&mut self) {
+        let Some(window_id) = self.compositor.as_ref().map(|c| c.current_window) else {
+            return;
+        };
+        let now = Instant::now();
+        while let Some(input) = self.replayer.as_ref().and_then(|r| r.due(now)) {
+            match input {
+                ReplayedInput::Window(event) => self.handle_winit_window_event(window_id, event),
+                ReplayedInput::Key(event, text) => {
+                    self.handle_keyboard_event(window_id, event, text.as_deref())
+                }
+            }
+            let Some(replayer) = &mut self.replayer else {
+                return;
+            };
+            replayer.advance();
+            if replayer.is_finished() {
+                log::debug!("Verso has finished replaying recorded events");
+            }
+        }
+    }
+
+    /// Reload the page of the current window every `interval`, or stop reloading with `None`.
+    /// Stopping cancels the pending reload right away. See [`crate::auto_reload`].
+    pub fn set_auto_reload(&mut self, interval: Option<Duration>, options: AutoReloadOptions) {
+        self.auto_reload = interval.and_then(|interval| {
+            let window = self.current_window()?;
+            let url = window.url.clone()?;
+            log::debug!(
+                "Verso Window {:?} reloads {url} every {interval:?}",
+                window.id()
+            );
+            Some(AutoReload::new(
+                window.id(),
+                url,
+                interval,
+                options,
+                Instant::now(),
+            ))
+        });
+    }
+
+    /// Reload the page of auto-reload if it's due. See [`crate::auto_reload`].
+    fn tick_auto_reload(&mut self) {
+        let Some(auto_reload) = &mut self.auto_reload else {
+            return;
+        };
+        let Some(window) = self.windows.get_mut(&auto_reload.window_id) else {
+            self.auto_reload = None;
+            return;
+        };
+        let Some(webview_id) = window.webview.as_ref().map(|w| w.webview_id) else {
+            return;
+        };
+        match auto_reload.poll(window.url.as_ref(), Instant::now()) {
+            ReloadAction::Wait => {}
+            ReloadAction::Cancel => {
+                log::debug!(
+                    "Verso Window {:?} navigated away and stops auto-reload",
+                    window.id()
+                );
+                self.auto_reload = None;
+            }
+            ReloadAction::Reload(count) => {
+                log::debug!("Verso Window {:?} auto-reloads its page", window.id());
+                window.set_load_status(LoadStatus::Reloading);
+                match auto_reload.reload_script() {
+                    Some(script) => run_script(
+                        &self.constellation_sender,
+                        webview_id,
+                        script,
+                        "auto-reload the page",
+                    ),
+                    None => send_to_constellation(
+                        &self.constellation_sender,
+                        ConstellationMsg::Reload(webview_id),
+                    ),
+                }
+                window
+                    .lifecycle_events
+                    .push(LifecycleEvent::AutoReloadFired(window.id(), count));
+            }
+        }
+    }
+
+    /// Set how Verso answers pages asking to confirm unloading with a `beforeunload` handler.
+    pub fn set_before_unload_policy(&mut self, policy: BeforeUnloadPolicy) {
+        self.config.before_unload = policy;
+    }
+
+    /// Enable or disable middle-click autoscroll. Disabling it ends autoscroll in progress.
+    pub fn set_autoscroll(&mut self, enabled: bool) {
+        self.config.autoscroll = enabled;
+        if enabled {
+            return;
+        }
+        for window in self.windows.values() {
+            if window.autoscroll.take().is_some() {
+                if let Some(compositor) = &mut self.compositor {
+                    compositor.send_root_pipeline_display_list(window);
+                }
+            }
+        }
+    }
+
+    /// Enable or disable zooming on smart magnify gesture and double tap.
+    pub fn set_smart_magnify(&mut self, enabled: bool) {
+        self.config.smart_magnify = enabled;
+    }
+
+    /// Bind the keyboard shortcut to the browser action, replacing the previous shortcut of the
+    /// action.
+    pub fn set_shortcut(&mut self, action: ShortcutAction, chord: Chord) {
+        if let Some(overridden) = self.config.shortcuts.set(action, chord.clone()) {
+            log::warn!("Verso shortcut {chord} is rebound from {overridden:?} to {action:?}");
+        }
+    }
+
+    /// Return true if one of the Verso windows is animating.
+    pub fn is_animating(&self) -> bool {
+        self.compositor
+            .as_ref()
+            .map(|c| c.is_animating)
+            .unwrap_or(false)
+            || self.windows.values().any(|w| {
+                w.progress_bar.get().is_animating()
+                    || w.scrollbars.get().is_animating()
+                    || w.autoscroll.get().is_some()
+            })
+    }
+
+    fn setup_logging(&self) {
+        let constellation_chan = self.constellation_sender.clone();
+        let env = env_logger::Env::default();
+        let env_logger = env_logger::Builder::from_env(env).build();
+        let con_logger = FromCompositorLogger::new(constellation_chan);
+
+        let filter = std::cmp::max(env_logger.filter(), con_logger.filter());
+        // Keeping every log line costs a format and a lock, so it's only done for crash reports
+        // enabled explicitly.
+        let env_logger: Box<dyn Log> =
+            if std::env::var_os(crate::crash::CRASH_REPORTS_ENV).is_some() {
+                Box::new(crate::crash::RecordingLogger(env_logger))
+            } else {
+                Box::new(env_logger)
+            };
+        let logger = BothLogger(env_logger, con_logger);
+
+        log::set_boxed_logger(Box::new(logger)).expect("Failed to set logger.");
+        log::set_max_level(filter);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Waker(pub EventLoopProxy<()>);
+
+impl EventLoopWaker for Waker {
+    fn clone_box(&self) -> Box<dyn EventLoopWaker> {
+        Box::new(self.clone())
+    }
+
+    fn wake(&self) {
+        if let Err(e) = self.0.send_event(()) {
+            log::error!("Servo failed to send wake up event to Verso: {}", e);
+        }
+    }
+}
+
+fn default_user_agent_string() -> String {
+    #[cfg(macos)]
+    const UA_STRING: &str =
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:109.0) Servo/1.0 Firefox/111.0";
+    #[cfg(ios)]
+    const UA_STRING: &str =
+        "Mozilla/5.0 (iPhone; CPU iPhone OS 16_4 like Mac OS X; rv:109.0) Servo/1.0 Firefox/111.0";
+    #[cfg(android)]
+    const UA_STRING: &str = "Mozilla/5.0 (Android; Mobile; rv:109.0) Servo/1.0 Firefox/111.0";
+    #[cfg(linux)]
+    const UA_STRING: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Servo/1.0 Firefox/111.0";
+    #[cfg(windows)]
+    const UA_STRING: &str =
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:109.0) Servo/1.0 Firefox/111.0";
+
+    format!("{UA_STRING} Verso/{}", crate::version::info().version)
+}
+
+#[derive(Clone)]
+struct RenderNotifier {
+    compositor_proxy: CompositorProxy,
+}
+
+impl RenderNotifier {
+    pub fn new(compositor_proxy: CompositorProxy) -> RenderNotifier {
+        RenderNotifier { compositor_proxy }
+    }
+}
+
+impl webrender::api::RenderNotifier for RenderNotifier {
+    fn clone(&self) -> Box<dyn webrender::api::RenderNotifier> {
+        Box::new(RenderNotifier::new(self.compositor_proxy.clone()))
+    }
+
+    fn wake_up(&self, _composite_needed: bool) {}
+
+    fn new_frame_ready(
+        &self,
+        _document_id: DocumentId,
+        _scrolled: bool,
+        composite_needed: bool,
+        _frame_publish_id: FramePublishId,
+    ) {
+        self.compositor_proxy
+            .send(CompositorMsg::NewWebRenderFrameReady(composite_needed));
+    }
+}
+
+struct WebRenderFontApiCompositorProxy(CompositorProxy);
 
 impl WebRenderFontApi for WebRenderFontApiCompositorProxy {
     fn add_font_instance(
@@ -713,6 +3078,301 @@ where
     }
 }
 
+/// Interval of capturing page states for crash recovery.
+const PAGE_STATE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Script pausing playing media, marking them to be resumed by [`RESUME_MEDIA`].
+const PAUSE_MEDIA: &str = "
+for (const media of document.querySelectorAll('audio, video')) {
+    if (!media.paused) {
+        media.pause();
+        media.dataset.versoSuspended = '';
+    }
+}";
+
+/// Script resuming the media paused by [`PAUSE_MEDIA`].
+const RESUME_MEDIA: &str = "
+for (const media of document.querySelectorAll('[data-verso-suspended]')) {
+    delete media.dataset.versoSuspended;
+    media.play().catch(() => {});
+}";
+
+/// Script pausing the playing media of the page and its same-origin frames, marking them to be
+/// resumed by [`RESUME_ALL_MEDIA`]. Cross-origin frames are skipped.
+const PAUSE_ALL_MEDIA: &str = "
+const pause = (win) => {
+    for (const media of win.document.querySelectorAll('audio, video')) {
+        if (!media.paused) {
+            media.pause();
+            media.dataset.versoPaused = '';
+        }
+    }
+    for (let i = 0; i < win.frames.length; i++) {
+        try {
+            pause(win.frames[i]);
+        } catch (e) {}
+    }
+};
+pause(window);";
+
+/// Script resuming the media paused by [`PAUSE_ALL_MEDIA`].
+const RESUME_ALL_MEDIA: &str = "
+const resume = (win) => {
+    for (const media of win.document.querySelectorAll('[data-verso-paused]')) {
+        delete media.dataset.versoPaused;
+        media.play().catch(() => {});
+    }
+    for (let i = 0; i < win.frames.length; i++) {
+        try {
+            resume(win.frames[i]);
+        } catch (e) {}
+    }
+};
+resume(window);";
+
+/// Script returning the URL of a frame and its number of child frames as JSON.
+const GET_FRAME: &str = "return JSON.stringify([location.href, window.frames.length]);";
+
+/// Script returning the timing metrics of the page as JSON, in milliseconds.
+const GET_PERFORMANCE_TIMING: &str = "
+const timing = performance.timing;
+const since = (time) => (time > 0 ? time - timing.navigationStart : null);
+const paint = (name) => {
+    const entry = performance.getEntriesByName(name, 'paint')[0];
+    return entry ? entry.startTime : null;
+};
+return JSON.stringify({
+    navigation_start: timing.navigationStart || null,
+    dom_content_loaded: since(timing.domContentLoadedEventEnd),
+    load_event_end: since(timing.loadEventEnd),
+    first_paint: paint('first-paint'),
+    first_contentful_paint: paint('first-contentful-paint'),
+});";
+
+/// URL of `panel.html` in the resources directory.
+fn panel_url(config: &Config) -> crate::Result<ServoUrl> {
+    let path = config.resource_dir.join("panel.html");
+    ServoUrl::from_file_path(&path).map_err(|_| crate::Error::InvalidPath(path))
+}
+
+/// Create a window with a panel web view loading `panel.html`.
+fn create_window(
+    evl: &ActiveEventLoop,
+    compositor: &mut IOCompositor,
+    constellation_sender: &Sender<ConstellationMsg>,
+    config: &Config,
+) -> crate::Result<Window> {
+    let mut window = Window::new_with_compositor(evl, compositor, config)?;
+    let panel_id = WebViewId::new();
+    match panel_url(config) {
+        Ok(url) => send_to_constellation(
+            constellation_sender,
+            ConstellationMsg::NewWebView(url, panel_id),
+        ),
+        Err(e) => log::error!("Verso failed to load panel: {e}"),
+    }
+    let rect = DeviceIntRect::from_size(window.size());
+    window.panel = Some(WebView::new(panel_id, rect));
+    Ok(window)
+}
+
+/// Define `navigator.getBattery()` in the web view's main frame.
+fn expose_battery_status(
+    sender: &Sender<ConstellationMsg>,
+    webview_id: WebViewId,
+    status: Option<BatteryStatus>,
+) {
+    let script = battery_script(status);
+    if let Some(receiver) = execute_script(sender, BrowsingContextId::from(webview_id), script) {
+        std::thread::spawn(move || {
+            if let Ok(Err(e)) = receiver.recv() {
+                log::warn!("Verso failed to expose battery status: {e:?}");
+            }
+        });
+    }
+}
+
+/// Ask the script thread of the browsing context to execute the script as a function body.
+pub(crate) fn execute_script(
+    sender: &Sender<ConstellationMsg>,
+    browsing_context_id: BrowsingContextId,
+    script: impl Into<String>,
+) -> Option<IpcReceiver<WebDriverJSResult>> {
+    let (reply, receiver) = ipc::channel()
+        .map_err(|e| log::warn!("Verso failed to create IPC channel: {e}"))
+        .ok()?;
+    send_to_constellation(
+        sender,
+        ConstellationMsg::WebDriverCommand(WebDriverCommandMsg::ScriptCommand(
+            browsing_context_id,
+            WebDriverScriptCommand::ExecuteScript(script.into(), reply),
+        )),
+    );
+    Some(receiver)
+}
+
+/// Send a WebDriver script command to the browsing context, and call the handler with its reply
+/// on Servo's IPC router thread. The handler isn't called if the reply never comes.
+fn route_script_command<T>(
+    sender: &Sender<ConstellationMsg>,
+    browsing_context_id: BrowsingContextId,
+    command: impl FnOnce(IpcSender<T>) -> WebDriverScriptCommand,
+    handler: impl FnOnce(T) + Send + 'static,
+) where
+    T: serde::Serialize + for<'de> serde::Deserialize<'de>,
+{
+    let (reply, receiver) = match ipc::channel() {
+        Ok(channel) => channel,
+        Err(e) => {
+            log::warn!("Verso failed to create IPC channel: {e}");
+            return;
+        }
+    };
+    send_to_constellation(
+        sender,
+        ConstellationMsg::WebDriverCommand(WebDriverCommandMsg::ScriptCommand(
+            browsing_context_id,
+            command(reply),
+        )),
+    );
+    // The handler is dropped after the first reply, or when the channel closes without one.
+    let mut handler = Some(handler);
+    ROUTER.add_route(
+        receiver.to_opaque(),
+        Box::new(move |message| {
+            let Some(handler) = handler.take() else {
+                return;
+            };
+            match message.to::<T>() {
+                Ok(reply) => handler(reply),
+                Err(e) => log::debug!(
+                    "Verso got an invalid reply from frame {browsing_context_id}: {e:?}"
+                ),
+            }
+        }),
+    );
+}
+
+/// Frames of a web view collected by [`Verso::frames`]. The handler of each pending reply holds
+/// a reference, so the callback runs when the last one is dropped.
+struct FrameQuery {
+    sender: Sender<ConstellationMsg>,
+    /// Frames with the indices of the frames leading to them, to sort them in tree order.
+    frames: Mutex<Vec<(Vec<u16>, FrameInfo)>>,
+    callback: Mutex<Option<Box<dyn FnOnce(Vec<FrameInfo>) + Send>>>,
+}
+
+impl FrameQuery {
+    /// Ask the frame for its URL and child frames, and visit the child frames.
+    fn visit(
+        self: &Arc<Self>,
+        id: BrowsingContextId,
+        parent: Option<BrowsingContextId>,
+        path: Vec<u16>,
+    ) {
+        let query = self.clone();
+        route_script_command(
+            &self.sender,
+            id,
+            |sender| WebDriverScriptCommand::ExecuteScript(GET_FRAME.to_string(), sender),
+            move |result| {
+                let (url, count) = match result {
+                    Ok(WebDriverJSValue::String(json)) => {
+                        serde_json::from_str::<(String, u32)>(&json).unwrap_or_default()
+                    }
+                    result => {
+                        log::debug!("Verso failed to read frame {id}: {result:?}");
+                        Default::default()
+                    }
+                };
+                for index in 0..count.min(u16::MAX as u32) as u16 {
+                    let child_query = query.clone();
+                    let mut child_path = path.clone();
+                    child_path.push(index);
+                    route_script_command(
+                        &query.sender,
+                        id,
+                        |sender| {
+                            WebDriverScriptCommand::GetBrowsingContextId(
+                                WebDriverFrameId::Short(index),
+                                sender,
+                            )
+                        },
+                        move |result| match result {
+                            Ok(child) => child_query.visit(child, Some(id), child_path),
+                            Err(e) => {
+                                log::debug!("Verso failed to get frame {index} of {id}: {e:?}")
+                            }
+                        },
+                    );
+                }
+                if let Ok(mut frames) = query.frames.lock() {
+                    let frame = FrameInfo {
+                        id,
+                        parent,
+                        url: ServoUrl::parse(&url).ok(),
+                        cross_origin: false,
+                    };
+                    frames.push((path, frame));
+                }
+            },
+        );
+    }
+}
+
+impl Drop for FrameQuery {
+    fn drop(&mut self) {
+        let Some(callback) = self.callback.get_mut().ok().and_then(Option::take) else {
+            return;
+        };
+        let mut frames = self
+            .frames
+            .get_mut()
+            .map(std::mem::take)
+            .unwrap_or_default();
+        frames.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut frames: Vec<FrameInfo> = frames.into_iter().map(|(_, frame)| frame).collect();
+        if let Some(main) = frames.first().and_then(|f| f.url.clone()) {
+            for frame in &mut frames[1..] {
+                frame.cross_origin = frame
+                    .url
+                    .as_ref()
+                    .map_or(false, |url| url.origin() != main.origin());
+            }
+        }
+        callback(frames);
+    }
+}
+
+/// Return true if the URLs are the same page. An empty fragment is the same as none.
+fn is_same_url(a: &ServoUrl, b: &ServoUrl) -> bool {
+    let without_empty_fragment = |url: &ServoUrl| {
+        let mut url = url.as_url().clone();
+        if url.fragment() == Some("") {
+            url.set_fragment(None);
+        }
+        url
+    };
+    without_empty_fragment(a) == without_empty_fragment(b)
+}
+
+/// Execute the script in the web view, logging the error if it fails with the description of
+/// what it does.
+pub(crate) fn run_script(
+    sender: &Sender<ConstellationMsg>,
+    webview_id: WebViewId,
+    script: String,
+    what: &'static str,
+) {
+    if let Some(receiver) = execute_script(sender, BrowsingContextId::from(webview_id), script) {
+        std::thread::spawn(move || {
+            if let Ok(Err(e)) = receiver.recv() {
+                log::warn!("Verso failed to {what}: {e:?}");
+            }
+        });
+    }
+}
+
 pub(crate) fn send_to_constellation(sender: &Sender<ConstellationMsg>, msg: ConstellationMsg) {
     let variant_name = msg.variant_name();
     if let Err(e) = sender.send(msg) {
@@ -0,0 +1,176 @@
+//! Recording of the composited frames of the current window to a video file, started with
+//! [`crate::Verso::start_recording`], like for demos and bug reports of rendering issues.
+//!
+//! Frames are read back from the GL framebuffer after each composite and piped to an `ffmpeg`
+//! process, which must be installed, as raw RGBA. The container and codec follow the extension of
+//! the output path. Verso only composites when something changes, so the last frame is repeated
+//! to keep the video at the target frame rate.
+//!
+//! Recording has a cost: reading back a frame stalls the GPU pipeline until it's rendered, and
+//! copies `width * height * 4` bytes per composite, about 8 MB for a 1080p window. Encoding runs
+//! in the `ffmpeg` process, but frames are queued in memory if it can't keep up. Frames are
+//! captured at the render resolution if one is set, so setting a small one with
+//! [`crate::Verso::set_render_resolution`] keeps recordings cheap and of a fixed size. Frames
+//! of another size than the first one, like after the window is resized, are dropped.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Sender},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use webrender_api::units::DeviceIntSize;
+
+/// Options of a video recording.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordingOptions {
+    /// Frames per second of the video. Defaults to 30.
+    pub frame_rate: u32,
+    /// Path of the `ffmpeg` executable. Defaults to `ffmpeg`, found in `PATH`.
+    pub ffmpeg: PathBuf,
+    /// Extra arguments passed to `ffmpeg` before the output path, like `["-crf", "18"]`.
+    pub encoder_args: Vec<String>,
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        Self {
+            frame_rate: 30,
+            ffmpeg: PathBuf::from("ffmpeg"),
+            encoder_args: Vec::new(),
+        }
+    }
+}
+
+/// Recording in progress, owned by the compositor.
+pub(crate) struct VideoRecorder {
+    path: PathBuf,
+    options: RecordingOptions,
+    /// Encoder process and the thread writing frames to its input, started with the first frame
+    /// since its size isn't known before.
+    encoder: Option<(Child, Sender<Vec<u8>>, JoinHandle<()>)>,
+    size: Option<DeviceIntSize>,
+    start: Instant,
+    /// Number of frames sent to the encoder.
+    frames: u64,
+    /// Last frame, repeated until the next one.
+    last_frame: Option<Vec<u8>>,
+}
+
+impl VideoRecorder {
+    /// Prepare recording to the path. The encoder starts with the first frame.
+    pub fn new(path: &Path, options: RecordingOptions) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            options: RecordingOptions {
+                frame_rate: options.frame_rate.max(1),
+                ..options
+            },
+            encoder: None,
+            size: None,
+            start: Instant::now(),
+            frames: 0,
+            last_frame: None,
+        }
+    }
+
+    /// Add a frame of RGBA pixels read back from GL, bottom row first, composited at `now`.
+    pub fn push_frame(&mut self, size: DeviceIntSize, pixels: Vec<u8>, now: Instant) {
+        match self.size {
+            Some(recorded) if recorded != size => {
+                log::debug!(
+                    "Verso drops a recorded frame of size {size:?} instead of {recorded:?}"
+                );
+                return;
+            }
+            Some(_) => {}
+            None => {
+                if let Err(e) = self.start_encoder(size) {
+                    log::warn!("Verso failed to start ffmpeg to record video: {e}");
+                    return;
+                }
+                self.size = Some(size);
+                self.start = now;
+            }
+        }
+        // The previous frame lasts until this one.
+        self.repeat_last_frame(now);
+        self.last_frame = Some(pixels);
+    }
+
+    /// Send the last frame until the video reaches `now`.
+    fn repeat_last_frame(&mut self, now: Instant) {
+        let (Some((_, sender, _)), Some(frame)) = (&self.encoder, &self.last_frame) else {
+            return;
+        };
+        let due = (now.saturating_duration_since(self.start).as_secs_f64()
+            * self.options.frame_rate as f64) as u64;
+        // Frames shorter than a frame of the video are replaced by the next one.
+        let count = due.saturating_sub(self.frames);
+        for _ in 0..count {
+            if sender.send(frame.clone()).is_err() {
+                break;
+            }
+        }
+        self.frames += count;
+    }
+
+    fn start_encoder(&mut self, size: DeviceIntSize) -> std::io::Result<()> {
+        let mut child = Command::new(&self.options.ffmpeg)
+            .args([
+                "-y",
+                "-loglevel",
+                "error",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "rgba",
+            ])
+            .args(["-s", &format!("{}x{}", size.width, size.height)])
+            .args(["-r", &self.options.frame_rate.to_string()])
+            .args(["-i", "-"])
+            // GL rows start at the bottom, and most codecs need even sizes in yuv420p.
+            .args(["-vf", "vflip,pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+            .args(["-pix_fmt", "yuv420p"])
+            .args(&self.options.encoder_args)
+            .arg(&self.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()?;
+        let mut stdin = child.stdin.take().ok_or(std::io::ErrorKind::BrokenPipe)?;
+        let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+        let writer = std::thread::spawn(move || {
+            for frame in receiver {
+                if let Err(e) = stdin.write_all(&frame) {
+                    log::warn!("Verso failed to write a frame to ffmpeg: {e}");
+                    break;
+                }
+            }
+        });
+        self.encoder = Some((child, sender, writer));
+        Ok(())
+    }
+
+    /// Send the last frame until `now`, and wait for the encoder to write the file.
+    pub fn finish(mut self, now: Instant) -> std::io::Result<PathBuf> {
+        // The video has at least one frame.
+        let frame = Duration::from_secs(1) / self.options.frame_rate;
+        self.repeat_last_frame(now.max(self.start + frame));
+        let Some((mut child, sender, writer)) = self.encoder.take() else {
+            return Err(std::io::Error::other("no frame was recorded"));
+        };
+        // Closing the input ends the video.
+        drop(sender);
+        let _ = writer.join();
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(std::io::Error::other(format!(
+                "ffmpeg exited with {status}"
+            )));
+        }
+        Ok(self.path)
+    }
+}
@@ -0,0 +1,463 @@
+//! Visual regression testing of pages rendered by Verso, for this crate's tests and downstream
+//! embedders, enabled with the `visual-test` feature.
+//!
+//! [`assert_page_matches`] loads a page at a fixed render size and scale factor, waits for it to
+//! load and for a few identical frames in a row, and compares the web view area of the frame to a
+//! baseline PNG. Pixels are compared channel by channel with a tolerance, and a pixel which
+//! differs but matches one of its neighbors in the other image is counted as antialiasing, not as
+//! a difference. On failure, the rendered frame and an image of the differences are written next
+//! to the baseline as `<name>.actual.png` and `<name>.diff.png`.
+//!
+//! Run the tests with the `VERSO_UPDATE_BASELINES` environment variable set to write the rendered
+//! frames as the new baselines instead of comparing them.
+//!
+//! Servo can only be started once per process, and Winit needs the main thread on some platforms,
+//! so visual tests run sequentially on the main thread of a test with `harness = false`, sharing a
+//! single Verso instance. The window is shown while testing, but frames are rendered offscreen at
+//! the render resolution, so the size and position of the window don't matter.
+
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use euclid::Size2D;
+use image::RgbaImage;
+use servo_url::ServoUrl;
+use style_traits::DevicePixel;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
+    platform::run_on_demand::EventLoopExtRunOnDemand,
+    window::WindowId,
+};
+
+use crate::{config::Config, webview::LoadStatus, Verso};
+
+/// Environment variable which makes [`assert_page_matches`] write baselines instead of comparing.
+pub const UPDATE_BASELINES_ENV: &str = "VERSO_UPDATE_BASELINES";
+
+/// Time between two frames captured while waiting for the page to settle.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Error of a visual test.
+#[non_exhaustive]
+#[derive(Debug, thiserror::Error)]
+pub enum VisualTestError {
+    /// The page didn't load and settle before the timeout.
+    #[error("the page didn't load and settle within {0:?}")]
+    Timeout(Duration),
+    /// Running the event loop failed.
+    #[error(transparent)]
+    EventLoop(#[from] winit::error::EventLoopError),
+    /// Reading or writing a PNG image failed.
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+    /// Verso failed to start, like without a GL driver.
+    #[error(transparent)]
+    Verso(#[from] crate::Error),
+    /// An I/O error while writing images.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// How different a rendered page can be from its baseline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    /// Largest difference of a color channel for pixels to be considered equal. Defaults to 16.
+    pub channel_delta: u8,
+    /// Largest fraction of differing pixels, antialiasing excluded. Defaults to 0.
+    pub max_different_ratio: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            channel_delta: 16,
+            max_different_ratio: 0.0,
+        }
+    }
+}
+
+/// Options of a [`VisualTest`] session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VisualTestOptions {
+    /// Size the window is rendered at, panel included. Defaults to 800×600.
+    pub size: Size2D<u32, DevicePixel>,
+    /// Scale factor pages are rendered at. Defaults to 1.
+    pub scale_factor: f32,
+    /// Number of identical frames in a row after the load for the page to be considered settled.
+    /// Defaults to 3.
+    pub stable_frames: u32,
+    /// Time for the page to load and settle. Defaults to 30 seconds.
+    pub timeout: Duration,
+    /// Resources directory of Verso. Defaults to the one of this crate.
+    pub resource_dir: PathBuf,
+}
+
+impl Default for VisualTestOptions {
+    fn default() -> Self {
+        Self {
+            size: Size2D::new(800, 600),
+            scale_factor: 1.0,
+            stable_frames: 3,
+            timeout: Duration::from_secs(30),
+            resource_dir: Path::new(env!("CARGO_MANIFEST_DIR")).join("resources"),
+        }
+    }
+}
+
+/// Frame of a web view.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Pixels, 4 bytes per pixel, rows from top to bottom.
+    pub rgba: Vec<u8>,
+}
+
+impl std::fmt::Debug for Screenshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Screenshot")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Screenshot {
+    /// Read a PNG file.
+    pub fn load(path: &Path) -> Result<Self, VisualTestError> {
+        let image = image::open(path)?.to_rgba8();
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            rgba: image.into_raw(),
+        })
+    }
+
+    /// Write the screenshot as a PNG file, creating its directory.
+    pub fn save(&self, path: &Path) -> Result<(), VisualTestError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let image = RgbaImage::from_raw(self.width, self.height, self.rgba.clone())
+            .ok_or_else(|| std::io::Error::other("the pixels don't match the size"))?;
+        image.save(path)?;
+        Ok(())
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y * self.width + x) as usize * 4;
+        [
+            self.rgba[i],
+            self.rgba[i + 1],
+            self.rgba[i + 2],
+            self.rgba[i + 3],
+        ]
+    }
+}
+
+/// Result of comparing a screenshot to its baseline.
+#[derive(Debug)]
+pub struct Comparison {
+    /// Number of differing pixels, antialiasing excluded.
+    pub different_pixels: usize,
+    /// Fraction of differing pixels, or 1 if the sizes differ.
+    pub different_ratio: f64,
+    /// Image of the differences: differing pixels in red, antialiasing in yellow, and the other
+    /// pixels of the baseline faded.
+    pub diff: Option<Screenshot>,
+}
+
+/// Compare the screenshot to its baseline with the tolerance.
+pub fn compare(actual: &Screenshot, expected: &Screenshot, tolerance: Tolerance) -> Comparison {
+    if (actual.width, actual.height) != (expected.width, expected.height) {
+        return Comparison {
+            different_pixels: (actual.width * actual.height).max(expected.width * expected.height)
+                as usize,
+            different_ratio: 1.0,
+            diff: None,
+        };
+    }
+    let (width, height) = (actual.width, actual.height);
+    let equal = |a: [u8; 4], b: [u8; 4]| {
+        a.iter()
+            .zip(b)
+            .all(|(a, b)| a.abs_diff(b) <= tolerance.channel_delta)
+    };
+    // Whether the pixel of one image is in the neighborhood of the same place in the other one.
+    let near = |image: &Screenshot, x: u32, y: u32, pixel: [u8; 4]| {
+        (y.saturating_sub(1)..=(y + 1).min(height - 1)).any(|ny| {
+            (x.saturating_sub(1)..=(x + 1).min(width - 1))
+                .any(|nx| equal(image.pixel(nx, ny), pixel))
+        })
+    };
+    let mut diff = Vec::with_capacity(actual.rgba.len());
+    let mut different_pixels = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let (a, e) = (actual.pixel(x, y), expected.pixel(x, y));
+            let color = if equal(a, e) {
+                let [r, g, b, _] = e.map(|c| 191 + c / 4);
+                [r, g, b, 255]
+            } else if near(expected, x, y, a) && near(actual, x, y, e) {
+                [255, 255, 0, 255]
+            } else {
+                different_pixels += 1;
+                [255, 0, 0, 255]
+            };
+            diff.extend_from_slice(&color);
+        }
+    }
+    Comparison {
+        different_pixels,
+        different_ratio: different_pixels as f64 / (width * height).max(1) as f64,
+        diff: Some(Screenshot {
+            width,
+            height,
+            rgba: diff,
+        }),
+    }
+}
+
+/// Session of visual tests sharing an event loop and a Verso instance.
+pub struct VisualTest {
+    event_loop: EventLoop<()>,
+    harness: Harness,
+}
+
+impl VisualTest {
+    /// Create the event loop of the session. Verso starts with the first page.
+    pub fn new(options: VisualTestOptions) -> Result<Self, VisualTestError> {
+        let event_loop = EventLoop::new()?;
+        let mut config = Config::new(options.resource_dir.clone());
+        // Tests don't depend on the cookies and settings of the user.
+        config.opts.config_dir = None;
+        let harness = Harness {
+            proxy: event_loop.create_proxy(),
+            config: Some(config),
+            options,
+            verso: None,
+            capture: None,
+        };
+        Ok(Self {
+            event_loop,
+            harness,
+        })
+    }
+
+    /// Load the page and capture its web view once it's loaded and settled.
+    pub fn capture(&mut self, url: ServoUrl) -> Result<Screenshot, VisualTestError> {
+        self.harness.capture = Some(Capture {
+            url,
+            state: CaptureState::WaitingForWebView,
+            last_frame: None,
+            stable_frames: 0,
+            deadline: Instant::now() + self.harness.options.timeout,
+            result: None,
+        });
+        self.event_loop.run_app_on_demand(&mut self.harness)?;
+        self.harness
+            .capture
+            .take()
+            .and_then(|capture| capture.result)
+            .unwrap_or(Err(VisualTestError::Timeout(self.harness.options.timeout)))
+    }
+
+    /// Capture the page and compare it to the baseline PNG, or write the baseline if
+    /// [`UPDATE_BASELINES_ENV`] is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the page doesn't match the baseline, the baseline is missing, or the page can't
+    /// be captured.
+    #[track_caller]
+    pub fn assert_page_matches(&mut self, url: ServoUrl, baseline: &Path, tolerance: Tolerance) {
+        let actual = self
+            .capture(url.clone())
+            .unwrap_or_else(|e| panic!("Failed to capture {url}: {e}"));
+        if std::env::var_os(UPDATE_BASELINES_ENV).is_some() {
+            actual
+                .save(baseline)
+                .unwrap_or_else(|e| panic!("Failed to write {}: {e}", baseline.display()));
+            return;
+        }
+        let expected = Screenshot::load(baseline).unwrap_or_else(|e| {
+            panic!(
+                "Failed to read the baseline {}: {e}. Run with {UPDATE_BASELINES_ENV}=1 to create it",
+                baseline.display()
+            )
+        });
+        let comparison = compare(&actual, &expected, tolerance);
+        if comparison.different_ratio <= tolerance.max_different_ratio {
+            return;
+        }
+        let actual_path = baseline.with_extension("actual.png");
+        let diff_path = baseline.with_extension("diff.png");
+        let _ = actual.save(&actual_path);
+        if let Some(diff) = &comparison.diff {
+            let _ = diff.save(&diff_path);
+        }
+        panic!(
+            "{url} doesn't match {}: {} pixels ({:.3}%) differ, {actual:?} instead of {expected:?}. \
+             See {} and {}",
+            baseline.display(),
+            comparison.different_pixels,
+            comparison.different_ratio * 100.0,
+            actual_path.display(),
+            diff_path.display(),
+        );
+    }
+}
+
+/// Load the page and compare it to the baseline PNG with a session of default options shared by
+/// the calls on this thread. See [`VisualTest::assert_page_matches`].
+#[track_caller]
+pub fn assert_page_matches(url: ServoUrl, baseline: impl AsRef<Path>, tolerance: Tolerance) {
+    thread_local! {
+        static SESSION: RefCell<Option<VisualTest>> = const { RefCell::new(None) };
+    }
+    SESSION.with_borrow_mut(|session| {
+        let session = session.get_or_insert_with(|| {
+            VisualTest::new(VisualTestOptions::default())
+                .unwrap_or_else(|e| panic!("Failed to start the visual test session: {e}"))
+        });
+        session.assert_page_matches(url, baseline.as_ref(), tolerance);
+    });
+}
+
+/// Stage of a capture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CaptureState {
+    /// The window has no web view yet.
+    WaitingForWebView,
+    /// The page is loading.
+    Loading,
+    /// The page is loaded, and frames are captured until they stop changing.
+    Settling,
+}
+
+/// Capture in progress.
+struct Capture {
+    url: ServoUrl,
+    state: CaptureState,
+    last_frame: Option<Screenshot>,
+    stable_frames: u32,
+    deadline: Instant,
+    result: Option<Result<Screenshot, VisualTestError>>,
+}
+
+struct Harness {
+    proxy: EventLoopProxy<()>,
+    config: Option<Config>,
+    options: VisualTestOptions,
+    verso: Option<Verso>,
+    capture: Option<Capture>,
+}
+
+impl Harness {
+    /// Advance the capture in progress, and return true once it's finished.
+    fn step(&mut self) -> bool {
+        let (Some(verso), Some(capture)) = (&mut self.verso, &mut self.capture) else {
+            return false;
+        };
+        if capture.result.is_some() {
+            return true;
+        }
+        if Instant::now() > capture.deadline {
+            capture.result = Some(Err(VisualTestError::Timeout(self.options.timeout)));
+            return true;
+        }
+        match capture.state {
+            CaptureState::WaitingForWebView => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(capture.url.clone());
+                    capture.state = CaptureState::Loading;
+                }
+            }
+            CaptureState::Loading => {
+                if verso.load_status() == LoadStatus::Complete {
+                    verso.capture_frame();
+                    capture.state = CaptureState::Settling;
+                }
+            }
+            CaptureState::Settling => {
+                let Some((size, rgba)) = verso.take_captured_frame() else {
+                    return false;
+                };
+                let frame = Screenshot {
+                    width: size.width as u32,
+                    height: size.height as u32,
+                    rgba,
+                };
+                if capture.last_frame.as_ref() == Some(&frame) {
+                    capture.stable_frames += 1;
+                } else {
+                    capture.stable_frames = 1;
+                }
+                if capture.stable_frames >= self.options.stable_frames {
+                    capture.result = Some(Ok(frame));
+                    return true;
+                }
+                capture.last_frame = Some(frame);
+                verso.capture_frame();
+            }
+        }
+        false
+    }
+}
+
+impl ApplicationHandler for Harness {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(config) = self.config.take() {
+            let mut verso = match Verso::new(event_loop, self.proxy.clone(), config) {
+                Ok(verso) => verso,
+                Err(e) => {
+                    if let Some(capture) = &mut self.capture {
+                        capture.result = Some(Err(e.into()));
+                    }
+                    event_loop.exit();
+                    return;
+                }
+            };
+            verso.override_scale_factor(self.options.scale_factor);
+            verso.set_render_resolution(Some(self.options.size));
+            self.verso = Some(verso);
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if let Some(verso) = &mut self.verso {
+            verso.handle_winit_window_event(window_id, event);
+            verso.handle_servo_messages(event_loop);
+        }
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, _: ()) {
+        if let Some(verso) = &mut self.verso {
+            verso.handle_servo_messages(event_loop);
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(verso) = &mut self.verso {
+            verso.handle_servo_messages(event_loop);
+        }
+        if self.step() {
+            event_loop.exit();
+        } else {
+            // Keep compositing frames while the page settles.
+            event_loop.set_control_flow(ControlFlow::WaitUntil(Instant::now() + FRAME_INTERVAL));
+        }
+    }
+}
@@ -1,14 +1,315 @@
+use std::time::{Duration, Instant};
+
 use arboard::Clipboard;
-use base::id::{PipelineNamespace, PipelineNamespaceId, WebViewId};
+use base::id::{BrowsingContextId, PipelineNamespace, PipelineNamespaceId, WebViewId};
 use compositing_traits::ConstellationMsg;
 use crossbeam_channel::Sender;
-use embedder_traits::{CompositorEventVariant, EmbedderMsg, PromptDefinition};
-use script_traits::TraversalDirection;
+use embedder_traits::{EmbedderMsg, PermissionRequest, PromptDefinition, PromptResult};
+use ipc_channel::ipc::IpcSender;
+use script_traits::{webdriver_msg::WebDriverJSValue, TraversalDirection};
+use serde::Deserialize;
 use servo_url::ServoUrl;
 use url::Url;
 use webrender_api::units::DeviceIntRect;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    window::Fullscreen,
+};
+
+use crate::{
+    autofill::AutofillRequest,
+    clipboard::copy_selection_html,
+    compositor::IOCompositor,
+    config::{Config, RetryPolicy},
+    dispatch::{dispatch, Action, DispatchState, UpdateThrottle, USER_ACTIVATION_DURATION},
+    external::is_external_url,
+    flow::{PageFlow, GET_PAGE_FLOW},
+    hooks::{LifecycleEvent, LoadResult},
+    verso::{execute_script, send_to_constellation},
+    window::Window,
+};
+
+/// Script returning the reason of Servo's network error page, or `null` for other pages.
+const GET_NET_ERROR: &str = "
+const prefix = 'Could not load the requested page: ';
+const text = document.body && document.body.textContent.trim();
+if (document.title !== 'Error loading page' || !text || !text.startsWith(prefix)) return null;
+return text.slice(prefix.length);";
+
+/// Return true if the network error may succeed when retried, like temporary DNS failures,
+/// resets and timeouts. Certificate errors show another page and are never retried.
+fn is_transient_net_error(error: &str) -> bool {
+    let error = error.to_ascii_lowercase();
+    [
+        "temporary failure",
+        "try again",
+        "connection reset",
+        "connection aborted",
+        "broken pipe",
+        "timed out",
+        "timeout",
+    ]
+    .iter()
+    .any(|pattern| error.contains(pattern))
+}
+
+/// Script returning the scroll position and form fields of the page as JSON, to restore them
+/// with [`restore_page_state_script`]. Fields are identified by their index in the document.
+pub(crate) const GET_PAGE_STATE: &str = "
+const fields = [];
+document.querySelectorAll('input, textarea, select').forEach((field, i) => {
+    if (['password', 'file', 'hidden'].includes(field.type)) return;
+    const checkable = field.type === 'checkbox' || field.type === 'radio';
+    fields.push([i, checkable ? field.checked : field.value]);
+});
+return JSON.stringify({ url: location.href, x: scrollX, y: scrollY, fields });";
+
+/// Script restoring the page state captured by [`GET_PAGE_STATE`], if the page is the same.
+pub(crate) fn restore_page_state_script(state: &str, restore_fields: bool) -> String {
+    format!(
+        "const state = {state};
+        if (location.href !== state.url) return;
+        if ({restore_fields}) {{
+            const fields = document.querySelectorAll('input, textarea, select');
+            for (const [i, value] of state.fields) {{
+                const field = fields[i];
+                if (!field) continue;
+                try {{
+                    if (typeof value === 'boolean') field.checked = value;
+                    else field.value = value;
+                    field.dispatchEvent(new Event('input', {{ bubbles: true }}));
+                }} catch (e) {{}}
+            }}
+        }}
+        scrollTo(state.x, state.y);"
+    )
+}
+
+/// Progress of restoring a web view after its content crashed, or of resuming it from a
+/// snapshot, holding the page state captured before.
+#[derive(Debug)]
+pub enum CrashRestore {
+    /// Servo is loading its crash page.
+    Crashed(Option<String>),
+    /// The page is loading again.
+    Reloading(Option<String>),
+    /// The page of a snapshot is loading. See [`crate::suspend`].
+    Resuming(Option<String>),
+}
+
+/// Load status of a web view's page.
+///
+/// Navigations and reloads are told apart before the first byte arrives, and both continue with
+/// [`LoadStatus::Started`] once the document starts loading. Consumers only interested in
+/// whether the page is busy can use [`LoadStatus::is_loading`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoadStatus {
+    /// Nothing has been loaded yet.
+    #[default]
+    Idle,
+    /// A navigation to another document was requested and is waiting for the response.
+    NavigationStarted,
+    /// A reload of the document was requested and is waiting for the response.
+    Reloading,
+    /// The document started loading.
+    Started,
+    /// The head of the document was parsed.
+    HeadParsed,
+    /// The document and its subresources finished loading.
+    Complete,
+    /// The load failed with a transient network error and will be retried after a backoff
+    /// delay. It holds the number of the attempt, starting from 1. See
+    /// [`crate::config::RetryPolicy`].
+    Retrying(u32),
+}
+
+impl LoadStatus {
+    /// Return true if a navigation or load is in progress.
+    pub fn is_loading(self) -> bool {
+        !matches!(self, LoadStatus::Idle | LoadStatus::Complete)
+    }
+}
+
+/// Timing metrics of a page, like the ones of the Navigation Timing and Paint Timing APIs. Times
+/// other than `navigation_start` are relative to it. Metrics which aren't available are `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PerformanceTiming {
+    /// Time the navigation started, since the Unix epoch.
+    pub navigation_start: Option<Duration>,
+    /// Time the `DOMContentLoaded` event handlers finished.
+    pub dom_content_loaded: Option<Duration>,
+    /// Time the `load` event handlers finished.
+    pub load_event_end: Option<Duration>,
+    /// Time anything was first painted.
+    pub first_paint: Option<Duration>,
+    /// Time text, images or canvases were first painted.
+    pub first_contentful_paint: Option<Duration>,
+}
+
+impl PerformanceTiming {
+    /// Parse the metrics in milliseconds returned by the timing script.
+    pub(crate) fn from_json(json: &str) -> serde_json::Result<Self> {
+        #[derive(Deserialize)]
+        struct Millis {
+            navigation_start: Option<f64>,
+            dom_content_loaded: Option<f64>,
+            load_event_end: Option<f64>,
+            first_paint: Option<f64>,
+            first_contentful_paint: Option<f64>,
+        }
+        let millis: Millis = serde_json::from_str(json)?;
+        let duration = |ms: Option<f64>| {
+            ms.filter(|ms| ms.is_finite() && *ms >= 0.0)
+                .map(|ms| Duration::from_secs_f64(ms / 1000.0))
+        };
+        Ok(Self {
+            navigation_start: duration(millis.navigation_start),
+            dom_content_loaded: duration(millis.dom_content_loaded),
+            load_event_end: duration(millis.load_event_end),
+            first_paint: duration(millis.first_paint),
+            first_contentful_paint: duration(millis.first_contentful_paint),
+        })
+    }
+}
+
+/// Kind of a dialog.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DialogKind {
+    /// `alert()`, which can only be accepted.
+    Alert,
+    /// `confirm()`.
+    Confirm,
+    /// `prompt()`, which can be answered with text.
+    Prompt,
+    /// Confirmation of leaving a page with a `beforeunload` handler. Accepting it leaves the page,
+    /// dismissing it cancels the navigation or the closing.
+    BeforeUnload,
+}
+
+/// A dialog waiting for an answer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialogInfo {
+    /// Kind of the dialog.
+    pub kind: DialogKind,
+    /// Message of the dialog. Empty for [`DialogKind::BeforeUnload`], as pages can't set it.
+    pub message: String,
+    /// Default text of a prompt dialog.
+    pub default_text: Option<String>,
+}
+
+/// Answer to a JavaScript dialog.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DialogResponse {
+    /// Press OK. Prompt dialogs return the text, or their default text if it's `None`.
+    Accept(Option<String>),
+    /// Press Cancel. Prompt dialogs return `null`.
+    Dismiss,
+}
+
+/// A dialog kept open until the embedder answers it.
+#[derive(Debug)]
+pub enum HeldDialog {
+    /// A JavaScript dialog.
+    Prompt(PromptDefinition),
+    /// A `beforeunload` confirmation, answered with whether the page can unload.
+    BeforeUnload(IpcSender<bool>),
+}
+
+/// Answer the dialog, waking up the page waiting for it.
+pub(crate) fn respond_to_dialog(dialog: HeldDialog, response: DialogResponse) {
+    let definition = match dialog {
+        HeldDialog::Prompt(definition) => definition,
+        HeldDialog::BeforeUnload(sender) => {
+            let allow = matches!(response, DialogResponse::Accept(_));
+            if let Err(e) = sender.send(allow) {
+                log::warn!("Verso failed to respond to beforeunload dialog: {e}");
+            }
+            return;
+        }
+    };
+    let result = match (definition, response) {
+        (PromptDefinition::Alert(_, sender), _) => sender.send(()),
+        (PromptDefinition::OkCancel(_, sender) | PromptDefinition::YesNo(_, sender), response) => {
+            sender.send(match response {
+                DialogResponse::Accept(_) => PromptResult::Primary,
+                DialogResponse::Dismiss => PromptResult::Secondary,
+            })
+        }
+        (PromptDefinition::Input(_, default, sender), response) => sender.send(match response {
+            DialogResponse::Accept(text) => Some(text.unwrap_or(default)),
+            DialogResponse::Dismiss => None,
+        }),
+    };
+    if let Err(e) = result {
+        log::warn!("Verso failed to respond to dialog: {e}");
+    }
+}
+
+/// Answer the request of the message with its cancel or deny value, like while shutting down, so
+/// Servo doesn't wait for an answer on a channel which is dropped. Messages without a response
+/// channel are ignored.
+pub(crate) fn cancel_request(message: EmbedderMsg) {
+    let result = match message {
+        EmbedderMsg::Prompt(definition, _) => {
+            return respond_to_dialog(HeldDialog::Prompt(definition), DialogResponse::Dismiss)
+        }
+        // Unloading doesn't block quitting.
+        EmbedderMsg::AllowUnload(sender) => sender.send(true),
+        EmbedderMsg::SelectFiles(_, _, sender) => sender.send(None),
+        EmbedderMsg::PromptPermission(_, sender) => sender.send(PermissionRequest::Denied),
+        EmbedderMsg::GetSelectedBluetoothDevice(_, sender) => sender.send(None),
+        EmbedderMsg::GetClipboardContents(sender) => sender.send(String::new()),
+        message => {
+            return log::trace!("Verso ignores {message:?} while shutting down");
+        }
+    };
+    if let Err(e) = result {
+        log::warn!("Verso failed to cancel a request: {e}");
+    }
+}
+
+/// Describe the dialog.
+pub(crate) fn dialog_info(dialog: &HeldDialog) -> DialogInfo {
+    let definition = match dialog {
+        HeldDialog::Prompt(definition) => definition,
+        HeldDialog::BeforeUnload(_) => {
+            return DialogInfo {
+                kind: DialogKind::BeforeUnload,
+                message: String::new(),
+                default_text: None,
+            }
+        }
+    };
+    let (kind, message, default_text) = match definition {
+        PromptDefinition::Alert(message, _) => (DialogKind::Alert, message, None),
+        PromptDefinition::OkCancel(message, _) | PromptDefinition::YesNo(message, _) => {
+            (DialogKind::Confirm, message, None)
+        }
+        PromptDefinition::Input(message, default, _) => {
+            (DialogKind::Prompt, message, Some(default.clone()))
+        }
+    };
+    DialogInfo {
+        kind,
+        message: message.clone(),
+        default_text,
+    }
+}
 
-use crate::{compositor::IOCompositor, verso::send_to_constellation, window::Window};
+/// A browsing context in a web view, which is the main frame or an iframe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameInfo {
+    /// ID of the browsing context. Pass it to [`crate::Verso::evaluate_script_in_frame`] to run
+    /// scripts in the frame.
+    pub id: BrowsingContextId,
+    /// ID of the parent browsing context. It's `None` for the main frame.
+    pub parent: Option<BrowsingContextId>,
+    /// URL of the frame's document, if the frame answered with a valid one.
+    pub url: Option<ServoUrl>,
+    /// True if the frame's origin differs from the main frame's. Scripts evaluated in such a
+    /// frame run with its own origin and can't reach the main frame's objects.
+    pub cross_origin: bool,
+}
 
 /// A web view is an area to display web browsing context. It's what user will treat as a "web page".
 #[derive(Debug, Clone)]
@@ -48,79 +349,297 @@ impl WebView {
 }
 
 impl Window {
-    /// Handle servo messages with corresponding web view ID.
+    /// Handle servo messages with corresponding web view ID. See [`crate::dispatch`].
     pub fn handle_servo_messages_with_webview(
         &mut self,
         webview_id: WebViewId,
         message: EmbedderMsg,
         sender: &Sender<ConstellationMsg>,
         clipboard: Option<&mut Clipboard>,
-        _compositor: &mut IOCompositor,
+        compositor: &mut IOCompositor,
+        config: &Config,
     ) {
         log::trace!("Verso WebView {webview_id:?} is handling Embedder message: {message:?}",);
-        match message {
-            EmbedderMsg::LoadStart
-            | EmbedderMsg::HeadParsed
-            | EmbedderMsg::WebViewOpened(_)
-            | EmbedderMsg::WebViewClosed(_) => {
-                // Most WebView messages are ignored because it's done by compositor.
-                log::trace!("Verso WebView {webview_id:?} ignores this message: {message:?}")
-            }
-            EmbedderMsg::WebViewFocused(w) => {
-                log::debug!(
-                    "Verso Window {:?}'s webview {} has loaded completely.",
-                    self.id(),
-                    w
-                );
-            }
-            EmbedderMsg::LoadComplete => {
-                self.window.request_redraw();
-                send_to_constellation(sender, ConstellationMsg::FocusWebView(webview_id));
-            }
-            EmbedderMsg::AllowNavigationRequest(id, _url) => {
-                // TODO should provide a API for users to check url
-                send_to_constellation(sender, ConstellationMsg::AllowNavigationResponse(id, true));
-            }
-            EmbedderMsg::GetClipboardContents(sender) => {
-                let contents = clipboard
-                    .map(|c| {
-                        c.get_text().unwrap_or_else(|e| {
-                            log::warn!(
-                                "Verso WebView {webview_id:?} failed to get clipboard content: {}",
-                                e
-                            );
-                            String::new()
-                        })
-                    })
-                    .unwrap_or_default();
-                if let Err(e) = sender.send(contents) {
-                    log::warn!(
-                        "Verso WebView {webview_id:?} failed to send clipboard content: {}",
-                        e
-                    );
+        let actions = dispatch(webview_id, message, self.dispatch_state(config));
+        self.apply_actions(
+            webview_id,
+            actions,
+            sender,
+            clipboard,
+            Some(compositor),
+            config,
+        );
+    }
+
+    /// Get the state of the window the handling of messages depends on.
+    pub(crate) fn dispatch_state<'a>(&'a self, config: &'a Config) -> DispatchState<'a> {
+        DispatchState {
+            config,
+            url: self.url.as_ref(),
+            title: self
+                .update_throttle
+                .pending_title()
+                .unwrap_or(self.title.as_deref()),
+            user_gesture: self.user_gesture,
+            user_activation: self
+                .last_activation
+                .is_some_and(|time| time.elapsed() < USER_ACTIVATION_DURATION),
+            token: &self.token,
+            crash_restore: self.crash_restore.as_ref(),
+            restores: self.restores.get(),
+            downloads: self.downloads,
+            occluded: self.occluded.get(),
+            webview_rect: self.webview.as_ref().map(|webview| webview.rect),
+            report_load_errors: self.report_load_errors,
+            report_content_size: self.report_content_size,
+        }
+    }
+
+    /// Apply the actions dispatched for a message of the web view, in order.
+    pub(crate) fn apply_actions(
+        &mut self,
+        webview_id: WebViewId,
+        actions: Vec<Action>,
+        sender: &Sender<ConstellationMsg>,
+        clipboard: Option<&mut Clipboard>,
+        compositor: Option<&mut IOCompositor>,
+        config: &Config,
+    ) {
+        let actions =
+            self.update_throttle
+                .filter(actions, config.title_update_interval, Instant::now());
+        self.apply_unthrottled(webview_id, actions, sender, clipboard, compositor, config);
+    }
+
+    /// Apply the title and favicon updates whose interval elapsed. See [`UpdateThrottle`].
+    pub(crate) fn flush_updates(&mut self, sender: &Sender<ConstellationMsg>, config: &Config) {
+        let Some(webview_id) = self.webview.as_ref().map(|w| w.webview_id) else {
+            return;
+        };
+        let actions = self
+            .update_throttle
+            .flush(config.title_update_interval, Instant::now());
+        if !actions.is_empty() {
+            self.apply_unthrottled(webview_id, actions, sender, None, None, config);
+        }
+    }
+
+    fn apply_unthrottled(
+        &mut self,
+        webview_id: WebViewId,
+        actions: Vec<Action>,
+        sender: &Sender<ConstellationMsg>,
+        mut clipboard: Option<&mut Clipboard>,
+        mut compositor: Option<&mut IOCompositor>,
+        config: &Config,
+    ) {
+        for action in actions {
+            match action {
+                Action::SetLoadStatus(status) => self.set_load_status(status),
+                Action::ClearUserGesture => self.user_gesture = false,
+                Action::ClearEncodingOverride => self.set_encoding_override(None),
+                Action::RunScript(name, script) => {
+                    let browsing_context_id = BrowsingContextId::from(webview_id);
+                    if let Some(receiver) = execute_script(sender, browsing_context_id, script) {
+                        std::thread::spawn(move || {
+                            if let Ok(Err(e)) = receiver.recv() {
+                                log::warn!("Verso failed to {name}: {e:?}");
+                            }
+                        });
+                    }
                 }
-            }
-            EmbedderMsg::SetClipboardContents(text) => {
-                clipboard.map(|c| {
-                    if let Err(e) = c.set_text(text) {
+                Action::PushEvent(event) => self.lifecycle_events.push(event),
+                Action::Send(msg) => send_to_constellation(sender, msg),
+                Action::RequestRedraw => self.window.request_redraw(),
+                Action::SetCrashRestore(restore) => self.crash_restore = restore,
+                Action::SetDownloads(downloads) => self.downloads = downloads,
+                Action::SaveCrashState(restores) => {
+                    self.restores.set(restores);
+                    let state = self
+                        .page_state
+                        .lock()
+                        .ok()
+                        .and_then(|mut state| state.take());
+                    self.crash_restore = Some(CrashRestore::Crashed(state));
+                }
+                Action::ReadPageFlow => self.read_page_flow(sender, webview_id),
+                #[cfg(feature = "pdf-viewer")]
+                Action::OpenPdf => crate::pdf::open_if_pdf(sender, webview_id, self.url.as_ref()),
+                Action::CheckLoadError(retry) => {
+                    let error = self.net_error(sender, webview_id);
+                    let retrying = match &error {
+                        Some(error) if retry => self.retry_transient_error(
+                            sender,
+                            webview_id,
+                            error,
+                            &config.navigation_retry,
+                        ),
+                        _ => false,
+                    };
+                    if !retrying {
+                        self.retries.set(0);
+                        if let Some(url) = self.url.clone() {
+                            let result = error.map_or(LoadResult::Loaded, LoadResult::Failed);
+                            self.lifecycle_events
+                                .push(LifecycleEvent::Loaded(url, result));
+                        }
+                    }
+                }
+                Action::ResetRetries => self.retries.set(0),
+                Action::AllowUnload(reply, allow) => {
+                    if let Err(e) = reply.send(allow) {
                         log::warn!(
-                            "Verso WebView {webview_id:?} failed to set clipboard contents: {}",
+                            "Verso WebView {webview_id:?} failed to send AllowUnload response: {}",
                             e
                         );
                     }
-                });
-            }
-            EmbedderMsg::EventDelivered(event) => {
-                if let CompositorEventVariant::MouseButtonEvent = event {
-                    send_to_constellation(sender, ConstellationMsg::FocusWebView(webview_id));
                 }
-            }
-            e => {
-                log::trace!("Verso WebView isn't supporting this message yet: {e:?}")
+                Action::SendClipboardContents(reply) => {
+                    let contents = clipboard
+                        .as_deref_mut()
+                        .map(|c| {
+                            c.get_text().unwrap_or_else(|e| {
+                                log::warn!(
+                                    "Verso WebView {webview_id:?} failed to get clipboard content: {}",
+                                    e
+                                );
+                                String::new()
+                            })
+                        })
+                        .unwrap_or_default();
+                    if let Err(e) = reply.send(contents) {
+                        log::warn!(
+                            "Verso WebView {webview_id:?} failed to send clipboard content: {}",
+                            e
+                        );
+                    }
+                }
+                Action::SetClipboardContents(text) => {
+                    if let Some(c) = clipboard.as_deref_mut() {
+                        if let Err(e) = c.set_text(text.clone()) {
+                            log::warn!(
+                                "Verso WebView {webview_id:?} failed to set clipboard contents: {}",
+                                e
+                            );
+                        }
+                    }
+                    copy_selection_html(sender, BrowsingContextId::from(webview_id), text);
+                }
+                Action::ShowIme(webview_rect, rect) => self.show_ime(webview_rect, rect),
+                Action::HideIme => self.hide_ime(),
+                Action::StartDragOut(item) => {
+                    if let Err(e) = crate::drag_out::start(&self.window, &item) {
+                        log::warn!("Verso failed to drag {item:?} out of the page: {e}");
+                    }
+                }
+                Action::SmartMagnify(target) => {
+                    let webview = self.webview.as_ref().filter(|w| w.webview_id == webview_id);
+                    if let (Some(compositor), Some(webview)) = (compositor.as_deref_mut(), webview)
+                    {
+                        compositor.on_smart_magnify_target(target, webview.rect);
+                    }
+                }
+                Action::SetTitle(title) => {
+                    self.title = title;
+                    self.lifecycle_events
+                        .push(LifecycleEvent::TabUpdated(self.id(), self.tab_info()));
+                }
+                Action::SetFavicon(favicon) => self.set_favicon(favicon),
+                Action::SetContentSize(size) => {
+                    self.content_size = Some(size);
+                    self.lifecycle_events
+                        .push(LifecycleEvent::ContentSizeChanged(self.id(), size));
+                }
+                Action::SetHistory(entries, current) => {
+                    if let Some(url) = entries.get(current) {
+                        crate::crash::set_url(webview_id, url);
+                        self.url = Some(url.clone());
+                    }
+                    self.set_history_state(current, entries.len());
+                    self.history = (entries, current);
+                }
+                Action::AnswerPrompt(prompt_sender, answer) => {
+                    let _ = prompt_sender.send(answer);
+                }
+                Action::AnswerPermission(sender, answer) => {
+                    if let Err(e) = sender.send(answer) {
+                        log::warn!("Verso WebView {webview_id:?} failed to answer permission: {e}");
+                    }
+                }
+                #[cfg(feature = "pdf-viewer")]
+                Action::RenderPdfPage(prompt_sender, msg) => {
+                    let _ = prompt_sender.send(crate::pdf::render_page(webview_id, &msg));
+                }
+                #[cfg(feature = "pdf-viewer")]
+                Action::DownloadPdf(prompt_sender) => {
+                    let _ = prompt_sender.send(crate::pdf::download(webview_id));
+                }
+                Action::InjectAutofill => {
+                    self.autofill_requests
+                        .push(AutofillRequest::Inject(webview_id));
+                }
+                Action::RecordAutofill(origin, msg) => {
+                    self.autofill_requests
+                        .push(AutofillRequest::Record(origin, msg));
+                }
+                Action::QueryAutofill(prompt_sender, origin, msg) => {
+                    self.autofill_requests
+                        .push(AutofillRequest::Query(prompt_sender, origin, msg));
+                }
+                Action::HoldDialog(dialog) => {
+                    // The page is blocked while its dialog is open, so a newer dialog comes from
+                    // another page and the older one can't be answered anymore.
+                    if let Some(old) = self.dialog.replace(dialog) {
+                        respond_to_dialog(old, DialogResponse::Dismiss);
+                    }
+                }
+                Action::DismissDialog(definition) => {
+                    respond_to_dialog(HeldDialog::Prompt(definition), DialogResponse::Dismiss)
+                }
+                Action::MoveWindow(position) => self
+                    .window
+                    .set_outer_position(PhysicalPosition::new(position.x, position.y)),
+                Action::ResizeWindow(size) => {
+                    let _ = self
+                        .window
+                        .request_inner_size(PhysicalSize::new(size.width, size.height));
+                }
+                Action::SetFullscreen(fullscreen) => self
+                    .window
+                    .set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None))),
+                Action::SetCursor(cursor) => self.set_cursor_icon(cursor),
+                // Devtools messages aren't sent to a web view.
+                Action::AllowDevtoolsConnection(_) | Action::SetDevtoolsPort(_) => {
+                    log::warn!("Verso WebView received a devtools action")
+                }
+                Action::Unhandled(e) => {
+                    crate::crash::count_unhandled_message(&e);
+                    log::trace!("Verso WebView isn't supporting this message yet: {e:?}")
+                }
             }
         }
     }
 
+    /// Read the writing mode and direction of the loaded page. See [`crate::flow`].
+    fn read_page_flow(&self, sender: &Sender<ConstellationMsg>, webview_id: WebViewId) {
+        let Some(receiver) =
+            execute_script(sender, BrowsingContextId::from(webview_id), GET_PAGE_FLOW)
+        else {
+            return;
+        };
+        let page_flow = self.page_flow.clone();
+        std::thread::spawn(move || match receiver.recv() {
+            Ok(Ok(WebDriverJSValue::String(json))) => {
+                let flow = PageFlow::from_json(&json);
+                log::debug!("Verso WebView {webview_id:?} has the page flow {flow:?}");
+                if let Ok(mut page_flow) = page_flow.lock() {
+                    *page_flow = flow;
+                }
+            }
+            result => log::warn!("Verso failed to read the page flow: {result:?}"),
+        });
+    }
+
     /// Handle servo messages with main panel. Return true it requests a new window.
     pub fn handle_servo_messages_with_panel(
         &mut self,
@@ -129,6 +648,7 @@ impl Window {
         sender: &Sender<ConstellationMsg>,
         clipboard: Option<&mut Clipboard>,
         _compositor: &mut IOCompositor,
+        config: &Config,
     ) -> bool {
         log::trace!("Verso Panel {panel_id:?} is handling Embedder message: {message:?}",);
         match message {
@@ -150,14 +670,14 @@ impl Window {
                 self.window.request_redraw();
                 send_to_constellation(sender, ConstellationMsg::FocusWebView(panel_id));
 
-                let demo_url = ServoUrl::parse("https://example.com").unwrap();
-                let demo_id = WebViewId::new();
+                let homepage = config.homepage_url();
+                let webview_id = WebViewId::new();
                 let size = self.size();
                 let mut rect = DeviceIntRect::from_size(size);
                 rect.min.y = rect.max.y.min(76);
-                self.webview = Some(WebView::new(demo_id, rect));
-                send_to_constellation(sender, ConstellationMsg::NewWebView(demo_url, demo_id));
-                log::debug!("Verso Window {:?} adds webview {}", self.id(), demo_id);
+                self.webview = Some(WebView::new(webview_id, rect));
+                send_to_constellation(sender, ConstellationMsg::NewWebView(homepage, webview_id));
+                log::debug!("Verso Window {:?} adds webview {}", self.id(), webview_id);
             }
             EmbedderMsg::AllowNavigationRequest(id, _url) => {
                 // The panel shouldn't navigate to other pages.
@@ -187,7 +707,14 @@ impl Window {
                                     }
                                 };
 
-                                send_to_constellation(sender, ConstellationMsg::LoadUrl(id, ServoUrl::from_url(url)));
+                                let url = ServoUrl::from_url(url);
+                                if is_external_url(&url) {
+                                    self.lifecycle_events
+                                        .push(LifecycleEvent::OpenExternal(url));
+                                    return false;
+                                }
+                                self.set_load_status(LoadStatus::NavigationStarted);
+                                send_to_constellation(sender, ConstellationMsg::LoadUrl(id, url));
                             } else {
                                 match msg.as_str() {
                                     "PREV" => {
@@ -198,7 +725,7 @@ impl Window {
                                                 TraversalDirection::Back(1),
                                             ),
                                         );
-                                        // TODO Set EmbedderMsg::Status to None
+                                        self.set_load_status(LoadStatus::NavigationStarted);
                                     }
                                     "FORWARD" => {
                                         send_to_constellation(
@@ -208,7 +735,7 @@ impl Window {
                                                 TraversalDirection::Forward(1),
                                             ),
                                         );
-                                        // TODO Set EmbedderMsg::Status to None
+                                        self.set_load_status(LoadStatus::NavigationStarted);
                                     }
                                     "REFRESH" => {
                                         // send_to_constellation(sender, ConstellationMsg::Reload(id));
@@ -251,10 +778,60 @@ impl Window {
                     }
                 });
             }
+            EmbedderMsg::ShowIME(_, _, _, rect) => {
+                if let Some(panel) = &self.panel {
+                    self.show_ime(panel.rect, rect);
+                }
+            }
+            EmbedderMsg::HideIME => self.hide_ime(),
             e => {
+                crate::crash::count_unhandled_message(&e);
                 log::trace!("Verso Panel isn't supporting this message yet: {e:?}")
             }
         }
         false
     }
+
+    /// Get the network error if the loaded page is Servo's network error page.
+    fn net_error(
+        &self,
+        sender: &Sender<ConstellationMsg>,
+        webview_id: WebViewId,
+    ) -> Option<String> {
+        let browsing_context_id = BrowsingContextId::from(webview_id);
+        let receiver = execute_script(sender, browsing_context_id, GET_NET_ERROR)?;
+        // The page has just loaded, so its script thread answers right away.
+        match receiver.try_recv_timeout(Duration::from_millis(500)) {
+            Ok(Ok(WebDriverJSValue::String(error))) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Reload the page after a backoff delay if the network error is transient, until the policy
+    /// runs out of retries. Return true if the page will be reloaded.
+    fn retry_transient_error(
+        &self,
+        sender: &Sender<ConstellationMsg>,
+        webview_id: WebViewId,
+        error: &str,
+        policy: &RetryPolicy,
+    ) -> bool {
+        let attempt = self.retries.get() + 1;
+        if !is_transient_net_error(error) || attempt > policy.max_retries {
+            log::debug!("Verso WebView {webview_id:?} failed to load: {error}");
+            return false;
+        }
+        let delay = policy.delay(attempt);
+        log::debug!(
+            "Verso WebView {webview_id:?} retries loading in {delay:?} (attempt {attempt}): {error}"
+        );
+        self.retries.set(attempt);
+        self.set_load_status(LoadStatus::Retrying(attempt));
+        let script = format!(
+            "setTimeout(() => location.reload(), {});",
+            delay.as_millis()
+        );
+        execute_script(sender, BrowsingContextId::from(webview_id), script);
+        true
+    }
 }
@@ -1,32 +1,62 @@
-use std::cell::Cell;
+use std::{
+    cell::Cell,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use base::id::WebViewId;
+use base::id::{BrowsingContextId, WebViewId};
 use compositing_traits::ConstellationMsg;
 use crossbeam_channel::Sender;
 use embedder_traits::{Cursor, EmbedderMsg};
-use euclid::{Point2D, Size2D};
+use euclid::Size2D;
+use keyboard_types::{Key, KeyState, KeyboardEvent};
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
-use script_traits::{TouchEventType, WheelDelta, WheelMode};
+use script_traits::{TouchEventType, TraversalDirection};
+use servo_url::ServoUrl;
 use surfman::Connection;
 use surfman::SurfaceType;
 use webrender_api::{
-    units::{DeviceIntPoint, DeviceIntRect, DeviceIntSize, DevicePoint, LayoutVector2D},
+    units::{DeviceIntRect, DeviceIntSize, DevicePoint, LayoutVector2D},
     ScrollLocation,
 };
 use webrender_traits::RenderingContext;
 use winit::{
-    dpi::PhysicalPosition,
-    event::{ElementState, TouchPhase, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, MouseButton, TouchPhase, WindowEvent},
     event_loop::ActiveEventLoop,
     keyboard::ModifiersState,
-    window::{CursorIcon, Window as WinitWindow, WindowId},
+    monitor::MonitorHandle,
+    window::{
+        CursorIcon, Fullscreen, Icon, Window as WinitWindow, WindowAttributes, WindowId,
+        WindowLevel,
+    },
 };
 
 use crate::{
-    compositor::{IOCompositor, MouseWindowEvent},
-    keyboard::keyboard_event_from_winit,
-    verso::send_to_constellation,
-    webview::WebView,
+    auto_resize::{AutoResize, ContentSize},
+    autofill::AutofillRequest,
+    autoscroll::Autoscroll,
+    caret::{caret_follow_script, ContentInsets},
+    compositor::IOCompositor,
+    config::{Config, MonitorSelector, WindowConfig},
+    discard::DiscardedPage,
+    dispatch::UpdateThrottle,
+    drag_out,
+    encoding::Encoding,
+    flow::PageFlow,
+    hooks::LifecycleEvent,
+    input::{self, InputEvent, InputOptions, InputState},
+    keyboard::ImeInput,
+    progress::ProgressBar,
+    scrollbars::Scrollbars,
+    shortcuts::{HeldShortcuts, KeyOutcome, ShortcutAction},
+    suspend::{ResumeProgress, WebViewSnapshot},
+    tabs::{Favicon, TabInfo},
+    timers::timer_throttle_script,
+    verso::{execute_script, run_script, send_to_constellation},
+    webview::{CrashRestore, HeldDialog, LoadStatus, WebView},
+    zoom::{magnify_script, DoubleTap, FitMode},
+    Error, Result,
 };
 
 use arboard::Clipboard;
@@ -39,220 +69,709 @@ pub struct Window {
     pub(crate) panel: Option<WebView>,
     /// The WebView of this window.
     pub(crate) webview: Option<WebView>,
-    /// The mouse physical position in the web view.
-    mouse_position: Cell<PhysicalPosition<f64>>,
+    /// Cursor position and scroll remainder of the pointer inputs.
+    input: Cell<InputState>,
+    /// Built-in loading progress bar of the web view.
+    pub(crate) progress_bar: Cell<ProgressBar>,
+    /// Drawn state of the scrollbars of the document.
+    pub(crate) scrollbars: Cell<Scrollbars>,
     /// Modifiers state of the keyboard.
     modifiers_state: Cell<ModifiersState>,
+    /// State of the input method, so composed text is typed once.
+    ime: ImeInput,
+    /// If the web view can navigate back and forward in its session history.
+    history_state: (bool, bool),
+    /// If the user has interacted with the window since the page started loading.
+    pub(crate) user_gesture: bool,
+    /// Time of the last click, touch or key press in the window.
+    pub(crate) last_activation: Option<Instant>,
+    /// Random token of the scripts Verso injects into the pages of the window. See
+    /// [`crate::passwords`].
+    pub(crate) token: String,
+    /// Load status of the web view.
+    load_status: Cell<LoadStatus>,
+    /// Encoding the document of the web view was re-decoded with.
+    encoding_override: Cell<Option<&'static Encoding>>,
+    /// Dialog of the web view waiting for an answer.
+    pub(crate) dialog: Option<HeldDialog>,
+    /// Number of times the current load was retried after a transient network error.
+    pub(crate) retries: Cell<u32>,
+    /// URL of the current page of the web view.
+    pub(crate) url: Option<ServoUrl>,
+    /// Title of the current page of the web view.
+    pub(crate) title: Option<String>,
+    /// Favicon of the current page of the web view. See [`crate::tabs`].
+    pub(crate) favicon: Option<Arc<Favicon>>,
+    /// Lifecycle events waiting for Verso to call the hooks.
+    pub(crate) lifecycle_events: Vec<LifecycleEvent>,
+    /// Requests of the page waiting for the autofill store of Verso. See [`crate::autofill`].
+    pub(crate) autofill_requests: Vec<AutofillRequest>,
+    /// Scroll position and form fields of the page, captured to restore it after a crash.
+    pub(crate) page_state: Arc<Mutex<Option<String>>>,
+    /// Progress of restoring the page after a crash.
+    pub(crate) crash_restore: Option<CrashRestore>,
+    /// Number of times the page was restored after a crash since the web view navigated.
+    pub(crate) restores: Cell<u32>,
+    /// Number of downloads the page started since it started loading. See [`crate::downloads`].
+    pub(crate) downloads: u32,
+    /// Whether the window is hidden, like when it's minimized or covered by other windows.
+    pub(crate) occluded: Cell<bool>,
+    /// Time the window was last focused, to discard the least recently used web views first.
+    pub(crate) last_focused: Cell<Instant>,
+    /// Whether the web view is kept alive when web views are discarded.
+    pub(crate) pinned: bool,
+    /// Page of the web view if it was discarded. See [`crate::discard`].
+    pub(crate) discarded: Option<DiscardedPage>,
+    /// Position and size of the web view while it's suspended. See [`crate::suspend`].
+    suspended: Option<DeviceIntRect>,
+    /// URLs of the session history of the web view, with the index of the current page.
+    pub(crate) history: (Vec<ServoUrl>, usize),
+    /// Whether loads are checked for network errors to report them to the `after_load` hook.
+    pub(crate) report_load_errors: bool,
+    /// Whether the page reports its content size. See [`crate::auto_resize`].
+    pub(crate) report_content_size: bool,
+    /// Last content size reported by the page.
+    pub(crate) content_size: Option<ContentSize>,
+    /// Fitting of the window to its content, if enabled. See [`crate::auto_resize`].
+    pub(crate) auto_resize: Option<AutoResize>,
+    /// Title and favicon updates waiting for their interval. See [`UpdateThrottle`].
+    pub(crate) update_throttle: UpdateThrottle,
+    /// Writing mode and direction of the page, read once it finished loading.
+    pub(crate) page_flow: Arc<Mutex<PageFlow>>,
+    /// Whether an editable element of the page is focused.
+    editing: Cell<bool>,
+    /// Keys which triggered a shortcut, whose release isn't forwarded to the page.
+    held_shortcuts: HeldShortcuts,
+    /// Middle-click autoscroll in progress.
+    pub(crate) autoscroll: Cell<Option<Autoscroll>>,
+    /// Taps and Ctrl-clicks toggling the fit to the width on double tap.
+    double_tap: Cell<DoubleTap>,
 }
 
 impl Window {
-    /// Create a Verso window from Winit window and return the rendering context.
-    pub fn new(evl: &ActiveEventLoop) -> (Self, RenderingContext) {
-        let window = evl
-            .create_window(WinitWindow::default_attributes())
-            // .with_transparent(true)
-            // .with_decorations(false)
-            .expect("Failed to create window.");
-
-        let rwh = window.window_handle().expect("Failed to get window handle");
-        #[cfg(macos)]
-        unsafe {
-            if let RawWindowHandle::AppKit(AppKitWindowHandle { ns_view, .. }) = rwh.as_ref() {
-                decorate_window(
-                    ns_view.as_ptr() as *mut AnyObject,
-                    LogicalPosition::new(8.0, 40.0),
-                );
-            }
-        }
+    /// Create a Verso window from Winit window and return the rendering context. Fails if the
+    /// window or its rendering context can't be created, like without a GL driver.
+    pub fn new(evl: &ActiveEventLoop, config: &Config) -> Result<(Self, RenderingContext)> {
+        let window = create_winit_window(evl, config)?;
+        let rwh = window.window_handle()?;
         let window_size = window.inner_size();
         let window_size = Size2D::new(window_size.width as i32, window_size.height as i32);
-        let display_handle = window
-            .display_handle()
-            .expect("Failed to get display handle");
+        let display_handle = window.display_handle()?;
         let connection =
-            Connection::from_display_handle(display_handle).expect("Failed to create connection");
-        let adapter = connection
-            .create_adapter()
-            .expect("Failed to create adapter");
+            Connection::from_display_handle(display_handle).map_err(Error::SurfmanError)?;
+        let adapter = connection.create_adapter().map_err(Error::SurfmanError)?;
         let native_widget = connection
             .create_native_widget_from_window_handle(rwh, window_size)
-            .expect("Failed to create native widget");
+            .map_err(Error::SurfmanError)?;
         let surface_type = SurfaceType::Widget { native_widget };
         let rendering_context = RenderingContext::create(&connection, &adapter, surface_type)
-            .expect("Failed to create rendering context");
+            .map_err(Error::SurfmanError)?;
         log::trace!("Created rendering context for window {:?}", window);
 
-        let size = window.inner_size();
-        let size = Size2D::new(size.width as i32, size.height as i32);
-        (
-            Self {
-                window,
-                panel: Some(WebView::new_panel(DeviceIntRect::from_size(size))),
-                webview: None,
-                mouse_position: Cell::new(PhysicalPosition::default()),
-                modifiers_state: Cell::new(ModifiersState::default()),
-            },
+        let panel = WebView::new_panel(DeviceIntRect::from_size(window_size));
+        Ok((
+            Self::from_winit_window(window, Some(panel))?,
             rendering_context,
-        )
-    }
-
-    /// Create a Verso window with the rendering context.
-    pub fn new_with_compositor(evl: &ActiveEventLoop, compositor: &mut IOCompositor) -> Self {
-        let window = evl
-            .create_window(WinitWindow::default_attributes())
-            // .with_transparent(true)
-            // .with_decorations(false)
-            .expect("Failed to create window.");
-
-        let rwh = window.window_handle().expect("Failed to get window handle");
-        #[cfg(macos)]
-        unsafe {
-            if let RawWindowHandle::AppKit(AppKitWindowHandle { ns_view, .. }) = rwh.as_ref() {
-                decorate_window(
-                    ns_view.as_ptr() as *mut AnyObject,
-                    LogicalPosition::new(8.0, 40.0),
-                );
-            }
-        }
+        ))
+    }
+
+    /// Create a Verso window with the rendering context. Fails if the window or its native
+    /// widget can't be created.
+    pub fn new_with_compositor(
+        evl: &ActiveEventLoop,
+        compositor: &mut IOCompositor,
+        config: &Config,
+    ) -> Result<Self> {
+        let window = create_winit_window(evl, config)?;
+        let rwh = window.window_handle()?;
         let window_size = window.inner_size();
         let window_size = Size2D::new(window_size.width as i32, window_size.height as i32);
         let native_widget = compositor
             .rendering_context
             .connection()
             .create_native_widget_from_window_handle(rwh, window_size)
-            .expect("Failed to create native widget");
+            .map_err(Error::SurfmanError)?;
         let surface_type = SurfaceType::Widget { native_widget };
         let surface = compositor
             .rendering_context
             .create_surface(surface_type)
             .ok();
         compositor.surfaces.insert(window.id(), surface);
-        Self {
+        Self::from_winit_window(window, None)
+    }
+
+    /// Wrap the Winit window with the state of a window which has no web view yet. Both
+    /// constructors go through here, so each field is initialized in one place. Fails if the
+    /// random token of the window can't be generated.
+    fn from_winit_window(window: WinitWindow, panel: Option<WebView>) -> Result<Self> {
+        Ok(Self {
             window,
-            panel: None,
+            panel,
             webview: None,
-            mouse_position: Cell::new(PhysicalPosition::default()),
+            input: Cell::new(InputState::default()),
+            progress_bar: Cell::new(ProgressBar::default()),
+            scrollbars: Cell::new(Scrollbars::default()),
             modifiers_state: Cell::new(ModifiersState::default()),
-        }
+            ime: ImeInput::new(),
+            history_state: (false, false),
+            user_gesture: false,
+            last_activation: None,
+            token: crate::token::generate()?,
+            load_status: Cell::new(LoadStatus::default()),
+            encoding_override: Cell::new(None),
+            retries: Cell::new(0),
+            url: None,
+            title: None,
+            favicon: None,
+            lifecycle_events: Vec::new(),
+            autofill_requests: Vec::new(),
+            report_load_errors: false,
+            report_content_size: false,
+            content_size: None,
+            auto_resize: None,
+            update_throttle: UpdateThrottle::new(),
+            page_flow: Arc::default(),
+            editing: Cell::new(false),
+            held_shortcuts: HeldShortcuts::default(),
+            autoscroll: Cell::new(None),
+            double_tap: Cell::default(),
+            page_state: Arc::default(),
+            crash_restore: None,
+            restores: Cell::new(0),
+            downloads: 0,
+            occluded: Cell::new(false),
+            last_focused: Cell::new(Instant::now()),
+            pinned: false,
+            discarded: None,
+            suspended: None,
+            history: (Vec::new(), 0),
+            dialog: None,
+        })
     }
 
     /// Handle Winit window event and return a boolean to indicate if the compositor should repaint immediately.
+    ///
+    /// Keyboard input isn't handled here but by [`Window::handle_keyboard_event`], because
+    /// replayed keys can't be turned back into Winit events.
     pub fn handle_winit_window_event(
         &mut self,
         sender: &Sender<ConstellationMsg>,
         compositor: &mut IOCompositor,
         event: &winit::event::WindowEvent,
+        config: &Config,
     ) -> bool {
+        if config.measure_input_latency && is_input(event) {
+            compositor.on_input_event(Instant::now());
+        }
+        if is_user_gesture(event) {
+            self.last_activation = Some(Instant::now());
+            if !self.user_gesture {
+                log::debug!("Verso Window {:?} has received a user gesture", self.id());
+                self.user_gesture = true;
+            }
+        }
+
         match event {
             WindowEvent::Focused(focused) => {
                 if *focused {
+                    self.last_focused.set(Instant::now());
                     compositor.swap_current_window(self);
+                    self.restore_discarded(sender);
+                } else {
+                    self.held_shortcuts.clear();
+                }
+            }
+            WindowEvent::Occluded(occluded) => {
+                self.occluded.set(*occluded);
+                if config.background_timer_throttle > 0 {
+                    self.apply_timer_throttle(sender, config.background_timer_throttle);
                 }
             }
             WindowEvent::Resized(size) => {
                 let size = Size2D::new(size.width, size.height);
                 return compositor.resize(size.to_i32(), self);
             }
-            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+            WindowEvent::ThemeChanged(_theme) => {
+                // Windows changes the theme when high contrast is toggled.
+                crate::contrast::refresh();
+                // A forced theme is kept regardless of the system theme.
+                #[cfg(target_os = "windows")]
+                if config.window.theme.is_none() {
+                    set_dark_title_bar(&self.window, Some(*_theme));
+                }
+            }
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                inner_size_writer,
+            } => {
+                // Keep the logical size and round the physical size with the fractional scale
+                // factor, so the buffer matches the surface size the Wayland compositor expects
+                // instead of being rescaled by it.
+                let old_scale_factor = compositor.scale_factor() as f64;
+                let size = rescaled_size(self.window.inner_size(), old_scale_factor, *scale_factor);
+                if let Err(e) = inner_size_writer.clone().request_inner_size(size) {
+                    log::warn!("Verso Window failed to request size {size:?}: {e}");
+                }
+                log::debug!(
+                    "Verso Window {:?} scale factor changes from {old_scale_factor} to {scale_factor}, buffer size is {size:?}",
+                    self.id()
+                );
                 compositor.on_scale_factor_event(*scale_factor as f32, self);
+                return compositor.resize(Size2D::new(size.width, size.height).to_i32(), self);
             }
             WindowEvent::CursorEntered { .. } => {
                 compositor.swap_current_window(self);
             }
-            WindowEvent::CursorMoved { position, .. } => {
-                let cursor: DevicePoint = DevicePoint::new(position.x as f32, position.y as f32);
-                self.mouse_position.set(*position);
-                compositor.on_mouse_window_move_event_class(cursor);
-            }
-            WindowEvent::MouseInput { state, button, .. } => {
-                let button: script_traits::MouseButton = match button {
-                    winit::event::MouseButton::Left => script_traits::MouseButton::Left,
-                    winit::event::MouseButton::Right => script_traits::MouseButton::Right,
-                    winit::event::MouseButton::Middle => script_traits::MouseButton::Middle,
-                    _ => {
-                        log::trace!(
-                            "Verso Window isn't supporting this mouse button yet: {button:?}"
-                        );
-                        return false;
-                    }
+            WindowEvent::CursorMoved { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::Touch(_) => {
+                if config.smart_magnify && self.double_tapped(event) {
+                    compositor.on_fit_width_toggle_window_event(self);
+                }
+                self.dispatch_input(event, config, compositor);
+            }
+            WindowEvent::PinchGesture { delta, .. } => {
+                compositor.on_zoom_window_event(1.0 + *delta as f32, self);
+            }
+            WindowEvent::DoubleTapGesture { .. } => {
+                let Some(webview) = self.webview.as_ref().filter(|_| config.smart_magnify) else {
+                    return false;
                 };
-                let position = Point2D::new(
-                    self.mouse_position.get().x as f32,
-                    self.mouse_position.get().y as f32,
+                let cursor = DevicePoint::new(
+                    self.input.get().cursor.x as f32,
+                    self.input.get().cursor.y as f32,
                 );
+                if let Some(point) = compositor.on_smart_magnify_window_event(cursor, webview.rect)
+                {
+                    run_script(
+                        sender,
+                        webview.webview_id,
+                        magnify_script(point),
+                        "find the element to magnify",
+                    );
+                }
+            }
+            WindowEvent::ModifiersChanged(modifier) => self.modifiers_state.set(modifier.state()),
+            WindowEvent::Ime(ime) => {
+                let events = self.ime.handle_ime(ime);
+                if events.is_empty() {
+                    return false;
+                }
+                for event in events {
+                    send_to_constellation(sender, ConstellationMsg::Keyboard(event));
+                }
+                if self.editing.get() {
+                    self.follow_caret(sender, config.content_insets);
+                }
+            }
+            e => {
+                if log::log_enabled!(log::Level::Trace) {
+                    log::trace!("Verso Window isn't supporting this window event yet: {e:?}")
+                }
+            }
+        }
+        false
+    }
 
-                let event: MouseWindowEvent = match state {
-                    ElementState::Pressed => MouseWindowEvent::MouseDown(button, position),
-                    ElementState::Released => MouseWindowEvent::MouseUp(button, position),
-                };
-                compositor.on_mouse_window_event_class(event);
+    /// Handle a keyboard event converted from Winit's `KeyEvent` with the text the key inserts.
+    /// See [`crate::keyboard::keyboard_event_from_winit`].
+    pub fn handle_keyboard_event(
+        &mut self,
+        sender: &Sender<ConstellationMsg>,
+        compositor: &mut IOCompositor,
+        event: KeyboardEvent,
+        text: Option<&str>,
+        config: &Config,
+    ) {
+        if config.measure_input_latency {
+            compositor.on_input_event(Instant::now());
+        }
+        if event.state == KeyState::Down && !event.repeat {
+            self.last_activation = Some(Instant::now());
+            if !self.user_gesture {
+                log::debug!("Verso Window {:?} has received a user gesture", self.id());
+                self.user_gesture = true;
+            }
+        }
+        if log::log_enabled!(log::Level::Trace) {
+            log::trace!("Verso is handling {:?}", event);
+        }
+        if !self.ime.filter_key(event.state, text) {
+            return;
+        }
+        match self.held_shortcuts.handle(&config.shortcuts, &event) {
+            KeyOutcome::Forward => {}
+            KeyOutcome::Trigger(action) => {
+                self.handle_shortcut_action(action, sender, compositor);
+                return;
+            }
+            KeyOutcome::Suppress => return,
+        }
+        self.scroll_to_edge(&event, compositor);
+        let follow_caret = event.state == KeyState::Down && self.editing.get();
+        let msg = ConstellationMsg::Keyboard(event);
+        send_to_constellation(sender, msg);
+        if follow_caret {
+            self.follow_caret(sender, config.content_insets);
+        }
+    }
+
+    /// Update the clamp of timers of the web view, and whether they are throttled because the
+    /// window is hidden. See [`crate::timers`].
+    pub(crate) fn apply_timer_throttle(&self, sender: &Sender<ConstellationMsg>, clamp_secs: u32) {
+        let Some(webview) = &self.webview else {
+            return;
+        };
+        let script = timer_throttle_script(clamp_secs, self.occluded.get());
+        let Some(receiver) =
+            execute_script(sender, BrowsingContextId::from(webview.webview_id), script)
+        else {
+            return;
+        };
+        std::thread::spawn(move || {
+            if let Ok(Err(e)) = receiver.recv() {
+                log::warn!("Verso failed to throttle timers: {e:?}");
+            }
+        });
+    }
+
+    /// Scroll the page to keep the caret of the focused field visible out of the insets, once it
+    /// moved. See [`crate::caret`].
+    fn follow_caret(&self, sender: &Sender<ConstellationMsg>, insets: ContentInsets) {
+        let Some(webview) = &self.webview else {
+            return;
+        };
+        let script = caret_follow_script(insets);
+        let Some(receiver) =
+            execute_script(sender, BrowsingContextId::from(webview.webview_id), script)
+        else {
+            return;
+        };
+        std::thread::spawn(move || {
+            if let Ok(Err(e)) = receiver.recv() {
+                log::warn!("Verso failed to scroll to the caret: {e:?}");
+            }
+        });
+    }
+
+    /// Close the web view to save memory, keeping its page to restore it when the window is
+    /// focused again. See [`crate::discard`].
+    pub(crate) fn discard(&mut self, state: Option<String>, sender: &Sender<ConstellationMsg>) {
+        let (Some(webview), Some(url)) = (&self.webview, &self.url) else {
+            return;
+        };
+        log::debug!("Verso Window {:?} discards its web view", self.id());
+        self.discarded = Some(DiscardedPage {
+            url: url.clone(),
+            state,
+            rect: webview.rect,
+        });
+        send_to_constellation(sender, ConstellationMsg::CloseWebView(webview.webview_id));
+        self.lifecycle_events
+            .push(LifecycleEvent::Discarded(self.id(), true));
+    }
+
+    /// Open a new web view with the page of the discarded one, and scroll it back.
+    fn restore_discarded(&mut self, sender: &Sender<ConstellationMsg>) {
+        let Some(page) = self.discarded.take() else {
+            return;
+        };
+        self.open_restored_webview(
+            page.url,
+            page.rect,
+            CrashRestore::Reloading(page.state),
+            sender,
+        );
+        self.lifecycle_events
+            .push(LifecycleEvent::Discarded(self.id(), false));
+    }
 
-                // Winit didn't send click event, so we send it after mouse up
-                if *state == ElementState::Released {
-                    let event: MouseWindowEvent = MouseWindowEvent::Click(button, position);
-                    compositor.on_mouse_window_event_class(event);
+    /// Close the web view to suspend it, keeping the window open until it's resumed. See
+    /// [`crate::suspend`].
+    pub(crate) fn suspend(&mut self, sender: &Sender<ConstellationMsg>) {
+        let Some(webview) = &self.webview else {
+            return;
+        };
+        log::debug!("Verso Window {:?} suspends its web view", self.id());
+        self.suspended = Some(webview.rect);
+        send_to_constellation(sender, ConstellationMsg::CloseWebView(webview.webview_id));
+    }
+
+    /// Open a web view with the page of the snapshot, replacing the web view of the window, and
+    /// restore the page state once it loaded. See [`crate::suspend`].
+    pub(crate) fn resume(
+        &mut self,
+        snapshot: &WebViewSnapshot,
+        sender: &Sender<ConstellationMsg>,
+    ) -> WebViewId {
+        let rect = match (&self.webview, self.suspended.take()) {
+            (Some(webview), _) => {
+                // The replaced web view is no longer the one of the window once it's closed, so
+                // the window stays open.
+                send_to_constellation(sender, ConstellationMsg::CloseWebView(webview.webview_id));
+                webview.rect
+            }
+            (None, Some(rect)) => rect,
+            (None, None) => {
+                let mut rect = DeviceIntRect::from_size(self.size());
+                if self.panel.is_some() {
+                    rect.min.y = rect.max.y.min(76);
                 }
+                rect
             }
-            WindowEvent::PinchGesture { delta, .. } => {
-                compositor.on_zoom_window_event(1.0 + *delta as f32, self);
+        };
+        let state = snapshot
+            .page
+            .as_ref()
+            .and_then(|page| serde_json::to_string(page).ok());
+        let webview_id = self.open_restored_webview(
+            snapshot.url.clone(),
+            rect,
+            CrashRestore::Resuming(state),
+            sender,
+        );
+        self.lifecycle_events
+            .push(LifecycleEvent::Resumed(webview_id, ResumeProgress::Loading));
+        webview_id
+    }
+
+    /// Open a new web view with the page, which restores its state according to `restore` once
+    /// it loaded.
+    fn open_restored_webview(
+        &mut self,
+        url: ServoUrl,
+        rect: DeviceIntRect,
+        restore: CrashRestore,
+        sender: &Sender<ConstellationMsg>,
+    ) -> WebViewId {
+        let webview_id = WebViewId::new();
+        log::debug!(
+            "Verso Window {:?} restores {url} in {webview_id}",
+            self.id()
+        );
+        self.webview = Some(WebView::new(webview_id, rect));
+        self.set_load_status(LoadStatus::NavigationStarted);
+        self.crash_restore = Some(restore);
+        send_to_constellation(sender, ConstellationMsg::NewWebView(url, webview_id));
+        webview_id
+    }
+
+    /// Convert a pointer input with [`input::convert`] and pass the resulting events to the
+    /// compositor.
+    fn dispatch_input(&self, event: &WindowEvent, config: &Config, compositor: &mut IOCompositor) {
+        if self.handle_autoscroll(event, config, compositor) {
+            return;
+        }
+        // The page doesn't see the pointer while a link or image is dragged out of it.
+        match event {
+            WindowEvent::CursorMoved { .. } if drag_out::is_dragging() => return,
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                ..
+            } => drag_out::end(),
+            _ => {}
+        }
+        let options = InputOptions {
+            scale_factor: self.window.scale_factor(),
+            wheel_axis_lock: config.wheel_axis_lock,
+            // Pinch zoom scales offsets anyway, so fractions are expected while zoomed.
+            snap_scroll: config.snap_scroll && !compositor.is_pinch_zoomed(),
+            shift: self.modifiers_state.get().shift_key(),
+            flow: self.page_flow(),
+        };
+        let mut state = self.input.get();
+        let events = input::convert(event, &mut state, &options);
+        self.input.set(state);
+        for event in events {
+            match event {
+                InputEvent::MouseMove(point) => compositor.on_mouse_window_move_event_class(point),
+                InputEvent::Mouse(event) => compositor.on_mouse_window_event_class(event),
+                InputEvent::Wheel(delta, point) => compositor.on_wheel_event(delta, point),
+                InputEvent::Scroll(location, point, phase) => {
+                    compositor.on_scroll_event(location, point, phase)
+                }
             }
-            WindowEvent::MouseWheel { delta, phase, .. } => {
-                // FIXME: Pixels per line, should be configurable (from browser setting?) and vary by zoom level.
-                const LINE_HEIGHT: f32 = 38.0;
+        }
+    }
 
-                let (mut x, mut y, mode) = match delta {
-                    winit::event::MouseScrollDelta::LineDelta(x, y) => {
-                        (*x as f64, (*y * LINE_HEIGHT) as f64, WheelMode::DeltaLine)
-                    }
-                    winit::event::MouseScrollDelta::PixelDelta(position) => {
-                        let position = position.to_logical::<f64>(self.window.scale_factor());
-                        (position.x, position.y, WheelMode::DeltaPixel)
+    /// Record the end of a touch or of a left click with Ctrl, and return true if it completes a
+    /// double tap. See [`crate::zoom`].
+    fn double_tapped(&self, event: &WindowEvent) -> bool {
+        let point = match event {
+            WindowEvent::Touch(touch) if touch.phase == TouchPhase::Ended => {
+                DevicePoint::new(touch.location.x as f32, touch.location.y as f32)
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } if self.modifiers_state.get().control_key() => self.cursor(),
+            _ => return false,
+        };
+        let mut double_tap = self.double_tap.get();
+        let double = double_tap.tap(Instant::now(), point);
+        self.double_tap.set(double_tap);
+        double
+    }
+
+    /// Start, track and end middle-click autoscroll. Return true if the event is consumed by it.
+    /// See [`crate::autoscroll`].
+    fn handle_autoscroll(
+        &self,
+        event: &WindowEvent,
+        config: &Config,
+        compositor: &mut IOCompositor,
+    ) -> bool {
+        let cursor = self.cursor();
+        let mut autoscroll = self.autoscroll.get();
+        let consumed = match (event, &mut autoscroll) {
+            (WindowEvent::CursorMoved { position, .. }, Some(autoscroll)) => {
+                autoscroll.on_cursor_moved(DevicePoint::new(position.x as f32, position.y as f32));
+                false
+            }
+            (WindowEvent::MouseInput { state, .. }, Some(current)) => {
+                match state {
+                    ElementState::Pressed => current.on_press(),
+                    ElementState::Released => {
+                        if current.on_release() {
+                            autoscroll = None;
+                        }
                     }
-                };
+                }
+                true
+            }
+            (
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Middle,
+                    ..
+                },
+                None,
+            ) if config.autoscroll
+                && self
+                    .webview
+                    .as_ref()
+                    .is_some_and(|w| w.rect.to_f32().contains(cursor)) =>
+            {
+                autoscroll = Some(Autoscroll::new(cursor));
+                true
+            }
+            _ => false,
+        };
+        let changed = autoscroll.map(|a| (a.origin, a.is_ending()))
+            != self.autoscroll.get().map(|a| (a.origin, a.is_ending()));
+        self.autoscroll.set(autoscroll);
+        if changed {
+            compositor.send_root_pipeline_display_list(self);
+        }
+        consumed
+    }
 
-                // Wheel Event
-                compositor.on_wheel_event(
-                    WheelDelta { x, y, z: 0.0, mode },
-                    DevicePoint::new(
-                        self.mouse_position.get().x as f32,
-                        self.mouse_position.get().y as f32,
-                    ),
-                );
+    /// Get the cursor position in device pixels of the window.
+    pub(crate) fn cursor(&self) -> DevicePoint {
+        let cursor = self.input.get().cursor;
+        DevicePoint::new(cursor.x as f32, cursor.y as f32)
+    }
 
-                // Scroll Event
-                // Do one axis at a time.
-                if y.abs() >= x.abs() {
-                    x = 0.0;
-                } else {
-                    y = 0.0;
-                }
+    /// Scroll to the logical start or end of pages in vertical writing modes on `Home` and `End`,
+    /// where Servo would scroll vertically. See [`crate::flow`].
+    fn scroll_to_edge(&self, event: &KeyboardEvent, compositor: &mut IOCompositor) {
+        let end = match event.key {
+            Key::Home => false,
+            Key::End => true,
+            _ => return,
+        };
+        if event.state != KeyState::Down || !event.modifiers.is_empty() || self.editing.get() {
+            return;
+        }
+        let (Some(webview), Some((x, y))) = (&self.webview, self.page_flow().edge_delta(end))
+        else {
+            return;
+        };
+        compositor.on_scroll_event(
+            ScrollLocation::Delta(LayoutVector2D::new(x as f32, y as f32)),
+            webview.rect.center(),
+            TouchEventType::Move,
+        );
+    }
 
-                let phase: TouchEventType = match phase {
-                    TouchPhase::Started => TouchEventType::Down,
-                    TouchPhase::Moved => TouchEventType::Move,
-                    TouchPhase::Ended => TouchEventType::Up,
-                    TouchPhase::Cancelled => TouchEventType::Cancel,
-                };
+    /// Get the title and favicon of the page. See [`crate::tabs`].
+    pub(crate) fn tab_info(&self) -> TabInfo {
+        TabInfo::new(self.url.clone(), self.title.clone(), self.favicon.clone())
+    }
 
-                compositor.on_scroll_event(
-                    ScrollLocation::Delta(LayoutVector2D::new(x as f32, y as f32)),
-                    DeviceIntPoint::new(
-                        self.mouse_position.get().x as i32,
-                        self.mouse_position.get().y as i32,
-                    ),
-                    phase,
+    /// Set the favicon of the page, also as the icon of the window, and report the update.
+    pub(crate) fn set_favicon(&mut self, favicon: Arc<Favicon>) {
+        if self.favicon.as_ref() == Some(&favicon) {
+            return;
+        }
+        match Icon::from_rgba(favicon.rgba.clone(), favicon.width, favicon.height) {
+            Ok(icon) => self.window.set_window_icon(Some(icon)),
+            Err(e) => log::warn!("Verso failed to set the favicon as window icon: {e}"),
+        }
+        self.favicon = Some(favicon);
+        self.lifecycle_events
+            .push(LifecycleEvent::TabUpdated(self.id(), self.tab_info()));
+    }
+
+    /// Get the writing mode and direction of the page.
+    pub(crate) fn page_flow(&self) -> PageFlow {
+        self.page_flow.lock().map(|flow| *flow).unwrap_or_default()
+    }
+
+    /// Perform the browser action triggered by a keyboard shortcut.
+    pub(crate) fn handle_shortcut_action(
+        &mut self,
+        action: ShortcutAction,
+        sender: &Sender<ConstellationMsg>,
+        compositor: &mut IOCompositor,
+    ) {
+        log::debug!("Verso Window {:?} handles shortcut {action:?}", self.id());
+        let webview_id = self.webview.as_ref().map(|w| w.webview_id);
+        match (action, webview_id) {
+            (ShortcutAction::Reload, Some(id)) => {
+                self.set_load_status(LoadStatus::Reloading);
+                send_to_constellation(sender, ConstellationMsg::Reload(id));
+            }
+            (ShortcutAction::HardReload, Some(id)) => {
+                self.set_load_status(LoadStatus::Reloading);
+                send_to_constellation(sender, ConstellationMsg::ClearCache);
+                send_to_constellation(sender, ConstellationMsg::Reload(id));
+            }
+            (ShortcutAction::Back, Some(id)) => {
+                self.set_load_status(LoadStatus::NavigationStarted);
+                send_to_constellation(
+                    sender,
+                    ConstellationMsg::TraverseHistory(id, TraversalDirection::Back(1)),
                 );
             }
-            WindowEvent::ModifiersChanged(modifier) => self.modifiers_state.set(modifier.state()),
-            WindowEvent::KeyboardInput { event, .. } => {
-                let event = keyboard_event_from_winit(&event, self.modifiers_state.get());
-                log::trace!("Verso is handling {:?}", event);
-                let msg = ConstellationMsg::Keyboard(event);
-                send_to_constellation(sender, msg);
+            (ShortcutAction::Forward, Some(id)) => {
+                self.set_load_status(LoadStatus::NavigationStarted);
+                send_to_constellation(
+                    sender,
+                    ConstellationMsg::TraverseHistory(id, TraversalDirection::Forward(1)),
+                );
+            }
+            (ShortcutAction::Print, Some(id)) => {
+                self.lifecycle_events.push(LifecycleEvent::Print(id));
             }
-            e => log::trace!("Verso Window isn't supporting this window event yet: {e:?}"),
+            (ShortcutAction::FocusAddress, _) => {
+                if let Some(panel) = &self.panel {
+                    send_to_constellation(sender, ConstellationMsg::FocusWebView(panel.webview_id));
+                }
+            }
+            (ShortcutAction::ZoomIn, _) => compositor.on_zoom_window_event(1.1, self),
+            (ShortcutAction::ZoomOut, _) => compositor.on_zoom_window_event(1.0 / 1.1, self),
+            (ShortcutAction::ResetZoom, _) => compositor.on_zoom_reset_window_event(self),
+            (ShortcutAction::ZoomToFit, _) => {
+                compositor.on_zoom_fit_window_event(FitMode::Page, self)
+            }
+            (ShortcutAction::ZoomFitWidth, _) => {
+                compositor.on_zoom_fit_window_event(FitMode::Width, self)
+            }
+            (_, None) => log::trace!("Verso Window has no web view to handle {action:?}"),
         }
-        false
     }
 
     /// Handle servo messages. Return true if it requests a new window
@@ -263,17 +782,20 @@ impl Window {
         sender: &Sender<ConstellationMsg>,
         clipboard: Option<&mut Clipboard>,
         compositor: &mut IOCompositor,
+        config: &Config,
     ) -> bool {
         // // Handle message in Verso Panel
         if let Some(panel) = &self.panel {
             if panel.webview_id == webview_id {
                 return self.handle_servo_messages_with_panel(
-                    webview_id, message, sender, clipboard, compositor,
+                    webview_id, message, sender, clipboard, compositor, config,
                 );
             }
         }
         // Handle message in Verso WebView
-        self.handle_servo_messages_with_webview(webview_id, message, sender, clipboard, compositor);
+        self.handle_servo_messages_with_webview(
+            webview_id, message, sender, clipboard, compositor, config,
+        );
         false
     }
 
@@ -288,6 +810,11 @@ impl Window {
         Size2D::new(size.width as i32, size.height as i32)
     }
 
+    /// State of the modifier keys, as last reported by Winit.
+    pub(crate) fn modifiers(&self) -> ModifiersState {
+        self.modifiers_state.get()
+    }
+
     /// Get Winit window ID of the window.
     pub fn id(&self) -> WindowId {
         self.window.id()
@@ -298,6 +825,146 @@ impl Window {
         self.window.scale_factor()
     }
 
+    /// Get the load status of the web view.
+    pub fn load_status(&self) -> LoadStatus {
+        self.load_status.get()
+    }
+
+    /// Update the load status of the web view.
+    pub(crate) fn set_load_status(&self, status: LoadStatus) {
+        let old = self.load_status.replace(status);
+        if old != status {
+            log::debug!(
+                "Verso Window {:?} load status changes from {old:?} to {status:?}",
+                self.id()
+            );
+        }
+    }
+
+    /// Get the encoding the document of the web view was re-decoded with, if it's overridden.
+    pub fn encoding_override(&self) -> Option<&'static Encoding> {
+        self.encoding_override.get()
+    }
+
+    /// Update the encoding override of the web view's document.
+    pub(crate) fn set_encoding_override(&self, encoding: Option<&'static Encoding>) {
+        self.encoding_override.set(encoding);
+    }
+
+    /// Return true if the web view can navigate back in its session history.
+    pub fn can_go_back(&self) -> bool {
+        self.history_state.0
+    }
+
+    /// Return true if the web view can navigate forward in its session history.
+    pub fn can_go_forward(&self) -> bool {
+        self.history_state.1
+    }
+
+    /// Update the session history state of the web view.
+    pub(crate) fn set_history_state(&mut self, current: usize, len: usize) {
+        self.history_state = (current > 0, current + 1 < len);
+    }
+
+    /// Get the mouse position in CSS pixels relative to the web view, if the mouse is over it.
+    pub(crate) fn mouse_position_in_webview(
+        &self,
+        compositor: &IOCompositor,
+    ) -> Option<(f32, f32)> {
+        let webview = self.webview.as_ref()?;
+        let position = self.input.get().cursor;
+        let point = DevicePoint::new(position.x as f32, position.y as f32);
+        let rect = webview.rect.to_f32();
+        if !rect.contains(point) {
+            return None;
+        }
+        let point = (point - rect.min) / compositor.device_pixels_per_page_pixel().get();
+        Some((point.x, point.y))
+    }
+
+    /// Allow IME input when a text field is focused, and place the candidate window below the
+    /// field. `rect` is relative to the web view. On platforms with an on-screen keyboard, allowing
+    /// IME is what brings the keyboard up where Winit supports it.
+    pub(crate) fn show_ime(&self, webview_rect: DeviceIntRect, rect: DeviceIntRect) {
+        let rect = rect.translate(webview_rect.min.to_vector());
+        self.editing.set(true);
+        self.window.set_ime_allowed(true);
+        self.window.set_ime_cursor_area(
+            PhysicalPosition::new(rect.min.x, rect.min.y),
+            PhysicalSize::new(rect.width().max(0) as u32, rect.height().max(0) as u32),
+        );
+    }
+
+    /// Disallow IME input when the focused element isn't editable.
+    pub(crate) fn hide_ime(&self) {
+        self.editing.set(false);
+        self.window.set_ime_allowed(false);
+    }
+
+    /// Move the window to the selected monitor. A fullscreen window stays fullscreen on the new
+    /// monitor.
+    pub fn move_to_monitor(&self, selector: &MonitorSelector) {
+        let Some(monitor) = select_monitor(
+            self.window.available_monitors(),
+            self.window.primary_monitor(),
+            selector,
+        ) else {
+            return;
+        };
+        log::debug!(
+            "Verso Window {:?} moves to monitor {:?}",
+            self.id(),
+            monitor.name()
+        );
+        if self.window.fullscreen().is_some() {
+            self.window
+                .set_fullscreen(Some(Fullscreen::Borderless(Some(monitor))));
+        } else {
+            self.window.set_outer_position(monitor.position());
+        }
+    }
+
+    /// Keep the window on top of other windows or not. Wayland doesn't let clients change the
+    /// window level, so it returns [`Error::Unsupported`] there.
+    pub fn set_always_on_top(&self, always_on_top: bool) -> Result<()> {
+        if matches!(
+            self.window.window_handle().map(|h| h.as_raw()),
+            Ok(raw_window_handle::RawWindowHandle::Wayland(_))
+        ) {
+            return Err(Error::Unsupported("always on top window on Wayland"));
+        }
+        self.window.set_window_level(if always_on_top {
+            WindowLevel::AlwaysOnTop
+        } else {
+            WindowLevel::Normal
+        });
+        Ok(())
+    }
+
+    /// Hide the window from the taskbar or not. This can only be changed at runtime on Windows.
+    /// Other platforms return [`Error::Unsupported`].
+    pub fn set_skip_taskbar(&self, skip_taskbar: bool) -> Result<()> {
+        #[cfg(target_os = "windows")]
+        {
+            use winit::platform::windows::WindowExtWindows;
+            self.window.set_skip_taskbar(skip_taskbar);
+            Ok(())
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = skip_taskbar;
+            Err(Error::Unsupported("changing skip taskbar at runtime"))
+        }
+    }
+
+    /// Get the monitors available to the window.
+    pub fn available_monitors(&self) -> Vec<MonitorInfo> {
+        self.window
+            .available_monitors()
+            .map(|monitor| MonitorInfo::from(&monitor))
+            .collect()
+    }
+
     /// Check if the window has such webview.
     pub fn has_webview(&self, id: WebViewId) -> bool {
         self.panel.as_ref().map_or(false, |w| w.webview_id == id)
@@ -325,67 +992,228 @@ impl Window {
             .filter(|w| w.webview_id == id)
             .is_some()
         {
-            (self.webview.take(), self.panel.is_none())
+            self.history_state = (false, false);
+            self.load_status.set(LoadStatus::Idle);
+            #[cfg(feature = "pdf-viewer")]
+            crate::pdf::close(id);
+            // A discarded or suspended web view is restored later, so the window stays open.
+            (
+                self.webview.take(),
+                self.panel.is_none() && self.discarded.is_none() && self.suspended.is_none(),
+            )
         } else {
             (None, false)
         }
     }
 
-    /// Get the painting order of this window.
-    pub fn painting_order(&self) -> Vec<&WebView> {
-        let mut order = vec![];
-        if let Some(webview) = &self.webview {
-            order.push(webview);
-        }
-        if let Some(panel) = &self.panel {
-            order.push(panel);
-        }
-        order
+    /// Get the painting order of this window. The web view is painted first and the panel
+    /// is painted on top of it.
+    pub fn painting_order(&self) -> impl Iterator<Item = &WebView> {
+        self.webview.iter().chain(self.panel.iter())
     }
 
     /// Set cursor icon of the window.
     pub fn set_cursor_icon(&self, cursor: Cursor) {
-        let winit_cursor = match cursor {
-            Cursor::Default => CursorIcon::Default,
-            Cursor::Pointer => CursorIcon::Pointer,
-            Cursor::ContextMenu => CursorIcon::ContextMenu,
-            Cursor::Help => CursorIcon::Help,
-            Cursor::Progress => CursorIcon::Progress,
-            Cursor::Wait => CursorIcon::Wait,
-            Cursor::Cell => CursorIcon::Cell,
-            Cursor::Crosshair => CursorIcon::Crosshair,
-            Cursor::Text => CursorIcon::Text,
-            Cursor::VerticalText => CursorIcon::VerticalText,
-            Cursor::Alias => CursorIcon::Alias,
-            Cursor::Copy => CursorIcon::Copy,
-            Cursor::Move => CursorIcon::Move,
-            Cursor::NoDrop => CursorIcon::NoDrop,
-            Cursor::NotAllowed => CursorIcon::NotAllowed,
-            Cursor::Grab => CursorIcon::Grab,
-            Cursor::Grabbing => CursorIcon::Grabbing,
-            Cursor::EResize => CursorIcon::EResize,
-            Cursor::NResize => CursorIcon::NResize,
-            Cursor::NeResize => CursorIcon::NeResize,
-            Cursor::NwResize => CursorIcon::NwResize,
-            Cursor::SResize => CursorIcon::SResize,
-            Cursor::SeResize => CursorIcon::SeResize,
-            Cursor::SwResize => CursorIcon::SwResize,
-            Cursor::WResize => CursorIcon::WResize,
-            Cursor::EwResize => CursorIcon::EwResize,
-            Cursor::NsResize => CursorIcon::NsResize,
-            Cursor::NeswResize => CursorIcon::NeswResize,
-            Cursor::NwseResize => CursorIcon::NwseResize,
-            Cursor::ColResize => CursorIcon::ColResize,
-            Cursor::RowResize => CursorIcon::RowResize,
-            Cursor::AllScroll => CursorIcon::AllScroll,
-            Cursor::ZoomIn => CursorIcon::ZoomIn,
-            Cursor::ZoomOut => CursorIcon::ZoomOut,
-            _ => CursorIcon::Default,
-        };
+        let winit_cursor = CURSOR_ICONS
+            .get(cursor as usize)
+            .copied()
+            .unwrap_or(CursorIcon::Default);
         self.window.set_cursor(winit_cursor);
     }
 }
 
+/// Information of a monitor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorInfo {
+    /// Human readable name of the monitor, if the platform provides one.
+    pub name: Option<String>,
+    /// Size in physical pixels.
+    pub size: PhysicalSize<u32>,
+    /// Top-left position in physical pixels of the desktop.
+    pub position: PhysicalPosition<i32>,
+    /// Scale factor of the monitor.
+    pub scale_factor: f64,
+    /// Refresh rate in millihertz, if the platform provides one.
+    pub refresh_rate_millihertz: Option<u32>,
+}
+
+impl From<&MonitorHandle> for MonitorInfo {
+    fn from(monitor: &MonitorHandle) -> Self {
+        Self {
+            name: monitor.name(),
+            size: monitor.size(),
+            position: monitor.position(),
+            scale_factor: monitor.scale_factor(),
+            refresh_rate_millihertz: monitor.refresh_rate_millihertz(),
+        }
+    }
+}
+
+/// Find the selected monitor. Fall back to the primary monitor, or the first monitor if the
+/// platform can't tell the primary one, when nothing matches.
+fn select_monitor(
+    monitors: impl Iterator<Item = MonitorHandle>,
+    primary: Option<MonitorHandle>,
+    selector: &MonitorSelector,
+) -> Option<MonitorHandle> {
+    let monitors: Vec<_> = monitors.collect();
+    let found = match selector {
+        MonitorSelector::Primary => primary.clone(),
+        MonitorSelector::Index(index) => monitors.get(*index).cloned(),
+        MonitorSelector::NameContains(name) => monitors
+            .iter()
+            .find(|m| m.name().map_or(false, |n| n.contains(name.as_str())))
+            .cloned(),
+    };
+    found.or_else(|| {
+        if *selector != MonitorSelector::Primary {
+            log::warn!("Verso can't find monitor {selector:?}, using the primary monitor");
+        }
+        primary.or_else(|| monitors.into_iter().next())
+    })
+}
+
+/// Attributes of new windows following the window config.
+/// Create the Winit window of a Verso window, decorated for the platform.
+fn create_winit_window(evl: &ActiveEventLoop, config: &Config) -> Result<WinitWindow> {
+    // .with_transparent(true)
+    // .with_decorations(false)
+    let window = evl.create_window(window_attributes(evl, &config.window))?;
+    #[cfg(macos)]
+    unsafe {
+        let rwh = window.window_handle()?;
+        if let RawWindowHandle::AppKit(AppKitWindowHandle { ns_view, .. }) = rwh.as_ref() {
+            decorate_window(
+                ns_view.as_ptr() as *mut AnyObject,
+                LogicalPosition::new(8.0, 40.0),
+            );
+        }
+    }
+    #[cfg(target_os = "windows")]
+    set_dark_title_bar(&window, config.window.theme);
+    Ok(window)
+}
+
+fn window_attributes(evl: &ActiveEventLoop, config: &WindowConfig) -> WindowAttributes {
+    let attributes = WinitWindow::default_attributes().with_theme(config.theme);
+    let monitor = config.monitor.as_ref().and_then(|selector| {
+        select_monitor(evl.available_monitors(), evl.primary_monitor(), selector)
+    });
+    let attributes = match &monitor {
+        Some(monitor) => attributes.with_position(monitor.position()),
+        None => attributes,
+    };
+    let attributes = attributes.with_window_level(config.level);
+    let attributes = match config.headless {
+        Some(size) => attributes.with_visible(false).with_inner_size(size),
+        None => attributes,
+    };
+    let attributes = if config.skip_taskbar {
+        with_skip_taskbar(attributes)
+    } else {
+        attributes
+    };
+    if config.fullscreen {
+        attributes.with_fullscreen(Some(Fullscreen::Borderless(monitor)))
+    } else {
+        attributes
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn with_skip_taskbar(attributes: WindowAttributes) -> WindowAttributes {
+    use winit::platform::windows::WindowAttributesExtWindows;
+    attributes.with_skip_taskbar(true)
+}
+
+#[cfg(linux)]
+fn with_skip_taskbar(attributes: WindowAttributes) -> WindowAttributes {
+    use winit::platform::x11::{WindowAttributesExtX11, WindowType};
+    attributes.with_x11_window_type(vec![WindowType::Utility])
+}
+
+#[cfg(not(any(target_os = "windows", linux)))]
+fn with_skip_taskbar(attributes: WindowAttributes) -> WindowAttributes {
+    log::warn!("Verso can't hide windows from the taskbar on this platform");
+    attributes
+}
+
+/// Return true if the event is an input which counts as user activation, like clicking or typing.
+/// Physical size of a window of the physical size at the old scale factor, once the scale factor
+/// changes. The logical size is kept and the physical size is rounded to whole pixels, so
+/// fractional scale factors like `1.25` give the buffer size the platform expects.
+pub fn rescaled_size(
+    size: PhysicalSize<u32>,
+    old_scale_factor: f64,
+    scale_factor: f64,
+) -> PhysicalSize<u32> {
+    size.to_logical::<f64>(old_scale_factor)
+        .to_physical(scale_factor)
+}
+
+fn is_user_gesture(event: &WindowEvent) -> bool {
+    match event {
+        WindowEvent::MouseInput { state, .. } => *state == ElementState::Pressed,
+        WindowEvent::Touch(touch) => touch.phase == TouchPhase::Started,
+        _ => false,
+    }
+}
+
+/// Return true if the event is an input which may change what's painted.
+pub(crate) fn is_input(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::CursorMoved { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::KeyboardInput { .. }
+            | WindowEvent::Ime(_)
+            | WindowEvent::Touch(_)
+            | WindowEvent::PinchGesture { .. }
+            | WindowEvent::DoubleTapGesture { .. }
+    )
+}
+
+/// Lookup table from Servo's [`Cursor`] to Winit's [`CursorIcon`], indexed by the
+/// discriminant of [`Cursor`]. The order must follow the declaration order of [`Cursor`].
+const CURSOR_ICONS: [CursorIcon; 35] = [
+    CursorIcon::Default, // Cursor::None
+    CursorIcon::Default,
+    CursorIcon::Pointer,
+    CursorIcon::ContextMenu,
+    CursorIcon::Help,
+    CursorIcon::Progress,
+    CursorIcon::Wait,
+    CursorIcon::Cell,
+    CursorIcon::Crosshair,
+    CursorIcon::Text,
+    CursorIcon::VerticalText,
+    CursorIcon::Alias,
+    CursorIcon::Copy,
+    CursorIcon::Move,
+    CursorIcon::NoDrop,
+    CursorIcon::NotAllowed,
+    CursorIcon::Grab,
+    CursorIcon::Grabbing,
+    CursorIcon::EResize,
+    CursorIcon::NResize,
+    CursorIcon::NeResize,
+    CursorIcon::NwResize,
+    CursorIcon::SResize,
+    CursorIcon::SeResize,
+    CursorIcon::SwResize,
+    CursorIcon::WResize,
+    CursorIcon::EwResize,
+    CursorIcon::NsResize,
+    CursorIcon::NeswResize,
+    CursorIcon::NwseResize,
+    CursorIcon::ColResize,
+    CursorIcon::RowResize,
+    CursorIcon::AllScroll,
+    CursorIcon::ZoomIn,
+    CursorIcon::ZoomOut,
+];
+
 /* window decoration */
 #[cfg(macos)]
 use objc2::runtime::AnyObject;
@@ -393,6 +1221,8 @@ use objc2::runtime::AnyObject;
 use raw_window_handle::{AppKitWindowHandle, RawWindowHandle};
 #[cfg(macos)]
 use winit::dpi::LogicalPosition;
+#[cfg(target_os = "windows")]
+use winit::window::Theme;
 
 /// Window decoration for macOS.
 #[cfg(macos)]
@@ -414,3 +1244,46 @@ pub unsafe fn decorate_window(view: *mut AnyObject, _position: LogicalPosition<f
             | NSWindowStyleMask::Miniaturizable,
     );
 }
+
+/// Use dark or light title bar on Windows. `None` follows the system theme.
+#[cfg(target_os = "windows")]
+fn set_dark_title_bar(window: &WinitWindow, theme: Option<Theme>) {
+    use raw_window_handle::RawWindowHandle;
+    use windows_sys::Win32::Graphics::Dwm::{DwmSetWindowAttribute, DWMWA_USE_IMMERSIVE_DARK_MODE};
+
+    let dark = theme.or_else(|| window.theme()) == Some(Theme::Dark);
+    let Ok(handle) = window.window_handle() else {
+        return;
+    };
+    if let RawWindowHandle::Win32(handle) = handle.as_raw() {
+        let value = dark as i32;
+        // SAFETY: The window handle is valid while the Winit window is alive, and the attribute
+        // value is a BOOL.
+        let result = unsafe {
+            DwmSetWindowAttribute(
+                handle.hwnd.get() as _,
+                DWMWA_USE_IMMERSIVE_DARK_MODE as _,
+                &value as *const i32 as *const _,
+                std::mem::size_of::<i32>() as u32,
+            )
+        };
+        if result != 0 {
+            log::warn!("Verso failed to set dark title bar: {result:#x}");
+        }
+    }
+}
+
+/// Declare the process as per-monitor DPI aware (v2) on Windows, so windows are rescaled
+/// instead of being stretched when moving between monitors. It must be called before creating
+/// the event loop.
+#[cfg(target_os = "windows")]
+pub fn declare_per_monitor_dpi_awareness() {
+    use windows_sys::Win32::UI::HiDpi::{
+        SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+    };
+
+    // SAFETY: It only changes the DPI awareness of the current process.
+    if unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) } == 0 {
+        log::warn!("Verso failed to declare per-monitor DPI awareness");
+    }
+}
@@ -0,0 +1,175 @@
+//! Page zoom fitted to the content, set with [`crate::Verso::zoom_to_fit`] and
+//! [`crate::Verso::zoom_fit_width`] or their shortcuts, like to read wide documents.
+//!
+//! The fit mode stays active while the window is resized: the zoom is fitted again once the page
+//! is laid out for the new size. Zooming in, out or resetting the zoom leaves the mode. Changes
+//! are reported to [`crate::VersoBuilder::on_zoom_changed`].
+//!
+//! The content is measured at the current zoom, so the fit is a factor applied to it. Content
+//! which follows the viewport, like the width of responsive pages, fits at any zoom, so its
+//! factor is 1 and the zoom is kept rather than chasing its own layout.
+//!
+//! The smart magnify gesture pinch zooms into the element under the cursor instead. The page
+//! reports the bounds of the element with a [`MAGNIFY_PROMPT`] message, and the zoom fits the
+//! width of the element to the viewport. A double tap on a touch screen, or a double-click with
+//! Ctrl, toggles the page zoom between fitting the width and 1.
+//!
+//! Windows have two zoom factors. The page zoom scales CSS pixels, so the page is laid out again
+//! for a smaller or larger viewport. The pinch zoom then magnifies the rendered page without
+//! laying it out again. See [`css_to_device_scale`] for how they compose. Both are reported
+//! together to [`crate::VersoBuilder::on_zoom_changed`] when either changes.
+
+use std::time::{Duration, Instant};
+
+use euclid::Scale;
+use serde::Deserialize;
+use servo_geometry::DeviceIndependentPixel;
+use style_traits::{CSSPixel, DevicePixel};
+use webrender_api::units::{DevicePoint, LayoutPoint, LayoutRect, LayoutSize};
+
+/// Part of the content which is fitted to the web view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FitMode {
+    /// Fit the whole content, so it's visible without scrolling.
+    Page,
+    /// Fit the width of the content, so it's readable without scrolling horizontally.
+    Width,
+}
+
+/// Zoom factors of a window reported to [`crate::VersoBuilder::on_zoom_changed`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZoomChange {
+    /// Page zoom factor. See [`crate::Verso::page_zoom`].
+    pub page_zoom: f32,
+    /// Pinch zoom factor, applied after the page zoom. See [`crate::Verso::pinch_zoom`].
+    pub pinch_zoom: f32,
+    /// Fit mode which set the zoom, or `None` if it was set by hand.
+    pub fit: Option<FitMode>,
+}
+
+/// Device pixels covered by a CSS pixel. The page zoom applies first, scaling CSS pixels to
+/// device independent pixels, then the scale factor of the window to device pixels, and the pinch
+/// zoom magnifies the device pixels last. The product is the same in any order, but only the
+/// first two change the layout viewport, which is the web view size divided by them.
+pub fn css_to_device_scale(
+    scale_factor: f32,
+    page_zoom: f32,
+    pinch_zoom: f32,
+) -> Scale<f32, CSSPixel, DevicePixel> {
+    let page_zoom: Scale<f32, CSSPixel, DeviceIndependentPixel> = Scale::new(page_zoom);
+    let scale_factor: Scale<f32, DeviceIndependentPixel, DevicePixel> = Scale::new(scale_factor);
+    let pinch_zoom: Scale<f32, DevicePixel, DevicePixel> = Scale::new(pinch_zoom);
+    page_zoom * scale_factor * pinch_zoom
+}
+
+/// Longest time between the two taps or clicks of a double tap.
+pub const DOUBLE_TAP_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Farthest distance between the two taps or clicks of a double tap, in device pixels.
+pub const DOUBLE_TAP_DISTANCE: f32 = 24.0;
+
+/// Recognizer of the double tap toggling the fit to the width.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DoubleTap {
+    /// Time and position of the last tap which wasn't part of a double tap.
+    last: Option<(Instant, DevicePoint)>,
+}
+
+impl DoubleTap {
+    /// Record a tap, and return true if it completes a double tap. The tap after a double tap
+    /// starts a new one, so triple taps toggle once.
+    pub fn tap(&mut self, time: Instant, point: DevicePoint) -> bool {
+        let double = self.last.is_some_and(|(last, last_point)| {
+            time.saturating_duration_since(last) <= DOUBLE_TAP_INTERVAL
+                && (point - last_point).length() <= DOUBLE_TAP_DISTANCE
+        });
+        self.last = if double { None } else { Some((time, point)) };
+        double
+    }
+}
+
+/// Factor to multiply the page zoom with so the content fits the viewport in the mode. Both
+/// sizes are in CSS pixels at the current zoom. Sizes within a pixel of each other are treated
+/// as equal, and empty content or viewports give 1, so the zoom is kept.
+pub fn fit_factor(mode: FitMode, content: LayoutSize, viewport: LayoutSize) -> f32 {
+    if content.is_empty() || viewport.is_empty() {
+        return 1.0;
+    }
+    let factor = |content: f32, viewport: f32| {
+        if (content - viewport).abs() < 1.0 {
+            1.0
+        } else {
+            viewport / content
+        }
+    };
+    let width = factor(content.width, viewport.width);
+    match mode {
+        FitMode::Page => width.min(factor(content.height, viewport.height)),
+        FitMode::Width => width,
+    }
+}
+
+/// Factor to pinch zoom with so an element fits the width of the viewport, leaving the margin on
+/// both sides. All widths are in the same pixels. Pinch zoom doesn't zoom out, so elements which
+/// fit already, and empty elements or viewports, give 1.
+pub fn magnify_factor(element: f32, viewport: f32, margin: f32) -> f32 {
+    let available = viewport - 2.0 * margin;
+    if element <= 0.0 || available <= 0.0 {
+        return 1.0;
+    }
+    (available / element).max(1.0)
+}
+
+/// Prefix of the message the page sends with `prompt()` with the element to magnify.
+pub(crate) const MAGNIFY_PROMPT: &str = "VERSO_MAGNIFY:";
+
+/// Script reporting the element at the point, in CSS pixels of the viewport. Inline elements are
+/// replaced by their block, so a double tap on a link zooms into its paragraph.
+pub(crate) fn magnify_script(point: LayoutPoint) -> String {
+    let (x, y) = (point.x, point.y);
+    format!(
+        "
+let element = document.elementFromPoint({x}, {y});
+while (element && element.parentElement && getComputedStyle(element).display.startsWith('inline')) {{
+    element = element.parentElement;
+}}
+if (element) {{
+    const rect = element.getBoundingClientRect();
+    prompt('{MAGNIFY_PROMPT}' + JSON.stringify({{
+        left: rect.left, top: rect.top, width: rect.width, height: rect.height, y: {y}
+    }}));
+}}"
+    )
+}
+
+/// Element to zoom into with the smart magnify gesture, in CSS pixels of the viewport.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MagnifyTarget {
+    /// Bounds of the element.
+    pub element: LayoutRect,
+    /// Vertical position of the gesture, which stays in place while zooming.
+    pub y: f32,
+}
+
+/// Message of the element to magnify.
+#[derive(Deserialize)]
+struct MagnifyMessage {
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+    y: f32,
+}
+
+/// Read the element of a [`MAGNIFY_PROMPT`] message.
+pub(crate) fn read_magnify_target(message: &str) -> Option<MagnifyTarget> {
+    let json = message.strip_prefix(MAGNIFY_PROMPT)?;
+    let message: MagnifyMessage = serde_json::from_str(json).ok()?;
+    Some(MagnifyTarget {
+        element: LayoutRect::from_origin_and_size(
+            LayoutPoint::new(message.left, message.top),
+            LayoutSize::new(message.width, message.height),
+        ),
+        y: message.y,
+    })
+}
@@ -0,0 +1,215 @@
+//! Auto-reload fires every interval, is postponed while the user interacts, stops once the page
+//! navigates away, and is cancelled when it's turned off.
+//!
+//! The schedule is checked with a simulated clock first. Then a page served by a local server is
+//! reloaded with a short interval, keeping its scroll position, until auto-reload is turned off.
+
+mod common;
+
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+use verso::{
+    auto_reload::{AutoReload, AutoReloadOptions, ReloadAction, ACTIVITY_TIMEOUT},
+    config::Config,
+    webview::LoadStatus,
+    Verso, VersoBuilder,
+};
+use winit::{
+    event_loop::{ActiveEventLoop, EventLoopProxy},
+    window::WindowId,
+};
+
+const INTERVAL: Duration = Duration::from_secs(10);
+
+fn auto_reload(url: &ServoUrl, options: AutoReloadOptions, now: Instant) -> AutoReload {
+    // SAFETY: The ID is only compared, never passed to Winit.
+    let window_id = unsafe { WindowId::dummy() };
+    AutoReload::new(window_id, url.clone(), INTERVAL, options, now)
+}
+
+fn check_schedule() {
+    let url = ServoUrl::parse("https://example.com/dashboard").unwrap();
+    let other = ServoUrl::parse("https://example.com/settings").unwrap();
+    let start = Instant::now();
+    let at = |secs: u64| start + Duration::from_secs(secs);
+
+    // Fires every interval, counted from the last reload.
+    let mut reload = auto_reload(&url, AutoReloadOptions::default(), start);
+    assert_eq!(reload.poll(Some(&url), at(9)), ReloadAction::Wait);
+    assert_eq!(reload.poll(Some(&url), at(10)), ReloadAction::Reload(1));
+    assert_eq!(reload.poll(Some(&url), at(19)), ReloadAction::Wait);
+    assert_eq!(reload.poll(Some(&url), at(21)), ReloadAction::Reload(2));
+    assert_eq!(reload.deadline(), at(31));
+
+    // Input postpones the reload until the user stopped interacting.
+    let mut reload = auto_reload(&url, AutoReloadOptions::default(), start);
+    reload.on_input(at(8));
+    let idle = at(8) + ACTIVITY_TIMEOUT;
+    assert_eq!(reload.deadline(), idle.max(at(10)));
+    assert_eq!(reload.poll(Some(&url), at(10)), ReloadAction::Wait);
+    assert_eq!(reload.poll(Some(&url), idle), ReloadAction::Reload(1));
+
+    let options = AutoReloadOptions {
+        skip_while_active: false,
+        ..Default::default()
+    };
+    let mut reload = auto_reload(&url, options, start);
+    reload.on_input(at(9));
+    assert_eq!(reload.poll(Some(&url), at(10)), ReloadAction::Reload(1));
+
+    // Navigating away cancels it, unless it follows any URL.
+    let mut reload = auto_reload(&url, AutoReloadOptions::default(), start);
+    assert_eq!(reload.poll(Some(&other), at(1)), ReloadAction::Cancel);
+    assert_eq!(reload.poll(None, at(1)), ReloadAction::Cancel);
+    let options = AutoReloadOptions {
+        same_url_only: false,
+        ..Default::default()
+    };
+    let mut reload = auto_reload(&url, options, start);
+    assert_eq!(reload.poll(Some(&other), at(10)), ReloadAction::Reload(1));
+
+    // The scroll position is saved before each reload and restored once after it.
+    let mut reload = auto_reload(&url, AutoReloadOptions::default(), start);
+    assert!(reload.reload_script().is_some());
+    assert!(reload.take_restore_script().is_none());
+    reload.poll(Some(&url), at(10));
+    assert!(reload.take_restore_script().is_some());
+    assert!(reload.take_restore_script().is_none());
+    let options = AutoReloadOptions {
+        preserve_scroll: false,
+        ..Default::default()
+    };
+    let mut reload = auto_reload(&url, options, start);
+    assert!(reload.reload_script().is_none());
+    reload.poll(Some(&url), at(10));
+    assert!(reload.take_restore_script().is_none());
+}
+
+/// Page tall enough to scroll, served over HTTP so it has session storage for its scroll
+/// position.
+const PAGE: &str = "<!DOCTYPE html><div style=\"height: 5000px\"></div>";
+
+/// Interval of the reloads of the page.
+const SHORT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Reloads which must happen before auto-reload is turned off.
+const RELOADS: u32 = 2;
+
+/// Time to wait for reloads after auto-reload is turned off.
+const AFTER_OFF: Duration = Duration::from_millis(1500);
+
+enum Step {
+    Starting,
+    Loading,
+    Reloading,
+    Off(Instant, u32),
+    Done,
+}
+
+struct Reloading {
+    url: ServoUrl,
+    step: Step,
+    reloads: Rc<Cell<u32>>,
+    scroll: Option<f64>,
+}
+
+impl Scenario for Reloading {
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        config: Config,
+    ) -> verso::Result<Verso> {
+        let reloads = self.reloads.clone();
+        VersoBuilder::from_config(config)
+            .on_auto_reload(move |_, count| reloads.set(count))
+            .build(event_loop, proxy)
+    }
+
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.current_raw_url() == Some(self.url.clone())
+                    && verso.load_status() == LoadStatus::Complete
+                {
+                    evaluate(verso, "scrollTo(0, 1000); return scrollY;");
+                    verso.set_auto_reload(Some(SHORT_INTERVAL), AutoReloadOptions::default());
+                    self.step = Step::Reloading;
+                }
+            }
+            Step::Reloading => {
+                if self.reloads.get() < RELOADS || verso.load_status() != LoadStatus::Complete {
+                    return;
+                }
+                // The scroll position is restored once the page loaded.
+                let WebDriverJSValue::Number(scroll) = evaluate(verso, "return scrollY;") else {
+                    return;
+                };
+                if scroll == 0.0 {
+                    return;
+                }
+                self.scroll = Some(scroll);
+                verso.set_auto_reload(None, AutoReloadOptions::default());
+                self.step = Step::Off(Instant::now(), self.reloads.get());
+            }
+            Step::Off(off, count) => {
+                assert_eq!(
+                    self.reloads.get(),
+                    count,
+                    "The page reloaded after auto-reload was turned off"
+                );
+                if off.elapsed() >= AFTER_OFF {
+                    verso.shutdown();
+                    self.step = Step::Done;
+                }
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn evaluate(verso: &Verso, script: &str) -> WebDriverJSValue {
+    verso
+        .evaluate_script(script)
+        .expect("There's no web view")
+        .recv()
+        .expect("Script didn't answer")
+        .expect("Script failed")
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    check_schedule();
+
+    let port = common::serve(|_| PAGE)?;
+    let scenario = Reloading {
+        url: ServoUrl::parse(&format!("http://127.0.0.1:{port}/"))?,
+        step: Step::Starting,
+        reloads: Rc::default(),
+        scroll: None,
+    };
+    let (scenario, _) = common::run(common::config(), scenario)?;
+
+    assert!(
+        scenario.reloads.get() >= RELOADS,
+        "The page wasn't reloaded"
+    );
+    assert_eq!(
+        scenario.scroll,
+        Some(1000.0),
+        "The scroll position wasn't kept"
+    );
+    Ok(())
+}
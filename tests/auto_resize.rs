@@ -0,0 +1,83 @@
+//! Windows fit synthetic content sizes up to their maximum size, and stop fitting content which
+//! follows the window size, while content growing over time keeps being followed.
+//!
+//! Fitting only compares sizes and instants, so this runs without an event loop.
+
+use std::time::{Duration, Instant};
+
+use verso::auto_resize::{AutoResize, AutoResizeStep, FEEDBACK_LIMIT, FEEDBACK_WINDOW};
+use winit::dpi::LogicalSize;
+
+fn main() {
+    let start = Instant::now();
+    let chrome = LogicalSize::new(0.0, 40.0);
+    let window = LogicalSize::new(800.0, 600.0);
+
+    let mut fit = AutoResize::new(LogicalSize::new(1000, 900));
+    let cases = [
+        ("short content", (800.0, 200.0), (800.0, 240.0)),
+        (
+            "content wider than the window",
+            (950.5, 200.0),
+            (951.0, 240.0),
+        ),
+        (
+            "content over the maximum size",
+            (1400.0, 2000.0),
+            (1000.0, 900.0),
+        ),
+        ("empty content", (800.0, 0.0), (800.0, 40.0)),
+    ];
+    for (i, (case, (width, height), expected)) in cases.into_iter().enumerate() {
+        // Far enough apart not to look like feedback.
+        let now = start + FEEDBACK_WINDOW * 2 * i as u32;
+        let content = LogicalSize::new(width, height);
+        assert_eq!(
+            fit.fit(content, window, chrome, now),
+            AutoResizeStep::Resize(LogicalSize::new(expected.0, expected.1)),
+            "{case}"
+        );
+    }
+    let now = start + FEEDBACK_WINDOW * 10;
+    assert_eq!(
+        fit.fit(LogicalSize::new(800.0, 560.0), window, chrome, now),
+        AutoResizeStep::Keep,
+        "content fitting the window"
+    );
+
+    // Content of 100vh with a margin outgrows every size it's given right away.
+    let mut fit = AutoResize::new(LogicalSize::new(4000, 4000));
+    let mut window = LogicalSize::new(800.0, 600.0);
+    let mut now = start;
+    let mut resizes = 0;
+    loop {
+        let content = LogicalSize::new(800.0, window.height - chrome.height + 20.0);
+        match fit.fit(content, window, chrome, now) {
+            AutoResizeStep::Resize(size) => {
+                window = size;
+                resizes += 1;
+            }
+            AutoResizeStep::Keep => panic!("content following the window fits it"),
+            AutoResizeStep::Stop => break,
+        }
+        assert!(
+            resizes <= FEEDBACK_LIMIT,
+            "the feedback loop isn't detected"
+        );
+        now += Duration::from_millis(60);
+    }
+    assert_eq!(resizes, FEEDBACK_LIMIT);
+
+    // Paragraphs appended once per second are followed for as long as they come.
+    let mut fit = AutoResize::new(LogicalSize::new(4000, 4000));
+    let mut window = LogicalSize::new(800.0, 100.0);
+    for paragraph in 1..=20 {
+        let now = start + Duration::from_secs(paragraph);
+        let content = LogicalSize::new(800.0, 40.0 * paragraph as f64);
+        match fit.fit(content, window, chrome, now) {
+            AutoResizeStep::Resize(size) => window = size,
+            step => panic!("paragraph {paragraph} gave {step:?}"),
+        }
+    }
+    assert_eq!(window, LogicalSize::new(800.0, 840.0));
+}
@@ -0,0 +1,203 @@
+//! Values of a submitted form are offered when its fields are focused again, except card numbers
+//! and passwords, and choosing one fills the field. Clearing the store removes them.
+//!
+//! Values are only stored and offered right after the user pressed a key, so a page focusing a
+//! field by itself gets none, and messages without the token of Verso's script are ignored.
+//!
+//! A local server serves the form, so the values are stored for its origin. Scripts which focus
+//! fields run in a timeout, since the page asks Verso for the values with `prompt()`, which Verso
+//! can only answer once the test returns to the event loop.
+
+mod common;
+
+use common::Scenario;
+use keyboard_types::{Code, Key, KeyState, KeyboardEvent};
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+use verso::{
+    config::Config, dispatch::USER_ACTIVATION_DURATION, webview::LoadStatus, Verso, VersoBuilder,
+};
+use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
+
+const PAGE: &str = "<!DOCTYPE html>
+<form>
+  <input id=\"name\" name=\"name\" type=\"text\">
+  <input id=\"email\" name=\"email\" type=\"email\">
+  <input id=\"number\" name=\"number\" type=\"text\">
+  <input id=\"password\" name=\"password\" type=\"password\">
+</form>
+<script>
+document.querySelector('form').addEventListener('submit', (event) => event.preventDefault());
+</script>";
+
+const SUBMIT: &str = "
+document.getElementById('name').value = 'Ferris';
+document.getElementById('email').value = 'ferris@example.com';
+document.getElementById('number').value = '4111 1111 1111 1111';
+document.getElementById('password').value = 'hunter2';
+document.querySelector('form').requestSubmit();
+return 'submitted';";
+
+/// Script focusing the field of the id, and returning the values listed under it. With `choose`,
+/// the first value is chosen, and the value of the field is returned too.
+fn focus(id: &str, choose: bool) -> String {
+    format!(
+        "
+const field = document.getElementById('{id}');
+field.blur();
+field.value = '';
+field.focus();
+const list = document.documentElement.lastElementChild;
+const items = list === document.body ? [] : [...list.children];
+if ({choose} && items.length) {{
+    items[0].dispatchEvent(new MouseEvent('mousedown', {{ bubbles: true, cancelable: true }}));
+    return [items.map((item) => item.textContent), field.value];
+}}
+return items.map((item) => item.textContent);"
+    )
+}
+
+/// Wrap the body of a script so it runs in a timeout, and stores its result as JSON in
+/// `window.result`.
+fn deferred(body: &str) -> String {
+    format!(
+        "window.result = null;
+setTimeout(() => {{ window.result = JSON.stringify((() => {{ {body} }})()); }});
+return true;"
+    )
+}
+
+/// Script asking for the values of the field like Verso's script does, without its token.
+const FORGED_QUERY: &str = "
+return prompt('VERSO_AUTOFILL_QUERY:' + JSON.stringify(['forged', location.origin, 'name']));";
+
+/// Press and release `Shift`, which activates the window for the user like any key press.
+fn press_key(verso: &mut Verso) {
+    let window = verso.current_window_id().expect("There's no window");
+    for state in [KeyState::Down, KeyState::Up] {
+        let event = KeyboardEvent {
+            state,
+            key: Key::Shift,
+            code: Code::ShiftLeft,
+            ..Default::default()
+        };
+        verso.handle_keyboard_event(window, event, None);
+    }
+}
+
+/// Let the activation of the last key press expire.
+fn wait(_: &mut Verso) {
+    std::thread::sleep(USER_ACTIVATION_DURATION);
+}
+
+fn clear(verso: &mut Verso) {
+    verso.clear_autofill();
+    press_key(verso);
+}
+
+/// Actions on the page, each with the script run after it, and the expected result of the
+/// script.
+fn actions() -> Vec<(fn(&mut Verso), String, &'static str)> {
+    vec![
+        (press_key, SUBMIT.to_string(), "\"submitted\""),
+        (press_key, focus("name", true), "[[\"Ferris\"],\"Ferris\"]"),
+        (press_key, focus("email", false), "[\"ferris@example.com\"]"),
+        (press_key, focus("number", false), "[]"),
+        (wait, focus("name", false), "[]"),
+        (press_key, FORGED_QUERY.to_string(), "null"),
+        (clear, focus("name", false), "[]"),
+    ]
+}
+
+enum Step {
+    Starting,
+    Loading,
+    Running(usize),
+    Done,
+}
+
+struct Autofill {
+    url: ServoUrl,
+    step: Step,
+    results: Vec<String>,
+    /// Whether the script of the current action is running.
+    running: bool,
+}
+
+impl Scenario for Autofill {
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        config: Config,
+    ) -> verso::Result<Verso> {
+        VersoBuilder::from_config(config)
+            .autofill(true)
+            .build(event_loop, proxy)
+    }
+
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.current_raw_url() == Some(self.url.clone())
+                    && verso.load_status() == LoadStatus::Complete
+                {
+                    self.step = Step::Running(0);
+                }
+            }
+            Step::Running(i) => {
+                let actions = actions();
+                if !self.running {
+                    let (act, script, _) = &actions[i];
+                    act(verso);
+                    evaluate(verso, &deferred(script));
+                    self.running = true;
+                    return;
+                }
+                let WebDriverJSValue::String(result) = evaluate(verso, "return window.result;")
+                else {
+                    return;
+                };
+                self.results.push(result);
+                self.running = false;
+                if i + 1 < actions.len() {
+                    self.step = Step::Running(i + 1);
+                } else {
+                    verso.shutdown();
+                    self.step = Step::Done;
+                }
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn evaluate(verso: &Verso, script: &str) -> WebDriverJSValue {
+    verso
+        .evaluate_script(script)
+        .expect("There's no web view")
+        .recv()
+        .expect("Script didn't answer")
+        .expect("Script failed")
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let port = common::serve(|_| PAGE)?;
+    let scenario = Autofill {
+        url: ServoUrl::parse(&format!("http://127.0.0.1:{port}/"))?,
+        step: Step::Starting,
+        results: Vec::new(),
+        running: false,
+    };
+    let (scenario, _) = common::run(common::config(), scenario)?;
+
+    let expected: Vec<_> = actions().into_iter().map(|(_, _, result)| result).collect();
+    assert_eq!(scenario.results, expected, "The autofill cycle went wrong");
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Timers of pages in hidden windows are throttled to the clamp, run at full rate again once the
+//! window is visible, and aren't throttled with a clamp of 0.
+//!
+//! The page counts the runs of a 10 ms interval, and hiding the window is simulated with Winit's
+//! `Occluded` event.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+use verso::{webview::LoadStatus, Verso};
+use winit::event::WindowEvent;
+
+/// Time the interval runs in each phase.
+const PHASE: Duration = Duration::from_millis(2500);
+
+/// Phases of the test, each counting the interval runs for [`PHASE`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    /// Hidden with the default clamp of 1 second.
+    Hidden,
+    /// Visible again.
+    Visible,
+    /// Hidden with throttling disabled.
+    Unthrottled,
+}
+
+enum Step {
+    Starting,
+    Loading,
+    Counting(Phase, Instant),
+    Done,
+}
+
+struct BackgroundTimers {
+    url: ServoUrl,
+    step: Step,
+    ticks: Vec<(Phase, f64)>,
+}
+
+impl BackgroundTimers {
+    /// Set whether the window is hidden, reset the count and start counting the phase.
+    fn start_phase(&mut self, verso: &mut Verso, phase: Phase, occluded: bool) {
+        let window_id = verso.current_window_id().expect("Window is gone");
+        verso.handle_winit_window_event(window_id, WindowEvent::Occluded(occluded));
+        evaluate(verso, "window.ticks = 0; return 0;");
+        self.step = Step::Counting(phase, Instant::now());
+    }
+}
+
+impl Scenario for BackgroundTimers {
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.current_raw_url() == Some(self.url.clone())
+                    && verso.load_status() == LoadStatus::Complete
+                {
+                    self.start_phase(verso, Phase::Hidden, true);
+                }
+            }
+            Step::Counting(phase, start) => {
+                if start.elapsed() < PHASE {
+                    return;
+                }
+                let WebDriverJSValue::Number(ticks) = evaluate(verso, "return window.ticks;")
+                else {
+                    panic!("The page has no count of interval runs");
+                };
+                self.ticks.push((phase, ticks));
+                match phase {
+                    Phase::Hidden => self.start_phase(verso, Phase::Visible, false),
+                    Phase::Visible => {
+                        verso.set_background_timer_throttle(0);
+                        self.start_phase(verso, Phase::Unthrottled, true);
+                    }
+                    Phase::Unthrottled => {
+                        verso.shutdown();
+                        self.step = Step::Done;
+                    }
+                }
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn evaluate(verso: &Verso, script: &str) -> WebDriverJSValue {
+    verso
+        .evaluate_script(script)
+        .expect("There's no web view")
+        .recv()
+        .expect("Script didn't answer")
+        .expect("Script failed")
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let scenario = BackgroundTimers {
+        url: common::fixture("timers.html"),
+        step: Step::Starting,
+        ticks: Vec::new(),
+    };
+    let (scenario, _) = common::run(common::config(), scenario)?;
+
+    let [(Phase::Hidden, hidden), (Phase::Visible, visible), (Phase::Unthrottled, unthrottled)] =
+        scenario.ticks[..]
+    else {
+        panic!("The phases didn't run in order: {:?}", scenario.ticks);
+    };
+    // The interval runs once when it was scheduled before hiding, then once per second.
+    assert!(
+        hidden <= 4.0,
+        "The interval ran {hidden} times in 2.5 s while hidden"
+    );
+    // At full rate it runs up to 250 times, and slow machines still run it far more than hidden.
+    assert!(
+        visible >= 20.0,
+        "The interval ran {visible} times in 2.5 s once visible"
+    );
+    assert!(
+        unthrottled >= 20.0,
+        "The interval ran {unthrottled} times in 2.5 s while hidden without throttling"
+    );
+    Ok(())
+}
@@ -0,0 +1,281 @@
+//! Validation of the options of the builder, which rejects each invalid option and incompatible
+//! combination, and normalizes the options it accepts.
+//!
+//! Validating doesn't start Verso, so this runs without an event loop.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, TcpListener},
+    path::Path,
+};
+
+use verso::{
+    caret::ContentInsets,
+    config::{Config, FontConfig, IceServer, WebRtcConfig, WindowConfig},
+    features::{Feature, FeaturePolicy, ALL_ORIGINS},
+    policies::Policies,
+    sandbox::SandboxMode,
+    Error, VersoBuilder,
+};
+use winit::dpi::PhysicalSize;
+
+fn resources() -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("resources")
+}
+
+/// A builder with the crate's resources and without a config directory.
+fn builder() -> VersoBuilder {
+    let mut config = Config::new(resources());
+    config.opts.config_dir = None;
+    VersoBuilder::from_config(config)
+}
+
+#[track_caller]
+fn assert_valid(case: &str, builder: VersoBuilder) -> Config {
+    builder
+        .into_config()
+        .unwrap_or_else(|e| panic!("{case} was rejected: {e}"))
+}
+
+#[track_caller]
+fn assert_invalid(case: &str, builder: VersoBuilder) {
+    match builder.into_config() {
+        Err(Error::InvalidConfig(_)) => {}
+        Err(e) => panic!("{case} was rejected with another error: {e}"),
+        Ok(_) => panic!("{case} was accepted"),
+    }
+}
+
+fn ice_server(url: &str) -> IceServer {
+    IceServer {
+        urls: vec![url.to_string()],
+        ..IceServer::default()
+    }
+}
+
+fn main() {
+    assert_valid("the default options", builder());
+    assert_invalid(
+        "a missing resources directory",
+        VersoBuilder::new(resources().join("missing")),
+    );
+
+    check_window();
+    check_webrtc();
+    check_fonts();
+    check_sandbox();
+    check_content_insets();
+    check_devtools();
+    check_partition();
+    check_feature_policies();
+    check_dns_overrides();
+}
+
+fn check_window() {
+    for size in [
+        PhysicalSize::new(0, 600),
+        PhysicalSize::new(800, 0),
+        PhysicalSize::new(0, 0),
+    ] {
+        assert_invalid(
+            &format!("a headless window of {size:?}"),
+            builder().window(WindowConfig::default().headless(size)),
+        );
+    }
+    assert_invalid(
+        "a fullscreen headless window",
+        builder().window(
+            WindowConfig::default()
+                .headless(PhysicalSize::new(800, 600))
+                .fullscreen(true),
+        ),
+    );
+    let config = assert_valid(
+        "a headless window",
+        builder().window(WindowConfig::default().headless(PhysicalSize::new(800, 600))),
+    );
+    assert_eq!(config.window.headless, Some(PhysicalSize::new(800, 600)));
+    assert_valid(
+        "a fullscreen window",
+        builder().window(WindowConfig::default().fullscreen(true)),
+    );
+}
+
+fn check_webrtc() {
+    let disabled = WebRtcConfig::default();
+    assert_invalid(
+        "hidden candidates with WebRTC disabled",
+        builder().webrtc(WebRtcConfig {
+            hide_non_relay_candidates: true,
+            ..disabled.clone()
+        }),
+    );
+    assert_invalid(
+        "ICE servers with WebRTC disabled",
+        builder().webrtc(WebRtcConfig {
+            ice_servers: vec![ice_server("stun:stun.example.com")],
+            ..disabled.clone()
+        }),
+    );
+    assert_invalid(
+        "an ICE server without URL",
+        builder().webrtc(WebRtcConfig {
+            enabled: true,
+            ice_servers: vec![ice_server("stun:stun.example.com"), IceServer::default()],
+            hide_non_relay_candidates: false,
+        }),
+    );
+    assert_valid(
+        "hidden candidates with a TURN server",
+        builder().webrtc(WebRtcConfig {
+            enabled: true,
+            ice_servers: vec![ice_server("turn:turn.example.com:3478")],
+            hide_non_relay_candidates: true,
+        }),
+    );
+}
+
+fn check_fonts() {
+    assert_invalid(
+        "a zero default font size",
+        builder().fonts(FontConfig {
+            default_size: Some(0),
+            ..FontConfig::default()
+        }),
+    );
+    assert_invalid(
+        "a zero default monospace font size",
+        builder().fonts(FontConfig {
+            default_monospace_size: Some(0),
+            ..FontConfig::default()
+        }),
+    );
+    assert_valid(
+        "font sizes",
+        builder().fonts(FontConfig {
+            default_size: Some(20),
+            default_monospace_size: Some(15),
+            ..FontConfig::default()
+        }),
+    );
+}
+
+fn check_sandbox() {
+    let enabled = builder().sandbox(SandboxMode::Enabled);
+    if verso::sandbox::is_supported() {
+        assert_valid("a sandbox where it's supported", enabled);
+    } else {
+        assert_invalid("a sandbox where it isn't supported", enabled);
+    }
+    assert_valid("no sandbox", builder().sandbox(SandboxMode::Disabled));
+}
+
+fn check_content_insets() {
+    for inset in [-1.0, f32::NAN, f32::INFINITY] {
+        assert_invalid(
+            &format!("a content inset of {inset}"),
+            builder().content_insets(ContentInsets {
+                left: inset,
+                ..ContentInsets::default()
+            }),
+        );
+    }
+    assert_valid(
+        "content insets",
+        builder().content_insets(ContentInsets {
+            top: 40.0,
+            right: 0.0,
+            bottom: 12.5,
+            left: 0.0,
+        }),
+    );
+}
+
+fn check_devtools() {
+    let mut config = Config::new(resources());
+    config.opts.config_dir = None;
+    config.policies = Policies::parse(r#"{ "disable_devtools": true }"#);
+    match VersoBuilder::from_config(config)
+        .enable_devtools(0)
+        .into_config()
+    {
+        Err(Error::DisabledByPolicy(_)) => {}
+        Err(e) => panic!("Developer tools disabled by policy were rejected with: {e}"),
+        Ok(_) => panic!("Developer tools disabled by policy were accepted"),
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("No free port");
+    let port = listener
+        .local_addr()
+        .expect("Listener has no address")
+        .port();
+    assert!(
+        matches!(
+            builder().enable_devtools(port).into_config(),
+            Err(Error::IoError(_))
+        ),
+        "Developer tools on a port in use were accepted"
+    );
+    drop(listener);
+
+    let config = assert_valid(
+        "developer tools on a free port",
+        builder().enable_devtools(0),
+    );
+    assert!(config.opts.devtools_server_enabled);
+    assert_ne!(config.opts.devtools_port, 0, "No port was picked");
+}
+
+fn check_partition() {
+    for name in ["", "work/profile", "../work", "work profile"] {
+        let mut config = Config::new(resources());
+        config.opts.config_dir = Some(std::env::temp_dir());
+        assert_invalid(
+            &format!("the partition name {name:?}"),
+            VersoBuilder::from_config(config).partition(name),
+        );
+    }
+    assert_invalid(
+        "a partition without config directory",
+        builder().partition("work"),
+    );
+    let mut config = Config::new(resources());
+    config.opts.config_dir = Some(std::env::temp_dir());
+    let config = assert_valid(
+        "a partition",
+        VersoBuilder::from_config(config).partition("work-2_b"),
+    );
+    assert_eq!(config.partition.as_deref(), Some("work-2_b"));
+}
+
+fn check_feature_policies() {
+    let policy = FeaturePolicy::default().deny(Feature::Camera);
+    assert_invalid(
+        "a feature policy for an invalid origin",
+        builder().feature_policy("not an origin", policy.clone()),
+    );
+    let config = assert_valid(
+        "feature policies",
+        builder()
+            .feature_policy("https://Example.com/path?query", policy.clone())
+            .feature_policy(ALL_ORIGINS, policy.clone()),
+    );
+    let origins: Vec<&str> = config.feature_policies.keys().map(String::as_str).collect();
+    assert_eq!(origins, [ALL_ORIGINS, "https://example.com"]);
+}
+
+fn check_dns_overrides() {
+    let local = IpAddr::from(Ipv4Addr::LOCALHOST);
+    for host in ["*.example.com", "", "staging example.com", "example..com"] {
+        assert_invalid(
+            &format!("the DNS override {host:?}"),
+            builder().dns_overrides(HashMap::from([(host.to_string(), local)])),
+        );
+    }
+    let config = assert_valid(
+        "DNS overrides",
+        builder().dns_overrides(HashMap::from([("Staging.Example.com.".to_string(), local)])),
+    );
+    let hosts: Vec<&str> = config.dns_overrides.keys().map(String::as_str).collect();
+    assert_eq!(hosts, ["staging.example.com"]);
+}
@@ -0,0 +1,171 @@
+//! A CDP client connects with the token of the server, while handshakes without it or from web
+//! pages are rejected. The client then evaluates statements and clicks at a position in CSS
+//! pixels of the page.
+
+mod common;
+
+use std::{
+    net::TcpStream,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use common::{Scenario, TIMEOUT};
+use serde_json::{json, Value};
+use servo_url::ServoUrl;
+use tungstenite::{client::IntoClientRequest, stream::MaybeTlsStream, Message, WebSocket};
+use verso::{config::Config, Verso};
+use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
+
+type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+/// Return true if the handshake to the URL is rejected with `403 Forbidden`.
+fn rejected(url: &str, origin: Option<&str>) -> bool {
+    let mut request = url.into_client_request().expect("The URL is valid");
+    if let Some(origin) = origin {
+        request
+            .headers_mut()
+            .insert("Origin", origin.parse().expect("The origin is valid"));
+    }
+    match tungstenite::connect(request) {
+        Err(tungstenite::Error::Http(response)) => response.status() == 403,
+        _ => false,
+    }
+}
+
+/// Call the method and return its result, or its error message.
+fn call(socket: &mut Socket, id: u64, method: &str, params: Value) -> Result<Value, String> {
+    let request = json!({ "id": id, "method": method, "params": params });
+    socket
+        .send(Message::Text(request.to_string()))
+        .expect("The request is sent");
+    loop {
+        let Message::Text(text) = socket.read().expect("The reply is received") else {
+            continue;
+        };
+        let reply: Value = serde_json::from_str(&text).expect("The reply is JSON");
+        if reply["id"] != id {
+            continue;
+        }
+        return match reply.get("error") {
+            Some(error) => Err(error["message"].to_string()),
+            None => Ok(reply["result"].clone()),
+        };
+    }
+}
+
+/// Evaluate the expression until its value satisfies the condition.
+fn wait_for(socket: &mut Socket, what: &str, expression: &str, condition: impl Fn(&Value) -> bool) {
+    let start = Instant::now();
+    for id in 1000.. {
+        let params = json!({ "expression": expression });
+        if let Ok(result) = call(socket, id, "Runtime.evaluate", params) {
+            if condition(&result["result"]["value"]) {
+                return;
+            }
+        }
+        assert!(start.elapsed() < TIMEOUT, "{what} within {TIMEOUT:?}");
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn drive(url: String, page: ServoUrl) {
+    let root = url
+        .split("/devtools/")
+        .next()
+        .expect("The URL has a path")
+        .to_string();
+    assert!(rejected(&format!("{root}/"), None), "The root is accepted");
+    assert!(
+        rejected(&format!("{root}/devtools/browser/wrong"), None),
+        "A wrong token is accepted"
+    );
+    assert!(
+        rejected(&url, Some("https://attacker.example")),
+        "A web page is accepted"
+    );
+
+    let (mut socket, _) = tungstenite::connect(&url).expect("The client connects");
+    let error = call(&mut socket, 1, "Browser.missing", json!({}));
+    assert!(error.is_err(), "A missing method succeeded");
+
+    call(
+        &mut socket,
+        2,
+        "Page.navigate",
+        json!({ "url": page.as_str() }),
+    )
+    .expect("The page is navigated");
+    wait_for(&mut socket, "The page loads", "location.href", |href| {
+        href == page.as_str()
+    });
+
+    let answer = call(
+        &mut socket,
+        3,
+        "Runtime.evaluate",
+        json!({ "expression": "const answer = 40;\nanswer + 2 // The answer" }),
+    )
+    .expect("The statements are evaluated");
+    assert_eq!(answer["result"]["value"], 42);
+
+    call(
+        &mut socket,
+        4,
+        "Runtime.evaluate",
+        json!({ "expression": "addEventListener('click', (e) => window.clicked = [e.clientX, e.clientY])" }),
+    )
+    .expect("The listener is added");
+    for (id, event_type) in [(5, "mousePressed"), (6, "mouseReleased")] {
+        let params = json!({ "type": event_type, "x": 30, "y": 40, "button": "left" });
+        call(&mut socket, id, "Input.dispatchMouseEvent", params).expect("The mouse is clicked");
+    }
+    wait_for(
+        &mut socket,
+        "The page is clicked at the CSS position",
+        "JSON.stringify(window.clicked)",
+        |clicked| clicked == "[30,40]",
+    );
+}
+
+struct Cdp {
+    page: ServoUrl,
+    client: Option<JoinHandle<()>>,
+}
+
+impl Scenario for Cdp {
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        config: Config,
+    ) -> verso::Result<Verso> {
+        let mut verso = Verso::new(event_loop, proxy, config)?;
+        let url = verso
+            .enable_remote_debugging(0)
+            .expect("The CDP server starts");
+        let page = self.page.clone();
+        self.client = Some(std::thread::spawn(move || drive(url, page)));
+        Ok(verso)
+    }
+
+    fn step(&mut self, verso: &mut Verso) {
+        if self.client.as_ref().is_some_and(JoinHandle::is_finished) {
+            verso.shutdown();
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let scenario = Cdp {
+        page: common::fixture("remote_control.html"),
+        client: None,
+    };
+    let (mut scenario, _) = common::run(common::config(), scenario)?;
+
+    let client = scenario.client.take().expect("The CDP server started");
+    if let Err(panic) = client.join() {
+        std::panic::resume_unwind(panic);
+    }
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! Harness shared by the integration tests which drive Verso through an event loop.
+
+// Each test uses part of the harness only.
+#![allow(dead_code)]
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use servo_url::ServoUrl;
+use verso::{config::Config, Verso};
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
+    window::WindowId,
+};
+
+/// Time a scenario has to finish, from the start of the event loop.
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest time the event loop waits, so scenarios can wait for time to pass on idle pages.
+const TICK: Duration = Duration::from_millis(100);
+
+/// The root of the crate.
+pub fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// A config with the crate's resources and without a config directory.
+pub fn config() -> Config {
+    let mut config = Config::new(root().join("resources"));
+    config.opts.config_dir = None;
+    config
+}
+
+/// The URL of a file in `tests/fixtures`.
+pub fn fixture(name: &str) -> ServoUrl {
+    ServoUrl::from_file_path(root().join("tests/fixtures").join(name))
+        .expect("Fixture path isn't absolute")
+}
+
+/// A test driving Verso from the event loop.
+pub trait Scenario {
+    /// Start Verso once the event loop is resumed. The scenario ends if it fails.
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        config: Config,
+    ) -> verso::Result<Verso> {
+        Verso::new(event_loop, proxy, config)
+    }
+
+    /// Advance the scenario after Verso handled Servo's messages. The scenario ends when it
+    /// shuts Verso down.
+    fn step(&mut self, verso: &mut Verso);
+}
+
+struct Harness<S> {
+    proxy: EventLoopProxy<()>,
+    config: Option<Config>,
+    verso: Option<Verso>,
+    error: Option<verso::Error>,
+    scenario: S,
+    start: Instant,
+}
+
+impl<S: Scenario> Harness<S> {
+    fn step(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(verso) = &mut self.verso else {
+            return;
+        };
+        verso.handle_servo_messages(event_loop);
+        assert!(
+            self.start.elapsed() < TIMEOUT,
+            "The test didn't finish within {TIMEOUT:?}"
+        );
+        self.scenario.step(verso);
+    }
+}
+
+impl<S: Scenario> ApplicationHandler for Harness<S> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(config) = self.config.take() {
+            match self.scenario.start(event_loop, self.proxy.clone(), config) {
+                Ok(verso) => self.verso = Some(verso),
+                Err(e) => {
+                    self.error = Some(e);
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if let Some(verso) = &mut self.verso {
+            verso.handle_winit_window_event(window_id, event);
+        }
+        self.step(event_loop);
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, _: ()) {
+        self.step(event_loop);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.step(event_loop);
+        let tick = Instant::now() + TICK;
+        match event_loop.control_flow() {
+            ControlFlow::Wait => event_loop.set_control_flow(ControlFlow::WaitUntil(tick)),
+            ControlFlow::WaitUntil(deadline) if deadline > tick => {
+                event_loop.set_control_flow(ControlFlow::WaitUntil(tick))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Run the scenario until Verso shuts down, and return it with Verso.
+pub fn run<S: Scenario>(
+    config: Config,
+    scenario: S,
+) -> Result<(S, Verso), Box<dyn std::error::Error>> {
+    let event_loop = EventLoop::new()?;
+    let mut harness = Harness {
+        proxy: event_loop.create_proxy(),
+        config: Some(config),
+        verso: None,
+        error: None,
+        scenario,
+        start: Instant::now(),
+    };
+    event_loop.run_app(&mut harness)?;
+    if let Some(e) = harness.error {
+        return Err(e.into());
+    }
+    let verso = harness.verso.ok_or("Verso didn't start")?;
+    Ok((harness.scenario, verso))
+}
+
+/// A request received by [`serve`].
+pub struct Request {
+    pub path: String,
+    /// Headers with lowercase names.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Serve HTTP on a local port, answering each request with the page `respond` returns, and
+/// return the port. Pages are served without a charset, and answers allow cross-origin requests
+/// with any header.
+pub fn serve<B: Into<Vec<u8>>>(
+    respond: impl Fn(&Request) -> B + Send + 'static,
+) -> std::io::Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = answer(stream, &respond);
+        }
+    });
+    Ok(port)
+}
+
+fn answer<B: Into<Vec<u8>>>(
+    stream: TcpStream,
+    respond: &impl Fn(&Request) -> B,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let path = request_line
+        .split(' ')
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.trim_end().split_once(':') {
+            headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    let request = Request { path, headers };
+    let allowed = request
+        .header("access-control-request-headers")
+        .unwrap_or_default()
+        .to_string();
+    let body = respond(&request).into();
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\
+        Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Headers: {allowed}\r\n\
+        Connection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(&body)
+}
@@ -0,0 +1,29 @@
+//! The `verso` binary started as a content process with a token no main process gave exits with
+//! an error instead of panicking.
+//!
+//! The content process fails before opening a window, so this runs without a display.
+
+use std::process::Command;
+
+use verso::sandbox::CONTENT_PROCESS_ARG;
+
+fn main() {
+    let output = Command::new(env!("CARGO_BIN_EXE_verso"))
+        .args([CONTENT_PROCESS_ARG, "not-a-token"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("Failed to run verso");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        !output.status.success(),
+        "The content process succeeded without a main process"
+    );
+    assert!(
+        !stderr.contains("panicked"),
+        "The content process panicked: {stderr}"
+    );
+    assert!(
+        stderr.contains("ContentProcessError"),
+        "The content process failed with another error: {stderr}"
+    );
+}
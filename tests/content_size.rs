@@ -0,0 +1,113 @@
+//! A page appending a paragraph on a timer reports its content size after each one, growing
+//! every time, while its window fits the content without detecting a feedback loop.
+
+mod common;
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use common::Scenario;
+use servo_url::ServoUrl;
+use verso::{auto_resize::ContentSize, config::Config, webview::LoadStatus, Verso, VersoBuilder};
+use winit::{
+    dpi::LogicalSize,
+    event_loop::{ActiveEventLoop, EventLoopProxy},
+};
+
+/// Number of paragraphs the page appends.
+const APPENDED: usize = 5;
+
+/// Time for the last report to arrive after the page is done.
+const SETTLE: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+struct Reports {
+    sizes: RefCell<Vec<ContentSize>>,
+    done: Cell<bool>,
+    stopped: Cell<bool>,
+}
+
+struct GrowingContent {
+    url: ServoUrl,
+    reports: Rc<Reports>,
+    loading: bool,
+    done_at: Option<Instant>,
+}
+
+impl Scenario for GrowingContent {
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        config: Config,
+    ) -> verso::Result<Verso> {
+        let sizes = self.reports.clone();
+        let done = self.reports.clone();
+        let stopped = self.reports.clone();
+        VersoBuilder::from_config(config)
+            .on_content_size_changed(move |_, size| sizes.sizes.borrow_mut().push(size))
+            .on_tab_updated(move |_, info| {
+                if info.title.as_deref() == Some("Done") {
+                    done.done.set(true);
+                }
+            })
+            .on_auto_resize_stopped(move |_| stopped.stopped.set(true))
+            .build(event_loop, proxy)
+    }
+
+    fn step(&mut self, verso: &mut Verso) {
+        if !self.loading {
+            if verso.current_webview_id().is_some() {
+                verso.auto_resize_window(Some(LogicalSize::new(1000, 2000)));
+                verso.load_url(self.url.clone());
+                self.loading = true;
+            }
+            return;
+        }
+        if self.done_at.is_none()
+            && self.reports.done.get()
+            && verso.load_status() == LoadStatus::Complete
+        {
+            self.done_at = Some(Instant::now());
+        }
+        if self.done_at.is_some_and(|at| at.elapsed() > SETTLE) {
+            verso.shutdown();
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let reports = Rc::new(Reports::default());
+    let scenario = GrowingContent {
+        url: common::fixture("content_size.html"),
+        reports: reports.clone(),
+        loading: false,
+        done_at: None,
+    };
+    common::run(common::config(), scenario)?;
+
+    let sizes = reports.sizes.borrow();
+    assert!(
+        sizes.len() > APPENDED,
+        "Only {} content sizes were reported",
+        sizes.len()
+    );
+    // Earlier reports may come from the page loaded before the fixture.
+    let appended = &sizes[sizes.len() - APPENDED - 1..];
+    for pair in appended.windows(2) {
+        assert!(
+            pair[1].height > pair[0].height,
+            "The content didn't grow from {:?} to {:?}",
+            pair[0],
+            pair[1]
+        );
+    }
+    assert!(
+        !reports.stopped.get(),
+        "Growing content was taken for a feedback loop"
+    );
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! The `verso` binary writes a crash report when it panics with `--crash-test`, in the directory
+//! of [`CRASH_REPORTS_ENV`] when it's set.
+//!
+//! The binary panics before opening a window, so this runs without a display.
+
+use std::{fs, path::Path, process::Command};
+
+use verso::crash::CRASH_REPORTS_ENV;
+
+/// Run the binary with `--crash-test` and return its standard error.
+fn crash(reports: Option<&Path>) -> String {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_verso"));
+    command
+        .arg("--crash-test")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env_remove(CRASH_REPORTS_ENV);
+    if let Some(dir) = reports {
+        command.env(CRASH_REPORTS_ENV, dir);
+    }
+    let output = command.output().expect("Failed to run verso");
+    assert!(!output.status.success(), "Verso didn't crash");
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn main() -> std::io::Result<()> {
+    let dir = std::env::temp_dir().join(format!("verso-crash-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let stderr = crash(None);
+    let default_report = stderr
+        .lines()
+        .find_map(|line| line.strip_prefix("Verso wrote a crash report to "))
+        .unwrap_or_else(|| panic!("No crash report was written by default: {stderr}"));
+    fs::remove_dir_all(default_report)?;
+
+    let stderr = crash(Some(&dir));
+    assert!(
+        stderr.contains("Verso wrote a crash report"),
+        "No crash report was written: {stderr}"
+    );
+    let reports: Vec<_> = fs::read_dir(&dir)?.collect::<Result<_, _>>()?;
+    assert_eq!(reports.len(), 1, "The crash wrote several reports");
+    let report = reports[0].path();
+    let name = report.file_name().unwrap_or_default().to_string_lossy();
+    assert!(name.starts_with("crash-"), "Unexpected report name {name}");
+    let text = fs::read_to_string(report.join("report.txt"))?;
+    for expected in [
+        "Panic: ",
+        "Verso crashes deliberately for --crash-test",
+        "Version: ",
+        "Renderer: unknown",
+        "Backtrace:",
+    ] {
+        assert!(
+            text.contains(expected),
+            "The report has no {expected:?}: {text}"
+        );
+    }
+    assert!(
+        report.join("log.txt").is_file(),
+        "The report has no log lines"
+    );
+
+    fs::remove_dir_all(&dir)
+}
@@ -0,0 +1,72 @@
+//! Selection of the web views discarded to save memory, over simulated patterns of focusing
+//! windows, and the number of web views discarded by a policy.
+//!
+//! Selection only compares focus times, so this runs without an event loop.
+
+use std::time::{Duration, Instant};
+
+use verso::discard::{victims, Candidate, DiscardPolicy};
+
+/// Windows 0 to `count - 1`, focused in the order of `focus`, the last one being focused now.
+fn focused_in_order(count: usize, focus: &[usize]) -> Vec<Candidate<usize>> {
+    let start = Instant::now();
+    let mut candidates: Vec<_> = (0..count)
+        .map(|key| Candidate {
+            key,
+            last_focused: start,
+            focused: false,
+            pinned: false,
+            alive: true,
+        })
+        .collect();
+    for (i, &key) in focus.iter().enumerate() {
+        candidates[key].last_focused = start + Duration::from_secs(i as u64 + 1);
+    }
+    if let Some(&current) = focus.last() {
+        candidates[current].focused = true;
+    }
+    candidates
+}
+
+fn main() {
+    // Least recently focused first, and never the focused window.
+    let candidates = focused_in_order(4, &[0, 1, 2, 3]);
+    assert_eq!(victims(&candidates), [0, 1, 2]);
+
+    // Focusing a window again moves it to the back.
+    let candidates = focused_in_order(4, &[0, 1, 2, 3, 0, 2]);
+    assert_eq!(victims(&candidates), [1, 3, 0]);
+
+    // Windows never focused since they opened go first.
+    let candidates = focused_in_order(4, &[2, 3]);
+    assert_eq!(victims(&candidates), [0, 1, 2]);
+
+    // Pinned windows and windows without a live web view are kept.
+    let mut candidates = focused_in_order(5, &[0, 1, 2, 3, 4]);
+    candidates[1].pinned = true;
+    candidates[2].alive = false;
+    assert_eq!(victims(&candidates), [0, 3]);
+
+    // A single focused window has nothing to discard.
+    assert!(victims(&focused_in_order(1, &[0])).is_empty());
+    assert!(victims::<usize>(&[]).is_empty());
+
+    let limit = DiscardPolicy {
+        max_alive: Some(3),
+        discard_on_memory_pressure: false,
+    };
+    for (alive, pressure, count) in [(2, false, 0), (3, false, 0), (5, false, 2), (5, true, 2)] {
+        assert_eq!(
+            limit.discard_count(alive, pressure),
+            count,
+            "{alive} web views with a limit of 3, under pressure {pressure}"
+        );
+    }
+    let pressure = DiscardPolicy {
+        max_alive: None,
+        discard_on_memory_pressure: true,
+    };
+    assert_eq!(pressure.discard_count(10, false), 0);
+    assert_eq!(pressure.discard_count(10, true), 1);
+    assert_eq!(DiscardPolicy::default().discard_count(10, true), 0);
+}
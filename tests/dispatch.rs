@@ -0,0 +1,709 @@
+//! Every embedder message Verso handles dispatches to the expected actions, for the default
+//! configuration and a permissive one.
+//!
+//! Dispatching doesn't touch the window or the engine, so this runs without an event loop.
+
+use std::path::Path;
+
+use base::id::{PipelineId, PipelineNamespace, PipelineNamespaceId, WebViewId};
+use embedder_traits::{
+    CompositorEventVariant, Cursor, EmbedderMsg, InputMethodType, PermissionName, PermissionPrompt,
+    PromptDefinition, PromptOrigin,
+};
+use ipc_channel::ipc;
+use servo_url::ServoUrl;
+use verso::{
+    config::{AutoplayPolicy, Config, WindowControlPolicy},
+    contrast::ForcedColors,
+    dispatch::{dispatch, dispatch_global, Action, DispatchState},
+    downloads::MAX_DOWNLOADS_PER_PAGE,
+    features::{Feature, FeaturePolicy},
+    policies::Policies,
+    webview::CrashRestore,
+};
+use webrender_api::units::{DeviceIntPoint, DeviceIntRect, DeviceIntSize};
+
+/// Name of the action, with the event, status or constellation message it holds.
+fn name(action: &Action) -> String {
+    let debug = format!("{action:?}");
+    let variant: String = debug
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect();
+    match action {
+        Action::RunScript(what, _) => format!("RunScript({what})"),
+        Action::PushEvent(_) | Action::SetLoadStatus(_) | Action::Send(_) => {
+            let inner: String = debug[variant.len() + 1..]
+                .chars()
+                .take_while(char::is_ascii_alphanumeric)
+                .collect();
+            format!("{variant}({inner})")
+        }
+        _ => variant,
+    }
+}
+
+/// A `prompt()` call of the page with the message.
+fn input(message: &str) -> EmbedderMsg {
+    let (sender, _) = ipc::channel().expect("Failed to create IPC channel");
+    EmbedderMsg::Prompt(
+        PromptDefinition::Input(message.to_string(), String::new(), sender),
+        PromptOrigin::Untrusted,
+    )
+}
+
+/// A permission request of the page.
+fn permission(name: PermissionName) -> EmbedderMsg {
+    let (sender, _) = ipc::channel().expect("Failed to create IPC channel");
+    EmbedderMsg::PromptPermission(PermissionPrompt::Request(name), sender)
+}
+
+fn alert() -> EmbedderMsg {
+    let (sender, _) = ipc::channel().expect("Failed to create IPC channel");
+    EmbedderMsg::Prompt(
+        PromptDefinition::Alert("Hello".to_string(), sender),
+        PromptOrigin::Untrusted,
+    )
+}
+
+fn main() {
+    PipelineNamespace::install(PipelineNamespaceId(1));
+    let id = WebViewId::new();
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    let mut config = Config::new(root.join("resources"));
+    // The OS setting would decide otherwise.
+    config.forced_colors = ForcedColors::None;
+    let mut permissive = config.clone();
+    permissive.autoplay = AutoplayPolicy::Allow;
+    permissive.autofill = true;
+    permissive.hold_dialogs = true;
+    permissive.crash_recovery.max_restores = 1;
+    permissive.window_control = WindowControlPolicy {
+        allow_move: true,
+        allow_resize: true,
+        allow_fullscreen: true,
+    };
+
+    let mut no_downloads = config.clone();
+    no_downloads.policies = Policies::parse(r#"{ "disable_downloads": true }"#);
+
+    let mut no_devices = config.clone();
+    no_devices.feature_policies.insert(
+        "https://example.com".to_string(),
+        FeaturePolicy::default()
+            .deny(Feature::Camera)
+            .deny(Feature::Bluetooth),
+    );
+
+    let url = ServoUrl::parse("https://example.com/").unwrap();
+    let favicon = ServoUrl::parse("https://example.com/favicon.ico").unwrap();
+    let state = DispatchState {
+        config: &config,
+        url: Some(&url),
+        title: None,
+        user_gesture: false,
+        user_activation: false,
+        token: "token",
+        crash_restore: None,
+        restores: 0,
+        downloads: 0,
+        occluded: false,
+        webview_rect: Some(DeviceIntRect::from_size(DeviceIntSize::new(800, 600))),
+        report_load_errors: false,
+        report_content_size: false,
+    };
+    let allowed = DispatchState {
+        config: &permissive,
+        ..state
+    };
+    let activated = DispatchState {
+        user_activation: true,
+        ..allowed
+    };
+    let crashed = CrashRestore::Crashed(None);
+    let reloading = CrashRestore::Reloading(Some(r#"{"x":0,"y":0,"fields":[]}"#.to_string()));
+    let resuming = CrashRestore::Resuming(None);
+
+    let loaded = [
+        "SetLoadStatus(Complete)",
+        "RequestRedraw",
+        "Send(FocusWebView)",
+    ];
+    let cases: Vec<(&str, DispatchState, EmbedderMsg, Vec<&str>)> = vec![
+        (
+            "load start",
+            state,
+            EmbedderMsg::LoadStart,
+            vec![
+                "ClearUserGesture",
+                "SetDownloads",
+                "ClearEncodingOverride",
+                "SetLoadStatus(Started)",
+            ],
+        ),
+        (
+            "head parsed",
+            state,
+            EmbedderMsg::HeadParsed,
+            vec![
+                "SetLoadStatus(HeadParsed)",
+                "RunScript(apply print)",
+                "RunScript(apply downloads)",
+                "RunScript(apply passwords)",
+                "RunScript(apply timer throttling)",
+            ],
+        ),
+        (
+            "head parsed with autofill",
+            allowed,
+            EmbedderMsg::HeadParsed,
+            vec![
+                "SetLoadStatus(HeadParsed)",
+                "RunScript(apply print)",
+                "RunScript(apply downloads)",
+                "RunScript(apply passwords)",
+                "RunScript(apply timer throttling)",
+                "InjectAutofill",
+            ],
+        ),
+        (
+            "head parsed reporting the content size",
+            DispatchState {
+                report_content_size: true,
+                ..state
+            },
+            EmbedderMsg::HeadParsed,
+            vec![
+                "SetLoadStatus(HeadParsed)",
+                "RunScript(apply print)",
+                "RunScript(apply downloads)",
+                "RunScript(apply content size)",
+                "RunScript(apply passwords)",
+                "RunScript(apply timer throttling)",
+            ],
+        ),
+        (
+            "web view opened",
+            state,
+            EmbedderMsg::WebViewOpened(id),
+            vec!["PushEvent(Ready)"],
+        ),
+        (
+            "web view closed",
+            state,
+            EmbedderMsg::WebViewClosed(id),
+            vec![],
+        ),
+        (
+            "web view focused",
+            state,
+            EmbedderMsg::WebViewFocused(id),
+            vec![],
+        ),
+        (
+            "load complete",
+            state,
+            EmbedderMsg::LoadComplete,
+            [
+                &loaded[..],
+                &[
+                    "ReadPageFlow",
+                    "ResetRetries",
+                    "PushEvent(Loaded)",
+                    "RunScript(stop autoplaying media)",
+                ],
+            ]
+            .concat(),
+        ),
+        (
+            "load complete after a user gesture",
+            DispatchState {
+                user_gesture: true,
+                report_load_errors: true,
+                ..state
+            },
+            EmbedderMsg::LoadComplete,
+            [&loaded[..], &["ReadPageFlow", "CheckLoadError"]].concat(),
+        ),
+        (
+            "crash page loaded",
+            DispatchState {
+                crash_restore: Some(&crashed),
+                ..state
+            },
+            EmbedderMsg::LoadComplete,
+            [
+                &loaded[..],
+                &[
+                    "SetCrashRestore",
+                    "SetLoadStatus(NavigationStarted)",
+                    "Send(LoadUrl)",
+                    "SetCrashRestore",
+                ],
+            ]
+            .concat(),
+        ),
+        (
+            "page reloaded after a crash",
+            DispatchState {
+                crash_restore: Some(&reloading),
+                ..allowed
+            },
+            EmbedderMsg::LoadComplete,
+            [
+                &loaded[..],
+                &[
+                    "SetCrashRestore",
+                    "RunScript(restore page state)",
+                    "ReadPageFlow",
+                    "ResetRetries",
+                    "PushEvent(Loaded)",
+                ],
+            ]
+            .concat(),
+        ),
+        (
+            "page resumed",
+            DispatchState {
+                crash_restore: Some(&resuming),
+                ..allowed
+            },
+            EmbedderMsg::LoadComplete,
+            [
+                &loaded[..],
+                &[
+                    "SetCrashRestore",
+                    "PushEvent(Resumed)",
+                    "ReadPageFlow",
+                    "ResetRetries",
+                    "PushEvent(Loaded)",
+                ],
+            ]
+            .concat(),
+        ),
+        (
+            "navigation request",
+            state,
+            EmbedderMsg::AllowNavigationRequest(PipelineId::new(), url.clone()),
+            vec!["PushEvent(Navigate)"],
+        ),
+        (
+            "unload",
+            state,
+            EmbedderMsg::AllowUnload(ipc::channel().unwrap().0),
+            vec!["AllowUnload"],
+        ),
+        (
+            "clipboard read",
+            state,
+            EmbedderMsg::GetClipboardContents(ipc::channel().unwrap().0),
+            vec!["SendClipboardContents"],
+        ),
+        (
+            "clipboard write",
+            state,
+            EmbedderMsg::SetClipboardContents("text".to_string()),
+            vec!["SetClipboardContents"],
+        ),
+        (
+            "click",
+            state,
+            EmbedderMsg::EventDelivered(CompositorEventVariant::MouseButtonEvent),
+            vec!["Send(FocusWebView)"],
+        ),
+        (
+            "mouse move",
+            state,
+            EmbedderMsg::EventDelivered(CompositorEventVariant::MouseMoveEvent),
+            vec![],
+        ),
+        (
+            "input method shown",
+            state,
+            EmbedderMsg::ShowIME(InputMethodType::Text, None, false, DeviceIntRect::zero()),
+            vec!["ShowIme"],
+        ),
+        (
+            "input method shown without a web view",
+            DispatchState {
+                webview_rect: None,
+                ..state
+            },
+            EmbedderMsg::ShowIME(InputMethodType::Text, None, false, DeviceIntRect::zero()),
+            vec![],
+        ),
+        (
+            "input method hidden",
+            state,
+            EmbedderMsg::HideIME,
+            vec!["HideIme"],
+        ),
+        (
+            "title changed",
+            state,
+            EmbedderMsg::ChangePageTitle(Some("Title".to_string())),
+            vec!["SetTitle"],
+        ),
+        (
+            "title unchanged",
+            DispatchState {
+                title: Some("Title"),
+                ..state
+            },
+            EmbedderMsg::ChangePageTitle(Some("Title".to_string())),
+            vec![],
+        ),
+        (
+            "new favicon",
+            state,
+            EmbedderMsg::NewFavicon(favicon.clone()),
+            vec!["RunScript(decode favicon)"],
+        ),
+        (
+            "favicon decoded",
+            state,
+            input(&format!(
+                r#"VERSO_FAVICON:{{"url":"{favicon}","width":1,"height":1,"rgba":[0,0,0,255]}}"#
+            )),
+            vec!["AnswerPrompt", "SetFavicon"],
+        ),
+        (
+            "cached favicon",
+            state,
+            EmbedderMsg::NewFavicon(favicon.clone()),
+            vec!["SetFavicon"],
+        ),
+        (
+            "history changed",
+            state,
+            EmbedderMsg::HistoryChanged(vec![url.clone()], 0),
+            vec!["SetHistory"],
+        ),
+        (
+            "print",
+            state,
+            input("VERSO_PRINT"),
+            vec!["AnswerPrompt", "PushEvent(Print)"],
+        ),
+        (
+            "download",
+            DispatchState {
+                user_activation: true,
+                ..state
+            },
+            input(
+                r#"VERSO_DOWNLOAD:{"name":"export.png","data":"data:image/png;base64,iVBORw0KGgo="}"#,
+            ),
+            vec!["AnswerPrompt", "SetDownloads", "PushEvent(Download)"],
+        ),
+        (
+            "download without user activation",
+            state,
+            input(
+                r#"VERSO_DOWNLOAD:{"name":"export.png","data":"data:image/png;base64,iVBORw0KGgo="}"#,
+            ),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "download past the limit",
+            DispatchState {
+                user_activation: true,
+                downloads: MAX_DOWNLOADS_PER_PAGE,
+                ..state
+            },
+            input(
+                r#"VERSO_DOWNLOAD:{"name":"export.png","data":"data:image/png;base64,iVBORw0KGgo="}"#,
+            ),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "download of an invalid URL",
+            DispatchState {
+                user_activation: true,
+                ..state
+            },
+            input(r#"VERSO_DOWNLOAD:{"name":"export.png","data":"blob:https://example.com/1"}"#),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "download disabled by policy",
+            DispatchState {
+                config: &no_downloads,
+                ..state
+            },
+            input(r#"VERSO_DOWNLOAD:{"name":null,"data":"data:,hello"}"#),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "drag out",
+            state,
+            input(r#"VERSO_DRAG_OUT:{"url":"https://example.com/a.png","title":"","image":null}"#),
+            vec!["AnswerPrompt", "StartDragOut"],
+        ),
+        (
+            "content size",
+            state,
+            input(r#"VERSO_CONTENT_SIZE:{"width":800,"height":1250}"#),
+            vec!["AnswerPrompt", "SetContentSize"],
+        ),
+        (
+            "invalid content size",
+            state,
+            input(r#"VERSO_CONTENT_SIZE:{"width":800}"#),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "login form",
+            state,
+            input(
+                r#"VERSO_LOGIN_FORM:["token","https://example.com",{"username_field":"user","password_field":"pass"}]"#,
+            ),
+            vec!["AnswerPrompt", "PushEvent(LoginForm)"],
+        ),
+        (
+            "login form without a page",
+            DispatchState { url: None, ..state },
+            input(
+                r#"VERSO_LOGIN_FORM:["token","https://example.com",{"username_field":"user","password_field":"pass"}]"#,
+            ),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "login form without the token",
+            state,
+            input(
+                r#"VERSO_LOGIN_FORM:["forged","https://example.com",{"username_field":"user","password_field":"pass"}]"#,
+            ),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "login form of another origin",
+            state,
+            input(
+                r#"VERSO_LOGIN_FORM:["token","https://frame.example",{"username_field":"user","password_field":"pass"}]"#,
+            ),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "credentials",
+            state,
+            input(
+                r#"VERSO_CREDENTIALS:["token","https://example.com",{"username_field":"user","password_field":"pass","username":null,"password":null}]"#,
+            ),
+            vec!["AnswerPrompt", "PushEvent(CredentialsSubmitted)"],
+        ),
+        (
+            "credentials without the token",
+            state,
+            input(
+                r#"VERSO_CREDENTIALS:{"username_field":"user","password_field":"pass","username":null,"password":null}"#,
+            ),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "form submitted",
+            state,
+            input("VERSO_AUTOFILL_SUBMIT:[]"),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "form submitted with autofill",
+            allowed,
+            input("VERSO_AUTOFILL_SUBMIT:[]"),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "form submitted by the user with autofill",
+            activated,
+            input("VERSO_AUTOFILL_SUBMIT:[]"),
+            vec!["AnswerPrompt", "RecordAutofill"],
+        ),
+        (
+            "field focused",
+            state,
+            input("VERSO_AUTOFILL_QUERY:field"),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "field focused with autofill",
+            allowed,
+            input("VERSO_AUTOFILL_QUERY:field"),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "field focused by the user with autofill",
+            activated,
+            input("VERSO_AUTOFILL_QUERY:field"),
+            vec!["QueryAutofill"],
+        ),
+        ("alert", state, alert(), vec!["DismissDialog"]),
+        ("prompt", state, input("Name?"), vec!["DismissDialog"]),
+        ("held alert", allowed, alert(), vec!["HoldDialog"]),
+        (
+            "permission",
+            state,
+            permission(PermissionName::Camera),
+            vec!["Unhandled"],
+        ),
+        (
+            "denied permission",
+            DispatchState {
+                config: &no_devices,
+                ..state
+            },
+            permission(PermissionName::Camera),
+            vec!["AnswerPermission"],
+        ),
+        (
+            "permission of an allowed feature",
+            DispatchState {
+                config: &no_devices,
+                ..state
+            },
+            permission(PermissionName::Microphone),
+            vec!["Unhandled"],
+        ),
+        (
+            "denied Bluetooth device",
+            DispatchState {
+                config: &no_devices,
+                ..state
+            },
+            EmbedderMsg::GetSelectedBluetoothDevice(Vec::new(), ipc::channel().unwrap().0),
+            vec!["AnswerPrompt"],
+        ),
+        (
+            "held unload",
+            allowed,
+            EmbedderMsg::AllowUnload(ipc::channel().unwrap().0),
+            vec!["HoldDialog"],
+        ),
+        (
+            "move",
+            state,
+            EmbedderMsg::MoveTo(DeviceIntPoint::new(10, 10)),
+            vec![],
+        ),
+        (
+            "allowed move",
+            allowed,
+            EmbedderMsg::MoveTo(DeviceIntPoint::new(10, 10)),
+            vec!["MoveWindow"],
+        ),
+        (
+            "resize",
+            state,
+            EmbedderMsg::ResizeTo(DeviceIntSize::new(100, 100)),
+            vec![],
+        ),
+        (
+            "allowed resize",
+            allowed,
+            EmbedderMsg::ResizeTo(DeviceIntSize::new(100, 100)),
+            vec!["ResizeWindow"],
+        ),
+        (
+            "fullscreen",
+            state,
+            EmbedderMsg::SetFullscreenState(true),
+            vec![],
+        ),
+        (
+            "allowed fullscreen",
+            allowed,
+            EmbedderMsg::SetFullscreenState(true),
+            vec!["SetFullscreen"],
+        ),
+        (
+            "crash",
+            state,
+            EmbedderMsg::Panic("reason".to_string(), None),
+            vec![],
+        ),
+        (
+            "crash with recovery",
+            allowed,
+            EmbedderMsg::Panic("reason".to_string(), None),
+            vec!["SaveCrashState"],
+        ),
+        (
+            "crash after the last restore",
+            DispatchState {
+                restores: 1,
+                ..allowed
+            },
+            EmbedderMsg::Panic("reason".to_string(), None),
+            vec![],
+        ),
+        (
+            "unhandled message",
+            state,
+            EmbedderMsg::Status(None),
+            vec!["Unhandled"],
+        ),
+    ];
+    let pdf_cases = [
+        (
+            "PDF page",
+            state,
+            input("VERSO_PDF_PAGE:0"),
+            vec!["RenderPdfPage"],
+        ),
+        (
+            "PDF download",
+            state,
+            input("VERSO_PDF_DOWNLOAD"),
+            vec!["DownloadPdf"],
+        ),
+    ];
+    let svg_cases = [(
+        "SVG favicon",
+        state,
+        input("VERSO_FAVICON_SVG:{}"),
+        vec!["AnswerPrompt"],
+    )];
+    let cases = cases
+        .into_iter()
+        .chain(
+            pdf_cases
+                .into_iter()
+                .filter(|_| cfg!(feature = "pdf-viewer")),
+        )
+        .chain(
+            svg_cases
+                .into_iter()
+                .filter(|_| cfg!(feature = "svg-favicons")),
+        );
+
+    for (case, state, message, expected) in cases {
+        let actions: Vec<String> = dispatch(id, message, state)
+            .iter()
+            .map(name)
+            // Only built with the `pdf-viewer` and `drag-out` features.
+            .filter(|action| action != "OpenPdf" && action != "RunScript(apply drag out)")
+            .collect();
+        assert_eq!(actions, expected, "{case}");
+    }
+
+    let global = [
+        (
+            EmbedderMsg::SetCursor(Cursor::Pointer),
+            vec!["SetCursor".to_string()],
+        ),
+        (EmbedderMsg::Shutdown, vec![]),
+        (EmbedderMsg::ReadyToPresent(vec![id]), vec![]),
+        (
+            EmbedderMsg::OnDevtoolsStarted(Ok(6000), String::new()),
+            vec!["SetDevtoolsPort".to_string()],
+        ),
+        (
+            EmbedderMsg::OnDevtoolsStarted(Err(()), String::new()),
+            vec!["SetDevtoolsPort".to_string()],
+        ),
+    ];
+    for (message, expected) in global {
+        let actions: Vec<String> = dispatch_global(message).iter().map(name).collect();
+        assert_eq!(actions, expected);
+    }
+}
@@ -0,0 +1,65 @@
+//! Pages of a host with a DNS override are requested from the address of the override, instead
+//! of the address the system would resolve.
+//!
+//! The host is under the reserved `.test` domain, so the system can't resolve it, and the
+//! override points it to a local server.
+
+mod common;
+
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr},
+    sync::mpsc,
+};
+
+use common::Scenario;
+use servo_url::ServoUrl;
+use verso::{webview::LoadStatus, Verso};
+
+const HOST: &str = "staging.verso.test";
+
+const PAGE: &str = "<!DOCTYPE html><p>DNS</p>";
+
+struct Load {
+    url: ServoUrl,
+    loading: bool,
+}
+
+impl Scenario for Load {
+    fn step(&mut self, verso: &mut Verso) {
+        if !self.loading {
+            if verso.current_webview_id().is_some() {
+                verso.load_url(self.url.clone());
+                self.loading = true;
+            }
+            return;
+        }
+        if verso.current_raw_url().as_ref() == Some(&self.url)
+            && verso.load_status() == LoadStatus::Complete
+        {
+            verso.shutdown();
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (sender, receiver) = mpsc::channel();
+    let port = common::serve(move |request| {
+        let _ = sender.send(request.header("host").map(str::to_string));
+        PAGE
+    })?;
+
+    let mut config = common::config();
+    config.dns_overrides = BTreeMap::from([(HOST.to_string(), IpAddr::from(Ipv4Addr::LOCALHOST))]);
+    let scenario = Load {
+        url: ServoUrl::parse(&format!("http://{HOST}:{port}/"))?,
+        loading: false,
+    };
+    common::run(config, scenario)?;
+
+    let host = receiver
+        .try_recv()
+        .map_err(|_| "The local server wasn't requested")?;
+    assert_eq!(host.as_deref(), Some(format!("{HOST}:{port}").as_str()));
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Links and images dragged out of pages are offered in the expected formats.
+
+use servo_url::ServoUrl;
+use verso::drag_out::{flavors, DragItem, Flavor};
+
+fn main() {
+    let url = ServoUrl::parse("https://example.com/images/cat.png").unwrap();
+    let link = DragItem {
+        url: url.clone(),
+        title: Some("Cat".to_string()),
+        png: None,
+    };
+    assert_eq!(
+        flavors(&link),
+        [
+            Flavor {
+                mime_type: "text/uri-list",
+                data: b"https://example.com/images/cat.png\r\n".to_vec(),
+            },
+            Flavor {
+                mime_type: "text/plain",
+                data: b"https://example.com/images/cat.png".to_vec(),
+            },
+        ],
+        "links offer their URL"
+    );
+
+    let png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    let image = DragItem {
+        png: Some(png.clone()),
+        ..link
+    };
+    let offered = flavors(&image);
+    let types: Vec<&str> = offered.iter().map(|flavor| flavor.mime_type).collect();
+    assert_eq!(
+        types,
+        ["image/png", "text/uri-list", "text/plain"],
+        "images offer their pixels first"
+    );
+    assert_eq!(offered[0].data, png, "the image is offered as it's encoded");
+
+    // URLs are serialized, so the list has a single line even with spaces in the path.
+    let spaced = DragItem {
+        url: ServoUrl::parse("https://example.com/my cat.png").unwrap(),
+        title: None,
+        png: None,
+    };
+    assert_eq!(
+        flavors(&spaced)[0].data,
+        b"https://example.com/my%20cat.png\r\n".to_vec()
+    );
+}
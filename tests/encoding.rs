@@ -0,0 +1,127 @@
+//! A Shift-JIS page served without a charset is garbled until its encoding is overridden, and is
+//! sniffed again once the override is cleared.
+//!
+//! A local server serves the page, as the override fetches the document again.
+
+mod common;
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+use verso::{
+    encoding::{encoding_hint_for_language, Encoding},
+    webview::LoadStatus,
+    Verso,
+};
+
+const TEXT: &str = "日本語のページです";
+
+const READ_TEXT: &str = "
+const text = document.getElementById('text');
+return text ? text.textContent : null;";
+
+enum Step {
+    Starting,
+    Loading,
+    Overriding,
+    Clearing,
+    Done,
+}
+
+struct Override {
+    url: ServoUrl,
+    step: Step,
+    garbled: Option<String>,
+    overridden: Option<(String, Option<&'static Encoding>)>,
+    cleared: Option<(String, Option<&'static Encoding>)>,
+}
+
+impl Scenario for Override {
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.load_status() != LoadStatus::Complete
+                    || verso.current_raw_url().as_ref() != Some(&self.url)
+                {
+                    return;
+                }
+                self.garbled = read_text(verso);
+                verso.override_encoding(Some(encoding_rs::SHIFT_JIS));
+                self.step = Step::Overriding;
+            }
+            Step::Overriding => {
+                // The page rewrites itself once it fetched and decoded the document again.
+                let Some(text) = read_text(verso).filter(|text| text == TEXT) else {
+                    return;
+                };
+                self.overridden = Some((text, verso.encoding_override()));
+                verso.override_encoding(None);
+                self.step = Step::Clearing;
+            }
+            Step::Clearing => {
+                if verso.load_status() != LoadStatus::Complete {
+                    return;
+                }
+                if let Some(text) = read_text(verso) {
+                    self.cleared = Some((text, verso.encoding_override()));
+                }
+                verso.shutdown();
+                self.step = Step::Done;
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn read_text(verso: &Verso) -> Option<String> {
+    let text = verso
+        .evaluate_script(READ_TEXT)
+        .expect("There's no web view")
+        .recv()
+        .expect("Script didn't answer");
+    match text {
+        Ok(WebDriverJSValue::String(text)) => Some(text),
+        _ => None,
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        encoding_hint_for_language("ja-JP"),
+        Some(encoding_rs::SHIFT_JIS)
+    );
+
+    let page = format!("<!DOCTYPE html><html><body><p id=\"text\">{TEXT}</p></body></html>");
+    let (page, _, unmappable) = encoding_rs::SHIFT_JIS.encode(&page);
+    assert!(!unmappable, "The page isn't Shift-JIS");
+    let page = page.into_owned();
+    let port = common::serve(move |_| page.clone())?;
+    let scenario = Override {
+        url: ServoUrl::parse(&format!("http://127.0.0.1:{port}/"))?,
+        step: Step::Starting,
+        garbled: None,
+        overridden: None,
+        cleared: None,
+    };
+    let (scenario, _) = common::run(common::config(), scenario)?;
+
+    let garbled = scenario.garbled.expect("The page wasn't read");
+    assert_ne!(garbled, TEXT, "The page was decoded without the override");
+    assert_eq!(
+        scenario.overridden,
+        Some((TEXT.to_string(), Some(encoding_rs::SHIFT_JIS))),
+        "The override didn't decode the page"
+    );
+    assert_eq!(
+        scenario.cleared,
+        Some((garbled, None)),
+        "Clearing the override didn't sniff the page again"
+    );
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Links of schemes handled by other applications go to the `on_external_url` hook instead of
+//! being loaded, from both the page and [`Verso::load_url`], and blocked schemes are ignored.
+//!
+//! The homepage clicks a blocked `tel:` link, then a `mailto:` link, and finally navigates to
+//! `form.html`, which it can only do if both links were cancelled.
+
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::Scenario;
+use servo_url::ServoUrl;
+use verso::{config::Config, external::is_external_url, webview::LoadStatus, Verso, VersoBuilder};
+use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
+
+/// URLs passed to the hook, in order.
+type Opened = Rc<RefCell<Vec<String>>>;
+
+enum Step {
+    Starting,
+    Loading,
+    Done,
+}
+
+struct ExternalUrls {
+    opened: Opened,
+    form: ServoUrl,
+    step: Step,
+}
+
+impl Scenario for ExternalUrls {
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        mut config: Config,
+    ) -> verso::Result<Verso> {
+        config.homepage = Some(common::fixture("external.html"));
+        let opened = self.opened.clone();
+        VersoBuilder::from_config(config)
+            .on_external_url(move |url| opened.borrow_mut().push(url.to_string()))
+            .block_scheme("tel")
+            .build(event_loop, proxy)
+    }
+
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(ServoUrl::parse("mailto:typed@example.com").unwrap());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.current_raw_url() == Some(self.form.clone())
+                    && verso.load_status() == LoadStatus::Complete
+                {
+                    verso.shutdown();
+                    self.step = Step::Done;
+                }
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    for url in [
+        "https://example.com/",
+        "http://example.com/",
+        "file:///tmp/page.html",
+        "data:text/html,page",
+        "about:blank",
+        "resource:///panel.html",
+    ] {
+        assert!(
+            !is_external_url(&ServoUrl::parse(url)?),
+            "{url} isn't loaded as a page"
+        );
+    }
+    for url in [
+        "mailto:someone@example.com",
+        "tel:+15550100",
+        "sms:+15550100",
+        "magnet:?xt=urn:btih:0",
+    ] {
+        assert!(
+            is_external_url(&ServoUrl::parse(url)?),
+            "{url} is loaded as a page"
+        );
+    }
+
+    let opened = Opened::default();
+    common::run(
+        common::config(),
+        ExternalUrls {
+            opened: opened.clone(),
+            form: common::fixture("form.html"),
+            step: Step::Starting,
+        },
+    )?;
+    assert_eq!(
+        *opened.borrow(),
+        ["mailto:typed@example.com", "mailto:page@example.com"],
+        "The hook didn't get the external URLs, or got the blocked one"
+    );
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! The configured default fonts reach Servo's preferences and the computed style of text in
+//! generic families, and system fonts are listed sorted without duplicates.
+
+mod common;
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_config::pref;
+use servo_url::ServoUrl;
+use verso::{config::FontConfig, webview::LoadStatus, Verso};
+
+const COMPUTED_SIZES: &str = "
+return JSON.stringify(['serif', 'sans-serif', 'monospace'].map(
+    (id) => getComputedStyle(document.getElementById(id)).fontSize
+));";
+
+enum Step {
+    Starting,
+    Loading,
+    Done,
+}
+
+struct Fonts {
+    url: ServoUrl,
+    step: Step,
+    sizes: Option<String>,
+}
+
+impl Scenario for Fonts {
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.load_status() != LoadStatus::Complete {
+                    return;
+                }
+                let sizes = verso
+                    .evaluate_script(COMPUTED_SIZES)
+                    .expect("There's no web view")
+                    .recv()
+                    .expect("Script didn't answer")
+                    .expect("Script failed");
+                if let WebDriverJSValue::String(sizes) = sizes {
+                    self.sizes = Some(sizes);
+                }
+                verso.shutdown();
+                self.step = Step::Done;
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let fonts = Verso::list_system_fonts();
+    assert!(
+        fonts.windows(2).all(|pair| pair[0] < pair[1]),
+        "System fonts aren't sorted without duplicates"
+    );
+
+    let mut config = common::config();
+    // The text scale of the OS would multiply the sizes.
+    config.follow_os_text_scale = false;
+    config.fonts = FontConfig {
+        serif: Some("Verso Serif".into()),
+        sans_serif: Some("Verso Sans".into()),
+        monospace: Some("Verso Mono".into()),
+        default_size: Some(20),
+        default_monospace_size: Some(15),
+    };
+    let scenario = Fonts {
+        url: common::fixture("fonts.html"),
+        step: Step::Starting,
+        sizes: None,
+    };
+    let (scenario, _) = common::run(config, scenario)?;
+
+    assert_eq!(pref!(fonts.serif), "Verso Serif");
+    assert_eq!(pref!(fonts.sans_serif), "Verso Sans");
+    assert_eq!(pref!(fonts.monospace), "Verso Mono");
+    let sizes: Vec<String> =
+        serde_json::from_str(&scenario.sizes.expect("The page wasn't measured"))?;
+    assert_eq!(
+        sizes,
+        ["20px", "20px", "15px"],
+        "Text in generic families doesn't have the configured default sizes"
+    );
+    Ok(())
+}
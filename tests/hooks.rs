@@ -0,0 +1,106 @@
+//! The lifecycle hooks of the builder run in order: `on_ready` once the web view is opened,
+//! then `after_load` for the homepage, `before_navigate` for each navigation the page starts,
+//! and `after_load` for the page it was allowed to navigate to.
+//!
+//! `on_ready` also tries to close the window, which the re-entrancy guard must ignore, and
+//! `before_navigate` refuses `denied.html`.
+
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::Scenario;
+use servo_url::ServoUrl;
+use verso::{config::Config, hooks::LoadResult, Verso, VersoBuilder};
+use winit::{
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoopProxy},
+};
+
+/// Calls of the hooks, in order.
+type Calls = Rc<RefCell<Vec<String>>>;
+
+struct Hooks {
+    calls: Calls,
+    done: bool,
+}
+
+/// File name of a fixture URL.
+fn name(url: &ServoUrl) -> String {
+    url.as_url()
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or_default()
+        .to_string()
+}
+
+impl Scenario for Hooks {
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        mut config: Config,
+    ) -> verso::Result<Verso> {
+        config.homepage = Some(common::fixture("hooks.html"));
+        let ready = self.calls.clone();
+        let navigate = self.calls.clone();
+        let load = self.calls.clone();
+        VersoBuilder::from_config(config)
+            .on_ready(move |verso| {
+                let webview = verso.current_webview_id().is_some();
+                ready
+                    .borrow_mut()
+                    .push(format!("ready with web view {webview}"));
+                // Hooks must not drive the event loop, so this is ignored.
+                if let Some(window_id) = verso.current_window_id() {
+                    verso.handle_winit_window_event(window_id, WindowEvent::CloseRequested);
+                }
+            })
+            .before_navigate(move |url| {
+                let name = name(url);
+                let allow = name != "denied.html";
+                navigate
+                    .borrow_mut()
+                    .push(format!("navigate {name} {allow}"));
+                allow
+            })
+            .after_load(move |url, result| {
+                load.borrow_mut()
+                    .push(format!("load {} {result:?}", name(url)));
+            })
+            .build(event_loop, proxy)
+    }
+
+    fn step(&mut self, verso: &mut Verso) {
+        let loaded = self
+            .calls
+            .borrow()
+            .iter()
+            .any(|call| call.starts_with("load form.html"));
+        if loaded && !self.done {
+            verso.shutdown();
+            self.done = true;
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let calls = Calls::default();
+    common::run(
+        common::config(),
+        Hooks {
+            calls: calls.clone(),
+            done: false,
+        },
+    )?;
+
+    let expected = [
+        "ready with web view true".to_string(),
+        format!("load hooks.html {:?}", LoadResult::Loaded),
+        "navigate denied.html false".to_string(),
+        "navigate form.html true".to_string(),
+        format!("load form.html {:?}", LoadResult::Loaded),
+    ];
+    assert_eq!(*calls.borrow(), expected, "The hooks ran out of order");
+    Ok(())
+}
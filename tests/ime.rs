@@ -0,0 +1,57 @@
+//! Text of an input method reaches the page once: key presses are held back while text is
+//! composed, the committed text is typed, and a commit echoing the key press which already typed
+//! it is dropped.
+//!
+//! The input method state only sees Winit's events, so this runs without an event loop.
+
+use keyboard_types::{Key, KeyState, KeyboardEvent};
+use verso::keyboard::ImeInput;
+use winit::event::Ime;
+
+fn typed(events: &[KeyboardEvent]) -> String {
+    events
+        .iter()
+        .filter(|event| event.state == KeyState::Down)
+        .map(|event| match &event.key {
+            Key::Character(text) => text.as_str(),
+            key => panic!("A commit types {key:?}"),
+        })
+        .collect()
+}
+
+fn main() {
+    let mut ime = ImeInput::new();
+    assert!(ime.handle_ime(&Ime::Enabled).is_empty());
+    assert!(ime.filter_key(KeyState::Down, Some("a")));
+    assert!(ime.filter_key(KeyState::Up, None));
+    let events = ime.handle_ime(&Ime::Commit("a".to_string()));
+    assert!(events.is_empty(), "A typed key is committed again");
+
+    assert!(ime
+        .handle_ime(&Ime::Preedit("に".to_string(), Some((0, 3))))
+        .is_empty());
+    assert!(
+        !ime.filter_key(KeyState::Down, Some("h")),
+        "A key press is forwarded while composing"
+    );
+    assert!(!ime.filter_key(KeyState::Up, None));
+    ime.handle_ime(&Ime::Preedit("日本".to_string(), Some((0, 6))));
+    assert!(!ime.filter_key(KeyState::Down, None));
+    ime.handle_ime(&Ime::Preedit(String::new(), None));
+    let events = ime.handle_ime(&Ime::Commit("日本".to_string()));
+    assert_eq!(typed(&events), "日本");
+    assert_eq!(events.len(), 4, "Each character is pressed and released");
+    assert!(events
+        .iter()
+        .all(|event| event.modifiers.is_empty() && !event.repeat && !event.is_composing));
+
+    assert!(ime.filter_key(KeyState::Down, Some("x")));
+    let events = ime.handle_ime(&Ime::Commit("é".to_string()));
+    assert_eq!(
+        typed(&events),
+        "é",
+        "A commit without composition is dropped"
+    );
+    assert!(ime.handle_ime(&Ime::Disabled).is_empty());
+    assert!(ime.filter_key(KeyState::Down, Some("b")));
+}
@@ -0,0 +1,137 @@
+//! Properties of the conversion of pointer inputs to compositor events, over random streams of
+//! inputs and window settings.
+
+use proptest::{prelude::*, test_runner::TestRunner};
+use verso::{
+    compositor::MouseWindowEvent,
+    config::AxisLockMode,
+    flow::{PageFlow, WritingMode},
+    input::{convert_input, InputEvent, InputOptions, InputState},
+    recorder::RecordedInput,
+};
+use webrender_api::units::DevicePoint;
+
+fn input() -> impl Strategy<Value = RecordedInput> {
+    prop_oneof![
+        (-100.0..2000.0f64, -100.0..2000.0f64)
+            .prop_map(|(x, y)| RecordedInput::CursorMoved { x, y }),
+        (any::<bool>(), 0u16..3)
+            .prop_map(|(pressed, button)| RecordedInput::MouseInput { pressed, button }),
+        (-50.0..50.0f64, -50.0..50.0f64, any::<bool>(), 0u8..4)
+            .prop_map(|(x, y, pixel, phase)| RecordedInput::MouseWheel { x, y, pixel, phase }),
+        (-1.0..1.0f64, 0u8..4)
+            .prop_map(|(delta, phase)| RecordedInput::PinchGesture { delta, phase }),
+        any::<bool>().prop_map(|focused| RecordedInput::Focused { focused }),
+        Just(RecordedInput::CursorEntered),
+    ]
+}
+
+fn options() -> impl Strategy<Value = InputOptions> {
+    let axis_lock = prop_oneof![
+        Just(AxisLockMode::Always),
+        Just(AxisLockMode::Never),
+        Just(AxisLockMode::SmartDiagonal),
+    ];
+    let writing_mode = prop_oneof![
+        Just(WritingMode::HorizontalTb),
+        Just(WritingMode::VerticalRl),
+        Just(WritingMode::VerticalLr),
+    ];
+    (
+        0.5..4.0f64,
+        axis_lock,
+        any::<bool>(),
+        any::<bool>(),
+        writing_mode,
+        any::<bool>(),
+    )
+        .prop_map(
+            |(scale_factor, wheel_axis_lock, snap_scroll, shift, writing_mode, rtl)| InputOptions {
+                scale_factor,
+                wheel_axis_lock,
+                snap_scroll,
+                shift,
+                flow: PageFlow { writing_mode, rtl },
+            },
+        )
+}
+
+/// Each input results in the events of its kind, at the position of the cursor.
+fn check_event_kinds(
+    inputs: Vec<RecordedInput>,
+    options: InputOptions,
+) -> Result<(), TestCaseError> {
+    let mut state = InputState::default();
+    for input in inputs {
+        let before = state;
+        let events = convert_input(&input.to_window_event(), &mut state, &options);
+        let cursor = DevicePoint::new(state.cursor.x as f32, state.cursor.y as f32);
+        match input {
+            RecordedInput::CursorMoved { x, y } => {
+                prop_assert_eq!((state.cursor.x, state.cursor.y), (x, y));
+                prop_assert!(
+                    matches!(events[..], [InputEvent::MouseMove(point)] if point == cursor)
+                );
+            }
+            RecordedInput::MouseInput { pressed: true, .. } => {
+                prop_assert!(matches!(
+                    events[..],
+                    [InputEvent::Mouse(MouseWindowEvent::MouseDown(_, point))] if point == cursor
+                ));
+            }
+            RecordedInput::MouseInput { pressed: false, .. } => {
+                prop_assert!(matches!(
+                    events[..],
+                    [
+                        InputEvent::Mouse(MouseWindowEvent::MouseUp(_, up)),
+                        InputEvent::Mouse(MouseWindowEvent::Click(_, click)),
+                    ] if up == cursor && click == cursor
+                ));
+            }
+            RecordedInput::MouseWheel { .. } => {
+                prop_assert!(matches!(
+                    events[..],
+                    [] | [InputEvent::Wheel(_, _), InputEvent::Scroll(_, _, _)]
+                ));
+            }
+            _ => {
+                prop_assert!(events.is_empty());
+                prop_assert_eq!(state, before, "An input without events changed the state");
+            }
+        }
+        prop_assert!(!events.spilled(), "The events of an input were allocated");
+    }
+    Ok(())
+}
+
+/// The same inputs always result in the same state.
+fn check_deterministic(
+    inputs: Vec<RecordedInput>,
+    options: InputOptions,
+) -> Result<(), TestCaseError> {
+    let run = || {
+        let mut state = InputState::default();
+        let counts: Vec<usize> = inputs
+            .iter()
+            .map(|input| convert_input(&input.to_window_event(), &mut state, &options).len())
+            .collect();
+        (state, counts)
+    };
+    prop_assert_eq!(run(), run());
+    Ok(())
+}
+
+fn main() {
+    let mut runner = TestRunner::default();
+    let stream = (prop::collection::vec(input(), 0..200), options());
+    runner
+        .run(&stream, |(inputs, options)| {
+            check_event_kinds(inputs, options)
+        })
+        .unwrap();
+    runner
+        .run(&stream, |(inputs, options)| {
+            check_deterministic(inputs, options)
+        })
+        .unwrap();
+}
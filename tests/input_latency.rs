@@ -0,0 +1,224 @@
+//! Input latency samples are measured from the oldest input to the present of the first frame
+//! requested after it, and their statistics cover the latest samples.
+//!
+//! Then clicks are injected into a page changing its color on each click, which must produce
+//! samples when latency is measured.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+use verso::{
+    latency::{LatencyStats, LatencyTracker},
+    recorder::RecordedInput,
+    webview::LoadStatus,
+    Verso,
+};
+
+/// Number of clicks injected into the page.
+const CLICKS: u32 = 10;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+/// Record an input at `time` shown by a frame presented `latency` later.
+fn sample(tracker: &mut LatencyTracker, time: Instant, latency: Duration) {
+    tracker.on_input(time);
+    tracker.on_frame_requested();
+    tracker.on_frame_ready();
+    tracker.on_present(time + latency);
+}
+
+fn check_tracker() {
+    let start = Instant::now();
+    assert_eq!(LatencyTracker::default().stats(), LatencyStats::default());
+
+    // A frame requested before the input can't show it.
+    let mut tracker = LatencyTracker::default();
+    tracker.on_frame_requested();
+    tracker.on_input(start);
+    tracker.on_frame_ready();
+    tracker.on_present(start + ms(10));
+    assert_eq!(
+        tracker.stats().samples,
+        0,
+        "An older frame completed the input"
+    );
+    tracker.on_frame_requested();
+    tracker.on_present(start + ms(20));
+    assert_eq!(
+        tracker.stats().samples,
+        0,
+        "A frame completed the input before it was ready"
+    );
+    tracker.on_frame_ready();
+    tracker.on_present(start + ms(30));
+    let stats = tracker.stats();
+    assert_eq!((stats.samples, stats.min), (1, ms(30)));
+
+    // Inputs before the same frame are one sample from the oldest.
+    let mut tracker = LatencyTracker::default();
+    tracker.on_input(start);
+    tracker.on_input(start + ms(5));
+    tracker.on_frame_requested();
+    tracker.on_input(start + ms(8));
+    tracker.on_frame_ready();
+    tracker.on_present(start + ms(16));
+    let stats = tracker.stats();
+    assert_eq!((stats.samples, stats.max), (1, ms(16)));
+    // The input after the request waits for the next frame.
+    tracker.on_frame_requested();
+    tracker.on_frame_ready();
+    tracker.on_present(start + ms(33));
+    let stats = tracker.stats();
+    assert_eq!((stats.samples, stats.max), (2, ms(25)));
+
+    // More ready frames than requested ones don't complete later inputs early.
+    let mut tracker = LatencyTracker::default();
+    tracker.on_frame_ready();
+    tracker.on_input(start);
+    tracker.on_present(start + ms(1));
+    assert_eq!(
+        tracker.stats().samples,
+        0,
+        "An unrequested frame completed the input"
+    );
+
+    let mut tracker = LatencyTracker::default();
+    for i in 1..=20 {
+        sample(&mut tracker, start, ms(i));
+    }
+    assert_eq!(
+        tracker.stats(),
+        LatencyStats {
+            samples: 20,
+            min: ms(1),
+            max: ms(20),
+            mean: Duration::from_micros(10_500),
+            p50: ms(10),
+            p95: ms(19),
+        }
+    );
+
+    // Only the latest samples are kept.
+    let mut tracker = LatencyTracker::default();
+    for i in 1..=200 {
+        sample(&mut tracker, start, ms(i));
+    }
+    let stats = tracker.stats();
+    assert_eq!(stats.samples, 128);
+    assert_eq!((stats.min, stats.max), (ms(73), ms(200)));
+}
+
+enum Step {
+    Starting,
+    Loading,
+    Clicking(u32),
+    Measuring,
+    Done,
+}
+
+struct Clicks {
+    url: ServoUrl,
+    step: Step,
+    stats: Option<LatencyStats>,
+}
+
+impl Scenario for Clicks {
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.current_raw_url().as_ref() == Some(&self.url)
+                    && verso.load_status() == LoadStatus::Complete
+                {
+                    click(verso);
+                    self.step = Step::Clicking(1);
+                }
+            }
+            Step::Clicking(sent) => {
+                // Wait for the page to handle each click before the next one.
+                if clicks(verso) < sent {
+                    return;
+                }
+                if sent < CLICKS {
+                    click(verso);
+                    self.step = Step::Clicking(sent + 1);
+                } else {
+                    self.step = Step::Measuring;
+                }
+            }
+            Step::Measuring => {
+                let stats = verso.input_latency_stats();
+                if stats.samples > 0 {
+                    self.stats = Some(stats);
+                    verso.shutdown();
+                    self.step = Step::Done;
+                }
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn click(verso: &mut Verso) {
+    let window = verso.current_window_id().expect("There's no window");
+    for input in [
+        RecordedInput::CursorMoved { x: 400.0, y: 400.0 },
+        RecordedInput::MouseInput {
+            pressed: true,
+            button: 0,
+        },
+        RecordedInput::MouseInput {
+            pressed: false,
+            button: 0,
+        },
+    ] {
+        verso.handle_winit_window_event(window, input.to_window_event());
+    }
+}
+
+fn clicks(verso: &Verso) -> u32 {
+    let clicks = verso
+        .evaluate_script("return clicks;")
+        .expect("There's no web view")
+        .recv()
+        .expect("Script didn't answer");
+    match clicks {
+        Ok(WebDriverJSValue::Number(clicks)) => clicks as u32,
+        _ => 0,
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    check_tracker();
+
+    let mut config = common::config();
+    config.measure_input_latency = true;
+    let scenario = Clicks {
+        url: common::fixture("latency.html"),
+        step: Step::Starting,
+        stats: None,
+    };
+    let (scenario, _) = common::run(config, scenario)?;
+
+    let stats = scenario.stats.expect("No latency was measured");
+    assert!(
+        stats.min <= stats.p50 && stats.p50 <= stats.p95 && stats.p95 <= stats.max,
+        "The statistics aren't ordered: {stats:?}"
+    );
+    assert!(
+        stats.max < common::TIMEOUT,
+        "Latency is too long: {stats:?}"
+    );
+    Ok(())
+}
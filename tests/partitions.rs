@@ -0,0 +1,161 @@
+//! Cookies set in a storage partition are only seen by later instances in the same partition,
+//! and Verso fails to start when the directory of its partition can't be created.
+//!
+//! A partition applies to a whole process, so each instance runs in a child process of this
+//! test, started with the partition and the cookie to set, if any. The child prints the cookies
+//! of a page served by a local server, which answers with the same page whatever the path.
+
+mod common;
+
+use std::{fs, path::Path, process::Command};
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+use verso::{webview::LoadStatus, Verso};
+
+/// Environment variables holding the config directory, the partition, the port and the cookie
+/// to set of a child process.
+const CHILD_VARIABLES: [&str; 4] = [
+    "VERSO_PARTITIONS_DIR",
+    "VERSO_PARTITIONS_NAME",
+    "VERSO_PARTITIONS_PORT",
+    "VERSO_PARTITIONS_COOKIE",
+];
+
+/// Prefix of the line a child prints with the cookies of the page.
+const COOKIES_LINE: &str = "cookies: ";
+
+const PAGE: &str = "<!DOCTYPE html><p>Partitions</p>";
+
+struct Cookies {
+    url: ServoUrl,
+    /// Cookie to set, like `app=one`.
+    cookie: Option<String>,
+    /// Cookies of the page before the cookie is set.
+    cookies: Option<String>,
+    loading: bool,
+}
+
+impl Scenario for Cookies {
+    fn step(&mut self, verso: &mut Verso) {
+        if !self.loading {
+            if verso.current_webview_id().is_some() {
+                verso.load_url(self.url.clone());
+                self.loading = true;
+            }
+            return;
+        }
+        if self.cookies.is_some()
+            || verso.current_raw_url().as_ref() != Some(&self.url)
+            || verso.load_status() != LoadStatus::Complete
+        {
+            return;
+        }
+        if let WebDriverJSValue::String(cookies) = evaluate(verso, "return document.cookie;") {
+            self.cookies = Some(cookies);
+        }
+        if let Some(cookie) = &self.cookie {
+            // Cookies with an expiry are kept on disk when Verso shuts down.
+            evaluate(
+                verso,
+                &format!("document.cookie = '{cookie}; max-age=3600'; return true;"),
+            );
+        }
+        verso.shutdown();
+    }
+}
+
+fn evaluate(verso: &Verso, script: &str) -> WebDriverJSValue {
+    verso
+        .evaluate_script(script)
+        .expect("There's no web view")
+        .recv()
+        .expect("Script didn't answer")
+        .expect("Script failed")
+}
+
+/// Run Verso in the partition, set the cookie if any, and print the cookies the page had.
+fn child(dir: &str, name: &str, port: &str, cookie: Option<String>) {
+    let scenario = Cookies {
+        url: ServoUrl::parse(&format!("http://127.0.0.1:{port}/")).expect("The URL is valid"),
+        cookie,
+        cookies: None,
+        loading: false,
+    };
+    let mut config = common::config();
+    config.opts.config_dir = Some(dir.into());
+    config.partition = Some(name.to_string());
+    let (scenario, _) = common::run(config, scenario).expect("Verso failed");
+    let cookies = scenario.cookies.expect("The cookies weren't read");
+    println!("{COOKIES_LINE}{cookies}");
+}
+
+/// Run a child in the partition, setting the cookie if any, and return the cookies it read.
+fn run_child(dir: &Path, name: &str, port: u16, cookie: Option<&str>) -> String {
+    let mut command = Command::new(std::env::current_exe().expect("The test has a path"));
+    command
+        .env(CHILD_VARIABLES[0], dir)
+        .env(CHILD_VARIABLES[1], name)
+        .env(CHILD_VARIABLES[2], port.to_string());
+    if let Some(cookie) = cookie {
+        command.env(CHILD_VARIABLES[3], cookie);
+    }
+    let output = command.output().expect("The child starts");
+    assert!(
+        output.status.success(),
+        "The child in {name} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix(COOKIES_LINE))
+        .expect("The child didn't print the cookies")
+        .to_string()
+}
+
+/// Start Verso in a partition whose directory is taken by a file.
+struct Blocked;
+
+impl Scenario for Blocked {
+    fn step(&mut self, verso: &mut Verso) {
+        verso.shutdown();
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let [dir, name, port, cookie] = CHILD_VARIABLES.map(|name| std::env::var(name).ok());
+    if let (Some(dir), Some(name), Some(port)) = (dir, name, port) {
+        child(&dir, &name, &port, cookie);
+        return Ok(());
+    }
+
+    let dir = std::env::temp_dir().join(format!("verso-partitions-{}", std::process::id()));
+    fs::create_dir_all(dir.join("partitions"))?;
+    let port = common::serve(|_| PAGE)?;
+
+    assert_eq!(run_child(&dir, "one", port, Some("app=one")), "");
+    assert_eq!(
+        run_child(&dir, "two", port, None),
+        "",
+        "Another partition saw the cookie"
+    );
+    assert_eq!(
+        run_child(&dir, "one", port, None),
+        "app=one",
+        "The partition lost its cookie"
+    );
+
+    fs::write(dir.join("partitions").join("blocked"), "")?;
+    let mut config = common::config();
+    config.opts.config_dir = Some(dir.clone());
+    config.partition = Some("blocked".to_string());
+    let error = common::run(config, Blocked).err().map(|e| e.to_string());
+    fs::remove_dir_all(dir)?;
+    let error = error.expect("Verso started without its partition");
+    assert!(
+        error.contains("partition"),
+        "The error doesn't name the partition: {error}"
+    );
+    Ok(())
+}
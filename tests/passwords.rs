@@ -0,0 +1,184 @@
+//! A login page is reported to the password manager hooks, filled with credentials as if they
+//! were typed, and its submission is reported with the values when they're shared.
+//!
+//! A local server serves the page, so the form is reported with its origin. The page records
+//! the input events of its fields, like frameworks tracking them do.
+//!
+//! A frame of the page forges a login form message, which isn't reported, and filling the form
+//! for another origin leaves it untouched.
+
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+use verso::{
+    config::Config,
+    passwords::{LoginForm, SubmittedCredentials},
+    webview::LoadStatus,
+    Verso, VersoBuilder,
+};
+use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
+
+const PAGE: &str = "<!DOCTYPE html>
+<form>
+  <input id=\"user\" type=\"email\">
+  <input id=\"pass\" type=\"password\">
+</form>
+<script>
+window.inputs = [];
+for (const field of document.querySelectorAll('input')) {
+  field.addEventListener('input', () => window.inputs.push(field.id));
+}
+document.querySelector('form').addEventListener('submit', (event) => event.preventDefault());
+</script>
+<iframe srcdoc=\"<script>
+prompt('VERSO_LOGIN_FORM:' + JSON.stringify(['forged', location.origin, { password_field: 'forged' }]));
+</script>\"></iframe>";
+
+const READ_FIELDS: &str = "
+return JSON.stringify([
+    document.getElementById('user').value,
+    document.getElementById('pass').value,
+    window.inputs,
+]);";
+
+/// The form is submitted in a timeout, since the page reports it with `prompt()`, which Verso
+/// can only answer once the test returns to the event loop.
+const SUBMIT: &str = "
+setTimeout(() => document.querySelector('form').requestSubmit());
+return true;";
+
+/// Forms and submissions reported to the hooks.
+#[derive(Default)]
+struct Reports {
+    forms: Vec<LoginForm>,
+    submitted: Vec<SubmittedCredentials>,
+}
+
+enum Step {
+    Starting,
+    Loading,
+    Filling,
+    Submitting,
+    Done,
+}
+
+struct Passwords {
+    url: ServoUrl,
+    step: Step,
+    reports: Rc<RefCell<Reports>>,
+    fields: Option<String>,
+}
+
+impl Scenario for Passwords {
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        config: Config,
+    ) -> verso::Result<Verso> {
+        let forms = self.reports.clone();
+        let submitted = self.reports.clone();
+        VersoBuilder::from_config(config)
+            .share_credentials(true)
+            .on_login_form(move |form| forms.borrow_mut().forms.push(form.clone()))
+            .on_credentials_submitted(move |credentials| {
+                submitted.borrow_mut().submitted.push(credentials.clone())
+            })
+            .build(event_loop, proxy)
+    }
+
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.current_raw_url() == Some(self.url.clone())
+                    && verso.load_status() == LoadStatus::Complete
+                    && !self.reports.borrow().forms.is_empty()
+                {
+                    let origin = self.reports.borrow().forms[0].origin.clone();
+                    let other = origin.replace("127.0.0.1", "localhost");
+                    verso.fill_credentials(&other, "ferris@example.com", "stolen");
+                    verso.fill_credentials(&origin, "ferris@example.com", "hunter2");
+                    self.step = Step::Filling;
+                }
+            }
+            Step::Filling => {
+                let WebDriverJSValue::String(fields) = evaluate(verso, READ_FIELDS) else {
+                    panic!("The fields weren't read");
+                };
+                if fields.contains("hunter2") {
+                    self.fields = Some(fields);
+                    evaluate(verso, SUBMIT);
+                    self.step = Step::Submitting;
+                }
+            }
+            Step::Submitting => {
+                if !self.reports.borrow().submitted.is_empty() {
+                    verso.shutdown();
+                    self.step = Step::Done;
+                }
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn evaluate(verso: &Verso, script: &str) -> WebDriverJSValue {
+    verso
+        .evaluate_script(script)
+        .expect("There's no web view")
+        .recv()
+        .expect("Script didn't answer")
+        .expect("Script failed")
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let port = common::serve(|_| PAGE)?;
+    let reports = Rc::new(RefCell::new(Reports::default()));
+    let scenario = Passwords {
+        url: ServoUrl::parse(&format!("http://127.0.0.1:{port}/"))?,
+        step: Step::Starting,
+        reports: reports.clone(),
+        fields: None,
+    };
+    let (scenario, _) = common::run(common::config(), scenario)?;
+
+    let form = LoginForm {
+        origin: format!("http://127.0.0.1:{port}"),
+        username_field: Some("user".to_string()),
+        password_field: "pass".to_string(),
+    };
+    let reports = reports.borrow();
+    assert_eq!(reports.forms, [form.clone()], "The login form wasn't found");
+    let fields: (String, String, Vec<String>) =
+        serde_json::from_str(&scenario.fields.expect("The fields weren't filled"))?;
+    assert_eq!(
+        fields,
+        (
+            "ferris@example.com".to_string(),
+            "hunter2".to_string(),
+            vec!["user".to_string(), "pass".to_string()],
+        ),
+        "The fields weren't filled as if they were typed"
+    );
+    let submitted = SubmittedCredentials {
+        form,
+        username: Some("ferris@example.com".to_string()),
+        password: Some("hunter2".to_string()),
+    };
+    assert_eq!(
+        reports.submitted,
+        [submitted],
+        "The submission wasn't reported"
+    );
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! A PDF opens in the built-in viewer, which lays out all its pages and renders the first one.
+//!
+//! pdfium must be next to the test executable or installed on the system, as the viewer leaves
+//! PDFs to Servo without it.
+
+mod common;
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+use verso::{webview::LoadStatus, Verso};
+
+/// Script returning the number of pages and the width of the image of the first page, which is
+/// 0 until it's rendered and loaded.
+const READ_VIEWER: &str = "
+const pages = document.querySelectorAll('.page');
+const first = pages[0];
+return JSON.stringify([pages.length, first && first.complete ? first.naturalWidth : 0]);";
+
+enum Step {
+    Starting,
+    Opening,
+    Rendering,
+    Done,
+}
+
+struct PdfViewer {
+    url: ServoUrl,
+    step: Step,
+    viewer: Option<ServoUrl>,
+    pages: usize,
+    width: u32,
+}
+
+impl Scenario for PdfViewer {
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Opening;
+                }
+            }
+            Step::Opening => {
+                let Some(url) = verso.current_raw_url() else {
+                    return;
+                };
+                if url.path().ends_with("/viewer.html")
+                    && verso.load_status() == LoadStatus::Complete
+                {
+                    self.viewer = Some(url);
+                    self.step = Step::Rendering;
+                }
+            }
+            Step::Rendering => {
+                let WebDriverJSValue::String(viewer) = evaluate(verso, READ_VIEWER) else {
+                    panic!("The viewer wasn't read");
+                };
+                let (pages, width): (usize, u32) =
+                    serde_json::from_str(&viewer).expect("The viewer wasn't read");
+                if width == 0 {
+                    return;
+                }
+                self.pages = pages;
+                self.width = width;
+                verso.shutdown();
+                self.step = Step::Done;
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn evaluate(verso: &Verso, script: &str) -> WebDriverJSValue {
+    verso
+        .evaluate_script(script)
+        .expect("There's no web view")
+        .recv()
+        .expect("Script didn't answer")
+        .expect("Script failed")
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let scenario = PdfViewer {
+        url: common::fixture("two_pages.pdf"),
+        step: Step::Starting,
+        viewer: None,
+        pages: 0,
+        width: 0,
+    };
+    let (scenario, _) = common::run(common::config(), scenario)?;
+
+    assert!(scenario.viewer.is_some(), "The viewer didn't open");
+    assert_eq!(scenario.pages, 2, "The viewer has another page count");
+    assert_eq!(
+        scenario.width % 256,
+        0,
+        "The first page wasn't rendered at a width step"
+    );
+    Ok(())
+}
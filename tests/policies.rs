@@ -0,0 +1,153 @@
+//! Policies take precedence over user preferences when mandatory, and the deny list blocks
+//! navigations of pages and of the embedder.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::Scenario;
+use servo_config::prefs::{pref_map, PrefValue};
+use servo_url::ServoUrl;
+use verso::{
+    policies::{Policies, PrefSource},
+    webview::LoadStatus,
+    Verso,
+};
+
+/// Time left to a denied navigation to happen.
+const SETTLE: Duration = Duration::from_secs(2);
+
+const POLICIES: &str = r#"{
+    "prefs": {
+        "dom.webgpu.enabled": false,
+        "layout.threads": { "value": 2, "level": "default" },
+        "media.autoplay.enabled": { "value": false, "level": "default" },
+        "bad.pref": [1, 2]
+    },
+    "homepage": { "value": "https://intranet.example.com/", "level": "default" },
+    "url_denylist": ["file:///*denied.html"],
+    "disable_devtools": true,
+    "unknown": 1
+}"#;
+
+fn check_precedence() {
+    let mut config = common::config();
+    config.policies = Policies::parse(POLICIES);
+    config.pref_overrides = vec![
+        ("dom.webgpu.enabled".into(), PrefValue::Bool(true)),
+        ("layout.threads".into(), PrefValue::Int(8)),
+    ];
+    let active = config.active_policies();
+    let pref = |key: &str| {
+        active
+            .prefs
+            .iter()
+            .find(|p| p.key == key)
+            .map(|p| (p.value.clone(), p.source, p.locked))
+    };
+    assert_eq!(
+        pref("dom.webgpu.enabled"),
+        Some((PrefValue::Bool(false), PrefSource::Policy, true)),
+        "A mandatory policy didn't override the user"
+    );
+    assert_eq!(
+        pref("layout.threads"),
+        Some((PrefValue::Int(8), PrefSource::User, false)),
+        "The user didn't override a default policy"
+    );
+    assert_eq!(
+        pref("media.autoplay.enabled"),
+        Some((PrefValue::Bool(false), PrefSource::Policy, false)),
+        "A default policy wasn't applied"
+    );
+    assert_eq!(active.pref_source("layout.columns"), PrefSource::Default);
+    assert_eq!(active.errors.len(), 2, "Errors: {:?}", active.errors);
+    assert!(active.devtools_disabled);
+
+    assert_eq!(active.homepage.1, PrefSource::Policy);
+    config.homepage = Some(ServoUrl::parse("https://example.org/").unwrap());
+    assert_eq!(
+        config.active_policies().homepage.1,
+        PrefSource::User,
+        "The user didn't override a default homepage"
+    );
+
+    let broken = Policies::parse("{ not json");
+    config.policies = broken;
+    let active = config.active_policies();
+    assert_eq!(active.errors.len(), 1, "Invalid JSON wasn't reported");
+    assert_eq!(active.pref_source("dom.webgpu.enabled"), PrefSource::User);
+}
+
+enum Step {
+    Starting,
+    Loading,
+    Settling(Instant),
+    Loading2(Instant),
+    Done,
+}
+
+struct DenyList {
+    url: ServoUrl,
+    denied: ServoUrl,
+    step: Step,
+    urls: Vec<Option<ServoUrl>>,
+}
+
+impl Scenario for DenyList {
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.load_status() == LoadStatus::Complete {
+                    self.step = Step::Settling(Instant::now());
+                }
+            }
+            // The page navigates to the denied page on load.
+            Step::Settling(since) if since.elapsed() > SETTLE => {
+                self.urls.push(verso.current_raw_url());
+                verso.load_url(self.denied.clone());
+                self.step = Step::Loading2(Instant::now());
+            }
+            Step::Loading2(since) if since.elapsed() > SETTLE => {
+                self.urls.push(verso.current_raw_url());
+                verso.shutdown();
+                self.step = Step::Done;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    check_precedence();
+
+    let mut config = common::config();
+    config.policies = Policies::parse(POLICIES);
+    config.pref_overrides = vec![("dom.webgpu.enabled".into(), PrefValue::Bool(true))];
+    let url = common::fixture("policy.html");
+    let scenario = DenyList {
+        url: url.clone(),
+        denied: common::fixture("denied.html"),
+        step: Step::Starting,
+        urls: Vec::new(),
+    };
+    let (scenario, _) = common::run(config, scenario)?;
+
+    assert_eq!(
+        pref_map().get("dom.webgpu.enabled"),
+        PrefValue::Bool(false),
+        "The mandatory preference wasn't applied"
+    );
+    assert_eq!(
+        scenario.urls,
+        vec![Some(url.clone()), Some(url)],
+        "A denied page was loaded"
+    );
+    Ok(())
+}
@@ -0,0 +1,178 @@
+//! A Verso instance is driven only through the remote control interface: requests without the
+//! right token are rejected, then a page is loaded, evaluated, zoomed, captured and reloaded,
+//! and Verso shuts down on request.
+
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use common::{Scenario, TIMEOUT};
+use serde_json::{json, Value};
+use servo_url::ServoUrl;
+use verso::{config::Config, remote_control::TOKEN_VARIABLE, Verso};
+use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
+
+const TOKEN: &str = "remote-control-test";
+
+/// Send a request and return the status and the body of the response.
+fn request(
+    address: SocketAddr,
+    method: &str,
+    path: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (u16, Vec<u8>) {
+    let mut stream = TcpStream::connect(address).expect("The server accepts connections");
+    let body = body.map(|body| body.to_string()).unwrap_or_default();
+    let authorization = token
+        .map(|token| format!("Authorization: Bearer {token}\r\n"))
+        .unwrap_or_default();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {address}\r\nConnection: close\r\n{authorization}\
+        Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .expect("The request is sent");
+    let mut response = Vec::new();
+    stream
+        .read_to_end(&mut response)
+        .expect("The response is received");
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .expect("The response has headers");
+    let head = String::from_utf8_lossy(&response[..split]);
+    let status = head
+        .split(' ')
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .expect("The response has a status");
+    (status, response[split + 4..].to_vec())
+}
+
+/// Send an authenticated request which must succeed, and return its JSON answer.
+fn call(address: SocketAddr, method: &str, path: &str, body: Option<Value>) -> Value {
+    let (status, body) = request(address, method, path, Some(TOKEN), body);
+    let body: Value = serde_json::from_slice(&body).expect("The answer is JSON");
+    assert_eq!(status, 200, "{method} {path} failed: {body}");
+    body
+}
+
+/// Poll the status until it satisfies the condition.
+fn wait_for(address: SocketAddr, what: &str, condition: impl Fn(&Value) -> bool) -> Value {
+    let start = Instant::now();
+    loop {
+        let (status, body) = request(address, "GET", "/status", Some(TOKEN), None);
+        if status == 200 {
+            let body = serde_json::from_slice(&body).expect("The status is JSON");
+            if condition(&body) {
+                return body;
+            }
+        }
+        assert!(start.elapsed() < TIMEOUT, "{what} within {TIMEOUT:?}");
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn drive(address: SocketAddr, url: ServoUrl) {
+    let (status, _) = request(address, "GET", "/status", None, None);
+    assert_eq!(status, 401, "A request without a token is accepted");
+    let (status, _) = request(address, "GET", "/status", Some("wrong"), None);
+    assert_eq!(status, 401, "A request with a wrong token is accepted");
+
+    wait_for(address, "The web view loads a page", |s| {
+        !s["url"].is_null()
+    });
+    call(
+        address,
+        "POST",
+        "/navigate",
+        Some(json!({ "url": url.as_str() })),
+    );
+    let status = wait_for(address, "The page loads", |s| {
+        s["url"] == url.as_str() && s["load_status"] == "Complete"
+    });
+    assert_eq!(status["title"], "Remote control");
+
+    let answer = call(
+        address,
+        "POST",
+        "/evaluate",
+        Some(json!({ "script": "return document.title.length + 1" })),
+    );
+    assert_eq!(answer["result"], 15);
+
+    call(address, "POST", "/zoom", Some(json!({ "page_zoom": 2.0 })));
+    let status = wait_for(address, "The page is zoomed", |s| s["page_zoom"] == 2.0);
+    assert_eq!(status["url"], url.as_str());
+
+    let (status, png) = request(address, "GET", "/screenshot", Some(TOKEN), None);
+    assert_eq!(status, 200, "The screenshot failed");
+    assert!(
+        png.starts_with(b"\x89PNG\r\n"),
+        "The screenshot isn't a PNG"
+    );
+
+    let (status, _) = request(address, "GET", "/navigate", Some(TOKEN), None);
+    assert_eq!(status, 405, "Navigating with GET is accepted");
+    let (status, _) = request(address, "GET", "/missing", Some(TOKEN), None);
+    assert_eq!(status, 404, "A missing endpoint is found");
+
+    call(address, "POST", "/reload", None);
+    wait_for(address, "The page reloads", |s| {
+        s["load_status"] == "Complete" && s["title"] == "Remote control"
+    });
+
+    call(address, "POST", "/shutdown", None);
+}
+
+struct RemoteControl {
+    url: ServoUrl,
+    client: Option<JoinHandle<()>>,
+}
+
+impl Scenario for RemoteControl {
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        config: Config,
+    ) -> verso::Result<Verso> {
+        let mut verso = Verso::new(event_loop, proxy, config)?;
+        let (address, token) = verso
+            .enable_remote_control(0)
+            .expect("The remote control starts");
+        assert_eq!(token, TOKEN, "The token isn't the one of the environment");
+        let url = self.url.clone();
+        self.client = Some(std::thread::spawn(move || drive(address, url)));
+        Ok(verso)
+    }
+
+    fn step(&mut self, verso: &mut Verso) {
+        // Don't wait for a shutdown request from a client which failed.
+        if self.client.as_ref().is_some_and(JoinHandle::is_finished) {
+            verso.shutdown();
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    std::env::set_var(TOKEN_VARIABLE, TOKEN);
+    let scenario = RemoteControl {
+        url: common::fixture("remote_control.html"),
+        client: None,
+    };
+    let (mut scenario, _) = common::run(common::config(), scenario)?;
+
+    let client = scenario.client.take().expect("The remote control started");
+    if let Err(panic) = client.join() {
+        std::panic::resume_unwind(panic);
+    }
+    Ok(())
+}
@@ -0,0 +1,231 @@
+//! A recorded session replays to the same embedder messages.
+//!
+//! A hidden window records a page being clicked and typed into by synthetic inputs. Another
+//! hidden window replays the recording at twice the speed, and the messages the page causes must
+//! match the recording once Servo's IDs are remapped. Nothing is recorded while replaying.
+//!
+//! The ID remapping and the validation of the speed are checked without an event loop first.
+
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use common::Scenario;
+use keyboard_types::{Code, Key, KeyState, KeyboardEvent};
+use servo_url::ServoUrl;
+use verso::{
+    config::Config,
+    recorder::{EventReplayer, IdTable, RecordedInput, ReplayReport},
+    webview::LoadStatus,
+    Verso,
+};
+use winit::{
+    dpi::PhysicalSize,
+    event_loop::{ActiveEventLoop, EventLoopProxy},
+};
+
+/// Title of the page once it's clicked and `a` is typed.
+const TYPED: &str = "clicked a";
+
+/// Messages which only depend on the page, unlike cursor changes which depend on hovering.
+const COMPARED: [&str; 4] = [
+    "LoadStart",
+    "LoadComplete",
+    "HistoryChanged",
+    "ChangePageTitle",
+];
+
+fn title(verso: &Verso) -> Option<String> {
+    verso.tab_info(verso.current_window_id()?)?.title
+}
+
+fn key(state: KeyState) -> KeyboardEvent {
+    KeyboardEvent {
+        state,
+        key: Key::Character("a".to_string()),
+        code: Code::KeyA,
+        ..Default::default()
+    }
+}
+
+enum Step {
+    Loading,
+    Typing,
+    Done,
+}
+
+struct Record {
+    url: ServoUrl,
+    step: Step,
+}
+
+impl Scenario for Record {
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Loading => {
+                if verso.current_raw_url().as_ref() != Some(&self.url)
+                    || verso.load_status() != LoadStatus::Complete
+                {
+                    return;
+                }
+                let window = verso.current_window_id().expect("There's no window");
+                for input in [
+                    RecordedInput::CursorMoved { x: 400.0, y: 400.0 },
+                    RecordedInput::MouseInput {
+                        pressed: true,
+                        button: 0,
+                    },
+                    RecordedInput::MouseInput {
+                        pressed: false,
+                        button: 0,
+                    },
+                ] {
+                    verso.handle_winit_window_event(window, input.to_window_event());
+                }
+                verso.handle_keyboard_event(window, key(KeyState::Down), Some("a"));
+                verso.handle_keyboard_event(window, key(KeyState::Up), None);
+                self.step = Step::Typing;
+            }
+            Step::Typing => {
+                if title(verso).as_deref() == Some(TYPED) {
+                    verso.shutdown();
+                    self.step = Step::Done;
+                }
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+struct Replay {
+    path: PathBuf,
+    report: Option<ReplayReport>,
+}
+
+impl Scenario for Replay {
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        config: Config,
+    ) -> verso::Result<Verso> {
+        let mut verso = Verso::new(event_loop, proxy, config)?;
+        verso
+            .replay(&self.path, 2.0)
+            .expect("Recording can't be replayed");
+        Ok(verso)
+    }
+
+    fn step(&mut self, verso: &mut Verso) {
+        if self.report.is_none() && !verso.is_replaying() && title(verso).as_deref() == Some(TYPED)
+        {
+            self.report = verso.finish_replay();
+            verso.shutdown();
+        }
+    }
+}
+
+fn compared(messages: &[String]) -> Vec<&str> {
+    messages
+        .iter()
+        .filter_map(|message| message.split_once(' ').map(|(_, message)| message))
+        .filter(|message| COMPARED.iter().any(|kind| message.starts_with(kind)))
+        .collect()
+}
+
+fn check_id_table() {
+    let mut ids = IdTable::new();
+    let first =
+        "LoadStart(PipelineId { namespace_id: PipelineNamespaceId(1), index: PipelineIndex(7) })";
+    let second =
+        "LoadStart(PipelineId { namespace_id: PipelineNamespaceId(1), index: PipelineIndex(9) })";
+    assert_eq!(ids.remap(first), "LoadStart(PipelineId#0)");
+    assert_eq!(ids.remap(second), "LoadStart(PipelineId#1)");
+    assert_eq!(ids.remap(first), "LoadStart(PipelineId#0)");
+    assert_eq!(
+        ids.remap("ChangePageTitle(Some(\"Id (\"))"),
+        "ChangePageTitle(Some(\"Id (\"))",
+        "A message without IDs is changed"
+    );
+    assert_eq!(ids.id("WebView", "(1,1)"), "WebView#0");
+    assert_eq!(ids.id("WebView", "(1,5)"), "WebView#1");
+
+    let report = ReplayReport {
+        recorded: vec!["a".to_string(), "b".to_string()],
+        replayed: vec!["a".to_string(), "b".to_string()],
+    };
+    assert_eq!(report.first_divergence(), None);
+    let report = ReplayReport {
+        recorded: vec!["a".to_string(), "b".to_string()],
+        replayed: vec!["a".to_string()],
+    };
+    assert_eq!(report.first_divergence(), Some(1));
+}
+
+fn check_speed(path: &Path) {
+    fs::write(path, "").expect("Empty recording can't be written");
+    for speed in [0.0, -1.0, 1e-9, f32::NAN, f32::INFINITY] {
+        assert!(
+            EventReplayer::new(path, speed).is_err(),
+            "Replay speed {speed} is accepted"
+        );
+    }
+    assert!(EventReplayer::new(path, 2.0).is_ok());
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    check_id_table();
+    let dir = std::env::temp_dir().join(format!("verso-replay-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let recording = dir.join("recording.jsonl");
+    let rerecording = dir.join("rerecording.jsonl");
+    check_speed(&recording);
+
+    let url = common::fixture("replay.html");
+    let config = || {
+        let mut config = common::config();
+        config.homepage = Some(url.clone());
+        config.window.headless = Some(PhysicalSize::new(800, 600));
+        config
+    };
+
+    let mut record = config();
+    record.record_events = Some(recording.clone());
+    let scenario = Record {
+        url: url.clone(),
+        step: Step::Loading,
+    };
+    // Dropping Verso flushes the recording.
+    drop(common::run(record, scenario)?);
+
+    let mut replay = config();
+    replay.record_events = Some(rerecording.clone());
+    let scenario = Replay {
+        path: recording,
+        report: None,
+    };
+    let (scenario, verso) = common::run(replay, scenario)?;
+    drop(verso);
+
+    let report = scenario.report.expect("Replay didn't finish");
+    let recorded = compared(&report.recorded);
+    assert!(
+        recorded.contains(&format!("ChangePageTitle(Some({TYPED:?}))").as_str()),
+        "The recording has no typed title: {recorded:?}"
+    );
+    assert_eq!(
+        recorded,
+        compared(&report.replayed),
+        "The replay diverged from the recording"
+    );
+    let rerecorded = fs::read_to_string(&rerecording)?;
+    assert!(
+        !rerecorded.contains("\"kind\":\"input\"") && !rerecorded.contains("\"kind\":\"key\""),
+        "Replayed inputs were recorded again"
+    );
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Buffer sizes and input coordinates with fractional scale factors, like the 125% and 150%
+//! scaling of Wayland desktops.
+//!
+//! Both are computed from sizes and events only, so this runs without an event loop.
+
+use verso::{
+    config::AxisLockMode,
+    flow::PageFlow,
+    input::{convert_input, InputEvent, InputOptions, InputState},
+    window::rescaled_size,
+};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{DeviceId, MouseScrollDelta, TouchPhase, WindowEvent},
+};
+
+fn check_buffer_sizes() {
+    let size = PhysicalSize::new(1000, 800);
+    for (scale_factor, expected) in [
+        (1.25, PhysicalSize::new(1250, 1000)),
+        (1.5, PhysicalSize::new(1500, 1200)),
+        (1.75, PhysicalSize::new(1750, 1400)),
+        (2.0, PhysicalSize::new(2000, 1600)),
+    ] {
+        assert_eq!(
+            rescaled_size(size, 1.0, scale_factor),
+            expected,
+            "The buffer at {scale_factor} isn't the exact size"
+        );
+        assert_eq!(
+            rescaled_size(expected, scale_factor, 1.0),
+            size,
+            "The buffer back from {scale_factor} isn't the original size"
+        );
+    }
+
+    // Odd sizes don't divide evenly, and are rounded rather than truncated or ceiled.
+    let odd = PhysicalSize::new(1001, 801);
+    assert_eq!(rescaled_size(odd, 1.0, 1.25), PhysicalSize::new(1251, 1001));
+    assert_eq!(rescaled_size(odd, 1.0, 1.5), PhysicalSize::new(1502, 1202));
+    let there = rescaled_size(PhysicalSize::new(1251, 1001), 1.25, 1.75);
+    assert_eq!(there, PhysicalSize::new(1751, 1401));
+    assert_eq!(
+        rescaled_size(there, 1.75, 1.25),
+        PhysicalSize::new(1251, 1001),
+        "Changing the scale factor back and forth drifts the size"
+    );
+}
+
+fn options(scale_factor: f64) -> InputOptions {
+    InputOptions {
+        scale_factor,
+        wheel_axis_lock: AxisLockMode::Never,
+        snap_scroll: false,
+        shift: false,
+        flow: PageFlow::default(),
+    }
+}
+
+fn check_input_coordinates() {
+    for (scale_factor, cursor, delta, logical) in [
+        (1.25, (125.0, 250.0), (25.0, -50.0), (20.0, -40.0)),
+        (1.5, (150.0, 75.0), (-30.0, 45.0), (-20.0, 30.0)),
+    ] {
+        let options = options(scale_factor);
+        let mut state = InputState::default();
+        // SAFETY: The dummy device ID only fills the field of the events.
+        let device_id = unsafe { DeviceId::dummy() };
+        let moved = WindowEvent::CursorMoved {
+            device_id,
+            position: PhysicalPosition::new(cursor.0, cursor.1),
+        };
+        convert_input(&moved, &mut state, &options);
+        assert_eq!(
+            (state.cursor.x, state.cursor.y),
+            cursor,
+            "The cursor isn't kept in device pixels at {scale_factor}"
+        );
+
+        let wheel = WindowEvent::MouseWheel {
+            device_id,
+            delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(delta.0, delta.1)),
+            phase: TouchPhase::Moved,
+        };
+        let events = convert_input(&wheel, &mut state, &options);
+        match events[..] {
+            [InputEvent::Wheel(wheel, point), InputEvent::Scroll(..)] => {
+                assert_eq!(
+                    (wheel.x, wheel.y),
+                    logical,
+                    "The wheel delta isn't in CSS pixels at {scale_factor}"
+                );
+                assert_eq!((point.x as f64, point.y as f64), cursor);
+            }
+            _ => panic!("The wheel at {scale_factor} isn't converted to a wheel and a scroll"),
+        }
+    }
+}
+
+fn main() {
+    check_buffer_sizes();
+    check_input_coordinates();
+}
@@ -0,0 +1,149 @@
+//! Scroll deltas snapped to whole device pixels carry their fractions over, so sequences of tiny
+//! deltas scroll the exact pixel total, and the fractions are dropped when the direction reverses
+//! or a new gesture starts.
+//!
+//! The accumulator is only reachable through the input conversion, which runs without an event
+//! loop.
+
+use verso::{
+    config::AxisLockMode,
+    flow::PageFlow,
+    input::{convert_input, InputEvent, InputOptions, InputState},
+};
+use webrender_api::ScrollLocation;
+use winit::{
+    dpi::PhysicalPosition,
+    event::{DeviceId, MouseScrollDelta, TouchPhase, WindowEvent},
+};
+
+fn options(snap_scroll: bool) -> InputOptions {
+    InputOptions {
+        scale_factor: 1.0,
+        wheel_axis_lock: AxisLockMode::Never,
+        snap_scroll,
+        shift: false,
+        flow: PageFlow::default(),
+    }
+}
+
+/// Scroll by the pixel delta, and return the delta scrolled.
+fn scroll(
+    state: &mut InputState,
+    options: &InputOptions,
+    phase: TouchPhase,
+    (x, y): (f64, f64),
+) -> (f32, f32) {
+    // SAFETY: The dummy device ID only fills the field of the events.
+    let device_id = unsafe { DeviceId::dummy() };
+    let wheel = WindowEvent::MouseWheel {
+        device_id,
+        delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(x, y)),
+        phase,
+    };
+    match convert_input(&wheel, state, options)[..] {
+        [InputEvent::Wheel(..), InputEvent::Scroll(ScrollLocation::Delta(delta), ..)] => {
+            (delta.x, delta.y)
+        }
+        _ => panic!("The wheel isn't converted to a wheel and a scroll delta"),
+    }
+}
+
+/// Scroll by the deltas in one gesture and return the deltas scrolled.
+fn gesture(deltas: &[(f64, f64)]) -> Vec<(f32, f32)> {
+    let options = options(true);
+    let mut state = InputState::default();
+    deltas
+        .iter()
+        .enumerate()
+        .map(|(i, &delta)| {
+            let phase = if i == 0 {
+                TouchPhase::Started
+            } else {
+                TouchPhase::Moved
+            };
+            scroll(&mut state, &options, phase, delta)
+        })
+        .collect()
+}
+
+fn check_exact_totals() {
+    for (delta, count, total) in [
+        (0.1, 10, 1.0),
+        (0.1, 100, 10.0),
+        (0.3, 10, 3.0),
+        (0.25, 8, 2.0),
+        (1.0 / 3.0, 9, 3.0),
+        (-0.1, 30, -3.0),
+        (-0.7, 10, -7.0),
+    ] {
+        let scrolled = gesture(&vec![(0.0, delta); count]);
+        for &(x, y) in &scrolled {
+            assert_eq!(x, 0.0, "{count} deltas of {delta} scrolled the other axis");
+            assert_eq!(
+                y,
+                y.trunc(),
+                "{count} deltas of {delta} scrolled a fraction"
+            );
+        }
+        let sum: f32 = scrolled.iter().map(|&(_, y)| y).sum();
+        assert_eq!(
+            sum, total,
+            "{count} deltas of {delta} scrolled {scrolled:?}"
+        );
+    }
+
+    // The pixel is only scrolled once the deltas add up to it.
+    let scrolled: Vec<f32> = gesture(&[(0.0, 0.1); 10]).iter().map(|&(_, y)| y).collect();
+    assert_eq!(scrolled, [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+
+    // Large deltas keep their fractions too.
+    let scrolled: Vec<f32> = gesture(&[(0.0, 2.5), (0.0, 2.5), (0.0, 2.5)])
+        .iter()
+        .map(|&(_, y)| y)
+        .collect();
+    assert_eq!(scrolled, [2.0, 3.0, 2.0]);
+
+    // Each axis has its own remainder.
+    let scrolled = gesture(&[(0.5, 0.75); 4]);
+    assert_eq!(scrolled, [(0.0, 0.0), (1.0, 1.0), (0.0, 1.0), (1.0, 1.0)]);
+}
+
+fn check_resets() {
+    // Reversing drops the remainder of the other direction.
+    let scrolled: Vec<f32> = gesture(&[(0.0, 0.6), (0.0, -0.6), (0.0, -0.6)])
+        .iter()
+        .map(|&(_, y)| y)
+        .collect();
+    assert_eq!(scrolled, [0.0, 0.0, -1.0]);
+
+    // A new gesture drops the remainder of the last one.
+    let options = options(true);
+    let mut state = InputState::default();
+    let scrolled: Vec<f32> = [TouchPhase::Started, TouchPhase::Started, TouchPhase::Moved]
+        .into_iter()
+        .map(|phase| scroll(&mut state, &options, phase, (0.0, 0.6)).1)
+        .collect();
+    assert_eq!(scrolled, [0.0, 0.0, 1.0]);
+
+    // Turning snapping off passes fractions through and drops the remainder.
+    let mut state = InputState::default();
+    let snapped = self::options(true);
+    let unsnapped = self::options(false);
+    assert_eq!(
+        scroll(&mut state, &snapped, TouchPhase::Started, (0.0, 0.6)),
+        (0.0, 0.0)
+    );
+    assert_eq!(
+        scroll(&mut state, &unsnapped, TouchPhase::Moved, (0.0, 0.3)),
+        (0.0, 0.3)
+    );
+    assert_eq!(
+        scroll(&mut state, &snapped, TouchPhase::Moved, (0.0, 0.6)),
+        (0.0, 0.0)
+    );
+}
+
+fn main() {
+    check_exact_totals();
+    check_resets();
+}
@@ -0,0 +1,88 @@
+//! Pages read the battery status of the sensor provider with `navigator.getBattery()` once it's
+//! enabled.
+
+mod common;
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+use verso::{
+    sensors::{BatteryStatus, MockSensorProvider},
+    webview::LoadStatus,
+    Verso,
+};
+
+const BATTERY: BatteryStatus = BatteryStatus {
+    level: 0.25,
+    charging: false,
+};
+
+enum Step {
+    Starting,
+    Loading,
+    Reading,
+    Done,
+}
+
+struct Battery {
+    url: ServoUrl,
+    step: Step,
+    title: Option<String>,
+}
+
+impl Scenario for Battery {
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.set_sensor_provider(Box::new(MockSensorProvider {
+                        battery: Some(BATTERY),
+                    }));
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.load_status() == LoadStatus::Complete {
+                    self.step = Step::Reading;
+                }
+            }
+            Step::Reading => {
+                let title = verso
+                    .evaluate_script("return document.title;")
+                    .expect("There's no web view")
+                    .recv()
+                    .expect("Script didn't answer");
+                // The page sets its title once it read the battery.
+                if let Ok(WebDriverJSValue::String(title)) = title {
+                    if title != "Battery" {
+                        self.title = Some(title);
+                        verso.shutdown();
+                        self.step = Step::Done;
+                    }
+                }
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = common::config();
+    config.battery_status = true;
+    let scenario = Battery {
+        url: common::fixture("battery.html"),
+        step: Step::Starting,
+        title: None,
+    };
+    let (scenario, _) = common::run(config, scenario)?;
+
+    let title = scenario.title.expect("The page didn't read the battery");
+    let (level, charging): (f64, bool) = serde_json::from_str(&title)?;
+    assert_eq!(level, BATTERY.level, "The page read another level");
+    assert_eq!(
+        charging, BATTERY.charging,
+        "The page read another charging state"
+    );
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Shortcuts parse from their text, resolve conflicts of `shortcuts.json` deterministically, use
+//! Cmd on macOS and Ctrl elsewhere, and hide both key presses of a shortcut from the page.
+
+use std::fs;
+
+use keyboard_types::{Code, Key, KeyState, KeyboardEvent, Modifiers};
+use verso::{
+    keyboard::CMD_OR_CONTROL,
+    shortcuts::{
+        Chord, ChordParseError, HeldShortcuts, KeyOutcome, ShortcutAction, ShortcutConflict,
+        ShortcutMap,
+    },
+};
+
+fn character(c: &str) -> Key {
+    Key::Character(c.to_string())
+}
+
+fn check_parsing() {
+    for (text, modifiers, key) in [
+        (
+            "Ctrl+Shift+R",
+            Modifiers::CONTROL | Modifiers::SHIFT,
+            character("r"),
+        ),
+        ("Cmd+L", Modifiers::META, character("l")),
+        ("command+l", Modifiers::META, character("l")),
+        ("Option+ArrowLeft", Modifiers::ALT, Key::ArrowLeft),
+        ("CmdOrCtrl+F5", CMD_OR_CONTROL, Key::F5),
+        ("Ctrl++", Modifiers::CONTROL, character("+")),
+        ("F5", Modifiers::empty(), Key::F5),
+        (" Ctrl + p ", Modifiers::CONTROL, character("p")),
+    ] {
+        assert_eq!(
+            text.parse::<Chord>(),
+            Ok(Chord::new(modifiers, key)),
+            "{text} is parsed wrong"
+        );
+    }
+    assert_eq!(
+        "Ctrl+".parse::<Chord>(),
+        Err(ChordParseError::MissingKey("Ctrl+".into()))
+    );
+    assert_eq!(
+        "Hyper+R".parse::<Chord>(),
+        Err(ChordParseError::UnknownModifier(
+            "Hyper+R".into(),
+            "Hyper".into()
+        ))
+    );
+    assert_eq!(
+        "Ctrl+Foo".parse::<Chord>(),
+        Err(ChordParseError::UnknownKey("Ctrl+Foo".into(), "Foo".into()))
+    );
+    for text in ["Ctrl+Shift+R", "Alt+ArrowLeft", "Cmd+L", "F5"] {
+        let chord: Chord = text.parse().expect("Chord isn't parsed");
+        assert_eq!(chord.to_string(), text, "Chord isn't displayed as parsed");
+    }
+}
+
+fn check_conflicts() {
+    assert!(
+        ShortcutMap::from_json(r#"{ "Ctrl+J": "Bogus" }"#).is_err(),
+        "An unknown action is accepted"
+    );
+
+    let (map, conflicts) = ShortcutMap::from_json(
+        r#"{ "Ctrl+K": "Reload", "ctrl+k": "Print", "Ctrl+": "Back", "CmdOrCtrl+R": "Print" }"#,
+    )
+    .expect("Shortcuts aren't parsed");
+    let ctrl_k = Chord::new(Modifiers::CONTROL, character("k"));
+    // Bindings are applied in sorted order, so the lower case one wins.
+    assert_eq!(
+        conflicts,
+        vec![ShortcutConflict {
+            chord: ctrl_k.clone(),
+            overridden: ShortcutAction::Reload,
+            action: ShortcutAction::Print,
+        }],
+        "Overriding a default shortcut is a conflict, or a conflict is missed"
+    );
+    assert_eq!(map.get(&ctrl_k), Some(ShortcutAction::Print));
+    assert_eq!(
+        map.get(&Chord::new(CMD_OR_CONTROL, character("r"))),
+        Some(ShortcutAction::Print),
+        "A default shortcut isn't overridden"
+    );
+    assert_eq!(
+        map.get(&Chord::new(Modifiers::empty(), Key::F5)),
+        Some(ShortcutAction::Reload),
+        "Other default shortcuts are dropped"
+    );
+
+    let mut map = ShortcutMap::default();
+    let ctrl_b = Chord::new(CMD_OR_CONTROL, character("b"));
+    assert_eq!(map.set(ShortcutAction::Back, ctrl_b.clone()), None);
+    assert_eq!(
+        map.set(ShortcutAction::Forward, ctrl_b.clone()),
+        Some(ShortcutAction::Back)
+    );
+    assert_eq!(map.get(&ctrl_b), Some(ShortcutAction::Forward));
+    assert_eq!(
+        map.get(&Chord::new(Modifiers::ALT, Key::ArrowRight)),
+        None,
+        "The previous shortcut of the action is kept"
+    );
+}
+
+fn check_platform_defaults() {
+    let map = ShortcutMap::default();
+    let (primary, other) = if cfg!(target_os = "macos") {
+        (Modifiers::META, Modifiers::CONTROL)
+    } else {
+        (Modifiers::CONTROL, Modifiers::META)
+    };
+    assert_eq!(CMD_OR_CONTROL, primary);
+    for (key, action) in [
+        ("r", ShortcutAction::Reload),
+        ("l", ShortcutAction::FocusAddress),
+        ("p", ShortcutAction::Print),
+        ("0", ShortcutAction::ResetZoom),
+    ] {
+        assert_eq!(map.get(&Chord::new(primary, character(key))), Some(action));
+        assert_eq!(map.get(&Chord::new(other, character(key))), None);
+    }
+}
+
+fn check_loading() {
+    let dir = std::env::temp_dir().join(format!("verso-shortcuts-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("Directory can't be created");
+    let path = dir.join("shortcuts.json");
+    assert_eq!(ShortcutMap::load(&path), ShortcutMap::default());
+    fs::write(&path, "{ not json").expect("File can't be written");
+    assert_eq!(ShortcutMap::load(&path), ShortcutMap::default());
+    fs::write(&path, r#"{ "Alt+P": "Print" }"#).expect("File can't be written");
+    assert_eq!(
+        ShortcutMap::load(&path).get(&Chord::new(Modifiers::ALT, character("p"))),
+        Some(ShortcutAction::Print)
+    );
+    fs::remove_dir_all(dir).expect("Directory can't be removed");
+}
+
+fn key(state: KeyState, key: Key, code: Code, modifiers: Modifiers) -> KeyboardEvent {
+    KeyboardEvent {
+        state,
+        key,
+        code,
+        modifiers,
+        ..Default::default()
+    }
+}
+
+fn check_held_keys() {
+    use KeyState::{Down, Up};
+
+    let map = ShortcutMap::default();
+    let mut held = HeldShortcuts::default();
+    let control = |state| {
+        key(
+            state,
+            Key::Control,
+            Code::ControlLeft,
+            if state == Down {
+                CMD_OR_CONTROL
+            } else {
+                Modifiers::empty()
+            },
+        )
+    };
+    let r = |state| key(state, character("r"), Code::KeyR, CMD_OR_CONTROL);
+    assert_eq!(held.handle(&map, &control(Down)), KeyOutcome::Forward);
+    assert_eq!(
+        held.handle(&map, &r(Down)),
+        KeyOutcome::Trigger(ShortcutAction::Reload)
+    );
+    assert_eq!(
+        held.handle(&map, &r(Up)),
+        KeyOutcome::Suppress,
+        "The release of a shortcut reaches the page"
+    );
+    assert_eq!(held.handle(&map, &control(Up)), KeyOutcome::Forward);
+    let a = |state| key(state, character("a"), Code::KeyA, Modifiers::empty());
+    assert_eq!(held.handle(&map, &a(Down)), KeyOutcome::Forward);
+    assert_eq!(held.handle(&map, &a(Up)), KeyOutcome::Forward);
+    assert_eq!(
+        held.handle(&map, &r(Up)),
+        KeyOutcome::Forward,
+        "A key is held back twice"
+    );
+
+    held.handle(&map, &r(Down));
+    held.clear();
+    assert_eq!(held.handle(&map, &r(Up)), KeyOutcome::Forward);
+}
+
+fn main() {
+    check_parsing();
+    check_conflicts();
+    check_platform_defaults();
+    check_loading();
+    check_held_keys();
+}
@@ -0,0 +1,44 @@
+//! Quitting while a page waits for a dialog answers the dialog and shuts Servo down cleanly.
+
+mod common;
+
+use common::Scenario;
+use servo_url::ServoUrl;
+use verso::Verso;
+
+struct Shutdown {
+    url: ServoUrl,
+    loaded: bool,
+    shut_down: bool,
+}
+
+impl Scenario for Shutdown {
+    fn step(&mut self, verso: &mut Verso) {
+        if !self.loaded && verso.current_webview_id().is_some() {
+            verso.load_url(self.url.clone());
+            self.loaded = true;
+        }
+        if !self.shut_down && verso.dialog_info().is_some() {
+            verso.shutdown();
+            self.shut_down = true;
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = common::config();
+    config.hold_dialogs = true;
+    let scenario = Shutdown {
+        url: common::fixture("prompt.html"),
+        loaded: false,
+        shut_down: false,
+    };
+    let (scenario, verso) = common::run(config, scenario)?;
+
+    assert!(scenario.shut_down, "The page never opened its dialog");
+    assert!(
+        verso.dialog_info().is_none(),
+        "The dialog wasn't answered before shutting down"
+    );
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! Verso returns an error instead of panicking when its window can't be created, and the event
+//! loop keeps running so another Verso can start.
+//!
+//! The failing window is a headless window without pixels, which X11 and Wayland refuse, like
+//! the EGL surface of its rendering context.
+
+mod common;
+
+use common::Scenario;
+use verso::{config::Config, webview::LoadStatus, Verso};
+use winit::{
+    dpi::PhysicalSize,
+    event_loop::{ActiveEventLoop, EventLoopProxy},
+};
+
+#[derive(Default)]
+struct Startup {
+    error: Option<String>,
+    loaded: bool,
+}
+
+impl Scenario for Startup {
+    fn start(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        proxy: EventLoopProxy<()>,
+        config: Config,
+    ) -> verso::Result<Verso> {
+        let mut empty = config.clone();
+        empty.window = empty.window.headless(PhysicalSize::new(0, 0));
+        match Verso::new(event_loop, proxy.clone(), empty) {
+            Ok(_) => panic!("Verso started with a window without pixels"),
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        Verso::new(event_loop, proxy, config)
+    }
+
+    fn step(&mut self, verso: &mut Verso) {
+        if self.loaded || verso.current_webview_id().is_none() {
+            return;
+        }
+        if verso.load_status() == LoadStatus::Complete {
+            self.loaded = true;
+            verso.shutdown();
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (scenario, _) = common::run(common::config(), Startup::default())?;
+
+    let error = scenario.error.expect("The failing start wasn't tried");
+    assert!(!error.is_empty(), "The error has no message");
+    assert!(scenario.loaded, "Verso didn't start after the failure");
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! An origin which writes to local storage reports its usage, and reports none once its data is
+//! cleared.
+//!
+//! A local server serves a page storing 1000 characters in `localStorage`, as pages of `file`
+//! URLs have an opaque origin without local storage.
+
+mod common;
+
+use std::sync::mpsc::{self, Receiver};
+
+use common::Scenario;
+use servo_url::ServoUrl;
+use verso::{
+    storage::{OriginUsage, StorageKind},
+    webview::LoadStatus,
+    Verso,
+};
+
+enum Step {
+    Starting,
+    Loading,
+    Measuring(Receiver<Vec<OriginUsage>>),
+    Clearing(Receiver<Vec<OriginUsage>>),
+    Done,
+}
+
+struct Storage {
+    url: ServoUrl,
+    step: Step,
+    before: Option<OriginUsage>,
+    after: Option<OriginUsage>,
+}
+
+impl Scenario for Storage {
+    fn step(&mut self, verso: &mut Verso) {
+        let origin = self.url.clone();
+        let find = |usage: Vec<OriginUsage>| usage.into_iter().find(|u| u.origin == origin);
+        match &self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.load_status() == LoadStatus::Complete
+                    && verso.current_raw_url().as_ref() == Some(&self.url)
+                {
+                    self.step = Step::Measuring(request_usage(verso));
+                }
+            }
+            Step::Measuring(receiver) => {
+                let Ok(usage) = receiver.try_recv() else {
+                    return;
+                };
+                self.before = find(usage);
+                verso.clear_origin_data(
+                    &self.url,
+                    &[StorageKind::Cookies, StorageKind::LocalStorage],
+                );
+                self.step = Step::Clearing(request_usage(verso));
+            }
+            Step::Clearing(receiver) => {
+                let Ok(usage) = receiver.try_recv() else {
+                    return;
+                };
+                self.after = find(usage);
+                verso.shutdown();
+                self.step = Step::Done;
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn request_usage(verso: &Verso) -> Receiver<Vec<OriginUsage>> {
+    let (sender, receiver) = mpsc::channel();
+    verso.storage_usage(move |usage| {
+        let _ = sender.send(usage);
+    });
+    receiver
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let page = std::fs::read_to_string(common::root().join("tests/fixtures/storage.html"))?;
+    let port = common::serve(move |_| page.clone())?;
+    let scenario = Storage {
+        url: ServoUrl::parse(&format!("http://127.0.0.1:{port}/"))?,
+        step: Step::Starting,
+        before: None,
+        after: None,
+    };
+    let (scenario, _) = common::run(common::config(), scenario)?;
+
+    let before = scenario.before.expect("The origin wasn't reported");
+    assert_eq!(before.error, None, "The origin has a storage error");
+    assert!(
+        before.local_storage_bytes >= 1000,
+        "The local storage usage is {}",
+        before.local_storage_bytes
+    );
+    let after = scenario
+        .after
+        .expect("The origin wasn't reported after clearing");
+    assert_eq!(
+        after.local_storage_bytes, 0,
+        "The local storage wasn't cleared"
+    );
+    assert_eq!(after.total_bytes(), 0, "The origin still uses storage");
+    Ok(())
+}
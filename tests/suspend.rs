@@ -0,0 +1,119 @@
+//! A suspended web view resumes from its serialized snapshot with its URL, scroll position and
+//! form fields.
+
+mod common;
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_url::ServoUrl;
+use verso::{suspend::WebViewSnapshot, webview::LoadStatus, Verso};
+
+const FILL_FORM: &str = "
+document.getElementById('name').value = 'Ferris';
+document.getElementById('subscribe').checked = true;
+document.getElementById('secret').value = 'hunter2';
+scrollTo(0, 1200);
+return scrollY;";
+
+const READ_PAGE: &str = "
+return JSON.stringify([
+    location.href,
+    scrollY,
+    document.getElementById('name').value,
+    document.getElementById('subscribe').checked,
+    document.getElementById('secret').value,
+]);";
+
+enum Step {
+    Starting,
+    Loading,
+    Resuming,
+    Done,
+}
+
+struct Suspend {
+    url: ServoUrl,
+    step: Step,
+    snapshot: Option<WebViewSnapshot>,
+    page: Option<String>,
+}
+
+impl Scenario for Suspend {
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.load_status() != LoadStatus::Complete {
+                    return;
+                }
+                let scrolled = evaluate(verso, FILL_FORM);
+                assert!(
+                    matches!(scrolled, WebDriverJSValue::Number(y) if y > 0.0),
+                    "The page didn't scroll: {scrolled:?}"
+                );
+                let id = verso.current_webview_id().expect("Web view is gone");
+                let snapshot = verso
+                    .suspend_webview(id)
+                    .expect("Web view wasn't suspended");
+                let json = serde_json::to_string(&snapshot).expect("Snapshot isn't serializable");
+                let snapshot: WebViewSnapshot =
+                    serde_json::from_str(&json).expect("Snapshot isn't deserializable");
+                verso
+                    .resume_webview(&snapshot)
+                    .expect("Web view wasn't resumed");
+                self.snapshot = Some(snapshot);
+                self.step = Step::Resuming;
+            }
+            Step::Resuming => {
+                if verso.load_status() != LoadStatus::Complete {
+                    return;
+                }
+                // The restore script runs before this one, as both go to the same script thread.
+                if let WebDriverJSValue::String(page) = evaluate(verso, READ_PAGE) {
+                    self.page = Some(page);
+                }
+                verso.shutdown();
+                self.step = Step::Done;
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn evaluate(verso: &Verso, script: &str) -> WebDriverJSValue {
+    verso
+        .evaluate_script(script)
+        .expect("There's no web view")
+        .recv()
+        .expect("Script didn't answer")
+        .expect("Script failed")
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let url = common::fixture("form.html");
+    let scenario = Suspend {
+        url: url.clone(),
+        step: Step::Starting,
+        snapshot: None,
+        page: None,
+    };
+    let (scenario, _) = common::run(common::config(), scenario)?;
+
+    let snapshot = scenario.snapshot.expect("Web view wasn't suspended");
+    assert_eq!(snapshot.url, url, "The snapshot has another URL");
+    let state = snapshot.page.expect("The snapshot has no page state");
+    assert!(state.y > 0.0, "The snapshot has no scroll position");
+    let page: (String, f64, String, bool, String) =
+        serde_json::from_str(&scenario.page.expect("The resumed page wasn't read"))?;
+    assert_eq!(page.0, url.as_str(), "The resumed page has another URL");
+    assert_eq!(page.1, state.y, "The scroll position wasn't restored");
+    assert_eq!(page.2, "Ferris", "The text field wasn't restored");
+    assert!(page.3, "The checkbox wasn't restored");
+    assert_eq!(page.4, "", "The password field was restored");
+    Ok(())
+}
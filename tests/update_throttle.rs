@@ -0,0 +1,81 @@
+//! A burst of 1000 title changes, one per millisecond, is applied a bounded number of times, and
+//! the last title is applied once the interval elapsed. Other actions pass through.
+//!
+//! The throttle only compares instants, so this runs with synthetic time and without an event
+//! loop.
+
+use std::time::{Duration, Instant};
+
+use verso::dispatch::{Action, UpdateThrottle, DEFAULT_TITLE_UPDATE_INTERVAL};
+
+fn titles(actions: &[Action]) -> Vec<Option<String>> {
+    actions
+        .iter()
+        .filter_map(|action| match action {
+            Action::SetTitle(title) => Some(title.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn main() {
+    let interval = DEFAULT_TITLE_UPDATE_INTERVAL;
+    let start = Instant::now();
+    let mut throttle = UpdateThrottle::new();
+    let mut applied = Vec::new();
+    let mut now = start;
+    for i in 0..1000 {
+        now = start + Duration::from_millis(i);
+        let actions = vec![
+            Action::SetTitle(Some(format!("Title {i}"))),
+            Action::ClearUserGesture,
+        ];
+        let actions = throttle.filter(actions, interval, now);
+        assert!(
+            actions
+                .iter()
+                .any(|action| matches!(action, Action::ClearUserGesture)),
+            "Other actions are held back at {i} ms"
+        );
+        applied.extend(titles(&actions));
+        applied.extend(titles(&throttle.flush(interval, now)));
+    }
+    assert_eq!(applied.first(), Some(&Some("Title 0".to_string())));
+    assert!(
+        applied.len() <= 11,
+        "{} titles were applied during the burst",
+        applied.len()
+    );
+
+    let deadline = throttle.deadline(interval).expect("The last title is kept");
+    assert!(deadline <= now + interval, "The last title is due too late");
+    assert!(
+        throttle
+            .flush(interval, deadline - Duration::from_millis(1))
+            .is_empty(),
+        "The last title is applied before the interval elapsed"
+    );
+    let last = titles(&throttle.flush(interval, deadline));
+    assert_eq!(last, [Some("Title 999".to_string())]);
+    assert_eq!(throttle.deadline(interval), None);
+    assert!(throttle.flush(interval, deadline + interval).is_empty());
+
+    let later = deadline + interval;
+    let actions = throttle.filter(vec![Action::SetTitle(None)], interval, later);
+    assert_eq!(
+        titles(&actions),
+        [None],
+        "A title after a quiet interval waits"
+    );
+
+    let mut unthrottled = UpdateThrottle::new();
+    for i in 0..1000 {
+        let actions = vec![Action::SetTitle(Some(format!("Title {i}")))];
+        let actions = unthrottled.filter(actions, Duration::ZERO, start);
+        assert_eq!(
+            titles(&actions).len(),
+            1,
+            "A zero interval throttles titles"
+        );
+    }
+}
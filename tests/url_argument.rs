@@ -0,0 +1,90 @@
+//! URLs given on the command line are kept, paths are resolved against the current directory,
+//! and malformed URLs or missing files are errors instead of panics. Flags and their values
+//! aren't taken as the page to open.
+
+use std::path::Path;
+
+use servo_url::ServoUrl;
+use verso::{
+    config::{find_url_argument, parse_url_argument},
+    Error,
+};
+
+fn find(args: &[&str]) -> Option<String> {
+    find_url_argument(args.iter().map(|arg| arg.to_string()))
+}
+
+fn check_finding() {
+    assert_eq!(find(&[]), None);
+    assert_eq!(
+        find(&["https://servo.org/"]).as_deref(),
+        Some("https://servo.org/")
+    );
+    assert_eq!(
+        find(&["--content-process", "token"]),
+        None,
+        "A flag value was taken"
+    );
+    assert_eq!(
+        find(&["--content-process", "token", "page.html"]).as_deref(),
+        Some("page.html")
+    );
+    assert_eq!(
+        find(&["--crash-test", "page.html"]).as_deref(),
+        Some("page.html"),
+        "A switch took a value"
+    );
+    assert_eq!(
+        find(&["--unknown", "page.html"]).as_deref(),
+        Some("page.html"),
+        "An unknown flag took a value"
+    );
+    assert_eq!(
+        find(&["--zoom=2", "page.html", "-v"]).as_deref(),
+        Some("page.html")
+    );
+    assert_eq!(
+        find(&["-x", "--", "-page.html"]).as_deref(),
+        Some("-page.html"),
+        "An argument after -- was taken as a flag"
+    );
+    assert_eq!(
+        find(&["first.html", "second.html"]).as_deref(),
+        Some("second.html")
+    );
+}
+
+fn main() {
+    check_finding();
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    for url in ["https://servo.org/", "about:blank", "file:///tmp/page.html"] {
+        let parsed = parse_url_argument(url, root).expect("The URL parses");
+        assert_eq!(parsed.as_str(), url);
+    }
+
+    let fixture = root.join("tests/fixtures/content_size.html");
+    let expected = ServoUrl::from_file_path(fixture.canonicalize().unwrap()).unwrap();
+    let relative = parse_url_argument("tests/fixtures/content_size.html", root).unwrap();
+    assert_eq!(relative, expected);
+    let dotted = parse_url_argument(
+        "./fixtures/../fixtures/content_size.html",
+        &root.join("tests"),
+    )
+    .unwrap();
+    assert_eq!(dotted, expected);
+    let absolute = parse_url_argument(fixture.to_str().unwrap(), Path::new("/")).unwrap();
+    assert_eq!(absolute, expected);
+
+    assert!(matches!(
+        parse_url_argument("https://[::1", root),
+        Err(Error::InvalidUrl(..))
+    ));
+    match parse_url_argument("tests/fixtures/missing.html", root) {
+        Err(error @ Error::FileError(..)) => assert!(
+            error.to_string().contains("missing.html"),
+            "The error doesn't name the file: {error}"
+        ),
+        result => panic!("A missing file gave {result:?}"),
+    }
+}
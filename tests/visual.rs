@@ -0,0 +1,21 @@
+//! Visual regression tests of simple pages, catching changes of the sizing and scale factor
+//! handling of Verso. Run with `VERSO_UPDATE_BASELINES=1` to create or update the baselines.
+
+use std::path::{Path, PathBuf};
+
+use servo_url::ServoUrl;
+use verso::visual_test::{assert_page_matches, Tolerance};
+
+fn fixture(name: &str) -> (ServoUrl, PathBuf) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/visual");
+    let url = ServoUrl::from_file_path(dir.join(format!("{name}.html")))
+        .expect("Fixture path isn't absolute");
+    (url, dir.join(format!("{name}.png")))
+}
+
+fn main() {
+    for name in ["text", "flexbox", "image"] {
+        let (url, baseline) = fixture(name);
+        assert_page_matches(url, baseline, Tolerance::default());
+    }
+}
@@ -0,0 +1,138 @@
+//! Peer connections get the configured ICE servers unless they bring their own, and the
+//! candidates other than relay ones are hidden from the page when asked.
+//!
+//! The fixture replaces Servo's peer connection with a mock behind a TURN server before Verso
+//! wraps it, and the test calls it in a loopback.
+
+mod common;
+
+use common::Scenario;
+use script_traits::webdriver_msg::WebDriverJSValue;
+use servo_config::prefs::{pref_map, PrefValue};
+use servo_url::ServoUrl;
+use verso::{
+    config::{IceServer, WebRtcConfig},
+    webview::LoadStatus,
+    Verso,
+};
+
+const LOOPBACK: &str = "
+const call = (configuration) => {
+    const connection = new RTCPeerConnection(configuration);
+    const candidates = [];
+    connection.addEventListener('icecandidate', (event) => {
+        candidates.push(event.candidate.candidate);
+    });
+    connection.gather();
+    return {
+        servers: connection.configuration.iceServers,
+        policy: connection.configuration.iceTransportPolicy,
+        candidates,
+    };
+};
+return JSON.stringify([call(), call({ iceServers: [{ urls: ['stun:stun.example.org'] }] })]);";
+
+#[derive(serde::Deserialize, Debug)]
+struct Call {
+    servers: serde_json::Value,
+    policy: String,
+    candidates: Vec<String>,
+}
+
+enum Step {
+    Starting,
+    Loading,
+    Done,
+}
+
+struct WebRtc {
+    url: ServoUrl,
+    step: Step,
+    calls: Option<String>,
+}
+
+impl Scenario for WebRtc {
+    fn step(&mut self, verso: &mut Verso) {
+        match self.step {
+            Step::Starting => {
+                if verso.current_webview_id().is_some() {
+                    verso.load_url(self.url.clone());
+                    self.step = Step::Loading;
+                }
+            }
+            Step::Loading => {
+                if verso.load_status() != LoadStatus::Complete {
+                    return;
+                }
+                let calls = verso
+                    .evaluate_script(LOOPBACK)
+                    .expect("There's no web view")
+                    .recv()
+                    .expect("Script didn't answer")
+                    .expect("Script failed");
+                if let WebDriverJSValue::String(calls) = calls {
+                    self.calls = Some(calls);
+                }
+                verso.shutdown();
+                self.step = Step::Done;
+            }
+            Step::Done => {}
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = common::config();
+    config.webrtc = WebRtcConfig {
+        enabled: true,
+        ice_servers: vec![IceServer {
+            urls: vec!["turn:turn.example.com:3478".into()],
+            username: Some("verso".into()),
+            credential: Some("secret".into()),
+        }],
+        hide_non_relay_candidates: true,
+    };
+    let scenario = WebRtc {
+        url: common::fixture("webrtc.html"),
+        step: Step::Starting,
+        calls: None,
+    };
+    let (scenario, _) = common::run(config, scenario)?;
+
+    assert_eq!(
+        pref_map().get("dom.webrtc.enabled"),
+        PrefValue::Bool(true),
+        "WebRTC wasn't enabled"
+    );
+    let calls: Vec<Call> =
+        serde_json::from_str(&scenario.calls.expect("The page didn't make the calls"))?;
+    let [default, own] = &calls[..] else {
+        panic!("The page made other calls: {calls:?}");
+    };
+    assert_eq!(
+        default.servers,
+        serde_json::json!([{
+            "urls": ["turn:turn.example.com:3478"],
+            "username": "verso",
+            "credential": "secret",
+        }]),
+        "The configured ICE servers weren't used"
+    );
+    assert_eq!(
+        own.servers,
+        serde_json::json!([{ "urls": ["stun:stun.example.org"] }]),
+        "The ICE servers of the page were replaced"
+    );
+    for call in &calls {
+        assert_eq!(
+            call.policy, "relay",
+            "The relay transport policy wasn't set"
+        );
+        assert_eq!(
+            call.candidates,
+            ["candidate:1 1 udp 1 192.0.2.1 5000 typ relay"],
+            "The page saw candidates other than relay ones"
+        );
+    }
+    Ok(())
+}
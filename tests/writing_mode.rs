@@ -0,0 +1,204 @@
+//! Scrolling follows the writing mode and direction of pages, checked with the scroll offsets
+//! the page ends up with.
+//!
+//! In a Japanese vertical-rl page, the wheel scrolls the block axis leftwards, and `End` and
+//! `Home` scroll to its logical end and start. In a Hebrew right-to-left page, the wheel scrolls
+//! down, and `Shift` with the wheel scrolls leftwards.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::Scenario;
+use keyboard_types::{Code, Key, KeyState, KeyboardEvent};
+use script_traits::webdriver_msg::WebDriverJSValue;
+use verso::{recorder::RecordedInput, webview::LoadStatus, Verso};
+use winit::{event::WindowEvent, keyboard::ModifiersState};
+
+/// Time the page has to report its writing mode once it's loaded.
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// Time an action has to scroll the page.
+const ACTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Script returning the scroll offsets of the page and its horizontal scroll range.
+const READ_SCROLL: &str = "
+const root = document.documentElement;
+return JSON.stringify([scrollX, scrollY, root.scrollWidth - root.clientWidth]);";
+
+/// Scroll offsets of the page, and its horizontal scroll range.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Scroll {
+    x: f64,
+    y: f64,
+    range_x: f64,
+}
+
+/// Stage of the test: loading a fixture, or an action on the page and the scroll offsets it
+/// must lead to.
+enum Stage {
+    Load(&'static str),
+    Act(&'static str, fn(&mut Verso), fn(Scroll) -> bool),
+}
+
+const STAGES: [Stage; 7] = [
+    Stage::Load("vertical_rl.html"),
+    Stage::Act("wheel in vertical-rl", wheel, |s| s.x < 0.0 && s.y == 0.0),
+    Stage::Act("End in vertical-rl", end, |s| {
+        s.range_x > 0.0 && (s.x + s.range_x).abs() <= 1.0
+    }),
+    Stage::Act("Home in vertical-rl", home, |s| s.x == 0.0),
+    Stage::Load("rtl.html"),
+    Stage::Act("wheel in RTL", wheel, |s| s.y > 0.0 && s.x == 0.0),
+    Stage::Act("Shift with the wheel in RTL", shift_wheel, |s| s.x < 0.0),
+];
+
+fn window(verso: &Verso) -> winit::window::WindowId {
+    verso.current_window_id().expect("There's no window")
+}
+
+/// Scroll down one notch with the cursor in the page.
+fn wheel(verso: &mut Verso) {
+    let window = window(verso);
+    for input in [
+        RecordedInput::CursorMoved { x: 400.0, y: 400.0 },
+        RecordedInput::MouseWheel {
+            x: 0.0,
+            y: -3.0,
+            pixel: false,
+            phase: 1,
+        },
+    ] {
+        verso.handle_winit_window_event(window, input.to_window_event());
+    }
+}
+
+fn shift_wheel(verso: &mut Verso) {
+    let window = window(verso);
+    verso.handle_winit_window_event(
+        window,
+        WindowEvent::ModifiersChanged(ModifiersState::SHIFT.into()),
+    );
+    wheel(verso);
+    verso.handle_winit_window_event(
+        window,
+        WindowEvent::ModifiersChanged(ModifiersState::empty().into()),
+    );
+}
+
+fn press(verso: &mut Verso, key: Key, code: Code) {
+    let window = window(verso);
+    for state in [KeyState::Down, KeyState::Up] {
+        let event = KeyboardEvent {
+            state,
+            key: key.clone(),
+            code,
+            ..Default::default()
+        };
+        verso.handle_keyboard_event(window, event, None);
+    }
+}
+
+fn end(verso: &mut Verso) {
+    press(verso, Key::End, Code::End);
+}
+
+fn home(verso: &mut Verso) {
+    press(verso, Key::Home, Code::Home);
+}
+
+fn read_scroll(verso: &Verso) -> Scroll {
+    let value = verso
+        .evaluate_script(READ_SCROLL)
+        .expect("There's no web view")
+        .recv()
+        .expect("Script didn't answer")
+        .expect("Script failed");
+    let WebDriverJSValue::String(json) = value else {
+        panic!("The scroll offsets weren't read: {value:?}");
+    };
+    let (x, y, range_x) = serde_json::from_str(&json).expect("The scroll offsets weren't read");
+    Scroll { x, y, range_x }
+}
+
+struct WritingMode {
+    stage: usize,
+    /// Start of the current stage, once its load or action began.
+    started: Option<Instant>,
+    /// Scroll offsets each action led to.
+    results: Vec<(&'static str, Scroll)>,
+}
+
+impl WritingMode {
+    fn next_stage(&mut self, verso: &mut Verso) {
+        self.stage += 1;
+        self.started = None;
+        if self.stage == STAGES.len() {
+            verso.shutdown();
+        }
+    }
+}
+
+impl Scenario for WritingMode {
+    fn step(&mut self, verso: &mut Verso) {
+        if verso.current_webview_id().is_none() {
+            return;
+        }
+        let Some(stage) = STAGES.get(self.stage) else {
+            return;
+        };
+        match *stage {
+            Stage::Load(name) => {
+                let url = common::fixture(name);
+                let Some(started) = self.started else {
+                    verso.load_url(url);
+                    self.started = Some(Instant::now());
+                    return;
+                };
+                if verso.current_raw_url() == Some(url)
+                    && verso.load_status() == LoadStatus::Complete
+                    && started.elapsed() >= SETTLE
+                {
+                    self.next_stage(verso);
+                }
+            }
+            Stage::Act(name, act, done) => {
+                let Some(started) = self.started else {
+                    act(verso);
+                    self.started = Some(Instant::now());
+                    return;
+                };
+                let scroll = read_scroll(verso);
+                if done(scroll) {
+                    self.results.push((name, scroll));
+                    self.next_stage(verso);
+                } else {
+                    assert!(
+                        started.elapsed() < ACTION_TIMEOUT,
+                        "{name} scrolled to {scroll:?}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let scenario = WritingMode {
+        stage: 0,
+        started: None,
+        results: Vec::new(),
+    };
+    let (scenario, _) = common::run(common::config(), scenario)?;
+
+    let expected: Vec<_> = STAGES
+        .iter()
+        .filter_map(|stage| match stage {
+            Stage::Act(name, ..) => Some(*name),
+            Stage::Load(_) => None,
+        })
+        .collect();
+    let done: Vec<_> = scenario.results.iter().map(|(name, _)| *name).collect();
+    assert_eq!(done, expected, "Not every action scrolled the page");
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Fit factors of synthetic content in synthetic viewports, including responsive and empty
+//! content, pinch zoom factors of elements magnified with the smart magnify gesture, conversion
+//! of CSS pixels through both zoom factors, and recognition of the double tap fitting the width.
+//!
+//! Fitting only measures sizes, so this runs without an event loop.
+
+use std::time::{Duration, Instant};
+
+use euclid::{Point2D, Size2D};
+use style_traits::{CSSPixel, DevicePixel};
+use verso::zoom::{
+    css_to_device_scale, fit_factor, magnify_factor, DoubleTap, FitMode, DOUBLE_TAP_DISTANCE,
+    DOUBLE_TAP_INTERVAL,
+};
+use webrender_api::units::{DevicePoint, LayoutSize};
+
+fn main() {
+    let viewport = LayoutSize::new(1000.0, 800.0);
+    let cases = [
+        ("wide content", LayoutSize::new(2000.0, 800.0), 0.5, 0.5),
+        (
+            "long wide content",
+            LayoutSize::new(2000.0, 4000.0),
+            0.5,
+            0.2,
+        ),
+        ("responsive content", viewport, 1.0, 1.0),
+        (
+            "long responsive content",
+            LayoutSize::new(1000.0, 1600.0),
+            1.0,
+            0.5,
+        ),
+        ("rounded layout", LayoutSize::new(1000.4, 799.6), 1.0, 1.0),
+        ("narrow content", LayoutSize::new(500.0, 400.0), 2.0, 2.0),
+        ("empty content", LayoutSize::zero(), 1.0, 1.0),
+        (
+            "content without height",
+            LayoutSize::new(2000.0, 0.0),
+            1.0,
+            1.0,
+        ),
+    ];
+    for (case, content, width, page) in cases {
+        assert_eq!(
+            fit_factor(FitMode::Width, content, viewport),
+            width,
+            "{case} fitting the width"
+        );
+        assert_eq!(
+            fit_factor(FitMode::Page, content, viewport),
+            page,
+            "{case} fitting the page"
+        );
+    }
+
+    let content = LayoutSize::new(2000.0, 4000.0);
+    for viewport in [
+        LayoutSize::zero(),
+        LayoutSize::new(0.0, 800.0),
+        LayoutSize::new(1000.0, 0.0),
+    ] {
+        for mode in [FitMode::Page, FitMode::Width] {
+            assert_eq!(
+                fit_factor(mode, content, viewport),
+                1.0,
+                "{mode:?} in the empty viewport {viewport:?}"
+            );
+        }
+    }
+
+    let margin = 16.0;
+    for (case, element, viewport, factor) in [
+        ("narrow column", 484.0, 1000.0, 2.0),
+        ("column filling the viewport", 968.0, 1000.0, 1.0),
+        ("element wider than the viewport", 2000.0, 1000.0, 1.0),
+        ("empty element", 0.0, 1000.0, 1.0),
+        ("viewport narrower than the margins", 100.0, 20.0, 1.0),
+    ] {
+        assert_eq!(
+            magnify_factor(element, viewport, margin),
+            factor,
+            "magnifying {case}"
+        );
+    }
+
+    // Scale factor, page zoom, pinch zoom, and device pixels of a CSS pixel.
+    for (scale_factor, page_zoom, pinch_zoom, device) in [
+        (1.0, 1.0, 1.0, 1.0),
+        (2.0, 1.0, 1.0, 2.0),
+        (1.0, 1.5, 1.0, 1.5),
+        (1.0, 1.0, 3.0, 3.0),
+        (2.0, 1.5, 3.0, 9.0),
+        (1.25, 0.5, 2.0, 1.25),
+    ] {
+        let scale = css_to_device_scale(scale_factor, page_zoom, pinch_zoom);
+        assert_eq!(
+            scale.get(),
+            device,
+            "CSS pixels at scale {scale_factor}, page zoom {page_zoom} and pinch zoom {pinch_zoom}"
+        );
+        let point = scale.transform_point(Point2D::<f32, CSSPixel>::new(10.0, 20.0));
+        assert_eq!((point.x, point.y), (10.0 * device, 20.0 * device));
+        let back = scale.inverse().transform_point(point);
+        assert!(
+            (back - Point2D::new(10.0, 20.0)).length() < 1e-4,
+            "Device pixels at scale {scale_factor}, page zoom {page_zoom} and pinch zoom {pinch_zoom} convert back to {back:?}"
+        );
+    }
+
+    // The layout viewport is the web view divided by the scale factor and the page zoom.
+    let webview = Size2D::<f32, DevicePixel>::new(1600.0, 1200.0);
+    let layout_viewport = |page_zoom: f32| webview / css_to_device_scale(2.0, page_zoom, 1.0);
+    assert_eq!(layout_viewport(1.0), Size2D::new(800.0, 600.0));
+    assert_eq!(layout_viewport(2.0), Size2D::new(400.0, 300.0));
+
+    let start = Instant::now();
+    let point = DevicePoint::new(100.0, 100.0);
+    let near = DevicePoint::new(100.0 + DOUBLE_TAP_DISTANCE, 100.0);
+    let far = DevicePoint::new(101.0 + DOUBLE_TAP_DISTANCE, 100.0);
+    let soon = start + DOUBLE_TAP_INTERVAL;
+    let late = soon + Duration::from_millis(1);
+    for (case, second, at, double) in [
+        ("near and soon", near, soon, true),
+        ("too far", far, soon, false),
+        ("too late", point, late, false),
+    ] {
+        let mut double_tap = DoubleTap::default();
+        assert!(
+            !double_tap.tap(start, point),
+            "A single tap is a double tap"
+        );
+        assert_eq!(double_tap.tap(at, second), double, "Second tap {case}");
+    }
+
+    let mut double_tap = DoubleTap::default();
+    let taps: Vec<bool> = (0..4)
+        .map(|i| double_tap.tap(start + Duration::from_millis(100 * i), point))
+        .collect();
+    assert_eq!(
+        taps,
+        [false, true, false, true],
+        "The tap after a double tap doesn't start a new one"
+    );
+}